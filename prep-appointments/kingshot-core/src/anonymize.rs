@@ -0,0 +1,63 @@
+//! Pseudonymizes submission CSVs so problem files can be shared without player data.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+
+use crate::parser::SubmissionColumns;
+
+/// Counts from an [`anonymize_submissions`] run
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AnonymizeSummary {
+    pub rows: usize,
+    pub players: usize,
+}
+
+/// Copies a submission CSV with every player ID and character name replaced by a pseudonym.
+///
+/// Pseudonyms follow first appearance: the first player ID becomes 10000001 and its player
+/// "Player 1", and every row with that ID (resubmissions) gets the same pair, so merging,
+/// scores and slots parse exactly as before. Alliances, days, resources, times and the
+/// timestamp are kept; any other column (free text notes, feedback) is emptied.
+pub fn anonymize_submissions<R: Read, W: Write>(input: R, output: W) -> Result<AnonymizeSummary, Box<dyn std::error::Error>> {
+    let mut reader = ReaderBuilder::new().flexible(true).from_reader(input);
+    let mut writer = WriterBuilder::new().flexible(true).from_writer(output);
+
+    let headers = reader.headers()?.clone();
+    let columns = SubmissionColumns::find(&headers);
+    let mut kept = columns.used().to_vec();
+    kept.push(0); // Timestamp: keeps the submission order readable
+    writer.write_record(&headers)?;
+
+    let mut pseudonyms: HashMap<String, usize> = HashMap::new();
+    let mut summary = AnonymizeSummary::default();
+    for result in reader.records() {
+        let record = result?;
+        let player_id = record.get(columns.id).unwrap_or("").trim();
+        let pseudonym = (!player_id.is_empty()).then(|| {
+            let next = pseudonyms.len() + 1;
+            *pseudonyms.entry(player_id.to_string()).or_insert(next)
+        });
+
+        let anonymized: StringRecord = record.iter().enumerate().map(|(col, value)| {
+            if col == columns.id || col == columns.name {
+                match pseudonym {
+                    Some(n) if col == columns.id => (10_000_000 + n).to_string(),
+                    Some(n) if !value.trim().is_empty() => format!("Player {}", n),
+                    _ => String::new(),
+                }
+            } else if kept.contains(&col) {
+                value.to_string()
+            } else {
+                String::new()
+            }
+        }).collect();
+        writer.write_record(&anonymized)?;
+        summary.rows += 1;
+    }
+    writer.flush()?;
+
+    summary.players = pseudonyms.len();
+    Ok(summary)
+}
@@ -0,0 +1,383 @@
+//! Custom priority score formulas, such as `truegold*1500 + speedups*40 + power/1e6`.
+//!
+//! A formula is an arithmetic expression over the numbers a player submitted for a day:
+//! `+ - * /`, parentheses, unary minus and decimal numbers (`1e6` allowed). Variables:
+//!
+//! - `truegold`: truegold on Construction day, truegold dust on Research day (`dust` is an alias)
+//! - `speedups`: days of speedups for that day
+//! - `power`, `power_gain`: power level and gain since the baseline, 0 without a power dataset
+//!
+//! Results are rounded and clamped to `0..=u32::MAX`; division by zero counts as 0.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::parser::AppointmentEntry;
+use crate::power::PowerMatch;
+
+/// Longest accepted formula, in bytes
+pub const MAX_FORMULA_LENGTH: usize = 500;
+const MAX_NESTING: usize = 32;
+
+/// A parsed score formula
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreFormula {
+    source: String,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Variable(Variable),
+    Negate(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Variable {
+    Truegold,
+    Speedups,
+    Power,
+    PowerGain,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+/// The values a formula is evaluated with, for one player and day
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FormulaInputs {
+    pub truegold: f64,
+    pub speedups: f64,
+    pub power: f64,
+    pub power_gain: f64,
+}
+
+/// Why a formula couldn't be parsed; `position` is the 1-based character it was noticed at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormulaError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for FormulaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at character {})", self.message, self.position)
+    }
+}
+
+impl std::error::Error for FormulaError {}
+
+impl ScoreFormula {
+    /// The formula as it was written
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluates the formula into a score
+    pub fn evaluate(&self, inputs: &FormulaInputs) -> u32 {
+        let value = self.expr.evaluate(inputs);
+        if value.is_nan() || value <= 0.0 {
+            0
+        } else {
+            value.round().min(u32::MAX as f64) as u32
+        }
+    }
+}
+
+impl FromStr for ScoreFormula {
+    type Err = FormulaError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        if source.len() > MAX_FORMULA_LENGTH {
+            return Err(FormulaError { position: MAX_FORMULA_LENGTH, message: format!("Formulas can be at most {} characters long", MAX_FORMULA_LENGTH) });
+        }
+        let mut parser = FormulaParser { chars: source.chars().collect(), pos: 0, depth: 0 };
+        let expr = parser.expression()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error(format!("Unexpected \"{}\"", parser.chars[parser.pos])));
+        }
+        Ok(ScoreFormula { source: source.trim().to_string(), expr })
+    }
+}
+
+impl Expr {
+    fn evaluate(&self, inputs: &FormulaInputs) -> f64 {
+        match self {
+            Expr::Number(n) => *n,
+            Expr::Variable(Variable::Truegold) => inputs.truegold,
+            Expr::Variable(Variable::Speedups) => inputs.speedups,
+            Expr::Variable(Variable::Power) => inputs.power,
+            Expr::Variable(Variable::PowerGain) => inputs.power_gain,
+            Expr::Negate(inner) => -inner.evaluate(inputs),
+            Expr::Binary(op, left, right) => {
+                let (left, right) = (left.evaluate(inputs), right.evaluate(inputs));
+                match op {
+                    Op::Add => left + right,
+                    Op::Subtract => left - right,
+                    Op::Multiply => left * right,
+                    Op::Divide if right == 0.0 => 0.0,
+                    Op::Divide => left / right,
+                }
+            }
+        }
+    }
+}
+
+// Recursive descent over: expression = term (('+' | '-') term)*, term = factor (('*' | '/') factor)*,
+// factor = '-' factor | number | variable | '(' expression ')'
+struct FormulaParser {
+    chars: Vec<char>,
+    pos: usize,
+    depth: usize,
+}
+
+impl FormulaParser {
+    fn error(&self, message: impl Into<String>) -> FormulaError {
+        FormulaError { position: self.pos + 1, message: message.into() }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expression(&mut self) -> Result<Expr, FormulaError> {
+        let mut expr = self.term()?;
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.pos += 1;
+            let op = if op == '+' { Op::Add } else { Op::Subtract };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.term()?));
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> Result<Expr, FormulaError> {
+        let mut expr = self.factor()?;
+        while let Some(op @ ('*' | '/')) = self.peek() {
+            self.pos += 1;
+            let op = if op == '*' { Op::Multiply } else { Op::Divide };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.factor()?));
+        }
+        Ok(expr)
+    }
+
+    fn factor(&mut self) -> Result<Expr, FormulaError> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            return Err(self.error("The formula is nested too deeply"));
+        }
+        let expr = match self.peek() {
+            Some('-') => {
+                self.pos += 1;
+                Expr::Negate(Box::new(self.factor()?))
+            }
+            Some('(') => {
+                self.pos += 1;
+                let inner = self.expression()?;
+                if self.peek() != Some(')') {
+                    return Err(self.error("Missing \")\""));
+                }
+                self.pos += 1;
+                inner
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number()?,
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => self.variable()?,
+            Some(c) => return Err(self.error(format!("Unexpected \"{}\"", c))),
+            None => return Err(self.error("The formula ends too early")),
+        };
+        self.depth -= 1;
+        Ok(expr)
+    }
+
+    fn number(&mut self) -> Result<Expr, FormulaError> {
+        let start = self.pos;
+        while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_digit() || *c == '.') {
+            self.pos += 1;
+        }
+        // Exponent, as in 1e6 or 2.5E-3
+        if self.chars.get(self.pos).is_some_and(|c| *c == 'e' || *c == 'E') {
+            let mut end = self.pos + 1;
+            if self.chars.get(end).is_some_and(|c| *c == '+' || *c == '-') {
+                end += 1;
+            }
+            if self.chars.get(end).is_some_and(|c| c.is_ascii_digit()) {
+                self.pos = end;
+                while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_digit()) {
+                    self.pos += 1;
+                }
+            }
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        text.parse::<f64>()
+            .ok()
+            .filter(|n| n.is_finite())
+            .map(Expr::Number)
+            .ok_or_else(|| FormulaError { position: start + 1, message: format!("\"{}\" is not a number", text) })
+    }
+
+    fn variable(&mut self) -> Result<Expr, FormulaError> {
+        let start = self.pos;
+        while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_') {
+            self.pos += 1;
+        }
+        let name: String = self.chars[start..self.pos].iter().collect();
+        let variable = match name.to_lowercase().as_str() {
+            "truegold" | "dust" | "truegold_dust" => Variable::Truegold,
+            "speedups" => Variable::Speedups,
+            "power" => Variable::Power,
+            "power_gain" | "gain" => Variable::PowerGain,
+            _ => {
+                return Err(FormulaError {
+                    position: start + 1,
+                    message: format!("Unknown variable \"{}\", use truegold, speedups, power or power_gain", name),
+                })
+            }
+        };
+        Ok(Expr::Variable(variable))
+    }
+}
+
+/// Replaces the construction and research scores of the entries with the given formulas.
+/// Days without a formula keep their scores. `power` and `baseline` provide the `power`
+/// and `power_gain` variables; players missing from them have 0.
+pub fn apply_score_formulas(
+    entries: &mut [AppointmentEntry],
+    construction: Option<&ScoreFormula>,
+    research: Option<&ScoreFormula>,
+    power: Option<&PowerMatch>,
+    baseline: Option<&PowerMatch>,
+) {
+    for entry in entries {
+        let (current, gain) = power.and_then(|p| p.reading(&entry.player_id, baseline)).unwrap_or((0, 0));
+        if let Some(formula) = construction {
+            entry.construction_score = formula.evaluate(&FormulaInputs {
+                truegold: entry.construction_truegold as f64,
+                speedups: entry.construction_speedups as f64,
+                power: current as f64,
+                power_gain: gain as f64,
+            });
+        }
+        if let Some(formula) = research {
+            entry.research_score = formula.evaluate(&FormulaInputs {
+                truegold: entry.research_truegold_dust as f64,
+                speedups: entry.research_speedups as f64,
+                power: current as f64,
+                power_gain: gain as f64,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> ScoreFormula {
+        source.parse().unwrap_or_else(|e| panic!("{:?} failed to parse: {}", source, e))
+    }
+
+    fn eval(source: &str) -> u32 {
+        parse(source).evaluate(&FormulaInputs::default())
+    }
+
+    fn error(source: &str) -> FormulaError {
+        source.parse::<ScoreFormula>().expect_err(source)
+    }
+
+    #[test]
+    fn precedence_and_associativity() {
+        assert_eq!(eval("1+2*3"), 7);
+        assert_eq!(eval("(1+2)*3"), 9);
+        assert_eq!(eval("8-2-1"), 5);
+        assert_eq!(eval("8/2/2"), 2);
+        assert_eq!(eval("2*3+4*5"), 26);
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(eval("--3"), 3);
+        assert_eq!(eval("10+-3"), 7);
+        assert_eq!(eval("-(2-5)"), 3);
+    }
+
+    #[test]
+    fn exponent_literals() {
+        assert_eq!(eval("1e6"), 1_000_000);
+        assert_eq!(eval("2.5E-3*1000"), 3);
+        assert_eq!(eval("1e+2"), 100);
+        let err = error("1e");
+        assert_eq!(err.position, 2);
+        assert_eq!(err.message, "Unexpected \"e\"");
+    }
+
+    #[test]
+    fn results_are_rounded_and_clamped() {
+        assert_eq!(eval("2.5"), 3);
+        assert_eq!(eval("2.4"), 2);
+        assert_eq!(eval("5/0"), 0);
+        assert_eq!(eval("5/0 + 1"), 1);
+        assert_eq!(eval("3-10"), 0);
+        assert_eq!(eval("1e308*10 - 1e308*10"), 0); // inf - inf is NaN
+        assert_eq!(eval("1e20"), u32::MAX);
+    }
+
+    #[test]
+    fn variables() {
+        let formula = parse("truegold*1500 + speedups*40 + power/1e6 + GAIN");
+        let inputs = FormulaInputs { truegold: 2.0, speedups: 3.0, power: 5_000_000.0, power_gain: 7.0 };
+        assert_eq!(formula.evaluate(&inputs), 3000 + 120 + 5 + 7);
+        assert_eq!(parse("dust").evaluate(&inputs), 2);
+        assert_eq!(parse("  speedups  ").source(), "speedups");
+
+        let err = error("2 * foo");
+        assert_eq!(err.position, 5);
+        assert!(err.message.starts_with("Unknown variable \"foo\""), "{}", err.message);
+    }
+
+    #[test]
+    fn nesting_limit() {
+        // Every parenthesis is one level, and the number inside another
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(eval(&nested(MAX_NESTING - 1)), 1);
+        assert_eq!(error(&nested(MAX_NESTING)).message, "The formula is nested too deeply");
+        assert_eq!(error(&"-".repeat(MAX_NESTING + 1)).message, "The formula is nested too deeply");
+    }
+
+    #[test]
+    fn length_limit() {
+        let padded = |length: usize| format!("{:<1$}", "1", length);
+        assert_eq!(eval(&padded(MAX_FORMULA_LENGTH)), 1);
+
+        let err = error(&padded(MAX_FORMULA_LENGTH + 1));
+        assert_eq!(err.position, MAX_FORMULA_LENGTH);
+        assert!(err.message.contains("at most 500 characters"), "{}", err.message);
+    }
+
+    #[test]
+    fn error_positions() {
+        let at = |source: &str| error(source).position;
+        assert_eq!(at("1 + * 2"), 5);
+        assert_eq!(at("(1 + 2"), 7);
+        assert_eq!(at("1 + 2)"), 6);
+        assert_eq!(at("1 +"), 4);
+        assert_eq!(at("1.2.3"), 1);
+        assert_eq!(at(""), 1);
+        assert_eq!(error("1 + 2)").to_string(), "Unexpected \")\" (at character 6)");
+        assert_eq!(error("(1").message, "Missing \")\"");
+        assert_eq!(error("").message, "The formula ends too early");
+    }
+}
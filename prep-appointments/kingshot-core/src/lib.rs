@@ -0,0 +1,25 @@
+//! Domain logic for Kingshot SvS preparation week appointments.
+//!
+//! Shared by the `prep-appointments` web server and CLI, and meant for other tools
+//! (bots, integrations) that need to read submissions or build schedules.
+//!
+//! - [`parser`]: loads submission CSVs into [`parser::AppointmentEntry`] values, merging
+//!   resubmissions and computing priority scores ([`parser::ScoreWeights`]).
+//! - [`schedule`]: assigns players to the 49 time slots of each day.
+//!   [`schedule::generate_schedules`] runs all three days with predetermined slots and
+//!   append locking; the per-day `schedule_*_day` functions are the building blocks.
+//! - [`anonymize`]: replaces names and IDs in a submission CSV with pseudonyms for sharing.
+//! - [`power`]: reads power level datasets extracted from the member list and adds them to
+//!   priority scores.
+//! - [`formula`]: custom priority score formulas such as `truegold*1500 + speedups*40`.
+//! - [`roster`]: known players by ID, whose names and alliances replace the submitted ones.
+//!
+//! Slots are numbered 1-49: slot 1 = 00:00, slot 2 = 00:15, slot 3 = 00:45, then every
+//! 30 minutes. [`schedule::calculate_time_slots`] maps custom form time windows onto them.
+
+pub mod parser;
+pub mod schedule;
+pub mod anonymize;
+pub mod power;
+pub mod formula;
+pub mod roster;
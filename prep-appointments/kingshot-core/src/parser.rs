@@ -0,0 +1,497 @@
+//! Submission CSV parsing: one [`AppointmentEntry`] per player, with resubmissions merged.
+
+use csv::{Reader, StringRecord};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+
+/// One player's appointment request, merged across resubmissions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppointmentEntry {
+    pub alliance: String, // Alliance tag, empty if none
+    pub name: String, // In-game character name
+    pub player_id: String, // Canonical identifier; resubmissions are matched on it
+    pub wants_construction: bool,
+    pub wants_research: bool,
+    pub wants_troops: bool,
+    pub construction_speedups: u32, // Days of speedups
+    pub research_speedups: u32,
+    pub troops_speedups: u32, // Also the troops day priority score
+    pub construction_truegold: u32,
+    pub construction_score: u32, // Priority score, see ScoreWeights
+    pub research_truegold_dust: u32,
+    pub research_score: u32, // Priority score, see ScoreWeights
+    pub construction_available_slots: Vec<u8>, // Slot numbers (1-49) the player can attend
+    pub research_available_slots: Vec<u8>,
+    pub troops_available_slots: Vec<u8>,
+}
+
+/// Weights used to turn submitted resources into priority scores.
+/// Defaults: construction = truegold * 2000 + speedups * 30, research = truegold dust * 1000 + speedups * 30.
+/// The power weights only apply when a power dataset is attached, see [`crate::power`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoreWeights {
+    pub construction_truegold: u32,
+    pub construction_speedups: u32,
+    pub research_truegold_dust: u32,
+    pub research_speedups: u32,
+    pub power_per_million: u32, // Added to construction and research scores, default 0
+    pub power_gain_per_million: u32, // Per million gained since the baseline dataset, default 0
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        ScoreWeights {
+            construction_truegold: 2000,
+            construction_speedups: 30,
+            research_truegold_dust: 1000,
+            research_speedups: 30,
+            power_per_million: 0,
+            power_gain_per_million: 0,
+        }
+    }
+}
+
+impl ScoreWeights {
+    /// Construction day priority score for the given truegold and speedup days
+    pub fn construction_score(&self, truegold: u32, speedups: u32) -> u32 {
+        truegold * self.construction_truegold + speedups * self.construction_speedups
+    }
+
+    /// Research day priority score for the given truegold dust and speedup days
+    pub fn research_score(&self, truegold_dust: u32, speedups: u32) -> u32 {
+        truegold_dust * self.research_truegold_dust + speedups * self.research_speedups
+    }
+
+    /// Priority points for a player's power and the power gained since the baseline
+    pub fn power_score(&self, power: u64, gain: u64) -> u32 {
+        let points = power / 1_000_000 * self.power_per_million as u64 + gain / 1_000_000 * self.power_gain_per_million as u64;
+        points.min(u32::MAX as u64) as u32
+    }
+
+    /// Whether power datasets change any score
+    pub fn uses_power(&self) -> bool {
+        self.power_per_million > 0 || self.power_gain_per_million > 0
+    }
+}
+
+/// Recomputes construction and research scores of loaded entries with custom weights
+pub fn apply_score_weights(entries: &mut [AppointmentEntry], weights: &ScoreWeights) {
+    for entry in entries {
+        entry.construction_score = weights.construction_score(entry.construction_truegold, entry.construction_speedups);
+        entry.research_score = weights.research_score(entry.research_truegold_dust, entry.research_speedups);
+    }
+}
+
+/// Converts a time string (e.g., "00:15", "01:45") to a slot number (1-49)
+/// Slot 1 = 00:00, Slot 2 = 00:15, Slot 3 = 00:45, then increments by 30 min
+pub fn time_to_slot(time_str: &str) -> Option<u8> {
+    // Remove any notes or extra text in parentheses
+    let clean_time = time_str.split('(').next().unwrap_or(time_str).trim();
+    
+    // Handle "00:00" case
+    if clean_time == "00:00" {
+        return Some(1);
+    }
+    
+    // Parse HH:MM format
+    let parts: Vec<&str> = clean_time.split(':').collect();
+    if parts.len() != 2 {
+        return None;
+    }
+    
+    let hours: u32 = parts[0].parse().ok()?;
+    let minutes: u32 = parts[1].parse().ok()?;
+    
+    // Convert to total minutes
+    let total_minutes = hours * 60 + minutes;
+    
+    // Special cases for the first slots
+    if total_minutes == 0 {
+        return Some(1); // 00:00
+    } else if total_minutes == 15 {
+        return Some(2); // 00:15
+    } else if total_minutes == 45 {
+        return Some(3); // 00:45
+    }
+    
+    // For times after 00:45, calculate slot based on 30-minute increments
+    // Slot 3 is at 00:45 (45 minutes), so slot 4 should be at 01:15 (75 minutes)
+    // The pattern: slot = 3 + ((total_minutes - 45) / 30)
+    if total_minutes > 45 {
+        let slot = 3 + ((total_minutes - 45) / 30);
+        if slot <= 49 {
+            return Some(slot as u8);
+        }
+    }
+    
+    None
+}
+
+/// Maps a time string to a slot number using custom time slot mapping
+/// Returns None if the time string doesn't match any slot in the mapping
+fn time_string_to_slot_number(
+    time_str: &str,
+    time_slots: &[(u8, String)]
+) -> Option<u8> {
+    // Remove any notes or extra text in parentheses
+    let clean_time = time_str.split('(').next().unwrap_or(time_str).trim();
+    
+    time_slots.iter()
+        .find(|(_, time)| time.trim() == clean_time)
+        .map(|(slot, _)| *slot)
+}
+
+/// Parses a comma-separated list of time strings and converts them to slot numbers
+/// If custom_time_slots is provided, uses that mapping; otherwise falls back to fixed mapping
+fn parse_time_slots(
+    time_string: &str,
+    custom_time_slots: Option<&[(u8, String)]>
+) -> Vec<u8> {
+    let mut slots = HashSet::new();
+    
+    // Split by comma and process each time
+    for time_part in time_string.split(',') {
+        let trimmed = time_part.trim();
+        let slot = if let Some(custom_slots) = custom_time_slots {
+            // Use custom mapping
+            time_string_to_slot_number(trimmed, custom_slots)
+        } else {
+            // Fallback to fixed mapping (backward compatibility)
+            time_to_slot(trimmed)
+        };
+        
+        if let Some(slot) = slot {
+            slots.insert(slot);
+        }
+    }
+    
+    let mut result: Vec<u8> = slots.into_iter().collect();
+    result.sort();
+    result
+}
+
+/// Parses a boolean value from various string representations
+fn parse_bool(value: &str) -> bool {
+    let lower = value.trim().to_lowercase();
+    lower == "yes" || lower == "true" || lower == "1"
+}
+
+/// Parses a number, returning 0 if empty or invalid
+fn parse_number(value: &str) -> u32 {
+    value.trim().parse().unwrap_or(0)
+}
+
+/// Column positions in a submission CSV, found by header text (falling back to the form's default order)
+#[derive(Debug, Clone, Copy)]
+pub struct SubmissionColumns {
+    pub alliance: usize,
+    pub custom_alliance: usize,
+    pub name: usize,
+    pub id: usize,
+    pub submission_type: usize,
+    pub construction_want: usize,
+    pub construction_speedups: usize,
+    pub construction_truegold: usize,
+    pub construction_times: usize,
+    pub research_want: usize,
+    pub research_speedups: usize,
+    pub research_truegold_dust: usize,
+    pub research_times: usize,
+    pub troops_want: usize,
+    pub troops_speedups: usize,
+    pub troops_times: usize,
+}
+
+/// Names of the fields a submission CSV column can be mapped to, in [`SubmissionColumns::used`] order
+pub const SUBMISSION_FIELDS: [&str; 16] = [
+    "alliance", "custom_alliance", "name", "player_id", "submission_type",
+    "construction_want", "construction_speedups", "construction_truegold", "construction_times",
+    "research_want", "research_speedups", "research_truegold_dust", "research_times",
+    "troops_want", "troops_speedups", "troops_times",
+];
+
+/// Header text of the column holding each field (keyed by [`SUBMISSION_FIELDS`] name), for CSVs
+/// whose questions aren't recognized
+pub type ColumnMapping = BTreeMap<String, String>;
+
+/// Column of each field in the form's default order, used by [`SubmissionColumns::find`] for
+/// unrecognized headers
+const DEFAULT_POSITIONS: [usize; 16] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 13, 14, 15];
+
+impl SubmissionColumns {
+    pub fn find(headers: &StringRecord) -> Self {
+        let detected = Self::detect(headers);
+        Self::from_positions(std::array::from_fn(|i| detected[i].unwrap_or(DEFAULT_POSITIONS[i])))
+    }
+
+    /// Columns recognized by header text, with `mapping` taking precedence where the CSV has the
+    /// mapped header. Unlike [`Self::find`] nothing falls back to the default order: the fields
+    /// without a column are returned instead.
+    pub fn resolve(headers: &StringRecord, mapping: &ColumnMapping) -> Result<Self, Vec<&'static str>> {
+        let mut positions = Self::detect(headers);
+        for (position, field) in positions.iter_mut().zip(SUBMISSION_FIELDS) {
+            let mapped = mapping.get(field)
+                .and_then(|header| headers.iter().position(|h| h.trim() == header.trim()));
+            if mapped.is_some() {
+                *position = mapped;
+            }
+        }
+        let missing: Vec<&'static str> = SUBMISSION_FIELDS.iter().zip(&positions)
+            .filter(|(_, position)| position.is_none())
+            .map(|(field, _)| *field)
+            .collect();
+        if !missing.is_empty() {
+            return Err(missing);
+        }
+        Ok(Self::from_positions(positions.map(|p| p.unwrap_or_default())))
+    }
+
+    fn detect(headers: &StringRecord) -> [Option<usize>; 16] {
+        let position = |matches: &dyn Fn(&str) -> bool| headers.iter().position(matches);
+        [
+            position(&|h| h.contains("alliance")),
+            position(&|h| h.contains("Non of the above") && h.contains("type it here")),
+            position(&|h| h.contains("character name")),
+            position(&|h| h.contains("player ID")),
+            position(&|h| h.contains("Is this form")),
+            position(&|h| h.contains("Construction day appointment")),
+            position(&|h| h.contains("Construction day") && h.contains("speedups")),
+            position(&|h| h.contains("truegold") && !h.contains("dust")),
+            position(&|h| h.contains("Construction day appointment") && h.contains("times")),
+            position(&|h| h.contains("Research day appointment") && !h.contains("times")),
+            position(&|h| h.contains("Research day") && h.contains("speedups")),
+            position(&|h| h.contains("truegold dust")),
+            position(&|h| h.contains("Research day appointment") && h.contains("times")),
+            position(&|h| h.contains("Troops Training day appointment") && !h.contains("times")),
+            position(&|h| h.contains("Troops Training day") && h.contains("speedups")),
+            position(&|h| h.contains("Troops Training day appointment") && h.contains("times")),
+        ]
+    }
+
+    fn from_positions(p: [usize; 16]) -> Self {
+        SubmissionColumns {
+            alliance: p[0],
+            custom_alliance: p[1],
+            name: p[2],
+            id: p[3],
+            submission_type: p[4],
+            construction_want: p[5],
+            construction_speedups: p[6],
+            construction_truegold: p[7],
+            construction_times: p[8],
+            research_want: p[9],
+            research_speedups: p[10],
+            research_truegold_dust: p[11],
+            research_times: p[12],
+            troops_want: p[13],
+            troops_speedups: p[14],
+            troops_times: p[15],
+        }
+    }
+
+    /// Every column the parser reads
+    pub fn used(&self) -> [usize; 16] {
+        [
+            self.alliance, self.custom_alliance, self.name, self.id, self.submission_type,
+            self.construction_want, self.construction_speedups, self.construction_truegold, self.construction_times,
+            self.research_want, self.research_speedups, self.research_truegold_dust, self.research_times,
+            self.troops_want, self.troops_speedups, self.troops_times,
+        ]
+    }
+}
+
+/// Loads appointments from a CSV file
+/// 
+/// # Arguments
+/// * `csv_path` - Path to the CSV file
+/// * `construction_time_slots` - Optional mapping of (slot_number, time_string) for construction day
+/// * `research_time_slots` - Optional mapping of (slot_number, time_string) for research day
+/// * `troops_time_slots` - Optional mapping of (slot_number, time_string) for troops day
+/// 
+/// If time slot mappings are not provided, falls back to the fixed time mapping (backward compatibility)
+pub fn load_appointments<P: AsRef<Path>>(
+    csv_path: P,
+    construction_time_slots: Option<&[(u8, String)]>,
+    research_time_slots: Option<&[(u8, String)]>,
+    troops_time_slots: Option<&[(u8, String)]>,
+) -> Result<Vec<AppointmentEntry>, Box<dyn std::error::Error>> {
+    let file = std::fs::File::open(csv_path)?;
+    read_appointments(file, construction_time_slots, research_time_slots, troops_time_slots)
+}
+
+/// Same as [`load_appointments`], for CSV data from any reader (e.g. stdin)
+pub fn read_appointments<R: Read>(
+    csv: R,
+    construction_time_slots: Option<&[(u8, String)]>,
+    research_time_slots: Option<&[(u8, String)]>,
+    troops_time_slots: Option<&[(u8, String)]>,
+) -> Result<Vec<AppointmentEntry>, Box<dyn std::error::Error>> {
+    let mut reader = Reader::from_reader(csv);
+    // Read the header (which spans multiple lines in this CSV)
+    let columns = SubmissionColumns::find(reader.headers()?);
+    parse_records(&mut reader, columns, 16, construction_time_slots, research_time_slots, troops_time_slots)
+}
+
+/// Same as [`read_appointments`] for CSVs laid out differently from the form, with the columns
+/// found through [`SubmissionColumns::resolve`]. Fails if a field has no column.
+pub fn read_mapped_appointments<R: Read>(
+    csv: R,
+    mapping: &ColumnMapping,
+    construction_time_slots: Option<&[(u8, String)]>,
+    research_time_slots: Option<&[(u8, String)]>,
+    troops_time_slots: Option<&[(u8, String)]>,
+) -> Result<Vec<AppointmentEntry>, Box<dyn std::error::Error>> {
+    let mut reader = Reader::from_reader(csv);
+    let columns = SubmissionColumns::resolve(reader.headers()?, mapping)
+        .map_err(|missing| format!("No column for {}", missing.join(", ")))?;
+    // Only rows missing the name or ID are incomplete, as the mapped columns can be in any order
+    let min_len = columns.name.max(columns.id) + 1;
+    parse_records(&mut reader, columns, min_len, construction_time_slots, research_time_slots, troops_time_slots)
+}
+
+/// Reads the records after the header into entries, skipping records shorter than `min_len`
+fn parse_records<R: Read>(
+    reader: &mut Reader<R>,
+    columns: SubmissionColumns,
+    min_len: usize,
+    construction_time_slots: Option<&[(u8, String)]>,
+    research_time_slots: Option<&[(u8, String)]>,
+    troops_time_slots: Option<&[(u8, String)]>,
+) -> Result<Vec<AppointmentEntry>, Box<dyn std::error::Error>> {
+    // Use HashMap to track entries by player_id for handling resubmissions
+    let mut entries_map: HashMap<String, AppointmentEntry> = HashMap::new();
+    // First submission order of each player, so equal scores are always scheduled in the same order
+    let mut submission_order: Vec<String> = Vec::new();
+    
+    // Read all records
+    for result in reader.records() {
+        let record = result?;
+        
+        if record.len() < min_len {
+            continue; // Skip incomplete records
+        }
+        
+        let mut alliance = record.get(columns.alliance).unwrap_or("").trim().to_string();
+        // If alliance is "Non of the above", use the custom alliance tag instead
+        if alliance.to_lowercase().contains("non of the above") || alliance.to_lowercase() == "non" {
+            let custom_alliance = record.get(columns.custom_alliance).unwrap_or("").trim().to_string();
+            if !custom_alliance.is_empty() {
+                alliance = custom_alliance;
+            }
+        }
+        let name = record.get(columns.name).unwrap_or("").trim().to_string();
+        let player_id = record.get(columns.id).unwrap_or("").trim().to_string();
+        let submission_type = record.get(columns.submission_type).unwrap_or("").trim().to_lowercase();
+        
+        // Skip if essential fields are missing
+        if name.is_empty() || player_id.is_empty() {
+            continue;
+        }
+        
+        let is_resubmission = submission_type.contains("re-submission") || submission_type.contains("resubmission");
+        
+        let wants_construction = parse_bool(record.get(columns.construction_want).unwrap_or(""));
+        let wants_research = parse_bool(record.get(columns.research_want).unwrap_or(""));
+        let wants_troops = parse_bool(record.get(columns.troops_want).unwrap_or(""));
+        
+        let construction_speedups = parse_number(record.get(columns.construction_speedups).unwrap_or(""));
+        let research_speedups = parse_number(record.get(columns.research_speedups).unwrap_or(""));
+        let troops_speedups = parse_number(record.get(columns.troops_speedups).unwrap_or(""));
+        
+        let construction_truegold = parse_number(record.get(columns.construction_truegold).unwrap_or(""));
+        
+        let construction_score = ScoreWeights::default().construction_score(construction_truegold, construction_speedups);
+        
+        let research_truegold_dust = parse_number(record.get(columns.research_truegold_dust).unwrap_or(""));
+        
+        let research_score = ScoreWeights::default().research_score(research_truegold_dust, research_speedups);
+        
+        let construction_times = record.get(columns.construction_times).unwrap_or("");
+        let research_times = record.get(columns.research_times).unwrap_or("");
+        let troops_times = record.get(columns.troops_times).unwrap_or("");
+        
+        let construction_available_slots = parse_time_slots(construction_times, construction_time_slots);
+        let research_available_slots = parse_time_slots(research_times, research_time_slots);
+        let troops_available_slots = parse_time_slots(troops_times, troops_time_slots);
+        
+        if is_resubmission {
+            // Update existing entry if it exists
+            if let Some(existing_entry) = entries_map.get_mut(&player_id) {
+                // Update all fields with the new values
+                existing_entry.alliance = alliance;
+                existing_entry.name = name;
+                existing_entry.wants_construction = wants_construction;
+                existing_entry.wants_research = wants_research;
+                existing_entry.wants_troops = wants_troops;
+                existing_entry.construction_speedups = construction_speedups;
+                existing_entry.research_speedups = research_speedups;
+                existing_entry.troops_speedups = troops_speedups;
+                existing_entry.construction_truegold = construction_truegold;
+                existing_entry.construction_score = construction_score;
+                existing_entry.research_truegold_dust = research_truegold_dust;
+                existing_entry.research_score = research_score;
+                existing_entry.construction_available_slots = construction_available_slots.clone();
+                existing_entry.research_available_slots = research_available_slots.clone();
+                existing_entry.troops_available_slots = troops_available_slots.clone();
+            } else {
+                // If no existing entry found, treat it as a new entry (shouldn't happen, but handle gracefully)
+                let new_entry = AppointmentEntry {
+                    alliance,
+                    name,
+                    player_id: player_id.clone(),
+                    wants_construction,
+                    wants_research,
+                    wants_troops,
+                    construction_speedups,
+                    research_speedups,
+                    troops_speedups,
+                    construction_truegold,
+                    construction_score,
+                    research_truegold_dust,
+                    research_score,
+                    construction_available_slots,
+                    research_available_slots,
+                    troops_available_slots,
+                };
+                submission_order.push(player_id.clone());
+                entries_map.insert(player_id, new_entry);
+            }
+        } else {
+            // New submission - add or replace (in case of duplicate new submissions)
+            let new_entry = AppointmentEntry {
+                alliance,
+                name,
+                player_id: player_id.clone(),
+                wants_construction,
+                wants_research,
+                wants_troops,
+                construction_speedups,
+                research_speedups,
+                troops_speedups,
+                construction_truegold,
+                construction_score,
+                research_truegold_dust,
+                research_score,
+                construction_available_slots,
+                research_available_slots,
+                troops_available_slots,
+            };
+            if !entries_map.contains_key(&player_id) {
+                submission_order.push(player_id.clone());
+            }
+            entries_map.insert(player_id, new_entry);
+        }
+    }
+    
+    // Convert HashMap values to Vec, in submission order
+    let entries: Vec<AppointmentEntry> = submission_order.iter()
+        .filter_map(|player_id| entries_map.remove(player_id))
+        .collect();
+    
+    Ok(entries)
+}
+
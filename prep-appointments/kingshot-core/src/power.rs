@@ -0,0 +1,218 @@
+//! Power level datasets: one `(alliance, name, power)` row per player, as read from the
+//! in-game member list by an extraction tool, and their use as a priority score component.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::parser::{AppointmentEntry, ScoreWeights};
+
+/// One player's power as read from the member list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowerRecord {
+    #[serde(default, alias = "tag")]
+    pub alliance: String, // Alliance tag, empty if none
+    pub name: String,
+    #[serde(deserialize_with = "deserialize_power")]
+    pub power: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player_id: Option<String>, // Only when the dataset was matched against submissions
+}
+
+/// Reads a power CSV with an `alliance,name,power` header (`tag` is accepted for `alliance`,
+/// `player_id` is optional). The power column takes thousands separators ("12,345,678") and
+/// K/M/B suffixes ("12.5M"), see [`parse_power`]. Rows without a name are skipped.
+pub fn read_power_records<R: Read>(input: R) -> Result<Vec<PowerRecord>, csv::Error> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(input);
+    let mut records = Vec::new();
+    for record in reader.deserialize::<PowerRecord>() {
+        let mut record = record?;
+        record.player_id = record.player_id.filter(|id| !id.is_empty());
+        if !record.name.is_empty() {
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+/// Writes power records as CSV, in the format [`read_power_records`] reads
+pub fn write_power_records<W: Write>(output: W, records: &[PowerRecord]) -> Result<(), csv::Error> {
+    let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(output);
+    writer.write_record(["alliance", "name", "power", "player_id"])?;
+    for record in records {
+        writer.write_record([
+            record.alliance.as_str(),
+            record.name.as_str(),
+            &record.power.to_string(),
+            record.player_id.as_deref().unwrap_or(""),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// A power dataset matched against submission entries
+#[derive(Debug, Clone, Default)]
+pub struct PowerMatch {
+    pub power: HashMap<String, u64>, // Player ID -> power
+    pub unmatched: Vec<PowerRecord>, // Records matching no entry, or several
+}
+
+impl PowerMatch {
+    /// A player's power and gain since `baseline` (0 when the baseline doesn't list them)
+    pub fn reading(&self, player_id: &str, baseline: Option<&PowerMatch>) -> Option<(u64, u64)> {
+        let current = *self.power.get(player_id)?;
+        let gain = baseline
+            .and_then(|b| b.power.get(player_id))
+            .map_or(0, |&before| current.saturating_sub(before));
+        Some((current, gain))
+    }
+}
+
+/// Matches power records to submission entries: by `player_id` when the record has one,
+/// otherwise by alliance tag and name, ignoring case. A player listed twice keeps the
+/// higher reading.
+pub fn match_power_records(records: &[PowerRecord], entries: &[AppointmentEntry]) -> PowerMatch {
+    let key = |alliance: &str, name: &str| (alliance.trim().to_lowercase(), name.trim().to_lowercase());
+    let mut by_name: HashMap<(String, String), Vec<&str>> = HashMap::new();
+    for entry in entries {
+        by_name.entry(key(&entry.alliance, &entry.name)).or_default().push(&entry.player_id);
+    }
+
+    let mut matched = PowerMatch::default();
+    for record in records {
+        let player_id = match &record.player_id {
+            Some(id) => entries.iter().any(|e| &e.player_id == id).then_some(id.as_str()),
+            None => match by_name.get(&key(&record.alliance, &record.name)).map(Vec::as_slice) {
+                Some([id]) => Some(*id),
+                _ => None,
+            },
+        };
+        match player_id {
+            Some(id) => {
+                let power = matched.power.entry(id.to_string()).or_insert(0);
+                *power = (*power).max(record.power);
+            }
+            None => matched.unmatched.push(record.clone()),
+        }
+    }
+    matched
+}
+
+/// Adds [`ScoreWeights::power_score`] to the construction and research scores of every
+/// matched entry. The gain is measured against `baseline`; players missing from it gain nothing.
+pub fn apply_power_scores(entries: &mut [AppointmentEntry], power: &PowerMatch, baseline: Option<&PowerMatch>, weights: &ScoreWeights) {
+    for entry in entries {
+        let Some((current, gain)) = power.reading(&entry.player_id, baseline) else { continue };
+        let points = weights.power_score(current, gain);
+        entry.construction_score = entry.construction_score.saturating_add(points);
+        entry.research_score = entry.research_score.saturating_add(points);
+    }
+}
+
+fn deserialize_power<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    parse_power(&value).map_err(serde::de::Error::custom)
+}
+
+/// Parses a power value: a whole number, optionally grouped in thousands with `,` or `.`
+/// ("12,345,678", "12.345.678"), or a number with a K, M or B suffix as the game shows it,
+/// whose `.` or `,` is the decimal separator ("12.5M", "980K", "1,2B"). A fraction without a
+/// suffix ("12.5") is rejected rather than read as a different number.
+fn parse_power(value: &str) -> Result<u64, String> {
+    let compact: String = value.chars().filter(|c| !matches!(c, ' ' | '_')).collect();
+    let invalid = || format!("invalid power value \"{}\"", value);
+    let all_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    let (number, suffix_digits) = match compact.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => (&compact[..compact.len() - 1], 3),
+        Some('M') => (&compact[..compact.len() - 1], 6),
+        Some('B') => (&compact[..compact.len() - 1], 9),
+        _ => (compact.as_str(), 0),
+    };
+
+    if suffix_digits == 0 {
+        let mut groups = number.split([',', '.']);
+        let first = groups.next().unwrap_or_default();
+        let rest: Vec<&str> = groups.collect();
+        if !all_digits(first) || rest.iter().any(|group| !all_digits(group)) {
+            return Err(invalid());
+        }
+        if rest.iter().any(|group| group.len() != 3) {
+            return Err(format!("{}: use a whole number, or a K, M or B suffix for decimals (\"12.5M\")", invalid()));
+        }
+        return format!("{}{}", first, rest.concat()).parse().map_err(|_| invalid());
+    }
+
+    let (whole, fraction) = match number.split_once([',', '.']) {
+        Some((_, fraction)) if !all_digits(fraction) => return Err(invalid()),
+        Some(parts) => parts,
+        None => (number, ""),
+    };
+    if !all_digits(whole) {
+        return Err(invalid());
+    }
+    if fraction.len() > suffix_digits {
+        return Err(format!("{}: more decimals than the suffix allows", invalid()));
+    }
+    let scale = 10u64.pow(suffix_digits as u32);
+    let fraction = format!("{:0<width$}", fraction, width = suffix_digits);
+    whole.parse::<u64>().ok()
+        .and_then(|whole| whole.checked_mul(scale))
+        .zip(fraction.parse::<u64>().ok())
+        .and_then(|(whole, fraction)| whole.checked_add(fraction))
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_numbers_with_thousands_separators() {
+        assert_eq!(parse_power("12345678"), Ok(12_345_678));
+        assert_eq!(parse_power("12,345,678"), Ok(12_345_678));
+        assert_eq!(parse_power("12.345.678"), Ok(12_345_678));
+        assert_eq!(parse_power("12 345 678"), Ok(12_345_678));
+        assert_eq!(parse_power("12_345_678"), Ok(12_345_678));
+    }
+
+    #[test]
+    fn suffixes_scale_the_number() {
+        assert_eq!(parse_power("980K"), Ok(980_000));
+        assert_eq!(parse_power("12M"), Ok(12_000_000));
+        assert_eq!(parse_power("2b"), Ok(2_000_000_000));
+    }
+
+    #[test]
+    fn decimals_with_a_suffix_are_read_as_decimals() {
+        assert_eq!(parse_power("12.5M"), Ok(12_500_000));
+        assert_eq!(parse_power("12.3M"), Ok(12_300_000));
+        assert_eq!(parse_power("1,25B"), Ok(1_250_000_000));
+        assert_eq!(parse_power("0.5k"), Ok(500));
+        assert_eq!(parse_power("12.345M"), Ok(12_345_000));
+        assert_eq!(parse_power("12.5 M"), Ok(12_500_000));
+    }
+
+    #[test]
+    fn fractions_without_a_suffix_are_rejected() {
+        // Reading "12.5" as 125 is what the separators-only parsing used to do
+        assert!(parse_power("12.5").unwrap_err().contains("K, M or B suffix"));
+        assert!(parse_power("1,2345").is_err());
+    }
+
+    #[test]
+    fn malformed_values_are_rejected() {
+        for value in ["", "M", "12.5.1M", "1.2345K", "abc", "12x", "-5", "1.M", "99999999999B"] {
+            assert!(parse_power(value).is_err(), "{} should be rejected", value);
+        }
+    }
+
+    #[test]
+    fn csv_power_column_accepts_suffixes() {
+        let csv = "alliance,name,power\nABC,Alice,12.5M\nABC,Bob,\"1,234,567\"\n";
+        let records = read_power_records(csv.as_bytes()).unwrap();
+        assert_eq!(records.iter().map(|r| r.power).collect::<Vec<_>>(), [12_500_000, 1_234_567]);
+        assert!(read_power_records("alliance,name,power\nABC,Alice,12.5\n".as_bytes()).is_err());
+    }
+}
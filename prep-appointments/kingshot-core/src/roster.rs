@@ -0,0 +1,98 @@
+//! Player rosters: the players an account knows by ID, with the name and alliance the
+//! schedules should show, independent of what each week's submissions say.
+//!
+//! Players rename mid-week or type their name differently on each form. Matching the
+//! submissions against a roster by player ID keeps one name per player across schedules.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::parser::AppointmentEntry;
+
+/// One known player
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RosterEntry {
+    pub player_id: String,
+    #[serde(default)]
+    pub name: String, // Canonical name, empty keeps the submitted one
+    #[serde(default)]
+    pub alliance: String, // Alliance tag, empty keeps the submitted one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discord_id: Option<String>,
+    #[serde(default)]
+    pub notes: String,
+}
+
+/// Reads a roster CSV with a `player_id,name,alliance` header and optional `discord_id` and
+/// `notes` columns. Rows without a player ID are skipped.
+pub fn read_roster<R: Read>(input: R) -> Result<Vec<RosterEntry>, csv::Error> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(input);
+    let mut roster = Vec::new();
+    for entry in reader.deserialize::<RosterEntry>() {
+        let mut entry = entry?;
+        entry.discord_id = entry.discord_id.filter(|id| !id.is_empty());
+        if !entry.player_id.is_empty() {
+            roster.push(entry);
+        }
+    }
+    Ok(roster)
+}
+
+/// Writes a roster as CSV, in the format [`read_roster`] reads
+pub fn write_roster<W: Write>(output: W, roster: &[RosterEntry]) -> Result<(), csv::Error> {
+    let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(output);
+    writer.write_record(["player_id", "name", "alliance", "discord_id", "notes"])?;
+    for entry in roster {
+        writer.write_record([
+            entry.player_id.as_str(),
+            entry.name.as_str(),
+            entry.alliance.as_str(),
+            entry.discord_id.as_deref().unwrap_or(""),
+            entry.notes.as_str(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// The numeric Discord user ID in `raw`, which may also be a pasted mention (`<@123>`, `<@!123>`).
+/// None for anything else, such as a username, since only user IDs make mentions ping.
+pub fn normalize_discord_id(raw: &str) -> Option<String> {
+    let id = raw.trim();
+    let id = id.strip_prefix("<@").and_then(|id| id.strip_suffix('>')).map(|id| id.trim_start_matches('!')).unwrap_or(id);
+    (!id.is_empty() && id.len() <= 20 && id.chars().all(|c| c.is_ascii_digit())).then(|| id.to_string())
+}
+
+/// Player ID -> Discord user ID for the rostered players that have one
+pub fn discord_mentions(roster: &[RosterEntry]) -> HashMap<String, String> {
+    roster.iter()
+        .filter_map(|r| Some((r.player_id.clone(), normalize_discord_id(r.discord_id.as_deref()?)?)))
+        .collect()
+}
+
+/// Gives the entries of rostered players their roster name and alliance.
+/// Returns the number of entries whose name or alliance changed.
+pub fn apply_roster(entries: &mut [AppointmentEntry], roster: &[RosterEntry]) -> usize {
+    let by_id: HashMap<&str, &RosterEntry> = roster.iter().map(|r| (r.player_id.as_str(), r)).collect();
+    let mut changed = 0;
+    for entry in entries.iter_mut() {
+        let Some(known) = by_id.get(entry.player_id.as_str()) else { continue };
+        let name = known.name.trim();
+        let alliance = known.alliance.trim();
+        let mut renamed = false;
+        if !name.is_empty() && entry.name != name {
+            entry.name = name.to_string();
+            renamed = true;
+        }
+        if !alliance.is_empty() && entry.alliance != alliance {
+            entry.alliance = alliance.to_string();
+            renamed = true;
+        }
+        if renamed {
+            changed += 1;
+        }
+    }
+    changed
+}
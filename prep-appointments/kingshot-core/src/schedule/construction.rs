@@ -98,6 +98,8 @@ pub fn schedule_construction_day_with_locked(
                 alliance: entry.alliance.clone(),
                 slot: last_slot,
                 priority_score: entry.construction_score,
+                note: None,
+                manual: false,
             });
             used_slots.insert(last_slot);
             last_slot_assigned = true;
@@ -130,7 +132,7 @@ pub fn schedule_construction_day_with_locked(
             .iter()
             .map(|&slot| (slot, slot_rankings.get(&slot).copied().unwrap_or(0)))
             .collect();
-        ranked_slots.sort_by(|a, b| b.1.cmp(&a.1)); // Sort by rank descending
+        ranked_slots.sort_by_key(|s| std::cmp::Reverse(s.1)); // Sort by rank descending
         
         // Try to assign the highest-ranked available slot
         let mut assigned = false;
@@ -143,6 +145,8 @@ pub fn schedule_construction_day_with_locked(
                     alliance: entry.alliance.clone(),
                     slot: *slot,
                     priority_score: entry.construction_score,
+                    note: None,
+                    manual: false,
                 });
                 used_slots.insert(*slot);
                 assigned = true;
@@ -254,6 +258,8 @@ pub fn schedule_construction_day_with_locked(
                                 alliance: entry.alliance.clone(),
                                 slot: *requested_slot,
                                 priority_score: entry.construction_score,
+                                note: None,
+                                manual: false,
                             });
                             used_slots.insert(*requested_slot);
                             assigned = true;
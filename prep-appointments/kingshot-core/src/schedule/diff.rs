@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use super::{DaySchedule, ScheduleData, ScheduledAppointment};
+
+/// A player whose slot changed between two versions of a day schedule
+#[derive(Debug, Clone, Serialize)]
+pub struct MovedPlayer {
+    pub player_id: String,
+    pub name: String,
+    pub alliance: String,
+    pub from_slot: u8,
+    pub to_slot: u8,
+}
+
+/// Changes between two versions of one day schedule, matched by player ID
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DayDiff {
+    pub moved: Vec<MovedPlayer>,
+    pub added: Vec<ScheduledAppointment>,   // Assigned in the new schedule only
+    pub dropped: Vec<ScheduledAppointment>, // Assigned in the old schedule only
+}
+
+impl DayDiff {
+    pub fn is_empty(&self) -> bool {
+        self.moved.is_empty() && self.added.is_empty() && self.dropped.is_empty()
+    }
+}
+
+/// Compares two day schedules. A missing day counts as an empty schedule.
+/// Every list is sorted by slot (the new slot for moved players).
+pub fn diff_days(old: Option<&DaySchedule>, new: Option<&DaySchedule>) -> DayDiff {
+    let by_player = |schedule: Option<&DaySchedule>| -> HashMap<String, ScheduledAppointment> {
+        schedule.iter()
+            .flat_map(|s| s.appointments.values())
+            .map(|appt| (appt.player_id.clone(), appt.clone()))
+            .collect()
+    };
+    let old = by_player(old);
+    let mut new = by_player(new);
+
+    let mut diff = DayDiff::default();
+    for (player_id, old_appt) in old {
+        match new.remove(&player_id) {
+            Some(new_appt) if new_appt.slot != old_appt.slot => diff.moved.push(MovedPlayer {
+                player_id,
+                name: new_appt.name,
+                alliance: new_appt.alliance,
+                from_slot: old_appt.slot,
+                to_slot: new_appt.slot,
+            }),
+            Some(_) => {}
+            None => diff.dropped.push(old_appt),
+        }
+    }
+    diff.added = new.into_values().collect();
+
+    diff.moved.sort_by_key(|m| m.to_slot);
+    diff.added.sort_by_key(|a| a.slot);
+    diff.dropped.sort_by_key(|a| a.slot);
+    diff
+}
+
+/// Changes between two versions of a full schedule, per day
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScheduleDiff {
+    pub construction: DayDiff,
+    pub research: DayDiff,
+    pub troops: DayDiff,
+}
+
+/// Compares every day of two schedules, e.g. before and after an append run
+pub fn diff_schedules(old: &ScheduleData, new: &ScheduleData) -> ScheduleDiff {
+    ScheduleDiff {
+        construction: diff_days(old.construction_schedule.as_ref(), new.construction_schedule.as_ref()),
+        research: diff_days(old.research_schedule.as_ref(), new.research_schedule.as_ref()),
+        troops: diff_days(old.troops_schedule.as_ref(), new.troops_schedule.as_ref()),
+    }
+}
@@ -4,19 +4,6 @@ use super::types::{ScheduledAppointment, DaySchedule};
 use super::slot_utils::calculate_slot_rankings;
 use super::move_chain::{find_move_chain, apply_move_chain};
 
-/// Generic scheduling function with slot ranking and stealing
-pub fn schedule_day_generic<F>(
-    entries: &[AppointmentEntry],
-    wants_filter: F,
-    get_available_slots: fn(&AppointmentEntry) -> &Vec<u8>,
-    get_priority_score: fn(&AppointmentEntry) -> u32,
-) -> DaySchedule
-where
-    F: Fn(&AppointmentEntry) -> bool,
-{
-    schedule_day_generic_with_locked_slots(entries, wants_filter, get_available_slots, get_priority_score, &HashSet::new(), &HashSet::new())
-}
-
 /// Generic scheduling function with slot ranking and stealing, with pre-locked slots
 pub fn schedule_day_generic_with_locked_slots<F>(
     entries: &[AppointmentEntry],
@@ -67,7 +54,7 @@ where
             .iter()
             .map(|&slot| (slot, slot_rankings.get(&slot).copied().unwrap_or(0)))
             .collect();
-        ranked_slots.sort_by(|a, b| b.1.cmp(&a.1)); // Sort by rank descending
+        ranked_slots.sort_by_key(|s| std::cmp::Reverse(s.1)); // Sort by rank descending
         
         // Try to assign the highest-ranked available slot
         let mut assigned = false;
@@ -80,6 +67,8 @@ where
                     alliance: entry.alliance.clone(),
                     slot: *slot,
                     priority_score: get_priority_score(entry),
+                    note: None,
+                    manual: false,
                 });
                 used_slots.insert(*slot);
                 assigned = true;
@@ -98,7 +87,7 @@ where
                 .collect();
             
             // Sort by priority score (lowest first) - we'll try to move lowest-scoring players first
-            blocking_players.sort_by(|a, b| a.2.cmp(&b.2));
+            blocking_players.sort_by_key(|p| p.2);
             
             // Try to steal a slot with depth-limited search (up to 5 levels)
             for (requested_slot, _blocking_player_id, _blocking_score) in &blocking_players {
@@ -136,6 +125,8 @@ where
                                 alliance: entry.alliance.clone(),
                                 slot: *requested_slot,
                                 priority_score: get_priority_score(entry),
+                                note: None,
+                                manual: false,
                             });
                             used_slots.insert(*requested_slot);
                             assigned = true;
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::parser::AppointmentEntry;
+use super::{slot_to_time, DaySchedule, ScheduledAppointment};
+
+/// A line of a text schedule that couldn't be imported
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportError {
+    pub line: usize, // 1-based
+    pub message: String,
+}
+
+/// Headers written above each day of a text schedule, with the day they stand for
+const DAY_HEADERS: [(&str, &str); 3] = [
+    ("Construction Day", "construction"),
+    ("Research Day", "research"),
+    ("Troops Training Day", "troops"),
+];
+
+/// The day ("construction", "research" or "troops") named by the `** Construction Day **`
+/// header of a text schedule, if it has one
+pub fn text_schedule_day(text: &str) -> Option<&'static str> {
+    let header = text.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let title = header.strip_prefix("**")?.strip_suffix("**")?.trim();
+    DAY_HEADERS.iter().find(|(name, _)| name.eq_ignore_ascii_case(title)).map(|(_, day)| *day)
+}
+
+/// Parses a text schedule (`HH:MM [TAG] Name` per slot, `HH:MM [EMPTY]` for free slots) back
+/// into a day schedule, e.g. after a lead edited the exported `schedule_*.txt` by hand.
+///
+/// Players are matched against `entries` by name and alliance tag, ignoring case; a line
+/// without a tag matches a player without an alliance. Slots missing from the text stay empty.
+/// Players who asked for `day` ("construction", "research" or "troops") but aren't in the
+/// text become the unassigned list. Every line that can't be imported is reported, so all
+/// mistakes can be fixed in one go.
+pub fn import_day_text(text: &str, day: &str, entries: &[AppointmentEntry]) -> Result<DaySchedule, Vec<ImportError>> {
+    let slots_by_time: HashMap<String, u8> = (1..=49).map(|slot| (slot_to_time(slot), slot)).collect();
+    // Same priority scores the scheduler stores for each day
+    let score = |e: &AppointmentEntry| match day {
+        "construction" => e.construction_score,
+        "research" => e.research_score,
+        _ => e.troops_speedups,
+    };
+    let wants = |e: &AppointmentEntry| match day {
+        "construction" => e.wants_construction,
+        "research" => e.wants_research,
+        _ => e.wants_troops,
+    };
+
+    let mut appointments: HashMap<u8, ScheduledAppointment> = HashMap::new();
+    let mut errors = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || (line.starts_with("**") && line.ends_with("**")) {
+            continue;
+        }
+        let mut error = |message: String| errors.push(ImportError { line: index + 1, message });
+
+        let (time, player) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let Some(&slot) = slots_by_time.get(time) else {
+            error(format!("expected a slot time like 00:45 at the start, found \"{}\"", time));
+            continue;
+        };
+        let player = player.trim();
+        if player.is_empty() || player.eq_ignore_ascii_case("[EMPTY]") {
+            continue;
+        }
+
+        let matches = match_player(player, entries);
+        let entry = match matches.as_slice() {
+            [entry] => *entry,
+            [] => {
+                error(format!("no player \"{}\" in the submissions", player));
+                continue;
+            }
+            several => {
+                let ids: Vec<&str> = several.iter().map(|e| e.player_id.as_str()).collect();
+                error(format!("\"{}\" matches several players (IDs {})", player, ids.join(", ")));
+                continue;
+            }
+        };
+
+        if appointments.contains_key(&slot) {
+            error(format!("{} is listed twice", time));
+        } else if let Some(other) = appointments.values().find(|a| a.player_id == entry.player_id) {
+            error(format!("\"{}\" is already at {}", player, slot_to_time(other.slot)));
+        } else {
+            appointments.insert(slot, ScheduledAppointment {
+                player_id: entry.player_id.clone(),
+                name: entry.name.clone(),
+                alliance: entry.alliance.clone(),
+                slot,
+                priority_score: score(entry),
+                note: None,
+                manual: false,
+            });
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let unassigned = entries.iter()
+        .filter(|e| wants(e) && !appointments.values().any(|a| a.player_id == e.player_id))
+        .map(|e| e.player_id.clone())
+        .collect();
+    Ok(DaySchedule { appointments, unassigned })
+}
+
+/// Entries a `[TAG] Name` or `Name` line can stand for. A bracketed start is tried as the
+/// tag first and then as part of the name, for players whose name starts with a bracket.
+fn match_player<'a>(player: &str, entries: &'a [AppointmentEntry]) -> Vec<&'a AppointmentEntry> {
+    let find = |alliance: &str, name: &str| -> Vec<&'a AppointmentEntry> {
+        let (alliance, name) = (alliance.to_lowercase(), name.to_lowercase());
+        entries.iter()
+            .filter(|e| e.alliance.trim().to_lowercase() == alliance && e.name.trim().to_lowercase() == name)
+            .collect()
+    };
+    let tagged = player.strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+        .map(|(tag, name)| find(tag.trim(), name.trim()))
+        .unwrap_or_default();
+    if tagged.is_empty() { find("", player) } else { tagged }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(player_id: &str, alliance: &str, name: &str) -> AppointmentEntry {
+        AppointmentEntry {
+            alliance: alliance.to_string(),
+            name: name.to_string(),
+            player_id: player_id.to_string(),
+            wants_construction: true,
+            wants_research: false,
+            wants_troops: true,
+            construction_speedups: 0,
+            research_speedups: 0,
+            troops_speedups: 40,
+            construction_truegold: 0,
+            construction_score: 1000,
+            research_truegold_dust: 0,
+            research_score: 0,
+            construction_available_slots: Vec::new(),
+            research_available_slots: Vec::new(),
+            troops_available_slots: Vec::new(),
+        }
+    }
+
+    fn entries() -> Vec<AppointmentEntry> {
+        vec![
+            entry("1", "ABC", "Alice"),
+            entry("2", "", "Bob"),
+            entry("3", "", "[XYZ] Carol"),
+            entry("4", "ABC", "Dave"),
+            entry("5", "ABC", "Dave"),
+            entry("6", "DEF", "Dave"),
+        ]
+    }
+
+    fn messages(errors: &[ImportError]) -> Vec<(usize, &str)> {
+        errors.iter().map(|e| (e.line, e.message.as_str())).collect()
+    }
+
+    #[test]
+    fn day_header() {
+        assert_eq!(text_schedule_day("\n** Construction Day **\n00:00 [EMPTY]"), Some("construction"));
+        assert_eq!(text_schedule_day("**troops training day**"), Some("troops"));
+        assert_eq!(text_schedule_day("** Research Day **"), Some("research"));
+        assert_eq!(text_schedule_day("00:00 Bob\n** Research Day **"), None);
+        assert_eq!(text_schedule_day("** Party Day **"), None);
+        assert_eq!(text_schedule_day(""), None);
+    }
+
+    #[test]
+    fn imports_players_by_tag_and_name() {
+        let text = "** Construction Day **\n00:00 [abc] alice\n00:15 Bob\n00:45 [EMPTY]\n01:15 [XYZ] Carol\n\n01:45 [DEF] Dave\n";
+        let schedule = import_day_text(text, "construction", &entries()).unwrap();
+
+        let player = |slot: u8| schedule.appointments.get(&slot).map(|a| a.player_id.as_str());
+        assert_eq!(player(1), Some("1"));
+        assert_eq!(player(2), Some("2"));
+        assert_eq!(player(3), None);
+        assert_eq!(player(4), Some("3")); // No alliance XYZ, so the bracket is part of the name
+        assert_eq!(player(5), Some("6"));
+        assert_eq!(schedule.appointments.len(), 4);
+
+        let alice = &schedule.appointments[&1];
+        assert_eq!((alice.name.as_str(), alice.alliance.as_str(), alice.priority_score), ("Alice", "ABC", 1000));
+        assert_eq!(schedule.unassigned, vec!["4".to_string(), "5".to_string()]);
+    }
+
+    #[test]
+    fn scores_and_unassigned_follow_the_day() {
+        let schedule = import_day_text("00:00 Bob", "troops", &entries()).unwrap();
+        assert_eq!(schedule.appointments[&1].priority_score, 40);
+        assert_eq!(schedule.unassigned.len(), 5);
+
+        let schedule = import_day_text("00:00 Bob", "research", &entries()).unwrap();
+        assert!(schedule.unassigned.is_empty());
+    }
+
+    #[test]
+    fn reports_every_bad_line() {
+        let text = [
+            "** Construction Day **",
+            "0:00 Bob",
+            "00:15 Nobody",
+            "00:45 [ABC] Dave",
+            "01:15 [ABC] Alice",
+            "01:15 Bob",
+            "01:45 [abc] ALICE",
+            "12:34",
+        ].join("\n");
+        let errors = import_day_text(&text, "construction", &entries()).unwrap_err();
+        assert_eq!(messages(&errors), vec![
+            (2, "expected a slot time like 00:45 at the start, found \"0:00\""),
+            (3, "no player \"Nobody\" in the submissions"),
+            (4, "\"[ABC] Dave\" matches several players (IDs 4, 5)"),
+            (6, "01:15 is listed twice"),
+            (7, "\"[abc] ALICE\" is already at 01:15"),
+            (8, "expected a slot time like 00:45 at the start, found \"12:34\""),
+        ]);
+    }
+}
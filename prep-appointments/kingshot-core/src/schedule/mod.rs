@@ -0,0 +1,28 @@
+//! Slot assignment for the three preparation days.
+//!
+//! Each day fills its slots by priority score, moving already placed players to free slots
+//! when that lets a higher priority player in ([`move_chain`]). The construction day's last
+//! slot is linked to research slot 1 so one player can use both back to back.
+
+pub mod types;
+pub mod slot_utils;
+pub mod move_chain;
+pub mod generic;
+pub mod construction;
+pub mod research;
+pub mod troops;
+pub mod predetermined;
+pub mod diff;
+pub mod validate;
+pub mod import;
+
+pub use types::{derive_scheduled_player_ids, get_scheduled_player_ids, DaySchedule, DayTimeConfig, GuaranteedPlayers, PredeterminedSlot, ScheduleData, ScheduledAppointment, TimeWindow};
+pub use slot_utils::{slot_to_time, calculate_time_slots, calculate_day_time_slots, calculate_blackout_slots, remove_blackout_slots, validate_day_windows, MAX_DAY_SLOTS};
+pub use move_chain::{take_chain_stats, ChainStats};
+pub use construction::{schedule_construction_day, schedule_construction_day_with_locked};
+pub use research::{schedule_research_day, schedule_research_day_with_locked};
+pub use troops::{schedule_troops_day, schedule_troops_day_with_locked};
+pub use predetermined::{generate_schedules, last_construction_slot, merge_appended, regenerate_day, resolve_predetermined, DayTimeSlots, ExistingDays, ResolvedSlot};
+pub use diff::{diff_days, diff_schedules, DayDiff, MovedPlayer, ScheduleDiff};
+pub use validate::{check_predetermined, validate_schedule, Violation};
+pub use import::{import_day_text, text_schedule_day, ImportError};
@@ -1,9 +1,39 @@
-use std::collections::{HashMap, HashSet};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use crate::parser::AppointmentEntry;
 use super::types::{Move, ScheduledAppointment};
 
+/// Counts of slot stealing on the current thread since the last [`take_chain_stats`]
+#[derive(Debug, Clone, Default)]
+pub struct ChainStats {
+    pub searches: usize, // Blocked players the schedulers tried to move
+    pub applied: usize,  // Searches that found a chain
+    pub lengths: BTreeMap<usize, usize>, // Chain length (moves) -> number of chains
+}
+
+impl ChainStats {
+    /// Adds another run's counts
+    pub fn merge(&mut self, other: &ChainStats) {
+        self.searches += other.searches;
+        self.applied += other.applied;
+        for (length, count) in &other.lengths {
+            *self.lengths.entry(*length).or_insert(0) += count;
+        }
+    }
+}
+
+thread_local! {
+    static CHAIN_STATS: RefCell<ChainStats> = RefCell::new(ChainStats::default());
+}
+
+/// Returns the stealing counts collected on this thread and resets them
+pub fn take_chain_stats() -> ChainStats {
+    CHAIN_STATS.with(|stats| stats.take())
+}
+
 /// Tries to find a chain of moves to free up a slot, with depth limit
-/// Returns Some(Vec<Move>) if a chain is found, None otherwise
+/// Returns `Some(Vec<Move>)` if a chain is found, None otherwise
+#[allow(clippy::too_many_arguments)]
 pub fn find_move_chain(
     player_id: &str,
     current_slot: u8,
@@ -20,6 +50,9 @@ pub fn find_move_chain(
     if depth > max_depth {
         return None;
     }
+    if depth == 1 {
+        CHAIN_STATS.with(|stats| stats.borrow_mut().searches += 1);
+    }
     
     // Cannot move from a locked slot
     if locked_slots.contains(&current_slot) {
@@ -49,7 +82,7 @@ pub fn find_move_chain(
             (slot, priority)
         })
         .collect();
-    slot_priorities.sort_by(|a, b| b.1.cmp(&a.1));
+    slot_priorities.sort_by_key(|s| std::cmp::Reverse(s.1));
     
     for (target_slot, _) in slot_priorities {
         if let Some(blocking_appt) = schedule.get(&target_slot) {
@@ -110,11 +143,19 @@ pub fn apply_move_chain(
     schedule: &mut HashMap<u8, ScheduledAppointment>,
     used_slots: &mut HashSet<u8>,
 ) {
+    CHAIN_STATS.with(|stats| {
+        let mut stats = stats.borrow_mut();
+        stats.applied += 1;
+        *stats.lengths.entry(moves.len()).or_insert(0) += 1;
+    });
+    log::debug!("slot stealing: applying a chain of {} move(s)", moves.len());
+
     // Apply moves in reverse order to avoid conflicts
     for mv in moves.iter().rev() {
         if let Some(mut appt) = schedule.remove(&mv.from_slot) {
             // Verify we're moving the correct player
             if appt.player_id == mv.player_id {
+                log::trace!("moving player {} from slot {} to {}", appt.player_id, mv.from_slot, mv.to_slot);
                 appt.slot = mv.to_slot;
                 schedule.insert(mv.to_slot, appt);
                 used_slots.remove(&mv.from_slot);
@@ -122,7 +163,7 @@ pub fn apply_move_chain(
             } else {
                 // This shouldn't happen, but if it does, put the appointment back
                 schedule.insert(mv.from_slot, appt);
-                eprintln!("Warning: Attempted to move wrong player from slot {}", mv.from_slot);
+                log::warn!("attempted to move wrong player from slot {}", mv.from_slot);
             }
         }
     }
@@ -0,0 +1,583 @@
+use std::collections::{HashMap, HashSet};
+use crate::parser::{time_to_slot, AppointmentEntry};
+use super::types::{DaySchedule, GuaranteedPlayers, PredeterminedSlot, ScheduledAppointment};
+use super::{schedule_construction_day_with_locked, schedule_research_day_with_locked, schedule_troops_day_with_locked};
+
+/// Time slot mappings (slot number, time string) for each day, as produced by `calculate_time_slots`.
+/// `None` means the fixed default time mapping is used for that day.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DayTimeSlots<'a> {
+    pub construction: Option<&'a [(u8, String)]>,
+    pub research: Option<&'a [(u8, String)]>,
+    pub troops: Option<&'a [(u8, String)]>,
+}
+
+/// Schedule that new assignments are appended to. Its filled slots are locked.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExistingDays<'a> {
+    pub construction: Option<&'a DaySchedule>,
+    pub research: Option<&'a DaySchedule>,
+    pub troops: Option<&'a DaySchedule>,
+}
+
+/// Converts a predetermined time string to a slot number using the day's time configuration.
+/// Falls back to the default time mapping if the custom slots are empty or the time isn't found.
+pub(super) fn predetermined_time_to_slot(time_str: &str, time_slots: &[(u8, String)]) -> Option<u8> {
+    let clean_time = time_str.trim();
+    time_slots.iter()
+        .find(|(_, time)| time.trim() == clean_time)
+        .map(|(slot, _)| *slot)
+        .or_else(|| time_to_slot(clean_time))
+}
+
+/// A predetermined assignment resolved to a player ID and slot number
+#[derive(Debug, Clone)]
+pub struct ResolvedSlot {
+    pub day: String, // "construction", "research" or "troops"
+    pub slot: u8,
+    pub player_id: String,
+    pub alliance: String,
+    pub name: String,
+}
+
+/// The construction day's last slot (linked to research slot 1), from the time config or 49
+pub fn last_construction_slot(time_slots: DayTimeSlots) -> u8 {
+    time_slots.construction.unwrap_or_default().iter()
+        .map(|(s, _)| *s)
+        .max()
+        .unwrap_or(49)
+}
+
+/// Resolves predetermined assignments to player IDs (looking players up by alliance and name when
+/// no ID is given) and slot numbers. Errors on unknown players, unrecognized times and two players
+/// in one slot.
+pub fn resolve_predetermined(
+    entries: &[AppointmentEntry],
+    predetermined: &[PredeterminedSlot],
+    time_slots: DayTimeSlots,
+) -> Result<Vec<ResolvedSlot>, String> {
+    let construction_slots_vec = time_slots.construction.unwrap_or_default();
+    let research_slots_vec = time_slots.research.unwrap_or_default();
+    let troops_slots_vec = time_slots.troops.unwrap_or_default();
+
+    // Resolve all predetermined slots - get player_id (from slot or lookup) and resolve slot numbers
+    let mut invalid_slots: Vec<String> = Vec::new();
+    let mut resolved_slots: Vec<ResolvedSlot> = Vec::new();
+    let mut seen_slots: HashMap<String, String> = HashMap::new();
+
+    let find_by_name = |pred_slot: &PredeterminedSlot| {
+        entries.iter().find(|e| {
+            e.alliance.trim().eq_ignore_ascii_case(pred_slot.alliance.trim()) &&
+            e.name.trim().eq_ignore_ascii_case(pred_slot.name.trim())
+        })
+    };
+
+    for pred_slot in predetermined {
+        // Resolve player_id: use from slot if present, else lookup by alliance+name in entries
+        let (player_id, alliance, name) = match pred_slot.player_id.as_deref().map(str::trim) {
+            Some(pid) if !pid.is_empty() => {
+                let (a, n) = entries.iter()
+                    .find(|e| e.player_id == pid)
+                    .map(|e| (e.alliance.clone(), e.name.clone()))
+                    .unwrap_or((pred_slot.alliance.clone(), pred_slot.name.clone()));
+                (pid.to_string(), a, n)
+            }
+            Some(_) => match find_by_name(pred_slot) {
+                Some(e) => (e.player_id.clone(), e.alliance.clone(), e.name.clone()),
+                None => return Err(format!(
+                    "Predetermined slot for {} {}: Player ID required. Enter player ID in the form, or ensure {} {} has submitted the form.",
+                    pred_slot.day, pred_slot.time, pred_slot.alliance, pred_slot.name
+                )),
+            },
+            None => match find_by_name(pred_slot) {
+                Some(e) => (e.player_id.clone(), e.alliance.clone(), e.name.clone()),
+                None => return Err(format!(
+                    "Predetermined slot for {} {}: Could not resolve player ID for {} {}. They must have submitted the form, or use player ID.",
+                    pred_slot.day, pred_slot.time, pred_slot.alliance, pred_slot.name
+                )),
+            },
+        };
+
+        // Validation: Check for duplicate predetermined slots (same day + time)
+        let slot_key = format!("{}:{}", pred_slot.day, pred_slot.time.trim());
+        if let Some(prev_id) = seen_slots.get(&slot_key) {
+            return Err(format!(
+                "Conflict: Multiple players predetermined for {} {} (player IDs {} and {})",
+                pred_slot.day, pred_slot.time, prev_id, player_id
+            ));
+        }
+        seen_slots.insert(slot_key, player_id.clone());
+
+        let slot_num = match pred_slot.day.as_str() {
+            "construction" => predetermined_time_to_slot(&pred_slot.time, construction_slots_vec),
+            "research" => predetermined_time_to_slot(&pred_slot.time, research_slots_vec),
+            "troops" => predetermined_time_to_slot(&pred_slot.time, troops_slots_vec),
+            _ => None,
+        };
+
+        match slot_num {
+            Some(slot) => resolved_slots.push(ResolvedSlot { day: pred_slot.day.clone(), slot, player_id, alliance, name }),
+            None => invalid_slots.push(format!("{} {} ({})", pred_slot.day, pred_slot.time, name)),
+        }
+    }
+
+    if !invalid_slots.is_empty() {
+        return Err(format!(
+            "Invalid or unrecognized time slot(s) for predetermined assignments: {}",
+            invalid_slots.join("; ")
+        ));
+    }
+
+    // Validation: At most one player can have research slot 1 predetermined (either explicitly or via construction last slot)
+    let research_slot1_from_resolved = resolved_slots.iter()
+        .filter(|r| r.day == "research" && r.slot == 1)
+        .count();
+    if research_slot1_from_resolved > 1 {
+        return Err("Only one player can have research slot 1 predetermined. Multiple players were configured for research slot 1.".to_string());
+    }
+
+    Ok(resolved_slots)
+}
+
+/// Added to a guaranteed player's priority score on their day, so they are placed before
+/// everyone else and may take the slots of lower scored players
+const GUARANTEED_SCORE_BOOST: u32 = 1 << 30;
+
+/// Scores are capped below the boost while guaranteed players are placed, since submissions
+/// and formulas can reach `u32::MAX` and would otherwise outrank them
+const MAX_REGULAR_SCORE: u32 = GUARANTEED_SCORE_BOOST - 1;
+
+/// Copies the entries with every day score capped at [`MAX_REGULAR_SCORE`] and the guaranteed
+/// players' scores boosted on their days
+fn prioritize_guaranteed(entries: &[AppointmentEntry], guaranteed: &GuaranteedPlayers) -> Vec<AppointmentEntry> {
+    entries.iter().cloned().map(|mut entry| {
+        let listed = |ids: &[String]| ids.contains(&entry.player_id);
+        let tier = |score: u32, listed: bool| score.min(MAX_REGULAR_SCORE) + if listed { GUARANTEED_SCORE_BOOST } else { 0 };
+        entry.construction_score = tier(entry.construction_score, listed(&guaranteed.construction));
+        entry.research_score = tier(entry.research_score, listed(&guaranteed.research));
+        entry.troops_speedups = tier(entry.troops_speedups, listed(&guaranteed.troops));
+        entry
+    }).collect()
+}
+
+/// Generates all three day schedules, locking the predetermined slots first and placing the
+/// guaranteed players before everyone else
+///
+/// # Arguments
+/// * `entries` - All loaded submissions, used to resolve predetermined and guaranteed players
+/// * `entries_to_use` - Submissions that still need a slot (all of them unless appending)
+/// * `predetermined` - Predetermined slot assignments
+/// * `guaranteed` - Players who must get some slot on a day, at any time
+/// * `time_slots` - Time slot mappings per day (the last construction slot is taken from these)
+/// * `existing` - Schedule being appended to, if any. Its filled slots are kept and locked.
+///
+/// Returns the new appointments only; merging them into `existing` is up to the caller.
+/// Errors describe the invalid or conflicting predetermined assignment, or the guaranteed
+/// player who couldn't be placed.
+pub fn generate_schedules(
+    entries: &[AppointmentEntry],
+    entries_to_use: &[AppointmentEntry],
+    predetermined: &[PredeterminedSlot],
+    guaranteed: &GuaranteedPlayers,
+    time_slots: DayTimeSlots,
+    existing: Option<ExistingDays>,
+) -> Result<(DaySchedule, DaySchedule, DaySchedule), String> {
+    let guaranteed = &guaranteed.trimmed();
+    if guaranteed.is_empty() {
+        return generate_with_predetermined(entries, entries_to_use, predetermined, time_slots, existing);
+    }
+
+    for day in ["construction", "research", "troops"] {
+        if let Some(player_id) = guaranteed.for_day(day).iter().find(|id| !entries.iter().any(|e| e.player_id == **id)) {
+            return Err(format!(
+                "Guaranteed {} slot for player ID {}: No submission found. They must have submitted the form.",
+                day, player_id
+            ));
+        }
+    }
+
+    let prioritized = prioritize_guaranteed(entries_to_use, guaranteed);
+    let (mut construction, mut research, mut troops) =
+        generate_with_predetermined(entries, &prioritized, predetermined, time_slots, existing)?;
+
+    // Show the submitted scores again (of the capped and boosted players), and check that every
+    // guaranteed player got a slot
+    let existing = existing.unwrap_or_default();
+    let days = [
+        ("construction", &mut construction, existing.construction, (|e| e.construction_score) as fn(&AppointmentEntry) -> u32),
+        ("research", &mut research, existing.research, |e| e.research_score),
+        ("troops", &mut troops, existing.troops, |e| e.troops_speedups),
+    ];
+    for (day, schedule, existing_day, score) in days {
+        for appt in schedule.appointments.values_mut().filter(|a| a.priority_score >= MAX_REGULAR_SCORE) {
+            if let Some(entry) = entries_to_use.iter().find(|e| e.player_id == appt.player_id) {
+                appt.priority_score = score(entry);
+            }
+        }
+        for player_id in guaranteed.for_day(day) {
+            let scheduled = |s: &DaySchedule| s.appointments.values().any(|a| a.player_id == *player_id);
+            if !scheduled(schedule) && !existing_day.is_some_and(scheduled) {
+                let name = entries.iter().find(|e| e.player_id == *player_id).map(|e| e.name.as_str()).unwrap_or_default();
+                return Err(format!(
+                    "Guaranteed {} slot for {} ({}): No slot left for them. They need {} times picked on the form that aren't all taken by predetermined slots or other guaranteed players.",
+                    day, name, player_id, day
+                ));
+            }
+        }
+    }
+
+    Ok((construction, research, troops))
+}
+
+/// [`generate_schedules`] without guaranteed players
+fn generate_with_predetermined<'a>(
+    entries: &[AppointmentEntry],
+    entries_to_use: &[AppointmentEntry],
+    predetermined: &[PredeterminedSlot],
+    time_slots: DayTimeSlots,
+    existing: Option<ExistingDays<'a>>,
+) -> Result<(DaySchedule, DaySchedule, DaySchedule), String> {
+    let existing = existing.unwrap_or_default();
+    let locked = |day: Option<&DaySchedule>| -> HashSet<u8> {
+        day.map(|s| s.appointments.keys().copied().collect()).unwrap_or_default()
+    };
+    let existing_construction_slots = locked(existing.construction);
+    let existing_research_slots = locked(existing.research);
+    let existing_troops_slots = locked(existing.troops);
+    // Players holding a slot of an existing day aren't placed on that day again (ID-based)
+    let held = |day: Option<&'a DaySchedule>| -> HashSet<&'a String> {
+        day.map(|s| s.appointments.values().map(|appt| &appt.player_id).collect()).unwrap_or_default()
+    };
+
+    if predetermined.is_empty() {
+        // No predetermined slots, generate normally but pass last_slot from the time config when available
+        let last_slot_override = time_slots.construction
+            .and_then(|slots| slots.iter().map(|(s, _)| *s).max());
+        let without_held = |day: Option<&'a DaySchedule>| -> Vec<AppointmentEntry> {
+            let held = held(day);
+            entries_to_use.iter().filter(|e| !held.contains(&e.player_id)).cloned().collect()
+        };
+        let construction_schedule = schedule_construction_day_with_locked(
+            &without_held(existing.construction),
+            &existing_construction_slots,
+            last_slot_override,
+        );
+        let research_schedule = schedule_research_day_with_locked(&without_held(existing.research), &construction_schedule, &existing_research_slots);
+        let troops_schedule = schedule_troops_day_with_locked(&without_held(existing.troops), &existing_troops_slots);
+        return Ok((construction_schedule, research_schedule, troops_schedule));
+    }
+
+    // Collect predetermined slot numbers FIRST so we can pass them as pre_locked_slots
+    let mut construction_predetermined_slots = HashSet::new();
+    let mut research_predetermined_slots = HashSet::new();
+    let mut troops_predetermined_slots = HashSet::new();
+
+    let resolved_slots = resolve_predetermined(entries, predetermined, time_slots)?;
+
+    // Use last slot from the time config (not from entries) for correct research handoff
+    let last_construction_slot = last_construction_slot(time_slots);
+
+    // Collect predetermined slot numbers for each day
+    // Also track: research_slot1_players (get construction last slot), construction_last_slot_players (get research slot 1)
+    let mut research_slot1_players: Vec<String> = Vec::new(); // player_ids
+    let mut construction_last_slot_players: Vec<String> = Vec::new(); // player_ids
+
+    for ResolvedSlot { day, slot, player_id, .. } in &resolved_slots {
+        match day.as_str() {
+            "construction" => {
+                construction_predetermined_slots.insert(*slot);
+                if *slot == last_construction_slot {
+                    construction_last_slot_players.push(player_id.clone());
+                }
+            },
+            "research" => {
+                research_predetermined_slots.insert(*slot);
+                if *slot == 1 {
+                    research_slot1_players.push(player_id.clone());
+                }
+            },
+            "troops" => {
+                troops_predetermined_slots.insert(*slot);
+            },
+            _ => {},
+        }
+    }
+
+    // Build effective research slot 1 players (for validation) - ID-based
+    let effective_research_slot1: HashSet<String> = research_slot1_players.iter()
+        .chain(construction_last_slot_players.iter())
+        .cloned()
+        .collect();
+    if effective_research_slot1.len() > 1 {
+        return Err("Conflict: Only one player can have the research slot 1 + construction last slot link. You have multiple players for research slot 1 and/or construction last slot.".to_string());
+    }
+
+    // When appending: validate that predetermined slots don't conflict with existing schedule (different player_id in same slot)
+    let check_conflict = |existing_appts: Option<&DaySchedule>, slot: u8, player_id: &str| {
+        existing_appts
+            .and_then(|s| s.appointments.get(&slot))
+            .is_some_and(|appt| appt.player_id != player_id)
+    };
+    for ResolvedSlot { day, slot, player_id, .. } in &resolved_slots {
+        let conflict = match day.as_str() {
+            "construction" => check_conflict(existing.construction, *slot, player_id),
+            "research" => check_conflict(existing.research, *slot, player_id),
+            "troops" => check_conflict(existing.troops, *slot, player_id),
+            _ => false,
+        };
+        if conflict {
+            return Err(format!(
+                "Append conflict: Predetermined slot {} {} for player {} is already filled by a different player in the existing schedule. Clear the slot manually or generate without append.",
+                day, slot, player_id
+            ));
+        }
+    }
+    // Also validate research slot 1 / construction last slot link (ID-based)
+    if let Some(pred_id) = effective_research_slot1.iter().next() {
+        if check_conflict(existing.research, 1, pred_id) {
+            return Err("Append conflict: Existing schedule has a different player in research slot 1. The research slot 1 + construction last slot link requires one player for both. Clear research slot 1 and construction last slot in the existing schedule first, or generate without append.".to_string());
+        }
+        if check_conflict(existing.construction, last_construction_slot, pred_id) {
+            return Err("Append conflict: Existing schedule has a different player in construction last slot. The research slot 1 + construction last slot link requires one player for both. Clear research slot 1 and construction last slot in the existing schedule first, or generate without append.".to_string());
+        }
+    }
+
+    // When appending, merge existing schedule slots with predetermined slots (both are locked)
+    construction_predetermined_slots.extend(&existing_construction_slots);
+    research_predetermined_slots.extend(&existing_research_slots);
+    troops_predetermined_slots.extend(&existing_troops_slots);
+
+    // Add research slot 1 to pre-locked slots for construction last slot players (they get it automatically)
+    if !construction_last_slot_players.is_empty() {
+        research_predetermined_slots.insert(1);
+    }
+
+    // Bidirectional link: research slot 1 <-> construction last slot
+    // If someone has research slot 1 predetermined, they must also have the last construction slot
+    for player_id in &research_slot1_players {
+        // Check if this player already has a construction predetermined slot
+        let already_has_construction = resolved_slots.iter().any(|r| {
+            r.day == "construction" && r.player_id == *player_id
+        });
+        if !already_has_construction {
+            construction_predetermined_slots.insert(last_construction_slot);
+        }
+    }
+
+    // Filter entries per day - only remove players from days where they have predetermined slots (ID-based)
+    let predetermined_ids = |day: &str| -> HashSet<&String> {
+        resolved_slots.iter()
+            .filter(|r| r.day == day)
+            .map(|r| &r.player_id)
+            .collect()
+    };
+    let mut construction_pred_player_ids = predetermined_ids("construction");
+    construction_pred_player_ids.extend(research_slot1_players.iter()); // research slot 1 also gets construction last
+    construction_pred_player_ids.extend(held(existing.construction));
+    let mut research_pred_player_ids = predetermined_ids("research");
+    research_pred_player_ids.extend(construction_last_slot_players.iter());
+    research_pred_player_ids.extend(held(existing.research));
+    let mut troops_pred_player_ids = predetermined_ids("troops");
+    troops_pred_player_ids.extend(held(existing.troops));
+
+    let filter_entries = |ids: &HashSet<&String>| -> Vec<AppointmentEntry> {
+        entries_to_use.iter()
+            .filter(|entry| !ids.contains(&entry.player_id))
+            .cloned()
+            .collect()
+    };
+    let construction_entries_filtered = filter_entries(&construction_pred_player_ids);
+    let research_entries_filtered = filter_entries(&research_pred_player_ids);
+    let troops_entries_filtered = filter_entries(&troops_pred_player_ids);
+
+    // Generate schedules with day-specific filtered entries, passing predetermined slots as pre_locked_slots
+    // This ensures predetermined slots are respected from the start, but players can still be scheduled on other days
+    let mut construction_schedule = schedule_construction_day_with_locked(
+        &construction_entries_filtered,
+        &construction_predetermined_slots,
+        Some(last_construction_slot),
+    );
+    let mut research_schedule = schedule_research_day_with_locked(&research_entries_filtered, &construction_schedule, &research_predetermined_slots);
+    let mut troops_schedule = schedule_troops_day_with_locked(&troops_entries_filtered, &troops_predetermined_slots);
+
+    // Apply predetermined slots to the schedules (insert the actual appointments)
+    for ResolvedSlot { day, slot, player_id, alliance, name } in &resolved_slots {
+        let appointment_at = |slot: u8| ScheduledAppointment {
+            player_id: player_id.clone(),
+            name: name.clone(),
+            alliance: alliance.clone(),
+            slot,
+            priority_score: 9999,
+            note: None,
+            manual: false,
+        };
+
+        match day.as_str() {
+            "construction" => {
+                construction_schedule.appointments.insert(*slot, appointment_at(*slot));
+                if *slot == last_construction_slot {
+                    let already_has_research = resolved_slots.iter().any(|r| r.day == "research" && r.player_id == *player_id);
+                    if !already_has_research {
+                        research_schedule.appointments.insert(1, appointment_at(1));
+                    }
+                }
+            },
+            "research" => {
+                research_schedule.appointments.insert(*slot, appointment_at(*slot));
+                if *slot == 1 {
+                    let already_has_construction = resolved_slots.iter().any(|r| r.day == "construction" && r.player_id == *player_id);
+                    if !already_has_construction {
+                        construction_schedule.appointments.retain(|_, appt| appt.player_id != *player_id);
+                        construction_schedule.appointments.insert(last_construction_slot, appointment_at(last_construction_slot));
+                    }
+                }
+            },
+            "troops" => {
+                troops_schedule.appointments.insert(*slot, appointment_at(*slot));
+            },
+            _ => {},
+        }
+    }
+
+    Ok((construction_schedule, research_schedule, troops_schedule))
+}
+
+/// Merges newly generated appointments into an existing day (keep existing, fill empty slots with new).
+/// The unassigned list comes from the new run.
+pub fn merge_appended(existing: Option<&DaySchedule>, new: DaySchedule) -> DaySchedule {
+    let mut merged = existing
+        .map(|e| e.appointments.clone())
+        .unwrap_or_default();
+    for (slot, appt) in new.appointments {
+        merged.entry(slot).or_insert(appt);
+    }
+    DaySchedule {
+        appointments: merged,
+        unassigned: new.unassigned,
+    }
+}
+
+/// Regenerates one day of a schedule from scratch and returns it; the other two days stay as
+/// they are. Their filled slots are held like an appended-to schedule's, and so is this day's
+/// end of the construction/research link (the last construction slot or research slot 1), so
+/// the player holding both keeps them.
+///
+/// # Arguments
+/// * `entries` - All loaded submissions
+/// * `predetermined`, `guaranteed`, `time_slots` - As for [`generate_schedules`]
+/// * `current` - The schedule the day is regenerated in
+/// * `day` - "construction", "research" or "troops"
+/// * `keep_manual` - Also hold the day's appointments placed by hand
+pub fn regenerate_day(
+    entries: &[AppointmentEntry],
+    predetermined: &[PredeterminedSlot],
+    guaranteed: &GuaranteedPlayers,
+    time_slots: DayTimeSlots,
+    current: ExistingDays,
+    day: &str,
+    keep_manual: bool,
+) -> Result<DaySchedule, String> {
+    let (current_day, link_slot) = match day {
+        "construction" => (current.construction, Some(last_construction_slot(time_slots))),
+        "research" => (current.research, Some(1)),
+        "troops" => (current.troops, None),
+        _ => return Err(format!("Unknown day \"{}\"", day)),
+    };
+    let kept = DaySchedule {
+        appointments: current_day.iter()
+            .flat_map(|s| s.appointments.values())
+            .filter(|appt| Some(appt.slot) == link_slot || (keep_manual && appt.manual))
+            .map(|appt| (appt.slot, appt.clone()))
+            .collect(),
+        unassigned: Vec::new(),
+    };
+    let existing = match day {
+        "construction" => ExistingDays { construction: Some(&kept), ..current },
+        "research" => ExistingDays { research: Some(&kept), ..current },
+        _ => ExistingDays { troops: Some(&kept), ..current },
+    };
+
+    let (construction, research, troops) = generate_schedules(entries, entries, predetermined, guaranteed, time_slots, Some(existing))?;
+    let new = match day {
+        "construction" => construction,
+        "research" => research,
+        _ => troops,
+    };
+    Ok(merge_appended(Some(&kept), new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A player who only wants the troops day, where the score is the speedups
+    fn troops_entry(player_id: &str, score: u32, slots: &[u8]) -> AppointmentEntry {
+        AppointmentEntry {
+            alliance: "ABC".to_string(),
+            name: format!("Player {}", player_id),
+            player_id: player_id.to_string(),
+            wants_construction: false,
+            wants_research: false,
+            wants_troops: true,
+            construction_speedups: 0,
+            research_speedups: 0,
+            troops_speedups: score,
+            construction_truegold: 0,
+            construction_score: 0,
+            research_truegold_dust: 0,
+            research_score: 0,
+            construction_available_slots: Vec::new(),
+            research_available_slots: Vec::new(),
+            troops_available_slots: slots.to_vec(),
+        }
+    }
+
+    fn guaranteed_troops(ids: &[&str]) -> GuaranteedPlayers {
+        GuaranteedPlayers {
+            troops: ids.iter().map(|id| id.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn troops_day(entries: &[AppointmentEntry], guaranteed: &GuaranteedPlayers) -> Result<DaySchedule, String> {
+        generate_schedules(entries, entries, &[], guaranteed, DayTimeSlots::default(), None).map(|(_, _, troops)| troops)
+    }
+
+    #[test]
+    fn guaranteed_player_wins_a_contested_slot() {
+        let entries = [troops_entry("1", 5000, &[5]), troops_entry("2", 10, &[5])];
+        let troops = troops_day(&entries, &guaranteed_troops(&["2"])).unwrap();
+        assert_eq!(troops.appointments[&5].player_id, "2");
+        assert!(troops.appointments.values().all(|a| a.player_id != "1"));
+    }
+
+    #[test]
+    fn submitted_scores_are_restored_after_placing() {
+        // u32::MAX is capped while placing, so it must come back unchanged as well
+        let entries = [troops_entry("1", 10, &[5]), troops_entry("2", u32::MAX, &[6])];
+        let troops = troops_day(&entries, &guaranteed_troops(&["1"])).unwrap();
+        assert_eq!(troops.appointments[&5].priority_score, 10);
+        assert_eq!(troops.appointments[&6].priority_score, u32::MAX);
+    }
+
+    #[test]
+    fn guaranteed_player_outranks_the_highest_regular_score() {
+        let entries = [troops_entry("1", u32::MAX, &[5]), troops_entry("2", 0, &[5])];
+        let troops = troops_day(&entries, &guaranteed_troops(&["2"])).unwrap();
+        assert_eq!(troops.appointments[&5].player_id, "2");
+    }
+
+    #[test]
+    fn guaranteed_player_without_available_times_is_an_error() {
+        let entries = [troops_entry("1", 5000, &[5]), troops_entry("2", 10, &[])];
+        let error = troops_day(&entries, &guaranteed_troops(&["2"])).unwrap_err();
+        assert!(error.contains("No slot left"), "{}", error);
+    }
+
+    #[test]
+    fn guaranteed_player_without_a_submission_is_an_error() {
+        let entries = [troops_entry("1", 5000, &[5])];
+        let error = troops_day(&entries, &guaranteed_troops(&["404"])).unwrap_err();
+        assert!(error.contains("No submission found"), "{}", error);
+    }
+}
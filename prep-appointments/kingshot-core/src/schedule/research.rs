@@ -39,6 +39,8 @@ pub fn schedule_research_day_with_locked(entries: &[AppointmentEntry], construct
                         alliance: entry.alliance.clone(),
                         slot: 1,
                         priority_score: entry.research_score,
+                        note: None,
+                        manual: false,
                     });
                     used_slots.insert(1);
                     locked_player_id = Some(entry.player_id.clone());
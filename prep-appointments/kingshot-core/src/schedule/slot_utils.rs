@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+
+use crate::parser::AppointmentEntry;
+use super::types::{DayTimeConfig, TimeWindow};
+
+/// Most slots a day can have; submissions and schedules number them 1-49
+pub const MAX_DAY_SLOTS: usize = 49;
+
+/// Converts slot number back to time string for display (legacy function for backward compatibility)
+pub fn slot_to_time(slot: u8) -> String {
+    match slot {
+        1 => "00:00".to_string(),
+        2 => "00:15".to_string(),
+        3 => "00:45".to_string(),
+        _ => {
+            // Slot 3 is at 00:45 (45 minutes)
+            // Slot 4 is at 01:15 (75 minutes)
+            // Pattern: total_minutes = 45 + (slot - 3) * 30
+            let total_minutes = 45 + (slot as u32 - 3) * 30;
+            let hours = total_minutes / 60;
+            let minutes = total_minutes % 60;
+            format!("{:02}:{:02}", hours, minutes)
+        }
+    }
+}
+
+/// Parses a time string (HH:MM) to minutes since midnight
+pub fn parse_time_to_minutes(time_str: &str) -> Option<u32> {
+    let parts: Vec<&str> = time_str.split(':').collect();
+    if parts.len() != 2 {
+        return None;
+    }
+    let hours: u32 = parts[0].parse().ok()?;
+    let minutes: u32 = parts[1].parse().ok()?;
+    if hours >= 24 || minutes >= 60 {
+        return None;
+    }
+    Some(hours * 60 + minutes)
+}
+
+/// Formats minutes since midnight to time string (HH:MM)
+pub fn minutes_to_time_string(minutes: u32) -> String {
+    let hours = minutes / 60;
+    let mins = minutes % 60;
+    format!("{:02}:{:02}", hours % 24, mins)
+}
+
+/// Calculates time slots based on start time, end time, and interval rules
+/// Slot 1 = start_time
+/// Slot 2 = start_time + 15 minutes
+/// Slot 3+ = previous slot + 30 minutes
+/// Continues until end_time (or start_time + 24 hours if end_time is None)
+pub fn calculate_time_slots(start_time: &str, end_time: Option<&str>) -> Vec<(u8, String)> {
+    let start_minutes = parse_time_to_minutes(start_time).unwrap_or(0);
+    let end_minutes = if let Some(end) = end_time {
+        parse_time_to_minutes(end).unwrap_or(start_minutes + 24 * 60)
+    } else {
+        start_minutes + 24 * 60
+    };
+    
+    let mut slots = Vec::new();
+    let mut current_minutes = start_minutes;
+    let mut slot_num = 1u8;
+    
+    // Slot 1 = start time
+    slots.push((slot_num, minutes_to_time_string(current_minutes)));
+    slot_num += 1;
+    
+    // Slot 2 = start + 15 minutes
+    current_minutes += 15;
+    if current_minutes < end_minutes || (end_minutes < start_minutes && current_minutes < end_minutes + 24 * 60) {
+        slots.push((slot_num, minutes_to_time_string(current_minutes % (24 * 60))));
+        slot_num += 1;
+        
+        // Slot 3+ = previous + 30 minutes
+        while slot_num <= 200 { // Safety limit
+            current_minutes += 30;
+            let check_minutes = if end_minutes < start_minutes {
+                // Handle wrap-around (e.g., 23:00 to 01:00)
+                if current_minutes >= 24 * 60 {
+                    current_minutes % (24 * 60)
+                } else {
+                    current_minutes
+                }
+            } else {
+                current_minutes
+            };
+            
+            // Check if we've reached the end time
+            if end_minutes < start_minutes {
+                // Wrap-around case
+                if check_minutes >= end_minutes && check_minutes < start_minutes {
+                    break;
+                }
+            } else {
+                // Normal case
+                if check_minutes >= end_minutes {
+                    break;
+                }
+            }
+            
+            slots.push((slot_num, minutes_to_time_string(check_minutes % (24 * 60))));
+            slot_num += 1;
+        }
+    }
+    
+    slots
+}
+
+// Minutes of an "HH:MM" window end for a window starting at `start` minutes, on the same
+// scale as `start`. "24:00" is midnight, and ends at or before the start fall on the next day.
+fn window_end_minutes(end: &str, start: u32) -> Option<u32> {
+    let end = if end == "24:00" { 24 * 60 } else { parse_time_to_minutes(end)? };
+    let mut end = start - start % (24 * 60) + end;
+    if end <= start {
+        end += 24 * 60;
+    }
+    Some(end)
+}
+
+/// Time slots of a day across all of its windows. The first window follows
+/// [`calculate_time_slots`]; each additional window has a slot at its start and then every
+/// 30 minutes until its end. Slot numbers continue across windows, so a day split into
+/// sessions has a gap in time but not in slot numbers. Days with more than [`MAX_DAY_SLOTS`]
+/// slots are rejected by [`validate_day_windows`].
+pub fn calculate_day_time_slots(config: &DayTimeConfig) -> Vec<(u8, String)> {
+    let mut slots = calculate_time_slots(&config.start_time, config.end_time.as_deref());
+    if let Ok(ranges) = day_window_ranges(config) {
+        for &(start, end) in ranges.iter().skip(1) {
+            let mut minutes = start;
+            while minutes < end {
+                slots.push(((slots.len() + 1) as u8, minutes_to_time_string(minutes % (24 * 60))));
+                minutes += 30;
+            }
+        }
+    }
+    slots
+}
+
+// Windows of a day as [start, end) minute ranges counted from midnight before the first
+// window's start, so later windows past midnight are above 24 * 60
+fn day_window_ranges(config: &DayTimeConfig) -> Result<Vec<(u32, u32)>, String> {
+    let start = parse_time_to_minutes(&config.start_time)
+        .ok_or_else(|| format!("\"{}\" is not a time, use HH:MM", config.start_time))?;
+    let end = match &config.end_time {
+        Some(end) => window_end_minutes(end, start).ok_or_else(|| format!("\"{}\" is not a time, use HH:MM", end))?,
+        None => start + 24 * 60,
+    };
+    let mut ranges = vec![(start, end)];
+    for window in &config.additional_windows {
+        let previous_end = ranges.last().map_or(end, |r| r.1);
+        let mut window_start = parse_time_to_minutes(&window.start_time)
+            .ok_or_else(|| format!("\"{}\" is not a time, use HH:MM", window.start_time))?;
+        // Same-day times before the previous end belong to the next day
+        while window_start < previous_end {
+            window_start += 24 * 60;
+        }
+        let window_end = window_end_minutes(&window.end_time, window_start)
+            .ok_or_else(|| format!("\"{}\" is not a time, use HH:MM", window.end_time))?;
+        ranges.push((window_start, window_end));
+    }
+    Ok(ranges)
+}
+
+// Whether a clock time (minutes since midnight) falls in [start, end) of a blackout; an end
+// at or before the start wraps past midnight
+fn in_blackout(minutes: u32, blackout: &TimeWindow) -> bool {
+    let (Some(start), Some(end)) = (parse_time_to_minutes(&blackout.start_time), window_end_minutes(&blackout.end_time, 0)) else {
+        return false;
+    };
+    let end = end % (24 * 60);
+    if start < end {
+        (start..end).contains(&minutes)
+    } else {
+        minutes >= start || minutes < end
+    }
+}
+
+/// Slot numbers of a day whose time falls in one of its blackouts. Blackouts keep the slot
+/// numbering of the day; the slots are only withheld from players and the schedulers.
+pub fn calculate_blackout_slots(config: &DayTimeConfig) -> Vec<u8> {
+    if config.blackouts.is_empty() {
+        return Vec::new();
+    }
+    calculate_day_time_slots(config).into_iter()
+        .filter(|(_, time)| parse_time_to_minutes(time).is_some_and(|minutes| config.blackouts.iter().any(|b| in_blackout(minutes, b))))
+        .map(|(slot, _)| slot)
+        .collect()
+}
+
+/// Removes each day's blackout slots from the players' available slots, so the schedulers
+/// leave them empty. Predetermined assignments are not affected.
+pub fn remove_blackout_slots(entries: &mut [AppointmentEntry], construction: &DayTimeConfig, research: &DayTimeConfig, troops: &DayTimeConfig) {
+    let (construction, research, troops) = (construction.blackout_slots(), research.blackout_slots(), troops.blackout_slots());
+    if construction.is_empty() && research.is_empty() && troops.is_empty() {
+        return;
+    }
+    for entry in entries {
+        entry.construction_available_slots.retain(|slot| !construction.contains(slot));
+        entry.research_available_slots.retain(|slot| !research.contains(slot));
+        entry.troops_available_slots.retain(|slot| !troops.contains(slot));
+    }
+}
+
+/// Checks a day's windows: valid times, additional windows only after an explicit end time,
+/// in order without overlapping, all within 24 hours of the start and at most [`MAX_DAY_SLOTS`] slots.
+/// Blackouts need valid times and must not be empty.
+pub fn validate_day_windows(config: &DayTimeConfig) -> Result<(), String> {
+    let ranges = day_window_ranges(config)?;
+    for blackout in &config.blackouts {
+        let start = parse_time_to_minutes(&blackout.start_time)
+            .ok_or_else(|| format!("\"{}\" is not a time, use HH:MM", blackout.start_time))?;
+        let end = window_end_minutes(&blackout.end_time, 0)
+            .ok_or_else(|| format!("\"{}\" is not a time, use HH:MM", blackout.end_time))?;
+        if start == end % (24 * 60) {
+            return Err(format!("The blackout {}-{} is empty, it must end after it starts", blackout.start_time, blackout.end_time));
+        }
+    }
+    if !config.additional_windows.is_empty() && config.end_time.is_none() {
+        return Err("Set an end time for the first window before adding more windows".to_string());
+    }
+    if ranges.last().is_some_and(|&(_, end)| end > ranges[0].0 + 24 * 60) {
+        return Err("Windows must be in order, must not overlap and must fit within 24 hours of the first start time".to_string());
+    }
+    let slot_count = calculate_time_slots(&config.start_time, config.end_time.as_deref()).len()
+        + ranges.iter().skip(1).map(|&(start, end)| (end - start).div_ceil(30) as usize).sum::<usize>();
+    if slot_count > MAX_DAY_SLOTS {
+        return Err(format!("The windows add up to {} time slots, the most a day can have is {}", slot_count, MAX_DAY_SLOTS));
+    }
+    Ok(())
+}
+
+/// Calculates slot rankings based on how many players requested each slot
+/// Returns a HashMap: slot -> request_count (higher count = higher rank/popularity)
+pub fn calculate_slot_rankings(available_slots_list: &[Vec<u8>]) -> HashMap<u8, u32> {
+    let mut rankings = HashMap::new();
+    for slots in available_slots_list {
+        for &slot in slots {
+            *rankings.entry(slot).or_insert(0) += 1;
+        }
+    }
+    rankings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(start: &str, end: Option<&str>, windows: &[(&str, &str)]) -> DayTimeConfig {
+        let mut config = DayTimeConfig::new(start, end);
+        config.additional_windows = windows.iter()
+            .map(|&(start_time, end_time)| TimeWindow { start_time: start_time.to_string(), end_time: end_time.to_string() })
+            .collect();
+        config
+    }
+
+    fn times(config: &DayTimeConfig) -> Vec<String> {
+        calculate_day_time_slots(config).into_iter().map(|(_, time)| time).collect()
+    }
+
+    #[test]
+    fn full_day_has_exactly_the_most_slots() {
+        let config = day("00:00", None, &[]);
+        assert_eq!(calculate_day_time_slots(&config).len(), MAX_DAY_SLOTS);
+        assert_eq!(validate_day_windows(&config), Ok(()));
+    }
+
+    #[test]
+    fn additional_windows_continue_the_slot_numbers() {
+        let config = day("10:00", Some("11:00"), &[("14:00", "15:00")]);
+        assert_eq!(calculate_day_time_slots(&config), vec![
+            (1, "10:00".to_string()),
+            (2, "10:15".to_string()),
+            (3, "10:45".to_string()),
+            (4, "14:00".to_string()),
+            (5, "14:30".to_string()),
+        ]);
+        assert_eq!(validate_day_windows(&config), Ok(()));
+    }
+
+    #[test]
+    fn window_crossing_midnight_runs_into_the_next_day() {
+        let config = day("20:00", Some("22:00"), &[("23:30", "01:00")]);
+        assert_eq!(day_window_ranges(&config), Ok(vec![(20 * 60, 22 * 60), (23 * 60 + 30, 25 * 60)]));
+        assert_eq!(times(&config)[5..], ["23:30", "00:00", "00:30"]);
+        assert_eq!(validate_day_windows(&config), Ok(()));
+    }
+
+    #[test]
+    fn window_ending_at_midnight_accepts_24_00() {
+        let config = day("18:00", Some("20:00"), &[("23:00", "24:00")]);
+        assert_eq!(times(&config)[5..], ["23:00", "23:30"]);
+        assert_eq!(validate_day_windows(&config), Ok(()));
+    }
+
+    #[test]
+    fn overlapping_windows_are_rejected() {
+        let config = day("10:00", Some("12:00"), &[("11:00", "13:00")]);
+        assert!(validate_day_windows(&config).unwrap_err().contains("must not overlap"));
+    }
+
+    #[test]
+    fn window_before_the_first_start_is_outside_the_day() {
+        // 09:00 comes after 12:00 only on the next day, and 10:30 then is past 24 hours from 10:00
+        let config = day("10:00", Some("12:00"), &[("09:00", "10:30")]);
+        assert!(validate_day_windows(&config).unwrap_err().contains("within 24 hours"));
+    }
+
+    #[test]
+    fn additional_windows_need_an_end_time() {
+        let config = day("10:00", None, &[("14:00", "15:00")]);
+        assert!(validate_day_windows(&config).unwrap_err().contains("end time"));
+    }
+
+    #[test]
+    fn invalid_times_are_rejected() {
+        assert!(validate_day_windows(&day("25:00", None, &[])).unwrap_err().contains("not a time"));
+        assert!(validate_day_windows(&day("10:00", Some("12:00"), &[("14:00", "1pm")])).unwrap_err().contains("not a time"));
+    }
+
+    #[test]
+    fn days_over_the_slot_cap_are_rejected_not_truncated() {
+        // 41 slots in the first window and 8 in the second make exactly the cap
+        let at_cap = day("00:00", Some("20:00"), &[("20:00", "24:00")]);
+        assert_eq!(calculate_day_time_slots(&at_cap).len(), MAX_DAY_SLOTS);
+        assert_eq!(validate_day_windows(&at_cap), Ok(()));
+
+        let over_cap = day("00:00", Some("19:50"), &[("19:50", "24:00")]);
+        assert_eq!(calculate_day_time_slots(&over_cap).len(), MAX_DAY_SLOTS + 1);
+        assert!(validate_day_windows(&over_cap).unwrap_err().contains("the most a day can have is 49"));
+    }
+
+    fn blackout(start: &str, end: &str) -> TimeWindow {
+        TimeWindow { start_time: start.to_string(), end_time: end.to_string() }
+    }
+
+    #[test]
+    fn blackout_includes_its_start_and_excludes_its_end() {
+        let lunch = blackout("12:00", "13:00");
+        assert!(!in_blackout(11 * 60 + 59, &lunch));
+        assert!(in_blackout(12 * 60, &lunch));
+        assert!(in_blackout(12 * 60 + 59, &lunch));
+        assert!(!in_blackout(13 * 60, &lunch));
+
+        // Slots at 12:00, 12:15, 12:45, 13:15, 13:45: the one at the end time stays open
+        let mut config = day("12:00", Some("14:00"), &[]);
+        config.blackouts = vec![blackout("12:00", "12:45")];
+        assert_eq!(calculate_blackout_slots(&config), vec![1, 2]);
+    }
+
+    #[test]
+    fn blackout_can_cover_a_whole_window() {
+        let mut config = day("10:00", Some("11:00"), &[("14:00", "15:00")]);
+        config.blackouts = vec![blackout("14:00", "15:00")];
+        assert_eq!(calculate_blackout_slots(&config), vec![4, 5]);
+    }
+
+    #[test]
+    fn blackout_spanning_midnight_wraps() {
+        let night = blackout("23:00", "01:00");
+        assert!(!in_blackout(22 * 60 + 59, &night));
+        assert!(in_blackout(23 * 60, &night));
+        assert!(in_blackout(0, &night));
+        assert!(in_blackout(59, &night));
+        assert!(!in_blackout(60, &night));
+
+        // Slots at 22:00, 22:15, 22:45, 23:15, 23:45, 00:15, 00:45, 01:15, 01:45
+        let mut config = day("22:00", Some("02:00"), &[]);
+        config.blackouts = vec![night];
+        assert_eq!(calculate_blackout_slots(&config), vec![4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn blackout_ending_at_midnight_accepts_24_00_and_00_00() {
+        for end in ["24:00", "00:00"] {
+            let evening = blackout("23:00", end);
+            assert!(in_blackout(23 * 60 + 30, &evening));
+            assert!(!in_blackout(0, &evening));
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::{HashMap, HashSet};
+use serde::{Serialize, Deserialize};
+use crate::parser::AppointmentEntry;
+
+/// Represents a scheduled appointment for a specific day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledAppointment {
+    pub player_id: String,
+    pub name: String,
+    pub alliance: String,
+    pub slot: u8,
+    pub priority_score: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>, // Admin note ("confirmed via DM"), never shown on public pages
+    /// Placed or moved by hand; regenerating keeps it unless told to discard manual edits
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub manual: bool,
+}
+
+/// Schedule for a single day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaySchedule {
+    pub appointments: HashMap<u8, ScheduledAppointment>, // slot -> appointment
+    pub unassigned: Vec<String>, // player IDs that couldn't be assigned
+}
+
+/// All three day schedules of an account/server, with the entries they were generated from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleData {
+    pub construction_schedule: Option<DaySchedule>,
+    pub research_schedule: Option<DaySchedule>,
+    pub troops_schedule: Option<DaySchedule>,
+    pub entries: Option<Vec<AppointmentEntry>>,
+    /// Player IDs that are assigned in the schedule (for ID-based append logic).
+    /// Populated when saving; derived from appointments when loading if missing (backward compat).
+    #[serde(default)]
+    pub scheduled_player_ids: Option<Vec<String>>,
+}
+
+/// Derives the set of scheduled player IDs from schedule appointments
+pub fn derive_scheduled_player_ids(data: &ScheduleData) -> HashSet<String> {
+    let mut ids = HashSet::new();
+    for appt in data.construction_schedule.as_ref().iter().flat_map(|s| s.appointments.values()) {
+        ids.insert(appt.player_id.clone());
+    }
+    for appt in data.research_schedule.as_ref().iter().flat_map(|s| s.appointments.values()) {
+        ids.insert(appt.player_id.clone());
+    }
+    for appt in data.troops_schedule.as_ref().iter().flat_map(|s| s.appointments.values()) {
+        ids.insert(appt.player_id.clone());
+    }
+    ids
+}
+
+/// Returns the set of scheduled player IDs, deriving from appointments if not stored
+pub fn get_scheduled_player_ids(data: &ScheduleData) -> HashSet<String> {
+    data.scheduled_player_ids.as_ref()
+        .map(|v| v.iter().cloned().collect())
+        .unwrap_or_else(|| derive_scheduled_player_ids(data))
+}
+
+/// Time window(s) for one day, as configured in the form settings. Days split into sessions
+/// list the later sessions in `additional_windows`; see [`super::slot_utils::calculate_day_time_slots`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DayTimeConfig {
+    pub start_time: String, // Format: "HH:MM" (e.g., "00:20")
+    pub end_time: Option<String>, // Format: "HH:MM", defaults to start_time + 24 hours if None
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional_windows: Vec<TimeWindow>, // Later sessions of the day, in order; needs end_time
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blackouts: Vec<TimeWindow>, // Reserved times inside the windows: not offered and not scheduled
+}
+
+/// A later session of a day, e.g. 14:00-24:00 after a 00:00-10:00 morning session, or a
+/// blackout interval such as 12:00-13:00
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeWindow {
+    pub start_time: String, // Format: "HH:MM"
+    pub end_time: String, // Format: "HH:MM", "24:00" or "00:00" for midnight
+}
+
+impl DayTimeConfig {
+    /// A single window from `start_time`, 24 hours long when `end_time` is None
+    pub fn new(start_time: &str, end_time: Option<&str>) -> Self {
+        DayTimeConfig {
+            start_time: start_time.to_string(),
+            end_time: end_time.map(str::to_string),
+            additional_windows: Vec::new(),
+            blackouts: Vec::new(),
+        }
+    }
+
+    /// Slot numbers and times of the day, across all windows
+    pub fn time_slots(&self) -> Vec<(u8, String)> {
+        super::slot_utils::calculate_day_time_slots(self)
+    }
+
+    /// Slot numbers whose time falls in one of the blackouts
+    pub fn blackout_slots(&self) -> Vec<u8> {
+        super::slot_utils::calculate_blackout_slots(self)
+    }
+}
+
+/// Predetermined slot assignment - locks a specific time slot to a player
+/// Primary identifier is player_id; alliance/name kept for display and backward compatibility
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PredeterminedSlot {
+    pub day: String, // "construction", "research", or "troops"
+    pub time: String, // Time string like "00:20"
+    /// Canonical player identifier - required for ID-based logic
+    #[serde(default)]
+    pub player_id: Option<String>,
+    #[serde(default)]
+    pub alliance: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+/// Players who must get a slot on a day without a fixed time: player IDs per day.
+/// The scheduler places them ahead of everyone else and fails if one can't be placed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuaranteedPlayers {
+    pub construction: Vec<String>,
+    pub research: Vec<String>,
+    pub troops: Vec<String>,
+}
+
+impl GuaranteedPlayers {
+    pub fn is_empty(&self) -> bool {
+        self.construction.is_empty() && self.research.is_empty() && self.troops.is_empty()
+    }
+
+    /// Player IDs guaranteed a slot on `day` ("construction", "research" or "troops")
+    pub fn for_day(&self, day: &str) -> &[String] {
+        match day {
+            "construction" => &self.construction,
+            "research" => &self.research,
+            "troops" => &self.troops,
+            _ => &[],
+        }
+    }
+
+    /// Removes the player from every day, returning the number of days they were listed on
+    pub fn remove(&mut self, player_id: &str) -> usize {
+        let mut removed = 0;
+        for ids in [&mut self.construction, &mut self.research, &mut self.troops] {
+            let before = ids.len();
+            ids.retain(|id| id != player_id);
+            removed += before - ids.len();
+        }
+        removed
+    }
+
+    /// Replaces player `from` with player `into` on every day, without listing `into` twice.
+    /// Returns whether anything changed.
+    pub fn rename(&mut self, from: &str, into: &str) -> bool {
+        let mut changed = false;
+        for ids in [&mut self.construction, &mut self.research, &mut self.troops] {
+            if !ids.iter().any(|id| id == from) {
+                continue;
+            }
+            let listed = ids.iter().any(|id| id == into);
+            ids.retain(|id| id != from || !listed);
+            for id in ids.iter_mut().filter(|id| *id == from) {
+                *id = into.to_string();
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    /// Trims the IDs, dropping empty and repeated ones
+    pub fn trimmed(&self) -> Self {
+        let trim = |ids: &[String]| {
+            let mut trimmed: Vec<String> = Vec::new();
+            for id in ids.iter().map(|id| id.trim()).filter(|id| !id.is_empty()) {
+                if !trimmed.iter().any(|t| t == id) {
+                    trimmed.push(id.to_string());
+                }
+            }
+            trimmed
+        };
+        GuaranteedPlayers {
+            construction: trim(&self.construction),
+            research: trim(&self.research),
+            troops: trim(&self.troops),
+        }
+    }
+}
+
+/// Represents a move in a chain of slot reassignments
+#[derive(Debug, Clone)]
+pub struct Move {
+    pub player_id: String,
+    pub from_slot: u8,
+    pub to_slot: u8,
+}
+
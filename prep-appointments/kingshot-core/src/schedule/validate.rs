@@ -0,0 +1,277 @@
+use std::collections::{HashMap, HashSet};
+use serde::Serialize;
+use crate::parser::AppointmentEntry;
+use super::predetermined::predetermined_time_to_slot;
+use super::{last_construction_slot, slot_to_time, DaySchedule, DayTimeSlots, GuaranteedPlayers, PredeterminedSlot, ResolvedSlot, ScheduleData};
+
+/// A broken schedule invariant, found by [`validate_schedule`]
+#[derive(Debug, Clone, Serialize)]
+pub struct Violation {
+    pub day: &'static str, // "construction", "research" or "troops"
+    pub slot: Option<u8>,
+    pub player_id: String,
+    pub message: String,
+}
+
+/// An assignment the schedule must contain: a predetermined slot or a slot of the schedule it was appended to
+struct LockedSlot<'a> {
+    day: &'static str,
+    slot: u8,
+    player_id: &'a str,
+    reason: &'static str,
+}
+
+type WantsDay = fn(&AppointmentEntry) -> Option<&Vec<u8>>;
+
+const DAYS: [(&str, WantsDay); 3] = [
+    ("construction", |e| e.wants_construction.then_some(&e.construction_available_slots)),
+    ("research", |e| e.wants_research.then_some(&e.research_available_slots)),
+    ("troops", |e| e.wants_troops.then_some(&e.troops_available_slots)),
+];
+
+fn day_schedule<'a>(data: &'a ScheduleData, day: &str) -> Option<&'a DaySchedule> {
+    match day {
+        "construction" => data.construction_schedule.as_ref(),
+        "research" => data.research_schedule.as_ref(),
+        _ => data.troops_schedule.as_ref(),
+    }
+}
+
+/// Checks a schedule against the submissions it was generated from:
+/// - every assigned player submitted the form, asked for that day and listed that slot
+/// - no player has two slots on one day, or a slot while also listed as unassigned
+/// - the construction last slot's player also has research slot 1 when they listed it
+/// - predetermined slots (with their research slot 1 / construction last slot link) and the slots of
+///   `existing`, the schedule this one was appended to, hold the same player
+///
+/// Predetermined and appended assignments don't need to be in the player's available times.
+pub fn validate_schedule(
+    data: &ScheduleData,
+    entries: &[AppointmentEntry],
+    predetermined: &[ResolvedSlot],
+    existing: Option<&ScheduleData>,
+    time_slots: DayTimeSlots,
+) -> Vec<Violation> {
+    let entries_by_id: HashMap<&str, &AppointmentEntry> = entries.iter().map(|e| (e.player_id.as_str(), e)).collect();
+
+    // Same last slot as the scheduler: from the time config, or with predetermined slots 49,
+    // otherwise the latest construction time anyone listed
+    let last_slot = if time_slots.construction.is_some() || !predetermined.is_empty() {
+        last_construction_slot(time_slots)
+    } else {
+        entries.iter()
+            .filter(|e| e.wants_construction)
+            .flat_map(|e| &e.construction_available_slots)
+            .max()
+            .copied()
+            .unwrap_or(49)
+    };
+
+    let mut locked: Vec<LockedSlot> = Vec::new();
+    for resolved in predetermined {
+        let day = match resolved.day.as_str() {
+            "construction" => "construction",
+            "research" => "research",
+            _ => "troops",
+        };
+        locked.push(LockedSlot { day, slot: resolved.slot, player_id: &resolved.player_id, reason: "predetermined slot" });
+
+        // The linked slot comes along unless the player has their own predetermined slot on that day
+        let has_own = |other_day: &str| predetermined.iter().any(|r| r.day == other_day && r.player_id == resolved.player_id);
+        if day == "construction" && resolved.slot == last_slot && !has_own("research") {
+            locked.push(LockedSlot { day: "research", slot: 1, player_id: &resolved.player_id, reason: "predetermined slot" });
+        }
+        if day == "research" && resolved.slot == 1 && !has_own("construction") {
+            locked.push(LockedSlot { day: "construction", slot: last_slot, player_id: &resolved.player_id, reason: "predetermined slot" });
+        }
+    }
+    if let Some(existing) = existing {
+        for (day, _) in DAYS {
+            for appt in day_schedule(existing, day).iter().flat_map(|s| s.appointments.values()) {
+                locked.push(LockedSlot { day, slot: appt.slot, player_id: &appt.player_id, reason: "existing schedule slot" });
+            }
+        }
+    }
+    let is_locked = |day: &str, slot: u8, player_id: &str| {
+        locked.iter().any(|l| l.day == day && l.slot == slot && l.player_id == player_id)
+    };
+
+    let mut violations = Vec::new();
+    for (day, available_slots) in DAYS {
+        let Some(schedule) = day_schedule(data, day) else { continue };
+        let mut violation = |slot: Option<u8>, player_id: &str, message: String| {
+            violations.push(Violation { day, slot, player_id: player_id.to_string(), message });
+        };
+
+        let mut slots: Vec<&u8> = schedule.appointments.keys().collect();
+        slots.sort();
+        let mut seen: HashMap<&str, u8> = HashMap::new();
+        for &slot in slots {
+            let appt = &schedule.appointments[&slot];
+            if appt.slot != slot {
+                violation(Some(slot), &appt.player_id, format!("stored under slot {} but says slot {}", slot, appt.slot));
+            }
+            if !(1..=49).contains(&slot) {
+                violation(Some(slot), &appt.player_id, format!("slot {} is outside 1-49", slot));
+            }
+            if let Some(first) = seen.insert(&appt.player_id, slot) {
+                violation(Some(slot), &appt.player_id, format!("also assigned {}", slot_to_time(first)));
+            }
+            if is_locked(day, slot, &appt.player_id) {
+                continue;
+            }
+            match entries_by_id.get(appt.player_id.as_str()) {
+                None => violation(Some(slot), &appt.player_id, "not in the submissions".to_string()),
+                Some(entry) => match available_slots(entry) {
+                    None => violation(Some(slot), &appt.player_id, format!("didn't ask for {} day", day)),
+                    Some(available) if !available.contains(&slot) => {
+                        violation(Some(slot), &appt.player_id, format!("didn't list {}", slot_to_time(slot)))
+                    }
+                    Some(_) => {}
+                },
+            }
+        }
+
+        let unassigned: HashSet<&str> = schedule.unassigned.iter().map(String::as_str).collect();
+        for (player_id, slot) in &seen {
+            if unassigned.contains(player_id) {
+                violation(Some(*slot), player_id, "also listed as unassigned".to_string());
+            }
+        }
+
+        for lock in locked.iter().filter(|l| l.day == day) {
+            let holder = schedule.appointments.get(&lock.slot).map(|a| a.player_id.as_str());
+            if holder != Some(lock.player_id) {
+                violation(Some(lock.slot), lock.player_id, format!(
+                    "{} {} {}",
+                    lock.reason,
+                    slot_to_time(lock.slot),
+                    holder.map(|id| format!("now has player {}", id)).unwrap_or_else(|| "is empty".to_string()),
+                ));
+            }
+        }
+    }
+
+    // The construction last slot's player keeps research slot 1 if they listed it
+    let construction_last = data.construction_schedule.as_ref().and_then(|s| s.appointments.get(&last_slot));
+    if let (Some(appt), Some(research)) = (construction_last, data.research_schedule.as_ref()) {
+        let wants_slot_1 = entries_by_id.get(appt.player_id.as_str())
+            .is_some_and(|e| e.wants_research && e.research_available_slots.contains(&1));
+        let research_slot_1 = research.appointments.get(&1).map(|a| a.player_id.as_str());
+        if wants_slot_1 && research_slot_1 != Some(appt.player_id.as_str()) {
+            violations.push(Violation {
+                day: "research",
+                slot: Some(1),
+                player_id: appt.player_id.clone(),
+                message: format!(
+                    "has construction last slot {} and listed research {}, but research slot 1 is {}",
+                    slot_to_time(last_slot),
+                    slot_to_time(1),
+                    research_slot_1.map(|id| format!("player {}", id)).unwrap_or_else(|| "empty".to_string()),
+                ),
+            });
+        }
+    }
+
+    violations.sort_by_key(|v| (DAYS.iter().position(|(d, _)| *d == v.day), v.slot));
+    violations
+}
+
+/// Checks predetermined slots and guaranteed players against the submissions without generating
+/// anything, reporting every problem instead of stopping at the first like [`generate_schedules`]:
+/// - players without a submission, and predetermined slots with an unknown day or time
+/// - two players predetermined for one slot, or one player for two slots of a day
+/// - more than one player for research slot 1 and its linked construction last slot
+/// - guaranteed players who didn't pick any times on their day
+///
+/// [`generate_schedules`]: super::generate_schedules
+pub fn check_predetermined(
+    entries: &[AppointmentEntry],
+    predetermined: &[PredeterminedSlot],
+    guaranteed: &GuaranteedPlayers,
+    time_slots: DayTimeSlots,
+) -> Vec<Violation> {
+    let entries_by_id: HashMap<&str, &AppointmentEntry> = entries.iter().map(|e| (e.player_id.as_str(), e)).collect();
+    let last_slot = last_construction_slot(time_slots);
+
+    let mut violations = Vec::new();
+    let mut taken: HashMap<(&str, u8), String> = HashMap::new();
+    let mut player_days: HashMap<(&str, String), u8> = HashMap::new();
+    let mut research_slot_1: Vec<String> = Vec::new(); // Players predetermined for research slot 1 or the construction last slot
+    for pred in predetermined {
+        let Some(day) = DAYS.iter().map(|(d, _)| *d).find(|d| *d == pred.day) else {
+            violations.push(Violation { day: "", slot: None, player_id: pred.player_id.clone().unwrap_or_default(), message: format!("unknown day \"{}\"", pred.day) });
+            continue;
+        };
+        let day_slots = match day {
+            "construction" => time_slots.construction,
+            "research" => time_slots.research,
+            _ => time_slots.troops,
+        };
+        let slot = predetermined_time_to_slot(&pred.time, day_slots.unwrap_or_default());
+        let time_of = |slot: u8| {
+            day_slots.unwrap_or_default().iter()
+                .find(|(s, _)| *s == slot)
+                .map(|(_, time)| time.clone())
+                .unwrap_or_else(|| slot_to_time(slot))
+        };
+
+        let player_id = match pred.player_id.as_deref().map(str::trim).filter(|id| !id.is_empty()) {
+            Some(id) => {
+                if !entries_by_id.contains_key(id) {
+                    violations.push(Violation { day, slot, player_id: id.to_string(), message: "predetermined player has no submission".to_string() });
+                }
+                id.to_string()
+            }
+            None => match entries.iter().find(|e| {
+                e.alliance.trim().eq_ignore_ascii_case(pred.alliance.trim()) && e.name.trim().eq_ignore_ascii_case(pred.name.trim())
+            }) {
+                Some(entry) => entry.player_id.clone(),
+                None => {
+                    violations.push(Violation { day, slot, player_id: String::new(), message: format!("no player ID, and {} {} has no submission", pred.alliance, pred.name) });
+                    continue;
+                }
+            },
+        };
+
+        let Some(slot) = slot else {
+            violations.push(Violation { day, slot: None, player_id, message: format!("unrecognized time \"{}\"", pred.time) });
+            continue;
+        };
+        let slot_taken = taken.get(&(day, slot)).filter(|other| **other != player_id);
+        if let Some(other) = slot_taken {
+            violations.push(Violation { day, slot: Some(slot), player_id: player_id.clone(), message: format!("also predetermined for player {}", other) });
+        }
+        let slot_taken = slot_taken.is_some();
+        taken.insert((day, slot), player_id.clone());
+        if let Some(other_slot) = player_days.insert((day, player_id.clone()), slot).filter(|s| *s != slot) {
+            violations.push(Violation { day, slot: Some(slot), player_id: player_id.clone(), message: format!("also predetermined for {}", time_of(other_slot)) });
+        }
+        if (day == "research" && slot == 1) || (day == "construction" && slot == last_slot) {
+            // Two players in the same one of the two slots are already reported above
+            if let Some(other) = research_slot_1.iter().find(|other| **other != player_id).filter(|_| !slot_taken) {
+                violations.push(Violation {
+                    day,
+                    slot: Some(slot),
+                    player_id: player_id.clone(),
+                    message: format!("research slot 1 and the construction last slot go to one player, but player {} is predetermined for them too", other),
+                });
+            }
+            research_slot_1.push(player_id);
+        }
+    }
+
+    for (day, available_slots) in DAYS {
+        for player_id in guaranteed.for_day(day).iter().map(|id| id.trim()).filter(|id| !id.is_empty()) {
+            let message = match entries_by_id.get(player_id) {
+                None => "guaranteed player has no submission",
+                Some(entry) if available_slots(entry).is_none_or(|slots| slots.is_empty()) => "guaranteed player didn't pick any times that day",
+                Some(_) => continue,
+            };
+            violations.push(Violation { day, slot: None, player_id: player_id.to_string(), message: message.to_string() });
+        }
+    }
+
+    violations.sort_by_key(|v| (DAYS.iter().position(|(d, _)| *d == v.day), v.slot));
+    violations
+}
@@ -0,0 +1,172 @@
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::error::{CliError, ErrorKind};
+use kingshot_core::parser::{apply_score_weights, AppointmentEntry, ScoreWeights};
+use kingshot_core::schedule::{generate_schedules, take_chain_stats, ChainStats, DaySchedule, DayTimeSlots, GuaranteedPlayers};
+
+const SLOT_COUNT: u8 = 49;
+
+/// How submitted resources (and so priority scores) are spread over the players
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ScoreDistribution {
+    /// Anything from nothing to the maximum, equally likely
+    Uniform,
+    /// Most players submit little, a few submit a lot (like a real server)
+    Exponential,
+    /// Everyone submits the same, so every slot conflict is a tie
+    Equal,
+}
+
+/// Benchmark settings, see `BenchArgs` for the command-line flags
+#[derive(Debug, Clone, Copy)]
+pub struct BenchSettings {
+    pub players: usize,
+    pub coverage: f64,
+    pub scores: ScoreDistribution,
+    pub runs: usize,
+    pub seed: u64,
+}
+
+/// Draws one resource amount between 0 and `max`
+fn resource(rng: &mut StdRng, max: u32, scores: ScoreDistribution) -> u32 {
+    match scores {
+        ScoreDistribution::Uniform => rng.gen_range(0..=max),
+        // Mean max/5, capped at max
+        ScoreDistribution::Exponential => (-(1.0 - rng.gen::<f64>()).ln() * max as f64 / 5.0).min(max as f64) as u32,
+        ScoreDistribution::Equal => max / 2,
+    }
+}
+
+/// Available slots for one day: one or two blocks of consecutive times (people are free for an
+/// evening, not at random quarter hours) covering about `coverage` of the day
+fn available_slots(rng: &mut StdRng, coverage: f64) -> Vec<u8> {
+    let target = ((SLOT_COUNT as f64 * coverage * rng.gen_range(0.5..1.5)).round() as u8).clamp(1, SLOT_COUNT);
+    let blocks = if target > 4 && rng.gen_bool(0.3) { 2 } else { 1 };
+    let mut slots = Vec::new();
+    for block in 0..blocks {
+        let len = if blocks == 2 && block == 0 { target / 2 } else { target - slots.len() as u8 };
+        let start = rng.gen_range(1..=SLOT_COUNT - len + 1);
+        slots.extend(start..start + len);
+    }
+    slots.sort();
+    slots.dedup();
+    slots
+}
+
+/// Generates `settings.players` random submissions, already merged (one entry per player)
+pub fn generate_entries(settings: &BenchSettings, rng: &mut StdRng) -> Vec<AppointmentEntry> {
+    const ALLIANCES: [&str; 5] = ["AAA", "BBB", "CCC", "DDD", ""];
+    let scores = settings.scores;
+
+    let mut entries: Vec<AppointmentEntry> = (0..settings.players).map(|i| {
+        let wants_construction = rng.gen_bool(0.7);
+        let wants_research = rng.gen_bool(0.7);
+        let wants_troops = rng.gen_bool(0.5);
+        AppointmentEntry {
+            alliance: ALLIANCES[rng.gen_range(0..ALLIANCES.len())].to_string(),
+            name: format!("Player {}", i + 1),
+            player_id: (10_000_001 + i).to_string(),
+            wants_construction,
+            wants_research,
+            wants_troops,
+            construction_speedups: if wants_construction { resource(rng, 2000, scores) } else { 0 },
+            research_speedups: if wants_research { resource(rng, 2000, scores) } else { 0 },
+            troops_speedups: if wants_troops { resource(rng, 3000, scores) } else { 0 },
+            construction_truegold: if wants_construction { resource(rng, 5000, scores) } else { 0 },
+            construction_score: 0,
+            research_truegold_dust: if wants_research { resource(rng, 8000, scores) } else { 0 },
+            research_score: 0,
+            construction_available_slots: if wants_construction { available_slots(rng, settings.coverage) } else { Vec::new() },
+            research_available_slots: if wants_research { available_slots(rng, settings.coverage) } else { Vec::new() },
+            troops_available_slots: if wants_troops { available_slots(rng, settings.coverage) } else { Vec::new() },
+        }
+    }).collect();
+    apply_score_weights(&mut entries, &ScoreWeights::default());
+    entries
+}
+
+/// Totals for one day over all runs
+#[derive(Debug, Clone, Copy, Default)]
+struct DayTotals {
+    requests: usize,
+    assigned: usize,
+}
+
+/// Generates fresh submissions for every run, schedules them and prints runtime, fill rate
+/// and slot stealing statistics
+pub fn run_bench(settings: BenchSettings) -> Result<(), Box<dyn std::error::Error>> {
+    if settings.runs == 0 {
+        return Err(CliError::new(ErrorKind::Usage, "--runs must be at least 1").into());
+    }
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+
+    type Wants = fn(&AppointmentEntry) -> bool;
+    let days: [(&str, Wants); 3] = [
+        ("Construction Day", |e| e.wants_construction),
+        ("Research Day", |e| e.wants_research),
+        ("Troops Training Day", |e| e.wants_troops),
+    ];
+    let mut totals = [DayTotals::default(); 3];
+    let mut runtimes: Vec<Duration> = Vec::new();
+    let mut chains = ChainStats::default();
+
+    take_chain_stats();
+    for _ in 0..settings.runs {
+        let entries = generate_entries(&settings, &mut rng);
+
+        let started = Instant::now();
+        let (construction, research, troops) = generate_schedules(&entries, &entries, &[], &GuaranteedPlayers::default(), DayTimeSlots::default(), None)?;
+        runtimes.push(started.elapsed());
+        chains.merge(&take_chain_stats());
+
+        let schedules: [&DaySchedule; 3] = [&construction, &research, &troops];
+        for ((totals, (_, wants)), schedule) in totals.iter_mut().zip(days).zip(schedules) {
+            totals.requests += entries.iter().filter(|e| wants(e)).count();
+            totals.assigned += schedule.appointments.len();
+        }
+    }
+
+    let runs = settings.runs as f64;
+    let mut stdout = std::io::stdout().lock();
+    writeln!(
+        stdout,
+        "Benchmark: {} run(s) of {} players, {:.0}% slot coverage, {:?} scores, seed {}",
+        settings.runs, settings.players, settings.coverage * 100.0, settings.scores, settings.seed
+    )?;
+
+    let ms = |d: &Duration| d.as_secs_f64() * 1000.0;
+    let total: Duration = runtimes.iter().sum();
+    writeln!(
+        stdout,
+        "\nScheduling time: mean {:.2} ms, min {:.2} ms, max {:.2} ms",
+        ms(&total) / runs,
+        runtimes.iter().map(ms).fold(f64::INFINITY, f64::min),
+        runtimes.iter().map(ms).fold(0.0, f64::max),
+    )?;
+
+    writeln!(stdout, "\n{:<20} {:>9} {:>9} {:>7} {:>7}", "Day (mean per run)", "Requests", "Assigned", "Fill", "Served")?;
+    for ((name, _), totals) in days.iter().zip(totals) {
+        let fill = totals.assigned as f64 / (runs * SLOT_COUNT as f64) * 100.0;
+        let served = if totals.requests == 0 { 0.0 } else { totals.assigned as f64 / totals.requests as f64 * 100.0 };
+        writeln!(
+            stdout,
+            "{:<20} {:>9.1} {:>9.1} {:>6.1}% {:>6.1}%",
+            name, totals.requests as f64 / runs, totals.assigned as f64 / runs, fill, served
+        )?;
+    }
+    writeln!(stdout, "Fill = share of the {} slots used, Served = share of requests that got a slot", SLOT_COUNT)?;
+
+    writeln!(
+        stdout,
+        "\nSlot stealing: {} chain(s) applied out of {} search(es) ({:.1} per run)",
+        chains.applied, chains.searches, chains.applied as f64 / runs
+    )?;
+    for (length, count) in &chains.lengths {
+        writeln!(stdout, "  {} move(s): {}", length, count)?;
+    }
+    Ok(())
+}
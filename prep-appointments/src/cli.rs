@@ -0,0 +1,1230 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use chrono::NaiveDate;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
+
+use crate::bench::{run_bench, BenchSettings, ScoreDistribution};
+use crate::config::{load_power_file, load_predetermined_file, load_roster_file, load_run_config, RunConfig};
+use crate::error::{with_context, CliError, ErrorFormat, ErrorKind};
+use crate::display::{format_player_name, OutputFormat, write_day_diff, write_day_schedule, write_schedule_csv, write_schedule_discord, write_schedule_ics, write_schedule_text, write_schedule_to_file, DISCORD_MESSAGE_LIMIT};
+use kingshot_core::anonymize::anonymize_submissions;
+use kingshot_core::parser::{apply_score_weights, load_appointments, read_appointments, AppointmentEntry};
+use kingshot_core::power::{apply_power_scores, match_power_records};
+use kingshot_core::roster::{apply_roster, discord_mentions};
+use kingshot_core::schedule::{derive_scheduled_player_ids, diff_days, diff_schedules, generate_schedules, get_scheduled_player_ids, import_day_text, merge_appended, remove_blackout_slots, resolve_predetermined, slot_to_time, text_schedule_day, validate_schedule};
+use kingshot_core::schedule::{DaySchedule, DayTimeConfig, DayTimeSlots, ExistingDays, ScheduleData};
+use crate::web::{self, FormConfig, FormData, StatsCell};
+
+/// SvS preparation week appointment scheduler
+#[derive(Debug, Parser)]
+#[command(name = "prep-appointments", version, about, long_about = None)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+
+    /// How errors are printed on stderr; `json` prints one object with the kind, exit code and message
+    #[arg(long, global = true, value_enum, default_value_t = ErrorFormat::Text)]
+    pub error_format: ErrorFormat,
+
+    /// Print debug messages (-vv for trace); schedules, tables and reports on stdout are unaffected
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Only print warnings and errors on stderr, not progress messages
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Parse a submissions CSV and list the merged entries
+    Parse(ParseArgs),
+    /// Generate schedules for all three days, print them and write them to files
+    Schedule(ScheduleArgs),
+    /// Show alliance request counts and time slot popularity for a submissions CSV
+    Stats(StatsArgs),
+    /// Generate schedules and write the schedule files without printing them
+    Export(ExportArgs),
+    /// Check a schedule JSON against its submissions (available times, duplicates, slot links, locked slots)
+    Validate(ValidateArgs),
+    /// Show who moved, was added or was dropped between two schedule JSONs
+    Diff(DiffArgs),
+    /// Copy a submissions CSV with names and IDs replaced by pseudonyms, for sharing problem files
+    Anonymize(AnonymizeArgs),
+    /// Schedule random submissions and report runtime, fill rate and slot stealing statistics
+    Bench(BenchArgs),
+    /// Read a submissions CSV on stdin and write the schedule JSON to stdout, printing nothing else
+    Pipe(PipeArgs),
+    /// Edit a schedule JSON in an interactive terminal UI
+    Edit(EditArgs),
+    /// Read hand-edited text schedules (schedule_*.txt) back into a schedule JSON
+    Import(ImportArgs),
+    /// Start the web server
+    #[command(alias = "web")]
+    Serve(ServeArgs),
+    /// Print a shell completion script (bash, zsh, fish, powershell or elvish)
+    Completions(CompletionsArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct InputArgs {
+    /// Submission CSVs, merged in order (a later file wins for the same player ID). A directory
+    /// adds all its .csv files by name, - reads stdin. Defaults to `inputs` from the config file.
+    #[arg(value_name = "CSV")]
+    pub inputs: Vec<PathBuf>,
+
+    /// TOML run configuration (inputs, score weights, time windows, predetermined slots, outputs)
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Power levels (alliance,name,power CSV) added to construction and research priority
+    /// scores with the `power_per_million` weight. Defaults to `power` from the config file.
+    #[arg(long, value_name = "FILE")]
+    pub power: Option<PathBuf>,
+
+    /// Earlier power levels; the gain since then is weighted with `power_gain_per_million`
+    #[arg(long, value_name = "FILE")]
+    pub power_baseline: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct AppendArgs {
+    /// Keep the assignments of an existing schedule and only place players who aren't in it yet
+    #[arg(long, requires = "existing")]
+    pub append: bool,
+
+    /// Schedule JSON to append to (written by `--format json`, or a file from the server's schedules/ directory)
+    #[arg(long, value_name = "FILE", requires = "append")]
+    pub existing: Option<PathBuf>,
+}
+
+impl AppendArgs {
+    fn load(&self) -> Result<Option<ScheduleData>, Box<dyn std::error::Error>> {
+        let Some(path) = self.existing.as_ref().filter(|_| self.append) else {
+            return Ok(None);
+        };
+        Ok(Some(load_schedule(path, "existing schedule")?))
+    }
+}
+
+/// A form JSON as stored by the server, or only its config
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum FormConfigFile {
+    Form(FormData),
+    Config(FormConfig),
+}
+
+/// Reads the form config from `--form-config`
+fn load_form_config(path: &Path) -> Result<FormConfig, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| with_context(e, format_args!("failed to read form config {}", path.display())))?;
+    let file = serde_json::from_str(&content)
+        .map_err(|e| with_context(e, format_args!("failed to parse form config {}", path.display())))?;
+    Ok(match file {
+        FormConfigFile::Form(form) => form.config,
+        FormConfigFile::Config(config) => config,
+    })
+}
+
+/// Reads a schedule JSON; `what` names it in error messages
+fn load_schedule(path: &Path, what: &str) -> Result<ScheduleData, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| with_context(e, format_args!("failed to read {} {}", what, path.display())))?;
+    let data = serde_json::from_str(&content)
+        .map_err(|e| with_context(e, format_args!("failed to parse {} {}", what, path.display())))?;
+    Ok(data)
+}
+
+#[derive(Debug, Args)]
+pub struct PredeterminedArgs {
+    /// File of predetermined assignments locked before scheduling: CSV with day,time,player_id
+    /// columns, or a JSON array. Added to `predetermined_slots` from the config file.
+    #[arg(long, value_name = "FILE")]
+    pub predetermined: Option<PathBuf>,
+}
+
+/// Dates of the three days, for `--format ics` (slot times are UTC on that date)
+#[derive(Debug, Args)]
+pub struct CalendarArgs {
+    /// Date of construction day, e.g. 2026-10-19
+    #[arg(long, value_name = "DATE")]
+    pub construction_date: Option<NaiveDate>,
+
+    /// Date of research day
+    #[arg(long, value_name = "DATE")]
+    pub research_date: Option<NaiveDate>,
+
+    /// Date of troops training day
+    #[arg(long, value_name = "DATE")]
+    pub troops_date: Option<NaiveDate>,
+}
+
+impl CalendarArgs {
+    fn date(&self, day: &str) -> Option<NaiveDate> {
+        match day {
+            "construction" => self.construction_date,
+            "research" => self.research_date,
+            _ => self.troops_date,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ParseArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// Print the parsed entries as JSON instead of a table
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ScheduleArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    #[command(flatten)]
+    pub append: AppendArgs,
+
+    #[command(flatten)]
+    pub predetermined: PredeterminedArgs,
+
+    /// Directory the schedule files are written to [default: config output_dir or .]
+    #[arg(short, long, value_name = "DIR")]
+    pub output_dir: Option<PathBuf>,
+
+    /// Output formats, comma separated [default: config formats or text]
+    #[arg(short, long = "format", value_name = "FORMAT", value_enum, value_delimiter = ',')]
+    pub formats: Vec<OutputFormat>,
+
+    #[command(flatten)]
+    pub calendar: CalendarArgs,
+
+    /// Only print the schedules, don't write any files
+    #[arg(long)]
+    pub no_files: bool,
+
+    /// Keep running and regenerate whenever an input CSV, the config or the predetermined file
+    /// changes, printing who moved
+    #[arg(long)]
+    pub watch: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct StatsArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// Form JSON from the server's current_forms/ directory (or just its `config`), for the
+    /// form's time windows; the statistics then match the web statistics page exactly
+    #[arg(long, value_name = "FILE")]
+    pub form_config: Option<PathBuf>,
+
+    /// Number of most requested time slots to list per day
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    pub top: usize,
+
+    /// Print the statistics as JSON, in the shape of the web statistics API
+    #[arg(long, conflicts_with = "export")]
+    pub json: bool,
+
+    /// Write the statistics tables to a file: .xlsx holds every table, .csv the one picked with --table
+    #[arg(long, value_name = "FILE")]
+    pub export: Option<PathBuf>,
+
+    /// Table for a CSV export
+    #[arg(long, value_parser = ["alliances", "slots", "scores"], default_value = "alliances", requires = "export")]
+    pub table: String,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    /// Write the files of an existing schedule JSON instead of generating one from submissions
+    #[arg(long = "in", value_name = "SCHEDULE_JSON", conflicts_with_all = ["inputs", "append", "predetermined"])]
+    pub schedule: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub append: AppendArgs,
+
+    #[command(flatten)]
+    pub predetermined: PredeterminedArgs,
+
+    /// Directory the schedule files are written to, or - to write a single format to stdout
+    /// [default: config output_dir or .]
+    #[arg(short, long, value_name = "DIR")]
+    pub output_dir: Option<PathBuf>,
+
+    /// Output formats, comma separated [default: config formats or text]
+    #[arg(short, long = "format", visible_alias = "formats", value_name = "FORMAT", value_enum, value_delimiter = ',')]
+    pub formats: Vec<OutputFormat>,
+
+    #[command(flatten)]
+    pub calendar: CalendarArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct ValidateArgs {
+    /// Schedule JSON to check (from `export --format json` or the server's schedules/ directory)
+    #[arg(value_name = "SCHEDULE_JSON")]
+    pub schedule: PathBuf,
+
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    #[command(flatten)]
+    pub predetermined: PredeterminedArgs,
+
+    /// Schedule this one was appended to; all of its assignments must still be in place
+    #[arg(long, value_name = "FILE")]
+    pub existing: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct DiffArgs {
+    /// Schedule JSON before the change (e.g. before an append run)
+    #[arg(value_name = "OLD_JSON")]
+    pub old: PathBuf,
+
+    /// Schedule JSON after the change
+    #[arg(value_name = "NEW_JSON")]
+    pub new: PathBuf,
+
+    /// Print the report as JSON instead of text
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct AnonymizeArgs {
+    /// Submissions CSV to anonymize, or - for stdin
+    #[arg(value_name = "CSV")]
+    pub input: PathBuf,
+
+    /// File to write the anonymized CSV to [default: stdout]
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    /// Number of players per run
+    #[arg(short = 'n', long, value_name = "N", default_value_t = 100)]
+    pub players: usize,
+
+    /// Average share of a day's 49 slots each player is available for (0.0-1.0)
+    #[arg(long, value_name = "FRACTION", default_value_t = 0.15, value_parser = parse_fraction)]
+    pub coverage: f64,
+
+    /// How resources (and priority scores) are spread over the players
+    #[arg(long, value_enum, default_value_t = ScoreDistribution::Exponential)]
+    pub scores: ScoreDistribution,
+
+    /// Number of runs, each with freshly generated submissions
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    pub runs: usize,
+
+    /// Random seed, for repeating a benchmark exactly [default: random]
+    #[arg(long)]
+    pub seed: Option<u64>,
+}
+
+fn parse_fraction(value: &str) -> Result<f64, String> {
+    let fraction: f64 = value.parse().map_err(|e: std::num::ParseFloatError| e.to_string())?;
+    if !(fraction > 0.0 && fraction <= 1.0) {
+        return Err("must be above 0 and at most 1".to_string());
+    }
+    Ok(fraction)
+}
+
+#[derive(Debug, Args)]
+pub struct PipeArgs {
+    /// TOML run configuration (score weights, time windows, predetermined slots); its inputs and outputs are ignored
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub predetermined: PredeterminedArgs,
+
+    /// Indent the JSON instead of writing it on one line
+    #[arg(long)]
+    pub pretty: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct EditArgs {
+    /// Schedule JSON to edit (from `export --format json`), saved in place
+    #[arg(value_name = "SCHEDULE_JSON")]
+    pub schedule: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct ImportArgs {
+    /// Schedule JSON to update (from `export --format json`)
+    #[arg(value_name = "SCHEDULE_JSON")]
+    pub schedule: PathBuf,
+
+    /// Text schedules in the `HH:MM [TAG] Name` format, one day per file, recognized by their
+    /// `** Construction Day **` header. Days without a file keep their assignments.
+    #[arg(value_name = "TXT", required = true)]
+    pub texts: Vec<PathBuf>,
+
+    /// Day of the text schedule if it has no header (needs a single file)
+    #[arg(long, value_parser = ["construction", "research", "troops"])]
+    pub day: Option<String>,
+
+    /// Submission CSVs to match player names against [default: the entries stored in the schedule JSON]
+    #[arg(long = "csv", value_name = "CSV")]
+    pub csvs: Vec<PathBuf>,
+
+    /// Write the updated schedule here instead of overwriting SCHEDULE_JSON
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Port to listen on
+    #[arg(default_value_t = 8080)]
+    pub port: u16,
+
+    /// Folder holding the accounts, forms and schedules
+    #[arg(long, env = "DATA_DIR", default_value = "data")]
+    pub data_dir: String,
+
+    /// Password for the operator dashboard; without one the dashboard is off
+    #[arg(long, env = "ADMIN_PASSWORD", hide_env_values = true)]
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct CompletionsArgs {
+    /// Shell to generate completions for
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
+
+/// One generated day: display name, output file name and the schedule itself
+struct GeneratedDay {
+    day: &'static str, // "construction", "research" or "troops"
+    name: &'static str,
+    file_name: &'static str,
+    schedule: DaySchedule,
+    score: fn(&AppointmentEntry) -> u32,
+}
+
+impl GeneratedDay {
+    fn construction(schedule: DaySchedule) -> Self {
+        GeneratedDay { day: "construction", name: "Construction Day", file_name: "schedule_construction.txt", schedule, score: |e| e.construction_score }
+    }
+
+    fn research(schedule: DaySchedule) -> Self {
+        GeneratedDay { day: "research", name: "Research Day", file_name: "schedule_research.txt", schedule, score: |e| e.research_score }
+    }
+
+    fn troops(schedule: DaySchedule) -> Self {
+        GeneratedDay { day: "troops", name: "Troops Training Day", file_name: "schedule_troops.txt", schedule, score: |e| e.troops_speedups }
+    }
+}
+
+pub async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    match cli.command {
+        Command::Parse(args) => run_parse(args),
+        Command::Schedule(args) => run_schedule(args),
+        Command::Stats(args) => run_stats(args),
+        Command::Export(args) => run_export(args),
+        Command::Validate(args) => run_validate(args),
+        Command::Diff(args) => run_diff(args),
+        Command::Anonymize(args) => run_anonymize(args),
+        Command::Bench(args) => run_bench(BenchSettings {
+            players: args.players,
+            coverage: args.coverage,
+            scores: args.scores,
+            runs: args.runs,
+            seed: args.seed.unwrap_or_else(rand::random),
+        }),
+        Command::Edit(args) => crate::tui::run_editor(&args.schedule),
+        Command::Import(args) => run_import(args),
+        Command::Pipe(args) => run_pipe(args),
+        Command::Serve(args) => run_serve(args).await,
+        Command::Completions(args) => {
+            clap_complete::generate(args.shell, &mut Cli::command(), "prep-appointments", &mut std::io::stdout());
+            Ok(())
+        }
+    }
+}
+
+/// Everything a scheduling run needs: the config (from `--config` or defaults) and the loaded entries
+struct Run {
+    config: RunConfig,
+    sources: Vec<PathBuf>, // Input CSVs and directories as given, before expanding directories
+    entries: Vec<AppointmentEntry>,
+    construction_slots: Option<Vec<(u8, String)>>,
+    research_slots: Option<Vec<(u8, String)>>,
+    troops_slots: Option<Vec<(u8, String)>>,
+    mentions: HashMap<String, String>, // Player ID -> Discord user ID, from the roster
+}
+
+impl Run {
+    fn load(input: &InputArgs) -> Result<Run, Box<dyn std::error::Error>> {
+        Run::load_with_config(input, Run::config(input)?)
+    }
+
+    /// The `--config` file, or the defaults without one
+    fn config(input: &InputArgs) -> Result<RunConfig, Box<dyn std::error::Error>> {
+        Ok(match &input.config {
+            Some(path) => load_run_config(path)?,
+            None => RunConfig::default(),
+        })
+    }
+
+    fn load_with_config(input: &InputArgs, config: RunConfig) -> Result<Run, Box<dyn std::error::Error>> {
+        let sources = if input.inputs.is_empty() { config.inputs.clone() } else { input.inputs.clone() };
+        if sources.is_empty() {
+            return Err(CliError::new(ErrorKind::Usage, "no input CSV given (pass a path or set `inputs` in the config file)").into());
+        }
+        let inputs = expand_inputs(&sources)?;
+        if inputs.iter().filter(|path| is_stdin(path)).count() > 1 {
+            return Err(CliError::new(ErrorKind::Usage, "stdin (-) can only be read once").into());
+        }
+
+        let day_slots = |times: &Option<DayTimeConfig>| {
+            times.as_ref().map(|t| t.time_slots())
+        };
+        let construction_slots = day_slots(&config.construction_times);
+        let research_slots = day_slots(&config.research_times);
+        let troops_slots = day_slots(&config.troops_times);
+
+        // Later files replace earlier entries for the same player, like a resubmission
+        let mut entries: Vec<AppointmentEntry> = Vec::new();
+        for path in &inputs {
+            let loaded = if is_stdin(path) {
+                read_appointments(std::io::stdin().lock(), construction_slots.as_deref(), research_slots.as_deref(), troops_slots.as_deref())
+            } else {
+                load_appointments(path, construction_slots.as_deref(), research_slots.as_deref(), troops_slots.as_deref())
+            };
+            let loaded = loaded
+                .map_err(|e| with_context(e, format_args!("failed to load {}", path.display())))?;
+            log::debug!("{}: {} entries", path.display(), loaded.len());
+            for entry in loaded {
+                match entries.iter_mut().find(|e| e.player_id == entry.player_id) {
+                    Some(existing) => *existing = entry,
+                    None => entries.push(entry),
+                }
+            }
+        }
+        apply_score_weights(&mut entries, &config.weights);
+        let default_times = DayTimeConfig::new("00:00", None);
+        remove_blackout_slots(
+            &mut entries,
+            config.construction_times.as_ref().unwrap_or(&default_times),
+            config.research_times.as_ref().unwrap_or(&default_times),
+            config.troops_times.as_ref().unwrap_or(&default_times),
+        );
+
+        let power = input.power.as_ref().or(config.power.as_ref());
+        let baseline = input.power_baseline.as_ref().or(config.power_baseline.as_ref());
+        if let Some(path) = power {
+            let power = match_power_records(&load_power_file(path)?, &entries);
+            let baseline = match baseline {
+                Some(path) => Some(match_power_records(&load_power_file(path)?, &entries)),
+                None => None,
+            };
+            log::info!("Matched power levels for {} of {} players", power.power.len(), entries.len());
+            for record in &power.unmatched {
+                log::debug!("No single submission for power record [{}] {}", record.alliance, record.name);
+            }
+            if !config.weights.uses_power() {
+                log::warn!("Power levels are loaded but power_per_million and power_gain_per_million are 0, scores are unchanged");
+            }
+            apply_power_scores(&mut entries, &power, baseline.as_ref(), &config.weights);
+        } else if baseline.is_some() {
+            return Err(CliError::new(ErrorKind::Usage, "a power baseline needs power levels to compare against (--power)").into());
+        }
+
+        // After power matching, which goes by the names shown in game
+        let mut mentions = HashMap::new();
+        if let Some(path) = &config.roster {
+            let roster = load_roster_file(path)?;
+            let renamed = apply_roster(&mut entries, &roster);
+            log::info!("Roster names applied to {} of {} players", renamed, entries.len());
+            mentions = discord_mentions(&roster);
+        }
+
+        Ok(Run { config, sources, entries, construction_slots, research_slots, troops_slots, mentions })
+    }
+
+    /// A run without submissions, for commands that only need the config's output settings
+    fn from_config(config: RunConfig) -> Result<Run, Box<dyn std::error::Error>> {
+        let mentions = match &config.roster {
+            Some(path) => discord_mentions(&load_roster_file(path)?),
+            None => HashMap::new(),
+        };
+        Ok(Run { config, sources: Vec::new(), entries: Vec::new(), construction_slots: None, research_slots: None, troops_slots: None, mentions })
+    }
+
+    fn output_dir(&self, arg: &Option<PathBuf>) -> PathBuf {
+        arg.clone()
+            .or_else(|| self.config.output_dir.clone())
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    /// Adds the assignments from `--predetermined` to the ones from the config file
+    fn with_predetermined(mut self, args: &PredeterminedArgs) -> Result<Run, Box<dyn std::error::Error>> {
+        if let Some(path) = &args.predetermined {
+            self.config.predetermined_slots.extend(load_predetermined_file(path)?);
+        }
+        Ok(self)
+    }
+
+    fn formats(&self, arg: &[OutputFormat]) -> Vec<OutputFormat> {
+        if !arg.is_empty() {
+            arg.to_vec()
+        } else if !self.config.formats.is_empty() {
+            self.config.formats.clone()
+        } else {
+            vec![OutputFormat::Text]
+        }
+    }
+
+    /// Bundles generated days with the entries, in the same shape the web server stores
+    fn schedule_data(&self, days: &[GeneratedDay]) -> ScheduleData {
+        let day = |i: usize| days.get(i).map(|d| d.schedule.clone());
+        let mut data = ScheduleData {
+            construction_schedule: day(0),
+            research_schedule: day(1),
+            troops_schedule: day(2),
+            entries: Some(self.entries.clone()),
+            scheduled_player_ids: None,
+        };
+        data.scheduled_player_ids = Some(derive_scheduled_player_ids(&data).into_iter().collect());
+        data
+    }
+
+    /// The configured time windows per day, as slot mappings
+    fn time_slots(&self) -> DayTimeSlots<'_> {
+        DayTimeSlots {
+            construction: self.construction_slots.as_deref(),
+            research: self.research_slots.as_deref(),
+            troops: self.troops_slots.as_deref(),
+        }
+    }
+
+    /// Generates the three days. When appending, players already in `existing` are skipped,
+    /// its filled slots stay locked and the new assignments only fill empty slots (same as the web generator).
+    fn generate_days(&self, existing: Option<&ScheduleData>) -> Result<Vec<GeneratedDay>, Box<dyn std::error::Error>> {
+        let entries_to_use: Vec<AppointmentEntry> = match existing {
+            Some(existing) => {
+                // Use scheduled_player_ids (ID-based) to filter - players already in schedule are excluded
+                let scheduled_player_ids = get_scheduled_player_ids(existing);
+                self.entries.iter()
+                    .filter(|e| !scheduled_player_ids.contains(&e.player_id))
+                    .cloned()
+                    .collect()
+            }
+            None => self.entries.clone(),
+        };
+        if existing.is_some() && entries_to_use.is_empty() {
+            log::info!("All submissions are already in the existing schedule. No new assignments to add.");
+        }
+
+        let existing_days = existing.map(|e| ExistingDays {
+            construction: e.construction_schedule.as_ref(),
+            research: e.research_schedule.as_ref(),
+            troops: e.troops_schedule.as_ref(),
+        });
+        let (construction, research, troops) = generate_schedules(
+            &self.entries,
+            &entries_to_use,
+            &self.config.predetermined_slots,
+            &self.config.guaranteed_players,
+            self.time_slots(),
+            existing_days,
+        ).map_err(|e| CliError::new(ErrorKind::Infeasible, e))?;
+        let existing_days = existing_days.unwrap_or_default();
+        let construction = merge_appended(existing_days.construction, construction);
+        let research = merge_appended(existing_days.research, research);
+        let troops = merge_appended(existing_days.troops, troops);
+
+        Ok(vec![
+            GeneratedDay::construction(construction),
+            GeneratedDay::research(research),
+            GeneratedDay::troops(troops),
+        ])
+    }
+}
+
+/// `-` stands for stdin wherever the CLI reads a submissions CSV
+fn is_stdin(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+/// Replaces each directory in `sources` with the .csv files in it, sorted by name
+fn expand_inputs(sources: &[PathBuf]) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut inputs = Vec::new();
+    for source in sources {
+        if !source.is_dir() {
+            inputs.push(source.clone());
+            continue;
+        }
+        let mut files: Vec<PathBuf> = std::fs::read_dir(source)
+            .map_err(|e| with_context(e, format_args!("failed to read {}", source.display())))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv")))
+            .collect();
+        files.sort();
+        inputs.extend(files);
+    }
+    if inputs.is_empty() {
+        return Err(CliError::new(ErrorKind::Usage, "no submission CSVs found in the input directories").into());
+    }
+    Ok(inputs)
+}
+
+/// Writes the schedules in every requested format. An output dir of `-` writes to stdout
+/// instead (one format only) and returns no paths.
+fn write_outputs(
+    days: &[GeneratedDay],
+    data: &ScheduleData,
+    output_dir: &Path,
+    formats: &[OutputFormat],
+    calendar: &CalendarArgs,
+    mentions: &HashMap<String, String>,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    // Check the dates before writing anything
+    let ics_days = if formats.contains(&OutputFormat::Ics) {
+        let missing: Vec<String> = days.iter()
+            .filter(|d| calendar.date(d.day).is_none())
+            .map(|d| format!("--{}-date", d.day))
+            .collect();
+        if !missing.is_empty() {
+            return Err(CliError::new(ErrorKind::Usage, format!("the ics format needs {}", missing.join(", "))).into());
+        }
+        days.iter().filter_map(|d| Some((d.name, &d.schedule, calendar.date(d.day)?))).collect()
+    } else {
+        Vec::new()
+    };
+
+    if output_dir == Path::new("-") {
+        let [format] = formats else {
+            return Err(CliError::new(ErrorKind::Usage, "writing to stdout (-o -) needs exactly one --format").into());
+        };
+        let mut stdout = std::io::stdout().lock();
+        match format {
+            OutputFormat::Text => {
+                for day in days {
+                    write_schedule_text(&mut stdout, day.name, &day.schedule)?;
+                }
+            }
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut stdout, data)?;
+                writeln!(stdout)?;
+            }
+            OutputFormat::Csv => write_schedule_csv(&mut stdout, data)?,
+            OutputFormat::Ics => write_schedule_ics(&mut stdout, &ics_days)?,
+            OutputFormat::Discord => {
+                for (i, day) in days.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(stdout)?;
+                    }
+                    write_schedule_discord(&mut stdout, day.name, &day.schedule, None, mentions)?;
+                }
+            }
+        }
+        return Ok(Vec::new());
+    }
+
+    std::fs::create_dir_all(output_dir)?;
+    let mut written = Vec::new();
+    for format in formats {
+        match format {
+            OutputFormat::Text => {
+                for day in days {
+                    let path = output_dir.join(day.file_name);
+                    write_schedule_to_file(day.name, &day.schedule, &path.to_string_lossy())?;
+                    written.push(path);
+                }
+            }
+            OutputFormat::Json => {
+                let path = output_dir.join("schedule.json");
+                std::fs::write(&path, serde_json::to_string_pretty(data)?)?;
+                written.push(path);
+            }
+            OutputFormat::Csv => {
+                let path = output_dir.join("schedule.csv");
+                write_schedule_csv(std::fs::File::create(&path)?, data)?;
+                written.push(path);
+            }
+            OutputFormat::Ics => {
+                let path = output_dir.join("schedule.ics");
+                write_schedule_ics(std::fs::File::create(&path)?, &ics_days)?;
+                written.push(path);
+            }
+            OutputFormat::Discord => {
+                // One message per file, since each day needs its own post
+                for day in days {
+                    let path = output_dir.join(day.file_name.replace(".txt", "_discord.md"));
+                    let mut message = Vec::new();
+                    write_schedule_discord(&mut message, day.name, &day.schedule, None, mentions)?;
+                    let message = String::from_utf8(message)?;
+                    if message.chars().count() > DISCORD_MESSAGE_LIMIT {
+                        log::warn!("{} is over Discord's {} character limit, post it in parts", path.display(), DISCORD_MESSAGE_LIMIT);
+                    }
+                    std::fs::write(&path, message)?;
+                    written.push(path);
+                }
+            }
+        }
+    }
+    Ok(written)
+}
+
+fn run_parse(args: ParseArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let entries = Run::load(&args.input)?.entries;
+    let mut stdout = std::io::stdout().lock();
+
+    if args.json {
+        writeln!(stdout, "{}", serde_json::to_string_pretty(&entries)?)?;
+        return Ok(());
+    }
+
+    log::info!("Loaded {} appointment entries (resubmissions merged)", entries.len());
+    writeln!(stdout, "{:<30} {:<12} {:>6} {:>6} {:>6}", "Player", "ID", "Cons", "Res", "Troops")?;
+    for entry in &entries {
+        let day = |wants: bool, slots: &Vec<u8>| if wants { slots.len().to_string() } else { "-".to_string() };
+        writeln!(
+            stdout,
+            "{:<30} {:<12} {:>6} {:>6} {:>6}",
+            format_player_name(&entry.alliance, &entry.name),
+            entry.player_id,
+            day(entry.wants_construction, &entry.construction_available_slots),
+            day(entry.wants_research, &entry.research_available_slots),
+            day(entry.wants_troops, &entry.troops_available_slots),
+        )?;
+    }
+    writeln!(stdout)?;
+    writeln!(stdout, "Columns show the number of available time slots per day (- = not requested)")?;
+
+    Ok(())
+}
+
+fn run_schedule(args: ScheduleArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let run = Run::load(&args.input)?.with_predetermined(&args.predetermined)?;
+    let output_dir = run.output_dir(&args.output_dir);
+    if output_dir == Path::new("-") && !args.no_files {
+        return Err(CliError::new(ErrorKind::Usage, "schedule prints to the terminal; use `export -o -` to write to stdout").into());
+    }
+    if args.watch && run.sources.iter().any(|path| is_stdin(path)) {
+        return Err(CliError::new(ErrorKind::Usage, "--watch can't watch stdin; pass the CSV files or their directory").into());
+    }
+    log::info!("Loaded {} appointment entries (resubmissions merged)", run.entries.len());
+
+    let existing = args.append.load()?;
+    let days = run.generate_days(existing.as_ref())?;
+    let mut stdout = std::io::stdout().lock();
+    for day in &days {
+        write_day_schedule(&mut stdout, day.name, &day.schedule, &run.entries, day.score)?;
+    }
+    drop(stdout);
+
+    if !args.no_files {
+        let written = write_outputs(&days, &run.schedule_data(&days), &output_dir, &run.formats(&args.formats), &args.calendar, &run.mentions)?;
+        log::info!("Schedules saved to:");
+        for path in written {
+            log::info!("  - {}", path.display());
+        }
+    }
+
+    if args.watch {
+        watch_schedule(&args, &run, days)?;
+    }
+    Ok(())
+}
+
+/// Files and directories that trigger a regeneration in watch mode
+fn watch_targets(args: &ScheduleArgs, run: &Run) -> Vec<PathBuf> {
+    run.sources.iter()
+        .chain(&args.input.config)
+        .chain(&args.predetermined.predetermined)
+        .filter_map(|path| std::path::absolute(path).ok())
+        .collect()
+}
+
+/// Modification time and size of every watched file (the .csv files for a directory),
+/// compared between file system events to skip the ones that didn't change any input
+fn fingerprint(targets: &[PathBuf]) -> Vec<(PathBuf, Option<SystemTime>, u64)> {
+    let stat = |path: PathBuf| {
+        let metadata = std::fs::metadata(&path).ok();
+        let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+        (path, modified, metadata.map(|m| m.len()).unwrap_or(0))
+    };
+    let mut files = Vec::new();
+    for target in targets {
+        if target.is_dir() {
+            let mut csvs: Vec<PathBuf> = std::fs::read_dir(target).into_iter()
+                .flatten()
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv")))
+                .collect();
+            csvs.sort();
+            files.extend(csvs.into_iter().map(stat));
+        } else {
+            files.push(stat(target.clone()));
+        }
+    }
+    files
+}
+
+/// Regenerates the schedules (and files) whenever a watched input changes, printing who moved
+/// compared to the previous run. Errors, like a CSV caught mid-write, are reported and the
+/// previous schedule is kept until the next change.
+fn watch_schedule(args: &ScheduleArgs, run: &Run, mut days: Vec<GeneratedDay>) -> Result<(), Box<dyn std::error::Error>> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut debouncer = new_debouncer(Duration::from_millis(500), tx)?;
+
+    // Editors often save by replacing the file, so watch the parent directories instead of the files
+    let mut targets = watch_targets(args, run);
+    let mut watched_dirs = BTreeSet::new();
+    let mut watch_dirs = |targets: &[PathBuf], watched_dirs: &mut BTreeSet<PathBuf>| -> Result<(), Box<dyn std::error::Error>> {
+        for target in targets {
+            let dir = if target.is_dir() { Some(target.as_path()) } else { target.parent() };
+            if let Some(dir) = dir.filter(|dir| !watched_dirs.contains(*dir)) {
+                debouncer.watcher().watch(dir, RecursiveMode::NonRecursive)?;
+                watched_dirs.insert(dir.to_path_buf());
+            }
+        }
+        Ok(())
+    };
+    watch_dirs(&targets, &mut watched_dirs)?;
+    let mut last_seen = fingerprint(&targets);
+    log::info!("Watching {} for changes (Ctrl-C to stop)", targets.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", "));
+
+    for result in rx {
+        let events = match result {
+            Ok(events) => events,
+            Err(e) => {
+                log::warn!("watch error: {}", e);
+                continue;
+            }
+        };
+        // Reads (including our own) and writes to unrelated files in the same directories show up too
+        let current = fingerprint(&targets);
+        if events.is_empty() || current == last_seen {
+            continue;
+        }
+        last_seen = current;
+
+        log::info!("\n=== {} Input changed, regenerating ===", chrono::Local::now().format("%H:%M:%S"));
+        let regenerated = (|| -> Result<_, Box<dyn std::error::Error>> {
+            let run = Run::load(&args.input)?.with_predetermined(&args.predetermined)?;
+            let new_days = run.generate_days(args.append.load()?.as_ref())?;
+            let new_written = if args.no_files {
+                Vec::new()
+            } else {
+                write_outputs(&new_days, &run.schedule_data(&new_days), &run.output_dir(&args.output_dir), &run.formats(&args.formats), &args.calendar, &run.mentions)?
+            };
+            Ok((run, new_days, new_written))
+        })();
+        let (run, new_days, new_written) = match regenerated {
+            Ok(result) => result,
+            Err(e) => {
+                log::error!("{} (keeping the previous schedule)", e);
+                continue;
+            }
+        };
+
+        log::info!("Loaded {} appointment entries", run.entries.len());
+        let mut stdout = std::io::stdout().lock();
+        for (old, new) in days.iter().zip(&new_days) {
+            write_day_diff(&mut stdout, new.name, &diff_days(Some(&old.schedule), Some(&new.schedule)))?;
+        }
+        drop(stdout);
+        if !new_written.is_empty() {
+            log::info!("Updated {}", new_written.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", "));
+        }
+
+        // The config may list different inputs now
+        let new_targets = watch_targets(args, &run);
+        if new_targets != targets {
+            watch_dirs(&new_targets, &mut watched_dirs)?;
+            last_seen = fingerprint(&new_targets);
+            targets = new_targets;
+        }
+        days = new_days;
+    }
+    Ok(())
+}
+
+fn run_pipe(args: PipeArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let input = InputArgs { inputs: vec![PathBuf::from("-")], config: args.config.clone(), power: None, power_baseline: None };
+    let run = Run::load(&input)?.with_predetermined(&args.predetermined)?;
+    // An empty schedule looks like a valid result, so input without a single submission is an error
+    if run.entries.is_empty() {
+        return Err(CliError::new(ErrorKind::Parse, "no submissions on stdin; expected a submissions CSV with a header row and a row per player").into());
+    }
+    let days = run.generate_days(None)?;
+    let data = run.schedule_data(&days);
+
+    let mut stdout = std::io::stdout().lock();
+    if args.pretty {
+        serde_json::to_writer_pretty(&mut stdout, &data)?;
+    } else {
+        serde_json::to_writer(&mut stdout, &data)?;
+    }
+    writeln!(stdout)?;
+    Ok(())
+}
+
+fn run_validate(args: ValidateArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let data = load_schedule(&args.schedule, "schedule")?;
+    let run = Run::load(&args.input)?.with_predetermined(&args.predetermined)?;
+    let existing = args.existing.as_deref().map(|path| load_schedule(path, "existing schedule")).transpose()?;
+    let predetermined = resolve_predetermined(&run.entries, &run.config.predetermined_slots, run.time_slots())
+        .map_err(|e| CliError::new(ErrorKind::Infeasible, e))?;
+    let violations = validate_schedule(&data, &run.entries, &predetermined, existing.as_ref(), run.time_slots());
+
+    let assigned: usize = [&data.construction_schedule, &data.research_schedule, &data.troops_schedule].iter()
+        .filter_map(|day| day.as_ref())
+        .map(|day| day.appointments.len())
+        .sum();
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "Checked {} assignments against {} submissions", assigned, run.entries.len())?;
+    if violations.is_empty() {
+        writeln!(stdout, "No violations found")?;
+        return Ok(());
+    }
+
+    let player = |player_id: &str| match run.entries.iter().find(|e| e.player_id == player_id) {
+        Some(entry) => format!("{} (ID: {})", format_player_name(&entry.alliance, &entry.name), player_id),
+        None => format!("ID {}", player_id),
+    };
+    for violation in &violations {
+        let time = violation.slot.map(slot_to_time).unwrap_or_default();
+        writeln!(stdout, "  {:<12} {:<5}  {}: {}", violation.day, time, player(&violation.player_id), violation.message)?;
+    }
+    Err(CliError::new(ErrorKind::Validation, format!("{} violation(s) found", violations.len())).into())
+}
+
+fn run_diff(args: DiffArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let old = load_schedule(&args.old, "schedule")?;
+    let new = load_schedule(&args.new, "schedule")?;
+    let diff = diff_schedules(&old, &new);
+    let mut stdout = std::io::stdout().lock();
+
+    if args.json {
+        writeln!(stdout, "{}", serde_json::to_string_pretty(&diff)?)?;
+        return Ok(());
+    }
+
+    writeln!(stdout, "{} -> {}", args.old.display(), args.new.display())?;
+    write_day_diff(&mut stdout, "Construction Day", &diff.construction)?;
+    write_day_diff(&mut stdout, "Research Day", &diff.research)?;
+    write_day_diff(&mut stdout, "Troops Training Day", &diff.troops)?;
+    Ok(())
+}
+
+fn run_anonymize(args: AnonymizeArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let input: Box<dyn std::io::Read> = if is_stdin(&args.input) {
+        Box::new(std::io::stdin().lock())
+    } else {
+        Box::new(std::fs::File::open(&args.input)
+            .map_err(|e| with_context(e, format_args!("failed to open {}", args.input.display())))?)
+    };
+    let summary = match &args.output {
+        Some(path) => {
+            let output = std::fs::File::create(path).map_err(|e| with_context(e, format_args!("failed to create {}", path.display())))?;
+            anonymize_submissions(input, output)?
+        }
+        None => anonymize_submissions(input, std::io::stdout().lock())?,
+    };
+    log::info!("Anonymized {} rows ({} players)", summary.rows, summary.players);
+    Ok(())
+}
+
+fn run_import(args: ImportArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut data = load_schedule(&args.schedule, "schedule")?;
+    let entries = if args.csvs.is_empty() {
+        data.entries.clone().ok_or_else(|| CliError::new(
+            ErrorKind::Usage,
+            format!("{} has no entries to match players against; pass the submissions with --csv", args.schedule.display()),
+        ))?
+    } else {
+        Run::load(&InputArgs { inputs: args.csvs.clone(), config: None, power: None, power_baseline: None })?.entries
+    };
+    if args.day.is_some() && args.texts.len() > 1 {
+        return Err(CliError::new(ErrorKind::Usage, "--day needs a single text schedule").into());
+    }
+
+    let mut failed = 0;
+    let mut imported: Vec<(&str, DaySchedule)> = Vec::new();
+    for path in &args.texts {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| with_context(e, format_args!("failed to read {}", path.display())))?;
+        let Some(day) = text_schedule_day(&text).or(args.day.as_deref()) else {
+            return Err(CliError::new(
+                ErrorKind::Usage,
+                format!("{} has no `** Construction Day **` style header; pass --day", path.display()),
+            ).into());
+        };
+        if imported.iter().any(|(other, _)| *other == day) {
+            return Err(CliError::new(ErrorKind::Usage, format!("two text schedules for {} day", day)).into());
+        }
+        match import_day_text(&text, day, &entries) {
+            Ok(schedule) => imported.push((day, schedule)),
+            Err(errors) => {
+                for error in &errors {
+                    log::error!("{}:{}: {}", path.display(), error.line, error.message);
+                }
+                failed += errors.len();
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(CliError::new(ErrorKind::Parse, format!("{} line(s) couldn't be imported; nothing was written", failed)).into());
+    }
+
+    for (day, schedule) in imported {
+        let (name, slot) = match day {
+            "construction" => ("Construction Day", &mut data.construction_schedule),
+            "research" => ("Research Day", &mut data.research_schedule),
+            _ => ("Troops Training Day", &mut data.troops_schedule),
+        };
+        write_day_diff(std::io::stdout().lock(), name, &diff_days(slot.as_ref(), Some(&schedule)))?;
+        *slot = Some(schedule);
+    }
+    if data.entries.is_none() {
+        data.entries = Some(entries);
+    }
+    data.scheduled_player_ids = Some(derive_scheduled_player_ids(&data).into_iter().collect());
+
+    let output = args.output.as_ref().unwrap_or(&args.schedule);
+    std::fs::write(output, serde_json::to_string_pretty(&data)?)
+        .map_err(|e| with_context(e, format_args!("failed to write {}", output.display())))?;
+    log::info!("Saved {}", output.display());
+    Ok(())
+}
+
+fn run_stats(args: StatsArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let form_config = args.form_config.as_deref().map(load_form_config).transpose()?;
+    let mut config = Run::config(&args.input)?;
+    if let Some(form_config) = &form_config {
+        // The form's time windows decide which slot each listed time is, like on the server
+        config.construction_times = Some(form_config.construction_times.clone());
+        config.research_times = Some(form_config.research_times.clone());
+        config.troops_times = Some(form_config.troops_times.clone());
+    }
+    let entries = Run::load_with_config(&args.input, config)?.entries;
+    let stats = web::compute_stats(&entries, form_config.as_ref());
+    let mut stdout = std::io::stdout().lock();
+
+    if args.json {
+        writeln!(stdout, "{}", serde_json::to_string_pretty(&stats)?)?;
+        return Ok(());
+    }
+    let tables = web::stats_tables(&stats, form_config.as_ref());
+    if let Some(path) = &args.export {
+        let content = if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("xlsx")) {
+            web::stats_workbook(&tables)?
+        } else {
+            let table = tables.iter().find(|t| t.name == args.table).expect("--table only accepts known tables");
+            web::stats_table_csv(table)?
+        };
+        std::fs::write(path, content).map_err(|e| with_context(e, format_args!("failed to write {}", path.display())))?;
+        log::info!("Statistics saved to {}", path.display());
+        return Ok(());
+    }
+
+    let table = |name: &str| tables.iter().find(|t| t.name == name).map(|t| t.rows.as_slice()).unwrap_or_default();
+    let text = |cell: &StatsCell| match cell {
+        StatsCell::Text(text) => text.clone(),
+        StatsCell::Number(number) => number.to_string(),
+    };
+    let number = |cell: &StatsCell| match cell {
+        StatsCell::Number(number) => *number,
+        StatsCell::Text(_) => 0.0,
+    };
+
+    writeln!(stdout, "{} players submitted", entries.len())?;
+    writeln!(stdout, "\n=== Alliance Request Counts ===")?;
+    writeln!(stdout, "{:<20} {:>6} {:>6} {:>6}", "Alliance", "Cons", "Res", "Troops")?;
+    for row in table("alliances") {
+        let alliance = text(&row[0]);
+        let alliance = if alliance.is_empty() { "(none)".to_string() } else { alliance };
+        writeln!(stdout, "{:<20} {:>6} {:>6} {:>6}", alliance, number(&row[1]), number(&row[2]), number(&row[3]))?;
+    }
+
+    // Time slot popularity per day, most requested first
+    for (day, day_name) in [("construction", "Construction Day"), ("research", "Research Day"), ("troops", "Troops Training Day")] {
+        let mut slots: Vec<(String, f64)> = table("slots").iter()
+            .filter(|row| text(&row[0]) == day)
+            .map(|row| (text(&row[1]), number(&row[2])))
+            .collect();
+        slots.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let requests = entries.iter().filter(|e| match day {
+            "construction" => e.wants_construction,
+            "research" => e.wants_research,
+            _ => e.wants_troops,
+        }).count();
+        writeln!(stdout, "\n=== {} ({} requests) ===", day_name, requests)?;
+        for (time, count) in slots.into_iter().filter(|(_, count)| *count > 0.0).take(args.top) {
+            writeln!(stdout, "  {} -> {} players", time, count)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn run_export(args: ExportArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let (run, days, data) = match &args.schedule {
+        Some(path) => {
+            // Only the config's output settings apply; the schedule is written as it is
+            let data = load_schedule(path, "schedule")?;
+            let config = args.input.config.as_deref().map(load_run_config).transpose()?.unwrap_or_default();
+            let run = Run::from_config(config)?;
+            let days: Vec<GeneratedDay> = [
+                data.construction_schedule.clone().map(GeneratedDay::construction),
+                data.research_schedule.clone().map(GeneratedDay::research),
+                data.troops_schedule.clone().map(GeneratedDay::troops),
+            ].into_iter().flatten().collect();
+            (run, days, data)
+        }
+        None => {
+            let run = Run::load(&args.input)?.with_predetermined(&args.predetermined)?;
+            let existing = args.append.load()?;
+            let days = run.generate_days(existing.as_ref())?;
+            let data = run.schedule_data(&days);
+            (run, days, data)
+        }
+    };
+    let written = write_outputs(&days, &data, &run.output_dir(&args.output_dir), &run.formats(&args.formats), &args.calendar, &run.mentions)?;
+    let mut stdout = std::io::stdout().lock();
+    for path in written {
+        writeln!(stdout, "{}", path.display())?;
+    }
+    Ok(())
+}
+
+async fn run_serve(args: ServeArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let port = args.port;
+    log::info!("Starting web server on port {}...", port);
+    // A blank password counts as none, so the dashboard can't be opened with an empty one
+    let password = args.password.filter(|p| !p.is_empty());
+    match password {
+        Some(_) => log::info!("Operator dashboard at http://localhost:{}/operator", port),
+        None => log::warn!("Operator dashboard disabled: set ADMIN_PASSWORD (or --password) to turn it on"),
+    }
+    log::info!("Access the site at http://localhost:{}", port);
+
+    web::start_server(port, password, args.data_dir).await?;
+    Ok(())
+}
@@ -0,0 +1,319 @@
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::http::{Method, Uri};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use crate::display::OutputFormat;
+use crate::error::{with_context, CliError, ErrorKind};
+use kingshot_core::parser::ScoreWeights;
+use kingshot_core::power::{read_power_records, PowerRecord};
+use kingshot_core::roster::{read_roster, RosterEntry};
+use kingshot_core::schedule::{validate_day_windows, DayTimeConfig, GuaranteedPlayers, PredeterminedSlot};
+
+/// Deployment-level server configuration, read from `server_config.json` in the data directory.
+/// Every section is optional so a missing file or an empty object keeps the default behavior.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServerConfig {
+    /// Cross-origin access to the JSON API. CORS headers are only sent when this is set.
+    #[serde(default)]
+    pub cors: Option<CorsConfig>,
+    /// Session cookie signing. Without it a random key is generated on every boot.
+    #[serde(default)]
+    pub session: Option<SessionConfig>,
+    /// Set when several instances share the same data directory (e.g. a network mount).
+    /// Accounts and forms are then re-read from disk on each request instead of trusting
+    /// the copy loaded at startup.
+    #[serde(default)]
+    pub shared_storage: bool,
+    /// Default per-account limits. The operator can override them for individual accounts.
+    #[serde(default)]
+    pub limits: AccountLimits,
+    /// External tool that reads power levels from uploaded member list recordings.
+    /// The upload endpoint is disabled without it.
+    #[serde(default)]
+    pub power_extraction: Option<PowerExtractionConfig>,
+    /// Google service account that writes schedules to the accounts' Google Sheets.
+    /// Publishing to a sheet is disabled without it.
+    #[serde(default)]
+    pub google_sheets: Option<GoogleSheetsConfig>,
+    /// Outgoing mail server for email notifications and password resets.
+    /// Email is disabled without it.
+    #[serde(default)]
+    pub smtp: Option<SmtpConfig>,
+}
+
+/// Usage limits for one account. Unset fields mean unlimited.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccountLimits {
+    /// Active forms across all of the account's servers
+    #[serde(default)]
+    pub max_active_forms: Option<usize>,
+    /// Submission rows (including re-submissions) per form
+    #[serde(default)]
+    pub max_submissions_per_form: Option<usize>,
+    /// Disk space used by the account's forms, schedules, statistics and uploads
+    #[serde(default)]
+    pub max_storage_bytes: Option<u64>,
+}
+
+impl AccountLimits {
+    /// Fills unset fields of `self` from `defaults`
+    pub fn or(&self, defaults: &AccountLimits) -> AccountLimits {
+        AccountLimits {
+            max_active_forms: self.max_active_forms.or(defaults.max_active_forms),
+            max_submissions_per_form: self.max_submissions_per_form.or(defaults.max_submissions_per_form),
+            max_storage_bytes: self.max_storage_bytes.or(defaults.max_storage_bytes),
+        }
+    }
+}
+
+/// How the server runs the power level extractor on uploaded recordings and screenshots
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerExtractionConfig {
+    /// Program and arguments. `{input}` is replaced with the directory holding the uploaded
+    /// files and `{output}` with the CSV (`alliance,name,power`) the program must write.
+    pub command: Vec<String>,
+    /// Jobs still running after this many seconds are stopped and marked as failed
+    #[serde(default = "default_extraction_timeout")]
+    pub timeout_secs: u64,
+    /// Total size of the files of one upload
+    #[serde(default = "default_max_upload_bytes")]
+    pub max_upload_bytes: u64,
+    /// Jobs run at the same time on this instance; later uploads wait for a free slot
+    #[serde(default = "default_parallel_jobs")]
+    pub parallel_jobs: usize,
+}
+
+fn default_extraction_timeout() -> u64 {
+    1800
+}
+
+fn default_max_upload_bytes() -> u64 {
+    500 * 1024 * 1024
+}
+
+fn default_parallel_jobs() -> usize {
+    1
+}
+
+/// Google Sheets access for publishing schedules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoogleSheetsConfig {
+    /// JSON key file of the service account, as downloaded from the Google Cloud console.
+    /// Admins share their sheet with the account's email so it can write to it.
+    pub service_account_key: PathBuf,
+}
+
+/// Mail server the instance sends email through
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpConfig {
+    pub host: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    /// Login, when the server requires one
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Sender of every message, e.g. "Schedule Maker <schedules@example.com>"
+    pub from: String,
+    /// Address the site is reached at (e.g. "https://schedules.example.com"), for links in
+    /// messages. Messages only name the pages without it.
+    #[serde(default)]
+    pub public_url: Option<String>,
+}
+
+/// How the connection to the mail server is encrypted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Plain connection upgraded with STARTTLS (usually port 587)
+    #[default]
+    Starttls,
+    /// TLS from the start (usually port 465)
+    Tls,
+    /// No encryption, for a relay on the same host
+    None,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+/// Session settings shared by every instance of a deployment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionConfig {
+    /// Secret used to sign session cookies, at least 32 characters. Every instance behind the
+    /// same load balancer must use the same value so a login on one is valid on the others.
+    pub secret_key: String,
+    /// Only send the session cookie over HTTPS
+    #[serde(default = "default_cookie_secure")]
+    pub cookie_secure: bool,
+}
+
+fn default_cookie_secure() -> bool {
+    true
+}
+
+/// CORS policy for alliance tooling hosted on other origins
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorsConfig {
+    /// Origins allowed to call the API (e.g. "https://tools.example.com"). "*" allows any origin.
+    pub allowed_origins: Vec<String>,
+    /// HTTP methods allowed for cross-origin requests
+    #[serde(default = "default_cors_methods")]
+    pub allowed_methods: Vec<String>,
+    /// Request headers allowed for cross-origin requests. Empty allows any header.
+    #[serde(default)]
+    pub allowed_headers: Vec<String>,
+    /// Whether cookies (the admin session) are sent with cross-origin requests
+    #[serde(default)]
+    pub allow_credentials: bool,
+    /// How long browsers may cache preflight responses, in seconds
+    #[serde(default)]
+    pub max_age: Option<usize>,
+}
+
+impl CorsConfig {
+    /// Checks the origins, methods and headers up front; actix-cors only rejects them once the
+    /// server workers start, which leaves the server running without answering
+    pub fn validate(&self) -> Result<(), String> {
+        for origin in self.allowed_origins.iter().map(|o| o.trim()).filter(|o| *o != "*") {
+            let is_origin = origin.parse::<Uri>().is_ok_and(|uri| {
+                matches!(uri.scheme_str(), Some("http" | "https")) && uri.authority().is_some() && uri.path() == "/"
+            });
+            if !is_origin || origin.ends_with('/') || HeaderValue::from_str(origin).is_err() {
+                return Err(format!("allowed_origins: \"{}\" is not an origin, use scheme://host[:port] without a path, or \"*\"", origin));
+            }
+        }
+        for method in &self.allowed_methods {
+            if Method::from_bytes(method.trim().to_uppercase().as_bytes()).is_err() {
+                return Err(format!("allowed_methods: \"{}\" is not an HTTP method", method));
+            }
+        }
+        for header in &self.allowed_headers {
+            if HeaderName::try_from(header.trim()).is_err() {
+                return Err(format!("allowed_headers: \"{}\" is not a header name", header));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn default_cors_methods() -> Vec<String> {
+    vec!["GET".to_string(), "POST".to_string(), "PUT".to_string()]
+}
+
+/// Loads the server configuration from `{data_dir}/server_config.json`.
+/// Falls back to the default (everything disabled) if the file is missing or invalid.
+pub fn load_server_config(data_dir: &str) -> ServerConfig {
+    let path = format!("{}/server_config.json", data_dir);
+    if Path::new(&path).exists() {
+        match std::fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str::<ServerConfig>(&content) {
+                Ok(config) => return config,
+                Err(e) => log::warn!("Failed to parse server config from {}: {}", path, e),
+            },
+            Err(e) => log::warn!("Failed to read server config file {}: {}", path, e),
+        }
+    }
+    ServerConfig::default()
+}
+
+/// Settings for a CLI scheduling run, read from the TOML file passed with `--config`.
+/// Command-line arguments take precedence over the values in the file.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RunConfig {
+    /// Submission CSVs, merged in order (a later file wins for the same player ID)
+    #[serde(default)]
+    pub inputs: Vec<PathBuf>,
+    /// Directory the schedule files are written to
+    #[serde(default)]
+    pub output_dir: Option<PathBuf>,
+    /// Output formats to write (text, json, csv). Empty means text only.
+    #[serde(default)]
+    pub formats: Vec<OutputFormat>,
+    /// Priority score weights
+    #[serde(default)]
+    pub weights: ScoreWeights,
+    /// Time window per day, same as in the form settings. Unset days use the fixed default mapping.
+    #[serde(default)]
+    pub construction_times: Option<DayTimeConfig>,
+    #[serde(default)]
+    pub research_times: Option<DayTimeConfig>,
+    #[serde(default)]
+    pub troops_times: Option<DayTimeConfig>,
+    /// Slots locked to a player before scheduling
+    #[serde(default)]
+    pub predetermined_slots: Vec<PredeterminedSlot>,
+    /// Player IDs per day who must get some slot, at whatever time is free
+    #[serde(default)]
+    pub guaranteed_players: GuaranteedPlayers,
+    /// Power levels (`alliance,name,power` CSV) weighted into priority scores, see `weights`
+    #[serde(default)]
+    pub power: Option<PathBuf>,
+    /// Earlier power levels, for weighting the power gained since
+    #[serde(default)]
+    pub power_baseline: Option<PathBuf>,
+    /// Player roster (`player_id,name,alliance` CSV) whose names and alliances replace the submitted ones
+    #[serde(default)]
+    pub roster: Option<PathBuf>,
+}
+
+/// Loads a run configuration from a TOML file.
+/// Relative input and output paths are resolved against the directory containing the file.
+pub fn load_run_config(path: &Path) -> Result<RunConfig, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| with_context(e, format_args!("failed to read config {}", path.display())))?;
+    let mut config: RunConfig = toml::from_str(&content)
+        .map_err(|e| with_context(e, format_args!("failed to parse config {}", path.display())))?;
+
+    let base = path.parent().unwrap_or(Path::new(""));
+    for input in &mut config.inputs {
+        *input = base.join(&*input);
+    }
+    for path in [&mut config.output_dir, &mut config.power, &mut config.power_baseline, &mut config.roster].into_iter().flatten() {
+        *path = base.join(&*path);
+    }
+    for (key, times) in [("construction_times", &config.construction_times), ("research_times", &config.research_times), ("troops_times", &config.troops_times)] {
+        if let Some(times) = times {
+            validate_day_windows(times).map_err(|e| CliError::new(ErrorKind::Parse, format!("invalid {} in config {}: {}", key, path.display(), e)))?;
+        }
+    }
+    Ok(config)
+}
+
+/// Loads predetermined slot assignments for a CLI run. JSON files hold an array of
+/// `{day, time, player_id}` objects (the form config format); anything else is read as CSV
+/// with a `day,time,player_id` header and optional `alliance,name` columns.
+pub fn load_predetermined_file(path: &Path) -> Result<Vec<PredeterminedSlot>, Box<dyn std::error::Error>> {
+    let context = format!("failed to read predetermined slots {}", path.display());
+
+    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+        let content = std::fs::read_to_string(path).map_err(|e| with_context(e, &context))?;
+        return Ok(serde_json::from_str(&content).map_err(|e| with_context(e, &context))?);
+    }
+
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_path(path).map_err(|e| with_context(e, &context))?;
+    let mut slots = Vec::new();
+    for record in reader.deserialize::<PredeterminedSlot>() {
+        slots.push(record.map_err(|e| with_context(e, &context))?);
+    }
+    Ok(slots)
+}
+
+/// Loads a power level CSV (`alliance,name,power`, as written by the power level uploads)
+pub fn load_power_file(path: &Path) -> Result<Vec<PowerRecord>, Box<dyn std::error::Error>> {
+    let context = format!("failed to read power levels {}", path.display());
+    let file = std::fs::File::open(path).map_err(|e| with_context(e, &context))?;
+    Ok(read_power_records(file).map_err(|e| with_context(e, &context))?)
+}
+
+/// Loads a player roster CSV (`player_id,name,alliance`, optionally `discord_id,notes`, as
+/// downloaded from the dashboard's Roster tab)
+pub fn load_roster_file(path: &Path) -> Result<Vec<RosterEntry>, Box<dyn std::error::Error>> {
+    let context = format!("failed to read roster {}", path.display());
+    let file = std::fs::File::open(path).map_err(|e| with_context(e, &context))?;
+    Ok(read_roster(file).map_err(|e| with_context(e, &context))?)
+}
@@ -1,8 +1,30 @@
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::Write;
-use crate::parser::AppointmentEntry;
-use crate::schedule::DaySchedule;
-use crate::schedule::slot_to_time;
+use kingshot_core::parser::AppointmentEntry;
+use kingshot_core::schedule::{DayDiff, DaySchedule, ScheduleData};
+use kingshot_core::schedule::slot_to_time;
+use kingshot_core::schedule::slot_utils::parse_time_to_minutes;
+use chrono::{NaiveDate, NaiveTime, Utc};
+use serde::Deserialize;
+
+/// Output format for schedule files written by the CLI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// One file per day, one line per slot: HH:MM [tag] name
+    #[value(alias = "txt")]
+    #[serde(alias = "txt")]
+    Text,
+    /// The full schedule data: appointments with scores, unassigned players and entries
+    Json,
+    /// One row per assignment or unassigned player, all days in one file
+    Csv,
+    /// Calendar events (iCalendar) for every assignment, in UTC; needs the date of each day
+    Ics,
+    /// Discord messages (markdown) with the filled slots of each day, ready to paste
+    Discord,
+}
 
 /// Formats a player name with alliance tag
 pub fn format_player_name(alliance: &str, name: &str) -> String {
@@ -19,8 +41,15 @@ pub fn write_schedule_to_file(
     schedule: &DaySchedule,
     filename: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut file = File::create(filename)?;
-    
+    write_schedule_text(File::create(filename)?, day_name, schedule)
+}
+
+/// Writes a day schedule in the text format (header line, then HH:MM [tag] name per slot)
+pub fn write_schedule_text<W: Write>(
+    mut file: W,
+    day_name: &str,
+    schedule: &DaySchedule,
+) -> Result<(), Box<dyn std::error::Error>> {
     // Write header with day name
     writeln!(file, "** {} **", day_name)?;
     
@@ -38,36 +67,217 @@ pub fn write_schedule_to_file(
     Ok(())
 }
 
-/// Prints a day schedule in a readable format
-pub fn print_day_schedule<F>(day_name: &str, schedule: &DaySchedule, entries: &[AppointmentEntry], get_priority_score: F)
+/// Discord markdown characters escaped in player names
+const DISCORD_MARKDOWN: [char; 8] = ['\\', '*', '_', '~', '`', '|', '>', '#'];
+
+/// Longest message Discord accepts, in characters
+pub const DISCORD_MESSAGE_LIMIT: usize = 2000;
+
+/// Writes a day schedule as a Discord message: a bold header, then `HH:MM` [tag] name per
+/// filled slot (empty slots are left out so the message stays under Discord's 2000 characters).
+/// Players in `mentions` (player ID -> Discord user ID) get a `<@id>` mention after their name.
+/// Times come from `time_slots` when given, otherwise from the fixed default mapping.
+pub fn write_schedule_discord<W: Write>(
+    mut writer: W,
+    day_name: &str,
+    schedule: &DaySchedule,
+    time_slots: Option<&[(u8, String)]>,
+    mentions: &HashMap<String, String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    writeln!(writer, "**{}**", day_name)?;
+    let mut slots: Vec<&u8> = schedule.appointments.keys().collect();
+    slots.sort();
+    for slot in slots {
+        let appt = &schedule.appointments[slot];
+        let name: String = format_player_name(&appt.alliance, &appt.name).chars()
+            .flat_map(|c| DISCORD_MARKDOWN.contains(&c).then_some('\\').into_iter().chain([c]))
+            .collect();
+        let time = time_slots
+            .and_then(|slots| slots.iter().find(|(s, _)| s == slot))
+            .map(|(_, time)| time.clone())
+            .unwrap_or_else(|| slot_to_time(*slot));
+        match mentions.get(&appt.player_id) {
+            Some(discord_id) => writeln!(writer, "`{}` {} <@{}>", time, name, discord_id)?,
+            None => writeln!(writer, "`{}` {}", time, name)?,
+        }
+    }
+    Ok(())
+}
+
+/// Splits a Discord message into messages within [`DISCORD_MESSAGE_LIMIT`], at line breaks
+pub fn split_discord_messages(text: &str) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut current = String::new();
+    for line in text.lines() {
+        if !current.is_empty() && current.chars().count() + line.chars().count() + 1 > DISCORD_MESSAGE_LIMIT {
+            messages.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(line);
+    }
+    if !current.is_empty() {
+        messages.push(current);
+    }
+    messages
+}
+
+/// Escapes an iCalendar text value
+fn ics_text(value: &str) -> String {
+    value.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+/// Writes one iCalendar content line, folded at 75 bytes as the format requires
+fn write_ics_line<W: Write>(writer: &mut W, line: &str) -> std::io::Result<()> {
+    let mut rest = line;
+    let mut limit = 75;
+    while rest.len() > limit {
+        let mut split = limit;
+        while !rest.is_char_boundary(split) {
+            split -= 1;
+        }
+        write!(writer, "{}\r\n ", &rest[..split])?;
+        rest = &rest[split..];
+        limit = 74; // Continuation lines start with a space
+    }
+    write!(writer, "{}\r\n", rest)
+}
+
+/// Writes the assignments of every given day as iCalendar events, one per slot.
+/// Slot times are UTC (game time) on the day's date; slot 1 lasts 15 minutes, the others 30.
+pub fn write_schedule_ics<W: Write>(mut writer: W, days: &[(&str, &DaySchedule, NaiveDate)]) -> Result<(), Box<dyn std::error::Error>> {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    write_ics_line(&mut writer, "BEGIN:VCALENDAR")?;
+    write_ics_line(&mut writer, "VERSION:2.0")?;
+    write_ics_line(&mut writer, "PRODID:-//prep-appointments//SvS preparation week//EN")?;
+    for (day_name, schedule, date) in days {
+        let mut slots: Vec<&u8> = schedule.appointments.keys().collect();
+        slots.sort();
+        for slot in slots {
+            let appt = &schedule.appointments[slot];
+            let minutes = parse_time_to_minutes(&slot_to_time(*slot)).unwrap_or(0);
+            let start = date.and_time(NaiveTime::MIN) + chrono::Duration::minutes(minutes as i64);
+            let end = start + chrono::Duration::minutes(if *slot == 1 { 15 } else { 30 });
+            write_ics_line(&mut writer, "BEGIN:VEVENT")?;
+            write_ics_line(&mut writer, &format!("UID:{}-{}-{}@prep-appointments", date.format("%Y%m%d"), slot, ics_text(&appt.player_id)))?;
+            write_ics_line(&mut writer, &format!("DTSTAMP:{}", stamp))?;
+            write_ics_line(&mut writer, &format!("DTSTART:{}", start.format("%Y%m%dT%H%M%SZ")))?;
+            write_ics_line(&mut writer, &format!("DTEND:{}", end.format("%Y%m%dT%H%M%SZ")))?;
+            write_ics_line(&mut writer, &format!("SUMMARY:{}", ics_text(&format!("{}: {}", day_name, format_player_name(&appt.alliance, &appt.name)))))?;
+            write_ics_line(&mut writer, &format!("DESCRIPTION:{}", ics_text(&format!("Player ID {}", appt.player_id))))?;
+            write_ics_line(&mut writer, "END:VEVENT")?;
+        }
+    }
+    write_ics_line(&mut writer, "END:VCALENDAR")?;
+    Ok(())
+}
+
+/// Returns an entry's priority score for one day
+type EntryScore = fn(&AppointmentEntry) -> u32;
+
+/// Writes all days of a schedule as CSV: day, slot, time, player_id, alliance, name, priority_score, status, note.
+/// Unassigned players get a row without slot and time; their priority score comes from the entries.
+pub fn write_schedule_csv<W: Write>(writer: W, data: &ScheduleData) -> Result<(), Box<dyn std::error::Error>> {
+    let mut csv = csv::Writer::from_writer(writer);
+    csv.write_record(["day", "slot", "time", "player_id", "alliance", "name", "priority_score", "status", "note"])?;
+
+    let entries = data.entries.as_deref().unwrap_or_default();
+    let days: [(&str, Option<&DaySchedule>, EntryScore); 3] = [
+        ("construction", data.construction_schedule.as_ref(), |e| e.construction_score),
+        ("research", data.research_schedule.as_ref(), |e| e.research_score),
+        ("troops", data.troops_schedule.as_ref(), |e| e.troops_speedups),
+    ];
+    for (day, schedule, score) in days {
+        let Some(schedule) = schedule else { continue };
+
+        let mut slots: Vec<&u8> = schedule.appointments.keys().collect();
+        slots.sort();
+        for slot in slots {
+            let appt = &schedule.appointments[slot];
+            csv.write_record([
+                day,
+                &slot.to_string(),
+                &slot_to_time(*slot),
+                &appt.player_id,
+                &appt.alliance,
+                &appt.name,
+                &appt.priority_score.to_string(),
+                "assigned",
+                appt.note.as_deref().unwrap_or(""),
+            ])?;
+        }
+        for player_id in &schedule.unassigned {
+            let entry = entries.iter().find(|e| e.player_id == *player_id);
+            csv.write_record([
+                day,
+                "",
+                "",
+                player_id,
+                entry.map(|e| e.alliance.as_str()).unwrap_or(""),
+                entry.map(|e| e.name.as_str()).unwrap_or(""),
+                &entry.map(|e| score(e).to_string()).unwrap_or_default(),
+                "unassigned",
+                "",
+            ])?;
+        }
+    }
+
+    csv.flush()?;
+    Ok(())
+}
+
+/// Writes who moved, was added or was dropped on one day, one line per player
+pub fn write_day_diff<W: Write>(mut out: W, day_name: &str, diff: &DayDiff) -> std::io::Result<()> {
+    if diff.is_empty() {
+        writeln!(out, "{}: no changes", day_name)?;
+        return Ok(());
+    }
+    writeln!(out, "{}: {} moved, {} added, {} dropped", day_name, diff.moved.len(), diff.added.len(), diff.dropped.len())?;
+    for moved in &diff.moved {
+        writeln!(out, "  moved    {:<30} {} -> {}", format_player_name(&moved.alliance, &moved.name), slot_to_time(moved.from_slot), slot_to_time(moved.to_slot))?;
+    }
+    for appt in &diff.added {
+        writeln!(out, "  added    {:<30} {}", format_player_name(&appt.alliance, &appt.name), slot_to_time(appt.slot))?;
+    }
+    for appt in &diff.dropped {
+        writeln!(out, "  dropped  {:<30} (was {})", format_player_name(&appt.alliance, &appt.name), slot_to_time(appt.slot))?;
+    }
+    Ok(())
+}
+
+/// Writes a day schedule in a readable format, for the terminal
+pub fn write_day_schedule<W, F>(mut out: W, day_name: &str, schedule: &DaySchedule, entries: &[AppointmentEntry], get_priority_score: F) -> std::io::Result<()>
 where
+    W: Write,
     F: Fn(&AppointmentEntry) -> u32,
 {
-    println!("\n=== {} Schedule ===", day_name);
-    println!("Total appointments scheduled: {}", schedule.appointments.len());
+    writeln!(out, "\n=== {} Schedule ===", day_name)?;
+    writeln!(out, "Total appointments scheduled: {}", schedule.appointments.len())?;
     
     if !schedule.unassigned.is_empty() {
-        println!("⚠️  Unassigned players ({}):", schedule.unassigned.len());
+        writeln!(out, "⚠️  Unassigned players ({}):", schedule.unassigned.len())?;
         for player_id in &schedule.unassigned {
             if let Some(entry) = entries.iter().find(|e| e.player_id == *player_id) {
                 let formatted_name = format_player_name(&entry.alliance, &entry.name);
                 let priority_score = get_priority_score(entry);
-                println!("  - {} (ID: {}, Priority: {})", formatted_name, player_id, priority_score);
+                writeln!(out, "  - {} (ID: {}, Priority: {})", formatted_name, player_id, priority_score)?;
             }
         }
     }
     
-    println!("\nSchedule by time slot (all 49 slots):");
+    writeln!(out, "\nSchedule by time slot (all 49 slots):")?;
     // Show all slots from 1 to 49
     for slot in 1..=49 {
         let time = slot_to_time(slot);
         if let Some(appt) = schedule.appointments.get(&slot) {
             let formatted_name = format_player_name(&appt.alliance, &appt.name);
-            println!("  Slot {} ({}) -> {} (ID: {}, Priority: {})", 
-                slot, time, formatted_name, appt.player_id, appt.priority_score);
+            writeln!(out, "  Slot {} ({}) -> {} (ID: {}, Priority: {})", 
+                slot, time, formatted_name, appt.player_id, appt.priority_score)?;
         } else {
-            println!("  Slot {} ({}) -> [EMPTY]", slot, time);
+            writeln!(out, "  Slot {} ({}) -> [EMPTY]", slot, time)?;
         }
     }
+    Ok(())
 }
 
@@ -0,0 +1,153 @@
+use std::error::Error;
+use std::fmt;
+use std::process::ExitCode;
+
+/// How `main` prints a failed command on stderr
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ErrorFormat {
+    /// `Error: <message>`
+    Text,
+    /// One JSON object: `{"error":{"kind":..,"code":..,"message":..}}`
+    Json,
+}
+
+/// What went wrong, which decides the exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Anything not covered below
+    Other,
+    /// Bad arguments or nothing to work on (clap uses the same code)
+    Usage,
+    /// A file couldn't be read or written
+    Io,
+    /// An input file (submissions CSV, schedule JSON, config, predetermined slots) is malformed
+    Parse,
+    /// `validate` found violations
+    Validation,
+    /// The predetermined slots can't all be placed
+    Infeasible,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::Usage => 2,
+            ErrorKind::Io => 3,
+            ErrorKind::Parse => 4,
+            ErrorKind::Validation => 5,
+            ErrorKind::Infeasible => 6,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorKind::Other => "other",
+            ErrorKind::Usage => "usage",
+            ErrorKind::Io => "io",
+            ErrorKind::Parse => "parse",
+            ErrorKind::Validation => "validation",
+            ErrorKind::Infeasible => "infeasible",
+        }
+    }
+}
+
+/// An error message with the kind it should be reported as
+#[derive(Debug)]
+pub struct CliError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl CliError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        CliError { kind, message: message.into() }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for CliError {}
+
+/// Prefixes `err` with `context` ("failed to read x.csv"), keeping the kind it classifies as
+pub fn with_context<E: Into<Box<dyn Error>>>(err: E, context: impl fmt::Display) -> CliError {
+    let err = err.into();
+    CliError::new(classify(err.as_ref()), format!("{}: {}", context, err))
+}
+
+/// The kind of any error: tagged [`CliError`]s keep theirs, file and format errors are
+/// recognized by type, plain string errors count as [`ErrorKind::Other`]
+pub fn classify(err: &(dyn Error + 'static)) -> ErrorKind {
+    if let Some(err) = err.downcast_ref::<CliError>() {
+        err.kind
+    } else if err.is::<std::io::Error>() {
+        ErrorKind::Io
+    } else if let Some(err) = err.downcast_ref::<csv::Error>() {
+        if matches!(err.kind(), csv::ErrorKind::Io(_)) { ErrorKind::Io } else { ErrorKind::Parse }
+    } else if let Some(err) = err.downcast_ref::<serde_json::Error>() {
+        if err.is_io() { ErrorKind::Io } else { ErrorKind::Parse }
+    } else if err.is::<toml::de::Error>() {
+        ErrorKind::Parse
+    } else {
+        ErrorKind::Other
+    }
+}
+
+/// Prints a failed command's error on stderr and returns its exit code. A reader that stopped
+/// early (`| head`) isn't an error, so a broken pipe ends the command quietly.
+pub fn report(err: &(dyn Error + 'static), format: ErrorFormat) -> ExitCode {
+    if is_broken_pipe(err) {
+        return ExitCode::SUCCESS;
+    }
+    let kind = classify(err);
+    print_error(kind, &err.to_string(), format);
+    ExitCode::from(kind.exit_code())
+}
+
+fn is_broken_pipe(err: &(dyn Error + 'static)) -> bool {
+    let kind = if let Some(err) = err.downcast_ref::<std::io::Error>() {
+        Some(err.kind())
+    } else if let Some(err) = err.downcast_ref::<serde_json::Error>() {
+        err.io_error_kind()
+    } else if let Some(csv::ErrorKind::Io(err)) = err.downcast_ref::<csv::Error>().map(csv::Error::kind) {
+        Some(err.kind())
+    } else {
+        None
+    };
+    kind == Some(std::io::ErrorKind::BrokenPipe)
+}
+
+/// Reports an argument error from clap. `--help` and `--version` print and exit as usual.
+pub fn report_clap(err: clap::Error) -> ExitCode {
+    if !err.use_stderr() || requested_format() == ErrorFormat::Text {
+        err.exit();
+    }
+    // Only the first line; the rest is the usage hint
+    let message = err.to_string();
+    let message = message.lines().next().unwrap_or_default().trim_start_matches("error: ");
+    print_error(ErrorKind::Usage, message, ErrorFormat::Json);
+    ExitCode::from(ErrorKind::Usage.exit_code())
+}
+
+fn print_error(kind: ErrorKind, message: &str, format: ErrorFormat) {
+    match format {
+        ErrorFormat::Text => eprintln!("Error: {}", message),
+        ErrorFormat::Json => eprintln!(
+            "{}",
+            serde_json::json!({ "error": { "kind": kind.name(), "code": kind.exit_code(), "message": message } })
+        ),
+    }
+}
+
+/// `--error-format` straight from the arguments, for errors clap hits before it parsed them
+fn requested_format() -> ErrorFormat {
+    let args: Vec<String> = std::env::args_os().map(|arg| arg.to_string_lossy().into_owned()).collect();
+    let json = args.iter().enumerate().any(|(i, arg)| {
+        arg == "--error-format=json" || (arg == "--error-format" && args.get(i + 1).is_some_and(|next| next == "json"))
+    });
+    if json { ErrorFormat::Json } else { ErrorFormat::Text }
+}
@@ -0,0 +1,65 @@
+use pulldown_cmark::{html, Options, Parser};
+use serde::{Deserialize, Serialize};
+
+/// Longest accepted text of one content field, in bytes
+pub const MAX_CONTENT_TEXT: usize = 20_000;
+
+/// Texts an admin writes for a form, in markdown
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormContent {
+    pub intro: Option<String>, // Shown at the top of the form instead of the standard introduction
+    pub construction: Option<String>, // Shown under each day's heading, e.g. what counts as speedups that day
+    pub research: Option<String>,
+    pub troops: Option<String>,
+}
+
+impl FormContent {
+    /// Drops empty texts so they don't show as empty boxes
+    pub fn trimmed(self) -> FormContent {
+        let keep = |text: Option<String>| text.filter(|t| !t.trim().is_empty());
+        FormContent {
+            intro: keep(self.intro),
+            construction: keep(self.construction),
+            research: keep(self.research),
+            troops: keep(self.troops),
+        }
+    }
+
+    /// The same content rendered to sanitized HTML
+    pub fn to_html(&self) -> FormContent {
+        let render = |text: &Option<String>| text.as_deref().map(render_markdown);
+        FormContent {
+            intro: render(&self.intro),
+            construction: render(&self.construction),
+            research: render(&self.research),
+            troops: render(&self.troops),
+        }
+    }
+
+    /// Checks that every text is within [`MAX_CONTENT_TEXT`]
+    pub fn validate(&self) -> Result<(), String> {
+        let fields = [("introduction", &self.intro), ("Construction day", &self.construction), ("Research day", &self.research), ("Troops Training day", &self.troops)];
+        for (label, text) in fields {
+            if text.as_ref().is_some_and(|t| t.len() > MAX_CONTENT_TEXT) {
+                return Err(format!("The {} text is longer than {} characters", label, MAX_CONTENT_TEXT));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Renders markdown to HTML that is safe to put on a public page: raw HTML, scripts and
+/// `javascript:` links are removed, links open in a new tab.
+pub fn render_markdown(markdown: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, Parser::new_ext(markdown, options));
+    ammonia::Builder::default()
+        .link_rel(Some("noopener noreferrer nofollow"))
+        .set_tag_attribute_value("a", "target", "_blank")
+        .clean(&unsafe_html)
+        .to_string()
+}
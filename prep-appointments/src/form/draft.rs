@@ -0,0 +1,110 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Drafts not saved again within this many days are discarded
+pub const DRAFT_MAX_AGE_DAYS: i64 = 30;
+/// Largest accepted draft, as serialized JSON
+pub const MAX_DRAFT_BYTES: usize = 16 * 1024;
+/// Most drafts kept per form
+pub const MAX_DRAFTS_PER_FORM: usize = 1000;
+
+const DRAFT_TOKEN_LENGTH: usize = 24;
+
+/// A partially filled form submission, saved so the player can resume it later.
+/// Every field may still be empty; nothing is validated until the final submit.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormDraft {
+    pub alliance: String,
+    pub custom_alliance: String,
+    pub character_name: String,
+    pub player_id: String,
+    pub submission_type: String,
+    pub wants_construction: bool,
+    pub construction_speedups: Option<u32>,
+    pub construction_truegold: Option<u32>,
+    pub construction_time_slots: Vec<u8>,
+    pub wants_research: bool,
+    pub research_speedups: Option<u32>,
+    pub research_truegold_dust: Option<u32>,
+    pub research_time_slots: Vec<u8>,
+    pub wants_troops: bool,
+    pub troops_speedups: Option<u32>,
+    pub troops_time_slots: Vec<u8>,
+    pub additional_notes: String,
+    pub suggestions: String,
+    pub saved_at: String, // RFC 3339, set by the server
+}
+
+/// Generates a new random draft token
+pub fn generate_draft_token() -> String {
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    let mut rng = rand::thread_rng();
+    (0..DRAFT_TOKEN_LENGTH)
+        .map(|_| CHARSET[rng.gen_range(0..CHARSET.len())] as char)
+        .collect()
+}
+
+/// Whether a token has the shape of a generated one (and is safe to use as a file name)
+pub fn is_valid_draft_token(token: &str) -> bool {
+    token.len() == DRAFT_TOKEN_LENGTH && token.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Directory holding the drafts of one form
+pub fn drafts_dir(data_dir: &str, form_code: &str) -> PathBuf {
+    Path::new(data_dir).join("drafts").join(form_code)
+}
+
+fn draft_path(data_dir: &str, form_code: &str, token: &str) -> PathBuf {
+    drafts_dir(data_dir, form_code).join(format!("{}.json", token))
+}
+
+/// Loads a draft. Expired drafts are removed and reported as missing.
+pub fn load_draft(data_dir: &str, form_code: &str, token: &str) -> Option<FormDraft> {
+    let path = draft_path(data_dir, form_code, token);
+    let draft: FormDraft = serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+    let expired = chrono::DateTime::parse_from_rfc3339(&draft.saved_at)
+        .map(|saved| chrono::Local::now().signed_duration_since(saved) > chrono::Duration::days(DRAFT_MAX_AGE_DAYS))
+        .unwrap_or(true);
+    if expired {
+        std::fs::remove_file(&path).ok();
+        return None;
+    }
+    Some(draft)
+}
+
+/// Saves a draft, stamping it with the current time
+pub fn save_draft(data_dir: &str, form_code: &str, token: &str, draft: &mut FormDraft) -> std::io::Result<()> {
+    draft.saved_at = chrono::Local::now().to_rfc3339();
+    std::fs::create_dir_all(drafts_dir(data_dir, form_code))?;
+    std::fs::write(draft_path(data_dir, form_code, token), serde_json::to_string(draft)?)
+}
+
+/// Removes a draft, typically once the submission it belongs to was sent
+pub fn delete_draft(data_dir: &str, form_code: &str, token: &str) {
+    std::fs::remove_file(draft_path(data_dir, form_code, token)).ok();
+}
+
+/// Unexpired drafts of any form with the given player ID, as (form code, token, draft)
+pub fn find_player_drafts(data_dir: &str, player_id: &str) -> Vec<(String, String, FormDraft)> {
+    let mut found = Vec::new();
+    let forms = std::fs::read_dir(Path::new(data_dir).join("drafts")).into_iter().flatten().flatten();
+    for form_dir in forms {
+        let form_code = form_dir.file_name().to_string_lossy().into_owned();
+        for file in std::fs::read_dir(form_dir.path()).into_iter().flatten().flatten() {
+            let Some(token) = file.path().file_stem().map(|t| t.to_string_lossy().into_owned()) else { continue };
+            if let Some(draft) = load_draft(data_dir, &form_code, &token).filter(|d| d.player_id.trim() == player_id) {
+                found.push((form_code.clone(), token, draft));
+            }
+        }
+    }
+    found
+}
+
+/// Number of drafts stored for a form
+pub fn count_drafts(data_dir: &str, form_code: &str) -> usize {
+    std::fs::read_dir(drafts_dir(data_dir, form_code))
+        .map(|entries| entries.flatten().count())
+        .unwrap_or(0)
+}
@@ -0,0 +1,210 @@
+use csv::{ReaderBuilder, StringRecord};
+use kingshot_core::parser::SubmissionColumns;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// New submissions with the same answers this many minutes apart (or less) are flagged
+pub const SAME_ANSWERS_WINDOW_MINUTES: i64 = 10;
+
+/// Review decision keeping both submissions of a pair; any other decision is the player ID kept
+pub const KEEP_BOTH: &str = "both";
+
+/// Review decisions of a form's flagged pairs, by [`DuplicateFlag::id`]
+pub type DuplicateReviews = BTreeMap<String, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateReason {
+    /// Same character name, different player IDs
+    SameName,
+    /// Same availability and speedups, submitted minutes apart
+    SameAnswers,
+}
+
+/// Two new submissions with different player IDs that look like the same player.
+/// Until an admin reviews the pair, the later one is left out of scheduling.
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateFlag {
+    pub id: String, // "{first}:{second}" player IDs
+    pub reason: DuplicateReason,
+    pub first: FlaggedSubmission,
+    pub second: FlaggedSubmission, // The later submission
+    pub decision: Option<String>, // KEEP_BOTH or the player ID kept, None until reviewed
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FlaggedSubmission {
+    pub player_id: String,
+    pub name: String,
+    pub alliance: String,
+    pub timestamp: String, // As in the CSV row
+}
+
+impl DuplicateFlag {
+    /// Player ID this pair keeps out of scheduling, if any
+    pub fn held(&self) -> Option<&str> {
+        match self.decision.as_deref() {
+            None => Some(&self.second.player_id),
+            Some(KEEP_BOTH) => None,
+            Some(kept) if kept == self.first.player_id => Some(&self.second.player_id),
+            Some(_) => Some(&self.first.player_id),
+        }
+    }
+}
+
+/// File next to a form's submissions CSV holding the review decisions of its flagged pairs
+pub fn reviews_path(data_dir: &str, form_code: &str) -> PathBuf {
+    Path::new(data_dir).join("current_forms").join(format!("{}_duplicates.json", form_code))
+}
+
+pub fn load_reviews(data_dir: &str, form_code: &str) -> DuplicateReviews {
+    std::fs::read_to_string(reviews_path(data_dir, form_code))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_reviews(data_dir: &str, form_code: &str, reviews: &DuplicateReviews) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(reviews)?;
+    std::fs::write(reviews_path(data_dir, form_code), json)
+}
+
+/// Removes the review decisions of a form, when it is archived
+pub fn delete_reviews(data_dir: &str, form_code: &str) {
+    std::fs::remove_file(reviews_path(data_dir, form_code)).ok();
+}
+
+/// Whether a review decision involves the player, as one of the pair or the one kept
+fn mentions_player(flag_id: &str, decision: &str, player_id: &str) -> bool {
+    decision == player_id || flag_id.split(':').any(|id| id == player_id)
+}
+
+/// Review decisions of every form that involve the player, by form code
+pub fn find_player_reviews(data_dir: &str, player_id: &str) -> Vec<(String, DuplicateReviews)> {
+    let mut found = Vec::new();
+    for file in std::fs::read_dir(Path::new(data_dir).join("current_forms")).into_iter().flatten().flatten() {
+        let file_name = file.file_name().to_string_lossy().into_owned();
+        let Some(form_code) = file_name.strip_suffix("_duplicates.json") else { continue };
+        let reviews: DuplicateReviews = load_reviews(data_dir, form_code).into_iter()
+            .filter(|(flag_id, decision)| mentions_player(flag_id, decision, player_id))
+            .collect();
+        if !reviews.is_empty() {
+            found.push((form_code.to_string(), reviews));
+        }
+    }
+    found
+}
+
+/// Removes the review decisions involving the player from every form; returns how many were removed
+pub fn remove_player_reviews(data_dir: &str, player_id: &str) -> std::io::Result<usize> {
+    let mut removed = 0;
+    for (form_code, player_reviews) in find_player_reviews(data_dir, player_id) {
+        let mut reviews = load_reviews(data_dir, &form_code);
+        reviews.retain(|flag_id, _| !player_reviews.contains_key(flag_id));
+        save_reviews(data_dir, &form_code, &reviews)?;
+        removed += player_reviews.len();
+    }
+    Ok(removed)
+}
+
+// A player's latest new submission, with the answers compared for SameAnswers
+struct NewSubmission {
+    submission: FlaggedSubmission,
+    time: Option<chrono::NaiveDateTime>,
+    answers: Vec<String>,
+    wants_any: bool,
+}
+
+fn read_new_submissions(csv_path: &Path) -> Result<Vec<NewSubmission>, Box<dyn std::error::Error>> {
+    let mut reader = ReaderBuilder::new().flexible(true).from_path(csv_path)?;
+    let headers = reader.headers()?.clone();
+    let columns = SubmissionColumns::find(&headers);
+    let field = |record: &StringRecord, col: usize| record.get(col).unwrap_or("").trim().to_string();
+
+    // Keyed by player ID in first submission order, so a player's own new submissions aren't compared
+    let mut order: Vec<String> = Vec::new();
+    let mut latest: HashMap<String, NewSubmission> = HashMap::new();
+    for result in reader.records() {
+        let record = result?;
+        let player_id = field(&record, columns.id);
+        let name = field(&record, columns.name);
+        let submission_type = field(&record, columns.submission_type).to_lowercase();
+        if player_id.is_empty() || name.is_empty() || submission_type.contains("re-submission") || submission_type.contains("resubmission") {
+            continue;
+        }
+        let timestamp = field(&record, 0);
+        let answers: Vec<String> = [
+            columns.construction_want, columns.construction_speedups, columns.construction_truegold, columns.construction_times,
+            columns.research_want, columns.research_speedups, columns.research_truegold_dust, columns.research_times,
+            columns.troops_want, columns.troops_speedups, columns.troops_times,
+        ].iter().map(|col| field(&record, *col).to_lowercase()).collect();
+        let wants_any = [columns.construction_want, columns.research_want, columns.troops_want].iter()
+            .any(|col| matches!(field(&record, *col).to_lowercase().as_str(), "yes" | "true" | "1"));
+        if !latest.contains_key(&player_id) {
+            order.push(player_id.clone());
+        }
+        latest.insert(player_id.clone(), NewSubmission {
+            time: chrono::NaiveDateTime::parse_from_str(&timestamp, "%d/%m/%Y %H.%M.%S").ok(),
+            submission: FlaggedSubmission { player_id, name, alliance: field(&record, columns.alliance), timestamp },
+            answers,
+            wants_any,
+        });
+    }
+    Ok(order.iter().filter_map(|id| latest.remove(id)).collect())
+}
+
+/// Flags pairs of new submissions from different player IDs that share a character name
+/// (ignoring case), or that have the same answers and were submitted at most
+/// [`SAME_ANSWERS_WINDOW_MINUTES`] apart. Each pair is flagged once, in submission order.
+pub fn find_duplicates(csv_path: &Path, reviews: &DuplicateReviews) -> Result<Vec<DuplicateFlag>, Box<dyn std::error::Error>> {
+    let submissions = read_new_submissions(csv_path)?;
+    let mut by_name: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut by_answers: HashMap<&[String], Vec<usize>> = HashMap::new();
+    for (i, s) in submissions.iter().enumerate() {
+        by_name.entry(s.submission.name.to_lowercase()).or_default().push(i);
+        if s.wants_any {
+            by_answers.entry(&s.answers).or_default().push(i);
+        }
+    }
+
+    let mut pairs: Vec<(usize, usize, DuplicateReason)> = Vec::new();
+    let mut seen: HashSet<(usize, usize)> = HashSet::new();
+    for group in by_name.values() {
+        for (n, &a) in group.iter().enumerate() {
+            for &b in &group[n + 1..] {
+                if seen.insert((a, b)) {
+                    pairs.push((a, b, DuplicateReason::SameName));
+                }
+            }
+        }
+    }
+    let window = chrono::Duration::minutes(SAME_ANSWERS_WINDOW_MINUTES);
+    for group in by_answers.values() {
+        for (n, &a) in group.iter().enumerate() {
+            for &b in &group[n + 1..] {
+                let close = match (submissions[a].time, submissions[b].time) {
+                    (Some(ta), Some(tb)) => (tb - ta).abs() <= window,
+                    _ => false,
+                };
+                if close && seen.insert((a, b)) {
+                    pairs.push((a, b, DuplicateReason::SameAnswers));
+                }
+            }
+        }
+    }
+    pairs.sort_by_key(|(a, b, _)| (*b, *a));
+
+    Ok(pairs.into_iter().map(|(a, b, reason)| {
+        let first = submissions[a].submission.clone();
+        let second = submissions[b].submission.clone();
+        let id = format!("{}:{}", first.player_id, second.player_id);
+        let decision = reviews.get(&id).cloned();
+        DuplicateFlag { id, reason, first, second, decision }
+    }).collect())
+}
+
+/// Player IDs the flagged pairs keep out of scheduling
+pub fn held_player_ids(flags: &[DuplicateFlag]) -> HashSet<String> {
+    flags.iter().filter_map(|flag| flag.held()).map(str::to_string).collect()
+}
@@ -1,23 +1,30 @@
 use crate::form::submission::FormSubmission;
-use crate::schedule::calculate_time_slots;
+use kingshot_core::schedule::DayTimeConfig;
 use std::path::Path;
 use csv::WriterBuilder;
 use std::fs::OpenOptions;
 
+/// Header of submission CSVs: the questions of the original Google Form, in its column order,
+/// after the timestamp
+pub const SUBMISSION_CSV_HEADER: &str = "timestamp,What alliance do you belong to? ,\"If chosen \"\"Non of the above\"\" please type it here\",\"What is your character name? \n(Note: Copy your name from your profile and paste it in the answer below.) \n(Pro Tip: Please do not change your character name after filling this form and before Friday of SvS preparation week.)\n\",\"What is your player ID?\n(Note: Your ID must be a number)\n\",Is this form a...,Do you want a Construction day appointment?,\"How many hours of speedups do you plan to use on Construction day? \n(Note: Your response must be in hours. Add together amount of general speedup hours and construction speedup hours you are planning to use on this day.)\n\",How much truegold do you plan too spend?,\"What times are you available for your Construction day appointment? (UTC time)\n(Note: Choose a minimum of 5 times.)\n\",Do you want a Research day appointment?,\"How many hours of speedups do you plan to use on Research day? \n(Note: Your response must be in hours. Add together amount of general speedup hours and research speedup hours you are planning to use during this day.)\n\",How much truegold dust do you plan to spend?,\"What times are you available for your Research day appointment? (UTC time)\n(Note: Choose a minimum of 5 times.)\n\",Do you want a Troops Training day appointment?,\"How many hours of speedups do you plan to use on Troops Training day? \n(Note: Your response must be in hours. Add together amount of general speedup hours and troops training speedup hours you are planning to use during this day.)\n\",\"What times are you available for your Troops Training day appointment? (UTC time)\n(Note: Choose a minimum of 5 times.)\n\",\"Please share any additional notes, clarifications, or comments about your responses on this form.\",What suggestions do you have for improving our state? We value your feedback!";
+
+/// Number of columns of a submission CSV row
+pub const SUBMISSION_CSV_COLUMNS: usize = 19;
+
 /// Exports a single form submission to CSV format compatible with the existing parser
 /// 
 /// # Arguments
 /// * `submission` - The form submission data
 /// * `csv_path` - Path to the CSV file
-/// * `construction_times` - Tuple of (start_time, end_time) for construction day
-/// * `research_times` - Tuple of (start_time, end_time) for research day
-/// * `troops_times` - Tuple of (start_time, end_time) for troops day
+/// * `construction_times` - Time window(s) of construction day
+/// * `research_times` - Time window(s) of research day
+/// * `troops_times` - Time window(s) of troops day
 pub fn export_submission_to_csv(
     submission: &FormSubmission,
     csv_path: &Path,
-    construction_times: (&str, Option<&str>),
-    research_times: (&str, Option<&str>),
-    troops_times: (&str, Option<&str>),
+    construction_times: &DayTimeConfig,
+    research_times: &DayTimeConfig,
+    troops_times: &DayTimeConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let file_exists = csv_path.exists();
     
@@ -35,7 +42,7 @@ pub fn export_submission_to_csv(
         use std::fs::File;
         use std::io::Write;
         let mut header_file = File::create(csv_path)?;
-        writeln!(header_file, "timestamp,What alliance do you belong to? ,\"If chosen \"\"Non of the above\"\" please type it here\",\"What is your character name? \n(Note: Copy your name from your profile and paste it in the answer below.) \n(Pro Tip: Please do not change your character name after filling this form and before Friday of SvS preparation week.)\n\",\"What is your player ID?\n(Note: Your ID must be a number)\n\",Is this form a...,Do you want a Construction day appointment?,\"How many hours of speedups do you plan to use on Construction day? \n(Note: Your response must be in hours. Add together amount of general speedup hours and construction speedup hours you are planning to use on this day.)\n\",How much truegold do you plan too spend?,\"What times are you available for your Construction day appointment? (UTC time)\n(Note: Choose a minimum of 5 times.)\n\",Do you want a Research day appointment?,\"How many hours of speedups do you plan to use on Research day? \n(Note: Your response must be in hours. Add together amount of general speedup hours and research speedup hours you are planning to use during this day.)\n\",How much truegold dust do you plan to spend?,\"What times are you available for your Research day appointment? (UTC time)\n(Note: Choose a minimum of 5 times.)\n\",Do you want a Troops Training day appointment?,\"How many hours of speedups do you plan to use on Troops Training day? \n(Note: Your response must be in hours. Add together amount of general speedup hours and troops training speedup hours you are planning to use during this day.)\n\",\"What times are you available for your Troops Training day appointment? (UTC time)\n(Note: Choose a minimum of 5 times.)\n\",\"Please share any additional notes, clarifications, or comments about your responses on this form.\",What suggestions do you have for improving our state? We value your feedback!")?;
+        writeln!(header_file, "{}", SUBMISSION_CSV_HEADER)?;
         drop(header_file);
     }
     
@@ -49,9 +56,9 @@ pub fn export_submission_to_csv(
         .from_writer(file);
     
     // Generate time slots for each day type based on form configuration
-    let construction_slots = calculate_time_slots(construction_times.0, construction_times.1);
-    let research_slots = calculate_time_slots(research_times.0, research_times.1);
-    let troops_slots = calculate_time_slots(troops_times.0, troops_times.1);
+    let construction_slots = construction_times.time_slots();
+    let research_slots = research_times.time_slots();
+    let troops_slots = troops_times.time_slots();
     
     // Convert slot numbers to actual time strings from form configuration
     let construction_time_strings: Vec<String> = submission.construction_time_slots.iter()
@@ -103,7 +110,7 @@ pub fn export_submission_to_csv(
     let additional_notes_str = submission.additional_notes.clone().unwrap_or_default();
     let suggestions_str = submission.suggestions.clone().unwrap_or_default();
     
-    wtr.write_record(&[
+    wtr.write_record([
         &submission.timestamp,
         &alliance_value,
         &custom_alliance,
@@ -128,3 +135,26 @@ pub fn export_submission_to_csv(
     wtr.flush()?;
     Ok(())
 }
+
+/// Appends rows already in the submission CSV column order (see [`SUBMISSION_CSV_HEADER`]),
+/// writing the header first if the file is new
+pub fn append_submission_rows(csv_path: &Path, rows: &[Vec<String>]) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+    let file_exists = csv_path.exists();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(csv_path)?;
+    if !file_exists {
+        writeln!(file, "{}", SUBMISSION_CSV_HEADER)?;
+    }
+    
+    let mut wtr = WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(file);
+    for row in rows {
+        wtr.write_record(row)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
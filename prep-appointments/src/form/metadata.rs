@@ -0,0 +1,112 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Longest user agent kept; longer ones are cut
+const MAX_USER_AGENT_CHARS: usize = 300;
+
+/// Request details recorded with a submission, for spotting spam and duplicate submitters.
+/// Only written for accounts that turned it on, and never shown outside the owner's
+/// submissions list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmissionMetadata {
+    pub timestamp: String, // Same as the submission's CSV row
+    pub player_id: String,
+    pub ip_hash: Option<String>, // Salted per account, so it can't be matched across accounts
+    pub user_agent: Option<String>,
+    pub form_version: String, // See form_version
+}
+
+/// File next to a form's submissions CSV holding the metadata of its submissions, one JSON per line
+pub fn metadata_path(data_dir: &str, form_code: &str) -> PathBuf {
+    Path::new(data_dir).join("current_forms").join(format!("{}_metadata.jsonl", form_code))
+}
+
+/// New random salt for an account's IP hashes
+pub fn generate_salt() -> String {
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    let mut rng = rand::thread_rng();
+    (0..32)
+        .map(|_| CHARSET[rng.gen_range(0..CHARSET.len())] as char)
+        .collect()
+}
+
+/// Hex SHA-256 of the client address with the account's salt
+pub fn hash_ip(ip: &str, salt: &str) -> String {
+    let digest = Sha256::digest(format!("{}:{}", salt, ip).as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Short fingerprint of the form configuration a submission was made against; it changes
+/// whenever the form is edited
+pub fn form_version<T: Serialize>(config: &T) -> String {
+    let json = serde_json::to_string(config).unwrap_or_default();
+    Sha256::digest(json.as_bytes()).iter().take(4).map(|b| format!("{:02x}", b)).collect()
+}
+
+/// User agent as stored: trimmed and cut to a sane length
+pub fn clean_user_agent(user_agent: &str) -> Option<String> {
+    let user_agent = user_agent.trim();
+    (!user_agent.is_empty()).then(|| user_agent.chars().take(MAX_USER_AGENT_CHARS).collect())
+}
+
+pub fn append_metadata(data_dir: &str, form_code: &str, metadata: &SubmissionMetadata) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(metadata_path(data_dir, form_code))?;
+    writeln!(file, "{}", serde_json::to_string(metadata)?)
+}
+
+/// Metadata of a form's submissions by (timestamp, player ID); lines that don't parse are skipped
+pub fn load_metadata(data_dir: &str, form_code: &str) -> HashMap<(String, String), SubmissionMetadata> {
+    std::fs::read_to_string(metadata_path(data_dir, form_code))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<SubmissionMetadata>(line).ok())
+        .map(|m| ((m.timestamp.clone(), m.player_id.clone()), m))
+        .collect()
+}
+
+/// Metadata of the player's submissions to every form, by form code
+pub fn find_player_metadata(data_dir: &str, player_id: &str) -> Vec<(String, Vec<SubmissionMetadata>)> {
+    let mut found = Vec::new();
+    for file in std::fs::read_dir(Path::new(data_dir).join("current_forms")).into_iter().flatten().flatten() {
+        let file_name = file.file_name().to_string_lossy().into_owned();
+        let Some(form_code) = file_name.strip_suffix("_metadata.jsonl") else { continue };
+        let mut entries: Vec<SubmissionMetadata> = load_metadata(data_dir, form_code).into_values()
+            .filter(|m| m.player_id == player_id)
+            .collect();
+        if !entries.is_empty() {
+            entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+            found.push((form_code.to_string(), entries));
+        }
+    }
+    found
+}
+
+/// Removes the metadata of one player's submissions to a form; returns how many were removed
+pub fn remove_player_metadata(data_dir: &str, form_code: &str, player_id: &str) -> std::io::Result<usize> {
+    let path = metadata_path(data_dir, form_code);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let (removed, kept): (Vec<&str>, Vec<&str>) = content.lines().partition(|line| {
+        serde_json::from_str::<SubmissionMetadata>(line).is_ok_and(|m| m.player_id == player_id)
+    });
+    if !removed.is_empty() {
+        let kept: String = kept.iter().map(|line| format!("{}\n", line)).collect();
+        std::fs::write(&path, kept)?;
+    }
+    Ok(removed.len())
+}
+
+/// Removes all metadata of a form, once it is archived
+pub fn delete_metadata(data_dir: &str, form_code: &str) {
+    std::fs::remove_file(metadata_path(data_dir, form_code)).ok();
+}
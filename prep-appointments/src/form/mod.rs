@@ -1,5 +1,13 @@
 pub mod submission;
 pub mod export;
+pub mod privacy;
+pub mod draft;
+pub mod content;
+pub mod metadata;
+pub mod duplicates;
 
-pub use submission::{FormSubmission, FormSubmissionRequest, validate_submission};
-pub use export::export_submission_to_csv;
+pub use submission::{FormSubmission, FormSubmissionRequest, validate_blackout_slots, validate_passcode, validate_submission};
+pub use export::{append_submission_rows, export_submission_to_csv, SUBMISSION_CSV_COLUMNS};
+pub use privacy::{find_player_rows, merge_player_rows, remove_player_rows, rename_alliance_rows};
+pub use draft::FormDraft;
+pub use content::FormContent;
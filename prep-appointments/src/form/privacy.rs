@@ -0,0 +1,170 @@
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use std::path::Path;
+
+/// Finds the player ID column of a submissions CSV.
+/// Returns None for CSVs that don't contain player IDs (so they are never modified).
+fn player_id_column(headers: &StringRecord) -> Option<usize> {
+    headers.iter().position(|h| h.contains("player ID"))
+}
+
+/// Returns every row of a submissions CSV that belongs to the given player, as header -> value maps
+pub fn find_player_rows(
+    csv_path: &Path,
+    player_id: &str,
+) -> Result<Vec<serde_json::Map<String, serde_json::Value>>, Box<dyn std::error::Error>> {
+    let mut reader = ReaderBuilder::new().flexible(true).from_path(csv_path)?;
+    let headers = reader.headers()?.clone();
+    let id_col = match player_id_column(&headers) {
+        Some(col) => col,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut rows = Vec::new();
+    for result in reader.records() {
+        let record = result?;
+        if record.get(id_col).map(|id| id.trim()) != Some(player_id) {
+            continue;
+        }
+
+        let mut row = serde_json::Map::new();
+        for (i, field) in record.iter().enumerate() {
+            let header = headers.get(i)
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| format!("field_{}", i));
+            row.insert(header, serde_json::Value::String(field.to_string()));
+        }
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Removes every row belonging to the given player from a submissions CSV.
+/// The file is only rewritten when rows were removed. Returns the number of removed rows.
+pub fn remove_player_rows(csv_path: &Path, player_id: &str) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut reader = ReaderBuilder::new().flexible(true).from_path(csv_path)?;
+    let headers = reader.headers()?.clone();
+    let id_col = match player_id_column(&headers) {
+        Some(col) => col,
+        None => return Ok(0),
+    };
+
+    let mut kept = Vec::new();
+    let mut removed = 0;
+    for result in reader.records() {
+        let record = result?;
+        if record.get(id_col).map(|id| id.trim()) == Some(player_id) {
+            removed += 1;
+        } else {
+            kept.push(record);
+        }
+    }
+    drop(reader);
+
+    if removed == 0 {
+        return Ok(0);
+    }
+    write_rows(csv_path, &headers, &kept)?;
+
+    Ok(removed)
+}
+
+/// Moves the rows of player `from` to player `into` in a submissions CSV. When both players have
+/// rows, only those of `keep` (`from` or `into`) stay. The file is only rewritten when rows changed.
+/// Returns the number of moved or dropped rows.
+pub fn merge_player_rows(csv_path: &Path, from: &str, into: &str, keep: &str) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut reader = ReaderBuilder::new().flexible(true).from_path(csv_path)?;
+    let headers = reader.headers()?.clone();
+    let id_col = match player_id_column(&headers) {
+        Some(col) => col,
+        None => return Ok(0),
+    };
+    let records = reader.records().collect::<Result<Vec<StringRecord>, _>>()?;
+    drop(reader);
+
+    let has_rows = |id: &str| records.iter().any(|r| r.get(id_col).map(|c| c.trim()) == Some(id));
+    let winner = match (has_rows(from), has_rows(into)) {
+        (false, _) => return Ok(0),
+        (true, true) => keep,
+        (true, false) => from,
+    };
+    let mut kept = Vec::with_capacity(records.len());
+    let mut changed = 0;
+    for record in records {
+        let id = record.get(id_col).map(|c| c.trim()).unwrap_or_default();
+        if id != from && id != into {
+            kept.push(record);
+        } else if id != winner {
+            changed += 1;
+        } else if id == from {
+            let moved: StringRecord = record.iter()
+                .enumerate()
+                .map(|(i, field)| if i == id_col { into } else { field })
+                .collect();
+            kept.push(moved);
+            changed += 1;
+        } else {
+            kept.push(record);
+        }
+    }
+    write_rows(csv_path, &headers, &kept)?;
+
+    Ok(changed)
+}
+
+/// A submissions CSV with renamed rows, not yet written back
+pub struct RenamedRows {
+    pub renamed: usize,
+    pub content: Vec<u8>,
+}
+
+/// Renames an alliance in a submissions CSV, in the alliance column and in the tag typed in for
+/// "Non of the above". Returns None when no row uses the alliance. The file itself is left for
+/// the caller to replace.
+pub fn rename_alliance_rows(csv_path: &Path, from: &str, into: &str) -> Result<Option<RenamedRows>, Box<dyn std::error::Error>> {
+    let mut reader = ReaderBuilder::new().flexible(true).from_path(csv_path)?;
+    let headers = reader.headers()?.clone();
+    let Some(alliance_col) = headers.iter().position(|h| h.contains("alliance")) else {
+        return Ok(None);
+    };
+    let custom_col = headers.iter().position(|h| h.contains("Non of the above") && h.contains("type it here"));
+
+    let mut rows = Vec::new();
+    let mut renamed = 0;
+    for result in reader.records() {
+        let record = result?;
+        let rename = |i: usize| (i == alliance_col || Some(i) == custom_col) && record.get(i).map(|c| c.trim()) == Some(from);
+        if (0..record.len()).any(rename) {
+            renamed += 1;
+            rows.push(record.iter().enumerate().map(|(i, field)| if rename(i) { into } else { field }).collect());
+        } else {
+            rows.push(record);
+        }
+    }
+    if renamed == 0 {
+        return Ok(None);
+    }
+
+    let mut writer = WriterBuilder::new().flexible(true).from_writer(Vec::new());
+    writer.write_record(&headers)?;
+    for record in &rows {
+        writer.write_record(record)?;
+    }
+    let content = writer.into_inner().map_err(|e| e.into_error())?;
+    Ok(Some(RenamedRows { renamed, content }))
+}
+
+/// Replaces the rows of a submissions CSV
+fn write_rows(csv_path: &Path, headers: &StringRecord, rows: &[StringRecord]) -> Result<(), Box<dyn std::error::Error>> {
+    // Write to a temporary file first so a failure can't leave a truncated CSV behind
+    let tmp_path = csv_path.with_extension("csv.tmp");
+    {
+        let mut writer = WriterBuilder::new().flexible(true).from_path(&tmp_path)?;
+        writer.write_record(headers)?;
+        for record in rows {
+            writer.write_record(record)?;
+        }
+        writer.flush()?;
+    }
+    std::fs::rename(&tmp_path, csv_path)?;
+    Ok(())
+}
@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 
 /// Form submission data structure matching the form fields
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,45 +47,107 @@ pub struct FormSubmissionRequest {
     pub troops_time_slots: Vec<u8>,
     pub additional_notes: Option<String>,
     pub suggestions: Option<String>,
+    #[serde(default)]
+    pub draft_token: Option<String>, // Draft to discard once the submission is saved
+    #[serde(default)]
+    pub language: Option<String>, // Language the form was filled in, for error messages
+    #[serde(default)]
+    pub passcode: Option<String>, // Alliance or form passcode, when the form asks for one
+}
+
+/// A rejected submission. `key` is the text ID the form page translates it with;
+/// `{name}` placeholders in the text are filled from `params`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pub key: &'static str,
+    pub params: Vec<(&'static str, String)>,
+}
+
+impl ValidationError {
+    fn new(key: &'static str) -> Self {
+        ValidationError { key, params: Vec::new() }
+    }
+
+    fn with_param(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.params.push((name, value.to_string()));
+        self
+    }
+
+    fn english(&self) -> &'static str {
+        match self.key {
+            "pleaseEnterCharacterName" => "Character name is required",
+            "playerIdRequired" => "Player ID is required",
+            "playerIdMustBeNumber" => "Player ID must contain only digits",
+            "invalidSubmissionType" => "Invalid submission type",
+            "invalidPasscode" => "The passcode is wrong. Ask your alliance leadership for it.",
+            "pleaseSelectAlliance" => "Alliance selection is required",
+            "pleaseEnterCustomAlliance" => "Custom alliance name is required when 'Non of the above' is selected",
+            "pleaseSelectConstructionTimeSlots" => "Construction day requires at least 5 time slots",
+            "invalidConstructionTimeSlot" => "Invalid construction time slot: {slot}",
+            "pleaseSelectResearchTimeSlots" => "Research day requires at least 5 time slots",
+            "invalidResearchTimeSlot" => "Invalid research time slot: {slot}",
+            "pleaseSelectTroopsTimeSlots" => "Troops Training day requires at least 5 time slots",
+            "invalidTroopsTimeSlot" => "Invalid troops time slot: {slot}",
+            "pleaseSelectAtLeastOneDay" => "At least one day type (Construction, Research, or Troops) must be selected",
+            _ => "Invalid submission",
+        }
+    }
+
+    /// The message in a form language pack's wording when it has one for this error, English otherwise
+    pub fn message(&self, strings: Option<&BTreeMap<String, String>>) -> String {
+        let template = strings.and_then(|s| s.get(self.key)).map_or(self.english(), String::as_str);
+        self.params.iter().fold(template.to_string(), |text, (name, value)| text.replace(&format!("{{{}}}", name), value))
+    }
+
+    /// Parameters as a JSON object, for the form page to fill its own translation
+    pub fn params_json(&self) -> serde_json::Value {
+        self.params.iter().map(|(name, value)| (name.to_string(), serde_json::Value::String(value.clone()))).collect()
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message(None))
+    }
 }
 
 /// Validates a form submission
-pub fn validate_submission(req: &FormSubmissionRequest) -> Result<(), String> {
+pub fn validate_submission(req: &FormSubmissionRequest) -> Result<(), ValidationError> {
     // Validate character name
     if req.character_name.trim().is_empty() {
-        return Err("Character name is required".to_string());
+        return Err(ValidationError::new("pleaseEnterCharacterName"));
     }
     
     // Validate player ID (must be a number)
     if req.player_id.trim().is_empty() {
-        return Err("Player ID is required".to_string());
+        return Err(ValidationError::new("playerIdRequired"));
     }
     if !req.player_id.trim().chars().all(|c| c.is_ascii_digit()) {
-        return Err("Player ID must contain only digits".to_string());
+        return Err(ValidationError::new("playerIdMustBeNumber"));
     }
     
     // Validate submission type
     if req.submission_type != "New submission" && req.submission_type != "Re-Submission" {
-        return Err("Invalid submission type".to_string());
+        return Err(ValidationError::new("invalidSubmissionType"));
     }
     
     // Validate alliance
     if req.alliance.trim().is_empty() {
-        return Err("Alliance selection is required".to_string());
+        return Err(ValidationError::new("pleaseSelectAlliance"));
     }
     if req.alliance == "Non of the above" && req.custom_alliance.as_ref().map(|s| s.trim().is_empty()).unwrap_or(true) {
-        return Err("Custom alliance name is required when 'Non of the above' is selected".to_string());
+        return Err(ValidationError::new("pleaseEnterCustomAlliance"));
     }
     
     // Validate construction day if selected
     if req.wants_construction {
         if req.construction_time_slots.len() < 5 {
-            return Err("Construction day requires at least 5 time slots".to_string());
+            return Err(ValidationError::new("pleaseSelectConstructionTimeSlots"));
         }
         // Validate slots are in range 1-49
         for &slot in &req.construction_time_slots {
-            if slot < 1 || slot > 49 {
-                return Err(format!("Invalid construction time slot: {}", slot));
+            if !(1..=49).contains(&slot) {
+                return Err(ValidationError::new("invalidConstructionTimeSlot").with_param("slot", slot));
             }
         }
     }
@@ -91,11 +155,11 @@ pub fn validate_submission(req: &FormSubmissionRequest) -> Result<(), String> {
     // Validate research day if selected
     if req.wants_research {
         if req.research_time_slots.len() < 5 {
-            return Err("Research day requires at least 5 time slots".to_string());
+            return Err(ValidationError::new("pleaseSelectResearchTimeSlots"));
         }
         for &slot in &req.research_time_slots {
-            if slot < 1 || slot > 49 {
-                return Err(format!("Invalid research time slot: {}", slot));
+            if !(1..=49).contains(&slot) {
+                return Err(ValidationError::new("invalidResearchTimeSlot").with_param("slot", slot));
             }
         }
     }
@@ -103,19 +167,43 @@ pub fn validate_submission(req: &FormSubmissionRequest) -> Result<(), String> {
     // Validate troops day if selected
     if req.wants_troops {
         if req.troops_time_slots.len() < 5 {
-            return Err("Troops Training day requires at least 5 time slots".to_string());
+            return Err(ValidationError::new("pleaseSelectTroopsTimeSlots"));
         }
         for &slot in &req.troops_time_slots {
-            if slot < 1 || slot > 49 {
-                return Err(format!("Invalid troops time slot: {}", slot));
+            if !(1..=49).contains(&slot) {
+                return Err(ValidationError::new("invalidTroopsTimeSlot").with_param("slot", slot));
             }
         }
     }
     
     // At least one day type must be selected
     if !req.wants_construction && !req.wants_research && !req.wants_troops {
-        return Err("At least one day type (Construction, Research, or Troops) must be selected".to_string());
+        return Err(ValidationError::new("pleaseSelectAtLeastOneDay"));
     }
     
     Ok(())
 }
+
+/// Rejects time slots that fall in a day's blackouts. The form doesn't offer them, so this
+/// only catches stale drafts and hand-made requests.
+pub fn validate_blackout_slots(req: &FormSubmissionRequest, construction: &[u8], research: &[u8], troops: &[u8]) -> Result<(), ValidationError> {
+    let days = [
+        (req.wants_construction, &req.construction_time_slots, construction, "invalidConstructionTimeSlot"),
+        (req.wants_research, &req.research_time_slots, research, "invalidResearchTimeSlot"),
+        (req.wants_troops, &req.troops_time_slots, troops, "invalidTroopsTimeSlot"),
+    ];
+    for (wants, slots, blackouts, key) in days {
+        if let Some(&slot) = slots.iter().find(|slot| wants && blackouts.contains(slot)) {
+            return Err(ValidationError::new(key).with_param("slot", slot));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the passcode a submission needs, if any. Surrounding spaces are ignored.
+pub fn validate_passcode(req: &FormSubmissionRequest, required: Option<&str>) -> Result<(), ValidationError> {
+    match required {
+        Some(passcode) if req.passcode.as_deref().map(str::trim) != Some(passcode) => Err(ValidationError::new("invalidPasscode")),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,200 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+/// Jobs running at once; later ones wait in the queue
+pub const MAX_RUNNING_JOBS: usize = 2;
+
+/// How long finished jobs stay around to be polled
+const FINISHED_JOB_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+/// Background work started by a request, polled by its ID until it finishes
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: String,
+    pub kind: &'static str, // "generate_schedule", "recompute_stats", "export_sheets", "sync_form", "task_*"
+    pub params: serde_json::Value, // Options it was started with, null when it takes none
+    #[serde(skip)]
+    pub account_name: String,
+    #[serde(skip)]
+    pub server_number: u32,
+    pub status: JobStatus,
+    pub progress: u8, // Percent
+    pub step: String, // What it is doing, for the progress display
+    pub created_at: String, // RFC 3339
+    pub finished_at: Option<String>, // RFC 3339
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+    #[serde(skip)]
+    finished: Option<Instant>,
+}
+
+/// Jobs of all accounts, kept in memory: a restart drops them, like the requests they replace
+#[derive(Clone)]
+pub struct JobQueue {
+    jobs: Arc<Mutex<HashMap<String, Job>>>,
+    slots: Arc<Semaphore>,
+}
+
+/// Handle a running job reports its progress through
+pub struct JobProgress {
+    jobs: Arc<Mutex<HashMap<String, Job>>>,
+    id: String,
+}
+
+impl JobProgress {
+    pub fn set(&self, progress: u8, step: &str) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(&self.id) {
+            job.progress = progress.min(100);
+            job.step = step.to_string();
+        }
+    }
+}
+
+impl JobQueue {
+    pub fn new(max_running: usize) -> Self {
+        JobQueue {
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+            slots: Arc::new(Semaphore::new(max_running)),
+        }
+    }
+
+    /// Queues `work` to run on a blocking thread once a slot is free and returns the queued job.
+    /// A job of the same kind with the same `params` already queued or running for the
+    /// account/server is returned instead, so a double click doesn't do the work twice.
+    pub fn enqueue<F>(&self, kind: &'static str, account_name: &str, server_number: u32, params: serde_json::Value, work: F) -> Job
+    where
+        F: FnOnce(&JobProgress) -> Result<serde_json::Value, String> + Send + 'static,
+    {
+        let job = {
+            let mut jobs = self.jobs.lock().unwrap();
+            jobs.retain(|_, job| job.finished.is_none_or(|at| at.elapsed() < FINISHED_JOB_RETENTION));
+            if let Some(pending) = jobs.values().find(|job| {
+                job.kind == kind && job.account_name == account_name && job.server_number == server_number
+                    && job.params == params && job.finished.is_none()
+            }) {
+                return pending.clone();
+            }
+            let job = Job {
+                id: new_job_id(),
+                kind,
+                params,
+                account_name: account_name.to_string(),
+                server_number,
+                status: JobStatus::Queued,
+                progress: 0,
+                step: "Waiting for other jobs to finish".to_string(),
+                created_at: chrono::Utc::now().to_rfc3339(),
+                finished_at: None,
+                result: None,
+                error: None,
+                finished: None,
+            };
+            jobs.insert(job.id.clone(), job.clone());
+            job
+        };
+
+        let queue = self.clone();
+        let id = job.id.clone();
+        tokio::spawn(async move {
+            let Ok(_permit) = queue.slots.acquire().await else { return };
+            queue.update(&id, |job| {
+                job.status = JobStatus::Running;
+                job.step = "Starting".to_string();
+            });
+            let progress = JobProgress { jobs: queue.jobs.clone(), id: id.clone() };
+            let outcome = tokio::task::spawn_blocking(move || work(&progress)).await
+                .unwrap_or_else(|e| Err(format!("The job stopped unexpectedly: {}", e)));
+            queue.update(&id, |job| {
+                match outcome {
+                    Ok(result) => {
+                        job.status = JobStatus::Done;
+                        job.progress = 100;
+                        job.step = "Done".to_string();
+                        job.result = Some(result);
+                    }
+                    Err(e) => {
+                        log::warn!("Job {} ({}) of {} #{} failed: {}", job.id, job.kind, job.account_name, job.server_number, e);
+                        job.status = JobStatus::Failed;
+                        job.step = "Failed".to_string();
+                        job.error = Some(e);
+                    }
+                }
+                job.finished_at = Some(chrono::Utc::now().to_rfc3339());
+                job.finished = Some(Instant::now());
+            });
+        });
+        job
+    }
+
+    /// A job of the account/server, None for unknown IDs and other accounts' jobs
+    pub fn get(&self, account_name: &str, server_number: u32, id: &str) -> Option<Job> {
+        self.jobs.lock().unwrap().get(id)
+            .filter(|job| job.account_name == account_name && job.server_number == server_number)
+            .cloned()
+    }
+
+    /// Jobs of an account/server, newest first
+    pub fn list(&self, account_name: &str, server_number: u32) -> Vec<Job> {
+        let mut jobs: Vec<Job> = self.jobs.lock().unwrap().values()
+            .filter(|job| job.account_name == account_name && job.server_number == server_number)
+            .cloned()
+            .collect();
+        jobs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        jobs
+    }
+
+    fn update(&self, id: &str, change: impl FnOnce(&mut Job)) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(id) {
+            change(job);
+        }
+    }
+}
+
+fn new_job_id() -> String {
+    use rand::Rng;
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    let mut rng = rand::thread_rng();
+    (0..16).map(|_| CHARSET[rng.gen_range(0..CHARSET.len())] as char).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // With no free slots, queued jobs stay queued and can be compared
+    fn stalled_queue() -> JobQueue {
+        JobQueue::new(0)
+    }
+
+    #[tokio::test]
+    async fn same_job_with_the_same_params_is_queued_once() {
+        let queue = stalled_queue();
+        let params = serde_json::json!({ "day": "construction", "append": false });
+        let first = queue.enqueue("generate_schedule", "acc", 1, params.clone(), |_| Ok(serde_json::Value::Null));
+        let second = queue.enqueue("generate_schedule", "acc", 1, params, |_| Ok(serde_json::Value::Null));
+        assert_eq!(first.id, second.id);
+        assert_eq!(queue.list("acc", 1).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn same_job_with_other_params_is_queued_separately() {
+        let queue = stalled_queue();
+        let first = queue.enqueue("generate_schedule", "acc", 1, serde_json::json!({ "day": "construction" }), |_| Ok(serde_json::Value::Null));
+        let second = queue.enqueue("generate_schedule", "acc", 1, serde_json::json!({ "day": "research" }), |_| Ok(serde_json::Value::Null));
+        assert_ne!(first.id, second.id);
+        assert_eq!(second.params["day"], "research");
+        assert_eq!(queue.list("acc", 1).len(), 2);
+    }
+}
@@ -0,0 +1,35 @@
+use std::io::Write;
+
+use log::{Level, LevelFilter};
+
+/// Sets up `log` output on stderr. Info lines are the progress messages a user reads
+/// ("Loaded 60 appointment entries") and print as they are; warnings and errors get a prefix,
+/// debug and trace lines their module. `-q` keeps only warnings and errors, each `-v` adds a
+/// level (and info from actix and the file watcher). `RUST_LOG` overrides both.
+/// `silent` turns logging off altogether.
+pub fn init(verbose: u8, quiet: bool, silent: bool) {
+    if silent {
+        log::set_max_level(LevelFilter::Off);
+        return;
+    }
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::Warn,
+        (false, 0) => LevelFilter::Info,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+    let dependencies = if verbose > 0 && !quiet { LevelFilter::Info } else { LevelFilter::Warn };
+
+    env_logger::Builder::new()
+        .filter_level(dependencies)
+        .filter_module("prep_appointments", level)
+        .filter_module("kingshot_core", level)
+        .parse_env("RUST_LOG")
+        .format(|buf, record| match record.level() {
+            Level::Info => writeln!(buf, "{}", record.args()),
+            Level::Warn => writeln!(buf, "Warning: {}", record.args()),
+            Level::Error => writeln!(buf, "Error: {}", record.args()),
+            level => writeln!(buf, "[{} {}] {}", level, record.target(), record.args()),
+        })
+        .init();
+}
@@ -0,0 +1,133 @@
+use crate::config::{SmtpConfig, SmtpSecurity};
+use askama::Template;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use std::time::Duration;
+
+/// Sending one message takes at most this long
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A rendered plain text email
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub subject: String,
+    pub body: String,
+}
+
+/// Sends email through the SMTP server of the server config
+pub struct Mailer {
+    transport: SmtpTransport,
+    from: Mailbox,
+    public_url: Option<String>,
+}
+
+impl Mailer {
+    pub fn from_config(config: &SmtpConfig) -> Result<Self, String> {
+        let from: Mailbox = config.from.parse()
+            .map_err(|e| format!("Invalid sender address \"{}\": {}", config.from, e))?;
+        let builder = match config.security {
+            SmtpSecurity::Starttls => SmtpTransport::starttls_relay(&config.host),
+            SmtpSecurity::Tls => SmtpTransport::relay(&config.host),
+            SmtpSecurity::None => Ok(SmtpTransport::builder_dangerous(&config.host)),
+        }.map_err(|e| format!("Invalid mail server \"{}\": {}", config.host, e))?;
+        let mut builder = builder.port(config.port).timeout(Some(SEND_TIMEOUT));
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(Mailer {
+            transport: builder.build(),
+            from,
+            public_url: config.public_url.as_deref().map(|url| url.trim_end_matches('/').to_string()),
+        })
+    }
+
+    /// Sender address, for the startup log
+    pub fn sender(&self) -> String {
+        self.from.to_string()
+    }
+
+    /// Absolute link to a page of the site (`path` starts with `/`), when the public URL is configured
+    pub fn link(&self, path: &str) -> Option<String> {
+        self.public_url.as_ref().map(|url| format!("{}{}", url, path))
+    }
+
+    /// Sends a plain text message. Blocks until the mail server accepted or refused it.
+    pub fn send(&self, to: &str, email: &Email) -> Result<(), String> {
+        let to: Mailbox = to.parse().map_err(|e| format!("Invalid address \"{}\": {}", to, e))?;
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(&email.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(email.body.clone())
+            .map_err(|e| format!("Failed to build the message: {}", e))?;
+        self.transport.send(&message)
+            .map(|_| ())
+            .map_err(|e| format!("The mail server didn't take the message: {}", e))
+    }
+}
+
+/// Rough check of an address typed into a settings form
+pub fn validate_address(email: &str) -> Result<(), String> {
+    let (local, domain) = email.split_once('@').ok_or("Enter a valid email address")?;
+    if local.is_empty() || !domain.contains('.') || email.chars().any(char::is_whitespace) {
+        return Err("Enter a valid email address".into());
+    }
+    Ok(())
+}
+
+/// Renders a message template under the given subject
+pub fn render<T: Template>(subject: String, template: &T) -> Result<Email, String> {
+    let body = template.render().map_err(|e| format!("Failed to render the message: {}", e))?;
+    Ok(Email { subject, body })
+}
+
+/// Any notification without a template of its own
+#[derive(Template)]
+#[template(path = "email/notification.txt")]
+pub struct NotificationEmail<'a> {
+    pub title: &'a str,
+    pub body: &'a str,
+    pub dashboard_url: Option<String>,
+}
+
+/// The filled slots of each day of a newly generated schedule
+#[derive(Template)]
+#[template(path = "email/slot_assignments.txt")]
+pub struct SlotAssignmentsEmail<'a> {
+    pub server_number: u32,
+    pub message: &'a str,
+    pub days: Vec<AssignedDay>,
+    pub schedule_url: Option<String>,
+}
+
+pub struct AssignedDay {
+    pub name: &'static str,
+    pub slots: Vec<AssignedSlot>,
+}
+
+pub struct AssignedSlot {
+    pub time: String,
+    pub player: String,
+}
+
+/// Something coming up soon, like a form about to close
+#[derive(Template)]
+#[template(path = "email/reminder.txt")]
+pub struct ReminderEmail<'a> {
+    pub title: &'a str,
+    pub details: &'a str,
+    pub link: Option<String>,
+}
+
+/// One-time code for setting a new password
+#[derive(Template)]
+#[template(path = "email/password_reset.txt")]
+pub struct PasswordResetEmail<'a> {
+    pub account_name: &'a str,
+    pub code: &'a str,
+    pub valid_minutes: i64,
+    pub reset_url: Option<String>,
+}
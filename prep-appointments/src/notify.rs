@@ -0,0 +1,160 @@
+use crate::display::DISCORD_MESSAGE_LIMIT;
+use crate::mailer::{self, Email, Mailer, NotificationEmail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Webhook posts that take longer than this fail
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Hosts a Discord webhook URL may point to; anything else is refused so the server can't be
+/// made to post to arbitrary addresses
+const DISCORD_WEBHOOK_HOSTS: [&str; 4] = ["discord.com", "discordapp.com", "ptb.discord.com", "canary.discord.com"];
+
+/// Where an account's notifications are delivered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationChannel {
+    #[default]
+    None,
+    Discord,
+    Email,
+}
+
+/// Things an account can be notified about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    NewSubmission,
+    ScheduleGenerated,
+    SlotChanged,
+    FormClosingSoon,
+    /// Posted by a reminder task; always sent, since setting up the task is the opt-in
+    Reminder,
+}
+
+/// Notification preferences of an account: one channel, and the events sent over it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationSettings {
+    #[serde(default)]
+    pub channel: NotificationChannel,
+    #[serde(default)]
+    pub discord_webhook: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub events: BTreeSet<NotificationEvent>,
+}
+
+impl NotificationSettings {
+    /// Whether `event` is sent anywhere
+    pub fn wants(&self, event: NotificationEvent) -> bool {
+        self.channel != NotificationChannel::None && self.events.contains(&event)
+    }
+
+    /// Checks that the chosen channel has somewhere to deliver to
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(url) = &self.discord_webhook {
+            validate_discord_webhook(url)?;
+        }
+        if let Some(email) = &self.email {
+            mailer::validate_address(email)?;
+        }
+        match self.channel {
+            NotificationChannel::Discord if self.discord_webhook.is_none() => Err("Enter the Discord webhook URL".into()),
+            NotificationChannel::Email if self.email.is_none() => Err("Enter the email address".into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn validate_discord_webhook(url: &str) -> Result<(), String> {
+    let rest = url.strip_prefix("https://").ok_or("The Discord webhook URL must start with https://")?;
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    if !DISCORD_WEBHOOK_HOSTS.contains(&host) || !path.starts_with("api/webhooks/") {
+        return Err("That isn't a Discord webhook URL (https://discord.com/api/webhooks/...)".into());
+    }
+    Ok(())
+}
+
+/// One message about an event: a short title and a line or two of detail. Events with a
+/// template of their own also bring the email to send; others are emailed as title and body.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub event: NotificationEvent,
+    pub title: String,
+    pub body: String,
+    pub email: Option<Email>,
+}
+
+/// Delivers notifications over the channel each account picked. Every integration sends
+/// through [`Notifier::dispatch`], so preferences are checked in one place.
+pub struct Notifier {
+    agent: ureq::Agent,
+    mailer: Option<Arc<Mailer>>, // None when the server can't send email
+}
+
+impl Notifier {
+    pub fn new(mailer: Option<Arc<Mailer>>) -> Self {
+        Notifier {
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+            mailer,
+        }
+    }
+
+    /// Sends a notification now, whatever the events of `settings` are. Used for test messages.
+    pub fn deliver(&self, account_name: &str, settings: &NotificationSettings, notification: &Notification) -> Result<(), String> {
+        match settings.channel {
+            NotificationChannel::None => Ok(()),
+            NotificationChannel::Discord => {
+                let url = settings.discord_webhook.as_deref().ok_or("No Discord webhook URL set")?;
+                self.post_discord(url, notification)
+            }
+            NotificationChannel::Email => {
+                let mailer = self.mailer.as_ref().ok_or("Email isn't set up on this server")?;
+                let to = settings.email.as_deref().ok_or("No email address set")?;
+                let email = match &notification.email {
+                    Some(email) => email.clone(),
+                    None => mailer::render(notification.title.clone(), &NotificationEmail {
+                        title: &notification.title,
+                        body: &notification.body,
+                        dashboard_url: mailer.link(&format!("/dashboard/{}", account_name)),
+                    })?,
+                };
+                mailer.send(to, &email)
+            }
+        }
+    }
+
+    /// Sends a notification in the background when the account wants its event. Failures are logged.
+    pub fn dispatch(self: &Arc<Self>, account_name: &str, settings: &NotificationSettings, notification: Notification) {
+        if !settings.wants(notification.event) {
+            return;
+        }
+        let notifier = self.clone();
+        let settings = settings.clone();
+        let account_name = account_name.to_string();
+        std::thread::spawn(move || {
+            if let Err(e) = notifier.deliver(&account_name, &settings, &notification) {
+                log::warn!("Failed to send the {:?} notification of {}: {}", notification.event, account_name, e);
+            }
+        });
+    }
+
+    fn post_discord(&self, url: &str, notification: &Notification) -> Result<(), String> {
+        let content = format!("**{}**\n{}", notification.title, notification.body);
+        let content: String = content.chars().take(DISCORD_MESSAGE_LIMIT).collect();
+        // Names come from submissions, so nobody gets pinged by them
+        let payload = serde_json::json!({
+            "content": content,
+            "allowed_mentions": { "parse": [] }
+        });
+        match self.agent.post(url).send_json(payload) {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(404, _)) => Err("Discord says the webhook doesn't exist; it may have been deleted".into()),
+            Err(ureq::Error::Status(code, _)) => Err(format!("Discord refused the message (HTTP {})", code)),
+            Err(e) => Err(format!("Couldn't reach Discord: {}", e)),
+        }
+    }
+}
@@ -1,13 +0,0 @@
-pub mod types;
-pub mod slot_utils;
-pub mod move_chain;
-pub mod generic;
-pub mod construction;
-pub mod research;
-pub mod troops;
-
-pub use types::DaySchedule;
-pub use slot_utils::{slot_to_time, calculate_time_slots, parse_time_to_minutes, minutes_to_time_string};
-pub use construction::{schedule_construction_day, schedule_construction_day_with_locked};
-pub use research::{schedule_research_day, schedule_research_day_with_locked};
-pub use troops::{schedule_troops_day, schedule_troops_day_with_locked};
@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const SHEETS_API: &str = "https://sheets.googleapis.com/v4/spreadsheets";
+const SHEETS_SCOPE: &str = "https://www.googleapis.com/auth/spreadsheets";
+
+/// Requests to Google that take longer than this fail
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The fields of a service account key file that are needed to get access tokens
+#[derive(Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    token_uri: String,
+}
+
+#[derive(Serialize)]
+struct TokenClaims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+/// Writes tables to Google Sheets as a service account. Access tokens are reused until shortly
+/// before they expire.
+pub struct SheetsClient {
+    key: ServiceAccountKey,
+    token: Mutex<Option<(String, Instant)>>,
+    agent: ureq::Agent,
+}
+
+/// One tab of a spreadsheet: its title and rows, the first row being the header
+pub struct SheetTab {
+    pub title: String,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl SheetsClient {
+    pub fn from_key_file(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let key: ServiceAccountKey = serde_json::from_str(&content)
+            .map_err(|e| format!("{} is not a service account key: {}", path.display(), e))?;
+        Ok(SheetsClient {
+            key,
+            token: Mutex::new(None),
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+        })
+    }
+
+    /// Email of the service account; a sheet must be shared with it to be written
+    pub fn client_email(&self) -> &str {
+        &self.key.client_email
+    }
+
+    fn access_token(&self) -> Result<String, String> {
+        if let Some((token, expires)) = self.token.lock().unwrap().as_ref() {
+            if Instant::now() < *expires {
+                return Ok(token.clone());
+            }
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let claims = TokenClaims {
+            iss: &self.key.client_email,
+            scope: SHEETS_SCOPE,
+            aud: &self.key.token_uri,
+            iat: now,
+            exp: now + 3600,
+        };
+        let signing_key = jsonwebtoken::EncodingKey::from_rsa_pem(self.key.private_key.as_bytes())
+            .map_err(|e| format!("Invalid service account private key: {}", e))?;
+        let assertion = jsonwebtoken::encode(&jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256), &claims, &signing_key)
+            .map_err(|e| format!("Failed to sign the token request: {}", e))?;
+        let response: TokenResponse = self.agent.post(&self.key.token_uri)
+            .send_form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", &assertion),
+            ])
+            .map_err(google_error)?
+            .into_json()
+            .map_err(|e| format!("Unexpected token response: {}", e))?;
+
+        // Renewed a minute early so a token doesn't run out during a request
+        let expires = Instant::now() + Duration::from_secs(response.expires_in.saturating_sub(60));
+        *self.token.lock().unwrap() = Some((response.access_token.clone(), expires));
+        Ok(response.access_token)
+    }
+
+    /// Replaces the contents of the given tabs of a spreadsheet, adding the tabs that don't
+    /// exist yet. Other tabs are left alone.
+    pub fn write_tabs(&self, spreadsheet_id: &str, tabs: &[SheetTab]) -> Result<(), String> {
+        let authorization = format!("Bearer {}", self.access_token()?);
+        let url = format!("{}/{}", SHEETS_API, spreadsheet_id);
+
+        let spreadsheet: serde_json::Value = self.agent.get(&url)
+            .query("fields", "sheets.properties.title")
+            .set("Authorization", &authorization)
+            .call()
+            .map_err(google_error)?
+            .into_json()
+            .map_err(|e| format!("Unexpected response from Google Sheets: {}", e))?;
+        let existing: Vec<&str> = spreadsheet["sheets"].as_array().into_iter().flatten()
+            .filter_map(|sheet| sheet["properties"]["title"].as_str())
+            .collect();
+        let add_sheets: Vec<serde_json::Value> = tabs.iter()
+            .filter(|tab| !existing.contains(&tab.title.as_str()))
+            .map(|tab| serde_json::json!({ "addSheet": { "properties": { "title": tab.title } } }))
+            .collect();
+        if !add_sheets.is_empty() {
+            self.agent.post(&format!("{}:batchUpdate", url))
+                .set("Authorization", &authorization)
+                .send_json(serde_json::json!({ "requests": add_sheets }))
+                .map_err(google_error)?;
+        }
+
+        // Cleared first so rows of a longer earlier schedule don't stay behind
+        let ranges: Vec<String> = tabs.iter().map(|tab| tab_range(&tab.title)).collect();
+        self.agent.post(&format!("{}/values:batchClear", url))
+            .set("Authorization", &authorization)
+            .send_json(serde_json::json!({ "ranges": ranges }))
+            .map_err(google_error)?;
+        let data: Vec<serde_json::Value> = tabs.iter()
+            .map(|tab| serde_json::json!({ "range": tab_range(&tab.title), "values": tab.rows }))
+            .collect();
+        self.agent.post(&format!("{}/values:batchUpdate", url))
+            .set("Authorization", &authorization)
+            .send_json(serde_json::json!({ "valueInputOption": "RAW", "data": data }))
+            .map_err(google_error)?;
+        Ok(())
+    }
+
+    /// Rows of a tab as displayed in the sheet, the header row first. Trailing empty cells of a
+    /// row are left out, as the API returns them.
+    pub fn read_rows(&self, spreadsheet_id: &str, title: &str) -> Result<Vec<Vec<String>>, String> {
+        let values = self.read_values(spreadsheet_id, &tab_range(title), "FORMATTED_VALUE")?;
+        Ok(values.into_iter()
+            .map(|row| row.into_iter().map(|cell| match cell {
+                serde_json::Value::String(text) => text,
+                serde_json::Value::Null => String::new(),
+                other => other.to_string(),
+            }).collect())
+            .collect())
+    }
+
+    /// Dates of the first column of a tab, one per row including the header row (None where the
+    /// cell isn't a date). Read as serial numbers so the sheet's locale doesn't matter.
+    pub fn read_first_column_dates(&self, spreadsheet_id: &str, title: &str) -> Result<Vec<Option<chrono::NaiveDateTime>>, String> {
+        let range = format!("{}!A:A", tab_range(title));
+        let values = self.read_values(spreadsheet_id, &range, "UNFORMATTED_VALUE")?;
+        Ok(values.into_iter()
+            .map(|row| row.first().and_then(|cell| cell.as_f64()).and_then(serial_to_datetime))
+            .collect())
+    }
+
+    fn read_values(&self, spreadsheet_id: &str, range: &str, render: &str) -> Result<Vec<Vec<serde_json::Value>>, String> {
+        let authorization = format!("Bearer {}", self.access_token()?);
+        let url = format!("{}/{}/values/{}", SHEETS_API, spreadsheet_id, encode_path_segment(range));
+        let body: serde_json::Value = self.agent.get(&url)
+            .query("valueRenderOption", render)
+            .query("dateTimeRenderOption", "SERIAL_NUMBER")
+            .set("Authorization", &authorization)
+            .call()
+            .map_err(google_error)?
+            .into_json()
+            .map_err(|e| format!("Unexpected response from Google Sheets: {}", e))?;
+        Ok(body["values"].as_array().into_iter().flatten()
+            .map(|row| row.as_array().cloned().unwrap_or_default())
+            .collect())
+    }
+}
+
+/// Date and time of a spreadsheet serial number: days since 30 December 1899
+fn serial_to_datetime(serial: f64) -> Option<chrono::NaiveDateTime> {
+    let epoch = chrono::NaiveDate::from_ymd_opt(1899, 12, 30)?.and_hms_opt(0, 0, 0)?;
+    let seconds = (serial * 86_400.0).round();
+    if !seconds.is_finite() || seconds < 0.0 {
+        return None;
+    }
+    epoch.checked_add_signed(chrono::Duration::seconds(seconds as i64))
+}
+
+/// Percent-encodes a URL path segment, such as an A1 range with a quoted tab title
+fn encode_path_segment(segment: &str) -> String {
+    segment.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'!' | b':' => (b as char).to_string(),
+        _ => format!("%{:02X}", b),
+    }).collect()
+}
+
+/// A1 range covering a whole tab
+fn tab_range(title: &str) -> String {
+    format!("'{}'", title.replace('\'', "''"))
+}
+
+/// Error message of a failed Google request, using the message Google sent where there is one
+fn google_error(error: ureq::Error) -> String {
+    match error {
+        ureq::Error::Status(status, response) => {
+            let message = response.into_json::<serde_json::Value>().ok()
+                .and_then(|body| {
+                    body["error"]["message"].as_str()
+                        .or_else(|| body["error_description"].as_str())
+                        .map(str::to_string)
+                });
+            match (status, message) {
+                (403, _) => "Google refused access. Share the sheet with the service account as an editor.".to_string(),
+                (404, _) => "Spreadsheet not found. Check the spreadsheet ID.".to_string(),
+                (_, Some(message)) => format!("Google Sheets error {}: {}", status, message),
+                (_, None) => format!("Google Sheets error {}", status),
+            }
+        }
+        ureq::Error::Transport(e) => format!("Could not reach Google: {}", e),
+    }
+}
+
+/// Spreadsheet ID from either the ID itself or the URL of the sheet
+pub fn parse_spreadsheet_id(input: &str) -> Option<String> {
+    let input = input.trim();
+    let id = match input.split_once("/spreadsheets/d/") {
+        Some((_, rest)) => rest.split(['/', '?', '#']).next().unwrap_or_default(),
+        None => input,
+    };
+    let valid = id.len() >= 20 && id.len() <= 100 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then(|| id.to_string())
+}
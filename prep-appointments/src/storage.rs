@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Lock file taken in a directory while one of its files is checked and replaced
+const LOCK_FILE: &str = ".write.lock";
+
+/// What a file looked like on disk. Every save replaces the file, so any save by another
+/// instance gives it a different stamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    modified: SystemTime,
+    len: u64,
+    inode: u64,
+}
+
+/// Stamps of the files as this instance last read or wrote them
+static KNOWN_STAMPS: Mutex<BTreeMap<PathBuf, FileStamp>> = Mutex::new(BTreeMap::new());
+
+/// Stamp of a file or directory, `None` when it doesn't exist
+pub fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = std::fs::metadata(path).ok()?;
+    #[cfg(unix)]
+    let inode = std::os::unix::fs::MetadataExt::ino(&metadata);
+    #[cfg(not(unix))]
+    let inode = 0;
+    Some(FileStamp { modified: metadata.modified().ok()?, len: metadata.len(), inode })
+}
+
+/// Remembers a stamp taken before reading a file, so the next save can tell whether it was
+/// changed since. Taking it before the read errs on the side of a refused save.
+pub fn remember(path: &Path, stamp: Option<FileStamp>) {
+    let mut known = KNOWN_STAMPS.lock().unwrap();
+    match stamp {
+        Some(stamp) => known.insert(path.to_path_buf(), stamp),
+        None => known.remove(path),
+    };
+}
+
+/// Reads a file and remembers the version that was read
+pub fn read_tracked(path: &Path) -> io::Result<String> {
+    let stamp = file_stamp(path);
+    let content = std::fs::read_to_string(path)?;
+    remember(path, stamp);
+    Ok(content)
+}
+
+/// Whether a file or directory differs from the version this instance last read or wrote
+pub fn changed_on_disk(path: &Path) -> bool {
+    KNOWN_STAMPS.lock().unwrap().get(path).copied() != file_stamp(path)
+}
+
+fn lock_directory(path: &Path) -> io::Result<File> {
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let lock = OpenOptions::new().create(true).truncate(false).write(true).open(dir.join(LOCK_FILE))?;
+    lock.lock()?;
+    Ok(lock)
+}
+
+/// Writes a file through a temporary file next to it and renames it into place, so readers
+/// (including other instances sharing the data directory) never see it half written.
+///
+/// A save is refused when another instance changed the file since this instance last read or
+/// wrote it, instead of overwriting that change. The check and the rename happen under a lock
+/// shared by every instance. A file that no longer exists is written as new.
+pub fn write_atomic(path: &str, content: impl AsRef<[u8]>) -> io::Result<()> {
+    static NEXT_TMP: AtomicU64 = AtomicU64::new(0);
+    let target = Path::new(path);
+    let _lock = lock_directory(target)?;
+    let mut known = KNOWN_STAMPS.lock().unwrap();
+    if let (Some(last), Some(current)) = (known.get(target), file_stamp(target)) {
+        if *last != current {
+            return Err(io::Error::other(format!("{} was changed by another instance, reload and try again", path)));
+        }
+    }
+    let tmp = format!("{}.{}-{}.tmp", path, std::process::id(), NEXT_TMP.fetch_add(1, Ordering::Relaxed));
+    if let Err(e) = std::fs::write(&tmp, content) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e);
+    }
+    std::fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp);
+    })?;
+    match file_stamp(target) {
+        Some(stamp) => known.insert(target.to_path_buf(), stamp),
+        None => known.remove(target),
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("prep-appointments-storage-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn save_over_a_change_by_another_instance_is_refused() {
+        let path = temp_file("refused.json");
+        let path_str = path.to_str().unwrap();
+        write_atomic(path_str, "{\"v\":1}").unwrap();
+        assert_eq!(read_tracked(&path).unwrap(), "{\"v\":1}");
+
+        // Another instance saves the same way, through a new file renamed into place
+        let other = temp_file("refused.json.other");
+        std::fs::write(&other, "{\"v\":2,\"other\":true}").unwrap();
+        std::fs::rename(&other, &path).unwrap();
+        assert!(changed_on_disk(&path));
+
+        assert!(write_atomic(path_str, "{\"v\":3}").is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "{\"v\":2,\"other\":true}");
+
+        // Once the change is read, saving works again
+        read_tracked(&path).unwrap();
+        write_atomic(path_str, "{\"v\":3}").unwrap();
+        assert!(!changed_on_disk(&path));
+    }
+
+    #[test]
+    fn own_saves_and_deleted_files_are_not_conflicts() {
+        let path = temp_file("own.json");
+        let path_str = path.to_str().unwrap();
+        write_atomic(path_str, "a").unwrap();
+        write_atomic(path_str, "bb").unwrap();
+        std::fs::remove_file(&path).unwrap();
+        write_atomic(path_str, "ccc").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "ccc");
+    }
+}
@@ -0,0 +1,139 @@
+use chrono::{DateTime, Utc};
+use croner::Cron;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Scheduled tasks one server can have
+pub const MAX_TASKS_PER_SERVER: usize = 20;
+
+/// Backups of a server kept by [`TaskKind::Backup`]; older ones are deleted
+pub const BACKUPS_KEPT: usize = 7;
+
+/// Longest reminder message, so it fits in one Discord post with its title
+pub const MAX_REMINDER_CHARS: usize = 1500;
+
+/// What a scheduled task does when its time comes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskKind {
+    /// Closes the current form, which auto generation then picks up
+    CloseForm,
+    /// Posts the task's message over the account's notification channel
+    Reminder,
+    /// Copies the server's schedule, roster, statistics and current form into `backups/`
+    Backup,
+    /// Recomputes the cached statistics from the current submissions
+    RecomputeStats,
+}
+
+impl TaskKind {
+    /// Job kind the task runs as, shown in the jobs list
+    pub fn job_kind(self) -> &'static str {
+        match self {
+            TaskKind::CloseForm => "task_close_form",
+            TaskKind::Reminder => "task_reminder",
+            TaskKind::Backup => "task_backup",
+            TaskKind::RecomputeStats => "task_recompute_stats",
+        }
+    }
+}
+
+/// A recurring task of one server. `cron` is a five field pattern (minute, hour, day of month,
+/// month, day of week) evaluated in UTC, like the form close times.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledTask {
+    pub id: String,
+    pub kind: TaskKind,
+    pub cron: String,
+    #[serde(default)]
+    pub message: Option<String>, // Reminder text, only for TaskKind::Reminder
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub created_at: String, // RFC 3339
+    #[serde(default)]
+    pub last_run_at: Option<String>, // RFC 3339, when the last run was started
+    #[serde(default)]
+    pub last_error: Option<String>, // None if the last run worked
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl ScheduledTask {
+    /// Checks the pattern and that reminders have a message
+    pub fn validate(&self) -> Result<(), String> {
+        parse_cron(&self.cron)?;
+        match (&self.kind, self.message.as_deref()) {
+            (TaskKind::Reminder, None) => Err("Enter the reminder message".into()),
+            (TaskKind::Reminder, Some(m)) if m.chars().count() > MAX_REMINDER_CHARS => {
+                Err(format!("The reminder message can be at most {} characters", MAX_REMINDER_CHARS))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// First time the task runs after its last run (or after it was created)
+    pub fn next_run(&self) -> Option<DateTime<Utc>> {
+        let after = self.last_run_at.as_deref().unwrap_or(&self.created_at);
+        let after = DateTime::parse_from_rfc3339(after).ok()?.with_timezone(&Utc);
+        parse_cron(&self.cron).ok()?.find_next_occurrence(&after, false).ok()
+    }
+
+    /// Whether the task should start now. A server that was down over several runs starts it once.
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.enabled && self.next_run().is_some_and(|next| next <= now)
+    }
+}
+
+fn parse_cron(pattern: &str) -> Result<Cron, String> {
+    if pattern.split_whitespace().count() != 5 && !pattern.trim_start().starts_with('@') {
+        return Err("The schedule needs five fields: minute, hour, day of month, month, day of week".into());
+    }
+    Cron::new(pattern.trim()).parse().map_err(|e| format!("Invalid schedule \"{}\": {}", pattern, e))
+}
+
+/// Folder of one backup of a server, named by the time it was taken
+pub fn backup_dir(data_dir: &str, account_name: &str, server_number: u32, taken_at: DateTime<Utc>) -> PathBuf {
+    Path::new(data_dir)
+        .join("backups")
+        .join(account_name)
+        .join(server_number.to_string())
+        .join(taken_at.format("%Y%m%d_%H%M%S").to_string())
+}
+
+/// Copies `files` (relative to the data folder) into `dir` under the same relative paths, so a
+/// backup is restored by copying it back. Files that don't exist are skipped. Returns how many
+/// were copied.
+pub fn write_backup(data_dir: &str, dir: &Path, files: &[String]) -> std::io::Result<usize> {
+    let mut copied = 0;
+    for file in files {
+        let source = Path::new(data_dir).join(file);
+        if !source.is_file() {
+            continue;
+        }
+        let target = dir.join(file);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(&source, &target)?;
+        copied += 1;
+    }
+    Ok(copied)
+}
+
+/// Deletes all but the newest [`BACKUPS_KEPT`] backups of a server
+pub fn prune_backups(data_dir: &str, account_name: &str, server_number: u32) -> std::io::Result<()> {
+    let root = Path::new(data_dir).join("backups").join(account_name).join(server_number.to_string());
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(&root)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    // Names are timestamps, so they sort by age
+    backups.sort();
+    let excess = backups.len().saturating_sub(BACKUPS_KEPT);
+    for old in &backups[..excess] {
+        std::fs::remove_dir_all(old)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,384 @@
+use std::collections::HashSet;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Tabs};
+use ratatui::{DefaultTerminal, Frame};
+
+use kingshot_core::parser::AppointmentEntry;
+use kingshot_core::schedule::types::ScheduledAppointment;
+use kingshot_core::schedule::{derive_scheduled_player_ids, slot_to_time, DaySchedule, ScheduleData};
+
+use crate::display::format_player_name;
+use crate::error::{with_context, CliError, ErrorKind};
+
+const SLOT_COUNT: usize = 49;
+const DAY_NAMES: [&str; 3] = ["Construction Day", "Research Day", "Troops Training Day"];
+const HELP: &str = "↑↓ move  ←→/Enter pool  Tab day  c clear  s swap  w save  q quit";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Slots,
+    Pool,
+}
+
+/// Interactive editor for a schedule JSON file (`export --format json` output or a server schedule file)
+struct Editor {
+    path: PathBuf,
+    data: ScheduleData,
+    day: usize,
+    focus: Focus,
+    slots: ListState,
+    pool: ListState,
+    swap_from: Option<u8>, // Slot marked with `s`, swapped with the next slot `s` is pressed on
+    dirty: bool,
+    quit_armed: bool, // `q` pressed once with unsaved changes
+    status: String,
+}
+
+fn day_score(day: usize, entry: &AppointmentEntry) -> u32 {
+    match day {
+        0 => entry.construction_score,
+        1 => entry.research_score,
+        _ => entry.troops_speedups,
+    }
+}
+
+/// The entry's available slots for `day`, or None if they didn't ask for that day
+fn day_available_slots(day: usize, entry: &AppointmentEntry) -> Option<&Vec<u8>> {
+    match day {
+        0 => entry.wants_construction.then_some(&entry.construction_available_slots),
+        1 => entry.wants_research.then_some(&entry.research_available_slots),
+        _ => entry.wants_troops.then_some(&entry.troops_available_slots),
+    }
+}
+
+/// Opens the editor on `path` and blocks until the user quits
+pub fn run_editor(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| with_context(e, format_args!("failed to read schedule {}", path.display())))?;
+    let data: ScheduleData = serde_json::from_str(&content)
+        .map_err(|e| with_context(e, format_args!("failed to parse schedule {}", path.display())))?;
+
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return Err(CliError::new(ErrorKind::Usage, "edit needs an interactive terminal").into());
+    }
+
+    let mut editor = Editor::new(path.to_path_buf(), data);
+    let mut terminal = ratatui::try_init()?;
+    let result = editor.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl Editor {
+    fn new(path: PathBuf, data: ScheduleData) -> Self {
+        let mut slots = ListState::default();
+        slots.select(Some(0));
+        Editor {
+            path,
+            data,
+            day: 0,
+            focus: Focus::Slots,
+            slots,
+            pool: ListState::default(),
+            swap_from: None,
+            dirty: false,
+            quit_armed: false,
+            status: String::new(),
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), Box<dyn std::error::Error>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else { continue };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                return Ok(());
+            }
+
+            let quit_armed = std::mem::take(&mut self.quit_armed);
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc if self.swap_from.is_some() => {
+                    self.swap_from = None;
+                    self.status = "Swap cancelled".to_string();
+                }
+                KeyCode::Char('q') => {
+                    if !self.dirty || quit_armed {
+                        return Ok(());
+                    }
+                    self.quit_armed = true;
+                    self.status = "Unsaved changes - press q again to quit without saving, w to save".to_string();
+                }
+                KeyCode::Tab => self.select_day((self.day + 1) % DAY_NAMES.len()),
+                KeyCode::BackTab => self.select_day((self.day + DAY_NAMES.len() - 1) % DAY_NAMES.len()),
+                KeyCode::Char(c @ '1'..='3') => self.select_day(c as usize - '1' as usize),
+                KeyCode::Up | KeyCode::Char('k') => self.move_cursor(-1),
+                KeyCode::Down | KeyCode::Char('j') => self.move_cursor(1),
+                KeyCode::PageUp => self.move_cursor(-10),
+                KeyCode::PageDown => self.move_cursor(10),
+                KeyCode::Left | KeyCode::Right => self.toggle_focus(),
+                KeyCode::Enter => match self.focus {
+                    Focus::Slots => self.toggle_focus(),
+                    Focus::Pool => self.assign_selected(),
+                },
+                KeyCode::Char('c') | KeyCode::Delete | KeyCode::Backspace => self.clear_slot(),
+                KeyCode::Char('s') => self.swap(),
+                KeyCode::Char('w') => self.save(),
+                _ => {}
+            }
+        }
+    }
+
+    fn current_slot(&self) -> u8 {
+        self.slots.selected().unwrap_or(0) as u8 + 1
+    }
+
+    fn day_schedule(&self, day: usize) -> Option<&DaySchedule> {
+        match day {
+            0 => self.data.construction_schedule.as_ref(),
+            1 => self.data.research_schedule.as_ref(),
+            _ => self.data.troops_schedule.as_ref(),
+        }
+    }
+
+    fn day_schedule_mut(&mut self) -> &mut DaySchedule {
+        let schedule = match self.day {
+            0 => &mut self.data.construction_schedule,
+            1 => &mut self.data.research_schedule,
+            _ => &mut self.data.troops_schedule,
+        };
+        schedule.get_or_insert_with(|| DaySchedule {
+            appointments: Default::default(),
+            unassigned: Vec::new(),
+        })
+    }
+
+    fn day_score(&self, entry: &AppointmentEntry) -> u32 {
+        day_score(self.day, entry)
+    }
+
+    /// Players who asked for `day` but have no slot on it, highest priority first
+    fn pool_entries(&self, day: usize) -> Vec<&AppointmentEntry> {
+        let assigned: HashSet<&str> = self.day_schedule(day)
+            .map(|s| s.appointments.values().map(|a| a.player_id.as_str()).collect())
+            .unwrap_or_default();
+        let mut pool: Vec<&AppointmentEntry> = self.data.entries.iter()
+            .flatten()
+            .filter(|e| day_available_slots(day, e).is_some() && !assigned.contains(e.player_id.as_str()))
+            .collect();
+        pool.sort_by(|a, b| day_score(day, b).cmp(&day_score(day, a)).then(a.name.cmp(&b.name)));
+        pool
+    }
+
+    fn select_day(&mut self, day: usize) {
+        self.day = day;
+        self.swap_from = None;
+        self.clamp_pool();
+    }
+
+    fn toggle_focus(&mut self) {
+        self.focus = match self.focus {
+            Focus::Slots => Focus::Pool,
+            Focus::Pool => Focus::Slots,
+        };
+        self.clamp_pool();
+    }
+
+    fn clamp_pool(&mut self) {
+        let len = self.pool_entries(self.day).len();
+        let selected = self.pool.selected().unwrap_or(0);
+        self.pool.select(if len == 0 { None } else { Some(selected.min(len - 1)) });
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        let (state, len) = match self.focus {
+            Focus::Slots => (&mut self.slots, SLOT_COUNT),
+            Focus::Pool => {
+                let len = self.pool_entries(self.day).len();
+                (&mut self.pool, len)
+            }
+        };
+        if len == 0 {
+            return;
+        }
+        let current = state.selected().unwrap_or(0) as isize;
+        state.select(Some((current + delta).clamp(0, len as isize - 1) as usize));
+    }
+
+    fn assign_selected(&mut self) {
+        let Some(index) = self.pool.selected() else {
+            self.status = "Nobody left in the pool for this day".to_string();
+            return;
+        };
+        let Some(entry) = self.pool_entries(self.day).get(index).map(|e| (*e).clone()) else { return };
+        let slot = self.current_slot();
+        let available = day_available_slots(self.day, &entry).is_some_and(|slots| slots.contains(&slot));
+        let appointment = ScheduledAppointment {
+            player_id: entry.player_id.clone(),
+            name: entry.name.clone(),
+            alliance: entry.alliance.clone(),
+            slot,
+            priority_score: self.day_score(&entry),
+            note: None,
+            manual: true,
+        };
+
+        let replaced = self.day_schedule_mut().appointments.insert(slot, appointment);
+        self.dirty = true;
+        self.status = format!("{} {} -> {}", slot_to_time(slot), format_player_name(&entry.alliance, &entry.name), match replaced {
+            Some(old) => format!("replaced {}", format_player_name(&old.alliance, &old.name)),
+            None => "assigned".to_string(),
+        });
+        if !available {
+            self.status.push_str(" (not in their available times)");
+        }
+        self.clamp_pool();
+    }
+
+    fn clear_slot(&mut self) {
+        let slot = self.current_slot();
+        if let Some(old) = self.day_schedule_mut().appointments.remove(&slot) {
+            self.dirty = true;
+            self.status = format!("{} cleared ({} back in the pool)", slot_to_time(slot), format_player_name(&old.alliance, &old.name));
+            self.clamp_pool();
+        }
+    }
+
+    fn swap(&mut self) {
+        let slot = self.current_slot();
+        let Some(from) = self.swap_from.take() else {
+            self.swap_from = Some(slot);
+            self.status = format!("Swapping {} - move to another slot and press s (Esc cancels)", slot_to_time(slot));
+            return;
+        };
+        if from == slot {
+            self.status = "Swap cancelled".to_string();
+            return;
+        }
+
+        let appointments = &mut self.day_schedule_mut().appointments;
+        let a = appointments.remove(&from);
+        let b = appointments.remove(&slot);
+        if let Some(mut appt) = a {
+            appt.slot = slot;
+            appointments.insert(slot, appt);
+        }
+        if let Some(mut appt) = b {
+            appt.slot = from;
+            appointments.insert(from, appt);
+        }
+        self.dirty = true;
+        self.status = format!("Swapped {} and {}", slot_to_time(from), slot_to_time(slot));
+    }
+
+    fn save(&mut self) {
+        // Players who wanted a day but have no slot on it are that day's unassigned list
+        if self.data.entries.is_some() {
+            let unassigned: Vec<Vec<String>> = (0..DAY_NAMES.len())
+                .map(|day| self.pool_entries(day).iter().map(|e| e.player_id.clone()).collect())
+                .collect();
+            let days = [&mut self.data.construction_schedule, &mut self.data.research_schedule, &mut self.data.troops_schedule];
+            for (schedule, unassigned) in days.into_iter().zip(unassigned) {
+                if let Some(schedule) = schedule {
+                    schedule.unassigned = unassigned;
+                }
+            }
+        }
+        self.data.scheduled_player_ids = Some(derive_scheduled_player_ids(&self.data).into_iter().collect());
+
+        let result = serde_json::to_string_pretty(&self.data)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&self.path, json).map_err(|e| e.to_string()));
+        match result {
+            Ok(()) => {
+                self.dirty = false;
+                self.status = format!("Saved {}", self.path.display());
+            }
+            Err(e) => self.status = format!("Save failed: {}", e),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [tabs_area, main_area, status_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(2),
+        ]).areas(frame.area());
+        let [slots_area, pool_area] = Layout::horizontal([
+            Constraint::Percentage(60),
+            Constraint::Percentage(40),
+        ]).areas(main_area);
+
+        let highlight = Style::default().fg(Color::Black).bg(Color::Cyan).add_modifier(Modifier::BOLD);
+        let focused = |focus: Focus| if self.focus == focus { Style::default().fg(Color::Cyan) } else { Style::default() };
+
+        let title = format!("{}{}", self.path.display(), if self.dirty { " *" } else { "" });
+        frame.render_widget(
+            Tabs::new(DAY_NAMES.iter().enumerate().map(|(i, name)| format!("{} {}", i + 1, name)))
+                .select(self.day)
+                .highlight_style(highlight),
+            tabs_area,
+        );
+        frame.render_widget(Line::from(title).right_aligned(), tabs_area);
+
+        // Slots
+        let schedule = self.day_schedule(self.day);
+        let slot_items: Vec<ListItem> = (1..=SLOT_COUNT as u8).map(|slot| {
+            let marker = if self.swap_from == Some(slot) { "⇄ " } else { "  " };
+            let line = match schedule.and_then(|s| s.appointments.get(&slot)) {
+                Some(appt) => Line::from(vec![
+                    Span::raw(format!("{}{} ", marker, slot_to_time(slot))),
+                    Span::raw(format_player_name(&appt.alliance, &appt.name)),
+                    Span::styled(format!("  {}", appt.priority_score), Style::default().fg(Color::DarkGray)),
+                ]),
+                None => Line::from(vec![
+                    Span::raw(format!("{}{} ", marker, slot_to_time(slot))),
+                    Span::styled("[EMPTY]", Style::default().fg(Color::DarkGray)),
+                ]),
+            };
+            ListItem::new(line)
+        }).collect();
+        let filled = schedule.map(|s| s.appointments.len()).unwrap_or(0);
+        let slots = List::new(slot_items)
+            .block(Block::bordered().title(format!(" Slots {}/{} ", filled, SLOT_COUNT)).border_style(focused(Focus::Slots)))
+            .highlight_style(highlight);
+        frame.render_stateful_widget(slots, slots_area, &mut self.slots);
+
+        // Unassigned pool, marking who is available at the selected slot
+        let slot = self.current_slot();
+        let pool = self.pool_entries(self.day);
+        let pool_items: Vec<ListItem> = pool.iter().map(|entry| {
+            let available = day_available_slots(self.day, entry).is_some_and(|slots| slots.contains(&slot));
+            ListItem::new(Line::from(vec![
+                Span::styled(if available { "✓ " } else { "  " }, Style::default().fg(Color::Green)),
+                Span::raw(format_player_name(&entry.alliance, &entry.name)),
+                Span::styled(format!("  {}", self.day_score(entry)), Style::default().fg(Color::DarkGray)),
+            ]))
+        }).collect();
+        let pool_title = if self.data.entries.is_some() {
+            format!(" Unassigned ({}) - ✓ available at {} ", pool.len(), slot_to_time(slot))
+        } else {
+            " Unassigned - no entries in this file ".to_string()
+        };
+        let pool_list = List::new(pool_items)
+            .block(Block::bordered().title(pool_title).border_style(focused(Focus::Pool)))
+            .highlight_style(highlight);
+        frame.render_stateful_widget(pool_list, pool_area, &mut self.pool);
+
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::styled(HELP, Style::default().fg(Color::DarkGray)),
+                Line::raw(self.status.as_str()),
+            ]),
+            status_area,
+        );
+    }
+}
@@ -1,16 +1,38 @@
-use actix_web::{web, App, HttpServer, HttpResponse, Result, HttpRequest, middleware, cookie::Key};
+use actix_web::{web, App, HttpServer, HttpResponse, Result, HttpRequest, middleware, cookie::Key, dev::Service};
 use actix_files::Files;
-use actix_session::{Session, SessionMiddleware, storage::CookieSessionStore};
+use actix_cors::Cors;
+use askama::Template;
+use actix_session::{Session, SessionExt, SessionMiddleware, storage::CookieSessionStore};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Mutex;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use rand::Rng;
-use crate::parser::{load_appointments, AppointmentEntry};
-use crate::schedule::{schedule_construction_day, schedule_construction_day_with_locked, schedule_research_day, schedule_research_day_with_locked, schedule_troops_day, schedule_troops_day_with_locked, DaySchedule, slot_to_time, calculate_time_slots};
-use crate::schedule::types::ScheduledAppointment;
-use crate::display::format_player_name;
-use crate::form::{FormSubmissionRequest, FormSubmission, validate_submission, export_submission_to_csv};
+use chrono::Timelike;
+use kingshot_core::parser::{load_appointments, read_mapped_appointments, AppointmentEntry, ColumnMapping, SubmissionColumns, SUBMISSION_FIELDS};
+use kingshot_core::schedule::{schedule_construction_day, schedule_construction_day_with_locked, schedule_research_day, schedule_troops_day, DaySchedule, slot_to_time, remove_blackout_slots, validate_day_windows};
+use kingshot_core::schedule::{check_predetermined, generate_schedules, merge_appended, regenerate_day, DayTimeConfig, DayTimeSlots, ExistingDays, GuaranteedPlayers, PredeterminedSlot};
+use kingshot_core::schedule::{derive_scheduled_player_ids, get_scheduled_player_ids, ScheduleData};
+use kingshot_core::schedule::types::ScheduledAppointment;
+use crate::display::{format_player_name, split_discord_messages, write_schedule_discord};
+use crate::form::{FormSubmissionRequest, FormSubmission, FormDraft, FormContent, validate_blackout_slots, validate_passcode, validate_submission, export_submission_to_csv, append_submission_rows, find_player_rows, merge_player_rows, remove_player_rows, rename_alliance_rows, SUBMISSION_CSV_COLUMNS};
+use crate::form::draft::{self, MAX_DRAFT_BYTES, MAX_DRAFTS_PER_FORM};
+use crate::form::content::render_markdown;
+use crate::form::metadata::{self, SubmissionMetadata};
+use crate::form::duplicates::{self, DuplicateFlag};
+use crate::mailer::{self, AssignedDay, AssignedSlot, Mailer, PasswordResetEmail, ReminderEmail, SlotAssignmentsEmail};
+use crate::notify::{Notification, NotificationChannel, NotificationEvent, NotificationSettings, Notifier};
+use crate::tasks::{self, ScheduledTask, TaskKind, MAX_TASKS_PER_SERVER};
+use crate::jobs::{JobProgress, JobQueue, MAX_RUNNING_JOBS};
+use crate::storage::{self, write_atomic};
+use crate::config::{load_server_config, AccountLimits, CorsConfig, PowerExtractionConfig, SessionConfig};
+use crate::sheets::{parse_spreadsheet_id, SheetTab, SheetsClient};
+use kingshot_core::parser::ScoreWeights;
+use kingshot_core::power::{apply_power_scores, match_power_records, read_power_records, write_power_records, PowerMatch, PowerRecord};
+use kingshot_core::formula::{apply_score_formulas, ScoreFormula};
+use kingshot_core::roster::{apply_roster, discord_mentions, normalize_discord_id, read_roster, write_roster, RosterEntry};
+use actix_multipart::Multipart;
+use futures_util::StreamExt;
 use std::collections::HashSet;
 
 // Account structure
@@ -20,63 +42,185 @@ pub struct Account {
     pub server_number: u32,
     pub password: String,
     pub in_game_name: String,
+    /// Servers managed in addition to `server_number` (the server the account was created with)
+    #[serde(default)]
+    pub additional_servers: Vec<u32>,
+    /// Limited logins created by the owner, each restricted to one alliance
+    #[serde(default)]
+    pub sub_accounts: Vec<SubAccount>,
+    /// Set by the operator; suspended accounts cannot log in or collect submissions
+    #[serde(default)]
+    pub suspended: bool,
+    /// Custom public URL per server: `/{slug}` instead of `/view/{account_name}/{server}`
+    #[serde(default)]
+    pub slugs: HashMap<u32, String>,
+    #[serde(default)]
+    pub branding: Branding,
+    /// Operator overrides of the instance-wide limits in server_config.json
+    #[serde(default)]
+    pub limits: AccountLimits,
+    /// Ownership transfer started by the owner and not yet claimed
+    #[serde(default)]
+    pub pending_transfer: Option<OwnershipTransfer>,
+    /// Bumped when ownership changes; sessions from an older version are logged out
+    #[serde(default)]
+    pub session_version: u32,
+    /// Servers whose score leaderboard is visible without logging in
+    #[serde(default)]
+    pub public_leaderboards: HashSet<u32>,
+    /// Seasons grouping the weekly forms of each server
+    #[serde(default)]
+    pub seasons: Vec<Season>,
+    /// Google Sheets spreadsheet ID per server the schedule is published to
+    #[serde(default)]
+    pub google_sheets: HashMap<u32, String>,
+    /// Google Form response sheets pulled into the submissions of each server
+    #[serde(default)]
+    pub form_syncs: HashMap<u32, FormSync>,
+    /// Columns of uploaded CSVs whose headers aren't recognized, by field name
+    #[serde(default)]
+    pub csv_columns: ColumnMapping,
+    /// Record a hashed IP address, user agent and form version with each submission
+    #[serde(default)]
+    pub submission_metadata: bool,
+    /// Salt of the IP hashes, generated when recording is first turned on
+    #[serde(default)]
+    pub metadata_salt: String,
+    /// Let returning players fill the form with their days and times of an earlier submission
+    #[serde(default)]
+    pub prefill_answers: bool,
+    /// Where notifications about the account's forms and schedules go, and which ones
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    /// Address password reset codes are sent to
+    #[serde(default)]
+    pub recovery_email: Option<String>,
+    /// Password reset requested through the login page and not yet used
+    #[serde(default)]
+    pub pending_reset: Option<PasswordReset>,
+    /// Recurring tasks of each server, run by the built-in scheduler
+    #[serde(default)]
+    pub scheduled_tasks: HashMap<u32, Vec<ScheduledTask>>,
 }
 
-// Schedule data for an account/server
+// A Google Form response sheet whose new rows are imported as submissions every interval_minutes
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ScheduleData {
-    pub construction_schedule: Option<DaySchedule>,
-    pub research_schedule: Option<DaySchedule>,
-    pub troops_schedule: Option<DaySchedule>,
-    pub entries: Option<Vec<AppointmentEntry>>,
-    /// Player IDs that are assigned in the schedule (for ID-based append logic).
-    /// Populated when saving; derived from appointments when loading if missing (backward compat).
+pub struct FormSync {
+    pub spreadsheet_id: String,
+    pub sheet: String, // Tab holding the responses
+    pub interval_minutes: u32,
     #[serde(default)]
-    pub scheduled_player_ids: Option<Vec<String>>,
+    pub last_synced_at: Option<String>, // RFC 3339, of the last attempt
+    #[serde(default)]
+    pub last_error: Option<String>, // None if the last attempt worked
+    #[serde(default)]
+    pub last_imported: usize, // Responses that were new in the last sync
 }
 
-/// Derives the set of scheduled player IDs from schedule appointments
-fn derive_scheduled_player_ids(data: &ScheduleData) -> HashSet<String> {
-    let mut ids = HashSet::new();
-    for appt in data.construction_schedule.as_ref().iter().flat_map(|s| s.appointments.values()) {
-        ids.insert(appt.player_id.clone());
-    }
-    for appt in data.research_schedule.as_ref().iter().flat_map(|s| s.appointments.values()) {
-        ids.insert(appt.player_id.clone());
-    }
-    for appt in data.troops_schedule.as_ref().iter().flat_map(|s| s.appointments.values()) {
-        ids.insert(appt.player_id.clone());
-    }
-    ids
+// A run of weekly forms on one server; a form belongs to the season if it was created between started_at and ended_at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Season {
+    pub id: String,
+    pub name: String,
+    pub server_number: u32,
+    pub started_at: String, // RFC 3339
+    #[serde(default)]
+    pub ended_at: Option<String>, // RFC 3339, None while the season is running
 }
 
-/// Returns the set of scheduled player IDs, deriving from appointments if not stored
-fn get_scheduled_player_ids(data: &ScheduleData) -> HashSet<String> {
-    data.scheduled_player_ids.as_ref()
-        .map(|v| v.iter().cloned().collect())
-        .unwrap_or_else(|| derive_scheduled_player_ids(data))
+impl Season {
+    // Whether a form created at `created_at` (RFC 3339) falls inside the season
+    fn contains(&self, created_at: &str) -> bool {
+        let created_at = match chrono::DateTime::parse_from_rfc3339(created_at) {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+        let started = chrono::DateTime::parse_from_rfc3339(&self.started_at).is_ok_and(|start| created_at >= start);
+        let not_ended = match self.ended_at.as_deref().map(chrono::DateTime::parse_from_rfc3339) {
+            Some(Ok(end)) => created_at < end,
+            Some(Err(_)) => false,
+            None => true,
+        };
+        started && not_ended
+    }
 }
 
-// Admin configuration for form settings
+// One-time code the current owner hands to the next owner
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DayTimeConfig {
-    pub start_time: String, // Format: "HH:MM" (e.g., "00:20")
-    pub end_time: Option<String>, // Format: "HH:MM", defaults to start_time + 24 hours if None
+pub struct OwnershipTransfer {
+    pub code: String,
+    pub expires_at: String, // RFC 3339
 }
 
-// Predetermined slot assignment - locks a specific time slot to a player
-// Primary identifier is player_id; alliance/name kept for display and backward compatibility
+// One-time code emailed to the recovery address for setting a new password
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PredeterminedSlot {
-    pub day: String, // "construction", "research", or "troops"
-    pub time: String, // Time string like "00:20"
-    /// Canonical player identifier - required for ID-based logic
+pub struct PasswordReset {
+    pub code: String,
+    pub expires_at: String, // RFC 3339
+}
+
+// Per-account look of the public schedule and form pages; unset fields use the defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Branding {
     #[serde(default)]
-    pub player_id: Option<String>,
+    pub title: Option<String>,
     #[serde(default)]
-    pub alliance: String,
+    pub accent_color: Option<String>, // Hex color, e.g. "#f97316"
     #[serde(default)]
-    pub name: String,
+    pub logo_url: Option<String>,
+    #[serde(default)]
+    pub footer_text: Option<String>,
+}
+
+// Alliance-scoped login: can view everything on the account but only edit its own alliance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubAccount {
+    /// New for every login created, so sessions of a deleted login don't carry over to a new
+    /// one with the same username. Empty for logins created before ids existed.
+    #[serde(default)]
+    pub id: String,
+    pub username: String,
+    pub password: String,
+    pub alliance: String,
+}
+
+impl Account {
+    /// All servers owned by this account, primary server first
+    pub fn servers(&self) -> Vec<u32> {
+        let mut servers = vec![self.server_number];
+        servers.extend(self.additional_servers.iter().copied());
+        servers
+    }
+    
+    pub fn owns_server(&self, server_number: u32) -> bool {
+        self.server_number == server_number || self.additional_servers.contains(&server_number)
+    }
+    
+    /// Gives the account to a new owner. Everything the previous owner could get back in with
+    /// (logins, sessions, the recovery email and its reset code) or keeps receiving mail and
+    /// webhooks through is cleared.
+    pub fn hand_over(&mut self, password: String, in_game_name: String) {
+        self.password = password;
+        self.in_game_name = in_game_name;
+        self.pending_transfer = None;
+        self.sub_accounts.clear();
+        self.session_version += 1;
+        self.recovery_email = None;
+        self.pending_reset = None;
+        self.notifications = NotificationSettings::default();
+    }
+    
+    /// Starts a password reset: the recovery email and a new code to send to it, None if the
+    /// account has no recovery email
+    pub fn start_password_reset(&mut self) -> Option<(String, String)> {
+        let to = self.recovery_email.clone()?;
+        let reset = PasswordReset {
+            code: generate_form_code(),
+            expires_at: (chrono::Local::now() + chrono::Duration::minutes(RESET_CODE_MINUTES)).to_rfc3339(),
+        };
+        self.pending_reset = Some(reset.clone());
+        Some((to, reset.code))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,27 +232,201 @@ pub struct FormConfig {
     #[serde(default)]
     pub predetermined_slots: Vec<PredeterminedSlot>, // Predetermined slot assignments
     #[serde(default)]
-    pub intro_text: Option<String>, // Optional introduction text displayed at the top of the form
+    pub guaranteed_players: GuaranteedPlayers, // Player IDs per day who get some slot, at any time
+    #[serde(default)]
+    pub intro_text: Option<String>, // When set, the form shows the standard (translated) introduction unless content has its own
+    #[serde(default)]
+    pub content: FormContent, // Markdown introduction and per-day descriptions
+    #[serde(default)]
+    pub public_stats: PublicStats, // What the public statistics pages show
+    #[serde(default)]
+    pub power: Option<FormPower>, // Power levels weighted into priority scores
+    #[serde(default)]
+    pub language_packs: BTreeMap<String, LanguagePack>, // Language code -> form texts in that language
+    #[serde(default)]
+    pub score_formulas: ScoreFormulas, // Custom priority scores instead of the default weights
+    #[serde(default)]
+    pub passcodes: FormPasscodes, // Never sent to the public form page
+    #[serde(default)]
+    pub closes_at: Option<String>, // RFC 3339; submissions are refused from then on
+    #[serde(default)]
+    pub auto_generate: bool, // Generate the schedule once the form closes
+}
+
+impl FormConfig {
+    // Whether the form's close time has passed
+    fn is_closed(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.closes_at.as_deref()
+            .and_then(|closes_at| chrono::DateTime::parse_from_rfc3339(closes_at).ok())
+            .is_some_and(|closes_at| closes_at <= now)
+    }
+}
+
+// Checks a close time sent by an admin
+fn validate_closes_at(closes_at: Option<&str>) -> std::result::Result<(), String> {
+    match closes_at {
+        Some(closes_at) if chrono::DateTime::parse_from_rfc3339(closes_at).is_err() => {
+            Err(format!("\"{}\" is not a valid close time", closes_at))
+        }
+        _ => Ok(()),
+    }
+}
+
+// Passcodes players must enter to submit under an alliance, so people outside the state who
+// find the link can't submit under its tags
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FormPasscodes {
+    #[serde(default)]
+    pub alliances: BTreeMap<String, String>, // Alliance name -> passcode
+    #[serde(default)]
+    pub form: Option<String>, // Needed for every alliance without its own, including "Non of the above"
+}
+
+const MAX_PASSCODE_LENGTH: usize = 100;
+
+impl FormPasscodes {
+    // Drops blank passcodes and those of alliances the form doesn't list
+    fn trimmed(self, alliances: &[String]) -> FormPasscodes {
+        let keep = |passcode: String| Some(passcode.trim().to_string()).filter(|p| !p.is_empty());
+        FormPasscodes {
+            alliances: self.alliances.into_iter()
+                .filter(|(alliance, _)| alliances.contains(alliance))
+                .filter_map(|(alliance, passcode)| Some((alliance, keep(passcode)?)))
+                .collect(),
+            form: self.form.and_then(keep),
+        }
+    }
+
+    fn validate(&self) -> std::result::Result<(), String> {
+        if self.alliances.values().chain(&self.form).any(|p| p.chars().count() > MAX_PASSCODE_LENGTH) {
+            return Err(format!("Passcodes can be at most {} characters long", MAX_PASSCODE_LENGTH));
+        }
+        Ok(())
+    }
+
+    // The passcode a submission needs. A custom alliance name matching a listed alliance
+    // needs that alliance's passcode, so "Non of the above" can't be used to get around it.
+    fn required_for(&self, alliance: &str, custom_alliance: &str) -> Option<&str> {
+        let name = if alliance == "Non of the above" { custom_alliance.trim() } else { alliance };
+        self.alliances.iter()
+            .find(|(listed, _)| listed.trim().eq_ignore_ascii_case(name))
+            .map(|(_, passcode)| passcode.as_str())
+            .or(self.form.as_deref())
+    }
+
+    // Alliances with their own passcode, for the form page to ask for it
+    fn protected_alliances(&self) -> Vec<&str> {
+        self.alliances.keys().map(String::as_str).collect()
+    }
+}
+
+// Per-day priority score formulas, e.g. "truegold*1500 + speedups*40 + power/1e6", see kingshot_core::formula.
+// Troops Training day always ranks by speedups.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScoreFormulas {
+    #[serde(default)]
+    pub construction: Option<String>,
+    #[serde(default)]
+    pub research: Option<String>,
+}
+
+impl ScoreFormulas {
+    // Drops blank formulas, which mean the default weights
+    fn trimmed(self) -> ScoreFormulas {
+        let keep = |formula: Option<String>| formula.map(|f| f.trim().to_string()).filter(|f| !f.is_empty());
+        ScoreFormulas { construction: keep(self.construction), research: keep(self.research) }
+    }
+    
+    // Parsed construction and research formulas; the error names the day whose formula is invalid
+    fn parsed(&self) -> std::result::Result<(Option<ScoreFormula>, Option<ScoreFormula>), String> {
+        let parse = |formula: &Option<String>, day: &str| {
+            formula.as_deref()
+                .map(|f| f.parse::<ScoreFormula>().map_err(|e| format!("Invalid {} day formula: {}", day, e)))
+                .transpose()
+        };
+        Ok((parse(&self.construction, "Construction")?, parse(&self.research, "Research")?))
+    }
+}
+
+// Form texts in one language. Keys are the form page's text IDs (e.g. "characterNameQuestion",
+// "pleaseSelectAlliance"); missing ones fall back to the built-in translation or English.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LanguagePack {
+    pub name: String, // Shown in the form's language selector, e.g. "Tiếng Việt"
+    #[serde(default)]
+    pub strings: BTreeMap<String, String>,
+}
+
+const MAX_LANGUAGE_PACKS: usize = 30;
+const MAX_LANGUAGE_PACK_STRINGS: usize = 500;
+const MAX_LANGUAGE_PACK_TEXT: usize = 10_000;
+
+// Checks language packs sent by an admin before they are stored with the form
+fn validate_language_packs(packs: &BTreeMap<String, LanguagePack>) -> std::result::Result<(), String> {
+    if packs.len() > MAX_LANGUAGE_PACKS {
+        return Err(format!("A form can have at most {} language packs", MAX_LANGUAGE_PACKS));
+    }
+    for (code, pack) in packs {
+        let valid_code = (2..=12).contains(&code.len())
+            && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && !code.starts_with('-');
+        if !valid_code {
+            return Err(format!("\"{}\" is not a language code, use one like \"vi\" or \"pt-BR\"", code));
+        }
+        if pack.name.trim().is_empty() {
+            return Err(format!("The {} language pack needs a name", code));
+        }
+        if pack.strings.len() > MAX_LANGUAGE_PACK_STRINGS {
+            return Err(format!("The {} language pack has more than {} texts", code, MAX_LANGUAGE_PACK_STRINGS));
+        }
+        if let Some(key) = pack.strings.iter().find(|(_, text)| text.len() > MAX_LANGUAGE_PACK_TEXT).map(|(key, _)| key) {
+            return Err(format!("The {} text of the {} language pack is too long", key, code));
+        }
+    }
+    Ok(())
+}
+
+// Power level upload attached to a form, added to construction and research priority scores
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormPower {
+    pub job_id: String, // Finished power level job of the same account/server
+    #[serde(default)]
+    pub baseline_job_id: Option<String>, // Earlier job the power gain is measured against
+    #[serde(default)]
+    pub power_per_million: u32,
+    #[serde(default)]
+    pub power_gain_per_million: u32,
+}
+
+// How much of a form's statistics visitors see without logging in; admins always see everything
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PublicStats {
+    #[default]
+    Full,
+    HideAlliances, // No per-alliance counts, resources or heatmap columns
+    SlotsOnly, // Time slot popularity only
+    Disabled,
 }
 
 impl Default for FormConfig {
     fn default() -> Self {
         FormConfig {
             alliances: vec![], // No default alliances - admin must input them
-            construction_times: DayTimeConfig {
-                start_time: "00:00".to_string(),
-                end_time: None,
-            },
-            research_times: DayTimeConfig {
-                start_time: "00:00".to_string(),
-                end_time: None,
-            },
-            troops_times: DayTimeConfig {
-                start_time: "00:00".to_string(),
-                end_time: None,
-            },
+            construction_times: DayTimeConfig::new("00:00", None),
+            research_times: DayTimeConfig::new("00:00", None),
+            troops_times: DayTimeConfig::new("00:00", None),
             predetermined_slots: vec![], // No predetermined slots by default
+            guaranteed_players: GuaranteedPlayers::default(),
             intro_text: None, // No intro text by default
+            content: FormContent::default(),
+            public_stats: PublicStats::Full,
+            power: None,
+            language_packs: BTreeMap::new(),
+            score_formulas: ScoreFormulas::default(),
+            passcodes: FormPasscodes::default(),
+            closes_at: None,
+            auto_generate: false,
         }
     }
 }
@@ -122,6 +440,16 @@ pub struct FormData {
     pub name: String, // Form name (e.g., "Week 1 Form", "January 2025 Form")
     pub created_at: String, // ISO 8601 timestamp when form was created
     pub config: FormConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_generation: Option<AutoGeneration>, // Set once the schedule was generated at the close time
+}
+
+// Outcome of generating a form's schedule automatically when it closed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoGeneration {
+    pub ran_at: String, // RFC 3339
+    pub success: bool,
+    pub message: String,
 }
 
 // App state with account-based storage
@@ -131,8 +459,31 @@ pub struct AppState {
     pub forms: Mutex<HashMap<String, FormData>>, // key: form_code (12-char alphanumeric)
     pub current_forms: Mutex<HashMap<String, String>>, // key: account_name:server_number -> form_code
     pub data_dir: String,
+    pub shared_storage: bool, // other instances write to the same data_dir
+    pub admin_password: Option<String>, // operator login, None keeps the operator dashboard off
+    pub default_limits: AccountLimits, // instance-wide limits from server_config.json
+    pub power_extraction: Option<PowerExtractionConfig>, // None disables recording uploads
+    pub power_job_slots: tokio::sync::Semaphore, // free slots for running extraction jobs
+    pub jobs: JobQueue, // schedule generation and other work polled through /api/jobs
+    pub parsed_submissions: Mutex<HashMap<String, ParsedSubmissions>>, // key: submissions CSV path
+    pub stats_locks: Mutex<HashMap<String, std::sync::Arc<Mutex<()>>>>, // key: account_name:server_number, held while computing
+    pub sheets: Option<std::sync::Arc<SheetsClient>>, // None disables publishing to Google Sheets
+    pub mailer: Option<std::sync::Arc<Mailer>>, // None when no SMTP server is configured
+    pub notifier: std::sync::Arc<Notifier>, // sends the accounts' notifications
+    pub closing_notices: Mutex<HashSet<String>>, // "form_code@closes_at" already announced as closing soon
+}
+
+// A parsed submissions CSV, reused while the file and the time slots it was read with are unchanged
+pub struct ParsedSubmissions {
+    modified: std::time::SystemTime,
+    len: u64,
+    slots: Option<[Vec<(u8, String)>; 3]>,
+    entries: Vec<AppointmentEntry>,
 }
 
+// Parsed CSVs kept at once; the cache starts over when it is full
+const MAX_PARSED_SUBMISSIONS: usize = 64;
+
 // Account creation request
 #[derive(Deserialize)]
 pub struct CreateAccountRequest {
@@ -153,3197 +504,11352 @@ pub struct CreateAccountResponse {
 pub struct LoginRequest {
     account_name: Option<String>,
     password: String,
+    #[serde(default)]
+    server_number: Option<u32>, // Server to manage after login, defaults to the primary server
+    #[serde(default)]
+    username: Option<String>, // Set when logging in with an alliance sub-account
+}
+
+// Operator login request
+#[derive(Deserialize)]
+pub struct OperatorLoginRequest {
+    password: String,
 }
 
+// Operator request to suspend or reinstate an account
+#[derive(Deserialize)]
+pub struct SuspendAccountRequest {
+    suspended: bool,
+}
+
+// Usage summary for one account on the operator dashboard
 #[derive(Serialize)]
-pub struct ServerInfo {
+pub struct AccountUsage {
     account_name: String,
+    in_game_name: String,
+    servers: Vec<u32>,
+    suspended: bool,
+    active_forms: Vec<ActiveFormUsage>,
+    total_submissions: usize,
+    last_activity: Option<String>, // RFC 3339 timestamp of the most recently modified file
+    disk_bytes: u64,
+    limits: AccountLimits, // effective limits (account overrides + instance defaults)
+    limit_overrides: AccountLimits,
+}
+
+#[derive(Serialize)]
+pub struct ActiveFormUsage {
+    code: String,
+    name: String,
     server_number: u32,
+    submissions: usize,
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct StatsResponse {
-    alliance_counts: HashMap<String, AllianceStats>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    time_slot_popularity: Option<HashMap<String, TimeSlotStats>>, // Deprecated, kept for backward compatibility
-    #[serde(skip_serializing_if = "Option::is_none")]
-    construction_start_time: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    research_start_time: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    troops_start_time: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    construction_time_slot_popularity: Option<HashMap<String, FormTimeSlotStats>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    research_time_slot_popularity: Option<HashMap<String, FormTimeSlotStats>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    troops_time_slot_popularity: Option<HashMap<String, FormTimeSlotStats>>,
+// Request body for creating an alliance sub-account
+#[derive(Deserialize)]
+pub struct CreateSubAccountRequest {
+    username: String,
+    password: String,
+    alliance: String,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct AllianceStats {
-    construction_requests: u32,
-    research_requests: u32,
-    troops_requests: u32,
+#[derive(Deserialize)]
+pub struct LeaderboardQuery {
+    limit: Option<usize>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct TimeSlotStats {
-    construction_requests: u32,
-    research_requests: u32,
-    troops_requests: u32,
+// Request body for making a server's leaderboard public or admin-only
+#[derive(Deserialize)]
+pub struct LeaderboardVisibilityRequest {
+    public: bool,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct FormTimeSlotStats {
-    requests: u32,
+// One row of the score leaderboard. Player IDs are left out because the leaderboard can be public.
+#[derive(Serialize)]
+pub struct LeaderboardEntry {
+    rank: usize,
+    name: String,
+    alliance: String,
+    score: u32,
 }
 
+// Archived form as listed in the archive browser
 #[derive(Serialize)]
-pub struct ScheduleResponse {
-    day_name: String,
-    appointments: Vec<ScheduleSlot>,
+pub struct ArchivedFormInfo {
+    id: String, // archive timestamp, YYYYMMDD_HHMMSS
+    code: String,
+    name: String,
+    created_at: String,
+    archived_at: String,
+    submissions: usize,
+    has_schedule: bool,
 }
 
+// Archived schedule as listed in the archive browser
 #[derive(Serialize)]
-pub struct ScheduleSlot {
-    time: String,
-    player: Option<String>,
-    is_empty: bool,
+pub struct ArchivedScheduleInfo {
+    id: String,
+    archived_at: String,
 }
 
-// Helper function to load accounts from file
-fn load_accounts(data_dir: &str) -> HashMap<String, Account> {
-    let accounts_path = format!("{}/accounts.json", data_dir);
-    if Path::new(&accounts_path).exists() {
-        if let Ok(content) = std::fs::read_to_string(&accounts_path) {
-            if let Ok(accounts) = serde_json::from_str::<HashMap<String, Account>>(&content) {
-                return accounts;
-            }
-        }
-    }
-    HashMap::new()
+// Request body for starting a season
+#[derive(Deserialize)]
+pub struct StartSeasonRequest {
+    name: String,
+    /// Start the season with the server's current form instead of the next one
+    #[serde(default)]
+    include_current_form: bool,
 }
 
-// Helper function to save accounts to file
-fn save_accounts(data_dir: &str, accounts: &HashMap<String, Account>) -> std::io::Result<()> {
-    std::fs::create_dir_all(data_dir)?;
-    let accounts_path = format!("{}/accounts.json", data_dir);
-    let content = serde_json::to_string_pretty(accounts)?;
-    std::fs::write(&accounts_path, content)?;
-    Ok(())
+// Request body for renaming a season
+#[derive(Deserialize)]
+pub struct RenameSeasonRequest {
+    name: String,
 }
 
-// Helper function to get schedule key
-fn schedule_key(account_name: &str, server_number: u32) -> String {
-    format!("{}:{}", account_name, server_number)
+// Season as listed in the dashboard
+#[derive(Serialize)]
+pub struct SeasonInfo {
+    id: String,
+    name: String,
+    started_at: String,
+    ended_at: Option<String>,
+    active: bool,
+    weeks: usize,
 }
 
-// Helper function to get the current form for an account/server
-fn get_current_form(forms: &HashMap<String, FormData>, current_forms: &HashMap<String, String>, account_name: &str, server_number: u32) -> Option<FormData> {
-    let account_name_lower = account_name.to_lowercase();
-    let key = schedule_key(&account_name_lower, server_number);
-    if let Some(form_code) = current_forms.get(&key) {
-        forms.get(form_code).cloned()
-    } else {
-        // Fallback: get most recent form by created_at (case-insensitive account_name comparison)
-        forms.values()
-            .filter(|f| f.account_name.to_lowercase() == account_name_lower && f.server_number == server_number)
-            .max_by_key(|f| &f.created_at)
-            .cloned()
-    }
+// One form of a season; archive_id is None for the server's current form
+#[derive(Serialize)]
+pub struct SeasonWeekInfo {
+    form_code: String,
+    form_name: String,
+    created_at: String,
+    archive_id: Option<String>,
+    players: usize,
+    scheduled: usize,
 }
 
-// Helper function to load current forms mapping
-fn load_current_forms(data_dir: &str) -> HashMap<String, String> {
-    let path = format!("{}/current_forms_map.json", data_dir);
-    if Path::new(&path).exists() {
-        if let Ok(content) = std::fs::read_to_string(&path) {
-            if let Ok(mapping) = serde_json::from_str::<HashMap<String, String>>(&content) {
-                return mapping;
-            }
-        }
-    }
-    HashMap::new()
+// What a player did over the weeks of a season
+#[derive(Serialize, Default)]
+pub struct PlayerSeasonHistory {
+    player_id: String,
+    name: String,
+    alliance: String,
+    weeks_submitted: u32,
+    weeks_scheduled: u32,
+    construction_scheduled: u32,
+    research_scheduled: u32,
+    troops_scheduled: u32,
 }
 
-// Helper function to save current forms mapping
-fn save_current_forms(data_dir: &str, current_forms: &HashMap<String, String>) -> std::io::Result<()> {
-    std::fs::create_dir_all(data_dir)?;
-    let path = format!("{}/current_forms_map.json", data_dir);
-    let content = serde_json::to_string_pretty(current_forms)?;
-    std::fs::write(&path, content)?;
-    Ok(())
+// Query for the week-over-week comparison: minimum change in percent for a resource to be reported
+#[derive(Deserialize)]
+pub struct ParticipationCompareQuery {
+    threshold: Option<u32>,
 }
 
-// Helper function to save schedule to disk
-fn save_schedule(data_dir: &str, account_name: &str, server_number: u32, schedule_data: &ScheduleData) -> std::io::Result<()> {
-    let schedules_dir = format!("{}/schedules/{}", data_dir, account_name);
-    std::fs::create_dir_all(&schedules_dir)?;
-    let path = format!("{}/{}.json", schedules_dir, server_number);
-    let content = serde_json::to_string_pretty(schedule_data)?;
-    std::fs::write(&path, content)?;
-    Ok(())
+// Player listed in the week-over-week comparison
+#[derive(Serialize)]
+pub struct ParticipationPlayer {
+    player_id: String,
+    name: String,
+    alliance: String,
 }
 
-// Helper function to load schedule from disk
-fn load_schedule(data_dir: &str, account_name: &str, server_number: u32) -> Option<ScheduleData> {
-    let path = format!("{}/schedules/{}/{}.json", data_dir, account_name, server_number);
-    if Path::new(&path).exists() {
-        if let Ok(content) = std::fs::read_to_string(&path) {
-            match serde_json::from_str::<ScheduleData>(&content) {
-                Ok(schedule_data) => return Some(schedule_data),
-                Err(e) => {
-                    eprintln!("Failed to deserialize schedule from {}: {}", path, e);
-                    return None;
-                }
-            }
-        } else {
-            eprintln!("Failed to read schedule file: {}", path);
-        }
-    }
-    None
+// A claimed resource that changed by at least the threshold since the previous form
+#[derive(Serialize)]
+pub struct ResourceChange {
+    player_id: String,
+    name: String,
+    alliance: String,
+    resource: &'static str,
+    previous: u32,
+    current: u32,
+    change_percent: f64,
 }
 
-// Helper function to save statistics to disk
-fn save_statistics(data_dir: &str, account_name: &str, server_number: u32, stats: &StatsResponse) -> std::io::Result<()> {
-    let stats_dir = format!("{}/statistics/{}", data_dir, account_name);
-    std::fs::create_dir_all(&stats_dir)?;
-    let path = format!("{}/{}.json", stats_dir, server_number);
-    let content = serde_json::to_string_pretty(stats)?;
-    std::fs::write(&path, content)?;
-    Ok(())
+// Player who listed a slot; assigned_time is where they were placed that day, if anywhere
+#[derive(Serialize)]
+pub struct SlotCandidate {
+    player_id: String,
+    name: String,
+    alliance: String,
+    score: u32,
+    assigned_time: Option<String>,
 }
 
-// Helper function to load statistics from disk
-fn load_statistics(data_dir: &str, account_name: &str, server_number: u32) -> Option<StatsResponse> {
-    let path = format!("{}/statistics/{}/{}.json", data_dir, account_name, server_number);
-    if Path::new(&path).exists() {
-        if let Ok(content) = std::fs::read_to_string(&path) {
-            match serde_json::from_str::<StatsResponse>(&content) {
-                Ok(stats) => return Some(stats),
-                Err(e) => {
-                    eprintln!("Failed to deserialize statistics from {}: {}", path, e);
-                    return None;
-                }
-            }
-        } else {
-            eprintln!("Failed to read statistics file: {}", path);
-        }
-    }
-    None
+// An empty slot of a generated schedule, why it stayed empty and who could fill it
+#[derive(Serialize)]
+pub struct UnfilledSlot {
+    time: String,
+    reason: &'static str, // "nobody_available", "all_placed_elsewhere" or "available_players_unassigned"
+    candidates: Vec<SlotCandidate>,
 }
 
-// Generate a unique 12-character alphanumeric code
-fn generate_form_code() -> String {
-    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
-    let mut rng = rand::thread_rng();
-    (0..12)
-        .map(|_| {
-            let idx = rng.gen_range(0..CHARSET.len());
-            CHARSET[idx] as char
-        })
-        .collect()
+#[derive(Serialize)]
+pub struct UnfilledDay {
+    total_slots: usize,
+    unfilled: Vec<UnfilledSlot>,
 }
 
-// Helper function to load all forms from current_forms folder
-fn load_forms(data_dir: &str) -> HashMap<String, FormData> {
-    let current_forms_dir = format!("{}/current_forms", data_dir);
-    let mut forms = HashMap::new();
-    
-    if !Path::new(&current_forms_dir).exists() {
-        // Try to migrate old forms.json if it exists
-        let old_forms_path = format!("{}/forms.json", data_dir);
-        if Path::new(&old_forms_path).exists() {
-            if let Ok(content) = std::fs::read_to_string(&old_forms_path) {
-                if let Ok(old_forms) = serde_json::from_str::<HashMap<String, FormData>>(&content) {
-                    // Create directories
-                    std::fs::create_dir_all(&current_forms_dir).ok();
-                    std::fs::create_dir_all(format!("{}/old_forms", data_dir)).ok();
-                    
-                    // Move each form to current_forms
-                    for (code, mut form_data) in old_forms {
-                        // Add default name and created_at if missing (for old forms)
-                        if form_data.name.is_empty() {
-                            form_data.name = format!("Form {} {}", form_data.account_name, form_data.server_number);
-                        }
-                        if form_data.created_at.is_empty() {
-                            form_data.created_at = chrono::Local::now().to_rfc3339();
-                        }
-                        
-                        // Save account/server info before cloning
-                        let account_name = form_data.account_name.clone();
-                        let server_number = form_data.server_number;
-                        
-                        let form_path = format!("{}/{}.json", current_forms_dir, code);
-                        if let Ok(content) = serde_json::to_string_pretty(&form_data) {
-                            std::fs::write(&form_path, content).ok();
-                            forms.insert(code.clone(), form_data.clone());
-                            
-                            // Try to move old CSV file if it exists
-                            let old_csv_path = format!("{}/{}_{}_form_submissions.csv", data_dir, account_name, server_number);
-                            if Path::new(&old_csv_path).exists() {
-                                let new_csv_path = format!("{}/{}_submissions.csv", current_forms_dir, code);
-                                std::fs::copy(&old_csv_path, &new_csv_path).ok();
-                                // Keep old CSV for now (don't delete during migration)
-                            }
-                        }
-                    }
-                    // Delete old forms.json after migration
-                    std::fs::remove_file(&old_forms_path).ok();
-                }
-            }
-        }
-        return forms;
-    }
-    
-    // Load all JSON files from current_forms directory
-    if let Ok(entries) = std::fs::read_dir(&current_forms_dir) {
-        for entry in entries.flatten() {
-            if let Some(file_name) = entry.file_name().to_str() {
-                if file_name.ends_with(".json") {
-                    if let Ok(content) = std::fs::read_to_string(entry.path()) {
-                        if let Ok(form_data) = serde_json::from_str::<FormData>(&content) {
-                            forms.insert(form_data.code.clone(), form_data);
-                        }
-                    }
-                }
-            }
-        }
-    }
-    
-    forms
+// One player's assigned times across the three days (None where they have no slot)
+#[derive(Serialize)]
+pub struct PlayerItinerary {
+    player_id: String,
+    alliance: String,
+    name: String,
+    construction: Option<String>,
+    research: Option<String>,
+    troops: Option<String>,
 }
 
-// Helper function to save a single form to current_forms folder
-fn save_form(data_dir: &str, form_data: &FormData) -> std::io::Result<()> {
-    let current_forms_dir = format!("{}/current_forms", data_dir);
-    std::fs::create_dir_all(&current_forms_dir)?;
-    let form_path = format!("{}/{}.json", current_forms_dir, form_data.code);
-    let content = serde_json::to_string_pretty(form_data)?;
-    std::fs::write(&form_path, content)?;
-    Ok(())
+// Query for the itinerary: format is "json" (default) or "csv"
+#[derive(Deserialize)]
+pub struct ItineraryQuery {
+    format: Option<String>,
 }
 
-// Helper function to move old forms to old_forms folder (including CSV files)
-fn archive_old_forms(data_dir: &str, account_name: &str, server_number: u32) -> std::io::Result<()> {
-    let current_forms_dir = format!("{}/current_forms", data_dir);
-    let old_forms_dir = format!("{}/old_forms", data_dir);
-    std::fs::create_dir_all(&old_forms_dir)?;
-    
-    // Find all forms for this account/server
-    if let Ok(entries) = std::fs::read_dir(&current_forms_dir) {
-        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
-        
-        for entry in entries.flatten() {
-            if let Some(file_name) = entry.file_name().to_str() {
-                if file_name.ends_with(".json") {
-                    if let Ok(content) = std::fs::read_to_string(entry.path()) {
-                        if let Ok(form_data) = serde_json::from_str::<FormData>(&content) {
-                            // Check if this form belongs to the account/server being updated
-                            if form_data.account_name == account_name && form_data.server_number == server_number {
-                                let code = &form_data.code;
-                                
-                                // Move JSON file to old_forms
-                                let old_form_json_path = format!("{}/{}_{}_{}.json", old_forms_dir, account_name, server_number, timestamp);
-                                std::fs::copy(entry.path(), &old_form_json_path)?;
-                                std::fs::remove_file(entry.path())?;
-                                
-                                // Move CSV file if it exists
-                                let csv_file_name = format!("{}_submissions.csv", code);
-                                let csv_path = format!("{}/{}", current_forms_dir, csv_file_name);
-                                if Path::new(&csv_path).exists() {
-                                    let old_csv_path = format!("{}/{}_{}_{}_submissions.csv", old_forms_dir, account_name, server_number, timestamp);
-                                    std::fs::copy(&csv_path, &old_csv_path)?;
-                                    std::fs::remove_file(&csv_path)?;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-    
-    Ok(())
+// Request body for choosing the spreadsheet a server's schedule is published to; an empty
+// spreadsheet removes it. Takes the ID or the URL of the sheet.
+#[derive(Deserialize)]
+pub struct GoogleSheetsRequest {
+    spreadsheet: String,
 }
 
-// Create account endpoint
-async fn create_account(
-    req: web::Json<CreateAccountRequest>,
-    state: web::Data<AppState>,
-) -> Result<HttpResponse> {
-    let account_name = req.account_name.trim().to_lowercase();
-    
-    if account_name.is_empty() {
-        return Ok(HttpResponse::BadRequest().json(CreateAccountResponse {
-            success: false,
-            message: "Account name cannot be empty".to_string(),
-            schedule_url: None,
-        }));
-    }
-    
-    // Check if account already exists
-    let mut accounts = state.accounts.lock().unwrap();
-    if accounts.contains_key(&account_name) {
-        return Ok(HttpResponse::BadRequest().json(CreateAccountResponse {
-            success: false,
-            message: "Account name already exists".to_string(),
-            schedule_url: None,
-        }));
-    }
-    
-    // Create new account
-    let account = Account {
-        account_name: account_name.clone(),
-        server_number: req.server_number,
-        password: req.password.clone(),
-        in_game_name: req.in_game_name.clone(),
-    };
-    
-    accounts.insert(account_name.clone(), account);
-    save_accounts(&state.data_dir, &accounts).map_err(|e| {
-        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
-    })?;
-    
-    // Initialize schedule data
-    let mut schedules = state.schedules.lock().unwrap();
-    let key = schedule_key(&account_name, req.server_number);
-    schedules.insert(key, ScheduleData {
-        construction_schedule: None,
-        research_schedule: None,
-        troops_schedule: None,
-        entries: None,
-        scheduled_player_ids: None,
-    });
-    drop(schedules);
-    
-    let schedule_url = format!("/{}/{}", account_name, req.server_number);
-    
-    Ok(HttpResponse::Ok().json(CreateAccountResponse {
-        success: true,
-        message: "Account created successfully".to_string(),
-        schedule_url: Some(schedule_url),
-    }))
+// Request body for the Google Form response sheet of a server; an empty spreadsheet stops the sync
+#[derive(Deserialize)]
+pub struct FormSyncRequest {
+    spreadsheet: String,
+    sheet: Option<String>, // Default "Form Responses 1", the tab Google Forms creates
+    interval_minutes: Option<u32>,
 }
 
-// Account login endpoint (for upload authentication)
-async fn account_login(
-    path: web::Path<(String, u32)>,
-    req: web::Json<LoginRequest>,
-    state: web::Data<AppState>,
-) -> Result<HttpResponse> {
-    let (account_name, _server_number) = path.into_inner();
-    let account_name = account_name.to_lowercase();
-    
-    let accounts = state.accounts.lock().unwrap();
-    if let Some(account) = accounts.get(&account_name) {
-        if account.password == req.password {
-        Ok(HttpResponse::Ok().json(serde_json::json!({"success": true})))
-    } else {
-        Ok(HttpResponse::Unauthorized().json(serde_json::json!({"success": false, "error": "Invalid password"})))
-        }
-    } else {
-        Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "error": "Account not found"})))
-    }
+// Request body for the column mapping of uploaded CSVs: header text per field name. Fields left
+// out are recognized by header text; an empty mapping removes it.
+#[derive(Deserialize)]
+pub struct CsvColumnsRequest {
+    mapping: ColumnMapping,
 }
 
-// CSV upload endpoint
-async fn account_upload(
-    path: web::Path<(String, u32)>,
-    req: HttpRequest,
-    body: web::Bytes,
-    state: web::Data<AppState>,
-) -> Result<HttpResponse> {
-    let (account_name, server_number) = path.into_inner();
-    let account_name = account_name.to_lowercase();
-    
-    // Check password from header
-    let password = req
-        .headers()
-        .get("X-Password")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
-    
-    // Verify account and password
-    let accounts = state.accounts.lock().unwrap();
-    let account = accounts.get(&account_name)
-        .ok_or_else(|| actix_web::error::ErrorNotFound("Account not found"))?;
-    
-    if account.password != password || account.server_number != server_number {
-        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({"success": false, "error": "Unauthorized"})));
-    }
-    drop(accounts);
+// Request body for turning submission metadata recording on or off
+#[derive(Deserialize)]
+pub struct SubmissionMetadataRequest {
+    enabled: bool,
+}
 
-    // Save uploaded CSV
-    std::fs::create_dir_all(&state.data_dir)?;
-    let csv_path = format!("{}/{}_{}.csv", state.data_dir, account_name, server_number);
-    std::fs::write(&csv_path, &body)
+// Request body for reviewing a flagged pair of submissions: "both", the player ID to keep, or null to flag it again
+#[derive(Deserialize)]
+pub struct DuplicateReviewRequest {
+    keep: Option<String>,
+}
+
+// Query for the statistics download: format is "csv" (default) or "xlsx"; CSV holds one table
+#[derive(Deserialize)]
+pub struct StatsExportQuery {
+    format: Option<String>,
+    table: Option<String>,
+}
+
+// Cell of an exported statistics table; numbers stay numeric in spreadsheets
+pub(crate) enum StatsCell {
+    Text(String),
+    Number(f64),
+}
+
+// One table of the statistics download (a CSV file or an XLSX sheet)
+pub(crate) struct StatsTable {
+    pub(crate) name: &'static str,
+    pub(crate) headers: &'static [&'static str],
+    pub(crate) rows: Vec<Vec<StatsCell>>,
+}
+
+// Request body for changing what the current form's public statistics show
+#[derive(Deserialize)]
+pub struct PublicStatsRequest {
+    public_stats: PublicStats,
+}
+
+// Request body for claiming an account with an ownership transfer code
+#[derive(Deserialize)]
+pub struct ClaimTransferRequest {
+    account_name: String,
+    code: String,
+    password: String,
+    in_game_name: String,
+}
+
+// Request body for setting or clearing the recovery email address
+#[derive(Deserialize)]
+pub struct RecoveryEmailRequest {
+    email: Option<String>,
+}
+
+// Request body for asking for a password reset code
+#[derive(Deserialize)]
+pub struct PasswordResetRequest {
+    account_name: String,
+}
+
+// Request body for setting a new password with a reset code
+#[derive(Deserialize)]
+pub struct ConfirmPasswordResetRequest {
+    account_name: String,
+    code: String,
+    password: String,
+}
+
+// Request body for creating or changing a scheduled task
+#[derive(Deserialize)]
+pub struct ScheduledTaskRequest {
+    kind: TaskKind,
+    cron: String,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    enabled: Option<bool>, // Defaults to on for new tasks, unchanged for existing ones
+}
+
+// Request body for adding a server to an account or switching the active server
+#[derive(Deserialize)]
+pub struct ServerSelectRequest {
+    server_number: u32,
+}
+
+#[derive(Serialize)]
+pub struct ServerInfo {
+    account_name: String,
+    server_number: u32,
+    slug: Option<String>,
+}
+
+// Request body for setting (or clearing, with null) a server's public URL slug
+#[derive(Deserialize)]
+pub struct UpdateSlugRequest {
+    slug: Option<String>,
+}
+
+// Request body for turning prefilling from previous submissions on or off
+#[derive(Deserialize)]
+pub struct PrefillAnswersRequest {
+    enabled: bool,
+}
+
+// Request body for looking up a player's previous answers: the passcode their alliance needs
+// to submit, or the token of a draft saved with the same player ID
+#[derive(Deserialize)]
+pub struct PreviousSubmissionRequest {
+    #[serde(default)]
+    passcode: Option<String>,
+    #[serde(default)]
+    draft_token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StatsResponse {
+    alliance_counts: HashMap<String, AllianceStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time_slot_popularity: Option<HashMap<String, TimeSlotStats>>, // Deprecated, kept for backward compatibility
+    #[serde(skip_serializing_if = "Option::is_none")]
+    construction_start_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    research_start_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    troops_start_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    construction_time_slot_popularity: Option<HashMap<String, FormTimeSlotStats>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    research_time_slot_popularity: Option<HashMap<String, FormTimeSlotStats>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    troops_time_slot_popularity: Option<HashMap<String, FormTimeSlotStats>>,
+    /// Claimed resources per alliance; missing in statistics cached before it was added
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    alliance_resources: Option<HashMap<String, AllianceResources>>,
+    /// Binned scores per day; missing in statistics cached before it was added
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    score_distribution: Option<ScoreDistributions>,
+}
+
+// Players whose score falls in [min, max]
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScoreBin {
+    min: u32,
+    max: u32,
+    count: u32,
+}
+
+// Score distribution of the players applying for one day, next to the number of slots they compete for
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ScoreHistogram {
+    applicants: u32,
+    slots: u32,
+    min: u32,
+    median: u32,
+    max: u32,
+    bins: Vec<ScoreBin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ScoreDistributions {
+    construction: ScoreHistogram,
+    research: ScoreHistogram,
+    troops: ScoreHistogram, // Troops slots are ranked by speedups alone
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AllianceStats {
+    construction_requests: u32,
+    research_requests: u32,
+    troops_requests: u32,
+}
+
+// Resources claimed by an alliance's players, counting only the days they applied for
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct AllianceResources {
+    construction_speedups: u64,
+    research_speedups: u64,
+    troops_speedups: u64,
+    construction_truegold: u64,
+    research_truegold_dust: u64,
+    average_construction_score: f64,
+    average_research_score: f64,
+    average_troops_score: f64, // Troops slots are ranked by speedups alone
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TimeSlotStats {
+    construction_requests: u32,
+    research_requests: u32,
+    troops_requests: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FormTimeSlotStats {
+    requests: u32,
+}
+
+// Availability per time slot and alliance for one day; counts[slot][alliance] follows `times` and `alliances`
+#[derive(Serialize)]
+pub struct DayHeatmap {
+    times: Vec<String>,
+    counts: Vec<Vec<u32>>,
+    totals: Vec<u32>,
+}
+
+#[derive(Serialize)]
+pub struct HeatmapResponse {
+    alliances: Vec<String>,
+    construction: DayHeatmap,
+    research: DayHeatmap,
+    troops: DayHeatmap,
+}
+
+// Submissions received in one hour or day, with the running total up to and including it
+#[derive(Serialize)]
+pub struct TimelineBucket {
+    start: String,
+    submissions: u32,
+    cumulative: u32,
+}
+
+#[derive(Serialize)]
+pub struct TimelineResponse {
+    form_created_at: Option<String>,
+    total_submissions: u32,
+    unique_players: usize,
+    last_submission_at: Option<String>,
+    hourly: Vec<TimelineBucket>,
+    daily: Vec<TimelineBucket>,
+}
+
+#[derive(Serialize)]
+pub struct ScheduleResponse {
+    day_name: String,
+    appointments: Vec<ScheduleSlot>,
+}
+
+#[derive(Serialize)]
+pub struct ScheduleSlot {
+    time: String,
+    player: Option<String>,
+    is_empty: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    player_id: Option<String>, // Only sent to logged-in admins
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>, // Admin note, only sent to logged-in admins
+    #[serde(skip_serializing_if = "Option::is_none")]
+    substitute: Option<SlotCandidate>, // Best unassigned player for a filled slot, only sent to admins
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    manual: bool, // Placed by hand and kept by regeneration, only sent to admins
+}
+
+// Server-rendered public schedule page (one day at a time, switched with ?day=)
+#[derive(Template)]
+#[template(path = "view_schedule.html")]
+struct ViewScheduleTemplate<'a> {
+    days: &'a [DayTab],
+    current_day: &'a str,
+    schedule: ScheduleResponse,
+    branding: Branding,
+}
+
+// Day selector entry for server-rendered schedule pages
+struct DayTab {
+    key: &'static str,
+    name: &'static str,
+    icon: &'static str,
+    button_class: &'static str,
+    ring_class: &'static str,
+}
+
+const DAY_TABS: [DayTab; 3] = [
+    DayTab {
+        key: "construction",
+        name: "Construction Day",
+        icon: "fas fa-hammer",
+        button_class: "bg-orange-600 hover:bg-orange-700 text-white",
+        ring_class: "ring-4 ring-orange-400",
+    },
+    DayTab {
+        key: "research",
+        name: "Research Day",
+        icon: "fas fa-flask",
+        button_class: "bg-blue-600 hover:bg-blue-700 text-white",
+        ring_class: "ring-4 ring-blue-400",
+    },
+    DayTab {
+        key: "troops",
+        name: "Troops Training Day",
+        icon: "fas fa-users",
+        button_class: "bg-green-600 hover:bg-green-700 text-white",
+        ring_class: "ring-4 ring-green-400",
+    },
+];
+
+// Public form page with the form config rendered into the page
+#[derive(Template)]
+#[template(path = "form.html")]
+struct FormPageTemplate {
+    code: String,
+    form_name: String,
+    intro_text: Option<String>,
+    intro_html: Option<String>, // Rendered markdown introduction, shown instead of intro_text
+    config_json: String,
+    branding: Branding,
+}
+
+// Renders an askama template into an HTML response
+fn render_page<T: Template>(template: &T) -> Result<HttpResponse> {
+    let html = template.render()
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to render page: {}", e)))?;
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+// Reads a JSON state file; a missing file is an empty map, an unreadable one an error, so a
+// broken file is never mistaken for having no data
+fn load_json_map<T: serde::de::DeserializeOwned>(path: &str) -> std::io::Result<HashMap<String, T>> {
+    let content = match storage::read_tracked(Path::new(path)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(std::io::Error::new(e.kind(), format!("Failed to read {}: {}", path, e))),
+    };
+    serde_json::from_str(&content)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Failed to parse {}: {}", path, e)))
+}
+
+// Helper function to load accounts from file
+fn load_accounts(data_dir: &str) -> std::io::Result<HashMap<String, Account>> {
+    load_json_map(&format!("{}/accounts.json", data_dir))
+}
+
+// Helper function to save accounts to file
+fn save_accounts(data_dir: &str, accounts: &HashMap<String, Account>) -> std::io::Result<()> {
+    std::fs::create_dir_all(data_dir)?;
+    let accounts_path = format!("{}/accounts.json", data_dir);
+    let content = serde_json::to_string_pretty(accounts)?;
+    write_atomic(&accounts_path, content)
+}
+
+// Helper function to get schedule key
+fn schedule_key(account_name: &str, server_number: u32) -> String {
+    format!("{}:{}", account_name, server_number)
+}
+
+// Alliance a sub-account session is restricted to (None for the account owner)
+fn session_alliance_scope(session: &Session) -> Option<String> {
+    session.get::<String>("alliance_scope").ok().flatten()
+}
+
+// Top-level path segments used by the app itself; slugs can't take these
+const RESERVED_SLUGS: &[&str] = &[
+    "api", "static", "form", "view", "dashboard", "servers", "operator",
+    "create-account", "claim-account", "reset-password", "admin", "stats", "login", "logout", "favicon.ico", "robots.txt",
+];
+
+// Validates a public URL slug: lowercase letters, digits and dashes, not a reserved route
+fn validate_slug(slug: &str) -> std::result::Result<(), String> {
+    if slug.len() < 3 || slug.len() > 40 {
+        return Err("Slug must be between 3 and 40 characters".to_string());
+    }
+    if !slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+        return Err("Slug can only contain lowercase letters, digits and dashes".to_string());
+    }
+    if slug.starts_with('-') || slug.ends_with('-') {
+        return Err("Slug cannot start or end with a dash".to_string());
+    }
+    if slug.chars().all(|c| c.is_ascii_digit()) {
+        return Err("Slug cannot be only digits".to_string());
+    }
+    if RESERVED_SLUGS.contains(&slug) {
+        return Err(format!("\"{}\" is reserved", slug));
+    }
+    Ok(())
+}
+
+// Finds the account/server a slug points to
+fn resolve_slug(state: &AppState, slug: &str) -> Option<(String, u32)> {
+    let slug = slug.to_lowercase();
+    state.accounts.lock().unwrap().values()
+        .find_map(|a| a.slugs.iter()
+            .find(|(_, s)| **s == slug)
+            .map(|(server, _)| (a.account_name.clone(), *server)))
+}
+
+// The slug configured for an account/server, if any
+fn slug_for(state: &AppState, account_name: &str, server_number: u32) -> Option<String> {
+    state.accounts.lock().unwrap()
+        .get(account_name)
+        .and_then(|a| a.slugs.get(&server_number).cloned())
+}
+
+// Permanent redirect used to send old account/server URLs to the slug URL
+fn redirect_to(location: String) -> HttpResponse {
+    HttpResponse::MovedPermanently()
+        .insert_header(("Location", location))
+        .finish()
+}
+
+// Branding of an account, or the defaults for unknown accounts
+fn account_branding(state: &AppState, account_name: &str) -> Branding {
+    state.accounts.lock().unwrap()
+        .get(account_name)
+        .map(|a| a.branding.clone())
+        .unwrap_or_default()
+}
+
+// Trims branding input (empty fields become unset) and rejects values that aren't safe to put in pages
+fn validate_branding(branding: Branding) -> std::result::Result<Branding, String> {
+    let clean = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let branding = Branding {
+        title: clean(branding.title),
+        accent_color: clean(branding.accent_color),
+        logo_url: clean(branding.logo_url),
+        footer_text: clean(branding.footer_text),
+    };
+    
+    if branding.title.as_ref().is_some_and(|t| t.chars().count() > 80) {
+        return Err("Title must be at most 80 characters".to_string());
+    }
+    if branding.footer_text.as_ref().is_some_and(|t| t.chars().count() > 200) {
+        return Err("Footer text must be at most 200 characters".to_string());
+    }
+    if let Some(color) = &branding.accent_color {
+        let hex = color.strip_prefix('#').unwrap_or("");
+        if !(hex.len() == 3 || hex.len() == 6) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err("Accent color must be a hex color like #f97316".to_string());
+        }
+    }
+    if let Some(url) = &branding.logo_url {
+        if !(url.starts_with("https://") || url.starts_with("http://")) || url.len() > 500 {
+            return Err("Logo URL must be an http(s) link".to_string());
+        }
+    }
+    Ok(branding)
+}
+
+// Limits that apply to an account: its operator overrides, then the instance defaults
+fn account_limits(state: &AppState, account_name: &str) -> AccountLimits {
+    state.accounts.lock().unwrap()
+        .get(account_name)
+        .map(|a| a.limits.or(&state.default_limits))
+        .unwrap_or_else(|| state.default_limits.clone())
+}
+
+// Human-readable size, matching the operator dashboard
+fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+// Checks the account's storage limit; returns the error message when it has been reached
+fn check_storage_limit(state: &AppState, account_name: &str, limits: &AccountLimits) -> Option<String> {
+    let max_storage = limits.max_storage_bytes?;
+    let forms = state.forms.lock().unwrap().clone();
+    let (disk_bytes, _) = account_disk_usage(&state.data_dir, account_name, &forms);
+    if disk_bytes >= max_storage {
+        Some(format!(
+            "Storage limit reached: this account uses {} of its {}",
+            format_bytes(disk_bytes),
+            format_bytes(max_storage)
+        ))
+    } else {
+        None
+    }
+}
+
+// Whether the operator has suspended this account
+fn is_account_suspended(state: &AppState, account_name: &str) -> bool {
+    state.accounts.lock().unwrap()
+        .get(account_name)
+        .is_some_and(|a| a.suspended)
+}
+
+// Whether the alliance login a session was created with still exists unchanged
+fn is_sub_account_current(state: &AppState, account_name: &str, username: &str, id: &str, alliance: &str) -> bool {
+    state.accounts.lock().unwrap()
+        .get(account_name)
+        .is_some_and(|a| a.sub_accounts.iter().any(|s| s.username == username && s.id == id && s.alliance == alliance))
+}
+
+// Whether a session was created after the account's last ownership transfer
+fn is_session_current(state: &AppState, account_name: &str, session_version: u32) -> bool {
+    state.accounts.lock().unwrap()
+        .get(account_name)
+        .is_none_or(|a| a.session_version == session_version)
+}
+
+// Refuses alliance logins, which only edit slots and view data, on endpoints that only the
+// account owner may use: `require_owner(&session)?;`
+fn require_owner(session: &Session) -> Result<()> {
+    if session_alliance_scope(session).is_none() {
+        return Ok(());
+    }
+    let response = HttpResponse::Forbidden().json(serde_json::json!({
+        "success": false,
+        "error": "Alliance logins cannot perform this action"
+    }));
+    Err(actix_web::error::InternalError::from_response("owner only", response).into())
+}
+
+// Helper function to get the current form for an account/server
+fn get_current_form(forms: &HashMap<String, FormData>, current_forms: &HashMap<String, String>, account_name: &str, server_number: u32) -> Option<FormData> {
+    let account_name_lower = account_name.to_lowercase();
+    let key = schedule_key(&account_name_lower, server_number);
+    if let Some(form_code) = current_forms.get(&key) {
+        forms.get(form_code).cloned()
+    } else {
+        // Fallback: get most recent form by created_at (case-insensitive account_name comparison)
+        forms.values()
+            .filter(|f| f.account_name.to_lowercase() == account_name_lower && f.server_number == server_number)
+            .max_by_key(|f| &f.created_at)
+            .cloned()
+    }
+}
+
+// Helper function to load current forms mapping
+fn load_current_forms(data_dir: &str) -> std::io::Result<HashMap<String, String>> {
+    load_json_map(&format!("{}/current_forms_map.json", data_dir))
+}
+
+// Helper function to save current forms mapping
+fn save_current_forms(data_dir: &str, current_forms: &HashMap<String, String>) -> std::io::Result<()> {
+    std::fs::create_dir_all(data_dir)?;
+    let path = format!("{}/current_forms_map.json", data_dir);
+    let content = serde_json::to_string_pretty(current_forms)?;
+    write_atomic(&path, content)
+}
+
+// Helper function to save schedule to disk
+fn save_schedule(data_dir: &str, account_name: &str, server_number: u32, schedule_data: &ScheduleData) -> std::io::Result<()> {
+    let schedules_dir = format!("{}/schedules/{}", data_dir, account_name);
+    std::fs::create_dir_all(&schedules_dir)?;
+    let path = format!("{}/{}.json", schedules_dir, server_number);
+    let content = serde_json::to_string_pretty(schedule_data)?;
+    write_atomic(&path, content)
+}
+
+// Helper function to load schedule from disk
+fn load_schedule(data_dir: &str, account_name: &str, server_number: u32) -> Option<ScheduleData> {
+    let path = format!("{}/schedules/{}/{}.json", data_dir, account_name, server_number);
+    if Path::new(&path).exists() {
+        if let Ok(content) = storage::read_tracked(Path::new(&path)) {
+            match serde_json::from_str::<ScheduleData>(&content) {
+                Ok(schedule_data) => return Some(schedule_data),
+                Err(e) => {
+                    log::warn!("Failed to deserialize schedule from {}: {}", path, e);
+                    return None;
+                }
+            }
+        } else {
+            log::warn!("Failed to read schedule file: {}", path);
+        }
+    }
+    None
+}
+
+// Helper function to save statistics to disk
+fn save_statistics(data_dir: &str, account_name: &str, server_number: u32, stats: &StatsResponse) -> std::io::Result<()> {
+    let stats_dir = format!("{}/statistics/{}", data_dir, account_name);
+    std::fs::create_dir_all(&stats_dir)?;
+    let path = format!("{}/{}.json", stats_dir, server_number);
+    let content = serde_json::to_string_pretty(stats)?;
+    write_atomic(&path, content)
+}
+
+// Helper function to load statistics from disk
+fn load_statistics(data_dir: &str, account_name: &str, server_number: u32) -> Option<StatsResponse> {
+    let path = format!("{}/statistics/{}/{}.json", data_dir, account_name, server_number);
+    if Path::new(&path).exists() {
+        if let Ok(content) = storage::read_tracked(Path::new(&path)) {
+            match serde_json::from_str::<StatsResponse>(&content) {
+                Ok(stats) => return Some(stats),
+                Err(e) => {
+                    log::warn!("Failed to deserialize statistics from {}: {}", path, e);
+                    return None;
+                }
+            }
+        } else {
+            log::warn!("Failed to read statistics file: {}", path);
+        }
+    }
+    None
+}
+
+// Helper function to save a player roster to disk
+fn save_roster(data_dir: &str, account_name: &str, server_number: u32, roster: &[RosterEntry]) -> std::io::Result<()> {
+    let rosters_dir = format!("{}/rosters/{}", data_dir, account_name);
+    std::fs::create_dir_all(&rosters_dir)?;
+    let path = format!("{}/{}.json", rosters_dir, server_number);
+    let content = serde_json::to_string_pretty(roster)?;
+    write_atomic(&path, content)
+}
+
+// Helper function to load a player roster from disk, empty when there is none
+fn load_roster(data_dir: &str, account_name: &str, server_number: u32) -> Vec<RosterEntry> {
+    let path = format!("{}/rosters/{}/{}.json", data_dir, account_name, server_number);
+    if !Path::new(&path).exists() {
+        return Vec::new();
+    }
+    match storage::read_tracked(Path::new(&path)).map_err(|e| e.to_string())
+        .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+    {
+        Ok(roster) => roster,
+        Err(e) => {
+            log::warn!("Failed to load roster from {}: {}", path, e);
+            Vec::new()
+        }
+    }
+}
+
+// Generate a unique 12-character alphanumeric code
+fn generate_form_code() -> String {
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    let mut rng = rand::thread_rng();
+    (0..12)
+        .map(|_| {
+            let idx = rng.gen_range(0..CHARSET.len());
+            CHARSET[idx] as char
+        })
+        .collect()
+}
+
+// Helper function to load all forms from current_forms folder
+fn load_forms(data_dir: &str) -> HashMap<String, FormData> {
+    let current_forms_dir = format!("{}/current_forms", data_dir);
+    let mut forms = HashMap::new();
+    
+    if !Path::new(&current_forms_dir).exists() {
+        // Try to migrate old forms.json if it exists
+        let old_forms_path = format!("{}/forms.json", data_dir);
+        if Path::new(&old_forms_path).exists() {
+            if let Ok(content) = std::fs::read_to_string(&old_forms_path) {
+                if let Ok(old_forms) = serde_json::from_str::<HashMap<String, FormData>>(&content) {
+                    // Create directories
+                    std::fs::create_dir_all(&current_forms_dir).ok();
+                    std::fs::create_dir_all(format!("{}/old_forms", data_dir)).ok();
+                    
+                    // Move each form to current_forms
+                    for (code, mut form_data) in old_forms {
+                        // Add default name and created_at if missing (for old forms)
+                        if form_data.name.is_empty() {
+                            form_data.name = format!("Form {} {}", form_data.account_name, form_data.server_number);
+                        }
+                        if form_data.created_at.is_empty() {
+                            form_data.created_at = chrono::Local::now().to_rfc3339();
+                        }
+                        
+                        // Save account/server info before cloning
+                        let account_name = form_data.account_name.clone();
+                        let server_number = form_data.server_number;
+                        
+                        let form_path = format!("{}/{}.json", current_forms_dir, code);
+                        if let Ok(content) = serde_json::to_string_pretty(&form_data) {
+                            std::fs::write(&form_path, content).ok();
+                            forms.insert(code.clone(), form_data.clone());
+                            
+                            // Try to move old CSV file if it exists
+                            let old_csv_path = format!("{}/{}_{}_form_submissions.csv", data_dir, account_name, server_number);
+                            if Path::new(&old_csv_path).exists() {
+                                let new_csv_path = format!("{}/{}_submissions.csv", current_forms_dir, code);
+                                std::fs::copy(&old_csv_path, &new_csv_path).ok();
+                                // Keep old CSV for now (don't delete during migration)
+                            }
+                        }
+                    }
+                    // Delete old forms.json after migration
+                    std::fs::remove_file(&old_forms_path).ok();
+                }
+            }
+        }
+        return forms;
+    }
+    
+    // Load all JSON files from current_forms directory
+    if let Ok(entries) = std::fs::read_dir(&current_forms_dir) {
+        for entry in entries.flatten() {
+            if let Some(file_name) = entry.file_name().to_str() {
+                if file_name.ends_with(".json") {
+                    match storage::read_tracked(&entry.path()).map_err(|e| e.to_string())
+                        .and_then(|content| serde_json::from_str::<FormData>(&content).map_err(|e| e.to_string()))
+                    {
+                        Ok(form_data) => {
+                            forms.insert(form_data.code.clone(), form_data);
+                        }
+                        Err(e) => log::warn!("Skipping form file {}: {}", entry.path().display(), e),
+                    }
+                }
+            }
+        }
+    }
+    
+    forms
+}
+
+// Helper function to save a single form to current_forms folder
+fn save_form(data_dir: &str, form_data: &FormData) -> std::io::Result<()> {
+    let current_forms_dir = format!("{}/current_forms", data_dir);
+    std::fs::create_dir_all(&current_forms_dir)?;
+    let form_path = format!("{}/{}.json", current_forms_dir, form_data.code);
+    let content = serde_json::to_string_pretty(form_data)?;
+    write_atomic(&form_path, content)
+}
+
+// Helper function to move old forms to old_forms folder (including CSV files)
+fn archive_old_forms(data_dir: &str, account_name: &str, server_number: u32) -> std::io::Result<()> {
+    let current_forms_dir = format!("{}/current_forms", data_dir);
+    let old_forms_dir = format!("{}/old_forms", data_dir);
+    std::fs::create_dir_all(&old_forms_dir)?;
+    
+    // Find all forms for this account/server
+    if let Ok(entries) = std::fs::read_dir(&current_forms_dir) {
+        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
+        
+        for entry in entries.flatten() {
+            if let Some(file_name) = entry.file_name().to_str() {
+                if file_name.ends_with(".json") {
+                    if let Ok(content) = std::fs::read_to_string(entry.path()) {
+                        if let Ok(form_data) = serde_json::from_str::<FormData>(&content) {
+                            // Check if this form belongs to the account/server being updated
+                            if form_data.account_name == account_name && form_data.server_number == server_number {
+                                let code = &form_data.code;
+                                
+                                // Move JSON file to old_forms
+                                let old_form_json_path = format!("{}/{}_{}_{}.json", old_forms_dir, account_name, server_number, timestamp);
+                                std::fs::copy(entry.path(), &old_form_json_path)?;
+                                std::fs::remove_file(entry.path())?;
+                                
+                                // Move CSV file if it exists
+                                let csv_file_name = format!("{}_submissions.csv", code);
+                                let csv_path = format!("{}/{}", current_forms_dir, csv_file_name);
+                                if Path::new(&csv_path).exists() {
+                                    let old_csv_path = format!("{}/{}_{}_{}_submissions.csv", old_forms_dir, account_name, server_number, timestamp);
+                                    std::fs::copy(&csv_path, &old_csv_path)?;
+                                    std::fs::remove_file(&csv_path)?;
+                                }
+                                
+                                // Drafts can't be submitted to an archived form
+                                std::fs::remove_dir_all(draft::drafts_dir(data_dir, code)).ok();
+                                // Metadata is only kept while the form collects submissions
+                                metadata::delete_metadata(data_dir, code);
+                                duplicates::delete_reviews(data_dir, code);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        
+        // Keep a copy of the schedule generated from the archived form
+        archive_schedule(data_dir, account_name, server_number, &timestamp)?;
+    }
+    
+    Ok(())
+}
+
+// Copies the saved schedule of an account/server into schedules/{account}/archive/{server}_{timestamp}.json
+// The current schedule stays in place until a new one is generated
+fn archive_schedule(data_dir: &str, account_name: &str, server_number: u32, timestamp: &str) -> std::io::Result<()> {
+    let path = format!("{}/schedules/{}/{}.json", data_dir, account_name, server_number);
+    if !Path::new(&path).exists() {
+        return Ok(());
+    }
+    let archive_dir = format!("{}/schedules/{}/archive", data_dir, account_name);
+    std::fs::create_dir_all(&archive_dir)?;
+    std::fs::copy(&path, format!("{}/{}_{}.json", archive_dir, server_number, timestamp))?;
+    Ok(())
+}
+
+// Archive IDs are the archive timestamps (YYYYMMDD_HHMMSS); anything else is rejected
+fn parse_archive_id(id: &str) -> Option<chrono::NaiveDateTime> {
+    chrono::NaiveDateTime::parse_from_str(id, "%Y%m%d_%H%M%S").ok()
+}
+
+// Path of an archived schedule
+fn archived_schedule_path(data_dir: &str, account_name: &str, server_number: u32, id: &str) -> String {
+    format!("{}/schedules/{}/archive/{}_{}.json", data_dir, account_name, server_number, id)
+}
+
+// Archived schedules of one account/server as (archive ID, archived at), newest first
+fn archived_schedule_ids(data_dir: &str, account_name: &str, server_number: u32) -> Vec<(String, chrono::NaiveDateTime)> {
+    let prefix = format!("{}_", server_number);
+    let mut ids = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(format!("{}/schedules/{}/archive", data_dir, account_name)) {
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let id = file_name.strip_prefix(&prefix).and_then(|n| n.strip_suffix(".json"));
+            if let Some((id, archived_at)) = id.and_then(|id| parse_archive_id(id).map(|t| (id.to_string(), t))) {
+                ids.push((id, archived_at));
+            }
+        }
+    }
+    ids.sort_by_key(|(_, archived_at)| std::cmp::Reverse(*archived_at));
+    ids
+}
+
+// Reloads the disk-backed state so changes made by other instances are visible, re-reading only
+// the files whose stamp changed since this instance last read or saved them
+// Only used when the data directory is shared between instances
+// A file that can't be read keeps what is in memory; replacing it with nothing would let the
+// next save wipe the file for every instance
+fn reload_shared_state(state: &AppState) {
+    let data_dir = Path::new(&state.data_dir);
+    if storage::changed_on_disk(&data_dir.join("accounts.json")) {
+        match load_accounts(&state.data_dir) {
+            Ok(accounts) => *state.accounts.lock().unwrap() = accounts,
+            Err(e) => log::warn!("Keeping the loaded accounts: {}", e),
+        }
+    }
+    if storage::changed_on_disk(&data_dir.join("current_forms_map.json")) {
+        match load_current_forms(&state.data_dir) {
+            Ok(current_forms) => *state.current_forms.lock().unwrap() = current_forms,
+            Err(e) => log::warn!("Keeping the loaded current forms: {}", e),
+        }
+    }
+    // Saving, adding or removing a form replaces an entry of the folder, so an unchanged folder
+    // means no form changed
+    let current_forms_dir = data_dir.join("current_forms");
+    if storage::changed_on_disk(&current_forms_dir) {
+        let stamp = storage::file_stamp(&current_forms_dir);
+        reload_changed_forms(state, &current_forms_dir);
+        storage::remember(&current_forms_dir, stamp);
+    }
+    // Cached schedules are dropped when their file was saved by another instance
+    state.schedules.lock().unwrap().retain(|key, _| {
+        key.rsplit_once(':').is_some_and(|(account_name, server_number)| {
+            !storage::changed_on_disk(&data_dir.join(format!("schedules/{}/{}.json", account_name, server_number)))
+        })
+    });
+}
+
+// Re-reads the form files that changed since they were last read or saved and drops forms
+// whose file is gone. Forms whose file couldn't be read keep their loaded copy.
+fn reload_changed_forms(state: &AppState, current_forms_dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(current_forms_dir) else {
+        return;
+    };
+    let mut forms = state.forms.lock().unwrap();
+    let mut on_disk = HashSet::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let Some(code) = path.file_stem().and_then(|s| s.to_str()).map(str::to_string) else {
+            continue;
+        };
+        on_disk.insert(code.clone());
+        if !storage::changed_on_disk(&path) {
+            continue;
+        }
+        match storage::read_tracked(&path).map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str::<FormData>(&content).map_err(|e| e.to_string()))
+        {
+            Ok(form_data) => {
+                forms.insert(form_data.code.clone(), form_data);
+            }
+            // Other JSON files live next to the forms, like the duplicate reviews
+            Err(e) if forms.contains_key(&code) => log::warn!("Keeping the loaded form {}: {}", code, e),
+            Err(_) => {}
+        }
+    }
+    forms.retain(|code, _| on_disk.contains(code));
+}
+
+// Create account endpoint
+async fn create_account(
+    req: web::Json<CreateAccountRequest>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let account_name = req.account_name.trim().to_lowercase();
+    
+    if account_name.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(CreateAccountResponse {
+            success: false,
+            message: "Account name cannot be empty".to_string(),
+            schedule_url: None,
+        }));
+    }
+    
+    // Check if account already exists
+    let mut accounts = state.accounts.lock().unwrap();
+    if accounts.contains_key(&account_name) {
+        return Ok(HttpResponse::BadRequest().json(CreateAccountResponse {
+            success: false,
+            message: "Account name already exists".to_string(),
+            schedule_url: None,
+        }));
+    }
+    
+    // Create new account
+    let account = Account {
+        account_name: account_name.clone(),
+        server_number: req.server_number,
+        password: req.password.clone(),
+        in_game_name: req.in_game_name.clone(),
+        additional_servers: Vec::new(),
+        sub_accounts: Vec::new(),
+        suspended: false,
+        slugs: HashMap::new(),
+        branding: Branding::default(),
+        limits: AccountLimits::default(),
+        pending_transfer: None,
+        session_version: 0,
+        public_leaderboards: HashSet::new(),
+        seasons: Vec::new(),
+        google_sheets: HashMap::new(),
+        form_syncs: HashMap::new(),
+        csv_columns: ColumnMapping::new(),
+        submission_metadata: false,
+        metadata_salt: String::new(),
+        prefill_answers: false,
+        notifications: NotificationSettings::default(),
+        recovery_email: None,
+        pending_reset: None,
+        scheduled_tasks: HashMap::new(),
+    };
+    
+    accounts.insert(account_name.clone(), account);
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    
+    // Initialize schedule data
+    let mut schedules = state.schedules.lock().unwrap();
+    let key = schedule_key(&account_name, req.server_number);
+    schedules.insert(key, ScheduleData {
+        construction_schedule: None,
+        research_schedule: None,
+        troops_schedule: None,
+        entries: None,
+        scheduled_player_ids: None,
+    });
+    drop(schedules);
+    
+    let schedule_url = format!("/{}/{}", account_name, req.server_number);
+    
+    Ok(HttpResponse::Ok().json(CreateAccountResponse {
+        success: true,
+        message: "Account created successfully".to_string(),
+        schedule_url: Some(schedule_url),
+    }))
+}
+
+// Account login endpoint (for upload authentication)
+async fn account_login(
+    path: web::Path<(String, u32)>,
+    req: web::Json<LoginRequest>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, _server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    
+    let accounts = state.accounts.lock().unwrap();
+    if let Some(account) = accounts.get(&account_name) {
+        if account.password == req.password {
+        Ok(HttpResponse::Ok().json(serde_json::json!({"success": true})))
+    } else {
+        Ok(HttpResponse::Unauthorized().json(serde_json::json!({"success": false, "error": "Invalid password"})))
+        }
+    } else {
+        Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "error": "Account not found"})))
+    }
+}
+
+// CSV upload endpoint
+async fn account_upload(
+    path: web::Path<(String, u32)>,
+    req: HttpRequest,
+    body: web::Bytes,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    
+    // Check password from header
+    let password = req
+        .headers()
+        .get("X-Password")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    
+    // Verify account and password
+    let accounts = state.accounts.lock().unwrap();
+    let account = accounts.get(&account_name)
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Account not found"))?;
+    
+    if account.password != password || !account.owns_server(server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({"success": false, "error": "Unauthorized"})));
+    }
+    let mapping = account.csv_columns.clone();
+    drop(accounts);
+    
+    // Guessing the columns of an unfamiliar CSV gives a wrong schedule, so the admin is asked to
+    // map the columns that weren't recognized instead
+    let headers = match csv::Reader::from_reader(&body[..]).headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": format!("Failed to read the CSV header: {}", e)
+            })));
+        }
+    };
+    if let Err(unrecognized) = SubmissionColumns::resolve(&headers, &mapping) {
+        return Ok(HttpResponse::UnprocessableEntity().json(serde_json::json!({
+            "success": false,
+            "error": "Some columns weren't recognized. Choose the column of each and upload again.",
+            "headers": headers.iter().map(str::trim).collect::<Vec<_>>(),
+            "unrecognized": unrecognized,
+            "mapping": mapping
+        })));
+    }
+
+    // Save uploaded CSV
+    std::fs::create_dir_all(&state.data_dir)?;
+    let csv_path = format!("{}/{}_{}.csv", state.data_dir, account_name, server_number);
+    std::fs::write(&csv_path, &body)
         .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save file: {}", e)))?;
 
-    // Process the CSV
-    // For uploaded CSV files, use None for time slot mappings to maintain backward compatibility
-    // (uploaded CSVs might use the old fixed time format)
-    match load_appointments(&csv_path, None, None, None) {
-        Ok(entries) => {
-            let construction_schedule = schedule_construction_day(&entries);
-            let research_schedule = schedule_research_day(&entries, &construction_schedule);
-            let troops_schedule = schedule_troops_day(&entries);
+    // Process the CSV
+    // For uploaded CSV files, use None for time slot mappings to maintain backward compatibility
+    // (uploaded CSVs might use the old fixed time format)
+    match read_mapped_appointments(&body[..], &mapping, None, None, None) {
+        Ok(entries) => {
+            let construction_schedule = schedule_construction_day(&entries);
+            let research_schedule = schedule_research_day(&entries, &construction_schedule);
+            let troops_schedule = schedule_troops_day(&entries);
+
+            // Update state
+            let mut schedules = state.schedules.lock().unwrap();
+            let key = schedule_key(&account_name, server_number);
+            let scheduled_ids: Vec<String> = {
+                let mut ids = HashSet::new();
+                for appt in construction_schedule.appointments.values() {
+                    ids.insert(appt.player_id.clone());
+                }
+                for appt in research_schedule.appointments.values() {
+                    ids.insert(appt.player_id.clone());
+                }
+                for appt in troops_schedule.appointments.values() {
+                    ids.insert(appt.player_id.clone());
+                }
+                ids.into_iter().collect()
+            };
+            schedules.insert(key, ScheduleData {
+                construction_schedule: Some(construction_schedule),
+                research_schedule: Some(research_schedule),
+                troops_schedule: Some(troops_schedule),
+                entries: Some(entries),
+                scheduled_player_ids: Some(scheduled_ids),
+            });
+
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "message": "Schedule generated successfully"
+            })))
+        }
+        Err(e) => Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": format!("Failed to process CSV: {}", e)
+        })))
+    }
+}
+
+// Account whose password is in the X-Password header, for the CSV upload page
+fn password_account(state: &AppState, req: &HttpRequest, account_name: &str, server_number: u32) -> Option<Account> {
+    let password = req.headers().get("X-Password").and_then(|v| v.to_str().ok())?;
+    state.accounts.lock().unwrap().get(account_name)
+        .filter(|a| a.password == password && a.owns_server(server_number))
+        .cloned()
+}
+
+// Column mapping used for uploaded CSVs, with the fields that can be mapped
+async fn get_csv_columns(
+    path: web::Path<(String, u32)>,
+    req: HttpRequest,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    let Some(account) = password_account(&state, &req, &account_name, server_number) else {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({"success": false, "error": "Unauthorized"})));
+    };
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "fields": SUBMISSION_FIELDS,
+        "mapping": account.csv_columns
+    })))
+}
+
+// Save the column mapping for uploaded CSVs; it applies to every server of the account
+async fn update_csv_columns(
+    path: web::Path<(String, u32)>,
+    req: HttpRequest,
+    body: web::Json<CsvColumnsRequest>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if password_account(&state, &req, &account_name, server_number).is_none() {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({"success": false, "error": "Unauthorized"})));
+    }
+    
+    let mut mapping = ColumnMapping::new();
+    for (field, header) in &body.mapping {
+        if !SUBMISSION_FIELDS.contains(&field.as_str()) {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": format!("Unknown field: {}", field)
+            })));
+        }
+        if header.chars().count() > 1000 {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": format!("Header of {} is too long", field)
+            })));
+        }
+        if !header.trim().is_empty() {
+            mapping.insert(field.clone(), header.trim().to_string());
+        }
+    }
+    
+    let mut accounts = state.accounts.lock().unwrap();
+    let Some(account) = accounts.get_mut(&account_name) else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "error": "Account not found"})));
+    };
+    account.csv_columns = mapping.clone();
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "mapping": mapping
+    })))
+}
+
+// Stats endpoint
+async fn get_stats(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    
+    let visibility = stats_visibility(&state, &session, &account_name, server_number);
+    if visibility == PublicStats::Disabled {
+        return Ok(stats_not_public_response());
+    }
+    // Recomputing reads the whole submissions CSV, so it runs off the request workers
+    let stats_state = state.clone();
+    let mut stats = web::block(move || load_or_compute_stats(&stats_state, &account_name, server_number)).await?;
+    if visibility != PublicStats::Full {
+        stats.alliance_counts.clear();
+        stats.alliance_resources = None;
+    }
+    if visibility == PublicStats::SlotsOnly {
+        stats.score_distribution = None;
+    }
+    Ok(HttpResponse::Ok().json(stats))
+}
+
+// What the statistics endpoints may show this session: everything for the server's admins,
+// otherwise the current form's public statistics setting
+fn stats_visibility(state: &AppState, session: &Session, account_name: &str, server_number: u32) -> PublicStats {
+    if session_is_for(session, account_name, server_number) {
+        return PublicStats::Full;
+    }
+    let forms = state.forms.lock().unwrap();
+    let current_forms = state.current_forms.lock().unwrap();
+    get_current_form(&forms, &current_forms, account_name, server_number)
+        .map(|f| f.config.public_stats)
+        .unwrap_or_default()
+}
+
+fn stats_not_public_response() -> HttpResponse {
+    HttpResponse::Forbidden().json(serde_json::json!({
+        "success": false,
+        "error": "Statistics for this form are not public"
+    }))
+}
+
+// Change what the current form's public statistics show
+async fn update_public_stats(
+    path: web::Path<(String, u32)>,
+    req: web::Json<PublicStatsRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let mut forms = state.forms.lock().unwrap();
+    let code = state.current_forms.lock().unwrap().get(&schedule_key(&account_name, server_number)).cloned();
+    let form_data = match code.and_then(|code| forms.get_mut(&code)) {
+        Some(form_data) => form_data,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "No current form found"
+            })));
+        }
+    };
+    form_data.config.public_stats = req.public_stats;
+    save_form(&state.data_dir, form_data)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save form: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "public_stats": req.public_stats
+    })))
+}
+
+// Replace the current form's introduction and day descriptions
+async fn update_form_content(
+    path: web::Path<(String, u32)>,
+    req: web::Json<FormContent>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let content = req.into_inner().trimmed();
+    if let Err(e) = content.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": e
+        })));
+    }
+    
+    let mut forms = state.forms.lock().unwrap();
+    let code = state.current_forms.lock().unwrap().get(&schedule_key(&account_name, server_number)).cloned();
+    let form_data = match code.and_then(|code| forms.get_mut(&code)) {
+        Some(form_data) => form_data,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "No current form found"
+            })));
+        }
+    };
+    form_data.config.content = content;
+    save_form(&state.data_dir, form_data)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save form: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "content": form_data.config.content
+    })))
+}
+
+// Replace the current form's language packs
+async fn update_language_packs(
+    path: web::Path<(String, u32)>,
+    req: web::Json<BTreeMap<String, LanguagePack>>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let packs = req.into_inner();
+    if let Err(e) = validate_language_packs(&packs) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": e
+        })));
+    }
+    
+    let mut forms = state.forms.lock().unwrap();
+    let code = state.current_forms.lock().unwrap().get(&schedule_key(&account_name, server_number)).cloned();
+    let form_data = match code.and_then(|code| forms.get_mut(&code)) {
+        Some(form_data) => form_data,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "No current form found"
+            })));
+        }
+    };
+    form_data.config.language_packs = packs;
+    save_form(&state.data_dir, form_data)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save form: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "language_packs": form_data.config.language_packs
+    })))
+}
+
+// Statistics of an account/server, from the disk cache or computed from the submissions and cached
+fn load_or_compute_stats(state: &AppState, account_name: &str, server_number: u32) -> StatsResponse {
+    let key = schedule_key(account_name, server_number);
+    
+    // Try to load cached statistics from disk first (recomputed if cached before resource totals or score distributions existed)
+    let cached = || load_statistics(&state.data_dir, account_name, server_number)
+        .filter(|stats| stats.alliance_resources.is_some() && stats.score_distribution.is_some());
+    if let Some(cached_stats) = cached() {
+        return cached_stats;
+    }
+    // Requests arriving together after a submission wait for one computation instead of each doing it
+    let lock = state.stats_locks.lock().unwrap().entry(key.clone()).or_default().clone();
+    let _computing = lock.lock().unwrap();
+    if let Some(cached_stats) = cached() {
+        return cached_stats;
+    }
+    
+    // The form submissions CSV is the source of truth; without one, fall back to the entries of an uploaded CSV
+    let form_csv_path = {
+        let forms = state.forms.lock().unwrap();
+        let current_forms = state.current_forms.lock().unwrap();
+        if let Some(current_form) = get_current_form(&forms, &current_forms, account_name, server_number) {
+            // Use new location: current_forms/{code}_submissions.csv
+            drop(current_forms);
+            format!("{}/current_forms/{}_submissions.csv", state.data_dir, current_form.code)
+        } else {
+            drop(current_forms);
+            // Fallback to old location for migration
+            format!("{}/{}_{}_form_submissions.csv", state.data_dir, account_name, server_number)
+        }
+    };
+    
+    let stats_response = if Path::new(&form_csv_path).exists() {
+        // Try to get form config to use custom time slots
+        let form_config = {
+            let forms = state.forms.lock().unwrap();
+            let current_forms = state.current_forms.lock().unwrap();
+            get_current_form(&forms, &current_forms, account_name, server_number)
+                .map(|f| f.config.clone())
+        };
+        let form_entries = load_csv_entries(state, &form_csv_path, form_config.as_ref());
+        compute_stats(&form_entries, form_config.as_ref())
+    } else {
+        let entries = state.schedules.lock().unwrap().get(&key).and_then(|data| data.entries.clone()).unwrap_or_default();
+        compute_stats(&entries, None)
+    };
+    
+    // Save statistics to disk
+    if let Err(e) = save_statistics(&state.data_dir, account_name, server_number, &stats_response) {
+        log::warn!("Failed to save statistics to disk: {}", e);
+    }
+    
+    stats_response
+}
+
+
+// Statistics of the given entries. With a form config, slot popularity is counted per day over the
+// form's time windows; the combined map is kept for statistics of uploaded CSVs and older pages.
+pub(crate) fn compute_stats(entries: &[AppointmentEntry], config: Option<&FormConfig>) -> StatsResponse {
+    let mut alliance_counts: HashMap<String, AllianceStats> = HashMap::new();
+    let mut time_slot_popularity: HashMap<String, TimeSlotStats> = HashMap::new();
+    
+    // Every time of the form's window starts at zero requests
+    let slots = |day_str: &str| config.map(|config| day_time_slots(Some(config), day_str));
+    let (construction_slots, research_slots, troops_slots) = (slots("construction"), slots("research"), slots("troops"));
+    let popularity = |slots: &Option<Vec<(u8, String)>>| -> Option<HashMap<String, FormTimeSlotStats>> {
+        slots.as_ref().map(|slots| slots.iter().map(|(_, time)| (time.clone(), FormTimeSlotStats { requests: 0 })).collect())
+    };
+    let mut construction_time_slot_popularity = popularity(&construction_slots);
+    let mut research_time_slot_popularity = popularity(&research_slots);
+    let mut troops_time_slot_popularity = popularity(&troops_slots);
+    
+    // A slot's time in the form's window
+    let form_time = |slots: &Option<Vec<(u8, String)>>, slot: u8| -> Option<String> {
+        slots.as_ref()?.iter().find(|(s, _)| *s == slot).map(|(_, t)| t.clone())
+    };
+    
+    for entry in entries {
+        // Count by alliance
+        let stats = alliance_counts.entry(entry.alliance.clone()).or_insert_with(|| AllianceStats {
+            construction_requests: 0,
+            research_requests: 0,
+            troops_requests: 0,
+        });
+        if entry.wants_construction {
+            stats.construction_requests += 1;
+        }
+        if entry.wants_research {
+            stats.research_requests += 1;
+        }
+        if entry.wants_troops {
+            stats.troops_requests += 1;
+        }
+        
+        let days = [
+            (&entry.construction_available_slots, &construction_slots, &mut construction_time_slot_popularity),
+            (&entry.research_available_slots, &research_slots, &mut research_time_slot_popularity),
+            (&entry.troops_available_slots, &troops_slots, &mut troops_time_slot_popularity),
+        ];
+        for (day, (available_slots, day_slots, day_popularity)) in days.into_iter().enumerate() {
+            for slot in available_slots {
+                let time = form_time(day_slots, *slot);
+                // Per-day map: only slots inside the form's window
+                if let Some(slot_stats) = time.as_ref().and_then(|time| day_popularity.as_mut()?.get_mut(time)) {
+                    slot_stats.requests += 1;
+                }
+                // Backward-compatible combined map, with the fixed mapping for uploaded CSVs
+                let time = time.unwrap_or_else(|| slot_to_time(*slot));
+                let slot_stats = time_slot_popularity.entry(time).or_insert_with(|| TimeSlotStats {
+                    construction_requests: 0,
+                    research_requests: 0,
+                    troops_requests: 0,
+                });
+                match day {
+                    0 => slot_stats.construction_requests += 1,
+                    1 => slot_stats.research_requests += 1,
+                    _ => slot_stats.troops_requests += 1,
+                }
+            }
+        }
+    }
+    
+    StatsResponse {
+        alliance_counts,
+        time_slot_popularity: if time_slot_popularity.is_empty() { None } else { Some(time_slot_popularity) },
+        construction_start_time: config.map(|c| c.construction_times.start_time.clone()),
+        research_start_time: config.map(|c| c.research_times.start_time.clone()),
+        troops_start_time: config.map(|c| c.troops_times.start_time.clone()),
+        construction_time_slot_popularity,
+        research_time_slot_popularity,
+        troops_time_slot_popularity,
+        alliance_resources: Some(alliance_resource_totals(entries)),
+        score_distribution: Some(score_distributions(entries, config)),
+    }
+}
+
+// Sums each alliance's claimed speedups, truegold and dust, and averages its scores per day
+fn alliance_resource_totals(entries: &[AppointmentEntry]) -> HashMap<String, AllianceResources> {
+    let mut totals: HashMap<String, AllianceResources> = HashMap::new();
+    // Players applying per alliance and day, for the averages
+    let mut applicants: HashMap<String, [u64; 3]> = HashMap::new();
+    
+    for entry in entries {
+        let resources = totals.entry(entry.alliance.clone()).or_default();
+        let counts = applicants.entry(entry.alliance.clone()).or_default();
+        if entry.wants_construction {
+            resources.construction_speedups += entry.construction_speedups as u64;
+            resources.construction_truegold += entry.construction_truegold as u64;
+            resources.average_construction_score += entry.construction_score as f64;
+            counts[0] += 1;
+        }
+        if entry.wants_research {
+            resources.research_speedups += entry.research_speedups as u64;
+            resources.research_truegold_dust += entry.research_truegold_dust as u64;
+            resources.average_research_score += entry.research_score as f64;
+            counts[1] += 1;
+        }
+        if entry.wants_troops {
+            resources.troops_speedups += entry.troops_speedups as u64;
+            resources.average_troops_score += entry.troops_speedups as f64;
+            counts[2] += 1;
+        }
+    }
+    
+    // Turn the score sums into averages
+    for (alliance, resources) in totals.iter_mut() {
+        let counts = applicants[alliance];
+        if counts[0] > 0 {
+            resources.average_construction_score /= counts[0] as f64;
+        }
+        if counts[1] > 0 {
+            resources.average_research_score /= counts[1] as f64;
+        }
+        if counts[2] > 0 {
+            resources.average_troops_score /= counts[2] as f64;
+        }
+    }
+    totals
+}
+
+// Number of bins score histograms aim for
+const SCORE_HISTOGRAM_BINS: u32 = 10;
+
+// Rounds a bin width up to 1, 2 or 5 times a power of ten so bin edges stay readable
+fn nice_bin_width(raw: u32) -> u32 {
+    let mut magnitude: u32 = 1;
+    loop {
+        for step in [1, 2, 5] {
+            let width = step * magnitude;
+            if width >= raw {
+                return width;
+            }
+        }
+        magnitude = match magnitude.checked_mul(10) {
+            Some(m) => m,
+            None => return raw,
+        };
+    }
+}
+
+// Histogram of the given scores, with bins covering the lowest to the highest score
+fn score_histogram(mut scores: Vec<u32>, slots: usize) -> ScoreHistogram {
+    scores.sort_unstable();
+    let (min, max) = match (scores.first(), scores.last()) {
+        (Some(min), Some(max)) => (*min, *max),
+        _ => return ScoreHistogram { slots: slots as u32, ..Default::default() },
+    };
+    
+    let width = nice_bin_width((max - min).div_ceil(SCORE_HISTOGRAM_BINS).max(1));
+    let first = min / width;
+    let mut bins: Vec<ScoreBin> = (first..=max / width)
+        .map(|i| ScoreBin {
+            min: i * width,
+            max: i * width + (width - 1),
+            count: 0,
+        })
+        .collect();
+    for score in &scores {
+        bins[(score / width - first) as usize].count += 1;
+    }
+    
+    ScoreHistogram {
+        applicants: scores.len() as u32,
+        slots: slots as u32,
+        min,
+        median: scores[scores.len() / 2],
+        max,
+        bins,
+    }
+}
+
+// Score distributions of the players applying for each day
+fn score_distributions(entries: &[AppointmentEntry], config: Option<&FormConfig>) -> ScoreDistributions {
+    let scores = |wants: fn(&AppointmentEntry) -> bool, score: fn(&AppointmentEntry) -> u32| -> Vec<u32> {
+        entries.iter().filter(|e| wants(e)).map(score).collect()
+    };
+    ScoreDistributions {
+        construction: score_histogram(
+            scores(|e| e.wants_construction, |e| e.construction_score),
+            day_time_slots(config, "construction").len(),
+        ),
+        research: score_histogram(
+            scores(|e| e.wants_research, |e| e.research_score),
+            day_time_slots(config, "research").len(),
+        ),
+        troops: score_histogram(
+            scores(|e| e.wants_troops, |e| e.troops_speedups),
+            day_time_slots(config, "troops").len(),
+        ),
+    }
+}
+
+// Computed statistics as flat tables: per-alliance counts and resources, slot popularity and score distribution
+pub(crate) fn stats_tables(stats: &StatsResponse, config: Option<&FormConfig>) -> Vec<StatsTable> {
+    let mut alliances: Vec<&String> = stats.alliance_counts.keys().collect();
+    alliances.sort();
+    let no_resources = AllianceResources::default();
+    let alliance_rows = alliances.into_iter()
+        .map(|alliance| {
+            let counts = &stats.alliance_counts[alliance];
+            let resources = stats.alliance_resources.as_ref().and_then(|r| r.get(alliance)).unwrap_or(&no_resources);
+            vec![
+                StatsCell::Text(alliance.clone()),
+                StatsCell::Number(counts.construction_requests as f64),
+                StatsCell::Number(counts.research_requests as f64),
+                StatsCell::Number(counts.troops_requests as f64),
+                StatsCell::Number(resources.construction_speedups as f64),
+                StatsCell::Number(resources.construction_truegold as f64),
+                StatsCell::Number(resources.research_speedups as f64),
+                StatsCell::Number(resources.research_truegold_dust as f64),
+                StatsCell::Number(resources.troops_speedups as f64),
+                StatsCell::Number(resources.average_construction_score),
+                StatsCell::Number(resources.average_research_score),
+                StatsCell::Number(resources.average_troops_score),
+            ]
+        })
+        .collect();
+    
+    // Per-day popularity from form statistics, or split from the combined map of uploaded CSVs
+    let mut slot_rows = Vec::new();
+    for day_str in ["construction", "research", "troops"] {
+        let popularity = match day_str {
+            "construction" => &stats.construction_time_slot_popularity,
+            "research" => &stats.research_time_slot_popularity,
+            _ => &stats.troops_time_slot_popularity,
+        };
+        let mut requests: Vec<(String, u32)> = match (popularity, &stats.time_slot_popularity) {
+            (Some(popularity), _) => popularity.iter().map(|(time, s)| (time.clone(), s.requests)).collect(),
+            (None, Some(combined)) => combined.iter()
+                .map(|(time, s)| {
+                    let count = match day_str {
+                        "construction" => s.construction_requests,
+                        "research" => s.research_requests,
+                        _ => s.troops_requests,
+                    };
+                    (time.clone(), count)
+                })
+                .collect(),
+            (None, None) => Vec::new(),
+        };
+        // Keep the order of the day's slots, which can wrap past midnight
+        let order: Vec<String> = day_time_slots(config, day_str).into_iter().map(|(_, time)| time).collect();
+        requests.sort_by_key(|(time, _)| (order.iter().position(|t| t == time).unwrap_or(usize::MAX), time.clone()));
+        for (time, count) in requests {
+            slot_rows.push(vec![
+                StatsCell::Text(day_str.to_string()),
+                StatsCell::Text(time),
+                StatsCell::Number(count as f64),
+            ]);
+        }
+    }
+    
+    let mut score_rows = Vec::new();
+    if let Some(distribution) = &stats.score_distribution {
+        for (day_str, histogram) in [("construction", &distribution.construction), ("research", &distribution.research), ("troops", &distribution.troops)] {
+            for bin in &histogram.bins {
+                score_rows.push(vec![
+                    StatsCell::Text(day_str.to_string()),
+                    StatsCell::Number(bin.min as f64),
+                    StatsCell::Number(bin.max as f64),
+                    StatsCell::Number(bin.count as f64),
+                ]);
+            }
+        }
+    }
+    
+    vec![
+        StatsTable {
+            name: "alliances",
+            headers: &[
+                "alliance", "construction_requests", "research_requests", "troops_requests",
+                "construction_speedups", "construction_truegold", "research_speedups", "research_truegold_dust", "troops_speedups",
+                "average_construction_score", "average_research_score", "average_troops_score",
+            ],
+            rows: alliance_rows,
+        },
+        StatsTable {
+            name: "slots",
+            headers: &["day", "time", "requests"],
+            rows: slot_rows,
+        },
+        StatsTable {
+            name: "scores",
+            headers: &["day", "score_min", "score_max", "players"],
+            rows: score_rows,
+        },
+    ]
+}
+
+pub(crate) fn stats_table_csv(table: &StatsTable) -> std::result::Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(table.headers)?;
+    for row in &table.rows {
+        writer.write_record(row.iter().map(|cell| match cell {
+            StatsCell::Text(text) => text.clone(),
+            StatsCell::Number(number) => number.to_string(),
+        }))?;
+    }
+    Ok(writer.into_inner()?)
+}
+
+// All statistics tables as one workbook, one sheet per table
+pub(crate) fn stats_workbook(tables: &[StatsTable]) -> std::result::Result<Vec<u8>, rust_xlsxwriter::XlsxError> {
+    let mut workbook = rust_xlsxwriter::Workbook::new();
+    for table in tables {
+        let sheet = workbook.add_worksheet();
+        sheet.set_name(table.name)?;
+        for (col, header) in table.headers.iter().enumerate() {
+            sheet.write_string(0, col as u16, *header)?;
+        }
+        for (row, cells) in table.rows.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                match cell {
+                    StatsCell::Text(text) => sheet.write_string(row as u32 + 1, col as u16, text)?,
+                    StatsCell::Number(number) => sheet.write_number(row as u32 + 1, col as u16, *number)?,
+                };
+            }
+        }
+    }
+    workbook.save_to_buffer()
+}
+
+// Download the computed statistics as CSV (one table) or XLSX (every table)
+async fn export_stats(
+    path: web::Path<(String, u32)>,
+    query: web::Query<StatsExportQuery>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let stats_state = state.clone();
+    let stats_account = account_name.clone();
+    let (stats, config) = web::block(move || {
+        let stats = load_or_compute_stats(&stats_state, &stats_account, server_number);
+        let (_, config) = load_form_entries(&stats_state, &stats_account, server_number);
+        (stats, config)
+    }).await?;
+    let tables = stats_tables(&stats, config.as_ref());
+    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+    
+    match query.format.as_deref().unwrap_or("csv") {
+        "xlsx" => {
+            let workbook = stats_workbook(&tables).map_err(|e| {
+                actix_web::error::ErrorInternalServerError(format!("Failed to build workbook: {}", e))
+            })?;
+            let filename = format!("{}_{}_stats_{}.xlsx", account_name, server_number, timestamp);
+            Ok(HttpResponse::Ok()
+                .content_type("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+                .append_header(("Content-Disposition", format!("attachment; filename=\"{}\"", filename)))
+                .body(workbook))
+        }
+        "csv" => {
+            let table_name = query.table.as_deref().unwrap_or("alliances");
+            let table = match tables.iter().find(|t| t.name == table_name) {
+                Some(table) => table,
+                None => {
+                    return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                        "success": false,
+                        "error": "Unknown table, use alliances, slots or scores"
+                    })));
+                }
+            };
+            let csv_content = stats_table_csv(table).map_err(|e| {
+                actix_web::error::ErrorInternalServerError(format!("Failed to write CSV: {}", e))
+            })?;
+            let filename = format!("{}_{}_stats_{}_{}.csv", account_name, server_number, table.name, timestamp);
+            Ok(HttpResponse::Ok()
+                .content_type("text/csv")
+                .append_header(("Content-Disposition", format!("attachment; filename=\"{}\"", filename)))
+                .body(csv_content))
+        }
+        _ => Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "Unknown format, use csv or xlsx"
+        }))),
+    }
+}
+
+// Time slots of one day: from the form config, or the fixed mapping for uploaded CSVs
+fn day_time_slots(config: Option<&FormConfig>, day_str: &str) -> Vec<(u8, String)> {
+    match (day_str, config) {
+        ("construction", Some(config)) => config.construction_times.time_slots(),
+        ("research", Some(config)) => config.research_times.time_slots(),
+        ("troops", Some(config)) => config.troops_times.time_slots(),
+        _ => (1..=49).map(|slot| (slot, slot_to_time(slot))).collect(),
+    }
+}
+
+// Parsed submissions of a form CSV, using the form's time slots if there is a config. Big forms
+// take a while to parse, so parsed files are cached until they change on disk.
+fn read_submissions(state: &AppState, csv_path: &str, config: Option<&FormConfig>) -> std::result::Result<Vec<AppointmentEntry>, String> {
+    let slots = config.map(|c| ["construction", "research", "troops"].map(|day| day_time_slots(Some(c), day)));
+    // Read before parsing, so a write during the parse makes the next read parse again
+    let metadata = std::fs::metadata(csv_path).map_err(|e| e.to_string())?;
+    let modified = metadata.modified().map_err(|e| e.to_string())?;
+    if let Some(cached) = state.parsed_submissions.lock().unwrap().get(csv_path) {
+        if cached.modified == modified && cached.len == metadata.len() && cached.slots == slots {
+            return Ok(cached.entries.clone());
+        }
+    }
+    
+    let entries = load_appointments(
+        csv_path,
+        slots.as_ref().map(|s| s[0].as_slice()),
+        slots.as_ref().map(|s| s[1].as_slice()),
+        slots.as_ref().map(|s| s[2].as_slice()),
+    ).map_err(|e| e.to_string())?;
+    let mut cache = state.parsed_submissions.lock().unwrap();
+    if cache.len() >= MAX_PARSED_SUBMISSIONS && !cache.contains_key(csv_path) {
+        cache.clear();
+    }
+    cache.insert(csv_path.to_string(), ParsedSubmissions {
+        modified,
+        len: metadata.len(),
+        slots,
+        entries: entries.clone(),
+    });
+    Ok(entries)
+}
+
+// Parsed submissions of a form CSV, empty if it can't be read
+fn load_csv_entries(state: &AppState, csv_path: &str, config: Option<&FormConfig>) -> Vec<AppointmentEntry> {
+    read_submissions(state, csv_path, config).unwrap_or_else(|e| {
+        log::warn!("Failed to load submissions from {}: {}", csv_path, e);
+        Vec::new()
+    })
+}
+
+// Submissions CSV of the current form, or the legacy uploaded CSV, with the form config if there is one
+fn current_submissions_csv(state: &AppState, account_name: &str, server_number: u32) -> (String, Option<FormConfig>) {
+    let current_form = {
+        let forms = state.forms.lock().unwrap();
+        let current_forms = state.current_forms.lock().unwrap();
+        get_current_form(&forms, &current_forms, account_name, server_number)
+    };
+    match current_form {
+        Some(form) => (format!("{}/current_forms/{}_submissions.csv", state.data_dir, form.code), Some(form.config)),
+        None => (format!("{}/{}_{}_form_submissions.csv", state.data_dir, account_name, server_number), None),
+    }
+}
+
+// Parsed submissions of the current form, or of a legacy uploaded CSV, with the form config if there is one
+fn load_form_entries(state: &AppState, account_name: &str, server_number: u32) -> (Vec<AppointmentEntry>, Option<FormConfig>) {
+    let (csv_path, config) = current_submissions_csv(state, account_name, server_number);
+    
+    if Path::new(&csv_path).exists() {
+        let mut entries = load_csv_entries(state, &csv_path, config.as_ref());
+        apply_roster(&mut entries, &load_roster(&state.data_dir, account_name, server_number));
+        return (entries, config);
+    }
+    
+    // Uploaded CSVs are only kept as parsed entries in the saved schedule
+    let entries = load_schedule(&state.data_dir, account_name, server_number)
+        .and_then(|s| s.entries)
+        .unwrap_or_default();
+    (entries, config)
+}
+
+// Slot x alliance availability counts for one day, only counting players who want that day
+fn day_heatmap(entries: &[AppointmentEntry], alliances: &[String], time_slots: Vec<(u8, String)>, day_str: &str) -> DayHeatmap {
+    let mut counts = vec![vec![0u32; alliances.len()]; time_slots.len()];
+    for entry in entries {
+        let (wants, available) = match day_str {
+            "construction" => (entry.wants_construction, &entry.construction_available_slots),
+            "research" => (entry.wants_research, &entry.research_available_slots),
+            _ => (entry.wants_troops, &entry.troops_available_slots),
+        };
+        let alliance_idx = match alliances.iter().position(|a| *a == entry.alliance) {
+            Some(idx) if wants => idx,
+            _ => continue,
+        };
+        for slot in available {
+            if let Some(slot_idx) = time_slots.iter().position(|(s, _)| s == slot) {
+                counts[slot_idx][alliance_idx] += 1;
+            }
+        }
+    }
+    
+    DayHeatmap {
+        totals: counts.iter().map(|row| row.iter().sum()).collect(),
+        times: time_slots.into_iter().map(|(_, time)| time).collect(),
+        counts,
+    }
+}
+
+// Availability heatmap (time slot x alliance) for each day, computed from the current submissions
+async fn get_stats_heatmap(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    
+    let visibility = stats_visibility(&state, &session, &account_name, server_number);
+    if visibility == PublicStats::Disabled {
+        return Ok(stats_not_public_response());
+    }
+    
+    let (entries, config) = load_form_entries(&state, &account_name, server_number);
+    let mut alliances: Vec<String> = entries.iter().map(|e| e.alliance.clone()).collect::<HashSet<_>>().into_iter().collect();
+    alliances.sort();
+    
+    let mut heatmap = HeatmapResponse {
+        construction: day_heatmap(&entries, &alliances, day_time_slots(config.as_ref(), "construction"), "construction"),
+        research: day_heatmap(&entries, &alliances, day_time_slots(config.as_ref(), "research"), "research"),
+        troops: day_heatmap(&entries, &alliances, day_time_slots(config.as_ref(), "troops"), "troops"),
+        alliances,
+    };
+    // Without the alliance breakdown only the per-slot totals remain
+    if visibility != PublicStats::Full {
+        heatmap.alliances.clear();
+        for day in [&mut heatmap.construction, &mut heatmap.research, &mut heatmap.troops] {
+            day.counts.iter_mut().for_each(Vec::clear);
+        }
+    }
+    Ok(HttpResponse::Ok().json(heatmap))
+}
+
+// Submission times (local time) and player IDs from a submissions CSV, in file order
+fn read_submission_times(csv_path: &str) -> Vec<(chrono::NaiveDateTime, String)> {
+    let mut reader = match csv::ReaderBuilder::new().flexible(true).from_path(csv_path) {
+        Ok(reader) => reader,
+        Err(_) => return Vec::new(),
+    };
+    let id_col = reader.headers()
+        .ok()
+        .and_then(|h| h.iter().position(|h| h.contains("player ID")))
+        .unwrap_or(4);
+    reader.records()
+        .filter_map(|r| r.ok())
+        .filter_map(|record| {
+            let time = chrono::NaiveDateTime::parse_from_str(record.get(0)?.trim(), "%d/%m/%Y %H.%M.%S").ok()?;
+            Some((time, record.get(id_col).unwrap_or("").trim().to_string()))
+        })
+        .collect()
+}
+
+// Buckets submission times by hour or day, filling empty buckets from `from` to the last submission
+fn timeline_buckets(times: &[chrono::NaiveDateTime], from: chrono::NaiveDateTime, step: chrono::Duration, label: &str) -> Vec<TimelineBucket> {
+    let last = match times.iter().max() {
+        Some(last) => *last,
+        None => return Vec::new(),
+    };
+    let mut buckets = Vec::new();
+    let mut start = from;
+    let mut cumulative = 0;
+    while start <= last {
+        let end = start + step;
+        let submissions = times.iter().filter(|t| **t >= start && **t < end).count() as u32;
+        cumulative += submissions;
+        buckets.push(TimelineBucket {
+            start: start.format(label).to_string(),
+            submissions,
+            cumulative,
+        });
+        start = end;
+    }
+    buckets
+}
+
+// Submissions per hour and per day since the current form was created, to judge whether to wait for stragglers
+async fn get_stats_timeline(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    
+    if matches!(stats_visibility(&state, &session, &account_name, server_number), PublicStats::SlotsOnly | PublicStats::Disabled) {
+        return Ok(stats_not_public_response());
+    }
+    
+    let current_form = {
+        let forms = state.forms.lock().unwrap();
+        let current_forms = state.current_forms.lock().unwrap();
+        get_current_form(&forms, &current_forms, &account_name, server_number)
+    };
+    let form = match current_form {
+        Some(form) => form,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "No active form for this server"
+            })));
+        }
+    };
+    
+    let rows = read_submission_times(&format!("{}/current_forms/{}_submissions.csv", state.data_dir, form.code));
+    let times: Vec<chrono::NaiveDateTime> = rows.iter().map(|(time, _)| *time).collect();
+    let created_at = chrono::DateTime::parse_from_rfc3339(&form.created_at)
+        .ok()
+        .map(|t| t.with_timezone(&chrono::Local).naive_local());
+    
+    // Start at the form's creation (or the first submission), rounded down to the hour/day
+    let start = created_at.into_iter().chain(times.iter().copied()).min();
+    let (hourly, daily) = match start {
+        Some(start) => {
+            let hour = start.date().and_hms_opt(start.time().hour(), 0, 0).unwrap_or(start);
+            let day = start.date().and_hms_opt(0, 0, 0).unwrap_or(start);
+            (
+                timeline_buckets(&times, hour, chrono::Duration::hours(1), "%Y-%m-%d %H:00"),
+                timeline_buckets(&times, day, chrono::Duration::days(1), "%Y-%m-%d"),
+            )
+        }
+        None => (Vec::new(), Vec::new()),
+    };
+    
+    Ok(HttpResponse::Ok().json(TimelineResponse {
+        form_created_at: created_at.map(|t| t.format("%Y-%m-%d %H:%M").to_string()),
+        total_submissions: times.len() as u32,
+        unique_players: rows.iter().map(|(_, id)| id.as_str()).filter(|id| !id.is_empty()).collect::<HashSet<_>>().len(),
+        last_submission_at: times.iter().max().map(|t| t.format("%Y-%m-%d %H:%M").to_string()),
+        hourly,
+        daily,
+    }))
+}
+
+// Default and maximum number of players per leaderboard day
+const LEADERBOARD_DEFAULT_LIMIT: usize = 10;
+const LEADERBOARD_MAX_LIMIT: usize = 100;
+
+// Top players of one day by score; troops use speedups since that is what troops slots are ranked by
+fn day_leaderboard(entries: &[AppointmentEntry], day_str: &str, limit: usize) -> Vec<LeaderboardEntry> {
+    let mut scored: Vec<&AppointmentEntry> = entries.iter()
+        .filter(|e| match day_str {
+            "construction" => e.wants_construction,
+            "research" => e.wants_research,
+            _ => e.wants_troops,
+        })
+        .collect();
+    let score = |e: &AppointmentEntry| day_score(e, day_str);
+    scored.sort_by(|a, b| score(b).cmp(&score(a)).then_with(|| a.name.cmp(&b.name)));
+    
+    scored.into_iter()
+        .take(limit)
+        .enumerate()
+        .map(|(i, e)| LeaderboardEntry {
+            rank: i + 1,
+            name: e.name.clone(),
+            alliance: e.alliance.clone(),
+            score: score(e),
+        })
+        .collect()
+}
+
+// Whether a server's leaderboard is public
+fn is_leaderboard_public(state: &AppState, account_name: &str, server_number: u32) -> bool {
+    state.accounts.lock().unwrap()
+        .get(account_name)
+        .is_some_and(|a| a.public_leaderboards.contains(&server_number))
+}
+
+// Top players per day from the current form's submissions. Admin-only unless the owner made it public.
+async fn get_leaderboard(
+    path: web::Path<(String, u32)>,
+    query: web::Query<LeaderboardQuery>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    
+    let public = is_leaderboard_public(&state, &account_name, server_number);
+    if !public && !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "The leaderboard is not public"
+        })));
+    }
+    
+    let limit = query.limit.unwrap_or(LEADERBOARD_DEFAULT_LIMIT).clamp(1, LEADERBOARD_MAX_LIMIT);
+    let (entries, _) = load_form_entries(&state, &account_name, server_number);
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "public": public,
+        "construction": day_leaderboard(&entries, "construction", limit),
+        "research": day_leaderboard(&entries, "research", limit),
+        "troops": day_leaderboard(&entries, "troops", limit)
+    })))
+}
+
+// Make the leaderboard of one of the logged-in account's servers public or admin-only
+async fn update_leaderboard_visibility(
+    path: web::Path<(String, u32)>,
+    req: web::Json<LeaderboardVisibilityRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let mut accounts = state.accounts.lock().unwrap();
+    let account = match accounts.get_mut(&account_name) {
+        Some(account) => account,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Account not found"
+            })));
+        }
+    };
+    if req.public {
+        account.public_leaderboards.insert(server_number);
+    } else {
+        account.public_leaderboards.remove(&server_number);
+    }
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "public": req.public
+    })))
+}
+
+// Builds the schedule response for one day, regenerating from form submissions if nothing is stored
+// Returns None if the day is not construction, research or troops. Slot notes are left out unless
+// `admin`, like the substitutes suggested for filled slots.
+fn build_schedule_response(
+    state: &AppState,
+    account_name: &str,
+    server_number: u32,
+    day_str: &str,
+    admin: bool,
+) -> Option<ScheduleResponse> {
+    let key = schedule_key(account_name, server_number);
+    
+    // Try to load from disk first
+    if let Some(schedule_data) = load_schedule(&state.data_dir, account_name, server_number) {
+        // Also update in-memory cache
+        let mut schedules = state.schedules.lock().unwrap();
+        schedules.insert(key.clone(), schedule_data.clone());
+        drop(schedules);
+        
+        // Get form config for custom time slots
+        let form_config = {
+            let forms = state.forms.lock().unwrap();
+            let current_forms = state.current_forms.lock().unwrap();
+            get_current_form(&forms, &current_forms, account_name, server_number)
+                .map(|f| f.config.clone())
+        };
+        
+        // Get the requested day's schedule
+        let schedule = match day_str {
+            "construction" => schedule_data.construction_schedule.clone(),
+            "research" => schedule_data.research_schedule.clone(),
+            "troops" => schedule_data.troops_schedule.clone(),
+        _ => return None,
+    };
+
+        if let Some(schedule) = schedule {
+            // Generate time slots based on form config or use fixed mapping
+            let time_slots: Vec<(u8, String)> = match (day_str, form_config.as_ref()) {
+                ("construction", Some(config)) => {
+                    config.construction_times.time_slots()
+                },
+                ("research", Some(config)) => {
+                    config.research_times.time_slots()
+                },
+                ("troops", Some(config)) => {
+                    config.troops_times.time_slots()
+                },
+                _ => {
+                    // Fallback to fixed mapping
+                    (1..=49).map(|slot| (slot, slot_to_time(slot))).collect()
+                }
+            };
+            
+            let mut substitutes = if admin {
+                let entries = schedule_data.entries.clone()
+                    .unwrap_or_else(|| load_form_entries(state, account_name, server_number).0);
+                slot_substitutes(&schedule, &entries, day_str)
+            } else {
+                HashMap::new()
+            };
+            
+            // Build response with appointments
+            let mut appointments = Vec::new();
+            for (slot, time) in time_slots {
+                if let Some(appt) = schedule.appointments.get(&slot) {
+                    appointments.push(ScheduleSlot {
+                        time,
+                        player: Some(format_player_name(&appt.alliance, &appt.name)),
+                        is_empty: false,
+                        player_id: Some(appt.player_id.clone()).filter(|_| admin),
+                        note: appt.note.clone().filter(|_| admin),
+                        substitute: substitutes.remove(&slot),
+                        manual: appt.manual && admin,
+                    });
+                } else {
+                    appointments.push(ScheduleSlot {
+                        time,
+                        player: None,
+                        is_empty: true,
+                        player_id: None,
+                        note: None,
+                        substitute: None,
+                        manual: false,
+                    });
+                }
+            }
+            
+            let day_name = match day_str {
+            "construction" => "Construction Day",
+            "research" => "Research Day",
+            "troops" => "Troops Training Day",
+                _ => "Unknown Day",
+        };
+
+            return Some(ScheduleResponse {
+                day_name: day_name.to_string(),
+                appointments,
+            });
+        }
+    }
+    
+    // If not found on disk, get form config for this account/server to get custom time slots
+    let form_config = {
+        let forms = state.forms.lock().unwrap();
+        let current_forms = state.current_forms.lock().unwrap();
+        get_current_form(&forms, &current_forms, account_name, server_number)
+            .map(|f| f.config.clone())
+    };
+    
+    // Generate time slots based on form config or use fixed mapping
+    let time_slots: Vec<(u8, String)> = match (day_str, form_config.as_ref()) {
+        ("construction", Some(config)) => {
+            config.construction_times.time_slots()
+        },
+        ("research", Some(config)) => {
+            config.research_times.time_slots()
+        },
+        ("troops", Some(config)) => {
+            config.troops_times.time_slots()
+        },
+        _ => {
+            // Fallback to fixed mapping (backward compatibility for uploaded CSVs)
+            (1..=49).map(|slot| (slot, slot_to_time(slot))).collect()
+        }
+    };
+    
+    let day_name = match day_str {
+        "construction" => "Construction Day",
+        "research" => "Research Day",
+        "troops" => "Troops Training Day",
+        _ => return None,
+    };
+    
+    // Check if schedule exists in memory
+    let schedule_opt = {
+        let schedules = state.schedules.lock().unwrap();
+        if let Some(schedule_data) = schedules.get(&key) {
+            match day_str {
+                "construction" => schedule_data.construction_schedule.as_ref().cloned(),
+                "research" => schedule_data.research_schedule.as_ref().cloned(),
+                "troops" => schedule_data.troops_schedule.as_ref().cloned(),
+                _ => None,
+            }
+        } else {
+            None
+        }
+    };
+    
+    // If schedule doesn't exist, try to regenerate from form submissions CSV
+    let schedule = if let Some(s) = schedule_opt {
+        s
+    } else {
+        // Try to load from form submissions CSV and regenerate schedules
+        // First try to find current form and use its CSV, otherwise try old location for migration
+        let form_csv_path = {
+            let forms = state.forms.lock().unwrap();
+            let current_forms = state.current_forms.lock().unwrap();
+            if let Some(current_form) = get_current_form(&forms, &current_forms, account_name, server_number) {
+                // Use new location: current_forms/{code}_submissions.csv
+                drop(current_forms);
+                format!("{}/current_forms/{}_submissions.csv", state.data_dir, current_form.code)
+            } else {
+                drop(current_forms);
+                // Fallback to old location for migration
+                format!("{}/{}_{}_form_submissions.csv", state.data_dir, account_name, server_number)
+            }
+        };
+        
+        if Path::new(&form_csv_path).exists() {
+            let config_for_loading = form_config.clone();
+            let (construction_slots, research_slots, troops_slots) = if let Some(config) = &config_for_loading {
+                (
+                    Some(config.construction_times.time_slots()),
+                    Some(config.research_times.time_slots()),
+                    Some(config.troops_times.time_slots()),
+                )
+            } else {
+                (None, None, None)
+            };
+            
+            if let Ok(mut entries) = load_appointments(
+                &form_csv_path,
+                construction_slots.as_deref(),
+                research_slots.as_deref(),
+                troops_slots.as_deref(),
+            ) {
+                if let Some(config) = &config_for_loading {
+                    remove_blackout_slots(&mut entries, &config.construction_times, &config.research_times, &config.troops_times);
+                }
+                // Generate schedules (pass last_slot from form config when available)
+                let last_slot_override = construction_slots.as_ref()
+                    .and_then(|slots| slots.iter().map(|(s, _)| *s).max());
+                let construction_schedule = schedule_construction_day_with_locked(
+                    &entries,
+                    &HashSet::new(),
+                    last_slot_override,
+                );
+                let research_schedule = schedule_research_day(&entries, &construction_schedule);
+                let troops_schedule = schedule_troops_day(&entries);
+                
+                // Create schedule data
+                let scheduled_ids: Vec<String> = {
+                    let mut ids = HashSet::new();
+                    for appt in construction_schedule.appointments.values() {
+                        ids.insert(appt.player_id.clone());
+                    }
+                    for appt in research_schedule.appointments.values() {
+                        ids.insert(appt.player_id.clone());
+                    }
+                    for appt in troops_schedule.appointments.values() {
+                        ids.insert(appt.player_id.clone());
+                    }
+                    ids.into_iter().collect()
+                };
+                let schedule_data = ScheduleData {
+                    construction_schedule: Some(construction_schedule.clone()),
+                    research_schedule: Some(research_schedule.clone()),
+                    troops_schedule: Some(troops_schedule.clone()),
+                    entries: Some(entries.clone()),
+                    scheduled_player_ids: Some(scheduled_ids),
+                };
+                
+                // Save to state
+                let mut schedules = state.schedules.lock().unwrap();
+                schedules.insert(key.clone(), schedule_data.clone());
+                drop(schedules);
+                
+                // Save to disk
+                if let Err(e) = save_schedule(&state.data_dir, account_name, server_number, &schedule_data) {
+                    log::warn!("Failed to save schedule to disk: {}", e);
+                }
+                
+                // Return the appropriate schedule
+                match day_str {
+                    "construction" => construction_schedule,
+                    "research" => research_schedule,
+                    "troops" => troops_schedule,
+                    _ => return None,
+                }
+            } else {
+                // No form submissions or error loading, return empty schedule
+                DaySchedule {
+                    appointments: HashMap::new(),
+                    unassigned: Vec::new(),
+                }
+            }
+        } else {
+            // No form submissions CSV, return empty schedule
+            DaySchedule {
+                appointments: HashMap::new(),
+                unassigned: Vec::new(),
+            }
+        }
+    };
+    
+    // Build response with appointments
+        let mut appointments = Vec::new();
+    for (slot, time) in time_slots {
+            if let Some(appt) = schedule.appointments.get(&slot) {
+                let formatted_name = format_player_name(&appt.alliance, &appt.name);
+                appointments.push(ScheduleSlot {
+                    time,
+                    player: Some(formatted_name),
+                    is_empty: false,
+                    player_id: None,
+                    note: None,
+                    substitute: None,
+                    manual: false,
+                });
+            } else {
+                appointments.push(ScheduleSlot {
+                    time,
+                    player: None,
+                    is_empty: true,
+                    player_id: None,
+                    note: None,
+                    substitute: None,
+                    manual: false,
+                });
+            }
+        }
+
+        Some(ScheduleResponse {
+            day_name: day_name.to_string(),
+            appointments,
+        })
+}
+
+// Schedule endpoint, with slot notes and substitutes for the account's admins
+async fn get_schedule(
+    path: web::Path<(String, u32, String)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number, day_str) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    let admin = session_is_for(&session, &account_name, server_number);
+    
+    match build_schedule_response(&state, &account_name, server_number, &day_str, admin) {
+        Some(schedule) => Ok(HttpResponse::Ok().json(schedule)),
+        None => Ok(HttpResponse::BadRequest().json(serde_json::json!({"error": "Invalid day"}))),
+    }
+}
+
+// HTML page handlers - account creation page
+async fn create_account_page() -> Result<HttpResponse> {
+    let html = include_str!("../templates/create_account.html");
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+async fn claim_account_page() -> Result<HttpResponse> {
+    let html = include_str!("../templates/claim_account.html");
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+async fn reset_password_page() -> Result<HttpResponse> {
+    let html = include_str!("../templates/reset_password.html");
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+// HTML page handlers - schedules page
+async fn schedules_page(
+    path: web::Path<(String, u32)>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    
+    // Verify account exists
+    let accounts = state.accounts.lock().unwrap();
+    let account_name_lower = account_name.to_lowercase();
+    if !accounts.contains_key(&account_name_lower) {
+        return Ok(HttpResponse::NotFound().body("Account not found"));
+    }
+    drop(accounts);
+    
+    if let Some(slug) = slug_for(&state, &account_name_lower, server_number) {
+        return Ok(redirect_to(format!("/{}", slug)));
+    }
+    
+    let html = include_str!("../templates/schedules.html");
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+// HTML page handlers - stats page
+async fn stats_page(
+    path: web::Path<(String, u32)>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    
+    // Verify account exists
+    let accounts = state.accounts.lock().unwrap();
+    let account_name_lower = account_name.to_lowercase();
+    if !accounts.contains_key(&account_name_lower) {
+        return Ok(HttpResponse::NotFound().body("Account not found"));
+    }
+    drop(accounts);
+    
+    if let Some(slug) = slug_for(&state, &account_name_lower, server_number) {
+        return Ok(redirect_to(format!("/{}/stats", slug)));
+    }
+    
+    let html = include_str!("../templates/stats.html");
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+// HTML page handlers - admin page
+async fn admin_page(
+    path: web::Path<(String, u32)>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, _server_number) = path.into_inner();
+    
+    // Verify account exists
+    let accounts = state.accounts.lock().unwrap();
+    let account_name_lower = account_name.to_lowercase();
+    if !accounts.contains_key(&account_name_lower) {
+        return Ok(HttpResponse::NotFound().body("Account not found"));
+    }
+    drop(accounts);
+    
+    let html = include_str!("../templates/admin.html");
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+// Form config as the public form page gets it, with the markdown content rendered to HTML
+// and the slot numbers withheld by blackouts. Passcodes are replaced by which alliances need one.
+fn public_form_config(config: &FormConfig) -> serde_json::Value {
+    let mut value = serde_json::to_value(config).unwrap_or_default();
+    if let Some(object) = value.as_object_mut() {
+        object.remove("passcodes");
+    }
+    value["passcode_alliances"] = serde_json::json!(config.passcodes.protected_alliances());
+    value["form_passcode"] = serde_json::json!(config.passcodes.form.is_some());
+    value["content_html"] = serde_json::to_value(config.content.to_html()).unwrap_or_default();
+    value["blackout_slots"] = serde_json::json!({
+        "construction": config.construction_times.blackout_slots(),
+        "research": config.research_times.blackout_slots(),
+        "troops": config.troops_times.blackout_slots(),
+    });
+    value
+}
+
+// Public form page - accessible via /form/{code}
+async fn public_form_page(
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let code = path.into_inner();
+    
+    // Verify form exists
+    let forms = state.forms.lock().unwrap();
+    let form_data = match forms.get(&code) {
+        Some(form_data) => form_data.clone(),
+        None => {
+            drop(forms);
+            return Ok(HttpResponse::NotFound().body("Form not found"));
+        }
+    };
+    drop(forms);
+    
+    if is_account_suspended(&state, &form_data.account_name) {
+        return Ok(HttpResponse::Forbidden().body("This form is no longer accepting submissions"));
+    }
+    
+    // Escape '<' so the JSON can't close the surrounding <script> tag
+    let config_json = serde_json::to_string(&public_form_config(&form_data.config))
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to serialize form config: {}", e)))?
+        .replace('<', "\\u003c");
+    
+    let branding = account_branding(&state, &form_data.account_name);
+    render_page(&FormPageTemplate {
+        code: form_data.code,
+        form_name: form_data.name,
+        intro_html: form_data.config.content.intro.as_deref().map(render_markdown),
+        intro_text: form_data.config.intro_text,
+        config_json,
+        branding,
+    })
+}
+
+// Form submission endpoint (by form code)
+async fn submit_form_by_code(
+    path: web::Path<String>,
+    req: web::Json<FormSubmissionRequest>,
+    http_req: HttpRequest,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let code = path.into_inner();
+    
+    // Verify form exists and get config
+    let forms = state.forms.lock().unwrap();
+    let form_data = forms.get(&code).cloned();
+    drop(forms);
+    
+    let form_account;
+    let form_server;
+    let form_name;
+    let config = if let Some(fd) = form_data {
+        if is_account_suspended(&state, &fd.account_name) {
+            return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+                "success": false,
+                "error": "This form is no longer accepting submissions"
+            })));
+        }
+        if fd.config.is_closed(chrono::Utc::now()) {
+            return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+                "success": false,
+                "error": "This form is closed and no longer accepts submissions",
+                "error_key": "formClosed"
+            })));
+        }
+        
+        // Enforce the owning account's limits
+        let limits = account_limits(&state, &fd.account_name);
+        if let Some(max_submissions) = limits.max_submissions_per_form {
+            let csv_path = format!("{}/current_forms/{}_submissions.csv", state.data_dir, code);
+            if count_submissions(Path::new(&csv_path)) >= max_submissions {
+                return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+                    "success": false,
+                    "error": format!("This form has reached its limit of {} submissions. Please contact your alliance leadership.", max_submissions)
+                })));
+            }
+        }
+        if check_storage_limit(&state, &fd.account_name, &limits).is_some() {
+            return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+                "success": false,
+                "error": "This form can't accept more submissions because its account has run out of storage. Please contact your alliance leadership."
+            })));
+        }
+        
+        form_account = fd.account_name;
+        form_server = fd.server_number;
+        form_name = fd.name;
+        fd.config
+    } else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Form not found"
+        })));
+    };
+    
+    // Validate submission
+    let validation = validate_submission(&req)
+        .and_then(|()| validate_passcode(&req, config.passcodes.required_for(&req.alliance, req.custom_alliance.as_deref().unwrap_or_default())))
+        .and_then(|()| validate_blackout_slots(
+            &req,
+            &config.construction_times.blackout_slots(),
+            &config.research_times.blackout_slots(),
+            &config.troops_times.blackout_slots(),
+        ));
+    if let Err(err) = validation {
+        let strings = req.language.as_ref().and_then(|lang| config.language_packs.get(lang)).map(|pack| &pack.strings);
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": err.message(strings),
+            "error_key": err.key,
+            "error_params": err.params_json()
+        })));
+    }
+    
+    // Create form submission with timestamp (format: DD/MM/YYYY HH.MM.SS)
+    let timestamp = chrono::Local::now().format("%d/%m/%Y %H.%M.%S").to_string();
+    let submission = FormSubmission {
+        timestamp,
+        alliance: req.alliance.clone(),
+        custom_alliance: req.custom_alliance.clone(),
+        character_name: req.character_name.clone(),
+        player_id: req.player_id.clone(),
+        submission_type: req.submission_type.clone(),
+        wants_construction: req.wants_construction,
+        construction_speedups: req.construction_speedups,
+        construction_truegold: req.construction_truegold,
+        construction_time_slots: req.construction_time_slots.clone(),
+        wants_research: req.wants_research,
+        research_speedups: req.research_speedups,
+        research_truegold_dust: req.research_truegold_dust,
+        research_time_slots: req.research_time_slots.clone(),
+        wants_troops: req.wants_troops,
+        troops_speedups: req.troops_speedups,
+        troops_time_slots: req.troops_time_slots.clone(),
+        additional_notes: req.additional_notes.clone(),
+        suggestions: req.suggestions.clone(),
+    };
+    
+    // Export to CSV (save in current_forms folder with form code)
+    let current_forms_dir = format!("{}/current_forms", state.data_dir);
+    std::fs::create_dir_all(&current_forms_dir)?;
+    let csv_path = format!("{}/{}_submissions.csv", current_forms_dir, code);
+    let csv_path = Path::new(&csv_path);
+    
+    if let Err(e) = export_submission_to_csv(
+        &submission,
+        csv_path,
+        &config.construction_times,
+        &config.research_times,
+        &config.troops_times,
+    ) {
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "success": false,
+            "error": format!("Failed to save submission: {}", e)
+        })));
+    }
+    
+    if let Some(token) = req.draft_token.as_deref().filter(|t| draft::is_valid_draft_token(t)) {
+        draft::delete_draft(&state.data_dir, &code, token);
+    }
+    
+    let salt = state.accounts.lock().unwrap().get(&form_account)
+        .filter(|a| a.submission_metadata)
+        .map(|a| a.metadata_salt.clone());
+    if let Some(salt) = salt {
+        let connection = http_req.connection_info();
+        // The port changes with every connection
+        let ip = connection.realip_remote_addr().map(|addr| {
+            addr.parse::<std::net::SocketAddr>().map(|a| a.ip().to_string()).unwrap_or_else(|_| addr.to_string())
+        });
+        let submission_metadata = SubmissionMetadata {
+            timestamp: submission.timestamp.clone(),
+            player_id: submission.player_id.trim().to_string(),
+            ip_hash: ip.map(|ip| metadata::hash_ip(&ip, &salt)),
+            user_agent: http_req.headers().get("User-Agent")
+                .and_then(|v| v.to_str().ok())
+                .and_then(metadata::clean_user_agent),
+            form_version: metadata::form_version(&config),
+        };
+        // The submission is saved already, so a failure here only loses its metadata
+        if let Err(e) = metadata::append_metadata(&state.data_dir, &code, &submission_metadata) {
+            log::warn!("Failed to save submission metadata of form {}: {}", code, e);
+        }
+    }
+    
+    notify(&state, &form_account, Notification {
+        event: NotificationEvent::NewSubmission,
+        title: format!("New submission on server {}", form_server),
+        body: format!(
+            "{} ({}) {} {}.",
+            format_player_name(&submission.alliance, &submission.character_name),
+            submission.player_id.trim(),
+            if submission.submission_type.to_lowercase().contains("re-submission") { "re-submitted" } else { "submitted" },
+            form_name
+        ),
+        email: None,
+    });
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "message": "Form submitted successfully"
+    })))
+}
+
+// Why a form can't take drafts: it doesn't exist, its account is suspended or out of storage
+fn draft_form_rejection(state: &AppState, code: &str) -> Option<HttpResponse> {
+    let form_data = state.forms.lock().unwrap().get(code).cloned();
+    match form_data {
+        Some(fd) if fd.config.is_closed(chrono::Utc::now()) => Some(HttpResponse::Forbidden().json(serde_json::json!({
+            "success": false,
+            "error": "This form is closed, drafts can no longer be saved"
+        }))),
+        Some(fd) if !is_account_suspended(state, &fd.account_name) => {
+            let limits = account_limits(state, &fd.account_name);
+            check_storage_limit(state, &fd.account_name, &limits).map(|_| {
+                HttpResponse::Forbidden().json(serde_json::json!({
+                    "success": false,
+                    "error": "Drafts can't be saved because this form's account has run out of storage. Please contact your alliance leadership."
+                }))
+            })
+        }
+        _ => Some(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Form not found"
+        }))),
+    }
+}
+
+fn draft_too_large(draft: &FormDraft) -> bool {
+    serde_json::to_string(draft).map(|s| s.len() > MAX_DRAFT_BYTES).unwrap_or(true)
+}
+
+// Save a partially filled submission as a new draft and return its token
+async fn create_form_draft(
+    path: web::Path<String>,
+    body: web::Json<FormDraft>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let code = path.into_inner();
+    if let Some(response) = draft_form_rejection(&state, &code) {
+        return Ok(response);
+    }
+    let mut form_draft = body.into_inner();
+    if draft_too_large(&form_draft) {
+        return Ok(HttpResponse::PayloadTooLarge().json(serde_json::json!({
+            "success": false,
+            "error": "The draft is too large to save"
+        })));
+    }
+    if draft::count_drafts(&state.data_dir, &code) >= MAX_DRAFTS_PER_FORM {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "success": false,
+            "error": "This form can't store more drafts. Please submit the form instead."
+        })));
+    }
+    
+    let token = draft::generate_draft_token();
+    draft::save_draft(&state.data_dir, &code, &token, &mut form_draft)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "token": token,
+        "saved_at": form_draft.saved_at
+    })))
+}
+
+// Overwrite an existing draft
+async fn update_form_draft(
+    path: web::Path<(String, String)>,
+    body: web::Json<FormDraft>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (code, token) = path.into_inner();
+    if let Some(response) = draft_form_rejection(&state, &code) {
+        return Ok(response);
+    }
+    if !draft::is_valid_draft_token(&token) || draft::load_draft(&state.data_dir, &code, &token).is_none() {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Draft not found"
+        })));
+    }
+    let mut form_draft = body.into_inner();
+    if draft_too_large(&form_draft) {
+        return Ok(HttpResponse::PayloadTooLarge().json(serde_json::json!({
+            "success": false,
+            "error": "The draft is too large to save"
+        })));
+    }
+    
+    draft::save_draft(&state.data_dir, &code, &token, &mut form_draft)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "token": token,
+        "saved_at": form_draft.saved_at
+    })))
+}
+
+// Load a draft to resume it
+async fn get_form_draft(
+    path: web::Path<(String, String)>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (code, token) = path.into_inner();
+    let form_exists = state.forms.lock().unwrap().get(&code)
+        .is_some_and(|fd| !is_account_suspended(&state, &fd.account_name));
+    let found = if form_exists && draft::is_valid_draft_token(&token) {
+        draft::load_draft(&state.data_dir, &code, &token)
+    } else {
+        None
+    };
+    match found {
+        Some(form_draft) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "draft": form_draft
+        }))),
+        None => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Draft not found"
+        }))),
+    }
+}
+
+// Days and times of a player's latest submission, from the current form or the account's
+// archived forms, so returning players can prefill the form. Only for accounts that turned
+// prefilling on, and only with the player's passcode or draft.
+async fn get_previous_submission_by_code(
+    path: web::Path<(String, String)>,
+    req: web::Json<PreviousSubmissionRequest>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (code, player_id) = path.into_inner();
+    let player_id = player_id.trim().to_string();
+    
+    let form_data = state.forms.lock().unwrap().get(&code).cloned();
+    let form_data = match form_data {
+        Some(fd) if !is_account_suspended(&state, &fd.account_name) => fd,
+        _ => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Form not found"
+            })));
+        }
+    };
+    let prefill_answers = state.accounts.lock().unwrap().get(&form_data.account_name).is_some_and(|a| a.prefill_answers);
+    if !prefill_answers {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "success": false,
+            "error": "This form doesn't fill in previous answers"
+        })));
+    }
+    if player_id.is_empty() || !player_id.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "Player ID must contain only digits"
+        })));
+    }
+    
+    // Older submissions are read with the current form's time slots, so times outside
+    // the current windows drop out
+    let mut sources = vec![(format!("{}/current_forms/{}_submissions.csv", state.data_dir, code), true)];
+    sources.extend(
+        archived_forms_for(&state.data_dir, &form_data.account_name, form_data.server_number).into_iter()
+            .map(|(id, _)| (format!("{}/old_forms/{}_{}_{}_submissions.csv", state.data_dir, form_data.account_name, form_data.server_number, id), false)),
+    );
+    let found = sources.into_iter()
+        .filter(|(path, _)| Path::new(path).exists())
+        .find_map(|(path, current)| {
+            load_csv_entries(&state, &path, Some(&form_data.config)).into_iter()
+                .find(|e| e.player_id == player_id)
+                .map(|e| (e, current))
+        });
+    let Some((entry, current)) = found else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "No previous submission found for this player ID"
+        })));
+    };
+    
+    // Alliances that are no longer listed go into the free text field
+    let (alliance, custom_alliance) = if form_data.config.alliances.contains(&entry.alliance) {
+        (entry.alliance.clone(), None)
+    } else {
+        ("Non of the above".to_string(), Some(entry.alliance.clone()))
+    };
+    
+    // The same secret submitting needs: the alliance's passcode, or else a draft of this player,
+    // so knowing an ID isn't enough to read someone's answers
+    let passcode_ok = form_data.config.passcodes.required_for(&alliance, custom_alliance.as_deref().unwrap_or_default())
+        .is_some_and(|passcode| req.passcode.as_deref().map(str::trim) == Some(passcode));
+    let draft_ok = req.draft_token.as_deref()
+        .filter(|token| draft::is_valid_draft_token(token))
+        .and_then(|token| draft::load_draft(&state.data_dir, &code, token))
+        .is_some_and(|form_draft| form_draft.player_id.trim() == player_id);
+    if !passcode_ok && !draft_ok {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "success": false,
+            "error": "invalidPasscode"
+        })));
+    }
+    
+    // Only what the form is filled with; resources change every week and are asked again
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "from_current_form": current,
+        "submission": {
+            "alliance": alliance,
+            "custom_alliance": custom_alliance,
+            "character_name": entry.name,
+            "submission_type": if current { "Re-Submission" } else { "New submission" },
+            "wants_construction": entry.wants_construction,
+            "construction_time_slots": entry.construction_available_slots,
+            "wants_research": entry.wants_research,
+            "research_time_slots": entry.research_available_slots,
+            "wants_troops": entry.wants_troops,
+            "troops_time_slots": entry.troops_available_slots,
+        }
+    })))
+}
+
+// Create form endpoint (admin only)
+#[derive(Deserialize)]
+pub struct CreateFormRequest {
+    pub name: Option<String>, // Optional form name
+    pub alliances: Vec<String>,
+    pub construction_times: DayTimeConfig,
+    pub research_times: DayTimeConfig,
+    pub troops_times: DayTimeConfig,
+    #[serde(default)]
+    pub predetermined_slots: Vec<PredeterminedSlot>, // Predetermined slot assignments
+    #[serde(default)]
+    pub guaranteed_players: GuaranteedPlayers,
+    #[serde(default)]
+    pub intro_text: Option<String>, // Optional introduction text
+    #[serde(default)]
+    pub content: FormContent,
+    #[serde(default)]
+    pub public_stats: PublicStats,
+    #[serde(default)]
+    pub closes_at: Option<String>,
+    #[serde(default)]
+    pub auto_generate: bool,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateFormConfigRequest {
+    pub predetermined_slots: Vec<PredeterminedSlot>, // Predetermined slot assignments
+    #[serde(default)]
+    pub guaranteed_players: GuaranteedPlayers,
+}
+
+async fn create_form(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    body: web::Json<CreateFormRequest>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    
+    let (url_account_name, server_number) = path.into_inner();
+    let url_account_name = url_account_name.to_lowercase();
+    
+    // Verify session authentication
+    let session_account_name: String = match session.get("account_name") {
+        Ok(Some(name)) => name,
+        Ok(None) => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not logged in"
+            })));
+        }
+        Err(_) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to read session"
+            })));
+        }
+    };
+    let session_server_number: u32 = match session.get("server_number") {
+        Ok(Some(num)) => num,
+        Ok(None) => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not logged in"
+            })));
+        }
+        Err(_) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to read session"
+            })));
+        }
+    };
+    
+    // Verify account name and server number match session
+    if session_account_name.to_lowercase() != url_account_name || session_server_number != server_number {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Unauthorized"
+        })));
+    }
+    
+    // Verify account exists
+    let accounts = state.accounts.lock().unwrap();
+    if !accounts.contains_key(&url_account_name) {
+        drop(accounts);
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Account not found"
+        })));
+    }
+    drop(accounts);
+    
+    // Validate alliances (must have at least one)
+    if body.alliances.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "At least one alliance must be specified"
+        })));
+    }
+    
+    // Enforce account limits. The current form of this server is archived below, so it doesn't count.
+    let limits = account_limits(&state, &url_account_name);
+    if let Some(max_forms) = limits.max_active_forms {
+        let active_forms = state.forms.lock().unwrap().values()
+            .filter(|f| f.account_name == url_account_name && f.server_number != server_number)
+            .count();
+        if active_forms >= max_forms {
+            return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+                "success": false,
+                "error": format!("Form limit reached: this account can have at most {} active form(s) across its servers", max_forms)
+            })));
+        }
+    }
+    if let Some(err) = check_storage_limit(&state, &url_account_name, &limits) {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "success": false,
+            "error": err
+        })));
+    }
+    
+    // Generate unique code - check both in-memory forms and files on disk
+    let mut code = generate_form_code();
+    let mut max_attempts = 100; // Prevent infinite loop
+    loop {
+        // Check in-memory forms
+        let forms = state.forms.lock().unwrap();
+        let in_memory = forms.contains_key(&code);
+        drop(forms);
+        
+        // Check if file exists on disk (current_forms folder)
+        let current_forms_file = format!("{}/current_forms/{}.json", state.data_dir, code);
+        let file_exists = Path::new(&current_forms_file).exists();
+        
+        // Check if file exists in old_forms folder (scan all subdirectories)
+        let old_forms_dir = format!("{}/old_forms", state.data_dir);
+        let mut old_file_exists = false;
+        if Path::new(&old_forms_dir).exists() {
+            if let Ok(entries) = std::fs::read_dir(&old_forms_dir) {
+                for entry in entries.flatten() {
+                    if let Ok(entry_path) = entry.path().canonicalize() {
+                        if entry_path.is_dir() {
+                            let old_form_file = entry_path.join(format!("{}.json", code));
+                            if old_form_file.exists() {
+                                old_file_exists = true;
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        
+        if !in_memory && !file_exists && !old_file_exists {
+            // Code is unique, break the loop
+            break;
+        }
+        
+        // Code collision detected, generate new one
+        code = generate_form_code();
+        max_attempts -= 1;
+        if max_attempts <= 0 {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to generate unique form code after multiple attempts. Please try again."
+            })));
+        }
+    }
+    
+    if let Err(e) = body.content.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": e
+        })));
+    }
+
+    if let Err(e) = validate_closes_at(body.closes_at.as_deref()) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": e
+        })));
+    }
+
+    for (label, times) in [("Construction", &body.construction_times), ("Research", &body.research_times), ("Troops Training", &body.troops_times)] {
+        if let Err(e) = validate_day_windows(times) {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": format!("{} day: {}", label, e)
+            })));
+        }
+    }
+
+    // Translations, scoring and passcodes are kept from week to week
+    let (language_packs, score_formulas, passcodes) = {
+        let forms = state.forms.lock().unwrap();
+        let current_forms = state.current_forms.lock().unwrap();
+        current_forms.get(&schedule_key(&url_account_name, server_number))
+            .and_then(|code| forms.get(code))
+            .map(|f| (f.config.language_packs.clone(), f.config.score_formulas.clone(), f.config.passcodes.clone()))
+            .unwrap_or_default()
+    };
+    
+    // Create form data
+    let form_name = body.name.clone().unwrap_or_else(|| {
+        format!("Form {} {}", url_account_name, server_number)
+    });
+    let created_at = chrono::Utc::now().to_rfc3339();
+    
+    let form_data = FormData {
+        code: code.clone(),
+        account_name: url_account_name.clone(),
+        server_number,
+        name: form_name,
+        created_at,
+        config: FormConfig {
+            alliances: body.alliances.clone(),
+            construction_times: body.construction_times.clone(),
+            research_times: body.research_times.clone(),
+            troops_times: body.troops_times.clone(),
+            predetermined_slots: body.predetermined_slots.clone(),
+            guaranteed_players: body.guaranteed_players.trimmed(),
+            intro_text: body.intro_text.clone(),
+            content: body.content.clone().trimmed(),
+            public_stats: body.public_stats,
+            power: None, // A new week needs a new recording
+            language_packs,
+            score_formulas,
+            passcodes: passcodes.trimmed(&body.alliances),
+            closes_at: body.closes_at.clone(),
+            auto_generate: body.auto_generate,
+        },
+        auto_generation: None,
+    };
+    
+    // Archive old forms for this account/server before creating new one
+    archive_old_forms(&state.data_dir, &url_account_name, server_number)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to archive old forms: {}", e)))?;
+    
+    // Save new form
+    let mut forms = state.forms.lock().unwrap();
+    // Remove old forms for this account/server from memory
+    forms.retain(|_, fd| !(fd.account_name == url_account_name && fd.server_number == server_number));
+    forms.insert(code.clone(), form_data.clone());
+    drop(forms);
+    
+    save_form(&state.data_dir, &form_data)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save form: {}", e)))?;
+    
+    // Update current forms mapping
+    let mut current_forms = state.current_forms.lock().unwrap();
+    let key = format!("{}:{}", url_account_name, server_number);
+    current_forms.insert(key, code.clone());
+    save_current_forms(&state.data_dir, &current_forms)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save current forms mapping: {}", e)))?;
+    drop(current_forms);
+    
+    // Build form URL - use relative path since we don't have HttpRequest
+    let form_url = format!("/form/{}", code);
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "message": "Form created successfully",
+        "code": code,
+        "url": form_url
+    })))
+}
+
+// Update form config endpoint (for updating predetermined slots)
+async fn update_form_config(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    body: web::Json<UpdateFormConfigRequest>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    
+    let (url_account_name, server_number) = path.into_inner();
+    let url_account_name = url_account_name.to_lowercase();
+    
+    // Verify session authentication
+    let session_account_name: String = match session.get("account_name") {
+        Ok(Some(name)) => name,
+        Ok(None) => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not logged in"
+            })));
+        }
+        Err(_) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to read session"
+            })));
+        }
+    };
+    let session_server_number: u32 = match session.get("server_number") {
+        Ok(Some(num)) => num,
+        Ok(None) => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not logged in"
+            })));
+        }
+        Err(_) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to read session"
+            })));
+        }
+    };
+    
+    // Verify account name and server number match session
+    if session_account_name.to_lowercase() != url_account_name || session_server_number != server_number {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Unauthorized"
+        })));
+    }
+    
+    // Get current form for this account/server
+    let mut forms = state.forms.lock().unwrap();
+    let current_forms = state.current_forms.lock().unwrap();
+    let key = format!("{}:{}", url_account_name, server_number);
+    
+    let form_code = if let Some(code) = current_forms.get(&key) {
+        code.clone()
+    } else {
+        drop(forms);
+        drop(current_forms);
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "No current form found"
+        })));
+    };
+    
+    // Get the form
+    let mut form_data = if let Some(form) = forms.get(&form_code).cloned() {
+        form
+    } else {
+        drop(forms);
+        drop(current_forms);
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Form not found"
+        })));
+    };
+    
+    drop(current_forms);
+    
+    // Update predetermined slots and guaranteed players
+    form_data.config.predetermined_slots = body.predetermined_slots.clone();
+    form_data.config.guaranteed_players = body.guaranteed_players.trimmed();
+    
+    // Save updated form
+    save_form(&state.data_dir, &form_data)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save form: {}", e)))?;
+    
+    // Update in memory
+    forms.insert(form_code.clone(), form_data);
+    drop(forms);
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "message": "Form config updated successfully"
+    })))
+}
+
+// Get form config by code (public)
+async fn get_form_config_by_code(
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let code = path.into_inner();
+    
+    let forms = state.forms.lock().unwrap();
+    if let Some(form_data) = forms.get(&code) {
+        let config = form_data.config.clone();
+        drop(forms);
+        Ok(HttpResponse::Ok().json(public_form_config(&config)))
+    } else {
+        drop(forms);
+        Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Form not found"
+        })))
+    }
+}
+
+// Get form statistics by code (public - shows only time slot popularity)
+#[derive(Serialize)]
+pub struct FormStatsResponse {
+    construction_start_time: String,
+    research_start_time: String,
+    troops_start_time: String,
+    construction_time_slot_popularity: HashMap<String, FormTimeSlotStats>,
+    research_time_slot_popularity: HashMap<String, FormTimeSlotStats>,
+    troops_time_slot_popularity: HashMap<String, FormTimeSlotStats>,
+}
+
+async fn get_form_stats_by_code(
+    path: web::Path<String>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let code = path.into_inner();
+    
+    // Get form data to find account_name and server_number, and get config
+    let forms = state.forms.lock().unwrap();
+    let form_data = forms.get(&code).cloned();
+    drop(forms);
+    
+    let config = if let Some(fd) = form_data {
+        if fd.config.public_stats == PublicStats::Disabled && !session_is_for(&session, &fd.account_name, fd.server_number) {
+            return Ok(stats_not_public_response());
+        }
+        fd.config
+    } else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Form not found"
+        })));
+    };
+    
+    // Read form submissions CSV file (using form code)
+    let current_forms_dir = format!("{}/current_forms", state.data_dir);
+    let csv_path = format!("{}/{}_submissions.csv", current_forms_dir, code);
+    
+    // Generate time slots for each day type based on form configuration
+    let construction_slots = config.construction_times.time_slots();
+    let research_slots = config.research_times.time_slots();
+    let troops_slots = config.troops_times.time_slots();
+    
+    // Convert to slices for passing to load_appointments
+    let construction_slots_ref: Vec<(u8, String)> = construction_slots.clone();
+    let research_slots_ref: Vec<(u8, String)> = research_slots.clone();
+    let troops_slots_ref: Vec<(u8, String)> = troops_slots.clone();
+    
+    // Parse CSV file using load_appointments with custom time slot mappings
+    let entries = match load_appointments(
+        &csv_path,
+        Some(&construction_slots_ref),
+        Some(&research_slots_ref),
+        Some(&troops_slots_ref),
+    ) {
+        Ok(e) => e,
+        Err(e) => {
+            // If file doesn't exist or can't be parsed, return empty stats
+            log::error!("Failed to load form submissions CSV from {}: {}", csv_path, e);
+            return Ok(HttpResponse::Ok().json(FormStatsResponse {
+                construction_start_time: "00:00".to_string(),
+                research_start_time: "00:00".to_string(),
+                troops_start_time: "00:00".to_string(),
+                construction_time_slot_popularity: HashMap::new(),
+                research_time_slot_popularity: HashMap::new(),
+                troops_time_slot_popularity: HashMap::new(),
+            }));
+        }
+    };
+    
+    // Initialize time slot popularity for Construction
+    let mut construction_time_slot_popularity: HashMap<String, FormTimeSlotStats> = HashMap::new();
+    for (_, time) in &construction_slots {
+        construction_time_slot_popularity.insert(time.clone(), FormTimeSlotStats {
+            requests: 0,
+        });
+    }
+    
+    // Initialize time slot popularity for Research
+    let mut research_time_slot_popularity: HashMap<String, FormTimeSlotStats> = HashMap::new();
+    for (_, time) in &research_slots {
+        research_time_slot_popularity.insert(time.clone(), FormTimeSlotStats {
+            requests: 0,
+        });
+    }
+    
+    // Initialize time slot popularity for Troops
+    let mut troops_time_slot_popularity: HashMap<String, FormTimeSlotStats> = HashMap::new();
+    for (_, time) in &troops_slots {
+        troops_time_slot_popularity.insert(time.clone(), FormTimeSlotStats {
+            requests: 0,
+        });
+    }
+    
+    // Create maps from slot number to time string for each day type
+    let construction_slot_to_time: HashMap<u8, String> = construction_slots.iter().map(|(s, t)| (*s, t.clone())).collect();
+    let research_slot_to_time: HashMap<u8, String> = research_slots.iter().map(|(s, t)| (*s, t.clone())).collect();
+    let troops_slot_to_time: HashMap<u8, String> = troops_slots.iter().map(|(s, t)| (*s, t.clone())).collect();
+    
+    // Count actual selections
+    for entry in entries {
+        // Count construction time slot popularity
+        for slot in &entry.construction_available_slots {
+            if let Some(time) = construction_slot_to_time.get(slot) {
+                if let Some(slot_stats) = construction_time_slot_popularity.get_mut(time) {
+                    slot_stats.requests += 1;
+                }
+            }
+        }
+        
+        // Count research time slot popularity
+        for slot in &entry.research_available_slots {
+            if let Some(time) = research_slot_to_time.get(slot) {
+                if let Some(slot_stats) = research_time_slot_popularity.get_mut(time) {
+                    slot_stats.requests += 1;
+                }
+            }
+        }
+        
+        // Count troops time slot popularity
+        for slot in &entry.troops_available_slots {
+            if let Some(time) = troops_slot_to_time.get(slot) {
+                if let Some(slot_stats) = troops_time_slot_popularity.get_mut(time) {
+                    slot_stats.requests += 1;
+                }
+            }
+        }
+    }
+    
+    Ok(HttpResponse::Ok().json(FormStatsResponse {
+        construction_start_time: config.construction_times.start_time.clone(),
+        research_start_time: config.research_times.start_time.clone(),
+        troops_start_time: config.troops_times.start_time.clone(),
+        construction_time_slot_popularity,
+        research_time_slot_popularity,
+        troops_time_slot_popularity,
+    }))
+}
+
+// Public form statistics page handler
+async fn public_form_stats_page(
+    path: web::Path<String>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let code = path.into_inner();
+    
+    // Verify form exists
+    let forms = state.forms.lock().unwrap();
+    if !forms.contains_key(&code) {
+        drop(forms);
+        return Ok(HttpResponse::NotFound().body("Form not found"));
+    }
+    drop(forms);
+    
+    let html = include_str!("../templates/form_stats.html");
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+// Get current form info for account (admin - to display current form link)
+async fn get_current_form_info(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse> {
+    let (url_account_name, server_number) = path.into_inner();
+    let url_account_name = url_account_name.to_lowercase();
+    
+    // Try session authentication first, fallback to password authentication
+    let authenticated = {
+        // Check session
+        let session_account_name: Option<String> = session.get("account_name").ok().flatten();
+        let session_server_number: Option<u32> = session.get("server_number").ok().flatten();
+        
+        if let (Some(session_account_name), Some(session_server_number)) = (session_account_name, session_server_number) {
+            // Verify the account_name and server_number match
+            session_account_name == url_account_name && session_server_number == server_number
+        } else {
+            // Fallback: check password header (for admin page)
+            if let Some(password_header) = req.headers().get("X-Password") {
+                if let Ok(password) = password_header.to_str() {
+                    let accounts = state.accounts.lock().unwrap();
+                    if let Some(account) = accounts.get(&url_account_name) {
+                        account.password == password && account.owns_server(server_number)
+                    } else {
+                        false
+                    }
+                } else {
+                    false
+                }
+            } else {
+                false
+            }
+        }
+    };
+    
+    if !authenticated {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authenticated"
+        })));
+    }
+    
+    // Get current form - first try from mapping, then check files in current_forms folder
+    let forms = state.forms.lock().unwrap();
+    let current_forms = state.current_forms.lock().unwrap();
+    let mut current_form = get_current_form(&forms, &current_forms, &url_account_name, server_number);
+    drop(forms);
+    drop(current_forms);
+    
+    // If not found in mapping or forms HashMap, check files in current_forms folder directly
+    if current_form.is_none() {
+        let current_forms_dir = format!("{}/current_forms", state.data_dir);
+        log::debug!("Checking current_forms directory: {}", current_forms_dir);
+        log::debug!("Looking for form with account_name: '{}', server_number: {}", url_account_name, server_number);
+        
+        if let Ok(entries) = std::fs::read_dir(&current_forms_dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                // Only process .json files (not CSV files)
+                if path.extension().and_then(|s| s.to_str()) == Some("json") {
+                    if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
+                        // Skip files that end with _submissions (those are CSV files, not form JSON)
+                        if file_name.contains("_submissions") {
+                            continue;
+                        }
+                        
+                        log::debug!("Checking file: {}", file_name);
+                        
+                        // Try to load the form JSON file
+                        if let Ok(content) = std::fs::read_to_string(&path) {
+                            if let Ok(mut form_data) = serde_json::from_str::<FormData>(&content) {
+                                // Normalize account_name to lowercase for comparison
+                                let form_account_name = form_data.account_name.to_lowercase();
+                                log::debug!("Found form: account_name='{}', server_number={}, code='{}'", 
+                                    form_account_name, form_data.server_number, form_data.code);
+                                
+                                // Check if this form belongs to the requested account/server
+                                if form_account_name == url_account_name && form_data.server_number == server_number {
+                                    log::debug!("Match found! Returning form: {}", form_data.code);
+                                    // Ensure account_name is lowercase in the returned form
+                                    form_data.account_name = form_account_name;
+                                    current_form = Some(form_data);
+                                    break;
+                                } else {
+                                    log::debug!("No match: form_account_name='{}' != url_account_name='{}' OR server_number={} != {}", 
+                                        form_account_name, url_account_name, form_data.server_number, server_number);
+                                }
+                            } else {
+                                log::warn!("Failed to parse JSON from file: {}", file_name);
+                            }
+                        } else {
+                            log::warn!("Failed to read file: {}", file_name);
+                        }
+                    }
+                }
+            }
+        } else {
+            log::warn!("Failed to read directory: {}", current_forms_dir);
+        }
+    }
+    
+    if let Some(form) = current_form {
+        // Build form URL
+        let host = req.headers().get("host")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("localhost:8080");
+        let protocol = if host.contains("localhost") { "http" } else { "https" };
+        let form_url = format!("{}://{}/form/{}", protocol, host, form.code);
+        
+        // Count submissions from CSV file
+        // The CSV header is multiline, so we count actual data rows by looking for timestamp pattern
+        // Data rows start with timestamp format: DD/MM/YYYY HH.MM.SS
+        // We check if a line starts with the timestamp pattern (2 digits/2 digits/4 digits)
+        let submissions_count = {
+            let csv_path = format!("{}/current_forms/{}_submissions.csv", state.data_dir, form.code);
+            if Path::new(&csv_path).exists() {
+                if let Ok(content) = std::fs::read_to_string(&csv_path) {
+                    // Count lines that start with a timestamp (DD/MM/YYYY format)
+                    // This pattern matches data rows, not header lines
+                    content.lines()
+                        .filter(|line| {
+                            let trimmed = line.trim();
+                            // Check if line starts with DD/MM/YYYY pattern (timestamp)
+                            trimmed.len() >= 10 && 
+                            trimmed.chars().take(2).all(|c| c.is_ascii_digit()) &&
+                            trimmed.chars().nth(2) == Some('/') &&
+                            trimmed.chars().skip(3).take(2).all(|c| c.is_ascii_digit()) &&
+                            trimmed.chars().nth(5) == Some('/') &&
+                            trimmed.chars().skip(6).take(4).all(|c| c.is_ascii_digit())
+                        })
+                        .count()
+                } else {
+                    0
+                }
+            } else {
+                0
+            }
+        };
+        
+        Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "form": {
+                "code": form.code,
+                "name": form.name,
+                "created_at": form.created_at,
+                "url": form_url,
+                "submissions_count": submissions_count,
+                "config": {
+                    "alliances": form.config.alliances,
+                    "construction_times": form.config.construction_times,
+                    "research_times": form.config.research_times,
+                    "troops_times": form.config.troops_times,
+                    "predetermined_slots": form.config.predetermined_slots,
+                    "guaranteed_players": form.config.guaranteed_players,
+                    "intro_text": form.config.intro_text,
+                    "content": form.config.content,
+                    "score_formulas": form.config.score_formulas,
+                    "passcodes": if session_alliance_scope(&session).is_none() { serde_json::json!(form.config.passcodes) } else { serde_json::Value::Null },
+                    "public_stats": form.config.public_stats,
+                    "closes_at": form.config.closes_at,
+                    "auto_generate": form.config.auto_generate
+                },
+                "auto_generation": form.auto_generation
+            }
+        })))
+    } else {
+        Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "form": null
+        })))
+    }
+}
+
+// Get player info by ID from form submissions
+async fn get_player_by_id(
+    path: web::Path<(String, u32, String)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (url_account_name, server_number, player_id) = path.into_inner();
+    let url_account_name = url_account_name.to_lowercase();
+    
+    // Verify session authentication
+    let session_account_name: String = match session.get("account_name") {
+        Ok(Some(name)) => name,
+        Ok(None) => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not logged in"
+            })));
+        }
+        Err(_) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to read session"
+            })));
+        }
+    };
+    let session_server_number: u32 = match session.get("server_number") {
+        Ok(Some(num)) => num,
+        Ok(None) => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not logged in"
+            })));
+        }
+        Err(_) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to read session"
+            })));
+        }
+    };
+    
+    // Verify account name and server number match session
+    if session_account_name.to_lowercase() != url_account_name || session_server_number != server_number {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    // Get current form to find CSV path
+    let forms = state.forms.lock().unwrap();
+    let current_forms = state.current_forms.lock().unwrap();
+    let csv_path = if let Some(current_form) = get_current_form(&forms, &current_forms, &url_account_name, server_number) {
+        format!("{}/current_forms/{}_submissions.csv", state.data_dir, current_form.code)
+    } else {
+        drop(forms);
+        drop(current_forms);
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "No current form found"
+        })));
+    };
+    drop(forms);
+    drop(current_forms);
+    
+    // Load submissions and find player by ID
+    if !Path::new(&csv_path).exists() {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Form submissions not found"
+        })));
+    }
+    
+    // Load appointments (which includes player info)
+    let mut entries = match load_appointments(&csv_path, None, None, None) {
+        Ok(e) => e,
+        Err(_) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to load form submissions"
+            })));
+        }
+    };
+    apply_roster(&mut entries, &load_roster(&state.data_dir, &url_account_name, server_number));
+    
+    // Find player by ID (alliance sub-accounts can only look up their own members)
+    let alliance_scope = session_alliance_scope(&session);
+    let visible = |e: &&AppointmentEntry| alliance_scope.as_ref().is_none_or(|scope| e.alliance.eq_ignore_ascii_case(scope));
+    if let Some(entry) = entries.iter().filter(visible).find(|e| e.player_id == player_id) {
+        Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "player": {
+                "player_id": entry.player_id,
+                "name": entry.name,
+                "alliance": entry.alliance
+            }
+        })))
+    } else {
+        Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Player ID not found in form submissions"
+        })))
+    }
+}
+
+// Download current form CSV submissions
+async fn download_form_csv(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    
+    let (url_account_name, server_number) = path.into_inner();
+    let url_account_name = url_account_name.to_lowercase();
+    
+    // Verify session authentication
+    let session_account_name: String = match session.get("account_name") {
+        Ok(Some(name)) => name,
+        Ok(None) => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not logged in"
+            })));
+        }
+        Err(_) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to read session"
+            })));
+        }
+    };
+    let session_server_number: u32 = match session.get("server_number") {
+        Ok(Some(num)) => num,
+        Ok(None) => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not logged in"
+            })));
+        }
+        Err(_) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to read session"
+            })));
+        }
+    };
+    
+    // Verify account name and server number match session
+    if session_account_name.to_lowercase() != url_account_name || session_server_number != server_number {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Unauthorized"
+        })));
+    }
+    
+    // Get current form to find CSV file
+    let forms = state.forms.lock().unwrap();
+    let current_forms = state.current_forms.lock().unwrap();
+    let mut current_form = get_current_form(&forms, &current_forms, &url_account_name, server_number);
+    drop(forms);
+    drop(current_forms);
+    
+    // If not found in mapping, check files in current_forms folder
+    if current_form.is_none() {
+        let current_forms_dir = format!("{}/current_forms", state.data_dir);
+        if let Ok(entries) = std::fs::read_dir(&current_forms_dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|s| s.to_str()) == Some("json") {
+                    if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
+                        if file_name.contains("_submissions") {
+                            continue;
+                        }
+                        if let Ok(content) = std::fs::read_to_string(&path) {
+                            if let Ok(mut form_data) = serde_json::from_str::<FormData>(&content) {
+                                let form_account_name = form_data.account_name.to_lowercase();
+                                if form_account_name == url_account_name && form_data.server_number == server_number {
+                                    form_data.account_name = form_account_name;
+                                    current_form = Some(form_data);
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    
+    if let Some(form) = current_form {
+        let csv_path = format!("{}/current_forms/{}_submissions.csv", state.data_dir, form.code);
+        if Path::new(&csv_path).exists() {
+            if let Ok(csv_content) = std::fs::read_to_string(&csv_path) {
+                let filename = format!("{}_submissions_{}.csv", form.code, 
+                    chrono::Utc::now().format("%Y%m%d_%H%M%S"));
+                return Ok(HttpResponse::Ok()
+                    .content_type("text/csv")
+                    .append_header(("Content-Disposition", format!("attachment; filename=\"{}\"", filename)))
+                    .body(csv_content));
+            }
+        }
+        Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "CSV file not found"
+        })))
+    } else {
+        Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "No current form found"
+        })))
+    }
+}
+
+// Get previous form config for account (admin - to load when creating new form)
+async fn get_previous_form_config(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (url_account_name, server_number) = path.into_inner();
+    let url_account_name = url_account_name.to_lowercase();
+    
+    // Verify session authentication
+    let session_account_name: String = match session.get("account_name") {
+        Ok(Some(name)) => name,
+        Ok(None) => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not logged in"
+            })));
+        }
+        Err(_) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to read session"
+            })));
+        }
+    };
+    let session_server_number: u32 = match session.get("server_number") {
+        Ok(Some(num)) => num,
+        Ok(None) => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not logged in"
+            })));
+        }
+        Err(_) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to read session"
+            })));
+        }
+    };
+    
+    // Verify the account name and server number match
+    if session_account_name != url_account_name || session_server_number != server_number {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "success": false,
+            "error": "Access denied"
+        })));
+    }
+    
+    let account_name = url_account_name;
+    
+    // Find the most recent form for this account (get the one with latest created_at)
+    let forms = state.forms.lock().unwrap();
+    let mut previous_form: Option<FormData> = None;
+    for form_data in forms.values() {
+        if form_data.account_name == account_name && form_data.server_number == server_number {
+            match &previous_form {
+                None => previous_form = Some(form_data.clone()),
+                Some(current) => {
+                    // Compare by created_at to get most recent
+                    if let (Ok(current_time), Ok(new_time)) = (
+                        chrono::DateTime::parse_from_rfc3339(&current.created_at),
+                        chrono::DateTime::parse_from_rfc3339(&form_data.created_at)
+                    ) {
+                        if new_time > current_time {
+                            previous_form = Some(form_data.clone());
+                        }
+                    } else {
+                        // If parsing fails, just use the new one
+                        previous_form = Some(form_data.clone());
+                    }
+                }
+            }
+        }
+    }
+    drop(forms);
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "config": previous_form.map(|f| f.config)
+    })))
+}
+
+// Home page
+async fn index() -> Result<HttpResponse> {
+    let html = include_str!("../templates/index.html");
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+// Operator dashboard page (login happens on the page itself)
+async fn operator_page() -> Result<HttpResponse> {
+    let html = include_str!("../templates/operator.html");
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+// List all servers page
+async fn servers_list_page() -> Result<HttpResponse> {
+    let html = include_str!("../templates/servers_list.html");
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+#[derive(Deserialize)]
+struct ViewScheduleQuery {
+    day: Option<String>,
+}
+
+// View-only schedule page (public, no admin/stats buttons)
+// Rendered on the server so it loads in one request and works without JavaScript
+async fn view_schedule_page(
+    path: web::Path<(String, u32)>,
+    query: web::Query<ViewScheduleQuery>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    
+    // Verify the account exists and manages this server
+    let accounts = state.accounts.lock().unwrap();
+    if !accounts.get(&account_name).is_some_and(|a| a.owns_server(server_number)) {
+        return Ok(HttpResponse::NotFound().body("Schedule not found"));
+    }
+    drop(accounts);
+    
+    if let Some(slug) = slug_for(&state, &account_name, server_number) {
+        let location = match &query.day {
+            Some(day) if DAY_TABS.iter().any(|tab| tab.key == day) => format!("/{}?day={}", slug, day),
+            _ => format!("/{}", slug),
+        };
+        return Ok(redirect_to(location));
+    }
+    
+    render_view_schedule(&state, &account_name, server_number, query.day.as_deref())
+}
+
+// Renders the public schedule page for an account/server
+fn render_view_schedule(state: &AppState, account_name: &str, server_number: u32, day: Option<&str>) -> Result<HttpResponse> {
+    let current_day = day.unwrap_or("construction");
+    let schedule = match build_schedule_response(state, account_name, server_number, current_day, false) {
+        Some(schedule) => schedule,
+        None => return Ok(HttpResponse::BadRequest().body("Invalid day")),
+    };
+    
+    render_page(&ViewScheduleTemplate {
+        days: &DAY_TABS,
+        current_day,
+        schedule,
+        branding: account_branding(state, account_name),
+    })
+}
+
+// Dashboard page (for logged-in users - requires authentication)
+async fn dashboard_page(path: web::Path<String>, session: Session) -> Result<HttpResponse> {
+    let url_account_name = path.into_inner().to_lowercase();
+    
+    // Check if user is logged in
+    let session_account_name: Option<String> = session.get("account_name")
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to read session"))?;
+    
+    // Verify the account_name in URL matches the logged-in account
+    match session_account_name {
+        Some(account_name) if account_name == url_account_name => {
+            // User is authenticated and accessing their own dashboard
+            let html = include_str!("../templates/dashboard.html");
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+        Some(_) => {
+            // User is logged in but trying to access someone else's dashboard
+            Ok(HttpResponse::Forbidden().content_type("text/html").body(
+                "<html><body><h1>Access Denied</h1><p>You can only access your own dashboard.</p><a href='/'>Go Home</a></body></html>"
+            ))
+        }
+        None => {
+            // User is not logged in
+            Ok(HttpResponse::Unauthorized().content_type("text/html").body(
+                "<html><body><h1>Unauthorized</h1><p>Please log in to access the dashboard.</p><a href='/'>Go Home</a></body></html>"
+            ))
+        }
+    }
+}
+
+// Get session info endpoint (for dashboard to get account/server info)
+async fn get_session_info(session: Session, state: web::Data<AppState>) -> Result<HttpResponse> {
+    let account_name: Option<String> = session.get("account_name")
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to read session"))?;
+    let server_number: Option<u32> = session.get("server_number")
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to read session"))?;
+    
+    if let (Some(account_name), Some(server_number)) = (account_name, server_number) {
+        let servers = state.accounts.lock().unwrap()
+            .get(&account_name)
+            .map(|a| a.servers())
+            .unwrap_or_else(|| vec![server_number]);
+        
+        Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "account_name": account_name,
+            "server_number": server_number,
+            "servers": servers,
+            "alliance": session_alliance_scope(&session),
+            "slug": slug_for(&state, &account_name, server_number)
+        })))
+    } else {
+        Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authenticated"
+        })))
+    }
+}
+
+// Logout endpoint
+async fn logout_api(session: Session) -> Result<HttpResponse> {
+    session.purge();
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "message": "Logged out successfully"
+    })))
+}
+
+// API endpoint to list all servers
+async fn list_servers(state: web::Data<AppState>) -> Result<HttpResponse> {
+    let accounts = state.accounts.lock().unwrap();
+    let mut servers: Vec<ServerInfo> = accounts.values()
+        .flat_map(|acc| acc.servers().into_iter().map(move |server_number| ServerInfo {
+            account_name: acc.account_name.clone(),
+            server_number,
+            slug: acc.slugs.get(&server_number).cloned(),
+        }))
+        .collect();
+    drop(accounts);
+    
+    // Sort by account name, then server number
+    servers.sort_by(|a, b| {
+        a.account_name.cmp(&b.account_name)
+            .then_with(|| a.server_number.cmp(&b.server_number))
+    });
+    
+    Ok(HttpResponse::Ok().json(servers))
+}
+
+#[derive(Deserialize)]
+struct GenerateScheduleRequest {
+    #[serde(default)]
+    append: bool,
+    day: Option<String>, // Regenerate only this day, keeping the others
+    #[serde(default)]
+    discard_manual: bool, // Don't keep the slots placed by hand when regenerating
+}
+
+// Generate schedule endpoint (from form submissions)
+async fn generate_schedule_api(
+    payload: Option<web::Json<GenerateScheduleRequest>>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    
+    let append = payload.as_ref().map(|p| p.append).unwrap_or(false);
+    let day = payload.as_ref().and_then(|p| p.day.clone());
+    let keep_manual = !payload.as_ref().is_some_and(|p| p.discard_manual);
+    match day.as_deref() {
+        None => {}
+        Some(_) if append => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": "Regenerating one day can't be combined with appending"
+            })));
+        }
+        Some("construction" | "research" | "troops") => {}
+        Some(_) => return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "error": "Invalid day"}))),
+    }
+    // Get account_name and server_number from session
+    let account_name: String = match session.get("account_name") {
+        Ok(Some(name)) => name,
+        Ok(None) => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not logged in"
+            })));
+        }
+        Err(_) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to read session"
+            })));
+        }
+    };
+    let server_number: u32 = match session.get("server_number") {
+        Ok(Some(num)) => num,
+        Ok(None) => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not logged in"
+            })));
+        }
+        Err(_) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": "Failed to read session"
+            })));
+        }
+    };
+    
+    // Generating for a big server takes a while, so it runs as a job the dashboard polls
+    let job_state = state.clone();
+    let job_account = account_name.clone();
+    let params = serde_json::json!({ "day": day, "append": append, "keep_manual": keep_manual });
+    let job = state.jobs.enqueue("generate_schedule", &account_name, server_number, params, move |progress| {
+        let message = generate_form_schedule(&job_state, &job_account, server_number, append, day.as_deref(), keep_manual, Some(progress))?;
+        notify_schedule_generated(&job_state, &job_account, server_number, &message);
+        Ok(serde_json::json!({ "message": message }))
+    });
+    Ok(HttpResponse::Accepted().json(serde_json::json!({
+        "success": true,
+        "job": job
+    })))
+}
+
+// Drops the cached statistics and computes them again
+fn refresh_stats(state: &AppState, account_name: &str, server_number: u32) -> std::result::Result<StatsResponse, String> {
+    let stats_path = format!("{}/statistics/{}/{}.json", state.data_dir, account_name, server_number);
+    if Path::new(&stats_path).exists() {
+        std::fs::remove_file(&stats_path).map_err(|e| format!("Failed to remove cached statistics: {}", e))?;
+    }
+    Ok(load_or_compute_stats(state, account_name, server_number))
+}
+
+// Recompute the statistics from the current submissions, for when the cached ones are out of
+// date. Runs as a job like schedule generation.
+async fn recompute_stats(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let job_state = state.clone();
+    let job_account = account_name.clone();
+    let job = state.jobs.enqueue("recompute_stats", &account_name, server_number, serde_json::Value::Null, move |progress| {
+        progress.set(10, "Reading submissions");
+        let stats = refresh_stats(&job_state, &job_account, server_number)?;
+        Ok(serde_json::json!({ "alliances": stats.alliance_counts.len() }))
+    });
+    Ok(HttpResponse::Accepted().json(serde_json::json!({
+        "success": true,
+        "job": job
+    })))
+}
+
+// Background jobs of an account/server, newest first; finished ones are kept for an hour
+async fn list_jobs(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "jobs": state.jobs.list(&account_name, server_number)
+    })))
+}
+
+// One background job, polled until its status is "done" (with its result) or "failed" (with the error)
+async fn get_job(
+    path: web::Path<(String, u32, String)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number, job_id) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    match state.jobs.get(&account_name, server_number, &job_id) {
+        Some(job) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "job": job
+        }))),
+        None => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Job not found"
+        }))),
+    }
+}
+
+// Checks the current form's predetermined slots and guaranteed players against its submissions
+// without generating anything, so problems show up before the schedule is needed
+async fn check_predetermined_api(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let (config, entries, held) = match load_scheduling_entries(&state, &account_name, server_number) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": e
+            })));
+        }
+    };
+    let construction_slots = config.construction_times.time_slots();
+    let research_slots = config.research_times.time_slots();
+    let troops_slots = config.troops_times.time_slots();
+    let time_slots = DayTimeSlots {
+        construction: Some(&construction_slots),
+        research: Some(&research_slots),
+        troops: Some(&troops_slots),
+    };
+    let problems: Vec<serde_json::Value> = check_predetermined(&entries, &config.predetermined_slots, &config.guaranteed_players, time_slots)
+        .into_iter()
+        .map(|v| {
+            let day_slots = match v.day {
+                "construction" => &construction_slots,
+                "research" => &research_slots,
+                _ => &troops_slots,
+            };
+            let time = v.slot.and_then(|slot| day_slots.iter().find(|(s, _)| *s == slot)).map(|(_, time)| time.clone());
+            serde_json::json!({
+                "day": v.day,
+                "slot": v.slot,
+                "time": time,
+                "player_id": v.player_id,
+                "message": v.message
+            })
+        })
+        .collect();
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "submissions_count": entries.len(),
+        "held_duplicates": held,
+        "problems": problems
+    })))
+}
+
+// Loads the submissions of an account's current form, scored, without blackout slots and with
+// the roster names, together with the form config. Errors say why there is nothing to schedule from.
+fn load_scheduling_entries(state: &AppState, account_name: &str, server_number: u32) -> std::result::Result<(FormConfig, Vec<AppointmentEntry>, usize), String> {
+    let (form_code, form_csv_path, config) = {
+        let forms = state.forms.lock().unwrap();
+        let current_forms = state.current_forms.lock().unwrap();
+        match get_current_form(&forms, &current_forms, account_name, server_number) {
+            Some(current_form) => (
+                current_form.code.clone(),
+                format!("{}/current_forms/{}_submissions.csv", state.data_dir, current_form.code),
+                current_form.config.clone(),
+            ),
+            None => return Err("No current form found. Please create a form first.".into()),
+        }
+    };
+    
+    if !Path::new(&form_csv_path).exists() {
+        return Err("No form submissions found. Please create a form and have players submit responses first.".into());
+    }
+    
+    let mut entries = read_submissions(state, &form_csv_path, Some(&config))
+        .map_err(|e| format!("Failed to load form submissions: {}", e))?;
+    // Likely duplicates wait for the owner's review instead of taking a second slot
+    let held = duplicates::held_player_ids(&form_duplicates(&state.data_dir, &form_code));
+    let before = entries.len();
+    entries.retain(|e| !held.contains(&e.player_id));
+    let held = before - entries.len();
+    
+    if entries.is_empty() {
+        return Err("No valid form submissions found.".into());
+    }
+    score_form_entries(&state.data_dir, account_name, server_number, &config, &mut entries)?;
+    remove_blackout_slots(&mut entries, &config.construction_times, &config.research_times, &config.troops_times);
+    // After scoring, since power levels are matched by the names shown in game
+    apply_roster(&mut entries, &load_roster(&state.data_dir, account_name, server_number));
+    Ok((config, entries, held))
+}
+
+// Appointments of a day placed by hand that regeneration keeps: those still in one of the day's
+// slots, and not in a slot or of a player the form now predetermines for the day
+fn manual_seeds(schedule: Option<&DaySchedule>, config: &FormConfig, day_str: &str) -> DaySchedule {
+    let time_slots = day_time_slots(Some(config), day_str);
+    let predetermined: Vec<&PredeterminedSlot> = config.predetermined_slots.iter().filter(|p| p.day == day_str).collect();
+    let appointments = schedule.iter()
+        .flat_map(|s| s.appointments.values())
+        .filter(|appt| appt.manual)
+        .filter(|appt| time_slots.iter().any(|(slot, time)| {
+            *slot == appt.slot && !predetermined.iter().any(|p| p.time.trim() == time.trim())
+        }))
+        .filter(|appt| !predetermined.iter().any(|p| p.player_id.as_deref() == Some(appt.player_id.as_str())))
+        .map(|appt| (appt.slot, appt.clone()))
+        .collect();
+    DaySchedule { appointments, unassigned: Vec::new() }
+}
+
+// Generates (or appends to) the schedule of an account's current form from its submissions,
+// and saves it with fresh statistics. With a day, only that day is generated again and the
+// others are kept (see regenerate_day). Unless appending, slots placed by hand are kept as they
+// are when keep_manual is set (see manual_seeds). Returns the message to show, or why it couldn't be generated.
+fn generate_form_schedule(state: &AppState, account_name: &str, server_number: u32, append: bool, day: Option<&str>, keep_manual: bool, progress: Option<&JobProgress>) -> std::result::Result<String, String> {
+    let step = |percent: u8, step: &str| {
+        if let Some(progress) = progress {
+            progress.set(percent, step);
+        }
+    };
+    let account_name = account_name.to_lowercase();
+    let key = schedule_key(&account_name, server_number);
+    
+    step(5, "Loading submissions");
+    let (form_config, entries, held) = load_scheduling_entries(state, &account_name, server_number)?;
+    let construction_slots = form_config.construction_times.time_slots();
+    let research_slots = form_config.research_times.time_slots();
+    let troops_slots = form_config.troops_times.time_slots();
+    let time_slots = DayTimeSlots {
+        construction: Some(&construction_slots),
+        research: Some(&research_slots),
+        troops: Some(&troops_slots),
+    };
+    
+    if let Some(day) = day {
+        let current = {
+            let schedules = state.schedules.lock().unwrap();
+            schedules.get(&key).cloned()
+        }.or_else(|| load_schedule(&state.data_dir, &account_name, server_number))
+            .ok_or("No schedule has been generated yet. Generate all days first.")?;
+        step(25, "Assigning slots");
+        let mut current = current;
+        let mut kept = 0;
+        if let Some(Some(schedule)) = day_schedule_slot(&mut current, day) {
+            let seeds = if keep_manual { manual_seeds(Some(schedule), &form_config, day).appointments } else { HashMap::new() };
+            for appt in schedule.appointments.values_mut() {
+                appt.manual = seeds.contains_key(&appt.slot);
+            }
+            kept = seeds.len();
+        }
+        let regenerated = regenerate_day(
+            &entries,
+            &form_config.predetermined_slots,
+            &form_config.guaranteed_players,
+            time_slots,
+            ExistingDays {
+                construction: current.construction_schedule.as_ref(),
+                research: current.research_schedule.as_ref(),
+                troops: current.troops_schedule.as_ref(),
+            },
+            day,
+            keep_manual,
+        )?;
+        let mut schedule_data = current;
+        if let Some(schedule) = day_schedule_slot(&mut schedule_data, day) {
+            *schedule = Some(regenerated);
+        }
+        schedule_data.entries = Some(entries);
+        schedule_data.scheduled_player_ids = Some(derive_scheduled_player_ids(&schedule_data).into_iter().collect());
+        
+        step(75, "Saving the schedule");
+        state.schedules.lock().unwrap().insert(key, schedule_data.clone());
+        if let Err(e) = save_schedule(&state.data_dir, &account_name, server_number, &schedule_data) {
+            log::warn!("Failed to save schedule to disk: {}", e);
+        }
+        step(85, "Updating statistics");
+        load_or_compute_stats(state, &account_name, server_number);
+        let message = match day {
+            "construction" => "Construction day regenerated. Research and troops day were kept.",
+            "research" => "Research day regenerated. Construction and troops day were kept.",
+            _ => "Troops day regenerated. Construction and research day were kept.",
+        };
+        return Ok(with_held_duplicates(with_kept_manual(message, kept), held));
+    }
+    
+    // Load existing schedule when appending (from in-memory state or disk)
+    // Note: Don't hold lock during load_schedule (file I/O) to avoid blocking other requests
+    let existing_schedule = if append || keep_manual {
+        let maybe_cached = {
+            let schedules = state.schedules.lock().unwrap();
+            schedules.get(&key).cloned()
+        };
+        maybe_cached.or_else(|| load_schedule(&state.data_dir, &account_name, server_number))
+    } else {
+        None
+    };
+    
+    let (entries_to_use, existing_appointments) = match existing_schedule.as_ref() {
+        Some(existing) if append => {
+            // Use scheduled_player_ids (ID-based) to filter - players already in schedule are excluded
+            let scheduled_player_ids = get_scheduled_player_ids(existing);
+            let entries_filtered: Vec<AppointmentEntry> = entries.iter()
+                .filter(|e| !scheduled_player_ids.contains(&e.player_id))
+                .cloned()
+                .collect();
+            
+            (
+                entries_filtered,
+                (existing.construction_schedule.clone(), existing.research_schedule.clone(), existing.troops_schedule.clone()),
+            )
+        }
+        // Generating from scratch: the slots placed by hand are held like appended-to ones
+        Some(existing) => {
+            let seeds = |schedule: Option<&DaySchedule>, day_str: &str| {
+                Some(manual_seeds(schedule, &form_config, day_str)).filter(|s| !s.appointments.is_empty())
+            };
+            (
+                entries.clone(),
+                (
+                    seeds(existing.construction_schedule.as_ref(), "construction"),
+                    seeds(existing.research_schedule.as_ref(), "research"),
+                    seeds(existing.troops_schedule.as_ref(), "troops"),
+                ),
+            )
+        }
+        None => (entries.clone(), (None, None, None)),
+    };
+    
+    // When appending: if all form submissions are already in the schedule, nothing to add
+    if append && existing_schedule.is_some() && entries_to_use.is_empty() {
+        return Ok("All form submissions are already in the schedule. No new assignments to add.".to_string());
+    }
+    
+    // Resolve and lock predetermined slots, then generate the three day schedules
+    step(25, "Assigning slots");
+    let existing_days = existing_schedule.as_ref().map(|_| ExistingDays {
+        construction: existing_appointments.0.as_ref(),
+        research: existing_appointments.1.as_ref(),
+        troops: existing_appointments.2.as_ref(),
+    });
+    let (construction_schedule, research_schedule, troops_schedule) = match generate_schedules(
+        &entries,
+        &entries_to_use,
+        &form_config.predetermined_slots,
+        &form_config.guaranteed_players,
+        time_slots,
+        existing_days,
+    ) {
+        Ok(schedules) => schedules,
+        Err(e) => {
+            return Err(e);
+        }
+    };
+    
+    // When appending, merge existing appointments with new (keep existing, fill empty slots with new)
+    let construction_schedule = merge_appended(existing_appointments.0.as_ref(), construction_schedule);
+    let research_schedule = merge_appended(existing_appointments.1.as_ref(), research_schedule);
+    let troops_schedule = merge_appended(existing_appointments.2.as_ref(), troops_schedule);
+    
+    // Create schedule data, populating scheduled_player_ids for ID-based append logic
+    let scheduled_ids: Vec<String> = {
+        let mut ids = HashSet::new();
+        for appt in construction_schedule.appointments.values() {
+            ids.insert(appt.player_id.clone());
+        }
+        for appt in research_schedule.appointments.values() {
+            ids.insert(appt.player_id.clone());
+        }
+        for appt in troops_schedule.appointments.values() {
+            ids.insert(appt.player_id.clone());
+        }
+        ids.into_iter().collect()
+    };
+    let schedule_data = ScheduleData {
+        construction_schedule: Some(construction_schedule.clone()),
+        research_schedule: Some(research_schedule.clone()),
+        troops_schedule: Some(troops_schedule.clone()),
+        entries: Some(entries.clone()),
+        scheduled_player_ids: Some(scheduled_ids),
+    };
+    
+    // Save to state
+    step(75, "Saving the schedule");
+    {
+        let mut schedules = state.schedules.lock().unwrap();
+        schedules.insert(key.clone(), schedule_data.clone());
+    }
+    
+    // Save to disk
+    if let Err(e) = save_schedule(&state.data_dir, &account_name, server_number, &schedule_data) {
+        log::warn!("Failed to save schedule to disk: {}", e);
+    }
+    
+    step(85, "Updating statistics");
+    // Also regenerate and save statistics after generating schedule
+    // (This ensures stats are up-to-date with the schedule)
+    load_or_compute_stats(state, &account_name, server_number);
+    
+    let actually_merged = append && existing_schedule.is_some();
+    let kept = if append {
+        0
+    } else {
+        [&existing_appointments.0, &existing_appointments.1, &existing_appointments.2].iter()
+            .map(|day| day.as_ref().map_or(0, |d| d.appointments.len()))
+            .sum()
+    };
+    let message = if actually_merged {
+        "Schedule appended successfully! New assignments added to empty slots."
+    } else if append {
+        "No existing schedule found. Generated new schedule from form submissions."
+    } else {
+        "Schedule generated successfully from form submissions!"
+    };
+    Ok(with_held_duplicates(with_kept_manual(message, kept), held))
+}
+
+// A generation message, mentioning how many slots placed by hand were kept
+fn with_kept_manual(message: &str, kept: usize) -> String {
+    match kept {
+        0 => message.to_string(),
+        1 => format!("{} Kept 1 slot placed by hand.", message),
+        n => format!("{} Kept {} slots placed by hand.", message, n),
+    }
+}
+
+// A generation message, mentioning how many likely duplicate submissions wait for review
+fn with_held_duplicates(message: String, held: usize) -> String {
+    match held {
+        0 => message,
+        1 => format!("{} 1 likely duplicate submission was left out until it is reviewed.", message),
+        n => format!("{} {} likely duplicate submissions were left out until they are reviewed.", message, n),
+    }
+}
+
+// Update schedule slot endpoint
+#[derive(Deserialize)]
+struct UpdateSlotRequest {
+    time: String,
+    player: Option<String>, // Format: "[alliance] name" or null to clear
+}
+
+async fn update_schedule_slot(
+    path: web::Path<(String, u32, String)>,
+    req: web::Json<UpdateSlotRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number, day_str) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    
+    // Check authentication
+    if let (Some(session_account), Some(session_server)) = (
+        session.get::<String>("account_name")?,
+        session.get::<u32>("server_number")?
+    ) {
+        if session_account != account_name || session_server != server_number {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not authorized"
+            })));
+        }
+    } else {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authenticated"
+        })));
+    }
+    
+    // Load schedule
+    let key = schedule_key(&account_name, server_number);
+    let mut schedule_data = {
+        let schedules = state.schedules.lock().unwrap();
+        schedules.get(&key).cloned()
+            .or_else(|| load_schedule(&state.data_dir, &account_name, server_number))
+    };
+    
+    if schedule_data.is_none() {
+        schedule_data = Some(ScheduleData {
+            construction_schedule: Some(DaySchedule {
+                appointments: HashMap::new(),
+                unassigned: Vec::new(),
+            }),
+            research_schedule: Some(DaySchedule {
+                appointments: HashMap::new(),
+                unassigned: Vec::new(),
+            }),
+            troops_schedule: Some(DaySchedule {
+                appointments: HashMap::new(),
+                unassigned: Vec::new(),
+            }),
+            entries: None,
+            scheduled_player_ids: None,
+        });
+    }
+    
+    let mut schedule_data = schedule_data.unwrap();
+    
+    // Get form config for time slot mapping
+    let form_config = {
+        let forms = state.forms.lock().unwrap();
+        let current_forms = state.current_forms.lock().unwrap();
+        get_current_form(&forms, &current_forms, &account_name, server_number)
+            .map(|f| f.config.clone())
+    };
+    
+    // Convert time to slot number
+    let time_slots: Vec<(u8, String)> = match (day_str.as_str(), form_config.as_ref()) {
+        ("construction", Some(config)) => {
+            config.construction_times.time_slots()
+        },
+        ("research", Some(config)) => {
+            config.research_times.time_slots()
+        },
+        ("troops", Some(config)) => {
+            config.troops_times.time_slots()
+        },
+        _ => {
+            (1..=49).map(|slot| (slot, slot_to_time(slot))).collect()
+        }
+    };
+    
+    let slot_num = time_slots.iter()
+        .find(|(_, time)| time == &req.time)
+        .map(|(slot, _)| *slot);
+    
+    if slot_num.is_none() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "Invalid time slot"
+        })));
+    }
+    
+    let slot = slot_num.unwrap();
+    
+    // Get or create the appropriate day schedule
+    let day_schedule = match day_str.as_str() {
+        "construction" => {
+            if schedule_data.construction_schedule.is_none() {
+                schedule_data.construction_schedule = Some(DaySchedule {
+                    appointments: HashMap::new(),
+                    unassigned: Vec::new(),
+                });
+            }
+            schedule_data.construction_schedule.as_mut().unwrap()
+        },
+        "research" => {
+            if schedule_data.research_schedule.is_none() {
+                schedule_data.research_schedule = Some(DaySchedule {
+                    appointments: HashMap::new(),
+                    unassigned: Vec::new(),
+                });
+            }
+            schedule_data.research_schedule.as_mut().unwrap()
+        },
+        "troops" => {
+            if schedule_data.troops_schedule.is_none() {
+                schedule_data.troops_schedule = Some(DaySchedule {
+                    appointments: HashMap::new(),
+                    unassigned: Vec::new(),
+                });
+            }
+            schedule_data.troops_schedule.as_mut().unwrap()
+        },
+        _ => return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "Invalid day"
+        }))),
+    };
+    
+    // Parse player name (format: "[alliance] name"); None or empty clears the slot
+    let new_player: Option<(String, String)> = req.player.as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|player_str| {
+            // Parse "[alliance] name" format
+            if let Some(start) = player_str.find('[') {
+                if let Some(end) = player_str.find(']') {
+                    let alliance = player_str[start+1..end].to_string();
+                    let name = player_str[end+1..].trim().to_string();
+                    (alliance, name)
+                } else {
+                    // No closing bracket, treat whole thing as name
+                    ("".to_string(), player_str.to_string())
+                }
+            } else {
+                // No bracket, treat whole thing as name
+                ("".to_string(), player_str.to_string())
+            }
+        });
+    
+    // Alliance sub-accounts can only touch slots held by their alliance and only assign their own members
+    if let Some(scope) = session_alliance_scope(&session) {
+        let holder_allowed = day_schedule.appointments.get(&slot)
+            .map(|appt| appt.alliance.eq_ignore_ascii_case(&scope))
+            .unwrap_or(true);
+        let new_player_allowed = new_player.as_ref()
+            .map(|(alliance, _)| alliance.eq_ignore_ascii_case(&scope))
+            .unwrap_or(true);
+        if !holder_allowed || !new_player_allowed {
+            return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+                "success": false,
+                "error": format!("This login can only edit slots for alliance [{}]", scope)
+            })));
+        }
+    }
+    
+    let change = match &new_player {
+        Some((alliance, name)) => format!("{} {}: {}", day_display_name(&day_str), req.time, format_player_name(alliance, name)),
+        None => format!("{} {} was cleared", day_display_name(&day_str), req.time),
+    };
+    if let Some((alliance, name)) = new_player {
+        // A note is about the player, so it only stays when the slot keeps the same one
+        let note = day_schedule.appointments.get(&slot)
+            .filter(|appt| appt.alliance == alliance && appt.name == name)
+            .and_then(|appt| appt.note.clone());
+        let appointment = ScheduledAppointment {
+            player_id: format!("MANUAL-{}-{}", alliance, name),
+            name,
+            alliance,
+            slot,
+            priority_score: 0,
+            note,
+            manual: true,
+        };
+        
+        day_schedule.appointments.insert(slot, appointment);
+    } else {
+        day_schedule.appointments.remove(&slot);
+    }
+    
+    // Recompute scheduled_player_ids after manual edit (append logic depends on this)
+    let scheduled_ids: Vec<String> = {
+        let mut ids = HashSet::new();
+        for appt in schedule_data.construction_schedule.as_ref().iter().flat_map(|s| s.appointments.values()) {
+            ids.insert(appt.player_id.clone());
+        }
+        for appt in schedule_data.research_schedule.as_ref().iter().flat_map(|s| s.appointments.values()) {
+            ids.insert(appt.player_id.clone());
+        }
+        for appt in schedule_data.troops_schedule.as_ref().iter().flat_map(|s| s.appointments.values()) {
+            ids.insert(appt.player_id.clone());
+        }
+        ids.into_iter().collect()
+    };
+    schedule_data.scheduled_player_ids = Some(scheduled_ids);
+    
+    // Update the schedule in state
+    {
+        let mut schedules = state.schedules.lock().unwrap();
+        schedules.insert(key.clone(), schedule_data.clone());
+    }
+    
+    // Save to disk
+    if let Err(e) = save_schedule(&state.data_dir, &account_name, server_number, &schedule_data) {
+        log::warn!("Failed to save schedule to disk: {}", e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "success": false,
+            "error": "Failed to save schedule"
+        })));
+    }
+    notify_slot_changed(&state, &account_name, server_number, change);
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "message": "Slot updated successfully"
+    })))
+}
+
+#[derive(Deserialize)]
+struct UpdateSlotNoteRequest {
+    time: String,
+    note: Option<String>, // None or empty removes the note
+}
+
+const MAX_SLOT_NOTE_LENGTH: usize = 500;
+
+// Set or remove the admin note of a filled slot ("confirmed via DM"). Notes are never shown publicly.
+async fn update_slot_note(
+    path: web::Path<(String, u32, String)>,
+    req: web::Json<UpdateSlotNoteRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number, day_str) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let note = req.note.as_deref().map(str::trim).filter(|n| !n.is_empty()).map(str::to_string);
+    if note.as_ref().is_some_and(|n| n.chars().count() > MAX_SLOT_NOTE_LENGTH) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": format!("Notes are limited to {} characters", MAX_SLOT_NOTE_LENGTH)
+        })));
+    }
+    
+    let key = schedule_key(&account_name, server_number);
+    let schedule_data = {
+        let schedules = state.schedules.lock().unwrap();
+        schedules.get(&key).cloned()
+            .or_else(|| load_schedule(&state.data_dir, &account_name, server_number))
+    };
+    let Some(mut schedule_data) = schedule_data else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "No schedule has been generated yet"
+        })));
+    };
+    let (_, config) = load_form_entries(&state, &account_name, server_number);
+    let slot = day_time_slots(config.as_ref(), &day_str).into_iter()
+        .find(|(_, time)| *time == req.time)
+        .map(|(slot, _)| slot);
+    let day_schedule = match day_str.as_str() {
+        "construction" => schedule_data.construction_schedule.as_mut(),
+        "research" => schedule_data.research_schedule.as_mut(),
+        "troops" => schedule_data.troops_schedule.as_mut(),
+        _ => return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "error": "Invalid day"}))),
+    };
+    let Some(appointment) = slot.and_then(|slot| day_schedule?.appointments.get_mut(&slot)) else {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "Only a filled slot can have a note"
+        })));
+    };
+    
+    // Alliance sub-accounts can only note their own alliance's slots
+    if let Some(scope) = session_alliance_scope(&session) {
+        if !appointment.alliance.eq_ignore_ascii_case(&scope) {
+            return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+                "success": false,
+                "error": format!("This login can only edit slots for alliance [{}]", scope)
+            })));
+        }
+    }
+    appointment.note = note.clone();
+    
+    save_schedule(&state.data_dir, &account_name, server_number, &schedule_data)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save schedule: {}", e)))?;
+    state.schedules.lock().unwrap().insert(key, schedule_data);
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "note": note
+    })))
+}
+
+#[derive(Deserialize)]
+struct MoveAssignmentRequest {
+    from_day: String,
+    from_time: String,
+    to_day: String,
+    to_time: String,
+    #[serde(default)]
+    force: bool, // Place the player even when their submission doesn't list the new time
+}
+
+// The day schedule of a ScheduleData, None for an unknown day
+fn day_schedule_slot<'a>(schedule_data: &'a mut ScheduleData, day_str: &str) -> Option<&'a mut Option<DaySchedule>> {
+    match day_str {
+        "construction" => Some(&mut schedule_data.construction_schedule),
+        "research" => Some(&mut schedule_data.research_schedule),
+        "troops" => Some(&mut schedule_data.troops_schedule),
+        _ => None,
+    }
+}
+
+// Move the player in a slot to a slot on another day, for players who mixed up their days.
+// The new slot must be empty, the player can't already have a slot that day and, unless
+// forced, their submission must list the new time for that day.
+async fn move_assignment(
+    path: web::Path<(String, u32)>,
+    req: web::Json<MoveAssignmentRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let bad_request = |error: String| Ok(HttpResponse::BadRequest().json(serde_json::json!({
+        "success": false,
+        "error": error
+    })));
+    if req.from_day == req.to_day {
+        return bad_request("Pick another day; within a day, edit the slots directly".to_string());
+    }
+    
+    let key = schedule_key(&account_name, server_number);
+    let schedule_data = {
+        let schedules = state.schedules.lock().unwrap();
+        schedules.get(&key).cloned()
+            .or_else(|| load_schedule(&state.data_dir, &account_name, server_number))
+    };
+    let Some(mut schedule_data) = schedule_data else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "No schedule has been generated yet"
+        })));
+    };
+    let (form_entries, config) = load_form_entries(&state, &account_name, server_number);
+    let slot_at = |day: &str, time: &str| day_time_slots(config.as_ref(), day).into_iter()
+        .find(|(_, t)| t == time)
+        .map(|(slot, _)| slot);
+    let (Some(from_slot), Some(to_slot)) = (slot_at(&req.from_day, &req.from_time), slot_at(&req.to_day, &req.to_time)) else {
+        return bad_request("Invalid time slot".to_string());
+    };
+    
+    let Some(from_schedule) = day_schedule_slot(&mut schedule_data, &req.from_day) else {
+        return bad_request("Invalid day".to_string());
+    };
+    let Some(appointment) = from_schedule.as_ref().and_then(|s| s.appointments.get(&from_slot)).cloned() else {
+        return bad_request(format!("{} on {} is empty", req.from_time, req.from_day));
+    };
+    if let Some(scope) = session_alliance_scope(&session) {
+        if !appointment.alliance.eq_ignore_ascii_case(&scope) {
+            return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+                "success": false,
+                "error": format!("This login can only edit slots for alliance [{}]", scope)
+            })));
+        }
+    }
+    
+    let entries = schedule_data.entries.clone().unwrap_or(form_entries);
+    let entry = entries.iter().find(|e| e.player_id == appointment.player_id);
+    let player = format_player_name(&appointment.alliance, &appointment.name);
+    let Some(to_schedule) = day_schedule_slot(&mut schedule_data, &req.to_day) else {
+        return bad_request("Invalid day".to_string());
+    };
+    let to_schedule = to_schedule.get_or_insert_with(|| DaySchedule {
+        appointments: HashMap::new(),
+        unassigned: Vec::new(),
+    });
+    if let Some(taken) = to_schedule.appointments.get(&to_slot) {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "success": false,
+            "error": format!("{} on {} is taken by {}", req.to_time, req.to_day, format_player_name(&taken.alliance, &taken.name))
+        })));
+    }
+    if to_schedule.appointments.values().any(|a| a.player_id == appointment.player_id) {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "success": false,
+            "error": format!("{} already has a slot on {}", player, req.to_day)
+        })));
+    }
+    if !req.force {
+        match entry {
+            None => return bad_request(format!("No submission found for {}, so their availability can't be checked", player)),
+            Some(e) if !lists_slot(e, &req.to_day, to_slot) => {
+                return bad_request(format!("{} did not list {} for {}", player, req.to_time, req.to_day));
+            }
+            Some(_) => {}
+        }
+    }
+    
+    to_schedule.unassigned.retain(|id| *id != appointment.player_id);
+    to_schedule.appointments.insert(to_slot, ScheduledAppointment {
+        slot: to_slot,
+        priority_score: entry.map(|e| day_score(e, &req.to_day)).unwrap_or(appointment.priority_score),
+        manual: true,
+        ..appointment
+    });
+    if let Some(Some(from_schedule)) = day_schedule_slot(&mut schedule_data, &req.from_day) {
+        from_schedule.appointments.remove(&from_slot);
+    }
+    
+    save_schedule(&state.data_dir, &account_name, server_number, &schedule_data)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save schedule: {}", e)))?;
+    state.schedules.lock().unwrap().insert(key, schedule_data);
+    notify_slot_changed(&state, &account_name, server_number, format!(
+        "{} moved from {} {} to {} {}",
+        player, day_display_name(&req.from_day), req.from_time, day_display_name(&req.to_day), req.to_time
+    ));
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "player": player,
+        "from_day": req.from_day,
+        "to_day": req.to_day,
+        "time": req.to_time
+    })))
+}
+
+#[derive(Deserialize)]
+struct FillSlotRequest {
+    time: String,
+    player_id: String,
+}
+
+#[derive(Deserialize)]
+struct AutoFillRequest {
+    day: Option<String>, // All three days without one
+}
+
+#[derive(Deserialize)]
+struct WithdrawPlayerRequest {
+    player_id: String,
+}
+
+// Withdraw a player from the current week: deletes their submission to the current form, takes
+// them off its predetermined slots and guaranteed days and vacates their slots. Each vacated slot
+// is filled again right away (see refill_slot); the rest of the schedule stays as it is.
+async fn withdraw_player(
+    path: web::Path<(String, u32)>,
+    req: web::Json<WithdrawPlayerRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let player_id = req.player_id.trim().to_string();
+    if player_id.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "Player ID required"
+        })));
+    }
+    
+    let key = schedule_key(&account_name, server_number);
+    let mut schedule_data = {
+        let schedules = state.schedules.lock().unwrap();
+        schedules.get(&key).cloned()
+            .or_else(|| load_schedule(&state.data_dir, &account_name, server_number))
+    };
+    let (form_entries, config) = load_form_entries(&state, &account_name, server_number);
+    let mut entries = schedule_data.as_ref().and_then(|s| s.entries.clone()).unwrap_or(form_entries.clone());
+    let appointments = || schedule_data.iter().flat_map(|s| {
+        [&s.construction_schedule, &s.research_schedule, &s.troops_schedule].into_iter().flatten().flat_map(|d| d.appointments.values())
+    });
+    let alliance = form_entries.iter().chain(entries.iter())
+        .find(|e| e.player_id == player_id)
+        .map(|e| e.alliance.clone())
+        .or_else(|| appointments().find(|a| a.player_id == player_id).map(|a| a.alliance.clone()));
+    let Some(alliance) = alliance else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": format!("Player ID {} has no submission or slot this week", player_id)
+        })));
+    };
+    if let Some(scope) = session_alliance_scope(&session) {
+        if !alliance.eq_ignore_ascii_case(&scope) {
+            return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+                "success": false,
+                "error": format!("This login can only withdraw players of alliance [{}]", scope)
+            })));
+        }
+    }
+    
+    // Submission, predetermined slots and guaranteed days of the current form
+    let (csv_path, _) = current_submissions_csv(&state, &account_name, server_number);
+    let removed_submissions = if Path::new(&csv_path).exists() {
+        match remove_player_rows(Path::new(&csv_path), &player_id) {
+            Ok(removed) => removed,
+            Err(e) => {
+                log::warn!("Failed to withdraw player from {}: {}", csv_path, e);
+                return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                    "success": false,
+                    "error": "Failed to update the submissions"
+                })));
+            }
+        }
+    } else {
+        0
+    };
+    let current_form = {
+        let forms = state.forms.lock().unwrap();
+        let current_forms = state.current_forms.lock().unwrap();
+        get_current_form(&forms, &current_forms, &account_name, server_number)
+    };
+    if let Some(code) = current_form.map(|f| f.code) {
+        if let Err(e) = metadata::remove_player_metadata(&state.data_dir, &code, &player_id) {
+            log::warn!("Failed to remove submission metadata of form {}: {}", code, e);
+        }
+        let mut forms = state.forms.lock().unwrap();
+        if let Some(form_data) = forms.get_mut(&code) {
+            let before = form_data.config.predetermined_slots.len();
+            form_data.config.predetermined_slots.retain(|p| p.player_id.as_deref() != Some(player_id.as_str()));
+            if form_data.config.guaranteed_players.remove(&player_id) > 0 || before != form_data.config.predetermined_slots.len() {
+                save_form(&state.data_dir, form_data)?;
+            }
+        }
+    }
+    if removed_submissions > 0 {
+        let stats_path = format!("{}/statistics/{}/{}.json", state.data_dir, account_name, server_number);
+        if Path::new(&stats_path).exists() {
+            if let Err(e) = std::fs::remove_file(&stats_path) {
+                log::warn!("Failed to remove cached statistics {}: {}", stats_path, e);
+            }
+        }
+    }
+    
+    // Vacate and refill their slots
+    let mut refills = Vec::new();
+    if let Some(schedule_data) = schedule_data.as_mut() {
+        entries.retain(|e| e.player_id != player_id);
+        for day_str in ["construction", "research", "troops"] {
+            let time_slots = day_time_slots(config.as_ref(), day_str);
+            let locked = refill_locked_slots(config.as_ref(), day_str, &time_slots);
+            let Some(Some(schedule)) = day_schedule_slot(schedule_data, day_str) else {
+                continue;
+            };
+            let mut vacated: Vec<u8> = schedule.appointments.values()
+                .filter(|a| a.player_id == player_id)
+                .map(|a| a.slot)
+                .collect();
+            vacated.sort_unstable();
+            schedule.appointments.retain(|_, a| a.player_id != player_id);
+            schedule.unassigned.retain(|id| *id != player_id);
+            
+            let slot_time = |slot: u8| time_slots.iter().find(|(s, _)| *s == slot).map(|(_, t)| t.clone()).unwrap_or_else(|| slot_to_time(slot));
+            for slot in vacated {
+                let mut refill = match refill_slot(schedule, &entries, day_str, slot, &locked) {
+                    Some(Refill::Waitlist(player)) => serde_json::json!({"player": player}),
+                    Some(Refill::Shift { player, from_slot, waitlisted }) => serde_json::json!({
+                        "player": player,
+                        "moved_from": slot_time(from_slot),
+                        "waitlisted": waitlisted
+                    }),
+                    None => serde_json::json!({"player": null}),
+                };
+                refill["day"] = day_str.into();
+                refill["time"] = slot_time(slot).into();
+                refills.push(refill);
+            }
+        }
+        
+        if let Some(stored) = schedule_data.entries.as_mut() {
+            stored.retain(|e| e.player_id != player_id);
+        }
+        let placed = derive_scheduled_player_ids(schedule_data);
+        if let Some(ids) = schedule_data.scheduled_player_ids.as_mut() {
+            ids.retain(|id| *id != player_id);
+            for id in placed {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+        save_schedule(&state.data_dir, &account_name, server_number, schedule_data)
+            .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save schedule: {}", e)))?;
+        state.schedules.lock().unwrap().insert(key, schedule_data.clone());
+    }
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "player_id": player_id,
+        "removed_submissions": removed_submissions,
+        "refills": refills
+    })))
+}
+
+// Whether submissions to the account's forms are recorded with a hashed IP address, user agent
+// and form version
+async fn get_submission_metadata(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let enabled = state.accounts.lock().unwrap().get(&account_name).is_some_and(|a| a.submission_metadata);
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "enabled": enabled
+    })))
+}
+
+// Turn recording of submission metadata on or off for all forms of the account. Turning it off
+// keeps what was recorded until the form is archived.
+async fn update_submission_metadata(
+    path: web::Path<(String, u32)>,
+    req: web::Json<SubmissionMetadataRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let mut accounts = state.accounts.lock().unwrap();
+    let account = match accounts.get_mut(&account_name) {
+        Some(account) => account,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Account not found"
+            })));
+        }
+    };
+    account.submission_metadata = req.enabled;
+    if req.enabled && account.metadata_salt.is_empty() {
+        account.metadata_salt = metadata::generate_salt();
+    }
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "enabled": req.enabled
+    })))
+}
+
+// Whether returning players can fill the form from their previous answers (owner only)
+async fn get_prefill_answers(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let enabled = state.accounts.lock().unwrap().get(&account_name).is_some_and(|a| a.prefill_answers);
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "enabled": enabled
+    })))
+}
+
+// Turn prefilling from previous submissions on or off for all forms of the account
+async fn update_prefill_answers(
+    path: web::Path<(String, u32)>,
+    req: web::Json<PrefillAnswersRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let mut accounts = state.accounts.lock().unwrap();
+    let account = match accounts.get_mut(&account_name) {
+        Some(account) => account,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Account not found"
+            })));
+        }
+    };
+    account.prefill_answers = req.enabled;
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "enabled": req.enabled
+    })))
+}
+
+// Flagged near-duplicate pairs of a form's submissions, with their review decisions
+fn form_duplicates(data_dir: &str, form_code: &str) -> Vec<DuplicateFlag> {
+    let csv_path = Path::new(data_dir).join("current_forms").join(format!("{}_submissions.csv", form_code));
+    if !csv_path.exists() {
+        return Vec::new();
+    }
+    duplicates::find_duplicates(&csv_path, &duplicates::load_reviews(data_dir, form_code)).unwrap_or_else(|e| {
+        log::warn!("Failed to check form {} for duplicate submissions: {}", form_code, e);
+        Vec::new()
+    })
+}
+
+// Pairs of new submissions to the current form that look like one player submitting twice
+// (see duplicates::find_duplicates), and the player IDs kept out of scheduling because of them
+async fn get_duplicate_submissions(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let code = {
+        let forms = state.forms.lock().unwrap();
+        let current_forms = state.current_forms.lock().unwrap();
+        get_current_form(&forms, &current_forms, &account_name, server_number).map(|f| f.code.clone())
+    };
+    let flags = code.map(|code| form_duplicates(&state.data_dir, &code)).unwrap_or_default();
+    let mut held: Vec<String> = duplicates::held_player_ids(&flags).into_iter().collect();
+    held.sort();
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "flags": flags,
+        "held": held
+    })))
+}
+
+// Review a flagged pair: keep both submissions, keep only one of the two player IDs, or (with
+// null) flag the pair again. The decision applies from the next schedule generation.
+async fn review_duplicate_submission(
+    path: web::Path<(String, u32, String)>,
+    req: web::Json<DuplicateReviewRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number, flag_id) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let code = {
+        let forms = state.forms.lock().unwrap();
+        let current_forms = state.current_forms.lock().unwrap();
+        get_current_form(&forms, &current_forms, &account_name, server_number).map(|f| f.code.clone())
+    };
+    let code = match code {
+        Some(code) => code,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "No current form found"
+            })));
+        }
+    };
+    let flag = match form_duplicates(&state.data_dir, &code).into_iter().find(|f| f.id == flag_id) {
+        Some(flag) => flag,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "These submissions are no longer flagged"
+            })));
+        }
+    };
+    let keep = req.keep.as_deref().map(str::trim);
+    if let Some(kept) = keep {
+        if kept != duplicates::KEEP_BOTH && kept != flag.first.player_id && kept != flag.second.player_id {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": "Keep either both submissions or one of the two player IDs"
+            })));
+        }
+    }
+    
+    let mut reviews = duplicates::load_reviews(&state.data_dir, &code);
+    match keep {
+        Some(kept) => reviews.insert(flag_id, kept.to_string()),
+        None => reviews.remove(&flag_id),
+    };
+    duplicates::save_reviews(&state.data_dir, &code, &reviews).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save the review: {}", e))
+    })?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "decision": keep
+    })))
+}
+
+// Get form submissions endpoint
+async fn get_form_submissions(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    
+    // Check authentication
+    if let (Some(session_account), Some(session_server)) = (
+        session.get::<String>("account_name")?,
+        session.get::<u32>("server_number")?
+    ) {
+        if session_account != account_name || session_server != server_number {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not authorized"
+            })));
+        }
+    } else {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authenticated"
+        })));
+    }
+    
+    // Get current form
+    let current_form = {
+        let forms = state.forms.lock().unwrap();
+        let current_forms = state.current_forms.lock().unwrap();
+        get_current_form(&forms, &current_forms, &account_name, server_number)
+    };
+    
+    if current_form.is_none() {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "No current form found"
+        })));
+    }
+    
+    let current_form = current_form.unwrap();
+    let form_csv_path = format!("{}/current_forms/{}_submissions.csv", state.data_dir, current_form.code);
+    
+    if !Path::new(&form_csv_path).exists() {
+        return Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "submissions": []
+        })));
+    }
+    
+    // Alliance sub-accounts only see their own alliance's submissions
+    let alliance_scope = session_alliance_scope(&session);
+    let mut submissions = read_submission_rows(&form_csv_path, alliance_scope.as_deref())?;
+    
+    // Metadata is for the owner only, under "metadata" on the submissions that have it
+    if alliance_scope.is_none() {
+        let recorded = metadata::load_metadata(&state.data_dir, &current_form.code);
+        if !recorded.is_empty() {
+            for submission in submissions.iter_mut().filter_map(|s| s.as_object_mut()) {
+                let player_id = submission.iter()
+                    .find(|(header, _)| header.contains("player ID"))
+                    .and_then(|(_, value)| value.as_str())
+                    .map(|value| value.trim().to_string())
+                    .unwrap_or_default();
+                // The timestamp header follows the sheet's locale, so match on the value instead
+                let found = submission.values()
+                    .filter_map(|value| value.as_str())
+                    .find_map(|value| recorded.get(&(value.trim().to_string(), player_id.clone())))
+                    .cloned();
+                if let Some(found) = found {
+                    submission.insert("metadata".to_string(), serde_json::json!(found));
+                }
+            }
+        }
+    }
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "submissions": submissions
+    })))
+}
+
+// Reads a submissions CSV as header -> value objects, optionally keeping only one alliance's rows
+fn read_submission_rows(csv_path: &str, alliance_scope: Option<&str>) -> Result<Vec<serde_json::Value>> {
+    let mut reader = csv::Reader::from_path(csv_path)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to read CSV: {}", e)))?;
+    
+    let headers = reader.headers()
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to read CSV headers: {}", e)))?
+        .clone();
+    
+    let alliance_col = headers.iter().position(|h| h.contains("alliance")).unwrap_or(1);
+    let custom_alliance_col = headers.iter().position(|h| h.contains("Non of the above") && h.contains("type it here")).unwrap_or(2);
+    
+    let mut submissions = Vec::new();
+    for result in reader.records() {
+        let record = result.map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to parse CSV record: {}", e)))?;
+        
+        // Skip header rows (check if first field is a timestamp pattern DD/MM/YYYY)
+        let first_field = record.get(0).unwrap_or("");
+        if !first_field.contains('/') || first_field.len() < 8 {
+            continue; // Skip header rows
+        }
+        
+        if let Some(scope) = alliance_scope {
+            let mut alliance = record.get(alliance_col).unwrap_or("").trim();
+            if alliance.to_lowercase().contains("non of the above") {
+                alliance = record.get(custom_alliance_col).unwrap_or("").trim();
+            }
+            if !alliance.eq_ignore_ascii_case(scope) {
+                continue;
+            }
+        }
+        
+        let mut submission = serde_json::Map::new();
+        for (i, field) in record.iter().enumerate() {
+            let header = headers.get(i)
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| format!("field_{}", i));
+            submission.insert(header, serde_json::Value::String(field.to_string()));
+        }
+        submissions.push(serde_json::Value::Object(submission));
+    }
+    
+    Ok(submissions)
+}
+
+// Whether the session is logged in to the given account/server
+fn session_is_for(session: &Session, account_name: &str, server_number: u32) -> bool {
+    session.get::<String>("account_name").ok().flatten().as_deref() == Some(account_name)
+        && session.get::<u32>("server_number").ok().flatten() == Some(server_number)
+}
+
+// Archived forms of one account/server with their archive IDs, newest first
+fn archived_forms_for(data_dir: &str, account_name: &str, server_number: u32) -> Vec<(String, FormData)> {
+    let prefix = format!("{}_{}_", account_name, server_number);
+    let mut forms: Vec<(String, FormData)> = archived_forms(data_dir).into_iter()
+        .filter(|(_, f)| f.account_name == account_name && f.server_number == server_number)
+        .filter_map(|(path, f)| {
+            let id = path.file_stem()?.to_str()?.strip_prefix(&prefix)?.to_string();
+            parse_archive_id(&id).map(|_| (id, f))
+        })
+        .collect();
+    forms.sort_by(|a, b| b.0.cmp(&a.0));
+    forms
+}
+
+// List archived forms and schedules of an account/server
+async fn list_archive(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let schedule_ids = archived_schedule_ids(&state.data_dir, &account_name, server_number);
+    let forms: Vec<ArchivedFormInfo> = archived_forms_for(&state.data_dir, &account_name, server_number).into_iter()
+        .map(|(id, form_data)| {
+            let csv_path = format!("{}/old_forms/{}_{}_{}_submissions.csv", state.data_dir, account_name, server_number, id);
+            ArchivedFormInfo {
+                archived_at: parse_archive_id(&id).map(|t| t.format("%Y-%m-%dT%H:%M:%S").to_string()).unwrap_or_default(),
+                has_schedule: schedule_ids.iter().any(|(schedule_id, _)| *schedule_id == id),
+                submissions: count_submissions(Path::new(&csv_path)),
+                code: form_data.code,
+                name: form_data.name,
+                created_at: form_data.created_at,
+                id,
+            }
+        })
+        .collect();
+    let schedules: Vec<ArchivedScheduleInfo> = schedule_ids.into_iter()
+        .map(|(id, archived_at)| ArchivedScheduleInfo {
+            id,
+            archived_at: archived_at.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        })
+        .collect();
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "forms": forms,
+        "schedules": schedules
+    })))
+}
+
+// Submissions of an archived form
+async fn get_archived_submissions(
+    path: web::Path<(String, u32, String)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number, id) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    if parse_archive_id(&id).is_none() {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Archived form not found"
+        })));
+    }
+    
+    let csv_path = format!("{}/old_forms/{}_{}_{}_submissions.csv", state.data_dir, account_name, server_number, id);
+    if !Path::new(&csv_path).exists() {
+        return Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "submissions": []
+        })));
+    }
+    let submissions = read_submission_rows(&csv_path, session_alliance_scope(&session).as_deref())?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "submissions": submissions
+    })))
+}
+
+// One day of an archived schedule, in the same format as the live schedule API
+async fn get_archived_schedule(
+    path: web::Path<(String, u32, String, String)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number, id, day_str) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let schedule_path = archived_schedule_path(&state.data_dir, &account_name, server_number, &id);
+    let schedule_data = parse_archive_id(&id)
+        .and_then(|_| std::fs::read_to_string(&schedule_path).ok())
+        .and_then(|content| serde_json::from_str::<ScheduleData>(&content).ok());
+    let schedule_data = match schedule_data {
+        Some(schedule_data) => schedule_data,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Archived schedule not found"
+            })));
+        }
+    };
+    
+    let (day_name, schedule) = match day_str.as_str() {
+        "construction" => ("Construction Day", schedule_data.construction_schedule),
+        "research" => ("Research Day", schedule_data.research_schedule),
+        "troops" => ("Troops Training Day", schedule_data.troops_schedule),
+        _ => return Ok(HttpResponse::BadRequest().json(serde_json::json!({"error": "Invalid day"}))),
+    };
+    
+    // Time slots come from the form archived together with the schedule
+    let form_config = archived_forms_for(&state.data_dir, &account_name, server_number).into_iter()
+        .find(|(form_id, _)| *form_id == id)
+        .map(|(_, f)| f.config);
+    let appointments = day_time_slots(form_config.as_ref(), &day_str).into_iter()
+        .map(|(slot, time)| match schedule.as_ref().and_then(|s| s.appointments.get(&slot)) {
+            Some(appt) => ScheduleSlot {
+                time,
+                player: Some(format_player_name(&appt.alliance, &appt.name)),
+                is_empty: false,
+                player_id: None,
+                note: appt.note.clone(),
+                substitute: None,
+                manual: false,
+            },
+            None => ScheduleSlot {
+                time,
+                player: None,
+                is_empty: true,
+                player_id: None,
+                note: None,
+                substitute: None,
+                manual: false,
+            },
+        })
+        .collect();
+    
+    Ok(HttpResponse::Ok().json(ScheduleResponse {
+        day_name: day_name.to_string(),
+        appointments,
+    }))
+}
+
+// Make an archived schedule the current one. The schedule it replaces is archived first.
+async fn restore_archived_schedule(
+    path: web::Path<(String, u32, String)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number, id) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let schedule_path = archived_schedule_path(&state.data_dir, &account_name, server_number, &id);
+    let schedule_data = parse_archive_id(&id)
+        .and_then(|_| std::fs::read_to_string(&schedule_path).ok())
+        .and_then(|content| serde_json::from_str::<ScheduleData>(&content).ok());
+    let schedule_data = match schedule_data {
+        Some(schedule_data) => schedule_data,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Archived schedule not found"
+            })));
+        }
+    };
+    
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
+    archive_schedule(&state.data_dir, &account_name, server_number, &timestamp).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to archive current schedule: {}", e))
+    })?;
+    save_schedule(&state.data_dir, &account_name, server_number, &schedule_data).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save schedule: {}", e))
+    })?;
+    state.schedules.lock().unwrap().insert(schedule_key(&account_name, server_number), schedule_data);
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "restored": id
+    })))
+}
+
+// One week of a season: the form, its parsed submissions and the schedule generated from them
+struct SeasonWeek {
+    form: FormData,
+    archive_id: Option<String>,
+    entries: Vec<AppointmentEntry>,
+    schedule: Option<ScheduleData>,
+}
+
+// Forms of a season as (archive ID, form), oldest first; the current form comes last with no archive ID
+fn season_forms(state: &AppState, account_name: &str, server_number: u32, season: &Season) -> Vec<(Option<String>, FormData)> {
+    let mut forms: Vec<(Option<String>, FormData)> = archived_forms_for(&state.data_dir, account_name, server_number).into_iter()
+        .rev()
+        .filter(|(_, f)| season.contains(&f.created_at))
+        .map(|(id, f)| (Some(id), f))
+        .collect();
+    let current_form = {
+        let forms = state.forms.lock().unwrap();
+        let current_forms = state.current_forms.lock().unwrap();
+        get_current_form(&forms, &current_forms, account_name, server_number)
+    };
+    if let Some(form) = current_form.filter(|f| season.contains(&f.created_at)) {
+        forms.push((None, form));
+    }
+    forms
+}
+
+// Loads the submissions and schedule of every week of a season
+fn season_weeks(state: &AppState, account_name: &str, server_number: u32, season: &Season) -> Vec<SeasonWeek> {
+    season_forms(state, account_name, server_number, season).into_iter()
+        .map(|(archive_id, form)| {
+            let (csv_path, schedule) = match &archive_id {
+                Some(id) => (
+                    format!("{}/old_forms/{}_{}_{}_submissions.csv", state.data_dir, account_name, server_number, id),
+                    std::fs::read_to_string(archived_schedule_path(&state.data_dir, account_name, server_number, id))
+                        .ok()
+                        .and_then(|content| serde_json::from_str::<ScheduleData>(&content).ok()),
+                ),
+                None => (
+                    format!("{}/current_forms/{}_submissions.csv", state.data_dir, form.code),
+                    // The saved schedule still belongs to the previous week until it is regenerated
+                    load_schedule(&state.data_dir, account_name, server_number)
+                        .filter(|_| schedule_saved_after(&state.data_dir, account_name, server_number, &form.created_at)),
+                ),
+            };
+            let entries = if Path::new(&csv_path).exists() {
+                load_csv_entries(state, &csv_path, Some(&form.config))
+            } else {
+                Vec::new()
+            };
+            SeasonWeek { form, archive_id, entries, schedule }
+        })
+        .collect()
+}
+
+// Whether the saved schedule of an account/server was written after `created_at` (RFC 3339)
+fn schedule_saved_after(data_dir: &str, account_name: &str, server_number: u32, created_at: &str) -> bool {
+    let modified = std::fs::metadata(format!("{}/schedules/{}/{}.json", data_dir, account_name, server_number))
+        .and_then(|m| m.modified())
+        .ok()
+        .map(chrono::DateTime::<chrono::Utc>::from);
+    match (modified, chrono::DateTime::parse_from_rfc3339(created_at)) {
+        (Some(modified), Ok(created_at)) => modified >= created_at,
+        _ => false,
+    }
+}
+
+// Per-player submissions and placements across the weeks of a season, keyed by player ID
+fn season_player_history(weeks: &[SeasonWeek]) -> HashMap<String, PlayerSeasonHistory> {
+    let mut history: HashMap<String, PlayerSeasonHistory> = HashMap::new();
+    for week in weeks {
+        for entry in &week.entries {
+            let player = history.entry(entry.player_id.clone()).or_default();
+            player.player_id = entry.player_id.clone();
+            player.name = entry.name.clone();
+            player.alliance = entry.alliance.clone();
+            player.weeks_submitted += 1;
+        }
+        
+        let schedule = match &week.schedule {
+            Some(schedule) => schedule,
+            None => continue,
+        };
+        let mut scheduled_this_week = HashSet::new();
+        let days = [
+            ("construction", &schedule.construction_schedule),
+            ("research", &schedule.research_schedule),
+            ("troops", &schedule.troops_schedule),
+        ];
+        for (day_str, day_schedule) in days {
+            for appt in day_schedule.iter().flat_map(|s| s.appointments.values()) {
+                // Players only placed through predetermined slots have no submission row
+                let player = history.entry(appt.player_id.clone()).or_insert_with(|| PlayerSeasonHistory {
+                    player_id: appt.player_id.clone(),
+                    name: appt.name.clone(),
+                    alliance: appt.alliance.clone(),
+                    ..Default::default()
+                });
+                match day_str {
+                    "construction" => player.construction_scheduled += 1,
+                    "research" => player.research_scheduled += 1,
+                    _ => player.troops_scheduled += 1,
+                }
+                if scheduled_this_week.insert(appt.player_id.clone()) {
+                    player.weeks_scheduled += 1;
+                }
+            }
+        }
+    }
+    history
+}
+
+// Looks up a season of an account/server
+fn find_season(state: &AppState, account_name: &str, server_number: u32, season_id: &str) -> Option<Season> {
+    state.accounts.lock().unwrap()
+        .get(account_name)?
+        .seasons.iter()
+        .find(|s| s.server_number == server_number && s.id == season_id)
+        .cloned()
+}
+
+fn season_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "success": false,
+        "error": "Season not found"
+    }))
+}
+
+// List the seasons of an account/server, newest first
+async fn list_seasons(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let seasons: Vec<Season> = state.accounts.lock().unwrap()
+        .get(&account_name)
+        .map(|a| a.seasons.iter().filter(|s| s.server_number == server_number).cloned().collect())
+        .unwrap_or_default();
+    let mut seasons: Vec<SeasonInfo> = seasons.into_iter()
+        .map(|season| SeasonInfo {
+            weeks: season_forms(&state, &account_name, server_number, &season).len(),
+            active: season.ended_at.is_none(),
+            id: season.id,
+            name: season.name,
+            started_at: season.started_at,
+            ended_at: season.ended_at,
+        })
+        .collect();
+    seasons.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "seasons": seasons
+    })))
+}
+
+// Start a new season on a server; the running season ends where the new one starts
+async fn start_season(
+    path: web::Path<(String, u32)>,
+    req: web::Json<StartSeasonRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let name = req.name.trim();
+    if name.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "Season name is required"
+        })));
+    }
+    
+    // Starting just before the current form was created makes it the season's first week
+    let current_form = {
+        let forms = state.forms.lock().unwrap();
+        let current_forms = state.current_forms.lock().unwrap();
+        get_current_form(&forms, &current_forms, &account_name, server_number)
+    };
+    let started_at = match current_form.filter(|_| req.include_current_form) {
+        Some(form) => form.created_at,
+        None => chrono::Utc::now().to_rfc3339(),
+    };
+    
+    let mut accounts = state.accounts.lock().unwrap();
+    let account = match accounts.get_mut(&account_name) {
+        Some(account) => account,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Account not found"
+            })));
+        }
+    };
+    let new_start = chrono::DateTime::parse_from_rfc3339(&started_at).ok();
+    let overlaps = account.seasons.iter()
+        .filter(|s| s.server_number == server_number)
+        .any(|s| chrono::DateTime::parse_from_rfc3339(&s.started_at).ok() >= new_start);
+    if overlaps {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "Another season already starts with the current form"
+        })));
+    }
+    
+    for season in account.seasons.iter_mut().filter(|s| s.server_number == server_number && s.ended_at.is_none()) {
+        season.ended_at = Some(started_at.clone());
+    }
+    let season = Season {
+        id: generate_form_code(),
+        name: name.to_string(),
+        server_number,
+        started_at,
+        ended_at: None,
+    };
+    account.seasons.push(season.clone());
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "season": season
+    })))
+}
+
+// Applies a change to one season of an account/server and saves the accounts
+fn modify_season(state: &AppState, account_name: &str, server_number: u32, season_id: &str, change: impl FnOnce(&mut Season) -> std::result::Result<(), String>) -> Result<HttpResponse> {
+    let mut accounts = state.accounts.lock().unwrap();
+    let season = accounts.get_mut(account_name)
+        .and_then(|a| a.seasons.iter_mut().find(|s| s.server_number == server_number && s.id == season_id));
+    let season = match season {
+        Some(season) => season,
+        None => return Ok(season_not_found()),
+    };
+    if let Err(error) = change(season) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": error
+        })));
+    }
+    let season = season.clone();
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "season": season
+    })))
+}
+
+// Rename a season
+async fn rename_season(
+    path: web::Path<(String, u32, String)>,
+    req: web::Json<RenameSeasonRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number, season_id) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let name = req.name.trim().to_string();
+    modify_season(&state, &account_name, server_number, &season_id, |season| {
+        if name.is_empty() {
+            return Err("Season name is required".to_string());
+        }
+        season.name = name;
+        Ok(())
+    })
+}
+
+// End a running season; forms created afterwards no longer belong to it
+async fn end_season(
+    path: web::Path<(String, u32, String)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number, season_id) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    modify_season(&state, &account_name, server_number, &season_id, |season| {
+        if season.ended_at.is_some() {
+            return Err("This season has already ended".to_string());
+        }
+        season.ended_at = Some(chrono::Utc::now().to_rfc3339());
+        Ok(())
+    })
+}
+
+// Delete a season; only the grouping is removed, its forms and schedules stay in the archive
+async fn delete_season(
+    path: web::Path<(String, u32, String)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number, season_id) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let mut accounts = state.accounts.lock().unwrap();
+    let seasons = match accounts.get_mut(&account_name) {
+        Some(account) => &mut account.seasons,
+        None => return Ok(season_not_found()),
+    };
+    let before = seasons.len();
+    seasons.retain(|s| !(s.server_number == server_number && s.id == season_id));
+    if seasons.len() == before {
+        return Ok(season_not_found());
+    }
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true
+    })))
+}
+
+// Weeks of a season and every player's submissions and placements across them
+async fn get_season_history(
+    path: web::Path<(String, u32, String)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number, season_id) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let season = match find_season(&state, &account_name, server_number, &season_id) {
+        Some(season) => season,
+        None => return Ok(season_not_found()),
+    };
+    
+    let season_weeks = season_weeks(&state, &account_name, server_number, &season);
+    let weeks: Vec<SeasonWeekInfo> = season_weeks.iter()
+        .map(|week| SeasonWeekInfo {
+            form_code: week.form.code.clone(),
+            form_name: week.form.name.clone(),
+            created_at: week.form.created_at.clone(),
+            archive_id: week.archive_id.clone(),
+            players: week.entries.len(),
+            scheduled: week.schedule.as_ref().map(|s| derive_scheduled_player_ids(s).len()).unwrap_or(0),
+        })
+        .collect();
+    
+    let alliance_scope = session_alliance_scope(&session);
+    let mut players: Vec<PlayerSeasonHistory> = season_player_history(&season_weeks).into_values()
+        .filter(|p| alliance_scope.as_deref().is_none_or(|scope| p.alliance.eq_ignore_ascii_case(scope)))
+        .collect();
+    players.sort_by(|a, b| b.weeks_submitted.cmp(&a.weeks_submitted).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "season": season,
+        "weeks": weeks,
+        "players": players
+    })))
+}
+
+// Status of a power level extraction job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerJobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+// Power level extraction of one upload, stored as job.json next to the uploaded files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerJob {
+    pub id: String,
+    pub created_at: String, // RFC 3339
+    pub files: Vec<String>, // Saved names, numbered in upload order
+    pub status: PowerJobStatus,
+    #[serde(default)]
+    pub finished_at: Option<String>, // RFC 3339
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub records: Option<usize>, // Players read, once done
+}
+
+// File types the extractor reads: screen recordings and screenshots
+const POWER_UPLOAD_EXTENSIONS: &[&str] = &["mp4", "mov", "mkv", "webm", "avi", "png", "jpg", "jpeg"];
+
+// Directory holding the power level jobs of an account/server, one subdirectory per job
+fn power_jobs_dir(data_dir: &str, account_name: &str, server_number: u32) -> PathBuf {
+    PathBuf::from(format!("{}/power/{}/{}", data_dir, account_name, server_number))
+}
+
+// Directory of an existing job. Job IDs are generated codes, so anything else can't name one.
+fn power_job_dir(data_dir: &str, account_name: &str, server_number: u32, job_id: &str) -> Option<PathBuf> {
+    if job_id.len() != 12 || !job_id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    let dir = power_jobs_dir(data_dir, account_name, server_number).join(job_id);
+    dir.join("job.json").exists().then_some(dir)
+}
+
+fn load_power_job(dir: &Path) -> Option<PowerJob> {
+    let content = std::fs::read_to_string(dir.join("job.json")).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn save_power_job(dir: &Path, job: &PowerJob) -> std::io::Result<()> {
+    let content = serde_json::to_string_pretty(job)?;
+    std::fs::write(dir.join("job.json"), content)
+}
+
+// Jobs of an account/server, newest first
+fn power_jobs_for(data_dir: &str, account_name: &str, server_number: u32) -> Vec<PowerJob> {
+    let mut jobs: Vec<PowerJob> = std::fs::read_dir(power_jobs_dir(data_dir, account_name, server_number))
+        .map(|entries| entries.flatten().filter_map(|entry| load_power_job(&entry.path())).collect())
+        .unwrap_or_default();
+    jobs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    jobs
+}
+
+// Players read by a finished job
+fn power_job_records(dir: &Path) -> Vec<PowerRecord> {
+    std::fs::File::open(dir.join("results.csv")).ok()
+        .and_then(|file| read_power_records(file).ok())
+        .unwrap_or_default()
+}
+
+// Marks jobs that were queued or running when the server stopped as failed
+fn fail_interrupted_power_jobs(data_dir: &str) {
+    let job_dirs = std::fs::read_dir(format!("{}/power", data_dir)).into_iter().flatten().flatten()
+        .flat_map(|account| std::fs::read_dir(account.path()).into_iter().flatten().flatten())
+        .flat_map(|server| std::fs::read_dir(server.path()).into_iter().flatten().flatten());
+    for entry in job_dirs {
+        let dir = entry.path();
+        let Some(mut job) = load_power_job(&dir) else { continue };
+        if matches!(job.status, PowerJobStatus::Queued | PowerJobStatus::Running) {
+            job.status = PowerJobStatus::Failed;
+            job.error = Some("Interrupted by a server restart, please upload again".to_string());
+            job.finished_at = Some(chrono::Utc::now().to_rfc3339());
+            if let Err(e) = save_power_job(&dir, &job) {
+                log::warn!("Failed to update power job {}: {}", dir.display(), e);
+            }
+        }
+    }
+}
+
+// Base name of an uploaded file with anything but letters, digits, dots, dashes and
+// underscores replaced; None for file types the extractor can't read
+fn power_upload_file_name(name: &str) -> Option<String> {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let clean: String = base.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    let extension = Path::new(&clean).extension()?.to_str()?.to_ascii_lowercase();
+    POWER_UPLOAD_EXTENSIONS.contains(&extension.as_str()).then_some(clean)
+}
+
+// Saves every file of a multipart upload into `dir`, prefixed with its position so
+// screenshots keep their order. Non-file fields are ignored.
+async fn save_power_upload(payload: &mut Multipart, dir: &Path, max_bytes: u64) -> std::result::Result<Vec<String>, String> {
+    use std::io::Write;
+
+    let mut files = Vec::new();
+    let mut total_bytes = 0u64;
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(|e| format!("Failed to read upload: {}", e))?;
+        let Some(original) = field.content_disposition().and_then(|cd| cd.get_filename()).map(str::to_string) else {
+            continue;
+        };
+        let name = power_upload_file_name(&original)
+            .ok_or_else(|| format!("{} is not a screen recording (mp4, mov, mkv, webm, avi) or screenshot (png, jpg)", original))?;
+        let name = format!("{:03}_{}", files.len() + 1, name);
+        let mut file = std::fs::File::create(dir.join(&name))
+            .map_err(|e| format!("Failed to save {}: {}", original, e))?;
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|e| format!("Failed to read upload: {}", e))?;
+            total_bytes += chunk.len() as u64;
+            if total_bytes > max_bytes {
+                return Err(format!("Upload is larger than the limit of {}", format_bytes(max_bytes)));
+            }
+            file.write_all(&chunk).map_err(|e| format!("Failed to save {}: {}", original, e))?;
+        }
+        files.push(name);
+    }
+    Ok(files)
+}
+
+// Runs the extractor on a job's files and returns the number of players it read
+async fn extract_power_levels(dir: &Path, config: &PowerExtractionConfig) -> std::result::Result<usize, String> {
+    let input = dir.join("input");
+    let output = dir.join("results.csv");
+    let command: Vec<String> = config.command.iter()
+        .map(|arg| arg.replace("{input}", &input.to_string_lossy()).replace("{output}", &output.to_string_lossy()))
+        .collect();
+    let (program, args) = command.split_first().ok_or("power_extraction.command is empty")?;
+
+    // The extractor's output is kept for the operator; its last line explains a failure
+    let log_path = dir.join("extractor.log");
+    let log = std::fs::File::create(&log_path).map_err(|e| format!("Failed to create log: {}", e))?;
+    let stdout = log.try_clone().map_err(|e| format!("Failed to create log: {}", e))?;
+    let mut child = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .stdout(stdout)
+        .stderr(log)
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+
+    let timeout = std::time::Duration::from_secs(config.timeout_secs);
+    let status = match tokio::time::timeout(timeout, child.wait()).await {
+        Ok(status) => status.map_err(|e| format!("Failed to run {}: {}", program, e))?,
+        Err(_) => {
+            let _ = child.kill().await;
+            return Err(format!("Stopped after {} seconds", config.timeout_secs));
+        }
+    };
+    if !status.success() {
+        let log = std::fs::read_to_string(&log_path).unwrap_or_default();
+        return Err(match log.lines().rev().map(str::trim).find(|line| !line.is_empty()) {
+            Some(line) => format!("Extraction failed ({}): {}", status, line),
+            None => format!("Extraction failed ({})", status),
+        });
+    }
+
+    let results = std::fs::File::open(&output).map_err(|_| "The extractor finished without writing any results".to_string())?;
+    let records = read_power_records(results).map_err(|e| format!("The extractor wrote invalid results: {}", e))?;
+    Ok(records.len())
+}
+
+// Background part of an upload: waits for a free job slot, runs the extractor and records the outcome
+async fn run_power_job(state: web::Data<AppState>, dir: PathBuf, config: PowerExtractionConfig) {
+    let Ok(_permit) = state.power_job_slots.acquire().await else { return };
+    // Deleted while it was queued
+    let Some(mut job) = load_power_job(&dir) else { return };
+    job.status = PowerJobStatus::Running;
+    if let Err(e) = save_power_job(&dir, &job) {
+        log::warn!("Failed to update power job {}: {}", job.id, e);
+    }
+
+    match extract_power_levels(&dir, &config).await {
+        Ok(records) => {
+            job.status = PowerJobStatus::Done;
+            job.records = Some(records);
+        }
+        Err(e) => {
+            log::warn!("Power job {} failed: {}", job.id, e);
+            job.status = PowerJobStatus::Failed;
+            job.error = Some(e);
+        }
+    }
+    job.finished_at = Some(chrono::Utc::now().to_rfc3339());
+    if let Err(e) = save_power_job(&dir, &job) {
+        log::error!("Failed to save power job {}: {}", job.id, e);
+    }
+}
+
+fn power_job_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "success": false,
+        "error": "Job not found"
+    }))
+}
+
+// Power level jobs of an account/server, newest first, and the upload attached to the current form
+async fn list_power_jobs(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+
+    let form_power = {
+        let forms = state.forms.lock().unwrap();
+        let current_forms = state.current_forms.lock().unwrap();
+        get_current_form(&forms, &current_forms, &account_name, server_number).and_then(|f| f.config.power)
+    };
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "enabled": state.power_extraction.is_some(),
+        "jobs": power_jobs_for(&state.data_dir, &account_name, server_number),
+        "form_power": form_power
+    })))
+}
+
+// Upload a screen recording or screenshots of the member list; power levels are extracted in the background
+async fn upload_power_recording(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    mut payload: Multipart,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let Some(config) = state.power_extraction.clone() else {
+        return Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "success": false,
+            "error": "Power level extraction is not set up on this server"
+        })));
+    };
+    let limits = account_limits(&state, &account_name);
+    if let Some(err) = check_storage_limit(&state, &account_name, &limits) {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "success": false,
+            "error": err
+        })));
+    }
+
+    let id = generate_form_code();
+    let dir = power_jobs_dir(&state.data_dir, &account_name, server_number).join(&id);
+    let input_dir = dir.join("input");
+    std::fs::create_dir_all(&input_dir)?;
+    let files = match save_power_upload(&mut payload, &input_dir, config.max_upload_bytes).await {
+        Ok(files) if !files.is_empty() => files,
+        result => {
+            let _ = std::fs::remove_dir_all(&dir);
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": result.err().unwrap_or_else(|| "No files uploaded".to_string())
+            })));
+        }
+    };
+
+    let job = PowerJob {
+        id,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        files,
+        status: PowerJobStatus::Queued,
+        finished_at: None,
+        error: None,
+        records: None,
+    };
+    save_power_job(&dir, &job).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save job: {}", e))
+    })?;
+    actix_web::rt::spawn(run_power_job(state.clone(), dir, config));
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "job": job
+    })))
+}
+
+// One power level job with the players it read
+async fn get_power_job(
+    path: web::Path<(String, u32, String)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number, job_id) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let Some((dir, job)) = power_job_dir(&state.data_dir, &account_name, server_number, &job_id)
+        .and_then(|dir| load_power_job(&dir).map(|job| (dir, job)))
+    else {
+        return Ok(power_job_not_found());
+    };
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "records": power_job_records(&dir),
+        "job": job
+    })))
+}
+
+// Download the players read by a power level job as CSV
+async fn download_power_results(
+    path: web::Path<(String, u32, String)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number, job_id) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let Some(dir) = power_job_dir(&state.data_dir, &account_name, server_number, &job_id) else {
+        return Ok(power_job_not_found());
+    };
+
+    let mut csv = Vec::new();
+    write_power_records(&mut csv, &power_job_records(&dir)).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to write CSV: {}", e))
+    })?;
+    let filename = format!("{}_{}_power_{}.csv", account_name, server_number, job_id);
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv")
+        .append_header(("Content-Disposition", format!("attachment; filename=\"{}\"", filename)))
+        .body(csv))
+}
+
+// Delete a power level job with its uploaded files and results
+async fn delete_power_job(
+    path: web::Path<(String, u32, String)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number, job_id) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let Some((dir, job)) = power_job_dir(&state.data_dir, &account_name, server_number, &job_id)
+        .and_then(|dir| load_power_job(&dir).map(|job| (dir, job)))
+    else {
+        return Ok(power_job_not_found());
+    };
+    if job.status == PowerJobStatus::Running {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "success": false,
+            "error": "The job is still running"
+        })));
+    }
+
+    std::fs::remove_dir_all(&dir).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to delete job: {}", e))
+    })?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "success": true })))
+}
+
+// Power levels read by a finished job, None if it doesn't exist or hasn't finished
+fn finished_power_records(data_dir: &str, account_name: &str, server_number: u32, job_id: &str) -> Option<Vec<PowerRecord>> {
+    let dir = power_job_dir(data_dir, account_name, server_number, job_id)?;
+    (load_power_job(&dir)?.status == PowerJobStatus::Done).then(|| power_job_records(&dir))
+}
+
+// A form's power levels and baseline matched against the entries; the error names an upload that is missing or unfinished
+fn load_form_power(data_dir: &str, account_name: &str, server_number: u32, power: &FormPower, entries: &[AppointmentEntry]) -> std::result::Result<(PowerMatch, Option<PowerMatch>), String> {
+    let load = |job_id: &str| {
+        finished_power_records(data_dir, account_name, server_number, job_id)
+            .map(|records| match_power_records(&records, entries))
+            .ok_or_else(|| format!("The power level upload {} doesn't exist or hasn't finished", job_id))
+    };
+    let matched = load(&power.job_id)?;
+    let baseline = power.baseline_job_id.as_deref().map(load).transpose()?;
+    Ok((matched, baseline))
+}
+
+fn form_power_weights(power: &FormPower) -> ScoreWeights {
+    ScoreWeights {
+        power_per_million: power.power_per_million,
+        power_gain_per_million: power.power_gain_per_million,
+        ..ScoreWeights::default()
+    }
+}
+
+// Adds a form's power levels to the entries' priority scores
+fn apply_form_power(data_dir: &str, account_name: &str, server_number: u32, power: &FormPower, entries: &mut [AppointmentEntry]) -> std::result::Result<PowerMatch, String> {
+    let (matched, baseline) = load_form_power(data_dir, account_name, server_number, power, entries)?;
+    apply_power_scores(entries, &matched, baseline.as_ref(), &form_power_weights(power));
+    Ok(matched)
+}
+
+// Priority scores as the form defines them: its score formulas (which may use the power levels),
+// then the power level weights on top
+fn score_form_entries(data_dir: &str, account_name: &str, server_number: u32, config: &FormConfig, entries: &mut [AppointmentEntry]) -> std::result::Result<(), String> {
+    let (construction, research) = config.score_formulas.parsed()?;
+    let power = config.power.as_ref()
+        .map(|power| load_form_power(data_dir, account_name, server_number, power, entries))
+        .transpose()?;
+    let (matched, baseline) = match &power {
+        Some((matched, baseline)) => (Some(matched), baseline.as_ref()),
+        None => (None, None),
+    };
+    apply_score_formulas(entries, construction.as_ref(), research.as_ref(), matched, baseline);
+    if let (Some(form_power), Some(matched)) = (&config.power, matched) {
+        apply_power_scores(entries, matched, baseline, &form_power_weights(form_power));
+    }
+    Ok(())
+}
+
+// Set the current form's score formulas; blank ones go back to the default weights
+async fn update_score_formulas(
+    path: web::Path<(String, u32)>,
+    req: web::Json<ScoreFormulas>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let formulas = req.into_inner().trimmed();
+    if let Err(e) = formulas.parsed() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": e
+        })));
+    }
+    
+    let mut forms = state.forms.lock().unwrap();
+    let code = state.current_forms.lock().unwrap().get(&schedule_key(&account_name, server_number)).cloned();
+    let form_data = match code.and_then(|code| forms.get_mut(&code)) {
+        Some(form_data) => form_data,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "No current form found"
+            })));
+        }
+    };
+    form_data.config.score_formulas = formulas;
+    save_form(&state.data_dir, form_data)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save form: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "score_formulas": form_data.config.score_formulas
+    })))
+}
+
+// Set the current form's alliance and form passcodes; blank ones are removed
+async fn update_form_passcodes(
+    path: web::Path<(String, u32)>,
+    req: web::Json<FormPasscodes>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    if let Err(e) = req.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": e
+        })));
+    }
+    
+    let mut forms = state.forms.lock().unwrap();
+    let code = state.current_forms.lock().unwrap().get(&schedule_key(&account_name, server_number)).cloned();
+    let form_data = match code.and_then(|code| forms.get_mut(&code)) {
+        Some(form_data) => form_data,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "No current form found"
+            })));
+        }
+    };
+    form_data.config.passcodes = req.into_inner().trimmed(&form_data.config.alliances);
+    save_form(&state.data_dir, form_data)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save form: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "passcodes": form_data.config.passcodes
+    })))
+}
+
+#[derive(Deserialize)]
+struct FormClosingRequest {
+    closes_at: Option<String>,
+    auto_generate: bool,
+}
+
+// Set when the current form closes and whether the schedule is generated then. Moving the
+// close time allows another automatic generation.
+async fn update_form_closing(
+    path: web::Path<(String, u32)>,
+    req: web::Json<FormClosingRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let closes_at = req.closes_at.as_deref().map(str::trim).filter(|c| !c.is_empty()).map(str::to_string);
+    if let Err(e) = validate_closes_at(closes_at.as_deref()) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": e
+        })));
+    }
+    
+    let mut forms = state.forms.lock().unwrap();
+    let code = state.current_forms.lock().unwrap().get(&schedule_key(&account_name, server_number)).cloned();
+    let form_data = match code.and_then(|code| forms.get_mut(&code)) {
+        Some(form_data) => form_data,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "No current form found"
+            })));
+        }
+    };
+    if form_data.config.closes_at != closes_at {
+        form_data.auto_generation = None;
+    }
+    form_data.config.closes_at = closes_at;
+    form_data.config.auto_generate = req.auto_generate;
+    save_form(&state.data_dir, form_data)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save form: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "closes_at": form_data.config.closes_at,
+        "auto_generate": form_data.config.auto_generate,
+        "auto_generation": form_data.auto_generation
+    })))
+}
+
+// How often closed forms are checked for automatic schedule generation
+const AUTO_GENERATE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+// Background task generating the schedules of forms that closed with auto generation on
+async fn run_auto_generation(state: web::Data<AppState>) {
+    let mut interval = tokio::time::interval(AUTO_GENERATE_INTERVAL);
+    loop {
+        interval.tick().await;
+        // Other instances may have closed or changed forms
+        if state.shared_storage {
+            reload_shared_state(&state);
+        }
+        auto_generate_closed_forms(&state);
+    }
+}
+
+// Generates the schedule of every current form that has closed with auto generation on, once
+// per form. The generated schedule replaces the existing one, like the Generate button does.
+fn auto_generate_closed_forms(state: &AppState) {
+    let now = chrono::Utc::now();
+    let due: Vec<(String, String, u32)> = {
+        let forms = state.forms.lock().unwrap();
+        let current_forms = state.current_forms.lock().unwrap();
+        current_forms.values()
+            .filter_map(|code| forms.get(code))
+            .filter(|f| f.config.auto_generate && f.auto_generation.is_none() && f.config.is_closed(now))
+            .map(|f| (f.code.clone(), f.account_name.clone(), f.server_number))
+            .collect()
+    };
+    for (code, account_name, server_number) in due {
+        let result = generate_form_schedule(state, &account_name, server_number, false, None, true, None);
+        match &result {
+            Ok(message) => {
+                log::info!("Form {} of {} #{} closed: {}", code, account_name, server_number, message);
+                notify_schedule_generated(state, &account_name, server_number, message);
+            }
+            Err(e) => log::warn!("Form {} of {} #{} closed, but its schedule couldn't be generated: {}", code, account_name, server_number, e),
+        }
+        let mut forms = state.forms.lock().unwrap();
+        if let Some(form_data) = forms.get_mut(&code) {
+            form_data.auto_generation = Some(AutoGeneration {
+                ran_at: now.to_rfc3339(),
+                success: result.is_ok(),
+                message: result.unwrap_or_else(|e| e),
+            });
+            if let Err(e) = save_form(&state.data_dir, form_data) {
+                log::warn!("Failed to save form {}: {}", code, e);
+            }
+        }
+    }
+}
+
+// Limits of an uploaded roster
+const MAX_ROSTER_ENTRIES: usize = 5000;
+const MAX_ROSTER_FIELD: usize = 100;
+const MAX_ROSTER_NOTES: usize = 1000;
+
+// Trims a roster and checks it: a player ID on every entry and only once, fields of sane length
+fn validate_roster(roster: Vec<RosterEntry>) -> std::result::Result<Vec<RosterEntry>, String> {
+    if roster.len() > MAX_ROSTER_ENTRIES {
+        return Err(format!("A roster holds at most {} players", MAX_ROSTER_ENTRIES));
+    }
+    let mut seen = HashSet::new();
+    let mut trimmed = Vec::with_capacity(roster.len());
+    for entry in roster {
+        let entry = RosterEntry {
+            player_id: entry.player_id.trim().to_string(),
+            name: entry.name.trim().to_string(),
+            alliance: entry.alliance.trim().to_string(),
+            discord_id: entry.discord_id.map(|id| id.trim().to_string()).filter(|id| !id.is_empty()),
+            notes: entry.notes.trim().to_string(),
+        };
+        if entry.player_id.is_empty() {
+            return Err(format!("Player ID required (for \"{}\")", entry.name));
+        }
+        if !seen.insert(entry.player_id.clone()) {
+            return Err(format!("Player ID {} is listed twice", entry.player_id));
+        }
+        let entry = match &entry.discord_id {
+            Some(raw) => match normalize_discord_id(raw) {
+                Some(id) => RosterEntry { discord_id: Some(id), ..entry },
+                None => return Err(format!("Player {}: Discord ID must be the numeric user ID, not \"{}\"", entry.player_id, raw)),
+            },
+            None => entry,
+        };
+        let too_long = [&entry.player_id, &entry.name, &entry.alliance].into_iter()
+            .chain(entry.discord_id.as_ref())
+            .any(|field| field.chars().count() > MAX_ROSTER_FIELD);
+        if too_long || entry.notes.chars().count() > MAX_ROSTER_NOTES {
+            return Err(format!("Player {}: fields are limited to {} characters, notes to {}", entry.player_id, MAX_ROSTER_FIELD, MAX_ROSTER_NOTES));
+        }
+        trimmed.push(entry);
+    }
+    Ok(trimmed)
+}
+
+// Player roster of an account/server (owner only: it holds Discord IDs and notes)
+async fn get_roster(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let roster = load_roster(&state.data_dir, &account_name, server_number);
+    // Current submitters who aren't on the roster yet, for adding them in one go
+    let (entries, _) = load_form_entries(&state, &account_name, server_number);
+    let unlisted: Vec<serde_json::Value> = entries.iter()
+        .filter(|e| !roster.iter().any(|r| r.player_id == e.player_id))
+        .map(|e| serde_json::json!({
+            "player_id": e.player_id,
+            "name": e.name,
+            "alliance": e.alliance
+        }))
+        .collect();
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "roster": roster,
+        "unlisted_submitters": unlisted
+    })))
+}
+
+#[derive(Deserialize)]
+struct UpdateRosterRequest {
+    roster: Vec<RosterEntry>,
+}
+
+// Replace the player roster of an account/server
+async fn update_roster(
+    path: web::Path<(String, u32)>,
+    req: web::Json<UpdateRosterRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let roster = match validate_roster(req.into_inner().roster) {
+        Ok(roster) => roster,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": e
+            })));
+        }
+    };
+    
+    save_roster(&state.data_dir, &account_name, server_number, &roster)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save roster: {}", e)))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "roster": roster
+    })))
+}
+
+// Download the player roster as CSV (the format `schedule --config` reads as `roster`)
+async fn download_roster_csv(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let mut csv = Vec::new();
+    write_roster(&mut csv, &load_roster(&state.data_dir, &account_name, server_number)).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to write CSV: {}", e))
+    })?;
+    let filename = format!("{}_{}_roster.csv", account_name, server_number);
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv")
+        .append_header(("Content-Disposition", format!("attachment; filename=\"{}\"", filename)))
+        .body(csv))
+}
+
+// Import roster rows from a CSV body. Imported players replace the listed ones with the same ID,
+// everyone else stays.
+async fn import_roster_csv(
+    path: web::Path<(String, u32)>,
+    body: String,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let imported = match read_roster(body.as_bytes()) {
+        Ok(imported) => imported,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": format!("Invalid roster CSV: {}", e)
+            })));
+        }
+    };
+    let imported_count = imported.len();
+    
+    let mut roster = load_roster(&state.data_dir, &account_name, server_number);
+    for entry in imported {
+        match roster.iter_mut().find(|r| r.player_id == entry.player_id.trim()) {
+            Some(existing) => *existing = entry,
+            None => roster.push(entry),
+        }
+    }
+    let roster = match validate_roster(roster) {
+        Ok(roster) => roster,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": e
+            })));
+        }
+    };
+    
+    save_roster(&state.data_dir, &account_name, server_number, &roster)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save roster: {}", e)))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "imported": imported_count,
+        "roster": roster
+    })))
+}
+
+// Which player's data stays where both merged players have some
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeKeep {
+    From,
+    #[default]
+    Into,
+}
+
+#[derive(Deserialize)]
+pub struct MergePlayersRequest {
+    from_player_id: String, // ID that disappears
+    into_player_id: String, // ID that remains
+    #[serde(default)]
+    keep: MergeKeep,
+}
+
+// Replaces `from` with `into` in a list of player IDs or alliance tags without listing `into` twice
+fn replace_in_list(ids: &mut Vec<String>, from: &str, into: &str) -> bool {
+    if !ids.iter().any(|id| id == from) {
+        return false;
+    }
+    let listed = ids.iter().any(|id| id == into);
+    ids.retain(|id| id != from || !listed);
+    for id in ids.iter_mut().filter(|id| *id == from) {
+        *id = into.to_string();
+    }
+    true
+}
+
+// Moves player `from`'s appointments and entry to player `into`. Where both have an
+// appointment on a day, or both have an entry, only `keep`'s stays. Returns whether anything changed.
+fn merge_player_in_schedule(schedule_data: &mut ScheduleData, from: &str, into: &str, keep: &str) -> bool {
+    let loser = if keep == from { into } else { from };
+    let mut changed = false;
+    let mut merged_entry = None;
+    if let Some(entries) = schedule_data.entries.as_mut() {
+        let listed = |id: &str| entries.iter().any(|e| e.player_id == id);
+        if listed(from) {
+            if listed(into) {
+                entries.retain(|e| e.player_id != loser);
+            }
+            for entry in entries.iter_mut().filter(|e| e.player_id == from) {
+                entry.player_id = into.to_string();
+            }
+            changed = true;
+        }
+        merged_entry = entries.iter().find(|e| e.player_id == into).map(|e| (e.name.clone(), e.alliance.clone()));
+    }
+    for schedule in [
+        &mut schedule_data.construction_schedule,
+        &mut schedule_data.research_schedule,
+        &mut schedule_data.troops_schedule,
+    ].into_iter().flatten() {
+        let scheduled = |id: &str| schedule.appointments.values().any(|a| a.player_id == id);
+        if scheduled(from) {
+            if scheduled(into) {
+                schedule.appointments.retain(|_, appt| appt.player_id != loser);
+            }
+            for appt in schedule.appointments.values_mut().filter(|a| a.player_id == from) {
+                appt.player_id = into.to_string();
+            }
+            changed = true;
+        }
+        if replace_in_list(&mut schedule.unassigned, from, into) {
+            if schedule.appointments.values().any(|a| a.player_id == into) {
+                schedule.unassigned.retain(|id| id != into);
+            }
+            changed = true;
+        }
+    }
+    if let Some(ids) = schedule_data.scheduled_player_ids.as_mut() {
+        changed |= replace_in_list(ids, from, into);
+    }
+    // Appointments show the name of the entry that stayed
+    if let Some((name, alliance)) = merged_entry.filter(|_| changed) {
+        for schedule in [
+            &mut schedule_data.construction_schedule,
+            &mut schedule_data.research_schedule,
+            &mut schedule_data.troops_schedule,
+        ].into_iter().flatten() {
+            for appt in schedule.appointments.values_mut().filter(|a| a.player_id == into) {
+                appt.name = name.clone();
+                appt.alliance = alliance.clone();
+            }
+        }
+    }
+    changed
+}
+
+// Moves player `from`'s predetermined slots and guaranteed days to player `into`.
+// Where both have a predetermined slot on a day, only `keep`'s stays. Returns whether anything changed.
+fn merge_player_in_form_config(config: &mut FormConfig, from: &str, into: &str, keep: &str) -> bool {
+    let loser = if keep == from { into } else { from };
+    let days_of = |id: &str| -> HashSet<String> {
+        config.predetermined_slots.iter()
+            .filter(|p| p.player_id.as_deref() == Some(id))
+            .map(|p| p.day.clone())
+            .collect()
+    };
+    let both: HashSet<String> = days_of(from).intersection(&days_of(into)).cloned().collect();
+    let before = config.predetermined_slots.len();
+    config.predetermined_slots.retain(|p| p.player_id.as_deref() != Some(loser) || !both.contains(&p.day));
+    let mut changed = before != config.predetermined_slots.len();
+    for slot in config.predetermined_slots.iter_mut().filter(|p| p.player_id.as_deref() == Some(from)) {
+        slot.player_id = Some(into.to_string());
+        changed = true;
+    }
+    changed |= config.guaranteed_players.rename(from, into);
+    changed
+}
 
-            // Update state
-            let mut schedules = state.schedules.lock().unwrap();
-            let key = schedule_key(&account_name, server_number);
-            let scheduled_ids: Vec<String> = {
-                let mut ids = HashSet::new();
-                for appt in construction_schedule.appointments.values() {
-                    ids.insert(appt.player_id.clone());
-                }
-                for appt in research_schedule.appointments.values() {
-                    ids.insert(appt.player_id.clone());
-                }
-                for appt in troops_schedule.appointments.values() {
-                    ids.insert(appt.player_id.clone());
-                }
-                ids.into_iter().collect()
-            };
-            schedules.insert(key, ScheduleData {
-                construction_schedule: Some(construction_schedule),
-                research_schedule: Some(research_schedule),
-                troops_schedule: Some(troops_schedule),
-                entries: Some(entries),
-                scheduled_player_ids: Some(scheduled_ids),
-            });
+// Moves player `from`'s roster entry to player `into`; where both have one, `keep`'s stays
+fn merge_roster_entries(roster: &mut Vec<RosterEntry>, from: &str, into: &str, keep: &str) -> bool {
+    let Some(from_index) = roster.iter().position(|r| r.player_id == from) else {
+        return false;
+    };
+    let mut entry = roster.remove(from_index);
+    entry.player_id = into.to_string();
+    match roster.iter_mut().find(|r| r.player_id == into) {
+        Some(existing) if keep == from => *existing = entry,
+        Some(_) => {}
+        None => roster.insert(from_index, entry),
+    }
+    true
+}
 
-            Ok(HttpResponse::Ok().json(serde_json::json!({
-                "success": true,
-                "message": "Schedule generated successfully"
-            })))
+// Submission CSVs of an account/server: each of its forms, the archived weeks and the legacy file
+fn account_submission_csvs(state: &AppState, account_name: &str, server_number: u32) -> Vec<PathBuf> {
+    let codes: Vec<String> = state.forms.lock().unwrap().values()
+        .filter(|f| f.account_name == account_name && f.server_number == server_number)
+        .map(|f| f.code.clone())
+        .collect();
+    codes.iter()
+        .map(|code| PathBuf::from(format!("{}/current_forms/{}_submissions.csv", state.data_dir, code)))
+        .chain(archived_forms_for(&state.data_dir, account_name, server_number).into_iter()
+            .map(|(id, _)| PathBuf::from(format!("{}/old_forms/{}_{}_{}_submissions.csv", state.data_dir, account_name, server_number, id))))
+        .chain([PathBuf::from(format!("{}/{}_{}_form_submissions.csv", state.data_dir, account_name, server_number))])
+        .filter(|path| path.exists())
+        .collect()
+}
+
+// Merge a player who submitted under two IDs into one (owner only): moves the submissions,
+// schedule appointments, predetermined slots, guaranteed days and roster entry of one ID to
+// the other, in the current and archived weeks. `keep` picks whose data stays where both have some.
+async fn merge_players(
+    path: web::Path<(String, u32)>,
+    req: web::Json<MergePlayersRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let from = req.from_player_id.trim().to_string();
+    let into = req.into_player_id.trim().to_string();
+    if from.is_empty() || into.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "Both player IDs are required"
+        })));
+    }
+    if from == into {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "Pick two different player IDs"
+        })));
+    }
+    let keep = match req.keep {
+        MergeKeep::From => from.as_str(),
+        MergeKeep::Into => into.as_str(),
+    };
+    
+    // Form submissions
+    let mut merged_submissions = 0;
+    for csv_path in account_submission_csvs(&state, &account_name, server_number) {
+        match merge_player_rows(&csv_path, &from, &into, keep) {
+            Ok(merged) => merged_submissions += merged,
+            Err(e) => {
+                log::warn!("Failed to merge players in {}: {}", csv_path.display(), e);
+                return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to update {}", csv_path.display())
+                })));
+            }
         }
-        Err(e) => Ok(HttpResponse::BadRequest().json(serde_json::json!({
+    }
+    
+    // Current and archived schedules
+    let mut merged_schedules = 0;
+    if let Some(mut schedule_data) = load_schedule(&state.data_dir, &account_name, server_number) {
+        if merge_player_in_schedule(&mut schedule_data, &from, &into, keep) {
+            save_schedule(&state.data_dir, &account_name, server_number, &schedule_data).map_err(|e| {
+                actix_web::error::ErrorInternalServerError(format!("Failed to save schedule: {}", e))
+            })?;
+            merged_schedules += 1;
+        }
+    }
+    for (id, _) in archived_schedule_ids(&state.data_dir, &account_name, server_number) {
+        let path = archived_schedule_path(&state.data_dir, &account_name, server_number, &id);
+        let Some(mut schedule_data) = std::fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str::<ScheduleData>(&c).ok()) else {
+            continue;
+        };
+        if merge_player_in_schedule(&mut schedule_data, &from, &into, keep) {
+            std::fs::write(&path, serde_json::to_string_pretty(&schedule_data)?)?;
+            merged_schedules += 1;
+        }
+    }
+    state.schedules.lock().unwrap().remove(&schedule_key(&account_name, server_number));
+    
+    // Predetermined slots and guaranteed days of current and archived forms
+    let mut merged_forms = 0;
+    {
+        let mut forms = state.forms.lock().unwrap();
+        for form_data in forms.values_mut().filter(|f| f.account_name == account_name && f.server_number == server_number) {
+            if merge_player_in_form_config(&mut form_data.config, &from, &into, keep) {
+                save_form(&state.data_dir, form_data)?;
+                merged_forms += 1;
+            }
+        }
+    }
+    for (path, mut form_data) in archived_forms(&state.data_dir) {
+        if form_data.account_name != account_name || form_data.server_number != server_number {
+            continue;
+        }
+        if merge_player_in_form_config(&mut form_data.config, &from, &into, keep) {
+            std::fs::write(&path, serde_json::to_string_pretty(&form_data)?)?;
+            merged_forms += 1;
+        }
+    }
+    
+    // Roster entry
+    let mut roster = load_roster(&state.data_dir, &account_name, server_number);
+    let merged_roster_entry = merge_roster_entries(&mut roster, &from, &into, keep);
+    if merged_roster_entry {
+        save_roster(&state.data_dir, &account_name, server_number, &roster)
+            .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save roster: {}", e)))?;
+    }
+    
+    if merged_submissions == 0 && merged_schedules == 0 && merged_forms == 0 && !merged_roster_entry {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
             "success": false,
-            "error": format!("Failed to process CSV: {}", e)
-        })))
+            "error": format!("Player ID {} was not found in this server's data", from)
+        })));
+    }
+    // Cached statistics count the two IDs as separate players
+    let stats_path = format!("{}/statistics/{}/{}.json", state.data_dir, account_name, server_number);
+    if Path::new(&stats_path).exists() {
+        if let Err(e) = std::fs::remove_file(&stats_path) {
+            log::warn!("Failed to remove cached statistics {}: {}", stats_path, e);
+        }
+    }
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "from_player_id": from,
+        "into_player_id": into,
+        "merged_submissions": merged_submissions,
+        "merged_schedules": merged_schedules,
+        "merged_forms": merged_forms,
+        "merged_roster_entry": merged_roster_entry
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct RenameAllianceRequest {
+    from: String, // Current tag
+    to: String, // New tag
+}
+
+// Renames an alliance in a schedule's appointments and stored entries
+fn rename_alliance_in_schedule(schedule_data: &mut ScheduleData, from: &str, into: &str) -> bool {
+    let mut changed = false;
+    for schedule in [
+        &mut schedule_data.construction_schedule,
+        &mut schedule_data.research_schedule,
+        &mut schedule_data.troops_schedule,
+    ].into_iter().flatten() {
+        for appt in schedule.appointments.values_mut().filter(|a| a.alliance == from) {
+            appt.alliance = into.to_string();
+            changed = true;
+        }
+    }
+    for entry in schedule_data.entries.iter_mut().flatten().filter(|e| e.alliance == from) {
+        entry.alliance = into.to_string();
+        changed = true;
+    }
+    changed
+}
+
+// Renames an alliance in a form's alliance list, passcodes and predetermined slots.
+// A passcode already set for `into` stays.
+fn rename_alliance_in_form_config(config: &mut FormConfig, from: &str, into: &str) -> bool {
+    let mut changed = replace_in_list(&mut config.alliances, from, into);
+    if let Some(passcode) = config.passcodes.alliances.remove(from) {
+        config.passcodes.alliances.entry(into.to_string()).or_insert(passcode);
+        changed = true;
+    }
+    for slot in config.predetermined_slots.iter_mut().filter(|p| p.alliance == from) {
+        slot.alliance = into.to_string();
+        changed = true;
+    }
+    changed
+}
+
+// Replaces several files together: every file is written next to its target first and only
+// moved into place once all of them were written, so a failed write changes none of them
+fn replace_files(files: &[(PathBuf, Vec<u8>)]) -> std::io::Result<()> {
+    let tmp_path = |path: &Path| {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        PathBuf::from(tmp)
+    };
+    for (i, (path, content)) in files.iter().enumerate() {
+        if let Err(e) = std::fs::write(tmp_path(path), content) {
+            for (path, _) in &files[..=i] {
+                let _ = std::fs::remove_file(tmp_path(path));
+            }
+            return Err(e);
+        }
+    }
+    for (path, _) in files {
+        std::fs::rename(tmp_path(path), path)?;
+        storage::remember(path, storage::file_stamp(path));
+    }
+    Ok(())
+}
+
+// Rename an alliance tag everywhere on an account/server (owner only): submissions, schedules,
+// form alliance lists, passcodes and predetermined slots of the current and archived weeks,
+// the roster and alliance logins. Every file is replaced together or none is.
+async fn rename_alliance(
+    path: web::Path<(String, u32)>,
+    req: web::Json<RenameAllianceRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let from = req.from.trim().to_string();
+    let into = req.to.trim().to_string();
+    if from.is_empty() || into.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "Both alliance tags are required"
+        })));
+    }
+    if from == into {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "The new tag is the same as the current one"
+        })));
+    }
+    
+    // Everything is read and renamed in memory first
+    let mut files: Vec<(PathBuf, Vec<u8>)> = Vec::new();
+    let mut renamed_submissions = 0;
+    for csv_path in account_submission_csvs(&state, &account_name, server_number) {
+        match rename_alliance_rows(&csv_path, &from, &into) {
+            Ok(Some(rows)) => {
+                renamed_submissions += rows.renamed;
+                files.push((csv_path, rows.content));
+            }
+            Ok(None) => {}
+            Err(e) => {
+                log::warn!("Failed to rename alliance in {}: {}", csv_path.display(), e);
+                return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to read {}", csv_path.display())
+                })));
+            }
+        }
+    }
+    
+    let mut renamed_schedules = 0;
+    let current_schedule = load_schedule(&state.data_dir, &account_name, server_number)
+        .map(|schedule_data| (PathBuf::from(format!("{}/schedules/{}/{}.json", state.data_dir, account_name, server_number)), schedule_data));
+    let archived_schedules = archived_schedule_ids(&state.data_dir, &account_name, server_number).into_iter()
+        .map(|(id, _)| PathBuf::from(archived_schedule_path(&state.data_dir, &account_name, server_number, &id)))
+        .filter_map(|path| {
+            let schedule_data = serde_json::from_str::<ScheduleData>(&std::fs::read_to_string(&path).ok()?).ok()?;
+            Some((path, schedule_data))
+        });
+    for (path, mut schedule_data) in current_schedule.into_iter().chain(archived_schedules) {
+        if rename_alliance_in_schedule(&mut schedule_data, &from, &into) {
+            files.push((path, serde_json::to_vec_pretty(&schedule_data)?));
+            renamed_schedules += 1;
+        }
+    }
+    
+    let mut renamed_forms = 0;
+    let current_forms: Vec<(PathBuf, FormData)> = state.forms.lock().unwrap().values()
+        .filter(|f| f.account_name == account_name && f.server_number == server_number)
+        .map(|f| (PathBuf::from(format!("{}/current_forms/{}.json", state.data_dir, f.code)), f.clone()))
+        .collect();
+    let archived = archived_forms(&state.data_dir).into_iter()
+        .filter(|(_, f)| f.account_name == account_name && f.server_number == server_number);
+    for (path, mut form_data) in current_forms.into_iter().chain(archived) {
+        if rename_alliance_in_form_config(&mut form_data.config, &from, &into) {
+            files.push((path, serde_json::to_vec_pretty(&form_data)?));
+            renamed_forms += 1;
+        }
+    }
+    
+    let mut roster = load_roster(&state.data_dir, &account_name, server_number);
+    let mut renamed_roster_entries = 0;
+    for entry in roster.iter_mut().filter(|r| r.alliance == from) {
+        entry.alliance = into.clone();
+        renamed_roster_entries += 1;
+    }
+    if renamed_roster_entries > 0 {
+        files.push((PathBuf::from(format!("{}/rosters/{}/{}.json", state.data_dir, account_name, server_number)), serde_json::to_vec_pretty(&roster)?));
+    }
+    
+    let renamed_logins = state.accounts.lock().unwrap().get(&account_name)
+        .map(|a| a.sub_accounts.iter().filter(|s| s.alliance == from).count())
+        .unwrap_or(0);
+    if files.is_empty() && renamed_logins == 0 {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": format!("No data uses the alliance {}", from)
+        })));
+    }
+    
+    if let Err(e) = replace_files(&files) {
+        log::warn!("Failed to rename alliance {} to {}: {}", from, into, e);
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "success": false,
+            "error": "Failed to save the renamed data, nothing was changed"
+        })));
+    }
+    {
+        let mut forms = state.forms.lock().unwrap();
+        for form_data in forms.values_mut().filter(|f| f.account_name == account_name && f.server_number == server_number) {
+            rename_alliance_in_form_config(&mut form_data.config, &from, &into);
+        }
+    }
+    state.schedules.lock().unwrap().remove(&schedule_key(&account_name, server_number));
+    // Alliance logins hold the tag they may edit; sessions logged in with the old tag are ended
+    if renamed_logins > 0 {
+        let mut accounts = state.accounts.lock().unwrap();
+        if let Some(account) = accounts.get_mut(&account_name) {
+            for sub in account.sub_accounts.iter_mut().filter(|s| s.alliance == from) {
+                sub.alliance = into.clone();
+            }
+        }
+        save_accounts(&state.data_dir, &accounts)
+            .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save accounts: {}", e)))?;
+    }
+    // Cached statistics still count the old tag
+    let stats_path = format!("{}/statistics/{}/{}.json", state.data_dir, account_name, server_number);
+    if Path::new(&stats_path).exists() {
+        if let Err(e) = std::fs::remove_file(&stats_path) {
+            log::warn!("Failed to remove cached statistics {}: {}", stats_path, e);
+        }
+    }
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "from": from,
+        "to": into,
+        "renamed_submissions": renamed_submissions,
+        "renamed_schedules": renamed_schedules,
+        "renamed_forms": renamed_forms,
+        "renamed_roster_entries": renamed_roster_entries,
+        "renamed_logins": renamed_logins
+    })))
+}
+
+// Attach a power level upload to the current form (or detach it with null); returns which submitters it matched
+async fn update_form_power(
+    path: web::Path<(String, u32)>,
+    req: web::Json<Option<FormPower>>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    let power = req.into_inner();
+    
+    // Check the uploads against the current submissions before saving
+    let form_code = state.current_forms.lock().unwrap().get(&schedule_key(&account_name, server_number)).cloned();
+    let Some(form_code) = form_code else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "No current form found"
+        })));
+    };
+    let csv_path = format!("{}/current_forms/{}_submissions.csv", state.data_dir, form_code);
+    let mut entries = load_appointments(&csv_path, None, None, None).unwrap_or_default();
+    let matched = match &power {
+        Some(power) => match apply_form_power(&state.data_dir, &account_name, server_number, power, &mut entries) {
+            Ok(matched) => Some(matched),
+            Err(e) => {
+                return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                    "success": false,
+                    "error": e
+                })));
+            }
+        },
+        None => None,
+    };
+    
+    let mut forms = state.forms.lock().unwrap();
+    let Some(form_data) = forms.get_mut(&form_code) else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "No current form found"
+        })));
+    };
+    form_data.config.power = power.clone();
+    save_form(&state.data_dir, form_data)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save form: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "power": power,
+        "players": entries.len(),
+        "matched": matched.as_ref().map_or(0, |m| m.power.len()),
+        "unmatched": matched.map(|m| m.unmatched).unwrap_or_default()
+    })))
+}
+
+// Number of suggested players per empty slot
+const UNFILLED_SLOT_CANDIDATES: usize = 5;
+
+// Whether a player asked for the day and listed the slot
+fn lists_slot(e: &AppointmentEntry, day_str: &str, slot: u8) -> bool {
+    match day_str {
+        "construction" => e.wants_construction && e.construction_available_slots.contains(&slot),
+        "research" => e.wants_research && e.research_available_slots.contains(&slot),
+        _ => e.wants_troops && e.troops_available_slots.contains(&slot),
+    }
+}
+
+// Score a day's slots are handed out by; troops go by speedups
+fn day_score(e: &AppointmentEntry, day_str: &str) -> u32 {
+    match day_str {
+        "construction" => e.construction_score,
+        "research" => e.research_score,
+        _ => e.troops_speedups,
+    }
+}
+
+// Players without a slot on a day, highest score first
+fn free_entries<'a>(schedule: &DaySchedule, entries: &'a [AppointmentEntry], day_str: &str) -> Vec<&'a AppointmentEntry> {
+    // Manually placed players have no real ID, so they are recognised by name too
+    let placed: HashSet<&str> = schedule.appointments.values().map(|a| a.player_id.as_str()).collect();
+    let placed_names: HashSet<(&str, &str)> = schedule.appointments.values().map(|a| (a.alliance.as_str(), a.name.as_str())).collect();
+    let mut free: Vec<&AppointmentEntry> = entries.iter()
+        .filter(|e| !placed.contains(e.player_id.as_str()) && !placed_names.contains(&(e.alliance.as_str(), e.name.as_str())))
+        .collect();
+    free.sort_by(|a, b| day_score(b, day_str).cmp(&day_score(a, day_str)).then_with(|| a.name.cmp(&b.name)));
+    free
+}
+
+// For every filled slot of a day, the highest scoring player without a slot that day who listed
+// its time: who to ask first when the player in it cancels
+fn slot_substitutes(schedule: &DaySchedule, entries: &[AppointmentEntry], day_str: &str) -> HashMap<u8, SlotCandidate> {
+    let free = free_entries(schedule, entries, day_str);
+    schedule.appointments.keys()
+        .filter_map(|slot| {
+            let e = free.iter().find(|e| lists_slot(e, day_str, *slot))?;
+            Some((*slot, SlotCandidate {
+                player_id: e.player_id.clone(),
+                name: e.name.clone(),
+                alliance: e.alliance.clone(),
+                score: day_score(e, day_str),
+                assigned_time: None,
+            }))
+        })
+        .collect()
+}
+
+// How a vacated slot was filled again
+enum Refill {
+    // By the highest scoring player without a slot that day who listed it
+    Waitlist(String),
+    // By a player shifted from another slot of the day, whose old slot went to a player without one
+    Shift { player: String, from_slot: u8, waitlisted: String },
+}
+
+// An appointment for a player placed by hand or by a refill
+fn entry_appointment(e: &AppointmentEntry, day_str: &str, slot: u8) -> ScheduledAppointment {
+    ScheduledAppointment {
+        player_id: e.player_id.clone(),
+        name: e.name.clone(),
+        alliance: e.alliance.clone(),
+        slot,
+        priority_score: day_score(e, day_str),
+        note: None,
+        manual: false,
+    }
+}
+
+// Fills one vacated slot of a day without disturbing the rest of the schedule: with the highest
+// scoring player without a slot that day who listed it or, failing that, by shifting a placed
+// player who listed it into it when that frees a slot such a player listed. Players in locked
+// slots are never shifted.
+fn refill_slot(schedule: &mut DaySchedule, entries: &[AppointmentEntry], day_str: &str, slot: u8, locked: &HashSet<u8>) -> Option<Refill> {
+    let free = free_entries(schedule, entries, day_str);
+    if let Some(e) = free.iter().find(|e| lists_slot(e, day_str, slot)) {
+        schedule.unassigned.retain(|id| *id != e.player_id);
+        schedule.appointments.insert(slot, entry_appointment(e, day_str, slot));
+        return Some(Refill::Waitlist(format_player_name(&e.alliance, &e.name)));
+    }
+    
+    // Highest scoring player without a slot for a slot freed by shifting its occupant, earliest slot on ties
+    let (from_slot, waitlisted) = schedule.appointments.values()
+        .filter(|a| !locked.contains(&a.slot))
+        .filter(|a| entries.iter().any(|e| e.player_id == a.player_id && lists_slot(e, day_str, slot)))
+        .filter_map(|a| Some((a.slot, *free.iter().find(|e| lists_slot(e, day_str, a.slot))?)))
+        .max_by(|(slot_a, a), (slot_b, b)| day_score(a, day_str).cmp(&day_score(b, day_str)).then_with(|| slot_b.cmp(slot_a)))?;
+    let mut shifted = schedule.appointments.remove(&from_slot)?;
+    let player = format_player_name(&shifted.alliance, &shifted.name);
+    shifted.slot = slot;
+    schedule.appointments.insert(slot, shifted);
+    schedule.unassigned.retain(|id| *id != waitlisted.player_id);
+    schedule.appointments.insert(from_slot, entry_appointment(waitlisted, day_str, from_slot));
+    Some(Refill::Shift {
+        player,
+        from_slot,
+        waitlisted: format_player_name(&waitlisted.alliance, &waitlisted.name),
+    })
+}
+
+// Slots a refill must not shift players out of: predetermined slots and the linked last
+// construction / first research slot
+fn refill_locked_slots(config: Option<&FormConfig>, day_str: &str, time_slots: &[(u8, String)]) -> HashSet<u8> {
+    let mut locked: HashSet<u8> = config.iter()
+        .flat_map(|c| &c.predetermined_slots)
+        .filter(|p| p.day == day_str)
+        .filter_map(|p| time_slots.iter().find(|(_, time)| time.trim() == p.time.trim()).map(|(slot, _)| *slot))
+        .collect();
+    match day_str {
+        "construction" => locked.extend(time_slots.iter().map(|(slot, _)| *slot).max()),
+        "research" => {
+            locked.insert(1);
+        }
+        _ => {}
+    }
+    locked
+}
+
+// Fills the empty slots of a day from the players without a slot that day, leaving every
+// placed player where they are. Highest score first, each player takes the slot they listed
+// that the fewest of the remaining players listed, so one pick blocks as few others as possible.
+// Locked slots stay empty: they belong to a predetermined player or the player of the other day.
+// Returns the filled slots.
+fn auto_fill_day(schedule: &mut DaySchedule, entries: &[AppointmentEntry], day_str: &str, time_slots: &[(u8, String)], locked: &HashSet<u8>) -> Vec<u8> {
+    let mut empty: HashSet<u8> = time_slots.iter()
+        .map(|(slot, _)| *slot)
+        .filter(|slot| !schedule.appointments.contains_key(slot) && !locked.contains(slot))
+        .collect();
+    let mut free = free_entries(schedule, entries, day_str);
+    free.retain(|e| empty.iter().any(|slot| lists_slot(e, day_str, *slot)));
+    
+    let mut filled = Vec::new();
+    for (i, entry) in free.iter().enumerate() {
+        let demand = |slot: u8| free[i + 1..].iter().filter(|e| lists_slot(e, day_str, slot)).count();
+        let Some(slot) = empty.iter().copied()
+            .filter(|slot| lists_slot(entry, day_str, *slot))
+            .min_by_key(|slot| (demand(*slot), *slot)) else { continue };
+        empty.remove(&slot);
+        schedule.unassigned.retain(|id| *id != entry.player_id);
+        schedule.appointments.insert(slot, entry_appointment(entry, day_str, slot));
+        filled.push(slot);
+    }
+    filled.sort();
+    filled
+}
+
+// Empty slots of one day's schedule with the players who listed them, unassigned players first,
+// keeping up to `limit` candidates per slot (all with None)
+fn unfilled_day(schedule: Option<&DaySchedule>, entries: &[AppointmentEntry], time_slots: &[(u8, String)], day_str: &str, limit: Option<usize>) -> UnfilledDay {
+    let empty = HashMap::new();
+    let appointments = schedule.map(|s| &s.appointments).unwrap_or(&empty);
+    let assigned: HashMap<&str, u8> = appointments.values().map(|a| (a.player_id.as_str(), a.slot)).collect();
+    let slot_time = |slot: u8| time_slots.iter().find(|(s, _)| *s == slot).map(|(_, t)| t.clone()).unwrap_or_else(|| slot_to_time(slot));
+    
+    let unfilled = time_slots.iter()
+        .filter(|(slot, _)| !appointments.contains_key(slot))
+        .map(|(slot, time)| {
+            let mut candidates: Vec<SlotCandidate> = entries.iter()
+                .filter(|e| lists_slot(e, day_str, *slot))
+                .map(|e| SlotCandidate {
+                    player_id: e.player_id.clone(),
+                    name: e.name.clone(),
+                    alliance: e.alliance.clone(),
+                    score: day_score(e, day_str),
+                    assigned_time: assigned.get(e.player_id.as_str()).map(|s| slot_time(*s)),
+                })
+                .collect();
+            candidates.sort_by(|a, b| {
+                a.assigned_time.is_some().cmp(&b.assigned_time.is_some()).then_with(|| b.score.cmp(&a.score))
+            });
+            
+            let reason = if candidates.is_empty() {
+                "nobody_available"
+            } else if candidates.iter().all(|c| c.assigned_time.is_some()) {
+                "all_placed_elsewhere"
+            } else {
+                "available_players_unassigned"
+            };
+            if let Some(limit) = limit {
+                candidates.truncate(limit);
+            }
+            UnfilledSlot { time: time.clone(), reason, candidates }
+        })
+        .collect();
+    
+    UnfilledDay {
+        total_slots: time_slots.len(),
+        unfilled,
     }
 }
 
-// Stats endpoint
-async fn get_stats(
+// Empty slots of the generated schedule per day, why they stayed empty and who could be moved into them
+async fn get_unfilled_slots(
     path: web::Path<(String, u32)>,
+    session: Session,
     state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
     let (account_name, server_number) = path.into_inner();
     let account_name = account_name.to_lowercase();
-    let key = schedule_key(&account_name, server_number);
-    
-    // Try to load cached statistics from disk first
-    if let Some(cached_stats) = load_statistics(&state.data_dir, &account_name, server_number) {
-        return Ok(HttpResponse::Ok().json(cached_stats));
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
     }
     
-        let mut alliance_counts: HashMap<String, AllianceStats> = HashMap::new();
-        let mut time_slot_popularity: HashMap<String, TimeSlotStats> = HashMap::new();
-
-    // Separate time slot popularity maps for each day
-    let mut construction_time_slot_popularity: Option<HashMap<String, FormTimeSlotStats>> = None;
-    let mut research_time_slot_popularity: Option<HashMap<String, FormTimeSlotStats>> = None;
-    let mut troops_time_slot_popularity: Option<HashMap<String, FormTimeSlotStats>> = None;
-    let mut construction_start_time: Option<String> = None;
-    let mut research_start_time: Option<String> = None;
-    let mut troops_start_time: Option<String> = None;
-    
-    // First, try to load from form submissions CSV (this is the source of truth)
-    // First try to find current form and use its CSV, otherwise try old location for migration
-    let form_csv_path = {
-        let forms = state.forms.lock().unwrap();
-        let current_forms = state.current_forms.lock().unwrap();
-        if let Some(current_form) = get_current_form(&forms, &current_forms, &account_name, server_number) {
-            // Use new location: current_forms/{code}_submissions.csv
-            drop(current_forms);
-            format!("{}/current_forms/{}_submissions.csv", state.data_dir, current_form.code)
-        } else {
-            drop(current_forms);
-            // Fallback to old location for migration
-            format!("{}/{}_{}_form_submissions.csv", state.data_dir, account_name, server_number)
+    let schedule_data = match load_schedule(&state.data_dir, &account_name, server_number) {
+        Some(schedule_data) => schedule_data,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "No schedule has been generated yet"
+            })));
         }
     };
+    // Analyse against the submissions the schedule was generated from
+    let (form_entries, config) = load_form_entries(&state, &account_name, server_number);
+    let entries = schedule_data.entries.clone().unwrap_or(form_entries);
     
-    if Path::new(&form_csv_path).exists() {
-        // Try to get form config to use custom time slots
-        let form_config = {
-            let forms = state.forms.lock().unwrap();
-            let current_forms = state.current_forms.lock().unwrap();
-            get_current_form(&forms, &current_forms, &account_name, server_number)
-                .map(|f| f.config.clone())
-        };
-        
-        let (construction_slots, research_slots, troops_slots) = if let Some(config) = &form_config {
-            construction_start_time = Some(config.construction_times.start_time.clone());
-            research_start_time = Some(config.research_times.start_time.clone());
-            troops_start_time = Some(config.troops_times.start_time.clone());
-            (
-                Some(calculate_time_slots(&config.construction_times.start_time, config.construction_times.end_time.as_deref())),
-                Some(calculate_time_slots(&config.research_times.start_time, config.research_times.end_time.as_deref())),
-                Some(calculate_time_slots(&config.troops_times.start_time, config.troops_times.end_time.as_deref())),
-            )
-        } else {
-            (None, None, None)
-        };
-        
-        // Initialize separate time slot popularity maps if we have form config
-        if let (Some(ref cs), Some(ref rs), Some(ref ts)) = (&construction_slots, &research_slots, &troops_slots) {
-            let mut cons_map = HashMap::new();
-            for (_, time) in cs {
-                cons_map.insert(time.clone(), FormTimeSlotStats { requests: 0 });
-            }
-            construction_time_slot_popularity = Some(cons_map);
-            
-            let mut res_map = HashMap::new();
-            for (_, time) in rs {
-                res_map.insert(time.clone(), FormTimeSlotStats { requests: 0 });
-            }
-            research_time_slot_popularity = Some(res_map);
-            
-            let mut troops_map = HashMap::new();
-            for (_, time) in ts {
-                troops_map.insert(time.clone(), FormTimeSlotStats { requests: 0 });
-            }
-            troops_time_slot_popularity = Some(troops_map);
-        }
-        
-        // Create slot-to-time maps for efficient lookup
-        let construction_slot_to_time: HashMap<u8, String> = construction_slots.as_ref()
-            .map(|slots| slots.iter().map(|(s, t)| (*s, t.clone())).collect())
-            .unwrap_or_default();
-        let research_slot_to_time: HashMap<u8, String> = research_slots.as_ref()
-            .map(|slots| slots.iter().map(|(s, t)| (*s, t.clone())).collect())
-            .unwrap_or_default();
-        let troops_slot_to_time: HashMap<u8, String> = troops_slots.as_ref()
-            .map(|slots| slots.iter().map(|(s, t)| (*s, t.clone())).collect())
-            .unwrap_or_default();
-        
-        if let Ok(form_entries) = load_appointments(
-            &form_csv_path,
-            construction_slots.as_ref().map(|v| v.as_slice()),
-            research_slots.as_ref().map(|v| v.as_slice()),
-            troops_slots.as_ref().map(|v| v.as_slice()),
-        ) {
-            for entry in form_entries {
-                // Count by alliance
-                let stats = alliance_counts.entry(entry.alliance.clone()).or_insert_with(|| AllianceStats {
-                    construction_requests: 0,
-                    research_requests: 0,
-                    troops_requests: 0,
-                });
-                
-                if entry.wants_construction {
-                    stats.construction_requests += 1;
-                }
-                if entry.wants_research {
-                    stats.research_requests += 1;
-                }
-                if entry.wants_troops {
-                    stats.troops_requests += 1;
-                }
-                
-                // Count time slot popularity for construction (separate map)
-                if let Some(ref mut cons_map) = construction_time_slot_popularity {
-                    for slot in &entry.construction_available_slots {
-                        if let Some(time) = construction_slot_to_time.get(slot) {
-                            if let Some(slot_stats) = cons_map.get_mut(time) {
-                                slot_stats.requests += 1;
-                            }
-                        }
-                    }
-                }
-                
-                // Count time slot popularity for research (separate map)
-                if let Some(ref mut res_map) = research_time_slot_popularity {
-                    for slot in &entry.research_available_slots {
-                        if let Some(time) = research_slot_to_time.get(slot) {
-                            if let Some(slot_stats) = res_map.get_mut(time) {
-                                slot_stats.requests += 1;
-                            }
-                        }
-                    }
-                }
-                
-                // Count time slot popularity for troops (separate map)
-                if let Some(ref mut troops_map) = troops_time_slot_popularity {
-                    for slot in &entry.troops_available_slots {
-                        if let Some(time) = troops_slot_to_time.get(slot) {
-                            if let Some(slot_stats) = troops_map.get_mut(time) {
-                                slot_stats.requests += 1;
-                            }
-                        }
-                    }
-                }
-                
-                // Also maintain backward-compatible combined map
-                for slot in &entry.construction_available_slots {
-                    let time = if let Some(ref slots) = construction_slots {
-                        slots.iter().find(|(s, _)| *s == *slot).map(|(_, t)| t.clone()).unwrap_or_else(|| slot_to_time(*slot))
-                    } else {
-                        slot_to_time(*slot)
-                    };
-                    let slot_stats = time_slot_popularity.entry(time.clone()).or_insert_with(|| TimeSlotStats {
-                        construction_requests: 0,
-                        research_requests: 0,
-                        troops_requests: 0,
-                    });
-                    slot_stats.construction_requests += 1;
-                }
-                
-                for slot in &entry.research_available_slots {
-                    let time = if let Some(ref slots) = research_slots {
-                        slots.iter().find(|(s, _)| *s == *slot).map(|(_, t)| t.clone()).unwrap_or_else(|| slot_to_time(*slot))
-                    } else {
-                        slot_to_time(*slot)
-                    };
-                    let slot_stats = time_slot_popularity.entry(time).or_insert_with(|| TimeSlotStats {
-                        construction_requests: 0,
-                        research_requests: 0,
-                        troops_requests: 0,
-                    });
-                    slot_stats.research_requests += 1;
-                }
-                
-                for slot in &entry.troops_available_slots {
-                    let time = if let Some(ref slots) = troops_slots {
-                        slots.iter().find(|(s, _)| *s == *slot).map(|(_, t)| t.clone()).unwrap_or_else(|| slot_to_time(*slot))
-                    } else {
-                        slot_to_time(*slot)
-                    };
-                    let slot_stats = time_slot_popularity.entry(time).or_insert_with(|| TimeSlotStats {
-                        construction_requests: 0,
-                        research_requests: 0,
-                        troops_requests: 0,
-                    });
-                    slot_stats.troops_requests += 1;
-                }
-            }
-        }
-    } else {
-        // Fallback: If no form CSV exists, try to load from uploaded CSV (if exists in memory)
-        // This is for backward compatibility with old CSV uploads
-        let schedules = state.schedules.lock().unwrap();
-        if let Some(schedule_data) = schedules.get(&key) {
-            if let Some(ref entries) = schedule_data.entries {
-        for entry in entries {
-            // Count by alliance
-            let stats = alliance_counts.entry(entry.alliance.clone()).or_insert_with(|| AllianceStats {
-                construction_requests: 0,
-                research_requests: 0,
-                troops_requests: 0,
-            });
-            
-            if entry.wants_construction {
-                stats.construction_requests += 1;
-            }
-            if entry.wants_research {
-                stats.research_requests += 1;
-            }
-            if entry.wants_troops {
-                stats.troops_requests += 1;
-            }
+    let day = |day_schedule: Option<&DaySchedule>, day_str: &str| {
+        unfilled_day(day_schedule, &entries, &day_time_slots(config.as_ref(), day_str), day_str, Some(UNFILLED_SLOT_CANDIDATES))
+    };
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "construction": day(schedule_data.construction_schedule.as_ref(), "construction"),
+        "research": day(schedule_data.research_schedule.as_ref(), "research"),
+        "troops": day(schedule_data.troops_schedule.as_ref(), "troops")
+    })))
+}
 
-            // Count time slot popularity
-            for slot in &entry.construction_available_slots {
-                let time = slot_to_time(*slot);
-                let slot_stats = time_slot_popularity.entry(time.clone()).or_insert_with(|| TimeSlotStats {
-                    construction_requests: 0,
-                    research_requests: 0,
-                    troops_requests: 0,
-                });
-                slot_stats.construction_requests += 1;
+// Every player of a schedule with their time on each day, sorted by alliance and name.
+// Players who submitted but got no slot are listed too, so they can be told.
+fn schedule_itinerary(schedule_data: &ScheduleData, entries: &[AppointmentEntry], config: Option<&FormConfig>) -> Vec<PlayerItinerary> {
+    let itinerary = |player_id: &str, alliance: &str, name: &str| PlayerItinerary {
+        player_id: player_id.to_string(),
+        alliance: alliance.to_string(),
+        name: name.to_string(),
+        construction: None,
+        research: None,
+        troops: None,
+    };
+    let mut players: HashMap<String, PlayerItinerary> = entries.iter()
+        .map(|e| (e.player_id.clone(), itinerary(&e.player_id, &e.alliance, &e.name)))
+        .collect();
+    
+    let days = [
+        ("construction", schedule_data.construction_schedule.as_ref()),
+        ("research", schedule_data.research_schedule.as_ref()),
+        ("troops", schedule_data.troops_schedule.as_ref()),
+    ];
+    for (day_str, schedule) in days {
+        let Some(schedule) = schedule else { continue };
+        let time_slots = day_time_slots(config, day_str);
+        for appt in schedule.appointments.values() {
+            let time = time_slots.iter().find(|(slot, _)| *slot == appt.slot)
+                .map(|(_, time)| time.clone())
+                .unwrap_or_else(|| slot_to_time(appt.slot));
+            // Manually assigned players have no entry
+            let player = players.entry(appt.player_id.clone())
+                .or_insert_with(|| itinerary(&appt.player_id, &appt.alliance, &appt.name));
+            match day_str {
+                "construction" => player.construction = Some(time),
+                "research" => player.research = Some(time),
+                _ => player.troops = Some(time),
             }
+        }
+    }
+    
+    let mut players: Vec<PlayerItinerary> = players.into_values().collect();
+    players.sort_by(|a, b| {
+        a.alliance.to_lowercase().cmp(&b.alliance.to_lowercase())
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+            .then_with(|| a.player_id.cmp(&b.player_id))
+    });
+    players
+}
 
-            for slot in &entry.research_available_slots {
-                let time = slot_to_time(*slot);
-                let slot_stats = time_slot_popularity.entry(time).or_insert_with(|| TimeSlotStats {
-                    construction_requests: 0,
-                    research_requests: 0,
-                    troops_requests: 0,
-                });
-                slot_stats.research_requests += 1;
-            }
+// The itinerary as CSV, one row per player with an empty cell for days without a slot
+fn itinerary_csv(players: &[PlayerItinerary]) -> std::result::Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["player_id", "alliance", "name", "construction", "research", "troops"])?;
+    for player in players {
+        writer.write_record([
+            player.player_id.as_str(),
+            player.alliance.as_str(),
+            player.name.as_str(),
+            player.construction.as_deref().unwrap_or(""),
+            player.research.as_deref().unwrap_or(""),
+            player.troops.as_deref().unwrap_or(""),
+        ])?;
+    }
+    Ok(writer.into_inner()?)
+}
 
-            for slot in &entry.troops_available_slots {
-                let time = slot_to_time(*slot);
-                let slot_stats = time_slot_popularity.entry(time).or_insert_with(|| TimeSlotStats {
-                    construction_requests: 0,
-                    research_requests: 0,
-                    troops_requests: 0,
-                });
-                slot_stats.troops_requests += 1;
-            }
-                }
-            }
-        }
-        drop(schedules);
+// Each player's assignments on all three days in one record, as JSON or a CSV download.
+// Alliance sub-accounts only get their own alliance's players.
+async fn get_schedule_itinerary(
+    path: web::Path<(String, u32)>,
+    query: web::Query<ItineraryQuery>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
     }
     
-    // Build final response
-    let stats_response = StatsResponse {
-        alliance_counts: alliance_counts.clone(),
-        time_slot_popularity: if time_slot_popularity.is_empty() { None } else { Some(time_slot_popularity.clone()) },
-        construction_start_time,
-        research_start_time,
-        troops_start_time,
-        construction_time_slot_popularity,
-        research_time_slot_popularity,
-        troops_time_slot_popularity,
+    let Some(schedule_data) = load_schedule(&state.data_dir, &account_name, server_number) else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "No schedule has been generated yet"
+        })));
     };
-    
-    // Save statistics to disk
-    if let Err(e) = save_statistics(&state.data_dir, &account_name, server_number, &stats_response) {
-        eprintln!("Warning: Failed to save statistics to disk: {}", e);
+    let (form_entries, config) = load_form_entries(&state, &account_name, server_number);
+    let entries = schedule_data.entries.clone().unwrap_or(form_entries);
+    let mut players = schedule_itinerary(&schedule_data, &entries, config.as_ref());
+    if let Some(scope) = session_alliance_scope(&session) {
+        players.retain(|p| p.alliance.eq_ignore_ascii_case(&scope));
     }
     
-    Ok(HttpResponse::Ok().json(stats_response))
+    match query.format.as_deref().unwrap_or("json") {
+        "json" => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "players": players
+        }))),
+        "csv" => {
+            let csv_content = itinerary_csv(&players).map_err(|e| {
+                actix_web::error::ErrorInternalServerError(format!("Failed to write CSV: {}", e))
+            })?;
+            let filename = format!("{}_{}_itinerary_{}.csv", account_name, server_number,
+                chrono::Utc::now().format("%Y%m%d_%H%M%S"));
+            Ok(HttpResponse::Ok()
+                .content_type("text/csv")
+                .append_header(("Content-Disposition", format!("attachment; filename=\"{}\"", filename)))
+                .body(csv_content))
+        }
+        _ => Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "Unknown format, use json or csv"
+        }))),
     }
+}
 
+// One tab per day for Google Sheets: a row for every time slot, empty where nobody is assigned
+fn schedule_sheet_tabs(schedule_data: &ScheduleData, config: Option<&FormConfig>) -> Vec<SheetTab> {
+    let days = [
+        ("Construction Day", "construction", schedule_data.construction_schedule.as_ref()),
+        ("Research Day", "research", schedule_data.research_schedule.as_ref()),
+        ("Troops Training Day", "troops", schedule_data.troops_schedule.as_ref()),
+    ];
+    days.into_iter().map(|(title, day_str, schedule)| {
+        let header = ["Time", "Alliance", "Player", "Player ID"].map(String::from).to_vec();
+        let rows = day_time_slots(config, day_str).into_iter().map(|(slot, time)| {
+            match schedule.and_then(|s| s.appointments.values().find(|a| a.slot == slot)) {
+                Some(appt) => vec![time, appt.alliance.clone(), appt.name.clone(), appt.player_id.clone()],
+                None => vec![time, String::new(), String::new(), String::new()],
+            }
+        });
+        SheetTab { title: title.to_string(), rows: std::iter::once(header).chain(rows).collect() }
+    }).collect()
+}
 
-// Schedule endpoint
-async fn get_schedule(
-    path: web::Path<(String, u32, String)>,
+// Whether publishing to Google Sheets is set up, the service account to share sheets with and
+// the spreadsheet of this server
+async fn get_google_sheets(
+    path: web::Path<(String, u32)>,
+    session: Session,
     state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
-    let (account_name, server_number, day_str) = path.into_inner();
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
     let account_name = account_name.to_lowercase();
-    let key = schedule_key(&account_name, server_number);
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
     
-    // Try to load from disk first
-    if let Some(schedule_data) = load_schedule(&state.data_dir, &account_name, server_number) {
-        // Also update in-memory cache
-        let mut schedules = state.schedules.lock().unwrap();
-        schedules.insert(key.clone(), schedule_data.clone());
-        drop(schedules);
-        
-        // Get form config for custom time slots
-        let form_config = {
-            let forms = state.forms.lock().unwrap();
-            let current_forms = state.current_forms.lock().unwrap();
-            get_current_form(&forms, &current_forms, &account_name, server_number)
-                .map(|f| f.config.clone())
-        };
-        
-        // Get the requested day's schedule
-        let schedule = match day_str.as_str() {
-            "construction" => schedule_data.construction_schedule.clone(),
-            "research" => schedule_data.research_schedule.clone(),
-            "troops" => schedule_data.troops_schedule.clone(),
-        _ => return Ok(HttpResponse::BadRequest().json(serde_json::json!({"error": "Invalid day"}))),
-    };
-
-        if let Some(schedule) = schedule {
-            // Generate time slots based on form config or use fixed mapping
-            let time_slots: Vec<(u8, String)> = match (day_str.as_str(), form_config.as_ref()) {
-                ("construction", Some(config)) => {
-                    calculate_time_slots(&config.construction_times.start_time, config.construction_times.end_time.as_deref())
-                },
-                ("research", Some(config)) => {
-                    calculate_time_slots(&config.research_times.start_time, config.research_times.end_time.as_deref())
-                },
-                ("troops", Some(config)) => {
-                    calculate_time_slots(&config.troops_times.start_time, config.troops_times.end_time.as_deref())
-                },
-                _ => {
-                    // Fallback to fixed mapping
-                    (1..=49).map(|slot| (slot, slot_to_time(slot))).collect()
-                }
-            };
-            
-            // Build response with appointments
-            let mut appointments = Vec::new();
-            for (slot, time) in time_slots {
-                if let Some(appt) = schedule.appointments.get(&slot) {
-                    appointments.push(ScheduleSlot {
-                        time,
-                        player: Some(format_player_name(&appt.alliance, &appt.name)),
-                        is_empty: false,
-                    });
-                } else {
-                    appointments.push(ScheduleSlot {
-                        time,
-                        player: None,
-                        is_empty: true,
-                    });
-                }
-            }
-            
-            let day_name = match day_str.as_str() {
-            "construction" => "Construction Day",
-            "research" => "Research Day",
-            "troops" => "Troops Training Day",
-                _ => "Unknown Day",
-        };
+    let spreadsheet_id = state.accounts.lock().unwrap().get(&account_name)
+        .and_then(|a| a.google_sheets.get(&server_number).cloned());
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "enabled": state.sheets.is_some(),
+        "service_account": state.sheets.as_ref().map(|c| c.client_email()),
+        "spreadsheet_id": spreadsheet_id
+    })))
+}
 
-            return Ok(HttpResponse::Ok().json(ScheduleResponse {
-                day_name: day_name.to_string(),
-                appointments,
-            }));
+// Set or remove the spreadsheet a server's schedule is published to
+async fn update_google_sheets(
+    path: web::Path<(String, u32)>,
+    req: web::Json<GoogleSheetsRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let spreadsheet_id = if req.spreadsheet.trim().is_empty() {
+        None
+    } else {
+        match parse_spreadsheet_id(&req.spreadsheet) {
+            Some(id) => Some(id),
+            None => {
+                return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                    "success": false,
+                    "error": "Not a Google Sheets URL or spreadsheet ID"
+                })));
+            }
         }
-    }
-    
-    // If not found on disk, get form config for this account/server to get custom time slots
-    let form_config = {
-        let forms = state.forms.lock().unwrap();
-        let current_forms = state.current_forms.lock().unwrap();
-        get_current_form(&forms, &current_forms, &account_name, server_number)
-            .map(|f| f.config.clone())
     };
     
-    // Generate time slots based on form config or use fixed mapping
-    let time_slots: Vec<(u8, String)> = match (day_str.as_str(), form_config.as_ref()) {
-        ("construction", Some(config)) => {
-            calculate_time_slots(&config.construction_times.start_time, config.construction_times.end_time.as_deref())
-        },
-        ("research", Some(config)) => {
-            calculate_time_slots(&config.research_times.start_time, config.research_times.end_time.as_deref())
-        },
-        ("troops", Some(config)) => {
-            calculate_time_slots(&config.troops_times.start_time, config.troops_times.end_time.as_deref())
-        },
-        _ => {
-            // Fallback to fixed mapping (backward compatibility for uploaded CSVs)
-            (1..=49).map(|slot| (slot, slot_to_time(slot))).collect()
+    let mut accounts = state.accounts.lock().unwrap();
+    let account = match accounts.get_mut(&account_name) {
+        Some(account) => account,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Account not found"
+            })));
         }
     };
+    match &spreadsheet_id {
+        Some(id) => account.google_sheets.insert(server_number, id.clone()),
+        None => account.google_sheets.remove(&server_number),
+    };
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
     
-    let day_name = match day_str.as_str() {
-        "construction" => "Construction Day",
-        "research" => "Research Day",
-        "troops" => "Troops Training Day",
-        _ => return Ok(HttpResponse::BadRequest().json(serde_json::json!({"error": "Invalid day"}))),
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "spreadsheet_id": spreadsheet_id
+    })))
+}
+
+// Write the current schedule to the server's spreadsheet, one tab per day. Other tabs of the
+// spreadsheet are left alone. Runs as a job since it takes several requests to Google.
+async fn publish_google_sheets(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let Some(client) = state.sheets.clone() else {
+        return Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "success": false,
+            "error": "Google Sheets is not set up on this server"
+        })));
+    };
+    let spreadsheet_id = state.accounts.lock().unwrap().get(&account_name)
+        .and_then(|a| a.google_sheets.get(&server_number).cloned());
+    let Some(spreadsheet_id) = spreadsheet_id else {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "Choose a spreadsheet first"
+        })));
+    };
+    let schedule_data = state.schedules.lock().unwrap().get(&schedule_key(&account_name, server_number)).cloned()
+        .or_else(|| load_schedule(&state.data_dir, &account_name, server_number));
+    let Some(schedule_data) = schedule_data else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "No schedule has been generated yet"
+        })));
+    };
+    let (_, config) = load_form_entries(&state, &account_name, server_number);
+    let tabs = schedule_sheet_tabs(&schedule_data, config.as_ref());
+    
+    let job = state.jobs.enqueue("export_sheets", &account_name, server_number, serde_json::Value::Null, move |progress| {
+        progress.set(10, "Writing to Google Sheets");
+        client.write_tabs(&spreadsheet_id, &tabs)?;
+        Ok(serde_json::json!({
+            "url": format!("https://docs.google.com/spreadsheets/d/{}/edit", spreadsheet_id)
+        }))
+    });
+    Ok(HttpResponse::Accepted().json(serde_json::json!({
+        "success": true,
+        "job": job
+    })))
+}
+
+// Bounds and default of the Google Form sync interval
+const FORM_SYNC_MIN_INTERVAL: u32 = 5;
+const FORM_SYNC_MAX_INTERVAL: u32 = 24 * 60;
+const FORM_SYNC_DEFAULT_INTERVAL: u32 = 15;
+const FORM_SYNC_DEFAULT_SHEET: &str = "Form Responses 1";
+
+// Converts the rows of a Google Form response sheet (header row first) to submission CSV rows.
+// Columns are found by question text like in an uploaded CSV. Timestamps are taken from `dates`
+// (see SheetsClient::read_first_column_dates) where the cell is a date, in the format the
+// dashboard form writes.
+fn response_rows_to_submissions(rows: &[Vec<String>], dates: &[Option<chrono::NaiveDateTime>]) -> Vec<Vec<String>> {
+    let Some((header, responses)) = rows.split_first() else { return Vec::new() };
+    let headers = csv::StringRecord::from(header.clone());
+    let columns = SubmissionColumns::find(&headers);
+    let notes = header.iter().position(|h| h.contains("additional notes"));
+    let suggestions = header.iter().position(|h| h.contains("suggestions"));
+    
+    responses.iter().enumerate()
+        .filter(|(_, row)| row.iter().any(|cell| !cell.trim().is_empty()))
+        .map(|(i, row)| {
+            let cell = |col: Option<usize>| col.and_then(|c| row.get(c)).map(|v| v.trim().to_string()).unwrap_or_default();
+            let timestamp = match dates.get(i + 1) {
+                Some(Some(date)) => date.format("%d/%m/%Y %H.%M.%S").to_string(),
+                _ => cell(Some(0)),
+            };
+            let mut record = Vec::with_capacity(SUBMISSION_CSV_COLUMNS);
+            record.push(timestamp);
+            record.extend(columns.used().map(|col| cell(Some(col))));
+            record.push(cell(notes));
+            record.push(cell(suggestions));
+            record
+        })
+        .collect()
+}
+
+// (timestamp, player ID) of every row of a submissions CSV, to recognize responses already imported
+fn submission_keys(csv_path: &str) -> HashSet<(String, String)> {
+    let mut reader = match csv::ReaderBuilder::new().flexible(true).from_path(csv_path) {
+        Ok(reader) => reader,
+        Err(_) => return HashSet::new(),
     };
+    let id_col = reader.headers()
+        .ok()
+        .and_then(|h| h.iter().position(|h| h.contains("player ID")))
+        .unwrap_or(4);
+    reader.records()
+        .filter_map(|r| r.ok())
+        .map(|record| (
+            record.get(0).unwrap_or("").trim().to_string(),
+            record.get(id_col).unwrap_or("").trim().to_string(),
+        ))
+        .collect()
+}
+
+// Players with a submission who aren't in the saved schedule, so the next append places them;
+// everyone when there is no schedule yet
+fn players_ready_for_append(state: &AppState, account_name: &str, server_number: u32) -> usize {
+    let (entries, _) = load_form_entries(state, account_name, server_number);
+    match load_schedule(&state.data_dir, account_name, server_number) {
+        Some(schedule_data) => {
+            let scheduled = get_scheduled_player_ids(&schedule_data);
+            entries.iter().filter(|e| !scheduled.contains(&e.player_id)).count()
+        }
+        None => entries.len(),
+    }
+}
+
+// Imports the responses of a server's Google Form sheet that aren't in its submissions yet and
+// records the outcome on the account. Returns how many responses were new.
+fn sync_form_responses(state: &AppState, client: &SheetsClient, account_name: &str, server_number: u32, sync: &FormSync) -> std::result::Result<usize, String> {
+    let result = (|| {
+        let rows = client.read_rows(&sync.spreadsheet_id, &sync.sheet)?;
+        let dates = client.read_first_column_dates(&sync.spreadsheet_id, &sync.sheet)?;
+        let (csv_path, _) = current_submissions_csv(state, account_name, server_number);
+        let mut known = submission_keys(&csv_path);
+        let new_rows: Vec<Vec<String>> = response_rows_to_submissions(&rows, &dates).into_iter()
+            .filter(|row| known.insert((row[0].clone(), row[4].clone())))
+            .collect();
+        if !new_rows.is_empty() {
+            append_submission_rows(Path::new(&csv_path), &new_rows)
+                .map_err(|e| format!("Failed to save the responses: {}", e))?;
+        }
+        Ok(new_rows.len())
+    })();
     
-    // Check if schedule exists in memory
-    let schedule_opt = {
-        let schedules = state.schedules.lock().unwrap();
-        if let Some(schedule_data) = schedules.get(&key) {
-            match day_str.as_str() {
-                "construction" => schedule_data.construction_schedule.as_ref().cloned(),
-                "research" => schedule_data.research_schedule.as_ref().cloned(),
-                "troops" => schedule_data.troops_schedule.as_ref().cloned(),
-                _ => None,
-            }
-        } else {
-            None
+    let mut accounts = state.accounts.lock().unwrap();
+    if let Some(recorded) = accounts.get_mut(account_name).and_then(|a| a.form_syncs.get_mut(&server_number)) {
+        recorded.last_synced_at = Some(chrono::Utc::now().to_rfc3339());
+        recorded.last_error = result.as_ref().err().cloned();
+        recorded.last_imported = *result.as_ref().unwrap_or(&0);
+        if let Err(e) = save_accounts(&state.data_dir, &accounts) {
+            log::warn!("Failed to save the form sync of {} #{}: {}", account_name, server_number, e);
         }
-    };
+    }
+    result
+}
+
+// Queues a sync of a server's Google Form responses, None if Sheets access or the sync isn't set up
+fn enqueue_form_sync(state: &web::Data<AppState>, account_name: &str, server_number: u32) -> Option<crate::jobs::Job> {
+    let client = state.sheets.clone()?;
+    let sync = state.accounts.lock().unwrap().get(account_name)
+        .and_then(|a| a.form_syncs.get(&server_number).cloned())?;
+    let job_state = state.clone();
+    let job_account = account_name.to_string();
+    Some(state.jobs.enqueue("sync_form", account_name, server_number, serde_json::Value::Null, move |progress| {
+        progress.set(10, "Reading the response sheet");
+        let imported = sync_form_responses(&job_state, &client, &job_account, server_number, &sync)?;
+        if imported > 0 {
+            notify(&job_state, &job_account, Notification {
+                event: NotificationEvent::NewSubmission,
+                title: format!("New submissions on server {}", server_number),
+                body: match imported {
+                    1 => "1 response was imported from the Google Form.".to_string(),
+                    n => format!("{} responses were imported from the Google Form.", n),
+                },
+                email: None,
+            });
+        }
+        Ok(serde_json::json!({
+            "imported": imported,
+            "ready_for_append": players_ready_for_append(&job_state, &job_account, server_number)
+        }))
+    }))
+}
+
+// How often Google Form syncs are checked for being due
+const FORM_SYNC_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+// Background task syncing every Google Form response sheet whose interval has passed
+async fn run_form_sync(state: web::Data<AppState>) {
+    if state.sheets.is_none() {
+        return;
+    }
+    let mut interval = tokio::time::interval(FORM_SYNC_CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        let now = chrono::Utc::now();
+        let due: Vec<(String, u32)> = state.accounts.lock().unwrap().values()
+            .filter(|a| !a.suspended)
+            .flat_map(|a| a.form_syncs.iter().map(move |(server, sync)| (a, *server, sync)))
+            .filter(|(_, _, sync)| {
+                sync.last_synced_at.as_deref()
+                    .and_then(|at| chrono::DateTime::parse_from_rfc3339(at).ok())
+                    .is_none_or(|at| now.signed_duration_since(at) >= chrono::Duration::minutes(sync.interval_minutes as i64))
+            })
+            .map(|(a, server, _)| (a.account_name.clone(), server))
+            .collect();
+        for (account_name, server_number) in due {
+            enqueue_form_sync(&state, &account_name, server_number);
+        }
+    }
+}
+
+// The Google Form response sheet synced into a server's submissions, with its last outcome
+async fn get_form_sync(
+    path: web::Path<(String, u32)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
     
-    // If schedule doesn't exist, try to regenerate from form submissions CSV
-    let schedule = if let Some(s) = schedule_opt {
-        s
+    let sync = state.accounts.lock().unwrap().get(&account_name)
+        .and_then(|a| a.form_syncs.get(&server_number).cloned());
+    let ready_for_append = sync.as_ref().map(|_| players_ready_for_append(&state, &account_name, server_number));
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "enabled": state.sheets.is_some(),
+        "service_account": state.sheets.as_ref().map(|c| c.client_email()),
+        "sync": sync,
+        "ready_for_append": ready_for_append
+    })))
+}
+
+// Set or stop the sync of a server's Google Form response sheet. Changing the sheet syncs it
+// on the next check.
+async fn update_form_sync(
+    path: web::Path<(String, u32)>,
+    req: web::Json<FormSyncRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let sync = if req.spreadsheet.trim().is_empty() {
+        None
     } else {
-        // Try to load from form submissions CSV and regenerate schedules
-        // First try to find current form and use its CSV, otherwise try old location for migration
-        let form_csv_path = {
-            let forms = state.forms.lock().unwrap();
-            let current_forms = state.current_forms.lock().unwrap();
-            if let Some(current_form) = get_current_form(&forms, &current_forms, &account_name, server_number) {
-                // Use new location: current_forms/{code}_submissions.csv
-                drop(current_forms);
-                format!("{}/current_forms/{}_submissions.csv", state.data_dir, current_form.code)
-            } else {
-                drop(current_forms);
-                // Fallback to old location for migration
-                format!("{}/{}_{}_form_submissions.csv", state.data_dir, account_name, server_number)
-            }
+        let Some(spreadsheet_id) = parse_spreadsheet_id(&req.spreadsheet) else {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": "Not a Google Sheets URL or spreadsheet ID"
+            })));
         };
-        
-        if Path::new(&form_csv_path).exists() {
-            let config_for_loading = form_config.clone();
-            let (construction_slots, research_slots, troops_slots) = if let Some(config) = &config_for_loading {
-                (
-                    Some(calculate_time_slots(&config.construction_times.start_time, config.construction_times.end_time.as_deref())),
-                    Some(calculate_time_slots(&config.research_times.start_time, config.research_times.end_time.as_deref())),
-                    Some(calculate_time_slots(&config.troops_times.start_time, config.troops_times.end_time.as_deref())),
-                )
-            } else {
-                (None, None, None)
-            };
-            
-            if let Ok(entries) = load_appointments(
-                &form_csv_path,
-                construction_slots.as_ref().map(|v| v.as_slice()),
-                research_slots.as_ref().map(|v| v.as_slice()),
-                troops_slots.as_ref().map(|v| v.as_slice()),
-            ) {
-                // Generate schedules (pass last_slot from form config when available)
-                let last_slot_override = construction_slots.as_ref()
-                    .and_then(|slots| slots.iter().map(|(s, _)| *s).max());
-                let construction_schedule = schedule_construction_day_with_locked(
-                    &entries,
-                    &HashSet::new(),
-                    last_slot_override,
-                );
-                let research_schedule = schedule_research_day(&entries, &construction_schedule);
-                let troops_schedule = schedule_troops_day(&entries);
-                
-                // Create schedule data
-                let scheduled_ids: Vec<String> = {
-                    let mut ids = HashSet::new();
-                    for appt in construction_schedule.appointments.values() {
-                        ids.insert(appt.player_id.clone());
-                    }
-                    for appt in research_schedule.appointments.values() {
-                        ids.insert(appt.player_id.clone());
-                    }
-                    for appt in troops_schedule.appointments.values() {
-                        ids.insert(appt.player_id.clone());
-                    }
-                    ids.into_iter().collect()
-                };
-                let schedule_data = ScheduleData {
-                    construction_schedule: Some(construction_schedule.clone()),
-                    research_schedule: Some(research_schedule.clone()),
-                    troops_schedule: Some(troops_schedule.clone()),
-                    entries: Some(entries.clone()),
-                    scheduled_player_ids: Some(scheduled_ids),
-                };
-                
-                // Save to state
-                let mut schedules = state.schedules.lock().unwrap();
-                schedules.insert(key.clone(), schedule_data.clone());
-                drop(schedules);
-                
-                // Save to disk
-                if let Err(e) = save_schedule(&state.data_dir, &account_name, server_number, &schedule_data) {
-                    eprintln!("Warning: Failed to save schedule to disk: {}", e);
-                }
-                
-                // Return the appropriate schedule
-                match day_str.as_str() {
-                    "construction" => construction_schedule,
-                    "research" => research_schedule,
-                    "troops" => troops_schedule,
-                    _ => return Ok(HttpResponse::BadRequest().json(serde_json::json!({"error": "Invalid day"}))),
-                }
-            } else {
-                // No form submissions or error loading, return empty schedule
-                DaySchedule {
-                    appointments: HashMap::new(),
-                    unassigned: Vec::new(),
-                }
-            }
-        } else {
-            // No form submissions CSV, return empty schedule
-            DaySchedule {
-                appointments: HashMap::new(),
-                unassigned: Vec::new(),
-            }
+        let sheet = req.sheet.as_deref().map(str::trim).filter(|s| !s.is_empty()).unwrap_or(FORM_SYNC_DEFAULT_SHEET);
+        if sheet.chars().count() > 100 {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": "Sheet name is too long"
+            })));
+        }
+        let interval_minutes = req.interval_minutes.unwrap_or(FORM_SYNC_DEFAULT_INTERVAL);
+        if !(FORM_SYNC_MIN_INTERVAL..=FORM_SYNC_MAX_INTERVAL).contains(&interval_minutes) {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": format!("The interval must be {} to {} minutes", FORM_SYNC_MIN_INTERVAL, FORM_SYNC_MAX_INTERVAL)
+            })));
         }
+        Some(FormSync {
+            spreadsheet_id,
+            sheet: sheet.to_string(),
+            interval_minutes,
+            last_synced_at: None,
+            last_error: None,
+            last_imported: 0,
+        })
     };
     
-    // Build response with appointments
-        let mut appointments = Vec::new();
-    for (slot, time) in time_slots {
-            if let Some(appt) = schedule.appointments.get(&slot) {
-                let formatted_name = format_player_name(&appt.alliance, &appt.name);
-                appointments.push(ScheduleSlot {
-                    time,
-                    player: Some(formatted_name),
-                    is_empty: false,
-                });
-            } else {
-                appointments.push(ScheduleSlot {
-                    time,
-                    player: None,
-                    is_empty: true,
-                });
+    let mut accounts = state.accounts.lock().unwrap();
+    let account = match accounts.get_mut(&account_name) {
+        Some(account) => account,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Account not found"
+            })));
+        }
+    };
+    match &sync {
+        Some(sync) => {
+            // Keep the last outcome when only the interval changes
+            let previous = account.form_syncs.insert(server_number, sync.clone());
+            if let Some(previous) = previous.filter(|p| p.spreadsheet_id == sync.spreadsheet_id && p.sheet == sync.sheet) {
+                let current = account.form_syncs.get_mut(&server_number).unwrap();
+                current.last_synced_at = previous.last_synced_at;
+                current.last_error = previous.last_error;
+                current.last_imported = previous.last_imported;
             }
         }
-
-        Ok(HttpResponse::Ok().json(ScheduleResponse {
-            day_name: day_name.to_string(),
-            appointments,
-        }))
-}
-
-// HTML page handlers - account creation page
-async fn create_account_page() -> Result<HttpResponse> {
-    let html = include_str!("../templates/create_account.html");
-    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+        None => {
+            account.form_syncs.remove(&server_number);
+        }
+    }
+    let saved = account.form_syncs.get(&server_number).cloned();
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "sync": saved
+    })))
 }
 
-// HTML page handlers - schedules page
-async fn schedules_page(
+// Sync a server's Google Form responses now instead of waiting for the interval
+async fn run_form_sync_now(
     path: web::Path<(String, u32)>,
+    session: Session,
     state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
-    let (account_name, _server_number) = path.into_inner();
-    
-    // Verify account exists
-    let accounts = state.accounts.lock().unwrap();
-    let account_name_lower = account_name.to_lowercase();
-    if !accounts.contains_key(&account_name_lower) {
-        return Ok(HttpResponse::NotFound().body("Account not found"));
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    if state.sheets.is_none() {
+        return Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "success": false,
+            "error": "Google Sheets is not set up on this server"
+        })));
     }
-    drop(accounts);
     
-    let html = include_str!("../templates/schedules.html");
-    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+    match enqueue_form_sync(&state, &account_name, server_number) {
+        Some(job) => Ok(HttpResponse::Accepted().json(serde_json::json!({
+            "success": true,
+            "job": job
+        }))),
+        None => Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "Choose the response sheet first"
+        }))),
+    }
 }
 
-// HTML page handlers - stats page
-async fn stats_page(
-    path: web::Path<(String, u32)>,
-    state: web::Data<AppState>,
-) -> Result<HttpResponse> {
-    let (account_name, _server_number) = path.into_inner();
-    
-    // Verify account exists
-    let accounts = state.accounts.lock().unwrap();
-    let account_name_lower = account_name.to_lowercase();
-    if !accounts.contains_key(&account_name_lower) {
-        return Ok(HttpResponse::NotFound().body("Account not found"));
+// Sends a notification over the account's channel if it wants the event (see Notifier::dispatch)
+fn notify(state: &AppState, account_name: &str, notification: Notification) {
+    let settings = match state.accounts.lock().unwrap().get(account_name) {
+        Some(account) if !account.suspended => account.notifications.clone(),
+        _ => return,
+    };
+    state.notifier.dispatch(account_name, &settings, notification);
+}
+
+fn notify_schedule_generated(state: &AppState, account_name: &str, server_number: u32, message: &str) {
+    let title = format!("Schedule generated on server {}", server_number);
+    let emailed = state.accounts.lock().unwrap().get(account_name)
+        .is_some_and(|a| a.notifications.channel == NotificationChannel::Email);
+    let email = state.mailer.as_ref().filter(|_| emailed).and_then(|mailer| {
+        let schedule = load_schedule(&state.data_dir, account_name, server_number)?;
+        let config = {
+            let forms = state.forms.lock().unwrap();
+            let current_forms = state.current_forms.lock().unwrap();
+            get_current_form(&forms, &current_forms, account_name, server_number).map(|f| f.config.clone())
+        };
+        let days = [
+            ("construction", &schedule.construction_schedule),
+            ("research", &schedule.research_schedule),
+            ("troops", &schedule.troops_schedule),
+        ].into_iter().filter_map(|(day, day_schedule)| {
+            let appointments = &day_schedule.as_ref()?.appointments;
+            let slots = day_time_slots(config.as_ref(), day).into_iter()
+                .filter_map(|(slot, time)| {
+                    let appt = appointments.get(&slot)?;
+                    Some(AssignedSlot { time, player: format_player_name(&appt.alliance, &appt.name) })
+                })
+                .collect();
+            Some(AssignedDay { name: day_display_name(day), slots })
+        }).collect();
+        mailer::render(title.clone(), &SlotAssignmentsEmail {
+            server_number,
+            message,
+            days,
+            schedule_url: mailer.link(&format!("/view/{}/{}", account_name, server_number)),
+        }).map_err(|e| log::warn!("{}", e)).ok()
+    });
+    notify(state, account_name, Notification {
+        event: NotificationEvent::ScheduleGenerated,
+        title,
+        body: message.to_string(),
+        email,
+    });
+}
+
+fn notify_slot_changed(state: &AppState, account_name: &str, server_number: u32, change: String) {
+    notify(state, account_name, Notification {
+        event: NotificationEvent::SlotChanged,
+        title: format!("Slot changed on server {}", server_number),
+        body: change,
+        email: None,
+    });
+}
+
+// Day name as shown in notifications
+fn day_display_name(day: &str) -> &'static str {
+    match day {
+        "construction" => "Construction Day",
+        "research" => "Research Day",
+        "troops" => "Troops Training Day",
+        _ => "Unknown Day",
     }
-    drop(accounts);
-    
-    let html = include_str!("../templates/stats.html");
-    Ok(HttpResponse::Ok().content_type("text/html").body(html))
 }
 
-// HTML page handlers - admin page
-async fn admin_page(
+// How long before a form closes the "closing soon" notification goes out
+const CLOSING_NOTICE_BEFORE: chrono::Duration = chrono::Duration::hours(1);
+
+// Background task announcing forms that close within CLOSING_NOTICE_BEFORE, once per close time.
+// What was announced is kept in memory, so a restart in the last hour announces a form again.
+async fn run_closing_notices(state: web::Data<AppState>) {
+    let mut interval = tokio::time::interval(AUTO_GENERATE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let now = chrono::Utc::now();
+        let closing: Vec<(String, FormData, chrono::DateTime<chrono::Utc>)> = {
+            let forms = state.forms.lock().unwrap();
+            let current_forms = state.current_forms.lock().unwrap();
+            current_forms.values()
+                .filter_map(|code| forms.get(code))
+                .filter_map(|f| {
+                    let closes_at = chrono::DateTime::parse_from_rfc3339(f.config.closes_at.as_deref()?).ok()?.with_timezone(&chrono::Utc);
+                    (closes_at > now && closes_at - now <= CLOSING_NOTICE_BEFORE)
+                        .then(|| (format!("{}@{}", f.code, closes_at.to_rfc3339()), f.clone(), closes_at))
+                })
+                .collect()
+        };
+        for (key, form, closes_at) in closing {
+            // Only marked once sent, so turning the event on later still announces the form
+            let wanted = state.accounts.lock().unwrap().get(&form.account_name)
+                .is_some_and(|a| a.notifications.wants(NotificationEvent::FormClosingSoon));
+            if !wanted || !state.closing_notices.lock().unwrap().insert(key) {
+                continue;
+            }
+            let csv_path = format!("{}/current_forms/{}_submissions.csv", state.data_dir, form.code);
+            let minutes = (closes_at - now).num_minutes().max(1);
+            let title = format!("Form closing soon on server {}", form.server_number);
+            let body = format!(
+                "{} closes at {} UTC, in {} minutes. It has {} submissions so far.",
+                form.name,
+                closes_at.format("%H:%M"),
+                minutes,
+                count_submissions(Path::new(&csv_path))
+            );
+            let email = state.mailer.as_ref().and_then(|mailer| {
+                mailer::render(title.clone(), &ReminderEmail {
+                    title: &title,
+                    details: &body,
+                    link: mailer.link(&format!("/form/{}", form.code)),
+                }).map_err(|e| log::warn!("{}", e)).ok()
+            });
+            notify(&state, &form.account_name, Notification {
+                event: NotificationEvent::FormClosingSoon,
+                title,
+                body,
+                email,
+            });
+        }
+    }
+}
+
+// The account's notification channel and the events sent over it
+async fn get_notification_settings(
     path: web::Path<(String, u32)>,
+    session: Session,
     state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
-    let (account_name, _server_number) = path.into_inner();
-    
-    // Verify account exists
-    let accounts = state.accounts.lock().unwrap();
-    let account_name_lower = account_name.to_lowercase();
-    if !accounts.contains_key(&account_name_lower) {
-        return Ok(HttpResponse::NotFound().body("Account not found"));
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
     }
-    drop(accounts);
     
-    let html = include_str!("../templates/admin.html");
-    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+    let settings = state.accounts.lock().unwrap().get(&account_name).map(|a| a.notifications.clone()).unwrap_or_default();
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "settings": settings,
+        "email_available": state.mailer.is_some()
+    })))
 }
 
-// Public form page - accessible via /form/{code}
-async fn public_form_page(
-    path: web::Path<String>,
+// Replace the account's notification settings. Blank addresses are cleared.
+async fn update_notification_settings(
+    path: web::Path<(String, u32)>,
+    req: web::Json<NotificationSettings>,
+    session: Session,
     state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
-    let code = path.into_inner();
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
     
-    // Verify form exists
-    let forms = state.forms.lock().unwrap();
-    if !forms.contains_key(&code) {
-        drop(forms);
-        return Ok(HttpResponse::NotFound().body("Form not found"));
+    let mut settings = req.into_inner();
+    let blank_to_none = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    settings.discord_webhook = blank_to_none(settings.discord_webhook);
+    settings.email = blank_to_none(settings.email);
+    if let Err(e) = settings.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": e
+        })));
+    }
+    if settings.channel == NotificationChannel::Email && state.mailer.is_none() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "Email isn't set up on this server"
+        })));
     }
-    drop(forms);
     
-    let html = include_str!("../templates/form.html");
-    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+    let mut accounts = state.accounts.lock().unwrap();
+    let account = match accounts.get_mut(&account_name) {
+        Some(account) => account,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Account not found"
+            })));
+        }
+    };
+    account.notifications = settings.clone();
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "settings": settings
+    })))
 }
 
-// Form submission endpoint (by form code)
-async fn submit_form_by_code(
-    path: web::Path<String>,
-    req: web::Json<FormSubmissionRequest>,
+// Send a test message over the saved channel right away, reporting whether it was delivered
+async fn send_test_notification(
+    path: web::Path<(String, u32)>,
+    session: Session,
     state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
-    let code = path.into_inner();
-    
-    // Verify form exists and get config
-    let forms = state.forms.lock().unwrap();
-    let form_data = forms.get(&code).cloned();
-    drop(forms);
-    
-    let config = if let Some(fd) = form_data {
-        fd.config
-    } else {
-        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
             "success": false,
-            "error": "Form not found"
+            "error": "Not authorized"
         })));
-    };
+    }
     
-    // Validate submission
-    if let Err(err) = validate_submission(&req) {
+    let settings = state.accounts.lock().unwrap().get(&account_name).map(|a| a.notifications.clone()).unwrap_or_default();
+    if settings.channel == NotificationChannel::None {
         return Ok(HttpResponse::BadRequest().json(serde_json::json!({
             "success": false,
-            "error": err
+            "error": "Choose a notification channel first"
         })));
     }
-    
-    // Create form submission with timestamp (format: DD/MM/YYYY HH.MM.SS)
-    let timestamp = chrono::Local::now().format("%d/%m/%Y %H.%M.%S").to_string();
-    let submission = FormSubmission {
-        timestamp,
-        alliance: req.alliance.clone(),
-        custom_alliance: req.custom_alliance.clone(),
-        character_name: req.character_name.clone(),
-        player_id: req.player_id.clone(),
-        submission_type: req.submission_type.clone(),
-        wants_construction: req.wants_construction,
-        construction_speedups: req.construction_speedups,
-        construction_truegold: req.construction_truegold,
-        construction_time_slots: req.construction_time_slots.clone(),
-        wants_research: req.wants_research,
-        research_speedups: req.research_speedups,
-        research_truegold_dust: req.research_truegold_dust,
-        research_time_slots: req.research_time_slots.clone(),
-        wants_troops: req.wants_troops,
-        troops_speedups: req.troops_speedups,
-        troops_time_slots: req.troops_time_slots.clone(),
-        additional_notes: req.additional_notes.clone(),
-        suggestions: req.suggestions.clone(),
+    let notifier = state.notifier.clone();
+    let notification = Notification {
+        event: NotificationEvent::ScheduleGenerated,
+        title: "Test notification".to_string(),
+        body: format!("Notifications of {} #{} arrive here.", account_name, server_number),
+        email: None,
     };
-    
-    // Export to CSV (save in current_forms folder with form code)
-    let current_forms_dir = format!("{}/current_forms", state.data_dir);
-    std::fs::create_dir_all(&current_forms_dir)?;
-    let csv_path = format!("{}/{}_submissions.csv", current_forms_dir, code);
-    let csv_path = Path::new(&csv_path);
-    
-    if let Err(e) = export_submission_to_csv(
-        &submission,
-        csv_path,
-        (&config.construction_times.start_time, config.construction_times.end_time.as_deref()),
-        (&config.research_times.start_time, config.research_times.end_time.as_deref()),
-        (&config.troops_times.start_time, config.troops_times.end_time.as_deref()),
-    ) {
-        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+    let delivered = web::block(move || notifier.deliver(&account_name, &settings, &notification)).await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to send: {}", e)))?;
+    match delivered {
+        Ok(()) => Ok(HttpResponse::Ok().json(serde_json::json!({ "success": true }))),
+        Err(e) => Ok(HttpResponse::BadGateway().json(serde_json::json!({
             "success": false,
-            "error": format!("Failed to save submission: {}", e)
-        })));
+            "error": e
+        }))),
     }
-    
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "success": true,
-        "message": "Form submitted successfully"
-    })))
 }
 
-// Create form endpoint (admin only)
-#[derive(Deserialize)]
-pub struct CreateFormRequest {
-    pub name: Option<String>, // Optional form name
-    pub alliances: Vec<String>,
-    pub construction_times: DayTimeConfig,
-    pub research_times: DayTimeConfig,
-    pub troops_times: DayTimeConfig,
-    #[serde(default)]
-    pub predetermined_slots: Vec<PredeterminedSlot>, // Predetermined slot assignments
-    #[serde(default)]
-    pub intro_text: Option<String>, // Optional introduction text
+// Closes the current form of a server now, unless it already is
+fn close_current_form(state: &AppState, account_name: &str, server_number: u32) -> std::result::Result<String, String> {
+    let mut forms = state.forms.lock().unwrap();
+    let code = state.current_forms.lock().unwrap().get(&schedule_key(account_name, server_number)).cloned();
+    let form_data = code.and_then(|code| forms.get_mut(&code)).ok_or("No current form to close")?;
+    let now = chrono::Utc::now();
+    if form_data.config.is_closed(now) {
+        return Ok(format!("{} was already closed", form_data.name));
+    }
+    form_data.config.closes_at = Some(now.to_rfc3339());
+    form_data.auto_generation = None;
+    save_form(&state.data_dir, form_data).map_err(|e| format!("Failed to save form: {}", e))?;
+    Ok(format!("Closed {}", form_data.name))
+}
+
+// Posts a reminder over the account's notification channel
+fn post_reminder(state: &AppState, account_name: &str, server_number: u32, message: &str) -> std::result::Result<(), String> {
+    let settings = state.accounts.lock().unwrap().get(account_name).map(|a| a.notifications.clone()).unwrap_or_default();
+    if settings.channel == NotificationChannel::None {
+        return Err("Choose a notification channel for reminders to be posted to".into());
+    }
+    let title = format!("Reminder for server {}", server_number);
+    let email = match &state.mailer {
+        Some(mailer) => Some(mailer::render(title.clone(), &ReminderEmail {
+            title: &title,
+            details: message,
+            link: mailer.link(&format!("/view/{}/{}", account_name, server_number)),
+        })?),
+        None => None,
+    };
+    state.notifier.deliver(account_name, &settings, &Notification {
+        event: NotificationEvent::Reminder,
+        title,
+        body: message.to_string(),
+        email,
+    })
+}
+
+// Copies the files of a server into a new backup folder and drops the oldest backups
+fn backup_server(state: &AppState, account_name: &str, server_number: u32) -> std::result::Result<serde_json::Value, String> {
+    let mut files = vec![
+        format!("schedules/{}/{}.json", account_name, server_number),
+        format!("rosters/{}/{}.json", account_name, server_number),
+        format!("statistics/{}/{}.json", account_name, server_number),
+    ];
+    // The current form with its submissions and the files kept next to them
+    let code = state.current_forms.lock().unwrap().get(&schedule_key(account_name, server_number)).cloned();
+    if let Some(code) = code {
+        if let Ok(entries) = std::fs::read_dir(format!("{}/current_forms", state.data_dir)) {
+            files.extend(entries
+                .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+                .filter(|name| name.starts_with(&code))
+                .map(|name| format!("current_forms/{}", name)));
+        }
+    }
+    let taken_at = chrono::Utc::now();
+    let dir = tasks::backup_dir(&state.data_dir, account_name, server_number, taken_at);
+    let copied = tasks::write_backup(&state.data_dir, &dir, &files).map_err(|e| format!("Failed to write the backup: {}", e))?;
+    tasks::prune_backups(&state.data_dir, account_name, server_number)
+        .map_err(|e| format!("Failed to remove old backups: {}", e))?;
+    Ok(serde_json::json!({
+        "files": copied,
+        "backup": dir.strip_prefix(&state.data_dir).unwrap_or(&dir).display().to_string()
+    }))
+}
+
+// Whether a job of this task kind hasn't finished yet. The job queue hands back the pending job
+// instead of queueing another of the same kind, so a second task would be skipped.
+fn task_kind_busy(state: &AppState, account_name: &str, server_number: u32, kind: TaskKind) -> bool {
+    state.jobs.list(account_name, server_number).iter()
+        .any(|job| job.kind == kind.job_kind() && job.finished_at.is_none())
+}
+
+// Starts a scheduled task as a job. Its start time is recorded right away so the scheduler
+// doesn't start it again while it waits in the queue; the outcome is recorded when it ends.
+fn enqueue_scheduled_task(state: &web::Data<AppState>, account_name: &str, server_number: u32, task_id: &str) -> Option<crate::jobs::Job> {
+    let task = {
+        let mut accounts = state.accounts.lock().unwrap();
+        let task = accounts.get_mut(account_name)
+            .and_then(|a| a.scheduled_tasks.get_mut(&server_number))
+            .and_then(|tasks| tasks.iter_mut().find(|t| t.id == task_id))?;
+        task.last_run_at = Some(chrono::Utc::now().to_rfc3339());
+        let task = task.clone();
+        if let Err(e) = save_accounts(&state.data_dir, &accounts) {
+            log::warn!("Failed to save the scheduled tasks of {} #{}: {}", account_name, server_number, e);
+        }
+        task
+    };
+    let job_state = state.clone();
+    let job_account = account_name.to_string();
+    Some(state.jobs.enqueue(task.kind.job_kind(), account_name, server_number, serde_json::json!({ "task_id": task.id }), move |progress| {
+        let result = match task.kind {
+            TaskKind::CloseForm => {
+                progress.set(10, "Closing the form");
+                close_current_form(&job_state, &job_account, server_number).map(|message| serde_json::json!({ "message": message }))
+            }
+            TaskKind::Reminder => {
+                progress.set(10, "Posting the reminder");
+                post_reminder(&job_state, &job_account, server_number, task.message.as_deref().unwrap_or_default())
+                    .map(|()| serde_json::json!({ "posted": true }))
+            }
+            TaskKind::Backup => {
+                progress.set(10, "Copying files");
+                backup_server(&job_state, &job_account, server_number)
+            }
+            TaskKind::RecomputeStats => {
+                progress.set(10, "Reading submissions");
+                refresh_stats(&job_state, &job_account, server_number)
+                    .map(|stats| serde_json::json!({ "alliances": stats.alliance_counts.len() }))
+            }
+        };
+        
+        let mut accounts = job_state.accounts.lock().unwrap();
+        let recorded = accounts.get_mut(&job_account)
+            .and_then(|a| a.scheduled_tasks.get_mut(&server_number))
+            .and_then(|tasks| tasks.iter_mut().find(|t| t.id == task.id));
+        if let Some(recorded) = recorded {
+            recorded.last_error = result.as_ref().err().cloned();
+            if let Err(e) = save_accounts(&job_state.data_dir, &accounts) {
+                log::warn!("Failed to save the scheduled tasks of {} #{}: {}", job_account, server_number, e);
+            }
+        }
+        if let Err(e) = &result {
+            log::warn!("Scheduled task {} of {} #{} failed: {}", task.id, job_account, server_number, e);
+        }
+        result
+    }))
+}
+
+// How often scheduled tasks are checked for being due
+const TASK_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+// Background task starting every scheduled task whose time has come
+async fn run_task_scheduler(state: web::Data<AppState>) {
+    let mut interval = tokio::time::interval(TASK_CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        let now = chrono::Utc::now();
+        let due: Vec<(String, u32, String, TaskKind)> = state.accounts.lock().unwrap().values()
+            .filter(|a| !a.suspended)
+            .flat_map(|a| a.scheduled_tasks.iter().flat_map(move |(server, tasks)| tasks.iter().map(move |t| (a, *server, t))))
+            .filter(|(_, _, task)| task.is_due(now))
+            .map(|(a, server, task)| (a.account_name.clone(), server, task.id.clone(), task.kind))
+            .collect();
+        for (account_name, server_number, task_id, kind) in due {
+            // Tried again on the next check
+            if task_kind_busy(&state, &account_name, server_number, kind) {
+                continue;
+            }
+            enqueue_scheduled_task(&state, &account_name, server_number, &task_id);
+        }
+    }
 }
 
-#[derive(Deserialize)]
-pub struct UpdateFormConfigRequest {
-    pub predetermined_slots: Vec<PredeterminedSlot>, // Predetermined slot assignments
+// A scheduled task with the time it runs next, for the API
+fn scheduled_task_json(task: &ScheduledTask) -> serde_json::Value {
+    let mut value = serde_json::to_value(task).unwrap_or_default();
+    value["next_run_at"] = serde_json::json!(task.enabled.then(|| task.next_run()).flatten().map(|t| t.to_rfc3339()));
+    value
 }
 
-async fn create_form(
+// The recurring tasks of a server, with when each runs next
+async fn list_scheduled_tasks(
     path: web::Path<(String, u32)>,
     session: Session,
-    body: web::Json<CreateFormRequest>,
     state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
-    let (url_account_name, server_number) = path.into_inner();
-    let url_account_name = url_account_name.to_lowercase();
-    
-    // Verify session authentication
-    let session_account_name: String = match session.get("account_name") {
-        Ok(Some(name)) => name,
-        Ok(None) => {
-            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
-                "success": false,
-                "error": "Not logged in"
-            })));
-        }
-        Err(_) => {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "success": false,
-                "error": "Failed to read session"
-            })));
-        }
-    };
-    let session_server_number: u32 = match session.get("server_number") {
-        Ok(Some(num)) => num,
-        Ok(None) => {
-            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
-                "success": false,
-                "error": "Not logged in"
-            })));
-        }
-        Err(_) => {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "success": false,
-                "error": "Failed to read session"
-            })));
-        }
-    };
-    
-    // Verify account name and server number match session
-    if session_account_name.to_lowercase() != url_account_name || session_server_number != server_number {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
         return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
             "success": false,
-            "error": "Unauthorized"
+            "error": "Not authorized"
         })));
     }
     
-    // Verify account exists
-    let accounts = state.accounts.lock().unwrap();
-    if !accounts.contains_key(&url_account_name) {
-        drop(accounts);
-        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+    let tasks: Vec<serde_json::Value> = state.accounts.lock().unwrap().get(&account_name)
+        .and_then(|a| a.scheduled_tasks.get(&server_number))
+        .map(|tasks| tasks.iter().map(scheduled_task_json).collect())
+        .unwrap_or_default();
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "tasks": tasks
+    })))
+}
+
+// Add a recurring task to a server
+async fn create_scheduled_task(
+    path: web::Path<(String, u32)>,
+    req: web::Json<ScheduledTaskRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
             "success": false,
-            "error": "Account not found"
+            "error": "Not authorized"
         })));
     }
-    drop(accounts);
     
-    // Validate alliances (must have at least one)
-    if body.alliances.is_empty() {
+    let req = req.into_inner();
+    let task = ScheduledTask {
+        id: generate_form_code(),
+        kind: req.kind,
+        cron: req.cron.trim().to_string(),
+        message: req.message.map(|m| m.trim().to_string()).filter(|m| !m.is_empty()).filter(|_| req.kind == TaskKind::Reminder),
+        enabled: req.enabled.unwrap_or(true),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        last_run_at: None,
+        last_error: None,
+    };
+    if let Err(e) = task.validate() {
         return Ok(HttpResponse::BadRequest().json(serde_json::json!({
             "success": false,
-            "error": "At least one alliance must be specified"
+            "error": e
         })));
     }
     
-    // Generate unique code - check both in-memory forms and files on disk
-    let mut code = generate_form_code();
-    let mut max_attempts = 100; // Prevent infinite loop
-    loop {
-        // Check in-memory forms
-        let forms = state.forms.lock().unwrap();
-        let in_memory = forms.contains_key(&code);
-        drop(forms);
-        
-        // Check if file exists on disk (current_forms folder)
-        let current_forms_file = format!("{}/current_forms/{}.json", state.data_dir, code);
-        let file_exists = Path::new(&current_forms_file).exists();
-        
-        // Check if file exists in old_forms folder (scan all subdirectories)
-        let old_forms_dir = format!("{}/old_forms", state.data_dir);
-        let mut old_file_exists = false;
-        if Path::new(&old_forms_dir).exists() {
-            if let Ok(entries) = std::fs::read_dir(&old_forms_dir) {
-                for entry in entries.flatten() {
-                    if let Ok(entry_path) = entry.path().canonicalize() {
-                        if entry_path.is_dir() {
-                            let old_form_file = entry_path.join(format!("{}.json", code));
-                            if old_form_file.exists() {
-                                old_file_exists = true;
-                                break;
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        
-        if !in_memory && !file_exists && !old_file_exists {
-            // Code is unique, break the loop
-            break;
-        }
-        
-        // Code collision detected, generate new one
-        code = generate_form_code();
-        max_attempts -= 1;
-        if max_attempts <= 0 {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+    let mut accounts = state.accounts.lock().unwrap();
+    let account = match accounts.get_mut(&account_name) {
+        Some(account) => account,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
                 "success": false,
-                "error": "Failed to generate unique form code after multiple attempts. Please try again."
+                "error": "Account not found"
             })));
         }
-    }
-    
-    // Create form data
-    let mut alliances = body.alliances.clone();
-    if !alliances.contains(&"Non of the above".to_string()) {
-        alliances.push("Non of the above".to_string());
-    }
-    
-    let config = FormConfig {
-        alliances,
-        construction_times: body.construction_times.clone(),
-        research_times: body.research_times.clone(),
-        troops_times: body.troops_times.clone(),
-        predetermined_slots: body.predetermined_slots.clone(),
-        intro_text: body.intro_text.clone(),
-    };
-    
-    let form_name = body.name.clone().unwrap_or_else(|| {
-        format!("Form {} {}", url_account_name, server_number)
-    });
-    let created_at = chrono::Utc::now().to_rfc3339();
-    
-    let form_data = FormData {
-        code: code.clone(),
-        account_name: url_account_name.clone(),
-        server_number,
-        name: form_name,
-        created_at,
-        config: FormConfig {
-            alliances: body.alliances.clone(),
-            construction_times: body.construction_times.clone(),
-            research_times: body.research_times.clone(),
-            troops_times: body.troops_times.clone(),
-            predetermined_slots: body.predetermined_slots.clone(),
-            intro_text: body.intro_text.clone(),
-        },
     };
-    
-    // Archive old forms for this account/server before creating new one
-    archive_old_forms(&state.data_dir, &url_account_name, server_number)
-        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to archive old forms: {}", e)))?;
-    
-    // Save new form
-    let mut forms = state.forms.lock().unwrap();
-    // Remove old forms for this account/server from memory
-    forms.retain(|_, fd| !(fd.account_name == url_account_name && fd.server_number == server_number));
-    forms.insert(code.clone(), form_data.clone());
-    drop(forms);
-    
-    save_form(&state.data_dir, &form_data)
-        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save form: {}", e)))?;
-    
-    // Update current forms mapping
-    let mut current_forms = state.current_forms.lock().unwrap();
-    let key = format!("{}:{}", url_account_name, server_number);
-    current_forms.insert(key, code.clone());
-    save_current_forms(&state.data_dir, &current_forms)
-        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save current forms mapping: {}", e)))?;
-    drop(current_forms);
-    
-    // Build form URL - use relative path since we don't have HttpRequest
-    let form_url = format!("/form/{}", code);
+    let tasks = account.scheduled_tasks.entry(server_number).or_default();
+    if tasks.len() >= MAX_TASKS_PER_SERVER {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": format!("A server can have at most {} scheduled tasks", MAX_TASKS_PER_SERVER)
+        })));
+    }
+    tasks.push(task.clone());
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
     
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "success": true,
-        "message": "Form created successfully",
-        "code": code,
-        "url": form_url
+        "task": scheduled_task_json(&task)
     })))
 }
 
-// Update form config endpoint (for updating predetermined slots)
-async fn update_form_config(
-    path: web::Path<(String, u32)>,
+// Change the schedule, message or kind of a task, or pause it. Its run history is kept.
+async fn update_scheduled_task(
+    path: web::Path<(String, u32, String)>,
+    req: web::Json<ScheduledTaskRequest>,
     session: Session,
-    body: web::Json<UpdateFormConfigRequest>,
     state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
-    let (url_account_name, server_number) = path.into_inner();
-    let url_account_name = url_account_name.to_lowercase();
+    require_owner(&session)?;
+    let (account_name, server_number, task_id) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
     
-    // Verify session authentication
-    let session_account_name: String = match session.get("account_name") {
-        Ok(Some(name)) => name,
-        Ok(None) => {
-            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
-                "success": false,
-                "error": "Not logged in"
-            })));
-        }
-        Err(_) => {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+    let req = req.into_inner();
+    let mut accounts = state.accounts.lock().unwrap();
+    let task = match accounts.get_mut(&account_name)
+        .and_then(|a| a.scheduled_tasks.get_mut(&server_number))
+        .and_then(|tasks| tasks.iter_mut().find(|t| t.id == task_id))
+    {
+        Some(task) => task,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
                 "success": false,
-                "error": "Failed to read session"
+                "error": "Task not found"
             })));
         }
     };
-    let session_server_number: u32 = match session.get("server_number") {
-        Ok(Some(num)) => num,
-        Ok(None) => {
-            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
-                "success": false,
-                "error": "Not logged in"
-            })));
-        }
-        Err(_) => {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+    let mut updated = task.clone();
+    updated.kind = req.kind;
+    updated.cron = req.cron.trim().to_string();
+    updated.message = req.message.map(|m| m.trim().to_string()).filter(|m| !m.is_empty()).filter(|_| req.kind == TaskKind::Reminder);
+    updated.enabled = req.enabled.unwrap_or(task.enabled);
+    if updated.cron != task.cron || (updated.enabled && !task.enabled) {
+        // Counted from now, so a new schedule or a resumed task doesn't run for times already past
+        updated.created_at = chrono::Utc::now().to_rfc3339();
+        updated.last_run_at = None;
+    }
+    if let Err(e) = updated.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": e
+        })));
+    }
+    *task = updated.clone();
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "task": scheduled_task_json(&updated)
+    })))
+}
+
+// Remove a task from a server
+async fn delete_scheduled_task(
+    path: web::Path<(String, u32, String)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number, task_id) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
+    
+    let mut accounts = state.accounts.lock().unwrap();
+    let tasks = match accounts.get_mut(&account_name).and_then(|a| a.scheduled_tasks.get_mut(&server_number)) {
+        Some(tasks) if tasks.iter().any(|t| t.id == task_id) => tasks,
+        _ => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
                 "success": false,
-                "error": "Failed to read session"
+                "error": "Task not found"
             })));
         }
     };
+    tasks.retain(|t| t.id != task_id);
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
     
-    // Verify account name and server number match session
-    if session_account_name.to_lowercase() != url_account_name || session_server_number != server_number {
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true
+    })))
+}
+
+// Run a task right away, as a job; its next scheduled run counts from now
+async fn run_scheduled_task_now(
+    path: web::Path<(String, u32, String)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number, task_id) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
         return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
             "success": false,
-            "error": "Unauthorized"
+            "error": "Not authorized"
         })));
     }
     
-    // Get current form for this account/server
-    let mut forms = state.forms.lock().unwrap();
-    let current_forms = state.current_forms.lock().unwrap();
-    let key = format!("{}:{}", url_account_name, server_number);
-    
-    let form_code = if let Some(code) = current_forms.get(&key) {
-        code.clone()
-    } else {
-        drop(forms);
-        drop(current_forms);
-        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+    let kind = state.accounts.lock().unwrap().get(&account_name)
+        .and_then(|a| a.scheduled_tasks.get(&server_number))
+        .and_then(|tasks| tasks.iter().find(|t| t.id == task_id))
+        .map(|t| t.kind);
+    if kind.is_some_and(|kind| task_kind_busy(&state, &account_name, server_number, kind)) {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
             "success": false,
-            "error": "No current form found"
+            "error": "A task of this kind is still running; try again when it's done"
         })));
-    };
+    }
+    match enqueue_scheduled_task(&state, &account_name, server_number, &task_id) {
+        Some(job) => Ok(HttpResponse::Accepted().json(serde_json::json!({
+            "success": true,
+            "job": job
+        }))),
+        None => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Task not found"
+        }))),
+    }
+}
+
+// Every empty slot of one day with all players who listed it (see unfilled_day), for picking
+// who to put in a slot by hand
+async fn get_slot_candidates(
+    path: web::Path<(String, u32, String)>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (account_name, server_number, day_str) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
     
-    // Get the form
-    let mut form_data = if let Some(form) = forms.get(&form_code).cloned() {
-        form
-    } else {
-        drop(forms);
-        drop(current_forms);
+    let Some(mut schedule_data) = load_schedule(&state.data_dir, &account_name, server_number) else {
         return Ok(HttpResponse::NotFound().json(serde_json::json!({
             "success": false,
-            "error": "Form not found"
+            "error": "No schedule has been generated yet"
         })));
     };
-    
-    drop(current_forms);
-    
-    // Update predetermined slots
-    form_data.config.predetermined_slots = body.predetermined_slots.clone();
-    
-    // Save updated form
-    save_form(&state.data_dir, &form_data)
-        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save form: {}", e)))?;
-    
-    // Update in memory
-    forms.insert(form_code.clone(), form_data);
-    drop(forms);
-    
+    let (form_entries, config) = load_form_entries(&state, &account_name, server_number);
+    let entries = schedule_data.entries.clone().unwrap_or(form_entries);
+    let Some(schedule) = day_schedule_slot(&mut schedule_data, &day_str) else {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "error": "Invalid day"})));
+    };
+    let day = unfilled_day(schedule.as_ref(), &entries, &day_time_slots(config.as_ref(), &day_str), &day_str, None);
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "success": true,
-        "message": "Form config updated successfully"
+        "day": day_str,
+        "total_slots": day.total_slots,
+        "unfilled": day.unfilled
     })))
 }
 
-// Get form config by code (public)
-async fn get_form_config_by_code(
-    path: web::Path<String>,
+// Put a player who listed an empty slot into it. A player already placed that day moves out
+// of their old slot, unless it is locked (predetermined or linked to the other days).
+async fn fill_slot(
+    path: web::Path<(String, u32, String)>,
+    req: web::Json<FillSlotRequest>,
+    session: Session,
     state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
-    let code = path.into_inner();
-    
-    let forms = state.forms.lock().unwrap();
-    if let Some(form_data) = forms.get(&code) {
-        let config = form_data.config.clone();
-        drop(forms);
-        Ok(HttpResponse::Ok().json(config))
-    } else {
-        drop(forms);
-        Ok(HttpResponse::NotFound().json(serde_json::json!({
+    let (account_name, server_number, day_str) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
             "success": false,
-            "error": "Form not found"
-        })))
+            "error": "Not authorized"
+        })));
     }
-}
-
-// Get form statistics by code (public - shows only time slot popularity)
-#[derive(Serialize)]
-pub struct FormStatsResponse {
-    construction_start_time: String,
-    research_start_time: String,
-    troops_start_time: String,
-    construction_time_slot_popularity: HashMap<String, FormTimeSlotStats>,
-    research_time_slot_popularity: HashMap<String, FormTimeSlotStats>,
-    troops_time_slot_popularity: HashMap<String, FormTimeSlotStats>,
-}
-
-async fn get_form_stats_by_code(
-    path: web::Path<String>,
-    state: web::Data<AppState>,
-) -> Result<HttpResponse> {
-    let code = path.into_inner();
-    
-    // Get form data to find account_name and server_number, and get config
-    let forms = state.forms.lock().unwrap();
-    let form_data = forms.get(&code).cloned();
-    drop(forms);
+    let bad_request = |error: String| Ok(HttpResponse::BadRequest().json(serde_json::json!({
+        "success": false,
+        "error": error
+    })));
     
-    let config = if let Some(fd) = form_data {
-        fd.config
-    } else {
+    let key = schedule_key(&account_name, server_number);
+    let schedule_data = {
+        let schedules = state.schedules.lock().unwrap();
+        schedules.get(&key).cloned()
+            .or_else(|| load_schedule(&state.data_dir, &account_name, server_number))
+    };
+    let Some(mut schedule_data) = schedule_data else {
         return Ok(HttpResponse::NotFound().json(serde_json::json!({
             "success": false,
-            "error": "Form not found"
+            "error": "No schedule has been generated yet"
         })));
     };
-    
-    // Read form submissions CSV file (using form code)
-    let current_forms_dir = format!("{}/current_forms", state.data_dir);
-    let csv_path = format!("{}/{}_submissions.csv", current_forms_dir, code);
-    
-    // Generate time slots for each day type based on form configuration
-    let construction_slots = calculate_time_slots(&config.construction_times.start_time, config.construction_times.end_time.as_deref());
-    let research_slots = calculate_time_slots(&config.research_times.start_time, config.research_times.end_time.as_deref());
-    let troops_slots = calculate_time_slots(&config.troops_times.start_time, config.troops_times.end_time.as_deref());
-    
-    // Convert to slices for passing to load_appointments
-    let construction_slots_ref: Vec<(u8, String)> = construction_slots.clone();
-    let research_slots_ref: Vec<(u8, String)> = research_slots.clone();
-    let troops_slots_ref: Vec<(u8, String)> = troops_slots.clone();
-    
-    // Parse CSV file using load_appointments with custom time slot mappings
-    let entries = match load_appointments(
-        &csv_path,
-        Some(&construction_slots_ref),
-        Some(&research_slots_ref),
-        Some(&troops_slots_ref),
-    ) {
-        Ok(e) => e,
-        Err(e) => {
-            // If file doesn't exist or can't be parsed, return empty stats
-            eprintln!("Error loading form submissions CSV from {}: {}", csv_path, e);
-            return Ok(HttpResponse::Ok().json(FormStatsResponse {
-                construction_start_time: "00:00".to_string(),
-                research_start_time: "00:00".to_string(),
-                troops_start_time: "00:00".to_string(),
-                construction_time_slot_popularity: HashMap::new(),
-                research_time_slot_popularity: HashMap::new(),
-                troops_time_slot_popularity: HashMap::new(),
-            }));
-        }
+    let (form_entries, config) = load_form_entries(&state, &account_name, server_number);
+    let time_slots = day_time_slots(config.as_ref(), &day_str);
+    let Some(slot) = time_slots.iter().find(|(_, t)| *t == req.time).map(|(slot, _)| *slot) else {
+        return bad_request("Invalid time slot".to_string());
     };
-    
-    // Initialize time slot popularity for Construction
-    let mut construction_time_slot_popularity: HashMap<String, FormTimeSlotStats> = HashMap::new();
-    for (_, time) in &construction_slots {
-        construction_time_slot_popularity.insert(time.clone(), FormTimeSlotStats {
-            requests: 0,
-        });
+    let entries = schedule_data.entries.clone().unwrap_or(form_entries);
+    let Some(entry) = entries.iter().find(|e| e.player_id == req.player_id) else {
+        return bad_request("No submission found for this player".to_string());
+    };
+    let player = format_player_name(&entry.alliance, &entry.name);
+    if let Some(scope) = session_alliance_scope(&session) {
+        if !entry.alliance.eq_ignore_ascii_case(&scope) {
+            return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+                "success": false,
+                "error": format!("This login can only edit slots for alliance [{}]", scope)
+            })));
+        }
+    }
+    if !lists_slot(entry, &day_str, slot) {
+        return bad_request(format!("{} did not list {} for {}", player, req.time, day_str));
     }
     
-    // Initialize time slot popularity for Research
-    let mut research_time_slot_popularity: HashMap<String, FormTimeSlotStats> = HashMap::new();
-    for (_, time) in &research_slots {
-        research_time_slot_popularity.insert(time.clone(), FormTimeSlotStats {
-            requests: 0,
-        });
+    let locked = refill_locked_slots(config.as_ref(), &day_str, &time_slots);
+    let Some(schedule) = day_schedule_slot(&mut schedule_data, &day_str) else {
+        return bad_request("Invalid day".to_string());
+    };
+    let schedule = schedule.get_or_insert_with(|| DaySchedule {
+        appointments: HashMap::new(),
+        unassigned: Vec::new(),
+    });
+    if let Some(taken) = schedule.appointments.get(&slot) {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "success": false,
+            "error": format!("{} is taken by {}", req.time, format_player_name(&taken.alliance, &taken.name))
+        })));
+    }
+    let current_slot = schedule.appointments.values().find(|a| a.player_id == entry.player_id).map(|a| a.slot);
+    let slot_time = |slot: u8| time_slots.iter().find(|(s, _)| *s == slot).map(|(_, t)| t.clone()).unwrap_or_else(|| slot_to_time(slot));
+    if let Some(current_slot) = current_slot {
+        if locked.contains(&current_slot) {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "success": false,
+                "error": format!("{} is locked at {}", player, slot_time(current_slot))
+            })));
+        }
+        schedule.appointments.remove(&current_slot);
+    }
+    schedule.unassigned.retain(|id| *id != entry.player_id);
+    schedule.appointments.insert(slot, ScheduledAppointment {
+        manual: true,
+        ..entry_appointment(entry, &day_str, slot)
+    });
+    if let Some(ids) = schedule_data.scheduled_player_ids.as_mut() {
+        if !ids.contains(&entry.player_id) {
+            ids.push(entry.player_id.clone());
+        }
     }
     
-    // Initialize time slot popularity for Troops
-    let mut troops_time_slot_popularity: HashMap<String, FormTimeSlotStats> = HashMap::new();
-    for (_, time) in &troops_slots {
-        troops_time_slot_popularity.insert(time.clone(), FormTimeSlotStats {
-            requests: 0,
-        });
+    save_schedule(&state.data_dir, &account_name, server_number, &schedule_data)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save schedule: {}", e)))?;
+    state.schedules.lock().unwrap().insert(key, schedule_data);
+    let moved_from = current_slot.map(slot_time);
+    notify_slot_changed(&state, &account_name, server_number, match &moved_from {
+        Some(from) => format!("{}: {} moved from {} to {}", day_display_name(&day_str), player, from, req.time),
+        None => format!("{} {}: {}", day_display_name(&day_str), req.time, player),
+    });
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "day": day_str,
+        "time": req.time,
+        "player": player,
+        "moved_from": moved_from
+    })))
+}
+
+// Fill the empty slots of the schedule from the players without a slot (see auto_fill_day),
+// without regenerating: everyone placed keeps their slot (owner only)
+async fn auto_fill_schedule(
+    path: web::Path<(String, u32)>,
+    req: web::Json<AutoFillRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
     }
+    let days: Vec<&str> = match req.day.as_deref() {
+        None => vec!["construction", "research", "troops"],
+        Some(day @ ("construction" | "research" | "troops")) => vec![day],
+        Some(_) => return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "error": "Invalid day"}))),
+    };
     
-    // Create maps from slot number to time string for each day type
-    let construction_slot_to_time: HashMap<u8, String> = construction_slots.iter().map(|(s, t)| (*s, t.clone())).collect();
-    let research_slot_to_time: HashMap<u8, String> = research_slots.iter().map(|(s, t)| (*s, t.clone())).collect();
-    let troops_slot_to_time: HashMap<u8, String> = troops_slots.iter().map(|(s, t)| (*s, t.clone())).collect();
+    let key = schedule_key(&account_name, server_number);
+    let schedule_data = {
+        let schedules = state.schedules.lock().unwrap();
+        schedules.get(&key).cloned()
+            .or_else(|| load_schedule(&state.data_dir, &account_name, server_number))
+    };
+    let Some(mut schedule_data) = schedule_data else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "No schedule has been generated yet"
+        })));
+    };
+    let (form_entries, config) = load_form_entries(&state, &account_name, server_number);
+    let mut entries = schedule_data.entries.clone().unwrap_or(form_entries);
+    if let Some(config) = &config {
+        remove_blackout_slots(&mut entries, &config.construction_times, &config.research_times, &config.troops_times);
+    }
     
-    // Count actual selections
-    for entry in entries {
-        // Count construction time slot popularity
-        for slot in &entry.construction_available_slots {
-            if let Some(time) = construction_slot_to_time.get(slot) {
-                if let Some(slot_stats) = construction_time_slot_popularity.get_mut(time) {
-                    slot_stats.requests += 1;
-                }
-            }
-        }
-        
-        // Count research time slot popularity
-        for slot in &entry.research_available_slots {
-            if let Some(time) = research_slot_to_time.get(slot) {
-                if let Some(slot_stats) = research_time_slot_popularity.get_mut(time) {
-                    slot_stats.requests += 1;
-                }
-            }
-        }
-        
-        // Count troops time slot popularity
-        for slot in &entry.troops_available_slots {
-            if let Some(time) = troops_slot_to_time.get(slot) {
-                if let Some(slot_stats) = troops_time_slot_popularity.get_mut(time) {
-                    slot_stats.requests += 1;
+    let mut filled = Vec::new();
+    for day_str in days {
+        let time_slots = day_time_slots(config.as_ref(), day_str);
+        let locked = refill_locked_slots(config.as_ref(), day_str, &time_slots);
+        let Some(schedule) = day_schedule_slot(&mut schedule_data, day_str) else { continue };
+        let schedule = schedule.get_or_insert_with(|| DaySchedule {
+            appointments: HashMap::new(),
+            unassigned: Vec::new(),
+        });
+        for slot in auto_fill_day(schedule, &entries, day_str, &time_slots, &locked) {
+            let appt = &schedule.appointments[&slot];
+            filled.push(serde_json::json!({
+                "day": day_str,
+                "time": time_slots.iter().find(|(s, _)| *s == slot).map(|(_, t)| t.clone()).unwrap_or_else(|| slot_to_time(slot)),
+                "player": format_player_name(&appt.alliance, &appt.name)
+            }));
+        }
+    }
+    
+    if !filled.is_empty() {
+        let placed = derive_scheduled_player_ids(&schedule_data);
+        if let Some(ids) = schedule_data.scheduled_player_ids.as_mut() {
+            for id in placed {
+                if !ids.contains(&id) {
+                    ids.push(id);
                 }
             }
         }
+        save_schedule(&state.data_dir, &account_name, server_number, &schedule_data)
+            .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to save schedule: {}", e)))?;
+        state.schedules.lock().unwrap().insert(key, schedule_data);
     }
-    
-    Ok(HttpResponse::Ok().json(FormStatsResponse {
-        construction_start_time: config.construction_times.start_time.clone(),
-        research_start_time: config.research_times.start_time.clone(),
-        troops_start_time: config.troops_times.start_time.clone(),
-        construction_time_slot_popularity,
-        research_time_slot_popularity,
-        troops_time_slot_popularity,
-    }))
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "filled": filled
+    })))
 }
 
-// Public form statistics page handler
-async fn public_form_stats_page(
-    path: web::Path<String>,
+// A day of the generated schedule as Discord messages, mentioning the players the roster
+// has a Discord ID for (owner only, like the roster). Split to fit Discord's message limit.
+async fn get_schedule_discord(
+    path: web::Path<(String, u32, String)>,
+    session: Session,
     state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
-    let code = path.into_inner();
-    
-    // Verify form exists
-    let forms = state.forms.lock().unwrap();
-    if !forms.contains_key(&code) {
-        drop(forms);
-        return Ok(HttpResponse::NotFound().body("Form not found"));
+    require_owner(&session)?;
+    let (account_name, server_number, day_str) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
     }
-    drop(forms);
-    
-    let html = include_str!("../templates/form_stats.html");
-    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+
+    let Some(schedule_data) = load_schedule(&state.data_dir, &account_name, server_number) else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "No schedule has been generated yet"
+        })));
+    };
+    let (day_name, schedule) = match day_str.as_str() {
+        "construction" => ("Construction Day", schedule_data.construction_schedule),
+        "research" => ("Research Day", schedule_data.research_schedule),
+        "troops" => ("Troops Training Day", schedule_data.troops_schedule),
+        _ => return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "error": "Invalid day"}))),
+    };
+    let schedule = schedule.unwrap_or(DaySchedule { appointments: HashMap::new(), unassigned: Vec::new() });
+    let (_, config) = load_form_entries(&state, &account_name, server_number);
+    let time_slots = day_time_slots(config.as_ref(), &day_str);
+    let mentions = discord_mentions(&load_roster(&state.data_dir, &account_name, server_number));
+
+    let mut message = Vec::new();
+    write_schedule_discord(&mut message, day_name, &schedule, Some(&time_slots), &mentions)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+    let mentioned = schedule.appointments.values().filter(|appt| mentions.contains_key(&appt.player_id)).count();
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "day": day_str,
+        "messages": split_discord_messages(&String::from_utf8_lossy(&message)),
+        "mentioned": mentioned,
+        "scheduled": schedule.appointments.len()
+    })))
 }
 
-// Get current form info for account (admin - to display current form link)
-async fn get_current_form_info(
+// Default minimum change in percent for the week-over-week comparison
+const RESOURCE_CHANGE_DEFAULT_THRESHOLD: u32 = 50;
+
+// Claimed resources of a player that changed by at least `threshold` percent between two submissions
+fn resource_changes(previous: &AppointmentEntry, current: &AppointmentEntry, threshold: u32) -> Vec<ResourceChange> {
+    let resources = [
+        ("construction_speedups", previous.construction_speedups, current.construction_speedups),
+        ("construction_truegold", previous.construction_truegold, current.construction_truegold),
+        ("research_speedups", previous.research_speedups, current.research_speedups),
+        ("research_truegold_dust", previous.research_truegold_dust, current.research_truegold_dust),
+        ("troops_speedups", previous.troops_speedups, current.troops_speedups),
+    ];
+    resources.into_iter()
+        .filter(|(_, before, after)| before != after)
+        .filter_map(|(resource, before, after)| {
+            // Going from nothing to something counts as a 100% change
+            let change_percent = if before == 0 {
+                100.0
+            } else {
+                (after as f64 - before as f64) / before as f64 * 100.0
+            };
+            (change_percent.abs() >= threshold as f64).then(|| ResourceChange {
+                player_id: current.player_id.clone(),
+                name: current.name.clone(),
+                alliance: current.alliance.clone(),
+                resource,
+                previous: before,
+                current: after,
+                change_percent: (change_percent * 10.0).round() / 10.0,
+            })
+        })
+        .collect()
+}
+
+// Compares the current form's submitters with the previous form's: who joined, who dropped out and whose claims changed
+async fn compare_participation(
     path: web::Path<(String, u32)>,
+    query: web::Query<ParticipationCompareQuery>,
     session: Session,
     state: web::Data<AppState>,
-    req: HttpRequest,
 ) -> Result<HttpResponse> {
-    let (url_account_name, server_number) = path.into_inner();
-    let url_account_name = url_account_name.to_lowercase();
+    let (account_name, server_number) = path.into_inner();
+    let account_name = account_name.to_lowercase();
+    if !session_is_for(&session, &account_name, server_number) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authorized"
+        })));
+    }
     
-    // Try session authentication first, fallback to password authentication
-    let authenticated = {
-        // Check session
-        let session_account_name: Option<String> = session.get("account_name").ok().flatten();
-        let session_server_number: Option<u32> = session.get("server_number").ok().flatten();
-        
-        if let (Some(session_account_name), Some(session_server_number)) = (session_account_name, session_server_number) {
-            // Verify the account_name and server_number match
-            session_account_name == url_account_name && session_server_number == server_number
-        } else {
-            // Fallback: check password header (for admin page)
-            if let Some(password_header) = req.headers().get("X-Password") {
-                if let Ok(password) = password_header.to_str() {
-                    let accounts = state.accounts.lock().unwrap();
-                    if let Some(account) = accounts.get(&url_account_name) {
-                        account.password == password && account.server_number == server_number
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                }
-            } else {
-                false
-            }
+    let (previous_id, previous_form) = match archived_forms_for(&state.data_dir, &account_name, server_number).into_iter().next() {
+        Some(previous) => previous,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "There is no previous form to compare with"
+            })));
         }
     };
+    let previous_csv = format!("{}/old_forms/{}_{}_{}_submissions.csv", state.data_dir, account_name, server_number, previous_id);
+    let previous_entries = if Path::new(&previous_csv).exists() {
+        load_csv_entries(&state, &previous_csv, Some(&previous_form.config))
+    } else {
+        Vec::new()
+    };
+    let (current_entries, _) = load_form_entries(&state, &account_name, server_number);
+    
+    // Sub-accounts only see their own alliance
+    let alliance_scope = session_alliance_scope(&session);
+    let in_scope = |e: &&AppointmentEntry| alliance_scope.as_deref().is_none_or(|scope| e.alliance.eq_ignore_ascii_case(scope));
+    let previous: HashMap<&str, &AppointmentEntry> = previous_entries.iter().filter(in_scope).map(|e| (e.player_id.as_str(), e)).collect();
+    let current: HashMap<&str, &AppointmentEntry> = current_entries.iter().filter(in_scope).map(|e| (e.player_id.as_str(), e)).collect();
+    
+    let player = |e: &AppointmentEntry| ParticipationPlayer {
+        player_id: e.player_id.clone(),
+        name: e.name.clone(),
+        alliance: e.alliance.clone(),
+    };
+    let by_name = |a: &ParticipationPlayer, b: &ParticipationPlayer| a.name.to_lowercase().cmp(&b.name.to_lowercase());
+    let mut joined: Vec<ParticipationPlayer> = current.iter()
+        .filter(|(id, _)| !previous.contains_key(*id))
+        .map(|(_, e)| player(e))
+        .collect();
+    joined.sort_by(by_name);
+    let mut dropped: Vec<ParticipationPlayer> = previous.iter()
+        .filter(|(id, _)| !current.contains_key(*id))
+        .map(|(_, e)| player(e))
+        .collect();
+    dropped.sort_by(by_name);
     
-    if !authenticated {
-        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+    let threshold = query.threshold.unwrap_or(RESOURCE_CHANGE_DEFAULT_THRESHOLD);
+    let mut changes: Vec<ResourceChange> = current.iter()
+        .filter_map(|(id, now)| previous.get(id).map(|before| resource_changes(before, now, threshold)))
+        .flatten()
+        .collect();
+    changes.sort_by(|a, b| b.change_percent.abs().total_cmp(&a.change_percent.abs()).then_with(|| a.name.cmp(&b.name)));
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "previous_form": {
+            "name": previous_form.name,
+            "created_at": previous_form.created_at,
+            "archive_id": previous_id
+        },
+        "threshold": threshold,
+        "returning": current.keys().filter(|id| previous.contains_key(*id)).count(),
+        "joined": joined,
+        "dropped": dropped,
+        "resource_changes": changes
+    })))
+}
+
+// Login endpoint (new - uses account name + password only, sets session cookie)
+async fn login_api(req: web::Json<LoginRequest>, session: Session, state: web::Data<AppState>) -> Result<HttpResponse> {
+    let account_name = req.account_name.as_ref()
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("Account name required"))?
+        .trim()
+        .to_lowercase();
+    
+    let accounts = state.accounts.lock().unwrap();
+    
+    if accounts.get(&account_name).is_some_and(|a| a.suspended) {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
             "success": false,
-            "error": "Not authenticated"
+            "error": "This account has been suspended"
         })));
     }
     
-    // Get current form - first try from mapping, then check files in current_forms folder
-    let forms = state.forms.lock().unwrap();
-    let current_forms = state.current_forms.lock().unwrap();
-    let mut current_form = get_current_form(&forms, &current_forms, &url_account_name, server_number);
-    drop(forms);
-    drop(current_forms);
-    
-    // If not found in mapping or forms HashMap, check files in current_forms folder directly
-    if current_form.is_none() {
-        let current_forms_dir = format!("{}/current_forms", state.data_dir);
-        eprintln!("Checking current_forms directory: {}", current_forms_dir);
-        eprintln!("Looking for form with account_name: '{}', server_number: {}", url_account_name, server_number);
-        
-        if let Ok(entries) = std::fs::read_dir(&current_forms_dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                // Only process .json files (not CSV files)
-                if path.extension().and_then(|s| s.to_str()) == Some("json") {
-                    if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                        // Skip files that end with _submissions (those are CSV files, not form JSON)
-                        if file_name.contains("_submissions") {
-                            continue;
-                        }
-                        
-                        eprintln!("Checking file: {}", file_name);
-                        
-                        // Try to load the form JSON file
-                        if let Ok(content) = std::fs::read_to_string(&path) {
-                            if let Ok(mut form_data) = serde_json::from_str::<FormData>(&content) {
-                                // Normalize account_name to lowercase for comparison
-                                let form_account_name = form_data.account_name.to_lowercase();
-                                eprintln!("Found form: account_name='{}', server_number={}, code='{}'", 
-                                    form_account_name, form_data.server_number, form_data.code);
-                                
-                                // Check if this form belongs to the requested account/server
-                                if form_account_name == url_account_name && form_data.server_number == server_number {
-                                    eprintln!("Match found! Returning form: {}", form_data.code);
-                                    // Ensure account_name is lowercase in the returned form
-                                    form_data.account_name = form_account_name;
-                                    current_form = Some(form_data);
-                                    break;
-                                } else {
-                                    eprintln!("No match: form_account_name='{}' != url_account_name='{}' OR server_number={} != {}", 
-                                        form_account_name, url_account_name, form_data.server_number, server_number);
-                                }
-                            } else {
-                                eprintln!("Failed to parse JSON from file: {}", file_name);
-                            }
-                        } else {
-                            eprintln!("Failed to read file: {}", file_name);
-                        }
-                    }
-                }
+    // Alliance sub-account login
+    if let Some(username) = req.username.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+        let sub_account = accounts.get(&account_name)
+            .and_then(|a| a.sub_accounts.iter().find(|s| s.username.eq_ignore_ascii_case(username)).map(|s| (a, s)));
+        return match sub_account {
+            Some((account, sub_account)) if sub_account.password == req.password => {
+                session.insert("account_name", &account.account_name)
+                    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to set session: {}", e)))?;
+                session.insert("server_number", account.server_number)
+                    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to set session: {}", e)))?;
+                session.insert("alliance_scope", &sub_account.alliance)
+                    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to set session: {}", e)))?;
+                session.insert("sub_account", &sub_account.username)
+                    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to set session: {}", e)))?;
+                session.insert("sub_account_id", &sub_account.id)
+                    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to set session: {}", e)))?;
+                session.insert("session_version", account.session_version)
+                    .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to set session: {}", e)))?;
+                
+                Ok(HttpResponse::Ok().json(serde_json::json!({
+                    "success": true,
+                    "account_name": account.account_name,
+                    "server_number": account.server_number,
+                    "servers": account.servers(),
+                    "alliance": sub_account.alliance
+                })))
             }
-        } else {
-            eprintln!("Failed to read directory: {}", current_forms_dir);
-        }
+            _ => Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Invalid username or password"
+            }))),
+        };
     }
     
-    if let Some(form) = current_form {
-        // Build form URL
-        let host = req.headers().get("host")
-            .and_then(|v| v.to_str().ok())
-            .unwrap_or("localhost:8080");
-        let protocol = if host.contains("localhost") { "http" } else { "https" };
-        let form_url = format!("{}://{}/form/{}", protocol, host, form.code);
-        
-        // Count submissions from CSV file
-        // The CSV header is multiline, so we count actual data rows by looking for timestamp pattern
-        // Data rows start with timestamp format: DD/MM/YYYY HH.MM.SS
-        // We check if a line starts with the timestamp pattern (2 digits/2 digits/4 digits)
-        let submissions_count = {
-            let csv_path = format!("{}/current_forms/{}_submissions.csv", state.data_dir, form.code);
-            if Path::new(&csv_path).exists() {
-                if let Ok(content) = std::fs::read_to_string(&csv_path) {
-                    // Count lines that start with a timestamp (DD/MM/YYYY format)
-                    // This pattern matches data rows, not header lines
-                    content.lines()
-                        .filter(|line| {
-                            let trimmed = line.trim();
-                            // Check if line starts with DD/MM/YYYY pattern (timestamp)
-                            trimmed.len() >= 10 && 
-                            trimmed.chars().take(2).all(|c| c.is_ascii_digit()) &&
-                            trimmed.chars().nth(2) == Some('/') &&
-                            trimmed.chars().skip(3).take(2).all(|c| c.is_ascii_digit()) &&
-                            trimmed.chars().nth(5) == Some('/') &&
-                            trimmed.chars().skip(6).take(4).all(|c| c.is_ascii_digit())
-                        })
-                        .count()
-                } else {
-                    0
-                }
-            } else {
-                0
-            }
-        };
-        
-        Ok(HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "form": {
-                "code": form.code,
-                "name": form.name,
-                "created_at": form.created_at,
-                "url": form_url,
-                "submissions_count": submissions_count,
-                "config": {
-                    "alliances": form.config.alliances,
-                    "construction_times": form.config.construction_times,
-                    "research_times": form.config.research_times,
-                    "troops_times": form.config.troops_times,
-                    "predetermined_slots": form.config.predetermined_slots,
-                    "intro_text": form.config.intro_text
-                }
+    if let Some(account) = accounts.get(&account_name) {
+        if account.password == req.password {
+            let server_number = req.server_number.unwrap_or(account.server_number);
+            if !account.owns_server(server_number) {
+                return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                    "success": false,
+                    "error": format!("Server {} is not managed by this account", server_number)
+                })));
             }
-        })))
+            
+            // Store account_name and the active server_number in session
+            session.insert("account_name", &account.account_name)
+                .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to set session: {}", e)))?;
+            session.insert("server_number", server_number)
+                .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to set session: {}", e)))?;
+            session.insert("session_version", account.session_version)
+                .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to set session: {}", e)))?;
+            session.remove("alliance_scope");
+            session.remove("sub_account");
+            session.remove("sub_account_id");
+            
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "account_name": account.account_name,
+                "server_number": server_number,
+                "servers": account.servers()
+            })))
+        } else {
+            Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Invalid password"
+            })))
+        }
     } else {
-        Ok(HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "form": null
+        Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Account not found"
         })))
     }
 }
 
-// Get player info by ID from form submissions
-async fn get_player_by_id(
-    path: web::Path<(String, u32, String)>,
+// Add another server to the logged-in account
+async fn add_account_server(
+    req: web::Json<ServerSelectRequest>,
     session: Session,
     state: web::Data<AppState>,
-) -> Result<HttpResponse> {
-    let (url_account_name, server_number, player_id) = path.into_inner();
-    let url_account_name = url_account_name.to_lowercase();
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
     
-    // Verify session authentication
-    let session_account_name: String = match session.get("account_name") {
+    let account_name: String = match session.get("account_name") {
         Ok(Some(name)) => name,
-        Ok(None) => {
+        _ => {
             return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
                 "success": false,
-                "error": "Not logged in"
+                "error": "Not authenticated"
             })));
         }
-        Err(_) => {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+    };
+    
+    let mut accounts = state.accounts.lock().unwrap();
+    let account = match accounts.get_mut(&account_name) {
+        Some(account) => account,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
                 "success": false,
-                "error": "Failed to read session"
+                "error": "Account not found"
             })));
         }
     };
-    let session_server_number: u32 = match session.get("server_number") {
-        Ok(Some(num)) => num,
-        Ok(None) => {
+    
+    if account.owns_server(req.server_number) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": format!("Server {} is already on this account", req.server_number)
+        })));
+    }
+    
+    account.additional_servers.push(req.server_number);
+    let servers = account.servers();
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    drop(accounts);
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "servers": servers
+    })))
+}
+
+// Switch the server the logged-in account is managing
+async fn switch_server(
+    req: web::Json<ServerSelectRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let account_name: String = match session.get("account_name") {
+        Ok(Some(name)) => name,
+        _ => {
             return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
                 "success": false,
-                "error": "Not logged in"
-            })));
-        }
-        Err(_) => {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "success": false,
-                "error": "Failed to read session"
+                "error": "Not authenticated"
             })));
         }
     };
     
-    // Verify account name and server number match session
-    if session_account_name.to_lowercase() != url_account_name || session_server_number != server_number {
-        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+    let owns_server = state.accounts.lock().unwrap()
+        .get(&account_name)
+        .is_some_and(|a| a.owns_server(req.server_number));
+    if !owns_server {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
             "success": false,
-            "error": "Not authorized"
+            "error": format!("Server {} is not managed by this account", req.server_number)
         })));
     }
     
-    // Get current form to find CSV path
-    let forms = state.forms.lock().unwrap();
-    let current_forms = state.current_forms.lock().unwrap();
-    let csv_path = if let Some(current_form) = get_current_form(&forms, &current_forms, &url_account_name, server_number) {
-        format!("{}/current_forms/{}_submissions.csv", state.data_dir, current_form.code)
-    } else {
-        drop(forms);
-        drop(current_forms);
-        return Ok(HttpResponse::NotFound().json(serde_json::json!({
-            "success": false,
-            "error": "No current form found"
-        })));
+    session.insert("server_number", req.server_number)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to set session: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "server_number": req.server_number
+    })))
+}
+
+// List the alliance sub-accounts of the logged-in account (passwords are not returned)
+async fn list_sub_accounts(session: Session, state: web::Data<AppState>) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let account_name: String = match session.get("account_name") {
+        Ok(Some(name)) => name,
+        _ => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not authenticated"
+            })));
+        }
     };
-    drop(forms);
-    drop(current_forms);
     
-    // Load submissions and find player by ID
-    if !Path::new(&csv_path).exists() {
-        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+    let accounts = state.accounts.lock().unwrap();
+    let sub_accounts: Vec<serde_json::Value> = accounts.get(&account_name)
+        .map(|a| a.sub_accounts.iter()
+            .map(|s| serde_json::json!({"username": s.username, "alliance": s.alliance}))
+            .collect())
+        .unwrap_or_default();
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "sub_accounts": sub_accounts
+    })))
+}
+
+// Create an alliance sub-account for the logged-in account
+async fn create_sub_account(
+    req: web::Json<CreateSubAccountRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let account_name: String = match session.get("account_name") {
+        Ok(Some(name)) => name,
+        _ => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not authenticated"
+            })));
+        }
+    };
+    
+    let username = req.username.trim().to_lowercase();
+    let alliance = req.alliance.trim().to_string();
+    if username.is_empty() || alliance.is_empty() || req.password.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
             "success": false,
-            "error": "Form submissions not found"
+            "error": "Username, password and alliance are required"
         })));
     }
     
-    // Load appointments (which includes player info)
-    let entries = match load_appointments(&csv_path, None, None, None) {
-        Ok(e) => e,
-        Err(_) => {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+    let mut accounts = state.accounts.lock().unwrap();
+    let account = match accounts.get_mut(&account_name) {
+        Some(account) => account,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
                 "success": false,
-                "error": "Failed to load form submissions"
+                "error": "Account not found"
             })));
         }
     };
     
-    // Find player by ID
-    if let Some(entry) = entries.iter().find(|e| e.player_id == player_id) {
-        Ok(HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "player": {
-                "player_id": entry.player_id,
-                "name": entry.name,
-                "alliance": entry.alliance
-            }
-        })))
-    } else {
-        Ok(HttpResponse::NotFound().json(serde_json::json!({
+    if account.sub_accounts.iter().any(|s| s.username == username) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
             "success": false,
-            "error": "Player ID not found in form submissions"
-        })))
+            "error": "A login with this username already exists"
+        })));
     }
+    
+    account.sub_accounts.push(SubAccount {
+        id: generate_form_code(),
+        username: username.clone(),
+        password: req.password.clone(),
+        alliance: alliance.clone(),
+    });
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "username": username,
+        "alliance": alliance
+    })))
 }
 
-// Download current form CSV submissions
-async fn download_form_csv(
-    path: web::Path<(String, u32)>,
+// Delete an alliance sub-account from the logged-in account
+async fn delete_sub_account(
+    path: web::Path<String>,
     session: Session,
     state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
-    let (url_account_name, server_number) = path.into_inner();
-    let url_account_name = url_account_name.to_lowercase();
-    
-    // Verify session authentication
-    let session_account_name: String = match session.get("account_name") {
+    require_owner(&session)?;
+    let account_name: String = match session.get("account_name") {
         Ok(Some(name)) => name,
-        Ok(None) => {
+        _ => {
             return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
                 "success": false,
-                "error": "Not logged in"
+                "error": "Not authenticated"
             })));
         }
-        Err(_) => {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+    };
+    let username = path.into_inner().to_lowercase();
+    
+    let mut accounts = state.accounts.lock().unwrap();
+    let removed = match accounts.get_mut(&account_name) {
+        Some(account) => {
+            let before = account.sub_accounts.len();
+            account.sub_accounts.retain(|s| s.username != username);
+            account.sub_accounts.len() != before
+        }
+        None => false,
+    };
+    
+    if !removed {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Login not found"
+        })));
+    }
+    
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true
+    })))
+}
+
+// How long a transfer code stays valid
+const TRANSFER_CODE_HOURS: i64 = 48;
+
+// The logged-in owner's account name; None for alliance logins and anonymous sessions
+fn session_owner(session: &Session) -> Option<String> {
+    if session_alliance_scope(session).is_some() {
+        return None;
+    }
+    session.get::<String>("account_name").ok().flatten()
+}
+
+// Pending transfer of the logged-in account, if any
+async fn get_ownership_transfer(session: Session, state: web::Data<AppState>) -> Result<HttpResponse> {
+    let account_name = match session_owner(&session) {
+        Some(name) => name,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
                 "success": false,
-                "error": "Failed to read session"
+                "error": "Not authenticated"
             })));
         }
     };
-    let session_server_number: u32 = match session.get("server_number") {
-        Ok(Some(num)) => num,
-        Ok(None) => {
+    
+    let transfer = state.accounts.lock().unwrap()
+        .get(&account_name)
+        .and_then(|a| a.pending_transfer.clone());
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "transfer": transfer
+    })))
+}
+
+// Generate a transfer code for the logged-in account, replacing any earlier code
+async fn start_ownership_transfer(session: Session, state: web::Data<AppState>) -> Result<HttpResponse> {
+    let account_name = match session_owner(&session) {
+        Some(name) => name,
+        None => {
             return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
                 "success": false,
-                "error": "Not logged in"
+                "error": "Only the account owner can transfer ownership"
             })));
         }
-        Err(_) => {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+    };
+    
+    let mut accounts = state.accounts.lock().unwrap();
+    let account = match accounts.get_mut(&account_name) {
+        Some(account) => account,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
                 "success": false,
-                "error": "Failed to read session"
+                "error": "Account not found"
             })));
         }
     };
     
-    // Verify account name and server number match session
-    if session_account_name.to_lowercase() != url_account_name || session_server_number != server_number {
-        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
-            "success": false,
-            "error": "Unauthorized"
-        })));
-    }
-    
-    // Get current form to find CSV file
-    let forms = state.forms.lock().unwrap();
-    let current_forms = state.current_forms.lock().unwrap();
-    let mut current_form = get_current_form(&forms, &current_forms, &url_account_name, server_number);
-    drop(forms);
-    drop(current_forms);
+    let transfer = OwnershipTransfer {
+        code: generate_form_code(),
+        expires_at: (chrono::Local::now() + chrono::Duration::hours(TRANSFER_CODE_HOURS)).to_rfc3339(),
+    };
+    account.pending_transfer = Some(transfer.clone());
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
     
-    // If not found in mapping, check files in current_forms folder
-    if current_form.is_none() {
-        let current_forms_dir = format!("{}/current_forms", state.data_dir);
-        if let Ok(entries) = std::fs::read_dir(&current_forms_dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.extension().and_then(|s| s.to_str()) == Some("json") {
-                    if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                        if file_name.contains("_submissions") {
-                            continue;
-                        }
-                        if let Ok(content) = std::fs::read_to_string(&path) {
-                            if let Ok(mut form_data) = serde_json::from_str::<FormData>(&content) {
-                                let form_account_name = form_data.account_name.to_lowercase();
-                                if form_account_name == url_account_name && form_data.server_number == server_number {
-                                    form_data.account_name = form_account_name;
-                                    current_form = Some(form_data);
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "transfer": transfer
+    })))
+}
+
+// Invalidate the logged-in account's transfer code
+async fn cancel_ownership_transfer(session: Session, state: web::Data<AppState>) -> Result<HttpResponse> {
+    let account_name = match session_owner(&session) {
+        Some(name) => name,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Only the account owner can transfer ownership"
+            })));
         }
+    };
+    
+    let mut accounts = state.accounts.lock().unwrap();
+    if let Some(account) = accounts.get_mut(&account_name) {
+        account.pending_transfer = None;
+    }
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true
+    })))
+}
+
+// Claim an account with a transfer code. The new owner sets their own password,
+// every existing session is logged out and the previous owner's alliance logins are removed.
+async fn claim_ownership_transfer(
+    req: web::Json<ClaimTransferRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let account_name = req.account_name.trim().to_lowercase();
+    let in_game_name = req.in_game_name.trim().to_string();
+    if req.password.is_empty() || in_game_name.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "A new password and in-game name are required"
+        })));
     }
     
-    if let Some(form) = current_form {
-        let csv_path = format!("{}/current_forms/{}_submissions.csv", state.data_dir, form.code);
-        if Path::new(&csv_path).exists() {
-            if let Ok(csv_content) = std::fs::read_to_string(&csv_path) {
-                let filename = format!("{}_submissions_{}.csv", form.code, 
-                    chrono::Utc::now().format("%Y%m%d_%H%M%S"));
-                return Ok(HttpResponse::Ok()
-                    .content_type("text/csv")
-                    .append_header(("Content-Disposition", format!("attachment; filename=\"{}\"", filename)))
-                    .body(csv_content));
-            }
+    let mut accounts = state.accounts.lock().unwrap();
+    let account = match accounts.get_mut(&account_name) {
+        Some(account) => account,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Account not found"
+            })));
         }
-        return Ok(HttpResponse::NotFound().json(serde_json::json!({
-            "success": false,
-            "error": "CSV file not found"
-        })));
-    } else {
-        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+    };
+    
+    let valid = account.pending_transfer.as_ref().is_some_and(|t| {
+        t.code == req.code.trim()
+            && chrono::DateTime::parse_from_rfc3339(&t.expires_at)
+                .is_ok_and(|expires| expires > chrono::Local::now())
+    });
+    if !valid {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
             "success": false,
-            "error": "No current form found"
+            "error": "Invalid or expired transfer code"
         })));
     }
+    
+    account.hand_over(req.password.clone(), in_game_name);
+    let server_number = account.server_number;
+    let session_version = account.session_version;
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    drop(accounts);
+    
+    // Log the new owner in
+    session.renew();
+    session.insert("account_name", &account_name)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to set session: {}", e)))?;
+    session.insert("server_number", server_number)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to set session: {}", e)))?;
+    session.insert("session_version", session_version)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to set session: {}", e)))?;
+    session.remove("alliance_scope");
+    session.remove("sub_account");
+    session.remove("sub_account_id");
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "account_name": account_name,
+        "server_number": server_number
+    })))
 }
 
-// Get previous form config for account (admin - to load when creating new form)
-async fn get_previous_form_config(
-    path: web::Path<(String, u32)>,
-    session: Session,
-    state: web::Data<AppState>,
-) -> Result<HttpResponse> {
-    let (url_account_name, server_number) = path.into_inner();
-    let url_account_name = url_account_name.to_lowercase();
-    
-    // Verify session authentication
-    let session_account_name: String = match session.get("account_name") {
-        Ok(Some(name)) => name,
-        Ok(None) => {
+// How long a password reset code stays valid
+const RESET_CODE_MINUTES: i64 = 60;
+
+// Recovery email of the logged-in account, and whether this server can send to it
+async fn get_recovery_email(session: Session, state: web::Data<AppState>) -> Result<HttpResponse> {
+    let account_name = match session_owner(&session) {
+        Some(name) => name,
+        None => {
             return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
                 "success": false,
-                "error": "Not logged in"
+                "error": "Not authenticated"
             })));
         }
-        Err(_) => {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+    };
+    
+    let email = state.accounts.lock().unwrap()
+        .get(&account_name)
+        .and_then(|a| a.recovery_email.clone());
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "email": email,
+        "email_available": state.mailer.is_some()
+    })))
+}
+
+// Set the recovery email of the logged-in account; a blank address clears it
+async fn update_recovery_email(
+    req: web::Json<RecoveryEmailRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let account_name = match session_owner(&session) {
+        Some(name) => name,
+        None => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
                 "success": false,
-                "error": "Failed to read session"
+                "error": "Only the account owner can change the recovery email"
             })));
         }
     };
-    let session_server_number: u32 = match session.get("server_number") {
-        Ok(Some(num)) => num,
-        Ok(None) => {
-            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+    
+    let email = req.email.as_deref().map(str::trim).filter(|e| !e.is_empty()).map(str::to_string);
+    if let Some(Err(e)) = email.as_deref().map(mailer::validate_address) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": e
+        })));
+    }
+    
+    let mut accounts = state.accounts.lock().unwrap();
+    let account = match accounts.get_mut(&account_name) {
+        Some(account) => account,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
                 "success": false,
-                "error": "Not logged in"
+                "error": "Account not found"
             })));
         }
-        Err(_) => {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+    };
+    account.recovery_email = email.clone();
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "email": email
+    })))
+}
+
+// Email a reset code to the account's recovery address. The reply is the same whether or not
+// the account exists or has an address, so the form can't be used to look up accounts.
+async fn request_password_reset(
+    req: web::Json<PasswordResetRequest>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let mailer = match &state.mailer {
+        Some(mailer) => mailer.clone(),
+        None => {
+            return Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
                 "success": false,
-                "error": "Failed to read session"
+                "error": "Password reset by email isn't available on this server; ask the operator"
             })));
         }
     };
+    let account_name = req.account_name.trim().to_lowercase();
     
-    // Verify the account name and server number match
-    if session_account_name != url_account_name || session_server_number != server_number {
-        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
-            "success": false,
-            "error": "Access denied"
-        })));
+    let mut accounts = state.accounts.lock().unwrap();
+    let recipient = accounts.get_mut(&account_name)
+        .filter(|a| !a.suspended)
+        .and_then(Account::start_password_reset);
+    if recipient.is_some() {
+        save_accounts(&state.data_dir, &accounts).map_err(|e| {
+            actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+        })?;
     }
+    drop(accounts);
     
-    let account_name = url_account_name;
-    
-    // Find the most recent form for this account (get the one with latest created_at)
-    let forms = state.forms.lock().unwrap();
-    let mut previous_form: Option<FormData> = None;
-    for form_data in forms.values() {
-        if form_data.account_name == account_name && form_data.server_number == server_number {
-            match &previous_form {
-                None => previous_form = Some(form_data.clone()),
-                Some(current) => {
-                    // Compare by created_at to get most recent
-                    if let (Ok(current_time), Ok(new_time)) = (
-                        chrono::DateTime::parse_from_rfc3339(&current.created_at),
-                        chrono::DateTime::parse_from_rfc3339(&form_data.created_at)
-                    ) {
-                        if new_time > current_time {
-                            previous_form = Some(form_data.clone());
-                        }
-                    } else {
-                        // If parsing fails, just use the new one
-                        previous_form = Some(form_data.clone());
-                    }
-                }
+    if let Some((to, code)) = recipient {
+        let reset_url = mailer.link(&format!("/reset-password?account={}&code={}", account_name, code));
+        let email = mailer::render(format!("Password reset for {}", account_name), &PasswordResetEmail {
+            account_name: &account_name,
+            code: &code,
+            valid_minutes: RESET_CODE_MINUTES,
+            reset_url,
+        });
+        // Sent in the background so the reply takes as long for unknown accounts
+        std::thread::spawn(move || {
+            if let Err(e) = email.and_then(|email| mailer.send(&to, &email)) {
+                log::warn!("Failed to send the password reset of {}: {}", account_name, e);
             }
-        }
+        });
     }
-    drop(forms);
     
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "success": true,
-        "config": previous_form.map(|f| f.config)
+        "message": "If the account has a recovery email, a reset code is on its way"
     })))
 }
 
-// Home page
-async fn index() -> Result<HttpResponse> {
-    let html = include_str!("../templates/index.html");
-    Ok(HttpResponse::Ok().content_type("text/html").body(html))
-}
-
-// List all servers page
-async fn servers_list_page() -> Result<HttpResponse> {
-    let html = include_str!("../templates/servers_list.html");
-    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+// Set a new password with an emailed reset code. Every existing session is logged out.
+async fn confirm_password_reset(
+    req: web::Json<ConfirmPasswordResetRequest>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let account_name = req.account_name.trim().to_lowercase();
+    if req.password.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "error": "A new password is required"
+        })));
+    }
+    
+    let mut accounts = state.accounts.lock().unwrap();
+    let account = accounts.get_mut(&account_name).filter(|account| {
+        account.pending_reset.as_ref().is_some_and(|r| {
+            r.code == req.code.trim()
+                && chrono::DateTime::parse_from_rfc3339(&r.expires_at)
+                    .is_ok_and(|expires| expires > chrono::Local::now())
+        })
+    });
+    let account = match account {
+        Some(account) => account,
+        None => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": "Invalid or expired reset code"
+            })));
+        }
+    };
+    
+    account.password = req.password.clone();
+    account.pending_reset = None;
+    account.session_version += 1;
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true
+    })))
 }
 
-// View-only schedule page (public, no admin/stats buttons)
-async fn view_schedule_page(_path: web::Path<(String, u32)>) -> Result<HttpResponse> {
-    let html = include_str!("../templates/view_schedule.html");
-    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+// Counts submission rows (rows starting with a DD/MM/YYYY timestamp) in a form CSV
+fn count_submissions(csv_path: &Path) -> usize {
+    let mut reader = match csv::ReaderBuilder::new().flexible(true).from_path(csv_path) {
+        Ok(reader) => reader,
+        Err(_) => return 0,
+    };
+    reader.records()
+        .filter_map(|r| r.ok())
+        .filter(|record| {
+            let first_field = record.get(0).unwrap_or("");
+            first_field.contains('/') && first_field.len() >= 8
+        })
+        .count()
 }
 
-// Dashboard page (for logged-in users - requires authentication)
-async fn dashboard_page(path: web::Path<String>, session: Session) -> Result<HttpResponse> {
-    let url_account_name = path.into_inner().to_lowercase();
-    
-    // Check if user is logged in
-    let session_account_name: Option<String> = session.get("account_name")
-        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to read session"))?;
-    
-    // Verify the account_name in URL matches the logged-in account
-    match session_account_name {
-        Some(account_name) if account_name == url_account_name => {
-            // User is authenticated and accessing their own dashboard
-            let html = include_str!("../templates/dashboard.html");
-    Ok(HttpResponse::Ok().content_type("text/html").body(html))
-}
-        Some(_) => {
-            // User is logged in but trying to access someone else's dashboard
-            Ok(HttpResponse::Forbidden().content_type("text/html").body(
-                "<html><body><h1>Access Denied</h1><p>You can only access your own dashboard.</p><a href='/'>Go Home</a></body></html>"
-            ))
+// Adds the size and modification time of a file (or every file under a directory) to the totals
+fn add_path_usage(path: &Path, disk_bytes: &mut u64, last_modified: &mut Option<std::time::SystemTime>) {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return,
+    };
+    if metadata.is_dir() {
+        if let Ok(entries) = std::fs::read_dir(path) {
+            for entry in entries.flatten() {
+                add_path_usage(&entry.path(), disk_bytes, last_modified);
+            }
         }
-        None => {
-            // User is not logged in
-            Ok(HttpResponse::Unauthorized().content_type("text/html").body(
-                "<html><body><h1>Unauthorized</h1><p>Please log in to access the dashboard.</p><a href='/'>Go Home</a></body></html>"
-            ))
+        return;
+    }
+    *disk_bytes += metadata.len();
+    if let Ok(modified) = metadata.modified() {
+        if last_modified.is_none_or(|last| modified > last) {
+            *last_modified = Some(modified);
         }
     }
 }
 
-// Get session info endpoint (for dashboard to get account/server info)
-async fn get_session_info(session: Session) -> Result<HttpResponse> {
-    let account_name: Option<String> = session.get("account_name")
-        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to read session"))?;
-    let server_number: Option<u32> = session.get("server_number")
-        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to read session"))?;
+// Total size and newest modification time of every file belonging to an account
+fn account_disk_usage(data_dir: &str, account_name: &str, forms: &HashMap<String, FormData>) -> (u64, Option<std::time::SystemTime>) {
+    let mut disk_bytes = 0;
+    let mut last_modified = None;
+    
+    for form in forms.values().filter(|f| f.account_name == account_name) {
+        add_path_usage(Path::new(&format!("{}/current_forms/{}_submissions.csv", data_dir, form.code)), &mut disk_bytes, &mut last_modified);
+        add_path_usage(Path::new(&format!("{}/current_forms/{}.json", data_dir, form.code)), &mut disk_bytes, &mut last_modified);
+        add_path_usage(&draft::drafts_dir(data_dir, &form.code), &mut disk_bytes, &mut last_modified);
+    }
     
-    if let (Some(account_name), Some(server_number)) = (account_name, server_number) {
-        Ok(HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "account_name": account_name,
-            "server_number": server_number
-        })))
-    } else {
-        Ok(HttpResponse::Unauthorized().json(serde_json::json!({
-            "success": false,
-            "error": "Not authenticated"
-        })))
+    add_path_usage(Path::new(&format!("{}/schedules/{}", data_dir, account_name)), &mut disk_bytes, &mut last_modified);
+    add_path_usage(Path::new(&format!("{}/statistics/{}", data_dir, account_name)), &mut disk_bytes, &mut last_modified);
+    add_path_usage(Path::new(&format!("{}/rosters/{}", data_dir, account_name)), &mut disk_bytes, &mut last_modified);
+    add_path_usage(Path::new(&format!("{}/power/{}", data_dir, account_name)), &mut disk_bytes, &mut last_modified);
+    
+    // Archived forms and uploaded/legacy CSVs are prefixed with the account name
+    let prefix = format!("{}_", account_name);
+    for dir in [format!("{}/old_forms", data_dir), data_dir.to_string()] {
+        if let Ok(entries) = std::fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                    add_path_usage(&entry.path(), &mut disk_bytes, &mut last_modified);
+                }
+            }
+        }
     }
+    
+    (disk_bytes, last_modified)
 }
 
-// Logout endpoint
-async fn logout_api(session: Session) -> Result<HttpResponse> {
-    session.purge();
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "success": true,
-        "message": "Logged out successfully"
-    })))
-}
-
-// API endpoint to list all servers
-async fn list_servers(state: web::Data<AppState>) -> Result<HttpResponse> {
-    let accounts = state.accounts.lock().unwrap();
-    let mut servers: Vec<ServerInfo> = accounts.values()
-        .map(|acc| ServerInfo {
-            account_name: acc.account_name.clone(),
-            server_number: acc.server_number,
+// Collects form, submission and disk usage for one account
+fn account_usage(data_dir: &str, account: &Account, forms: &HashMap<String, FormData>, default_limits: &AccountLimits) -> AccountUsage {
+    let mut active_forms: Vec<ActiveFormUsage> = forms.values()
+        .filter(|f| f.account_name == account.account_name)
+        .map(|f| ActiveFormUsage {
+            code: f.code.clone(),
+            name: f.name.clone(),
+            server_number: f.server_number,
+            submissions: count_submissions(Path::new(&format!("{}/current_forms/{}_submissions.csv", data_dir, f.code))),
         })
         .collect();
-    drop(accounts);
-    
-    // Sort by account name, then server number
-    servers.sort_by(|a, b| {
-        a.account_name.cmp(&b.account_name)
-            .then_with(|| a.server_number.cmp(&b.server_number))
-    });
-    
-    Ok(HttpResponse::Ok().json(servers))
+    active_forms.sort_by_key(|f| f.server_number);
+    
+    let (disk_bytes, last_modified) = account_disk_usage(data_dir, &account.account_name, forms);
+    
+    AccountUsage {
+        account_name: account.account_name.clone(),
+        in_game_name: account.in_game_name.clone(),
+        servers: account.servers(),
+        suspended: account.suspended,
+        total_submissions: active_forms.iter().map(|f| f.submissions).sum(),
+        active_forms,
+        last_activity: last_modified.map(|t| chrono::DateTime::<chrono::Local>::from(t).to_rfc3339()),
+        disk_bytes,
+        limits: account.limits.or(default_limits),
+        limit_overrides: account.limits.clone(),
+    }
 }
 
-#[derive(Deserialize)]
-struct GenerateScheduleRequest {
-    #[serde(default)]
-    append: bool,
+// Whether the session belongs to a logged-in operator
+fn is_operator(session: &Session) -> bool {
+    session.get::<bool>("operator").ok().flatten().unwrap_or(false)
 }
 
-// Generate schedule endpoint (from form submissions)
-async fn generate_schedule_api(
-    payload: Option<web::Json<GenerateScheduleRequest>>,
+// Operator login endpoint (uses the ADMIN_PASSWORD the server was started with)
+async fn operator_login(
+    req: web::Json<OperatorLoginRequest>,
     session: Session,
     state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
-    let append = payload.as_ref().map(|p| p.append).unwrap_or(false);
-    // Get account_name and server_number from session
-    let account_name: String = match session.get("account_name") {
-        Ok(Some(name)) => name,
-        Ok(None) => {
-            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
-                "success": false,
-                "error": "Not logged in"
-            })));
-        }
-        Err(_) => {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "success": false,
-                "error": "Failed to read session"
-            })));
-        }
-    };
-    let server_number: u32 = match session.get("server_number") {
-        Ok(Some(num)) => num,
-        Ok(None) => {
-            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
-                "success": false,
-                "error": "Not logged in"
-            })));
-        }
-        Err(_) => {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+    let admin_password = match &state.admin_password {
+        Some(password) => password,
+        None => {
+            return Ok(HttpResponse::Forbidden().json(serde_json::json!({
                 "success": false,
-                "error": "Failed to read session"
+                "error": "The operator dashboard is off; start the server with ADMIN_PASSWORD set"
             })));
         }
     };
-    
-    let account_name = account_name.to_lowercase();
-    let key = schedule_key(&account_name, server_number);
-    
-    // Get current form to find CSV path
-    let (form_csv_path, form_config, form_code) = {
-        let forms = state.forms.lock().unwrap();
-        let current_forms = state.current_forms.lock().unwrap();
-        if let Some(current_form) = get_current_form(&forms, &current_forms, &account_name, server_number) {
-            let csv_path = format!("{}/current_forms/{}_submissions.csv", state.data_dir, current_form.code.clone());
-            (csv_path, Some(current_form.config.clone()), Some(current_form.code.clone()))
-        } else {
-            // Try old location for migration
-            let csv_path = format!("{}/{}_{}_form_submissions.csv", state.data_dir, account_name, server_number);
-            (csv_path, None, None)
-        }
-    };
-    
-    // Verify we have a current form
-    if form_code.is_none() {
-        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+    if req.password != *admin_password {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
             "success": false,
-            "error": "No current form found. Please create a form first."
+            "error": "Invalid password"
         })));
     }
     
-    if !Path::new(&form_csv_path).exists() {
-        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+    session.insert("operator", true)
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to set session: {}", e)))?;
+    
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true
+    })))
+}
+
+// List every account with its usage (operator only)
+async fn operator_list_accounts(session: Session, state: web::Data<AppState>) -> Result<HttpResponse> {
+    if !is_operator(&session) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
             "success": false,
-            "error": "No form submissions found. Please create a form and have players submit responses first."
+            "error": "Not authenticated"
         })));
     }
     
-    let (construction_slots, research_slots, troops_slots) = if let Some(config) = &form_config {
-        (
-            Some(calculate_time_slots(&config.construction_times.start_time, config.construction_times.end_time.as_deref())),
-            Some(calculate_time_slots(&config.research_times.start_time, config.research_times.end_time.as_deref())),
-            Some(calculate_time_slots(&config.troops_times.start_time, config.troops_times.end_time.as_deref())),
-        )
-    } else {
-        (None, None, None)
-    };
+    let accounts: Vec<Account> = state.accounts.lock().unwrap().values().cloned().collect();
+    let forms = state.forms.lock().unwrap().clone();
     
-    // Load form submissions
-    let entries = match load_appointments(
-        &form_csv_path,
-        construction_slots.as_ref().map(|v| v.as_slice()),
-        research_slots.as_ref().map(|v| v.as_slice()),
-        troops_slots.as_ref().map(|v| v.as_slice()),
-    ) {
-        Ok(e) => e,
-        Err(e) => {
-            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                "success": false,
-                "error": format!("Failed to load form submissions: {}", e)
-            })));
-        }
-    };
+    let mut usage: Vec<AccountUsage> = accounts.iter()
+        .map(|account| account_usage(&state.data_dir, account, &forms, &state.default_limits))
+        .collect();
+    usage.sort_by(|a, b| a.account_name.cmp(&b.account_name));
     
-    if entries.is_empty() {
-        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "accounts": usage
+    })))
+}
+
+// Suspend or reinstate an account (operator only)
+async fn operator_suspend_account(
+    path: web::Path<String>,
+    req: web::Json<SuspendAccountRequest>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    if !is_operator(&session) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
             "success": false,
-            "error": "No valid form submissions found."
+            "error": "Not authenticated"
         })));
     }
+    let account_name = path.into_inner().to_lowercase();
     
-    // Load existing schedule when appending (from in-memory state or disk)
-    // Note: Don't hold lock during load_schedule (file I/O) to avoid blocking other requests
-    let existing_schedule = if append {
-        let maybe_cached = {
-            let schedules = state.schedules.lock().unwrap();
-            schedules.get(&key).cloned()
-        };
-        maybe_cached.or_else(|| load_schedule(&state.data_dir, &account_name, server_number))
-    } else {
-        None
-    };
-    
-    let (entries_to_use, existing_construction_slots, existing_research_slots, existing_troops_slots, existing_appointments) = if let Some(ref existing) = existing_schedule {
-        // Collect existing slot numbers per day (these will be locked)
-        let existing_construction_slots: HashSet<u8> = existing.construction_schedule.as_ref()
-            .map(|s| s.appointments.keys().copied().collect())
-            .unwrap_or_default();
-        let existing_research_slots: HashSet<u8> = existing.research_schedule.as_ref()
-            .map(|s| s.appointments.keys().copied().collect())
-            .unwrap_or_default();
-        let existing_troops_slots: HashSet<u8> = existing.troops_schedule.as_ref()
-            .map(|s| s.appointments.keys().copied().collect())
-            .unwrap_or_default();
-        
-        // Use scheduled_player_ids (ID-based) to filter - players already in schedule are excluded
-        let scheduled_player_ids = get_scheduled_player_ids(existing);
-        let entries_filtered: Vec<AppointmentEntry> = entries.iter()
-            .filter(|e| !scheduled_player_ids.contains(&e.player_id))
-            .cloned()
-            .collect();
-        
-        (
-            entries_filtered,
-            existing_construction_slots,
-            existing_research_slots,
-            existing_troops_slots,
-            (existing.construction_schedule.clone(), existing.research_schedule.clone(), existing.troops_schedule.clone()),
-        )
-    } else {
-        (
-            entries.clone(),
-            HashSet::new(),
-            HashSet::new(),
-            HashSet::new(),
-            (None, None, None),
-        )
-    };
+    let mut accounts = state.accounts.lock().unwrap();
+    match accounts.get_mut(&account_name) {
+        Some(account) => account.suspended = req.suspended,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Account not found"
+            })));
+        }
+    }
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
     
-    // When appending: if all form submissions are already in the schedule, nothing to add
-    if append && existing_schedule.is_some() && entries_to_use.is_empty() {
-        return Ok(HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "message": "All form submissions are already in the schedule. No new assignments to add."
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "account_name": account_name,
+        "suspended": req.suspended
+    })))
+}
+
+// Set per-account limit overrides (operator only). Unset fields fall back to the instance defaults.
+async fn operator_update_limits(
+    path: web::Path<String>,
+    req: web::Json<AccountLimits>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    if !is_operator(&session) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authenticated"
         })));
     }
+    let account_name = path.into_inner().to_lowercase();
     
-    // Helper function to convert time string to slot number using form's time configuration
-    // Falls back to default time mapping if custom slots are empty or time not found
-    let time_to_slot = |time_str: &str, time_slots: &[(u8, String)]| -> Option<u8> {
-        let clean_time = time_str.trim();
-        
-        // First try to find in custom time slots
-        if !time_slots.is_empty() {
-            if let Some(slot) = time_slots.iter()
-                .find(|(_, time)| time.trim() == clean_time)
-                .map(|(slot, _)| *slot) {
-                return Some(slot);
-            }
-        }
-        
-        // Fallback to default time mapping (same logic as parser.rs::time_to_slot)
-        // Handle "00:00" case
-        if clean_time == "00:00" {
-            return Some(1);
-        }
-        
-        // Parse HH:MM format
-        let parts: Vec<&str> = clean_time.split(':').collect();
-        if parts.len() != 2 {
-            return None;
-        }
-        
-        let hours: u32 = match parts[0].parse() {
-            Ok(h) => h,
-            Err(_) => return None,
-        };
-        let minutes: u32 = match parts[1].parse() {
-            Ok(m) => m,
-            Err(_) => return None,
-        };
-        
-        // Convert to total minutes
-        let total_minutes = hours * 60 + minutes;
-        
-        // Special cases for the first slots
-        if total_minutes == 0 {
-            Some(1) // 00:00
-        } else if total_minutes == 15 {
-            Some(2) // 00:15
-        } else if total_minutes == 45 {
-            Some(3) // 00:45
-        } else if total_minutes > 45 {
-            // For times after 00:45, calculate slot based on 30-minute increments
-            // Slot 3 is at 00:45 (45 minutes), so slot 4 should be at 01:15 (75 minutes)
-            // The pattern: slot = 3 + ((total_minutes - 45) / 30)
-            let slot = 3 + ((total_minutes - 45) / 30);
-            if slot <= 49 {
-                Some(slot as u8)
-            } else {
-                None
-            }
-        } else {
-            None
+    let mut accounts = state.accounts.lock().unwrap();
+    let account = match accounts.get_mut(&account_name) {
+        Some(account) => account,
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Account not found"
+            })));
         }
     };
+    account.limits = req.into_inner();
+    let limits = account.limits.or(&state.default_limits);
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
     
-    // Process predetermined slots if form config has them
-    // Collect predetermined slot numbers FIRST so we can pass them as pre_locked_slots
-    let mut construction_predetermined_slots = HashSet::new();
-    let mut research_predetermined_slots = HashSet::new();
-    let mut troops_predetermined_slots = HashSet::new();
-    
-    // Declare schedule variables outside the if/else blocks
-    let (construction_schedule, research_schedule, troops_schedule) = if let Some(config) = &form_config {
-        if !config.predetermined_slots.is_empty() {
-            // Get time slot mappings
-            let construction_slots_vec = construction_slots.as_ref().cloned().unwrap_or_default();
-            let research_slots_vec = research_slots.as_ref().cloned().unwrap_or_default();
-            let troops_slots_vec = troops_slots.as_ref().cloned().unwrap_or_default();
-            
-            // Validation: Resolve all predetermined slots - get player_id (from slot or lookup) and resolve slot numbers
-            let mut invalid_slots: Vec<String> = Vec::new();
-            let mut resolved_slots: Vec<(String, u8, String, String, String)> = Vec::new(); // day, slot, player_id, alliance, name
-            let mut seen_slots: std::collections::HashMap<String, String> = std::collections::HashMap::new();
-            
-            for pred_slot in &config.predetermined_slots {
-                // Resolve player_id: use from slot if present, else lookup by alliance+name in entries
-                let (player_id, alliance, name) = if let Some(ref pid) = pred_slot.player_id {
-                    if !pid.trim().is_empty() {
-                        let (a, n) = entries.iter()
-                            .find(|e| e.player_id == pid.trim())
-                            .map(|e| (e.alliance.clone(), e.name.clone()))
-                            .unwrap_or((pred_slot.alliance.clone(), pred_slot.name.clone()));
-                        (pid.trim().to_string(), a, n)
-                    } else {
-                        let entry = entries.iter().find(|e| {
-                            e.alliance.trim().eq_ignore_ascii_case(&pred_slot.alliance.trim()) &&
-                            e.name.trim().eq_ignore_ascii_case(&pred_slot.name.trim())
-                        });
-                        match entry {
-                            Some(e) => (e.player_id.clone(), e.alliance.clone(), e.name.clone()),
-                            None => return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                                "success": false,
-                                "error": format!(
-                                    "Predetermined slot for {} {}: Player ID required. Enter player ID in the form, or ensure {} {} has submitted the form.",
-                                    pred_slot.day, pred_slot.time, pred_slot.alliance, pred_slot.name
-                                )
-                            }))),
-                        }
-                    }
-                } else {
-                    let entry = entries.iter().find(|e| {
-                        e.alliance.trim().eq_ignore_ascii_case(&pred_slot.alliance.trim()) &&
-                        e.name.trim().eq_ignore_ascii_case(&pred_slot.name.trim())
-                    });
-                    match entry {
-                        Some(e) => (e.player_id.clone(), e.alliance.clone(), e.name.clone()),
-                        None => return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                            "success": false,
-                            "error": format!(
-                                "Predetermined slot for {} {}: Could not resolve player ID for {} {}. They must have submitted the form, or use player ID.",
-                                pred_slot.day, pred_slot.time, pred_slot.alliance, pred_slot.name
-                            )
-                        }))),
-                    }
-                };
-                
-                // Validation: Check for duplicate predetermined slots (same day + time)
-                let slot_key = format!("{}:{}", pred_slot.day, pred_slot.time.trim());
-                if let Some(prev_id) = seen_slots.get(&slot_key) {
-                    return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                        "success": false,
-                        "error": format!(
-                            "Conflict: Multiple players predetermined for {} {} (player IDs {} and {})",
-                            pred_slot.day, pred_slot.time, prev_id, player_id
-                        )
-                    })));
-                }
-                seen_slots.insert(slot_key, player_id.clone());
-                
-                let slot_num = match pred_slot.day.as_str() {
-                    "construction" => time_to_slot(&pred_slot.time, &construction_slots_vec),
-                    "research" => time_to_slot(&pred_slot.time, &research_slots_vec),
-                    "troops" => time_to_slot(&pred_slot.time, &troops_slots_vec),
-                    _ => None,
-                };
-                
-                match slot_num {
-                    Some(slot) => {
-                        resolved_slots.push((pred_slot.day.clone(), slot, player_id, alliance, name));
-                    },
-                    None => {
-                        invalid_slots.push(format!("{} {} ({})", pred_slot.day, pred_slot.time, name));
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "account_name": account_name,
+        "limits": limits
+    })))
+}
+
+// Every account/server pair with a saved file in a per-account directory such as schedules/
+fn saved_keys(data_dir: &str, dir: &str) -> Vec<(String, u32)> {
+    let mut keys = Vec::new();
+    if let Ok(accounts) = std::fs::read_dir(format!("{}/{}", data_dir, dir)) {
+        for account in accounts.flatten() {
+            let account_name = account.file_name().to_string_lossy().to_string();
+            if let Ok(files) = std::fs::read_dir(account.path()) {
+                for file in files.flatten() {
+                    let file_name = file.file_name().to_string_lossy().to_string();
+                    if let Some(server_number) = file_name.strip_suffix(".json").and_then(|n| n.parse().ok()) {
+                        keys.push((account_name.clone(), server_number));
                     }
                 }
             }
-            
-            if !invalid_slots.is_empty() {
-                return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                    "success": false,
-                    "error": format!(
-                        "Invalid or unrecognized time slot(s) for predetermined assignments: {}",
-                        invalid_slots.join("; ")
-                    )
-                })));
-            }
-            
-            // Validation: At most one player can have research slot 1 predetermined (either explicitly or via construction last slot)
-            let research_slot1_from_resolved = resolved_slots.iter()
-                .filter(|(day, slot, _, _, _)| day == "research" && *slot == 1)
-                .count();
-            if research_slot1_from_resolved > 1 {
-                return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                    "success": false,
-                    "error": "Only one player can have research slot 1 predetermined. Multiple players were configured for research slot 1."
-                })));
-            }
-            
-            // Use last slot from form config (not from entries) for correct research handoff
-            let last_construction_slot = construction_slots_vec.iter()
-                .map(|(s, _)| *s)
-                .max()
-                .unwrap_or(49);
-            
-            // Collect predetermined slot numbers for each day
-            // Also track: research_slot1_players (get construction last slot), construction_last_slot_players (get research slot 1)
-            let mut research_slot1_players: Vec<String> = Vec::new(); // player_ids
-            let mut construction_last_slot_players: Vec<String> = Vec::new(); // player_ids
-            
-            for (day, slot, player_id, _alliance, _name) in &resolved_slots {
-                match day.as_str() {
-                    "construction" => {
-                        construction_predetermined_slots.insert(*slot);
-                        if *slot == last_construction_slot {
-                            construction_last_slot_players.push(player_id.clone());
-                        }
-                    },
-                    "research" => {
-                        research_predetermined_slots.insert(*slot);
-                        if *slot == 1 {
-                            research_slot1_players.push(player_id.clone());
-                        }
-                    },
-                    "troops" => {
-                        troops_predetermined_slots.insert(*slot);
-                    },
-                    _ => {},
-                }
-            }
-            
-            // Build effective research slot 1 players (for validation) - ID-based
-            let mut effective_research_slot1: std::collections::HashSet<String> = std::collections::HashSet::new();
-            for id in &research_slot1_players {
-                effective_research_slot1.insert(id.clone());
-            }
-            for id in &construction_last_slot_players {
-                effective_research_slot1.insert(id.clone());
-            }
-            if effective_research_slot1.len() > 1 {
-                return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                    "success": false,
-                    "error": "Conflict: Only one player can have the research slot 1 + construction last slot link. You have multiple players for research slot 1 and/or construction last slot."
-                })));
-            }
-            
-            // When appending: validate that predetermined slots don't conflict with existing schedule (different player_id in same slot)
-            if append {
-                let check_conflict = |existing_slots: &HashSet<u8>, existing_appts: Option<&DaySchedule>, slot: u8, player_id: &str| {
-                    if !existing_slots.contains(&slot) {
-                        return false;
-                    }
-                    let Some(appt) = existing_appts.and_then(|s| s.appointments.get(&slot)) else {
-                        return false;
-                    };
-                    appt.player_id != player_id
-                };
-                for (day, slot, player_id, _alliance, _name) in &resolved_slots {
-                    let conflict = match day.as_str() {
-                        "construction" => check_conflict(&existing_construction_slots, existing_appointments.0.as_ref(), *slot, player_id),
-                        "research" => check_conflict(&existing_research_slots, existing_appointments.1.as_ref(), *slot, player_id),
-                        "troops" => check_conflict(&existing_troops_slots, existing_appointments.2.as_ref(), *slot, player_id),
-                        _ => false,
-                    };
-                    if conflict {
-                        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                            "success": false,
-                            "error": format!(
-                                "Append conflict: Predetermined slot {} {} for player {} is already filled by a different player in the existing schedule. Clear the slot manually or generate without append.",
-                                day, slot, player_id
-                            )
-                        })));
-                    }
-                }
-                // Also validate research slot 1 / construction last slot link (ID-based)
-                if !effective_research_slot1.is_empty() {
-                    let pred_id = effective_research_slot1.iter().next().unwrap();
-                    let existing_r1 = existing_appointments.1.as_ref().and_then(|s| s.appointments.get(&1));
-                    let existing_last = existing_appointments.0.as_ref().and_then(|s| s.appointments.get(&last_construction_slot));
-                    if let Some(ex_r1) = existing_r1 {
-                        if ex_r1.player_id != *pred_id {
-                            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                                "success": false,
-                                "error": "Append conflict: Existing schedule has a different player in research slot 1. The research slot 1 + construction last slot link requires one player for both. Clear research slot 1 and construction last slot in the existing schedule first, or generate without append."
-                            })));
-                        }
-                    }
-                    if let Some(ex_last) = existing_last {
-                        if ex_last.player_id != *pred_id {
-                            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                                "success": false,
-                                "error": "Append conflict: Existing schedule has a different player in construction last slot. The research slot 1 + construction last slot link requires one player for both. Clear research slot 1 and construction last slot in the existing schedule first, or generate without append."
-                            })));
-                        }
-                    }
+        }
+    }
+    keys
+}
+
+// Every CSV that may hold form submissions: current forms, archived forms and uploads
+fn submission_csv_files(data_dir: &str) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in [format!("{}/current_forms", data_dir), format!("{}/old_forms", data_dir), data_dir.to_string()] {
+        if let Ok(entries) = std::fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().is_some_and(|ext| ext == "csv") {
+                    files.push(path);
                 }
             }
-            
-            // When appending, merge existing schedule slots with predetermined slots (both are locked)
-            construction_predetermined_slots.extend(&existing_construction_slots);
-            research_predetermined_slots.extend(&existing_research_slots);
-            troops_predetermined_slots.extend(&existing_troops_slots);
-            
-            // Add research slot 1 to pre-locked slots for construction last slot players (they get it automatically)
-            if !construction_last_slot_players.is_empty() {
-                research_predetermined_slots.insert(1);
-            }
-            
-            // Bidirectional link: research slot 1 <-> construction last slot
-            // If someone has research slot 1 predetermined, they must also have the last construction slot
-            if !research_slot1_players.is_empty() {
-                for player_id in &research_slot1_players {
-                    // Check if this player already has a construction predetermined slot
-                    let already_has_construction = resolved_slots.iter().any(|(day, _, pid, _, _)| {
-                        *day == "construction" && pid == player_id
-                    });
-                    
-                    if !already_has_construction {
-                        construction_predetermined_slots.insert(last_construction_slot);
+        }
+    }
+    files.sort();
+    files
+}
+
+// Every archived schedule file (schedules/{account}/archive/*.json)
+fn archived_schedule_files(data_dir: &str) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Ok(accounts) = std::fs::read_dir(format!("{}/schedules", data_dir)) {
+        for account in accounts.flatten() {
+            if let Ok(entries) = std::fs::read_dir(account.path().join("archive")) {
+                for entry in entries.flatten() {
+                    let path = entry.path();
+                    if path.extension().is_some_and(|ext| ext == "json") {
+                        files.push(path);
                     }
                 }
             }
-            
-            // Filter entries per day - only remove players from days where they have predetermined slots (ID-based)
-            let construction_pred_player_ids: HashSet<String> = resolved_slots.iter()
-                .filter(|(day, _, _, _, _)| *day == "construction")
-                .map(|(_, _, pid, _, _)| pid.clone())
-                .chain(research_slot1_players.iter().cloned()) // research slot 1 also gets construction last
-                .collect();
-            let research_pred_player_ids: HashSet<String> = resolved_slots.iter()
-                .filter(|(day, _, _, _, _)| *day == "research")
-                .map(|(_, _, pid, _, _)| pid.clone())
-                .chain(construction_last_slot_players.iter().cloned())
-                .collect();
-            let troops_pred_player_ids: HashSet<String> = resolved_slots.iter()
-                .filter(|(day, _, _, _, _)| *day == "troops")
-                .map(|(_, _, pid, _, _)| pid.clone())
-                .collect();
-            
-            let construction_entries_filtered: Vec<AppointmentEntry> = entries_to_use.iter()
-                .filter(|entry| !construction_pred_player_ids.contains(&entry.player_id))
-                .cloned()
-                .collect();
-            let research_entries_filtered: Vec<AppointmentEntry> = entries_to_use.iter()
-                .filter(|entry| !research_pred_player_ids.contains(&entry.player_id))
-                .cloned()
-                .collect();
-            let troops_entries_filtered: Vec<AppointmentEntry> = entries_to_use.iter()
-                .filter(|entry| !troops_pred_player_ids.contains(&entry.player_id))
-                .cloned()
-                .collect();
-            
-            // Generate schedules with day-specific filtered entries, passing predetermined slots as pre_locked_slots
-            // This ensures predetermined slots are respected from the start, but players can still be scheduled on other days
-            let mut construction_schedule = schedule_construction_day_with_locked(
-                &construction_entries_filtered,
-                &construction_predetermined_slots,
-                Some(last_construction_slot),
-            );
-            let mut research_schedule = schedule_research_day_with_locked(&research_entries_filtered, &construction_schedule, &research_predetermined_slots);
-            let mut troops_schedule = schedule_troops_day_with_locked(&troops_entries_filtered, &troops_predetermined_slots);
-            
-            // Apply predetermined slots to the schedules (insert the actual appointments)
-            // Use resolved_slots which has (day, slot, player_id, alliance, name) - ID-based
-            for (day, slot, player_id, alliance, name) in &resolved_slots {
-                let appointment = ScheduledAppointment {
-                    player_id: player_id.clone(),
-                    name: name.clone(),
-                    alliance: alliance.clone(),
-                    slot: *slot,
-                    priority_score: 9999,
-                };
-                
-                match day.as_str() {
-                    "construction" => {
-                        construction_schedule.appointments.insert(*slot, appointment.clone());
-                        if *slot == last_construction_slot {
-                            let already_has_research = resolved_slots.iter().any(|(d, _, pid, _, _)| *d == "research" && pid == player_id);
-                            if !already_has_research {
-                                let research_appointment = ScheduledAppointment {
-                                    player_id: player_id.clone(),
-                                    name: name.clone(),
-                                    alliance: alliance.clone(),
-                                    slot: 1,
-                                    priority_score: 9999,
-                                };
-                                research_schedule.appointments.insert(1, research_appointment);
-                            }
-                        }
-                    },
-                    "research" => {
-                        research_schedule.appointments.insert(*slot, appointment.clone());
-                        if *slot == 1 {
-                            let already_has_construction = resolved_slots.iter().any(|(d, _, pid, _, _)| *d == "construction" && pid == player_id);
-                            if !already_has_construction {
-                                construction_schedule.appointments.retain(|_, appt| appt.player_id != *player_id);
-                                let construction_appointment = ScheduledAppointment {
-                                    player_id: player_id.clone(),
-                                    name: name.clone(),
-                                    alliance: alliance.clone(),
-                                    slot: last_construction_slot,
-                                    priority_score: 9999,
-                                };
-                                construction_schedule.appointments.insert(last_construction_slot, construction_appointment);
-                            }
-                        }
-                    },
-                    "troops" => {
-                        troops_schedule.appointments.insert(*slot, appointment);
-                    },
-                    _ => {},
-                }
-            }
-            
-            (construction_schedule, research_schedule, troops_schedule)
-        } else {
-            // No predetermined slots, generate normally but pass last_slot from form config when available
-            let last_slot_override = construction_slots.as_ref()
-                .and_then(|slots| slots.iter().map(|(s, _)| *s).max());
-            let construction_schedule = schedule_construction_day_with_locked(
-                &entries_to_use,
-                &existing_construction_slots,
-                last_slot_override,
-            );
-            let research_schedule = schedule_research_day_with_locked(&entries_to_use, &construction_schedule, &existing_research_slots);
-            let troops_schedule = schedule_troops_day_with_locked(&entries_to_use, &existing_troops_slots);
-            (construction_schedule, research_schedule, troops_schedule)
         }
-    } else {
-        // No form config, generate normally (no last_slot override)
-        let construction_schedule = schedule_construction_day_with_locked(
-            &entries_to_use,
-            &existing_construction_slots,
-            None,
-        );
-        let research_schedule = schedule_research_day_with_locked(&entries_to_use, &construction_schedule, &existing_research_slots);
-        let troops_schedule = schedule_troops_day_with_locked(&entries_to_use, &existing_troops_slots);
-        (construction_schedule, research_schedule, troops_schedule)
-    };
-    
-    // When appending, merge existing appointments with new (keep existing, fill empty slots with new)
-    let (construction_schedule, research_schedule, troops_schedule) = {
-        let merge_day = |existing: Option<&DaySchedule>, new: DaySchedule| {
-            let mut merged = existing
-                .map(|e| e.appointments.clone())
-                .unwrap_or_default();
-            for (slot, appt) in new.appointments {
-                if !merged.contains_key(&slot) {
-                    merged.insert(slot, appt);
-                }
+    }
+    files.sort();
+    files
+}
+
+// Removes a player's appointments and entries from a schedule.
+// Returns the number of removed appointments and whether anything changed.
+fn remove_player_from_schedule(schedule_data: &mut ScheduleData, player_id: &str) -> (usize, bool) {
+    let mut removed_assignments = 0;
+    let mut changed = false;
+    for schedule in [
+        &mut schedule_data.construction_schedule,
+        &mut schedule_data.research_schedule,
+        &mut schedule_data.troops_schedule,
+    ].into_iter().flatten() {
+        let before = schedule.appointments.len();
+        schedule.appointments.retain(|_, appt| appt.player_id != player_id);
+        removed_assignments += before - schedule.appointments.len();
+        changed |= before != schedule.appointments.len();
+        
+        let before = schedule.unassigned.len();
+        schedule.unassigned.retain(|id| id != player_id);
+        changed |= before != schedule.unassigned.len();
+    }
+    if let Some(entries) = schedule_data.entries.as_mut() {
+        let before = entries.len();
+        entries.retain(|e| e.player_id != player_id);
+        changed |= before != entries.len();
+    }
+    if let Some(ids) = schedule_data.scheduled_player_ids.as_mut() {
+        let before = ids.len();
+        ids.retain(|id| id != player_id);
+        changed |= before != ids.len();
+    }
+    (removed_assignments, changed)
+}
+
+// Archived form definitions (old_forms/*.json) with their file paths
+fn archived_forms(data_dir: &str) -> Vec<(PathBuf, FormData)> {
+    let mut forms = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(format!("{}/old_forms", data_dir)) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
             }
-            DaySchedule {
-                appointments: merged,
-                unassigned: new.unassigned,
+            if let Ok(form_data) = std::fs::read_to_string(&path).map(|c| serde_json::from_str::<FormData>(&c)) {
+                match form_data {
+                    Ok(form_data) => forms.push((path, form_data)),
+                    Err(e) => log::warn!("Failed to parse archived form {}: {}", path.display(), e),
+                }
             }
-        };
-        (
-            merge_day(existing_appointments.0.as_ref(), construction_schedule),
-            merge_day(existing_appointments.1.as_ref(), research_schedule),
-            merge_day(existing_appointments.2.as_ref(), troops_schedule),
-        )
-    };
-    
-    // Create schedule data, populating scheduled_player_ids for ID-based append logic
-    let scheduled_ids: Vec<String> = {
-        let mut ids = HashSet::new();
-        for appt in construction_schedule.appointments.values() {
-            ids.insert(appt.player_id.clone());
-        }
-        for appt in research_schedule.appointments.values() {
-            ids.insert(appt.player_id.clone());
         }
-        for appt in troops_schedule.appointments.values() {
-            ids.insert(appt.player_id.clone());
+    }
+    forms
+}
+
+// Results of every power level job, with the account/server and job they belong to
+fn power_result_files(data_dir: &str) -> Vec<(String, u32, String, PathBuf)> {
+    let mut files = Vec::new();
+    for account in std::fs::read_dir(format!("{}/power", data_dir)).into_iter().flatten().flatten() {
+        let account_name = account.file_name().to_string_lossy().to_string();
+        for server in std::fs::read_dir(account.path()).into_iter().flatten().flatten() {
+            let Ok(server_number) = server.file_name().to_string_lossy().parse::<u32>() else { continue };
+            for job in std::fs::read_dir(server.path()).into_iter().flatten().flatten() {
+                let path = job.path().join("results.csv");
+                if path.is_file() {
+                    files.push((account_name.clone(), server_number, job.file_name().to_string_lossy().to_string(), path));
+                }
+            }
         }
-        ids.into_iter().collect()
-    };
-    let schedule_data = ScheduleData {
-        construction_schedule: Some(construction_schedule.clone()),
-        research_schedule: Some(research_schedule.clone()),
-        troops_schedule: Some(troops_schedule.clone()),
-        entries: Some(entries.clone()),
-        scheduled_player_ids: Some(scheduled_ids),
-    };
-    
-    // Save to state
-    let mut schedules = state.schedules.lock().unwrap();
-    schedules.insert(key.clone(), schedule_data.clone());
-    drop(schedules);
-    
-    // Save to disk
-    if let Err(e) = save_schedule(&state.data_dir, &account_name, server_number, &schedule_data) {
-        eprintln!("Warning: Failed to save schedule to disk: {}", e);
     }
-    
-    // Also regenerate and save statistics after generating schedule
-    // (This ensures stats are up-to-date with the schedule)
-    let _ = get_stats(web::Path::from((account_name.clone(), server_number)), state.clone()).await;
-    
-    let actually_merged = append && existing_schedule.is_some();
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "success": true,
-        "message": if actually_merged {
-            "Schedule appended successfully! New assignments added to empty slots."
-        } else if append {
-            "No existing schedule found. Generated new schedule from form submissions."
-        } else {
-            "Schedule generated successfully from form submissions!"
+    files
+}
+
+// Power results list players by alliance and name unless a job matched their ID, so rows are
+// the player's when they carry the ID or the alliance and name the player submitted under
+fn player_power_rows(state: &AppState, player_id: &str) -> Vec<(String, u32, String, PathBuf, Vec<PowerRecord>)> {
+    let key = |alliance: &str, name: &str| (alliance.trim().to_lowercase(), name.trim().to_lowercase());
+    let mut names: HashMap<(String, u32), HashSet<(String, String)>> = HashMap::new();
+    let mut found = Vec::new();
+    for (account_name, server_number, job_id, path) in power_result_files(&state.data_dir) {
+        let player_names = names.entry((account_name.clone(), server_number)).or_insert_with(|| {
+            load_form_entries(state, &account_name, server_number).0.iter()
+                .filter(|e| e.player_id == player_id)
+                .map(|e| key(&e.alliance, &e.name))
+                .collect()
+        });
+        let records: Vec<PowerRecord> = std::fs::File::open(&path).ok()
+            .and_then(|file| read_power_records(file).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|r| match &r.player_id {
+                Some(id) => id == player_id,
+                None => player_names.contains(&key(&r.alliance, &r.name)),
+            })
+            .collect();
+        if !records.is_empty() {
+            found.push((account_name, server_number, job_id, path, records));
         }
-    })))
+    }
+    found
 }
 
-// Update schedule slot endpoint
-#[derive(Deserialize)]
-struct UpdateSlotRequest {
-    time: String,
-    player: Option<String>, // Format: "[alliance] name" or null to clear
+// Folders of every scheduled backup, data/backups/{account}/{server}/{taken at}
+fn backup_dirs(data_dir: &str) -> Vec<PathBuf> {
+    let subdirs = |path: PathBuf| std::fs::read_dir(path).into_iter().flatten().flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir());
+    subdirs(Path::new(data_dir).join("backups"))
+        .flat_map(subdirs)
+        .flat_map(subdirs)
+        .collect()
 }
 
-async fn update_schedule_slot(
-    path: web::Path<(String, u32, String)>,
-    req: web::Json<UpdateSlotRequest>,
+// Removes a player from one backup. Backups mirror the data folder, so the same files are
+// scrubbed as in the live data; the backed up statistics are deleted once anything was removed,
+// since they were computed with the player. Returns whether the backup held the player.
+fn erase_player_from_backup(dir: &Path, player_id: &str) -> std::result::Result<bool, Box<dyn std::error::Error>> {
+    let dir_str = dir.to_string_lossy();
+    let mut changed = false;
+    for csv_path in submission_csv_files(&dir_str) {
+        changed |= remove_player_rows(&csv_path, player_id)? > 0;
+    }
+    for (account_name, server_number) in saved_keys(&dir_str, "schedules") {
+        let path = dir.join(format!("schedules/{}/{}.json", account_name, server_number));
+        let mut schedule_data: ScheduleData = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        if remove_player_from_schedule(&mut schedule_data, player_id).1 {
+            std::fs::write(&path, serde_json::to_string_pretty(&schedule_data)?)?;
+            changed = true;
+        }
+    }
+    for (account_name, server_number) in saved_keys(&dir_str, "rosters") {
+        let path = dir.join(format!("rosters/{}/{}.json", account_name, server_number));
+        let mut roster: Vec<RosterEntry> = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        let before = roster.len();
+        roster.retain(|r| r.player_id != player_id);
+        if roster.len() != before {
+            std::fs::write(&path, serde_json::to_string_pretty(&roster)?)?;
+            changed = true;
+        }
+    }
+    for entry in std::fs::read_dir(dir.join("current_forms")).into_iter().flatten().flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        // The duplicate reviews are JSON too
+        let Ok(mut form_data) = serde_json::from_str::<FormData>(&std::fs::read_to_string(&path)?) else {
+            continue;
+        };
+        let before = form_data.config.predetermined_slots.len();
+        form_data.config.predetermined_slots.retain(|p| p.player_id.as_deref() != Some(player_id));
+        if form_data.config.guaranteed_players.remove(player_id) > 0 || before != form_data.config.predetermined_slots.len() {
+            std::fs::write(&path, serde_json::to_string_pretty(&form_data)?)?;
+            changed = true;
+        }
+        changed |= metadata::remove_player_metadata(&dir_str, &form_data.code, player_id)? > 0;
+    }
+    changed |= duplicates::remove_player_reviews(&dir_str, player_id)? > 0;
+    if changed {
+        for (account_name, server_number) in saved_keys(&dir_str, "statistics") {
+            std::fs::remove_file(dir.join(format!("statistics/{}/{}.json", account_name, server_number)))?;
+        }
+    }
+    Ok(changed)
+}
+
+// Export everything stored about one player across all accounts (operator only)
+async fn operator_export_player(
+    path: web::Path<String>,
     session: Session,
     state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
-    let (account_name, server_number, day_str) = path.into_inner();
-    let account_name = account_name.to_lowercase();
-    
-    // Check authentication
-    if let (Some(session_account), Some(session_server)) = (
-        session.get::<String>("account_name")?,
-        session.get::<u32>("server_number")?
-    ) {
-        if session_account != account_name || session_server != server_number {
-            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
-                "success": false,
-                "error": "Not authorized"
-            })));
-        }
-    } else {
+    if !is_operator(&session) {
         return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
             "success": false,
             "error": "Not authenticated"
         })));
     }
+    let player_id = path.into_inner().trim().to_string();
     
-    // Load schedule
-    let key = schedule_key(&account_name, server_number);
-    let mut schedule_data = {
-        let schedules = state.schedules.lock().unwrap();
-        schedules.get(&key).cloned()
-            .or_else(|| load_schedule(&state.data_dir, &account_name, server_number))
-    };
+    // Form submissions
+    let mut submissions = Vec::new();
+    for csv_path in submission_csv_files(&state.data_dir) {
+        match find_player_rows(&csv_path, &player_id) {
+            Ok(rows) if !rows.is_empty() => {
+                let file = csv_path.strip_prefix(&state.data_dir).unwrap_or(&csv_path).display().to_string();
+                submissions.push(serde_json::json!({"file": file, "rows": rows}));
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Failed to read {}: {}", csv_path.display(), e),
+        }
+    }
     
-    if schedule_data.is_none() {
-        schedule_data = Some(ScheduleData {
-            construction_schedule: Some(DaySchedule {
-                appointments: HashMap::new(),
-                unassigned: Vec::new(),
-            }),
-            research_schedule: Some(DaySchedule {
-                appointments: HashMap::new(),
-                unassigned: Vec::new(),
-            }),
-            troops_schedule: Some(DaySchedule {
-                appointments: HashMap::new(),
-                unassigned: Vec::new(),
-            }),
-            entries: None,
-            scheduled_player_ids: None,
-        });
+    // Schedule assignments and the parsed entries stored alongside them
+    let mut assignments = Vec::new();
+    let mut schedule_entries = Vec::new();
+    for (account_name, server_number) in saved_keys(&state.data_dir, "schedules") {
+        let schedule_data = match load_schedule(&state.data_dir, &account_name, server_number) {
+            Some(schedule_data) => schedule_data,
+            None => continue,
+        };
+        for (day, schedule) in [
+            ("construction", &schedule_data.construction_schedule),
+            ("research", &schedule_data.research_schedule),
+            ("troops", &schedule_data.troops_schedule),
+        ] {
+            for appt in schedule.iter().flat_map(|s| s.appointments.values()).filter(|a| a.player_id == player_id) {
+                assignments.push(serde_json::json!({
+                    "account_name": account_name,
+                    "server_number": server_number,
+                    "day": day,
+                    "slot": appt.slot,
+                    "name": appt.name,
+                    "alliance": appt.alliance,
+                    "note": appt.note
+                }));
+            }
+        }
+        for entry in schedule_data.entries.iter().flatten().filter(|e| e.player_id == player_id) {
+            schedule_entries.push(serde_json::json!({
+                "account_name": account_name,
+                "server_number": server_number,
+                "entry": entry
+            }));
+        }
     }
     
-    let mut schedule_data = schedule_data.unwrap();
+    for path in archived_schedule_files(&state.data_dir) {
+        let schedule_data = match std::fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str::<ScheduleData>(&c).ok()) {
+            Some(schedule_data) => schedule_data,
+            None => continue,
+        };
+        let file = path.strip_prefix(&state.data_dir).unwrap_or(&path).display().to_string();
+        for (day, schedule) in [
+            ("construction", &schedule_data.construction_schedule),
+            ("research", &schedule_data.research_schedule),
+            ("troops", &schedule_data.troops_schedule),
+        ] {
+            for appt in schedule.iter().flat_map(|s| s.appointments.values()).filter(|a| a.player_id == player_id) {
+                assignments.push(serde_json::json!({
+                    "archive_file": file,
+                    "day": day,
+                    "slot": appt.slot,
+                    "name": appt.name,
+                    "alliance": appt.alliance,
+                    "note": appt.note
+                }));
+            }
+        }
+        for entry in schedule_data.entries.iter().flatten().filter(|e| e.player_id == player_id) {
+            schedule_entries.push(serde_json::json!({
+                "archive_file": file,
+                "entry": entry
+            }));
+        }
+    }
     
-    // Get form config for time slot mapping
-    let form_config = {
-        let forms = state.forms.lock().unwrap();
-        let current_forms = state.current_forms.lock().unwrap();
-        get_current_form(&forms, &current_forms, &account_name, server_number)
-            .map(|f| f.config.clone())
-    };
+    // Roster entries
+    let mut roster_entries = Vec::new();
+    for (account_name, server_number) in saved_keys(&state.data_dir, "rosters") {
+        for entry in load_roster(&state.data_dir, &account_name, server_number).into_iter().filter(|r| r.player_id == player_id) {
+            roster_entries.push(serde_json::json!({
+                "account_name": account_name,
+                "server_number": server_number,
+                "entry": entry
+            }));
+        }
+    }
     
-    // Convert time to slot number
-    let time_slots: Vec<(u8, String)> = match (day_str.as_str(), form_config.as_ref()) {
-        ("construction", Some(config)) => {
-            calculate_time_slots(&config.construction_times.start_time, config.construction_times.end_time.as_deref())
-        },
-        ("research", Some(config)) => {
-            calculate_time_slots(&config.research_times.start_time, config.research_times.end_time.as_deref())
-        },
-        ("troops", Some(config)) => {
-            calculate_time_slots(&config.troops_times.start_time, config.troops_times.end_time.as_deref())
-        },
-        _ => {
-            (1..=49).map(|slot| (slot, slot_to_time(slot))).collect()
+    // Predetermined slots reserved for the player in current and archived forms
+    let mut predetermined = Vec::new();
+    let mut guaranteed = Vec::new();
+    let current: Vec<FormData> = state.forms.lock().unwrap().values().cloned().collect();
+    let archived = archived_forms(&state.data_dir).into_iter().map(|(_, f)| f);
+    for form_data in current.into_iter().chain(archived) {
+        for slot in form_data.config.predetermined_slots.iter().filter(|p| p.player_id.as_deref() == Some(player_id.as_str())) {
+            predetermined.push(serde_json::json!({
+                "form_code": form_data.code,
+                "account_name": form_data.account_name,
+                "server_number": form_data.server_number,
+                "slot": slot
+            }));
         }
-    };
+        for day in ["construction", "research", "troops"].into_iter().filter(|day| form_data.config.guaranteed_players.for_day(day).contains(&player_id)) {
+            guaranteed.push(serde_json::json!({
+                "form_code": form_data.code,
+                "account_name": form_data.account_name,
+                "server_number": form_data.server_number,
+                "day": day
+            }));
+        }
+    }
     
-    let slot_num = time_slots.iter()
-        .find(|(_, time)| time == &req.time)
-        .map(|(slot, _)| *slot);
+    // Saved drafts and the duplicate review decisions involving the player
+    let drafts: Vec<serde_json::Value> = draft::find_player_drafts(&state.data_dir, &player_id).into_iter()
+        .map(|(form_code, _, form_draft)| serde_json::json!({"form_code": form_code, "draft": form_draft}))
+        .collect();
+    let duplicate_reviews: Vec<serde_json::Value> = duplicates::find_player_reviews(&state.data_dir, &player_id).into_iter()
+        .map(|(form_code, reviews)| serde_json::json!({"form_code": form_code, "decisions": reviews}))
+        .collect();
+    let submission_metadata: Vec<serde_json::Value> = metadata::find_player_metadata(&state.data_dir, &player_id).into_iter()
+        .map(|(form_code, entries)| serde_json::json!({"form_code": form_code, "entries": entries}))
+        .collect();
+    let power_results: Vec<serde_json::Value> = player_power_rows(&state, &player_id).into_iter()
+        .map(|(account_name, server_number, job_id, _, records)| serde_json::json!({
+            "account_name": account_name,
+            "server_number": server_number,
+            "job_id": job_id,
+            "records": records
+        }))
+        .collect();
     
-    if slot_num.is_none() {
+    Ok(HttpResponse::Ok()
+        .insert_header(("Content-Disposition", format!("attachment; filename=\"player_{}.json\"", player_id)))
+        .json(serde_json::json!({
+            "success": true,
+            "player_id": player_id,
+            "submissions": submissions,
+            "schedule_assignments": assignments,
+            "schedule_entries": schedule_entries,
+            "predetermined_slots": predetermined,
+            "guaranteed_days": guaranteed,
+            "roster_entries": roster_entries,
+            "drafts": drafts,
+            "duplicate_reviews": duplicate_reviews,
+            "submission_metadata": submission_metadata,
+            "power_results": power_results
+        })))
+}
+
+// Delete everything stored about one player across all accounts (operator only)
+async fn operator_erase_player(
+    path: web::Path<String>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    if !is_operator(&session) {
+        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+            "success": false,
+            "error": "Not authenticated"
+        })));
+    }
+    let player_id = path.into_inner().trim().to_string();
+    if player_id.is_empty() {
         return Ok(HttpResponse::BadRequest().json(serde_json::json!({
             "success": false,
-            "error": "Invalid time slot"
+            "error": "Player ID required"
         })));
     }
     
-    let slot = slot_num.unwrap();
+    // Account/server pairs whose cached statistics must be rebuilt
+    let mut affected: HashSet<(String, u32)> = HashSet::new();
+    let forms_by_code = state.forms.lock().unwrap().clone();
     
-    // Get or create the appropriate day schedule
-    let day_schedule = match day_str.as_str() {
-        "construction" => {
-            if schedule_data.construction_schedule.is_none() {
-                schedule_data.construction_schedule = Some(DaySchedule {
-                    appointments: HashMap::new(),
-                    unassigned: Vec::new(),
-                });
-            }
-            schedule_data.construction_schedule.as_mut().unwrap()
-        },
-        "research" => {
-            if schedule_data.research_schedule.is_none() {
-                schedule_data.research_schedule = Some(DaySchedule {
-                    appointments: HashMap::new(),
-                    unassigned: Vec::new(),
-                });
+    // Power level results, found by the submitted names, so before the submissions are removed
+    let mut removed_power_results = 0;
+    for (_, _, _, path, records) in player_power_rows(&state, &player_id) {
+        let kept: Vec<PowerRecord> = power_job_records(path.parent().unwrap_or(&path)).into_iter()
+            .filter(|r| !records.contains(r))
+            .collect();
+        let written = std::fs::File::create(&path).map_err(csv::Error::from)
+            .and_then(|file| write_power_records(file, &kept));
+        if let Err(e) = written {
+            log::warn!("Failed to erase player from {}: {}", path.display(), e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": format!("Failed to update {}", path.display())
+            })));
+        }
+        removed_power_results += records.len();
+    }
+    
+    // Form submissions
+    let mut removed_submissions = 0;
+    for csv_path in submission_csv_files(&state.data_dir) {
+        match remove_player_rows(&csv_path, &player_id) {
+            Ok(0) => {}
+            Ok(removed) => {
+                removed_submissions += removed;
+                let code = csv_path.file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(|n| n.strip_suffix("_submissions.csv"));
+                if let Some(form_data) = code.and_then(|c| forms_by_code.get(c)) {
+                    affected.insert((form_data.account_name.clone(), form_data.server_number));
+                }
             }
-            schedule_data.research_schedule.as_mut().unwrap()
-        },
-        "troops" => {
-            if schedule_data.troops_schedule.is_none() {
-                schedule_data.troops_schedule = Some(DaySchedule {
-                    appointments: HashMap::new(),
-                    unassigned: Vec::new(),
-                });
+            Err(e) => {
+                log::warn!("Failed to erase player from {}: {}", csv_path.display(), e);
+                return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to update {}", csv_path.display())
+                })));
             }
-            schedule_data.troops_schedule.as_mut().unwrap()
-        },
-        _ => return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-            "success": false,
-            "error": "Invalid day"
-        }))),
-    };
+        }
+    }
     
-    // Parse player name (format: "[alliance] name")
-    if let Some(ref player_str) = req.player {
-        let player_str = player_str.trim();
-        if !player_str.is_empty() {
-            // Parse "[alliance] name" format
-            let (alliance, name) = if let Some(start) = player_str.find('[') {
-                if let Some(end) = player_str.find(']') {
-                    let alliance = player_str[start+1..end].to_string();
-                    let name = player_str[end+1..].trim().to_string();
-                    (alliance, name)
-                } else {
-                    // No closing bracket, treat whole thing as name
-                    ("".to_string(), player_str.to_string())
-                }
-            } else {
-                // No bracket, treat whole thing as name
-                ("".to_string(), player_str.to_string())
-            };
-            
-            let appointment = ScheduledAppointment {
-                player_id: format!("MANUAL-{}-{}", alliance, name),
-                name,
-                alliance,
-                slot,
-                priority_score: 0,
-            };
-            
-            day_schedule.appointments.insert(slot, appointment);
-        } else {
-            // Empty string, remove the slot
-            day_schedule.appointments.remove(&slot);
+    for code in forms_by_code.keys() {
+        if let Err(e) = metadata::remove_player_metadata(&state.data_dir, code, &player_id) {
+            log::warn!("Failed to erase player from the submission metadata of form {}: {}", code, e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": format!("Failed to update the submission metadata of form {}", code)
+            })));
+        }
+    }
+    
+    // Schedule assignments and stored entries
+    let mut removed_assignments = 0;
+    for (account_name, server_number) in saved_keys(&state.data_dir, "schedules") {
+        let mut schedule_data = match load_schedule(&state.data_dir, &account_name, server_number) {
+            Some(schedule_data) => schedule_data,
+            None => continue,
+        };
+        let (removed, changed) = remove_player_from_schedule(&mut schedule_data, &player_id);
+        removed_assignments += removed;
+        if changed {
+            save_schedule(&state.data_dir, &account_name, server_number, &schedule_data).map_err(|e| {
+                actix_web::error::ErrorInternalServerError(format!("Failed to save schedule: {}", e))
+            })?;
+            affected.insert((account_name, server_number));
+        }
+    }
+    for path in archived_schedule_files(&state.data_dir) {
+        let mut schedule_data = match std::fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str::<ScheduleData>(&c).ok()) {
+            Some(schedule_data) => schedule_data,
+            None => continue,
+        };
+        let (removed, changed) = remove_player_from_schedule(&mut schedule_data, &player_id);
+        removed_assignments += removed;
+        if changed {
+            let content = serde_json::to_string_pretty(&schedule_data)?;
+            std::fs::write(&path, content)?;
         }
-    } else {
-        // None, remove the slot
-        day_schedule.appointments.remove(&slot);
     }
+    // Drop cached copies so nothing stale is served or re-saved
+    state.schedules.lock().unwrap().clear();
     
-    // Recompute scheduled_player_ids after manual edit (append logic depends on this)
-    let scheduled_ids: Vec<String> = {
-        let mut ids = HashSet::new();
-        for appt in schedule_data.construction_schedule.as_ref().iter().flat_map(|s| s.appointments.values()) {
-            ids.insert(appt.player_id.clone());
+    // Predetermined slots and guaranteed days in current and archived forms
+    let mut removed_predetermined = 0;
+    let mut removed_guaranteed = 0;
+    {
+        let mut forms = state.forms.lock().unwrap();
+        for form_data in forms.values_mut() {
+            let before = form_data.config.predetermined_slots.len();
+            form_data.config.predetermined_slots.retain(|p| p.player_id.as_deref() != Some(player_id.as_str()));
+            let guaranteed_days = form_data.config.guaranteed_players.remove(&player_id);
+            if before != form_data.config.predetermined_slots.len() || guaranteed_days > 0 {
+                removed_predetermined += before - form_data.config.predetermined_slots.len();
+                removed_guaranteed += guaranteed_days;
+                save_form(&state.data_dir, form_data)?;
+            }
         }
-        for appt in schedule_data.research_schedule.as_ref().iter().flat_map(|s| s.appointments.values()) {
-            ids.insert(appt.player_id.clone());
+    }
+    for (path, mut form_data) in archived_forms(&state.data_dir) {
+        let before = form_data.config.predetermined_slots.len();
+        form_data.config.predetermined_slots.retain(|p| p.player_id.as_deref() != Some(player_id.as_str()));
+        let guaranteed_days = form_data.config.guaranteed_players.remove(&player_id);
+        if before != form_data.config.predetermined_slots.len() || guaranteed_days > 0 {
+            removed_predetermined += before - form_data.config.predetermined_slots.len();
+            removed_guaranteed += guaranteed_days;
+            let content = serde_json::to_string_pretty(&form_data)?;
+            std::fs::write(&path, content)?;
         }
-        for appt in schedule_data.troops_schedule.as_ref().iter().flat_map(|s| s.appointments.values()) {
-            ids.insert(appt.player_id.clone());
+    }
+    
+    // Roster entries
+    let mut removed_roster_entries = 0;
+    for (account_name, server_number) in saved_keys(&state.data_dir, "rosters") {
+        let mut roster = load_roster(&state.data_dir, &account_name, server_number);
+        let before = roster.len();
+        roster.retain(|r| r.player_id != player_id);
+        if roster.len() != before {
+            removed_roster_entries += before - roster.len();
+            save_roster(&state.data_dir, &account_name, server_number, &roster)?;
         }
-        ids.into_iter().collect()
-    };
-    schedule_data.scheduled_player_ids = Some(scheduled_ids);
+    }
     
-    // Update the schedule in state
-    {
-        let mut schedules = state.schedules.lock().unwrap();
-        schedules.insert(key.clone(), schedule_data.clone());
+    // Saved drafts and the duplicate review decisions involving the player
+    let player_drafts = draft::find_player_drafts(&state.data_dir, &player_id);
+    for (form_code, token, _) in &player_drafts {
+        draft::delete_draft(&state.data_dir, form_code, token);
     }
+    let removed_reviews = duplicates::remove_player_reviews(&state.data_dir, &player_id).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save duplicate reviews: {}", e))
+    })?;
     
-    // Save to disk
-    if let Err(e) = save_schedule(&state.data_dir, &account_name, server_number, &schedule_data) {
-        eprintln!("Warning: Failed to save schedule to disk: {}", e);
-        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-            "success": false,
-            "error": "Failed to save schedule"
-        })));
+    // Scheduled backups hold copies of the same files
+    let mut scrubbed_backups = 0;
+    for dir in backup_dirs(&state.data_dir) {
+        match erase_player_from_backup(&dir, &player_id) {
+            Ok(true) => scrubbed_backups += 1,
+            Ok(false) => {}
+            Err(e) => {
+                log::warn!("Failed to erase player from backup {}: {}", dir.display(), e);
+                return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                    "success": false,
+                    "error": format!("Failed to update backup {}", dir.display())
+                })));
+            }
+        }
+    }
+    
+    // Cached statistics were computed from the removed submissions
+    for (account_name, server_number) in &affected {
+        let stats_path = format!("{}/statistics/{}/{}.json", state.data_dir, account_name, server_number);
+        if Path::new(&stats_path).exists() {
+            if let Err(e) = std::fs::remove_file(&stats_path) {
+                log::warn!("Failed to remove cached statistics {}: {}", stats_path, e);
+            }
+        }
     }
     
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "success": true,
-        "message": "Slot updated successfully"
+        "player_id": player_id,
+        "removed_submissions": removed_submissions,
+        "removed_assignments": removed_assignments,
+        "removed_predetermined_slots": removed_predetermined,
+        "removed_guaranteed_days": removed_guaranteed,
+        "removed_roster_entries": removed_roster_entries,
+        "removed_drafts": player_drafts.len(),
+        "removed_duplicate_reviews": removed_reviews,
+        "removed_power_results": removed_power_results,
+        "scrubbed_backups": scrubbed_backups
     })))
 }
 
-// Get form submissions endpoint
-async fn get_form_submissions(
+// Public schedule page by custom slug
+async fn slug_schedule_page(
+    path: web::Path<String>,
+    query: web::Query<ViewScheduleQuery>,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    match resolve_slug(&state, &path.into_inner()) {
+        Some((account_name, server_number)) => render_view_schedule(&state, &account_name, server_number, query.day.as_deref()),
+        None => Ok(HttpResponse::NotFound().body("Schedule not found")),
+    }
+}
+
+// Public statistics page by custom slug
+async fn slug_stats_page(path: web::Path<String>, state: web::Data<AppState>) -> Result<HttpResponse> {
+    if resolve_slug(&state, &path.into_inner()).is_none() {
+        return Ok(HttpResponse::NotFound().body("Schedule not found"));
+    }
+    let html = include_str!("../templates/stats.html");
+    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+}
+
+// Statistics API by custom slug (used by the stats page when served from a slug URL)
+async fn slug_stats_api(path: web::Path<String>, session: Session, state: web::Data<AppState>) -> Result<HttpResponse> {
+    match resolve_slug(&state, &path.into_inner()) {
+        Some((account_name, server_number)) => get_stats(web::Path::from((account_name, server_number)), session, state).await,
+        None => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Schedule not found"
+        }))),
+    }
+}
+
+// Leaderboard API by custom slug (used by the stats page when served from a slug URL)
+async fn slug_leaderboard_api(
+    path: web::Path<String>,
+    query: web::Query<LeaderboardQuery>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    match resolve_slug(&state, &path.into_inner()) {
+        Some((account_name, server_number)) => get_leaderboard(web::Path::from((account_name, server_number)), query, session, state).await,
+        None => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "error": "Schedule not found"
+        }))),
+    }
+}
+
+// Set or clear the public URL slug of one of the logged-in account's servers
+async fn update_slug(
     path: web::Path<(String, u32)>,
+    req: web::Json<UpdateSlugRequest>,
     session: Session,
     state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
-    let (account_name, server_number) = path.into_inner();
-    let account_name = account_name.to_lowercase();
+    require_owner(&session)?;
+    let (url_account_name, server_number) = path.into_inner();
+    let url_account_name = url_account_name.to_lowercase();
     
-    // Check authentication
-    if let (Some(session_account), Some(session_server)) = (
-        session.get::<String>("account_name")?,
-        session.get::<u32>("server_number")?
-    ) {
-        if session_account != account_name || session_server != server_number {
-            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
-                "success": false,
-                "error": "Not authorized"
-            })));
-        }
-    } else {
+    let session_account_name: Option<String> = session.get("account_name").ok().flatten();
+    if session_account_name.as_deref() != Some(url_account_name.as_str()) {
         return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
             "success": false,
-            "error": "Not authenticated"
+            "error": "Not authorized"
         })));
     }
     
-    // Get current form
-    let current_form = {
-        let forms = state.forms.lock().unwrap();
-        let current_forms = state.current_forms.lock().unwrap();
-        get_current_form(&forms, &current_forms, &account_name, server_number)
-    };
-    
-    if current_form.is_none() {
-        return Ok(HttpResponse::NotFound().json(serde_json::json!({
-            "success": false,
-            "error": "No current form found"
-        })));
+    let slug = req.slug.as_deref().map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty());
+    if let Some(slug) = &slug {
+        if let Err(err) = validate_slug(slug) {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": err
+            })));
+        }
+        if resolve_slug(&state, slug).is_some_and(|owner| owner != (url_account_name.clone(), server_number)) {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": "This URL is already taken"
+            })));
+        }
     }
     
-    let current_form = current_form.unwrap();
-    let form_csv_path = format!("{}/current_forms/{}_submissions.csv", state.data_dir, current_form.code);
-    
-    if !Path::new(&form_csv_path).exists() {
-        return Ok(HttpResponse::Ok().json(serde_json::json!({
-            "success": true,
-            "submissions": []
-        })));
-    }
+    let mut accounts = state.accounts.lock().unwrap();
+    let account = match accounts.get_mut(&url_account_name) {
+        Some(account) if account.owns_server(server_number) => account,
+        _ => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Server not found on this account"
+            })));
+        }
+    };
+    match &slug {
+        Some(slug) => account.slugs.insert(server_number, slug.clone()),
+        None => account.slugs.remove(&server_number),
+    };
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
     
-    // Read CSV file
-    let mut reader = csv::Reader::from_path(&form_csv_path)
-        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to read CSV: {}", e)))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "slug": slug
+    })))
+}
+
+// Get the branding of the logged-in account
+async fn get_branding(session: Session, state: web::Data<AppState>) -> Result<HttpResponse> {
+    let account_name: String = match session.get("account_name") {
+        Ok(Some(name)) => name,
+        _ => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not authenticated"
+            })));
+        }
+    };
     
-    let headers = reader.headers()
-        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to read CSV headers: {}", e)))?
-        .clone();
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "branding": account_branding(&state, &account_name)
+    })))
+}
+
+// Update the branding of the logged-in account
+async fn update_branding(
+    req: web::Json<Branding>,
+    session: Session,
+    state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    require_owner(&session)?;
+    let account_name: String = match session.get("account_name") {
+        Ok(Some(name)) => name,
+        _ => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "success": false,
+                "error": "Not authenticated"
+            })));
+        }
+    };
     
-    let mut submissions = Vec::new();
-    for result in reader.records() {
-        let record = result.map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to parse CSV record: {}", e)))?;
-        
-        // Skip header rows (check if first field is a timestamp pattern DD/MM/YYYY)
-        let first_field = record.get(0).unwrap_or("");
-        if !first_field.contains('/') || first_field.len() < 8 {
-            continue; // Skip header rows
+    let branding = match validate_branding(req.into_inner()) {
+        Ok(branding) => branding,
+        Err(err) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "error": err
+            })));
         }
-        
-        let mut submission = serde_json::Map::new();
-        for (i, field) in record.iter().enumerate() {
-            let header = headers.get(i)
-                .map(|s| s.to_string())
-                .unwrap_or_else(|| format!("field_{}", i));
-            submission.insert(header, serde_json::Value::String(field.to_string()));
+    };
+    
+    let mut accounts = state.accounts.lock().unwrap();
+    match accounts.get_mut(&account_name) {
+        Some(account) => account.branding = branding.clone(),
+        None => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "success": false,
+                "error": "Account not found"
+            })));
         }
-        submissions.push(serde_json::Value::Object(submission));
     }
+    save_accounts(&state.data_dir, &accounts).map_err(|e| {
+        actix_web::error::ErrorInternalServerError(format!("Failed to save account: {}", e))
+    })?;
     
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "success": true,
-        "submissions": submissions
+        "branding": branding
     })))
 }
 
-// Login endpoint (new - uses account name + password only, sets session cookie)
-async fn login_api(req: web::Json<LoginRequest>, session: Session, state: web::Data<AppState>) -> Result<HttpResponse> {
-    let account_name = req.account_name.as_ref()
-        .ok_or_else(|| actix_web::error::ErrorBadRequest("Account name required"))?
-        .trim()
-        .to_lowercase();
+// Builds the CORS middleware from the deployment's server config
+fn build_cors(config: &CorsConfig) -> Cors {
+    let mut cors = Cors::default();
     
-    let accounts = state.accounts.lock().unwrap();
-    if let Some(account) = accounts.get(&account_name) {
-        if account.password == req.password {
-            // Store account_name and server_number in session
-            session.insert("account_name", &account.account_name)
-                .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to set session: {}", e)))?;
-            session.insert("server_number", account.server_number)
-                .map_err(|e| actix_web::error::ErrorInternalServerError(format!("Failed to set session: {}", e)))?;
-            
-            Ok(HttpResponse::Ok().json(serde_json::json!({
-                "success": true,
-                "account_name": account.account_name,
-                "server_number": account.server_number
-            })))
+    for origin in &config.allowed_origins {
+        if origin.trim() == "*" {
+            cors = cors.allow_any_origin();
         } else {
-            Ok(HttpResponse::Unauthorized().json(serde_json::json!({
-                "success": false,
-                "error": "Invalid password"
-            })))
+            cors = cors.allowed_origin(origin.trim());
         }
+    }
+    
+    // Checked by CorsConfig::validate at startup
+    let methods: Vec<actix_web::http::Method> = config.allowed_methods.iter()
+        .filter_map(|m| actix_web::http::Method::from_bytes(m.trim().to_uppercase().as_bytes()).ok())
+        .collect();
+    cors = cors.allowed_methods(methods);
+    
+    if config.allowed_headers.is_empty() {
+        cors = cors.allow_any_header();
     } else {
-        Ok(HttpResponse::NotFound().json(serde_json::json!({
-            "success": false,
-            "error": "Account not found"
-        })))
+        for header in &config.allowed_headers {
+            cors = cors.allowed_header(header.trim());
+        }
+    }
+    
+    if config.allow_credentials {
+        cors = cors.supports_credentials();
+    }
+    
+    cors.max_age(config.max_age)
+}
+
+// Returns the session cookie key: the configured shared secret, or a random per-boot key
+fn session_key(config: Option<&SessionConfig>) -> Key {
+    match config {
+        Some(session) if session.secret_key.len() >= 32 => Key::derive_from(session.secret_key.as_bytes()),
+        Some(_) => {
+            log::warn!("session.secret_key must be at least 32 characters. Using a random key; sessions will not survive restarts.");
+            Key::generate()
+        }
+        None => Key::generate(),
     }
 }
 
-pub async fn start_server(port: u16, _admin_password: String) -> std::io::Result<()> {
-    let data_dir = "data".to_string();
+pub async fn start_server(port: u16, admin_password: Option<String>, data_dir: String) -> std::io::Result<()> {
     std::fs::create_dir_all(&data_dir)?;
     
-    let accounts = load_accounts(&data_dir);
+    let server_config = load_server_config(&data_dir);
+    if let Some(ref cors) = server_config.cors {
+        cors.validate().map_err(|e| std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("invalid cors section in {}/server_config.json: {}", data_dir, e),
+        ))?;
+        log::info!("CORS enabled for origins: {}", cors.allowed_origins.join(", "));
+    }
+    
+    // With shared storage another instance may still be running them
+    if !server_config.shared_storage {
+        fail_interrupted_power_jobs(&data_dir);
+    }
+    
+    // Starting without the accounts or form mapping would overwrite them on the first save
+    let accounts = load_accounts(&data_dir)?;
     let forms = load_forms(&data_dir);
-    let current_forms = load_current_forms(&data_dir);
+    let current_forms = load_current_forms(&data_dir)?;
+    
+    // A broken key file only disables publishing, like a missing one
+    let sheets = server_config.google_sheets.as_ref().and_then(|c| {
+        match SheetsClient::from_key_file(&c.service_account_key) {
+            Ok(client) => {
+                log::info!("Publishing to Google Sheets as {}", client.client_email());
+                Some(std::sync::Arc::new(client))
+            }
+            Err(e) => {
+                log::warn!("Google Sheets disabled: {}", e);
+                None
+            }
+        }
+    });
+    
+    // Like Sheets, a broken SMTP section only disables email
+    let mailer = server_config.smtp.as_ref().and_then(|c| {
+        match Mailer::from_config(c) {
+            Ok(mailer) => {
+                log::info!("Sending email as {}", mailer.sender());
+                Some(std::sync::Arc::new(mailer))
+            }
+            Err(e) => {
+                log::warn!("Email disabled: {}", e);
+                None
+            }
+        }
+    });
     
     let app_state = web::Data::new(AppState {
         accounts: Mutex::new(accounts),
@@ -3351,28 +11857,113 @@ pub async fn start_server(port: u16, _admin_password: String) -> std::io::Result
         forms: Mutex::new(forms),
         current_forms: Mutex::new(current_forms),
         data_dir,
+        shared_storage: server_config.shared_storage,
+        admin_password,
+        default_limits: server_config.limits.clone(),
+        power_extraction: server_config.power_extraction.clone(),
+        power_job_slots: tokio::sync::Semaphore::new(
+            server_config.power_extraction.as_ref().map_or(1, |p| p.parallel_jobs.max(1))
+        ),
+        jobs: JobQueue::new(MAX_RUNNING_JOBS),
+        parsed_submissions: Mutex::new(HashMap::new()),
+        stats_locks: Mutex::new(HashMap::new()),
+        sheets,
+        notifier: std::sync::Arc::new(Notifier::new(mailer.clone())),
+        mailer,
+        closing_notices: Mutex::new(HashSet::new()),
     });
     
-    // Generate a random secret key for session cookies
-    // In production, this should be a fixed secret stored securely
-    let secret_key = Key::generate();
+    tokio::spawn(run_auto_generation(app_state.clone()));
+    tokio::spawn(run_task_scheduler(app_state.clone()));
+    tokio::spawn(run_form_sync(app_state.clone()));
+    tokio::spawn(run_closing_notices(app_state.clone()));
+    
+    // Session cookies are signed, self-contained claims, so any instance holding the same
+    // secret key can validate them. Without a configured key, a random one is generated per boot.
+    let secret_key = session_key(server_config.session.as_ref());
+    let cookie_secure = server_config.session.as_ref().map(|s| s.cookie_secure).unwrap_or(true);
 
     HttpServer::new(move || {
+        // CORS is opt-in per deployment; without a `cors` section no CORS headers are sent
+        let cors = middleware::Condition::new(
+            server_config.cors.is_some(),
+            server_config.cors.as_ref().map(build_cors).unwrap_or_default(),
+        );
+        
+        let request_state = app_state.clone();
+        
         App::new()
             .app_data(app_state.clone())
+            .wrap_fn(move |req, srv| {
+                if request_state.shared_storage && !req.path().starts_with("/static") {
+                    reload_shared_state(&request_state);
+                }
+                // Log out sessions of suspended accounts and sessions from before an ownership transfer
+                let session = req.get_session();
+                // Operator sessions from a run that had a password don't outlive it
+                if request_state.admin_password.is_none() {
+                    session.remove("operator");
+                }
+                if let Ok(Some(account_name)) = session.get::<String>("account_name") {
+                    let session_version = session.get::<u32>("session_version").ok().flatten().unwrap_or(0);
+                    // Alliance logins also end when their login is deleted or moved to another alliance
+                    let sub_account_current = match session.get::<String>("alliance_scope").ok().flatten() {
+                        Some(alliance) => {
+                            let username = session.get::<String>("sub_account").ok().flatten().unwrap_or_default();
+                            let id = session.get::<String>("sub_account_id").ok().flatten().unwrap_or_default();
+                            is_sub_account_current(&request_state, &account_name, &username, &id, &alliance)
+                        }
+                        None => true,
+                    };
+                    if is_account_suspended(&request_state, &account_name)
+                        || !is_session_current(&request_state, &account_name, session_version)
+                        || !sub_account_current
+                    {
+                        session.purge();
+                    }
+                }
+                srv.call(req)
+            })
             .wrap(
-                SessionMiddleware::new(CookieSessionStore::default(), secret_key.clone())
+                SessionMiddleware::builder(CookieSessionStore::default(), secret_key.clone())
+                    .cookie_secure(cookie_secure)
+                    .build()
             )
+            .wrap(cors)
             .wrap(middleware::Logger::default())
             .service(Files::new("/static", "static").show_files_listing())
             .route("/", web::get().to(index))
             .route("/create-account", web::get().to(create_account_page))
+            .route("/claim-account", web::get().to(claim_account_page))
+            .route("/reset-password", web::get().to(reset_password_page))
             .route("/api/create-account", web::post().to(create_account))
             .route("/api/login", web::post().to(login_api))
             .route("/api/logout", web::post().to(logout_api))
             .route("/api/session", web::get().to(get_session_info))
+            .route("/api/session/server", web::post().to(switch_server))
+            .route("/api/account/servers", web::post().to(add_account_server))
+            .route("/api/account/branding", web::get().to(get_branding))
+            .route("/api/account/branding", web::put().to(update_branding))
+            .route("/api/account/sub-accounts", web::get().to(list_sub_accounts))
+            .route("/api/account/sub-accounts", web::post().to(create_sub_account))
+            .route("/api/account/sub-accounts/{username}", web::delete().to(delete_sub_account))
+            .route("/api/account/transfer", web::get().to(get_ownership_transfer))
+            .route("/api/account/transfer", web::post().to(start_ownership_transfer))
+            .route("/api/account/transfer", web::delete().to(cancel_ownership_transfer))
+            .route("/api/account/transfer/claim", web::post().to(claim_ownership_transfer))
+            .route("/api/account/recovery", web::get().to(get_recovery_email))
+            .route("/api/account/recovery", web::put().to(update_recovery_email))
+            .route("/api/account/password-reset", web::post().to(request_password_reset))
+            .route("/api/account/password-reset/confirm", web::post().to(confirm_password_reset))
             .route("/api/generate-schedule", web::post().to(generate_schedule_api))
             .route("/servers", web::get().to(servers_list_page))
+            .route("/operator", web::get().to(operator_page))
+            .route("/api/operator/login", web::post().to(operator_login))
+            .route("/api/operator/accounts", web::get().to(operator_list_accounts))
+            .route("/api/operator/accounts/{account_name}/suspend", web::put().to(operator_suspend_account))
+            .route("/api/operator/accounts/{account_name}/limits", web::put().to(operator_update_limits))
+            .route("/api/operator/players/{player_id}", web::get().to(operator_export_player))
+            .route("/api/operator/players/{player_id}", web::delete().to(operator_erase_player))
             .route("/api/servers", web::get().to(list_servers))
             .route("/dashboard/{account_name}", web::get().to(dashboard_page))
             // View-only schedule route (public, no admin/stats buttons)
@@ -3383,25 +11974,126 @@ pub async fn start_server(port: u16, _admin_password: String) -> std::io::Result
             .service(web::resource("/form/{code}/api/config").route(web::get().to(get_form_config_by_code)))
             .service(web::resource("/form/{code}/api/stats").route(web::get().to(get_form_stats_by_code)))
             .service(web::resource("/form/{code}/api/submit").route(web::post().to(submit_form_by_code)))
+            .service(web::resource("/form/{code}/api/draft").route(web::post().to(create_form_draft)))
+            .service(web::resource("/form/{code}/api/draft/{token}").route(web::get().to(get_form_draft)).route(web::put().to(update_form_draft)))
+            .service(web::resource("/form/{code}/api/player/{player_id}").route(web::post().to(get_previous_submission_by_code)))
             // Account-specific routes - main schedule view at /{account_name}/{server}
-            .service(web::resource("/{account_name}/{server}").route(web::get().to(schedules_page)))
-            .service(web::resource("/{account_name}/{server}/stats").route(web::get().to(stats_page)))
-            .service(web::resource("/{account_name}/{server}/admin").route(web::get().to(admin_page)))
+            .service(web::resource("/{account_name}/{server:\\d+}").route(web::get().to(schedules_page)))
+            .service(web::resource("/{account_name}/{server:\\d+}/stats").route(web::get().to(stats_page)))
+            .service(web::resource("/{account_name}/{server:\\d+}/admin").route(web::get().to(admin_page)))
             // Admin form management routes
-            .service(web::resource("/{account_name}/{server}/api/form/create").to(create_form))
-            .service(web::resource("/{account_name}/{server}/api/form/config").route(web::put().to(update_form_config)))
-            .service(web::resource("/{account_name}/{server}/api/form/current").route(web::get().to(get_current_form_info)))
-            .service(web::resource("/{account_name}/{server}/api/form/previous").route(web::get().to(get_previous_form_config)))
-            .service(web::resource("/{account_name}/{server}/api/form/download-csv").route(web::get().to(download_form_csv)))
-            .service(web::resource("/{account_name}/{server}/api/form/player/{player_id}").route(web::get().to(get_player_by_id)))
-            .service(web::resource("/{account_name}/{server}/api/login").route(web::post().to(account_login)))
-            .service(web::resource("/{account_name}/{server}/api/upload").to(account_upload))
-            .service(web::resource("/{account_name}/{server}/api/stats").route(web::get().to(get_stats)))
-            .service(web::resource("/{account_name}/{server}/api/schedule/{day}").route(web::get().to(get_schedule)))
-            .service(web::resource("/{account_name}/{server}/api/schedule/{day}/slot").route(web::put().to(update_schedule_slot)))
-            .service(web::resource("/{account_name}/{server}/api/form/submissions").route(web::get().to(get_form_submissions)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/form/create").to(create_form))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/form/config").route(web::put().to(update_form_config)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/form/predetermined/check").route(web::get().to(check_predetermined_api)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/roster").route(web::get().to(get_roster)).route(web::put().to(update_roster)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/alliances/rename").route(web::post().to(rename_alliance)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/players/merge").route(web::post().to(merge_players)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/players/withdraw").route(web::post().to(withdraw_player)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/roster/csv").route(web::get().to(download_roster_csv)).route(web::post().to(import_roster_csv)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/form/public-stats").route(web::put().to(update_public_stats)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/form/languages").route(web::put().to(update_language_packs)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/form/content").route(web::put().to(update_form_content)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/form/scoring").route(web::put().to(update_score_formulas)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/form/passcodes").route(web::put().to(update_form_passcodes)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/form/closing").route(web::put().to(update_form_closing)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/form/power").route(web::put().to(update_form_power)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/form/current").route(web::get().to(get_current_form_info)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/form/previous").route(web::get().to(get_previous_form_config)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/form/download-csv").route(web::get().to(download_form_csv)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/form/player/{player_id}").route(web::get().to(get_player_by_id)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/login").route(web::post().to(account_login)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/upload").to(account_upload))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/form/duplicates").route(web::get().to(get_duplicate_submissions)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/form/duplicates/{flag_id}").route(web::put().to(review_duplicate_submission)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/privacy/submission-metadata").route(web::get().to(get_submission_metadata)).route(web::put().to(update_submission_metadata)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/privacy/prefill").route(web::get().to(get_prefill_answers)).route(web::put().to(update_prefill_answers)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/upload/columns").route(web::get().to(get_csv_columns)).route(web::put().to(update_csv_columns)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/stats").route(web::get().to(get_stats)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/stats/heatmap").route(web::get().to(get_stats_heatmap)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/stats/timeline").route(web::get().to(get_stats_timeline)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/stats/export").route(web::get().to(export_stats)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/stats/recompute").route(web::post().to(recompute_stats)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/jobs").route(web::get().to(list_jobs)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/jobs/{job_id}").route(web::get().to(get_job)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/leaderboard").route(web::get().to(get_leaderboard)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/leaderboard/visibility").route(web::put().to(update_leaderboard_visibility)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/integrations/google-sheets").route(web::get().to(get_google_sheets)).route(web::put().to(update_google_sheets)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/integrations/google-forms").route(web::get().to(get_form_sync)).route(web::put().to(update_form_sync)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/integrations/google-forms/sync").route(web::post().to(run_form_sync_now)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/notifications").route(web::get().to(get_notification_settings)).route(web::put().to(update_notification_settings)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/notifications/test").route(web::post().to(send_test_notification)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/tasks").route(web::get().to(list_scheduled_tasks)).route(web::post().to(create_scheduled_task)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/tasks/{task_id}").route(web::put().to(update_scheduled_task)).route(web::delete().to(delete_scheduled_task)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/tasks/{task_id}/run").route(web::post().to(run_scheduled_task_now)))
+            // Registered before /api/schedule/{day} so "unfilled", "move", "itinerary", "auto-fill" and "google-sheets" are not taken for a day
+            .service(web::resource("/{account_name}/{server:\\d+}/api/schedule/unfilled").route(web::get().to(get_unfilled_slots)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/schedule/move").route(web::post().to(move_assignment)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/schedule/itinerary").route(web::get().to(get_schedule_itinerary)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/schedule/auto-fill").route(web::post().to(auto_fill_schedule)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/schedule/google-sheets").route(web::post().to(publish_google_sheets)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/schedule/{day}").route(web::get().to(get_schedule)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/schedule/{day}/discord").route(web::get().to(get_schedule_discord)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/schedule/{day}/candidates").route(web::get().to(get_slot_candidates)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/schedule/{day}/fill").route(web::post().to(fill_slot)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/schedule/{day}/slot").route(web::put().to(update_schedule_slot)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/schedule/{day}/slot/note").route(web::put().to(update_slot_note)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/form/submissions").route(web::get().to(get_form_submissions)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/slug").route(web::put().to(update_slug)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/archive").route(web::get().to(list_archive)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/archive/forms/{id}/submissions").route(web::get().to(get_archived_submissions)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/archive/schedules/{id}/restore").route(web::post().to(restore_archived_schedule)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/participation/compare").route(web::get().to(compare_participation)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/seasons").route(web::get().to(list_seasons)).route(web::post().to(start_season)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/seasons/{season_id}").route(web::put().to(rename_season)).route(web::delete().to(delete_season)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/seasons/{season_id}/end").route(web::post().to(end_season)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/seasons/{season_id}/history").route(web::get().to(get_season_history)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/power/jobs").route(web::get().to(list_power_jobs)).route(web::post().to(upload_power_recording)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/power/jobs/{job_id}").route(web::get().to(get_power_job)).route(web::delete().to(delete_power_job)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/power/jobs/{job_id}/results.csv").route(web::get().to(download_power_results)))
+            .service(web::resource("/{account_name}/{server:\\d+}/api/archive/schedules/{id}/{day}").route(web::get().to(get_archived_schedule)))
+            // Custom slug routes (slugs are validated against the reserved top-level paths above)
+            .service(web::resource("/{slug}").route(web::get().to(slug_schedule_page)))
+            .service(web::resource("/{slug}/stats").route(web::get().to(slug_stats_page)))
+            .service(web::resource("/{slug}/api/stats").route(web::get().to(slug_stats_api)))
+            .service(web::resource("/{slug}/api/leaderboard").route(web::get().to(slug_leaderboard_api)))
     })
     .bind(("0.0.0.0", port))?
     .run()
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account() -> Account {
+        serde_json::from_value(serde_json::json!({
+            "account_name": "test",
+            "server_number": 77,
+            "password": "old-secret",
+            "in_game_name": "Old Owner"
+        })).unwrap()
+    }
+
+    #[test]
+    fn password_reset_after_transfer_does_not_reach_the_old_owner() {
+        let mut account = account();
+        account.recovery_email = Some("old@example.com".to_string());
+        account.notifications = NotificationSettings {
+            channel: NotificationChannel::Email,
+            email: Some("old@example.com".to_string()),
+            discord_webhook: Some("https://discord.com/api/webhooks/1/old".to_string()),
+            events: Default::default(),
+        };
+        let (to, _) = account.start_password_reset().unwrap();
+        assert_eq!(to, "old@example.com");
+
+        account.hand_over("new-secret".to_string(), "New Owner".to_string());
+        assert_eq!(account.start_password_reset(), None);
+        assert!(account.pending_reset.is_none());
+        assert!(account.recovery_email.is_none());
+        assert_eq!(account.notifications.channel, NotificationChannel::None);
+        assert!(account.notifications.email.is_none() && account.notifications.discord_webhook.is_none());
+        assert_eq!((account.password.as_str(), account.session_version), ("new-secret", 1));
+    }
+}